@@ -0,0 +1,185 @@
+//! `#[derive(RedisEntity)]`: implements `redis_rust_demo::repository::Entity`
+//! for a struct with named fields, so `EntityStore`, `UserRepository` and the
+//! consistency checks can store it.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize, RedisEntity)]
+//! #[redis(prefix = "user", ttl_secs = 86_400, validate = "validate_user")]
+//! pub struct User {
+//!     id: Uuid,
+//!     #[redis(index)]
+//!     username: String,
+//!     #[redis(index = "mail")]
+//!     email: String,
+//! }
+//! ```
+//!
+//! On the struct:
+//! - `prefix`: the `KIND` keys start with; the struct name in snake_case
+//!   if not given.
+//! - `ttl_secs`: every save sets this expiry.
+//! - `validate`: path of a `fn(&Self) -> ValidationReport` run on save
+//!   and load.
+//!
+//! On fields:
+//! - `id`: the field whose `Display` is the entity id; a field named `id`
+//!   if no field is marked.
+//! - `index` or `index = "name"`: adds `name` to `INDEXES` and
+//!   `<name>:<value>` to `index_keys`, `name` defaulting to the field's.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, Ident, LitInt, LitStr, Path, Token};
+
+#[proc_macro_derive(RedisEntity, attributes(redis))]
+pub fn derive_redis_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+struct Container {
+    prefix: String,
+    ttl_secs: Option<u64>,
+    validate: Option<Path>,
+}
+
+struct Index {
+    field: Ident,
+    name: String,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = named_fields(input)?;
+    let container = parse_container(input)?;
+    let (id, indexes) = parse_fields(input, fields)?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let prefix = &container.prefix;
+    let ttl = match container.ttl_secs {
+        Some(secs) => quote!(::std::option::Option::Some(::std::time::Duration::from_secs(#secs))),
+        None => quote!(::std::option::Option::None),
+    };
+    let index_names = indexes.iter().map(|index| &index.name);
+    let index_keys = indexes.iter().map(|Index { field, name }| quote!(::std::format!("{}:{}", #name, self.#field)));
+    let validate = container.validate.map(|path| {
+        quote! {
+            fn validate(&self) -> ::redis_rust_demo::repository::ValidationReport {
+                #path(self)
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::redis_rust_demo::repository::Entity for #name #ty_generics #where_clause {
+            const KIND: &'static str = #prefix;
+
+            const TTL: ::std::option::Option<::std::time::Duration> = #ttl;
+
+            const INDEXES: &'static [&'static str] = &[#(#index_names),*];
+
+            fn entity_id(&self) -> ::std::string::String {
+                ::std::string::ToString::to_string(&self.#id)
+            }
+
+            fn index_keys(&self) -> ::std::vec::Vec<::std::string::String> {
+                ::std::vec![#(#index_keys),*]
+            }
+
+            #validate
+        }
+    })
+}
+
+fn named_fields(input: &DeriveInput) -> syn::Result<&Punctuated<Field, Comma>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(syn::Error::new_spanned(&input.ident, "RedisEntity needs a struct with named fields")),
+        },
+        _ => Err(syn::Error::new_spanned(&input.ident, "RedisEntity needs a struct with named fields")),
+    }
+}
+
+fn parse_container(input: &DeriveInput) -> syn::Result<Container> {
+    let mut container = Container { prefix: snake_case(&input.ident.to_string()), ttl_secs: None, validate: None };
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("redis")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("prefix") {
+                let prefix: LitStr = meta.value()?.parse()?;
+                container.prefix = key_part(&prefix)?;
+            } else if meta.path.is_ident("ttl_secs") {
+                let ttl: LitInt = meta.value()?.parse()?;
+                match ttl.base10_parse::<u64>()? {
+                    0 => return Err(syn::Error::new_spanned(ttl, "ttl_secs must be at least 1")),
+                    secs => container.ttl_secs = Some(secs),
+                }
+            } else if meta.path.is_ident("validate") {
+                let path: LitStr = meta.value()?.parse()?;
+                container.validate = Some(path.parse()?);
+            } else {
+                return Err(meta.error("unknown redis attribute, expected `prefix`, `ttl_secs` or `validate`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(container)
+}
+
+fn parse_fields(input: &DeriveInput, fields: &Punctuated<Field, Comma>) -> syn::Result<(Ident, Vec<Index>)> {
+    let mut id: Option<Ident> = None;
+    let mut indexes: Vec<Index> = Vec::new();
+    for field in fields {
+        let ident = field.ident.clone().expect("named fields have names");
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("redis")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("id") {
+                    if id.is_some() {
+                        return Err(meta.error("only one field can be #[redis(id)]"));
+                    }
+                    id = Some(ident.clone());
+                } else if meta.path.is_ident("index") {
+                    let name = match meta.input.peek(Token![=]) {
+                        true => key_part(&meta.value()?.parse()?)?,
+                        false => ident.to_string(),
+                    };
+                    if indexes.iter().any(|index| index.name == name) {
+                        return Err(meta.error(format!("two fields are indexed as `{}`", name)));
+                    }
+                    indexes.push(Index { field: ident.clone(), name });
+                } else {
+                    return Err(meta.error("unknown redis field attribute, expected `id` or `index`"));
+                }
+                Ok(())
+            })?;
+        }
+    }
+    let id = id.or_else(|| fields.iter().filter_map(|field| field.ident.clone()).find(|ident| ident == "id"));
+    match id {
+        Some(id) => Ok((id, indexes)),
+        None => Err(syn::Error::new_spanned(&input.ident, "no id field: add an `id` field or mark one #[redis(id)]")),
+    }
+}
+
+/// A prefix or index name: it becomes the part of a key before `:`.
+fn key_part(lit: &LitStr) -> syn::Result<String> {
+    let value = lit.value();
+    if value.is_empty() || value.contains(|c: char| c == ':' || c == '*' || c.is_whitespace()) {
+        return Err(syn::Error::new_spanned(lit, "must be non-empty without `:`, `*` or whitespace"));
+    }
+    Ok(value)
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (n, c) in name.chars().enumerate() {
+        if c.is_uppercase() && n > 0 {
+            out.push('_');
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
@@ -0,0 +1,4 @@
+#[test]
+fn bad_attributes_fail_to_compile() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,12 @@
+use redis_rust_demo_derive::RedisEntity;
+
+#[derive(RedisEntity)]
+struct User {
+    id: u64,
+    #[redis(index = "contact")]
+    email: String,
+    #[redis(index = "contact")]
+    phone: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use redis_rust_demo_derive::RedisEntity;
+
+#[derive(RedisEntity)]
+enum Status {
+    Active,
+    Banned,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use redis_rust_demo_derive::RedisEntity;
+
+#[derive(RedisEntity)]
+#[redis(prefix = "app:user")]
+struct User {
+    id: u64,
+}
+
+fn main() {}
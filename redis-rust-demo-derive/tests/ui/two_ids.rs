@@ -0,0 +1,11 @@
+use redis_rust_demo_derive::RedisEntity;
+
+#[derive(RedisEntity)]
+struct Product {
+    #[redis(id)]
+    sku: String,
+    #[redis(id)]
+    barcode: String,
+}
+
+fn main() {}
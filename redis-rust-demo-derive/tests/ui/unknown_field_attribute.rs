@@ -0,0 +1,10 @@
+use redis_rust_demo_derive::RedisEntity;
+
+#[derive(RedisEntity)]
+struct User {
+    id: u64,
+    #[redis(unique)]
+    email: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use redis_rust_demo_derive::RedisEntity;
+
+#[derive(RedisEntity)]
+#[redis(ttl_secs = 0)]
+struct Session {
+    id: u64,
+}
+
+fn main() {}
@@ -0,0 +1,235 @@
+use super::stats::LatencySummary;
+use crate::{DemoError, RedisClient, Result};
+use redis::AsyncCommands;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+const KEY_PREFIX: &str = "bench:adaptive:";
+const KEYS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// Doubling until the first p99 breach.
+    SlowStart,
+    /// Additive increase, multiplicative decrease.
+    Avoidance,
+}
+
+/// AIMD concurrency controller: doubles until p99 first exceeds the target,
+/// then backs off by `backoff` on every breach and adds `increase` on every
+/// step within target. Stops after `max_breaches` breaches past slow start,
+/// or once `max` stays within target.
+#[derive(Debug, Clone)]
+pub struct AimdLimit {
+    limit: usize,
+    max: usize,
+    increase: usize,
+    backoff: f64,
+    max_breaches: usize,
+    breaches: usize,
+    phase: Phase,
+}
+
+impl AimdLimit {
+    pub fn new(max: usize) -> Self {
+        Self { limit: 1, max: max.max(1), increase: 0, backoff: 0.75, max_breaches: 3, breaches: 0, phase: Phase::SlowStart }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Feeds one step's p99 and returns the next concurrency, or `None` when
+    /// the search is over.
+    pub fn observe(&mut self, p99: Duration, target: Duration) -> Option<usize> {
+        if p99 > target {
+            self.breaches += 1;
+            if self.phase == Phase::SlowStart {
+                self.phase = Phase::Avoidance;
+                // Probe upwards in steps of a tenth of the breaching limit.
+                self.increase = (self.limit / 10).max(1);
+            } else if self.breaches > self.max_breaches {
+                return None;
+            }
+            self.limit = ((self.limit as f64 * self.backoff) as usize).max(1);
+        } else if self.limit == self.max {
+            return None;
+        } else {
+            self.limit = match self.phase {
+                Phase::SlowStart => self.limit * 2,
+                Phase::Avoidance => self.limit + self.increase,
+            }
+            .min(self.max);
+        }
+        Some(self.limit)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveStep {
+    pub concurrency: usize,
+    pub ops_per_sec: f64,
+    pub latency: LatencySummary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveReport {
+    pub target_p99: Duration,
+    pub steps: Vec<AdaptiveStep>,
+}
+
+impl AdaptiveReport {
+    /// The highest-throughput step that kept p99 within target.
+    pub fn max_sustainable(&self) -> Option<&AdaptiveStep> {
+        self.steps
+            .iter()
+            .filter(|step| step.latency.p99 <= self.target_p99)
+            .max_by(|a, b| a.ops_per_sec.total_cmp(&b.ops_per_sec))
+    }
+}
+
+/// Finds the throughput a server sustains under a p99 latency target by
+/// raising concurrency until the target breaks, instead of guessing a fixed
+/// client count.
+pub struct AdaptiveBench {
+    client: RedisClient,
+}
+
+impl AdaptiveBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// `concurrency` workers alternating SET and GET for `duration`.
+    async fn step(&self, concurrency: usize, duration: Duration, value: &str) -> Result<AdaptiveStep> {
+        let conn = self.client.get_async_connection().await?;
+        let started = Instant::now();
+        let handles: Vec<_> = (0..concurrency)
+            .map(|worker| {
+                let mut conn = conn.clone();
+                let value = value.to_string();
+                tokio::spawn(async move {
+                    let mut samples = Vec::new();
+                    let mut i = worker;
+                    while started.elapsed() < duration {
+                        let key = format!("{}{}", KEY_PREFIX, i % KEYS);
+                        let sent = Instant::now();
+                        if i % 2 == 0 {
+                            let _: () = conn.set(&key, &value).await?;
+                        } else {
+                            let _: Option<String> = conn.get(&key).await?;
+                        }
+                        samples.push(sent.elapsed());
+                        i += concurrency;
+                    }
+                    Ok::<_, DemoError>(samples)
+                })
+            })
+            .collect();
+        let mut samples = Vec::new();
+        for handle in handles {
+            samples.extend(handle.await.map_err(|e| DemoError::Demo(e.to_string()))??);
+        }
+        let elapsed = started.elapsed().as_secs_f64();
+        Ok(AdaptiveStep {
+            concurrency,
+            ops_per_sec: if elapsed > 0.0 { samples.len() as f64 / elapsed } else { 0.0 },
+            latency: LatencySummary::from_samples(samples),
+        })
+    }
+
+    pub async fn run(
+        &self,
+        target_p99: Duration,
+        step_duration: Duration,
+        max_concurrency: usize,
+        value_size: usize,
+    ) -> Result<AdaptiveReport> {
+        let value = "x".repeat(value_size);
+        let mut limit = AimdLimit::new(max_concurrency);
+        let mut report = AdaptiveReport { target_p99, steps: Vec::new() };
+
+        println!("\n=== Adaptive Concurrency ===\n");
+        println!("Target p99 {:?}, {:?} per step, up to {} workers\n", target_p99, step_duration, max_concurrency);
+        println!("{:>7} {:>12} {:>12} {:>12}", "workers", "ops/s", "p50", "p99");
+        loop {
+            let step = self.step(limit.limit(), step_duration, &value).await?;
+            let marker = if step.latency.p99 > target_p99 { "  ✗ over target" } else { "" };
+            println!(
+                "{:>7} {:>12.0} {:>12?} {:>12?}{}",
+                step.concurrency, step.ops_per_sec, step.latency.p50, step.latency.p99, marker
+            );
+            let next = limit.observe(step.latency.p99, target_p99);
+            report.steps.push(step);
+            if next.is_none() {
+                break;
+            }
+        }
+
+        let mut conn = self.client.get_async_connection().await?;
+        let keys: Vec<String> = (0..KEYS).map(|i| format!("{}{}", KEY_PREFIX, i)).collect();
+        let _: () = conn.del(&keys).await?;
+
+        match report.max_sustainable() {
+            Some(best) => println!(
+                "\n→ Max sustainable: {:.0} ops/s at {} workers (p99 {:?})",
+                best.ops_per_sec, best.concurrency, best.latency.p99
+            ),
+            None => println!("\n→ Even one worker exceeds the p99 target"),
+        }
+        println!("\n💡 Past the knee, more concurrency only queues: throughput flattens while");
+        println!("   tail latency climbs. The last step under target is the useful capacity.");
+        info!("Adaptive concurrency benchmark completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: Duration = Duration::from_millis(5);
+    const UNDER: Duration = Duration::from_millis(1);
+    const OVER: Duration = Duration::from_millis(9);
+
+    #[test]
+    fn test_aimd_slow_start_then_additive() {
+        let mut limit = AimdLimit::new(1000);
+        assert_eq!(limit.observe(UNDER, TARGET), Some(2));
+        assert_eq!(limit.observe(UNDER, TARGET), Some(4));
+        for _ in 0..4 {
+            limit.observe(UNDER, TARGET);
+        }
+        assert_eq!(limit.limit(), 64);
+        // First breach: back off by a quarter, then probe by 6 (64 / 10).
+        assert_eq!(limit.observe(OVER, TARGET), Some(48));
+        assert_eq!(limit.observe(UNDER, TARGET), Some(54));
+        assert_eq!(limit.observe(OVER, TARGET), Some(40));
+        assert_eq!(limit.observe(OVER, TARGET), Some(30));
+        assert_eq!(limit.observe(OVER, TARGET), None);
+    }
+
+    #[test]
+    fn test_aimd_stops_at_max() {
+        let mut limit = AimdLimit::new(3);
+        assert_eq!(limit.observe(UNDER, TARGET), Some(2));
+        assert_eq!(limit.observe(UNDER, TARGET), Some(3));
+        assert_eq!(limit.observe(UNDER, TARGET), None);
+    }
+
+    #[test]
+    fn test_max_sustainable() {
+        let step = |concurrency, ops_per_sec, p99| AdaptiveStep {
+            concurrency,
+            ops_per_sec,
+            latency: LatencySummary { p99, ..Default::default() },
+        };
+        let report = AdaptiveReport {
+            target_p99: TARGET,
+            steps: vec![step(1, 100.0, UNDER), step(8, 700.0, UNDER), step(16, 900.0, OVER)],
+        };
+        assert_eq!(report.max_sustainable().unwrap().concurrency, 8);
+        let none = AdaptiveReport { target_p99: TARGET, steps: vec![step(1, 100.0, OVER)] };
+        assert!(none.max_sustainable().is_none());
+    }
+}
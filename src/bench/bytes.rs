@@ -0,0 +1,94 @@
+use super::stats::LatencySummary;
+use crate::utils::bytes::get_bytes;
+use crate::{RedisClient, Result};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use redis::AsyncCommands;
+use std::time::Instant;
+use tracing::info;
+
+const KEY: &str = "bench:bytes:value";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BytesReport {
+    pub value_size: usize,
+    pub string: LatencySummary,
+    pub bytes: LatencySummary,
+}
+
+impl BytesReport {
+    /// Throughput in MiB/s at the mean latency.
+    pub fn throughput(&self, summary: &LatencySummary) -> f64 {
+        if summary.mean.is_zero() {
+            return 0.0;
+        }
+        self.value_size as f64 / (1024.0 * 1024.0) / summary.mean.as_secs_f64()
+    }
+}
+
+/// GETs one large value repeatedly as a `String` (copied out of the reply
+/// and UTF-8 checked) and as `Bytes` (the reply's buffer, moved).
+pub struct BytesBench {
+    client: RedisClient,
+}
+
+impl BytesBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn run(&self, value_size: usize, iterations: usize) -> Result<BytesReport> {
+        let mut conn = self.client.get_async_connection().await?;
+
+        println!("\n=== String vs Bytes Values ===\n");
+        println!("1. Storing one {} byte value", value_size);
+        let value: String = rand::thread_rng().sample_iter(&Alphanumeric).take(value_size).map(char::from).collect();
+        let _: () = conn.set(KEY, &value).await?;
+
+        println!("\n2. {} GETs each way (interleaved, so both see the same server):", iterations);
+        let mut as_string = Vec::with_capacity(iterations);
+        let mut as_bytes = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            let started = Instant::now();
+            let fetched: String = conn.get(KEY).await?;
+            as_string.push(started.elapsed());
+            std::hint::black_box(fetched);
+
+            let started = Instant::now();
+            let fetched = get_bytes(&mut conn, KEY).await?;
+            as_bytes.push(started.elapsed());
+            std::hint::black_box(fetched);
+        }
+
+        let report = BytesReport {
+            value_size,
+            string: LatencySummary::from_samples(as_string),
+            bytes: LatencySummary::from_samples(as_bytes),
+        };
+        println!("   String: {} ({:.0} MiB/s)", report.string, report.throughput(&report.string));
+        println!("   Bytes:  {} ({:.0} MiB/s)", report.bytes, report.throughput(&report.bytes));
+        let saved = report.string.mean.saturating_sub(report.bytes.mean);
+        println!("   → {:?} saved per GET on average", saved);
+
+        let _: () = conn.del(KEY).await?;
+        println!("\n💡 The network transfer is the same either way; what Bytes skips is the");
+        println!("   copy out of the parsed reply and the UTF-8 scan. It matters for big");
+        println!("   values that are forwarded or deserialized from &[u8], not for small keys.");
+        info!("Bytes benchmark completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_throughput() {
+        let summary = LatencySummary { mean: Duration::from_millis(10), ..Default::default() };
+        let report = BytesReport { value_size: 1024 * 1024, string: summary, bytes: LatencySummary::default() };
+        assert!((report.throughput(&summary) - 100.0).abs() < 1e-9);
+        assert_eq!(report.throughput(&report.bytes), 0.0);
+    }
+}
@@ -0,0 +1,188 @@
+use super::auto_pipeline::ThroughputRun;
+use super::stats::LatencySummary;
+use super::workload::Op;
+use crate::utils::RedisConnection;
+use crate::{DemoError, RedisClient, Result};
+use rand::Rng;
+use redis::{AsyncCommands, Value};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+const KEY_PREFIX: &str = "bench:cmd:";
+
+/// Parses `set,get,incr` into the commands to run, in that order.
+pub fn parse_ops(list: &str) -> Result<Vec<Op>> {
+    let ops = list.split(',').filter(|name| !name.trim().is_empty()).map(str::parse).collect::<Result<Vec<Op>>>()?;
+    if ops.is_empty() {
+        return Err(DemoError::Configuration("No bench commands given".to_string()));
+    }
+    Ok(ops)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandBenchOptions {
+    /// Run one after the other, each measured on its own.
+    pub ops: Vec<Op>,
+    /// Connections, each driven by its own task.
+    pub clients: usize,
+    /// Requests per command, split between the clients.
+    pub requests: usize,
+    /// Commands sent per round trip.
+    pub pipeline: usize,
+    pub payload_size: usize,
+    /// Keys drawn at random from this many per command family.
+    pub keyspace: usize,
+}
+
+impl CommandBenchOptions {
+    pub fn new(ops: Vec<Op>) -> Self {
+        Self { ops, clients: 50, requests: 100_000, pipeline: 1, payload_size: 64, keyspace: 10_000 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CommandBenchReport {
+    pub runs: Vec<(Op, ThroughputRun)>,
+}
+
+impl CommandBenchReport {
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "   {:<8} {:>10} {:>12} {:>10} {:>10} {:>10} {:>10}\n",
+            "", "requests", "ops/s", "p50", "p95", "p99", "max"
+        );
+        for (op, run) in &self.runs {
+            let latency = |d: Duration| format!("{:.3}ms", d.as_secs_f64() * 1000.0);
+            out.push_str(&format!(
+                "   {:<8} {:>10} {:>12.0} {:>10} {:>10} {:>10} {:>10}\n",
+                format!("{:?}", op).to_uppercase(),
+                run.ops,
+                run.ops_per_sec(),
+                latency(run.latency.p50),
+                latency(run.latency.p95),
+                latency(run.latency.p99),
+                latency(run.latency.max)
+            ));
+        }
+        out
+    }
+}
+
+/// Requests each of `clients` sends so they add up to `requests`.
+fn shares(requests: usize, clients: usize) -> Vec<usize> {
+    let clients = clients.max(1);
+    (0..clients).map(|n| requests / clients + usize::from(n < requests % clients)).collect()
+}
+
+/// A small redis-benchmark: each command in turn, sent by concurrent
+/// clients on their own connections, optionally pipelined.
+pub struct CommandBench {
+    client: RedisClient,
+}
+
+impl CommandBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn run(&self, options: &CommandBenchOptions) -> Result<CommandBenchReport> {
+        println!("\n=== Command Benchmark ===\n");
+        println!(
+            "{} requests per command, {} clients, pipeline {}, {} byte payloads, {} keys\n",
+            options.requests, options.clients, options.pipeline, options.payload_size, options.keyspace
+        );
+        let mut connections = Vec::with_capacity(options.clients);
+        for _ in 0..options.clients.max(1) {
+            connections.push(self.client.get_async_connection().await?);
+        }
+
+        let mut report = CommandBenchReport::default();
+        for op in &options.ops {
+            let run = drive(&connections, *op, options).await?;
+            println!("   {:<8} {:>10.0} ops/s  {}", format!("{:?}", op).to_uppercase(), run.ops_per_sec(), run.latency);
+            report.runs.push((*op, run));
+        }
+
+        println!("\nSummary:");
+        print!("{}", report.render());
+
+        let mut cleanup = self.client.get_async_connection().await?;
+        let mut scanner = self.client.scan_keys(&format!("{}*", KEY_PREFIX)).await?;
+        while let Some(keys) = scanner.next_batch().await? {
+            if !keys.is_empty() {
+                let _: () = cleanup.del(keys).await?;
+            }
+        }
+        info!("Command benchmark completed");
+        Ok(report)
+    }
+}
+
+/// Every client sends its share of `op` in pipelines of `options.pipeline`.
+/// Each command is recorded with its batch's round trip, as
+/// redis-benchmark does.
+async fn drive(connections: &[RedisConnection], op: Op, options: &CommandBenchOptions) -> Result<ThroughputRun> {
+    let payload = "x".repeat(options.payload_size);
+    let started = Instant::now();
+    let handles: Vec<_> = connections
+        .iter()
+        .zip(shares(options.requests, connections.len()))
+        .map(|(conn, share)| {
+            let (mut conn, payload) = (conn.clone(), payload.clone());
+            let (pipeline, keyspace) = (options.pipeline.max(1), options.keyspace.max(1));
+            tokio::spawn(async move {
+                let mut samples = Vec::with_capacity(share);
+                let mut left = share;
+                while left > 0 {
+                    let batch = left.min(pipeline);
+                    let mut pipe = redis::pipe();
+                    for _ in 0..batch {
+                        let key = format!("{}{}:{}", KEY_PREFIX, op.key_family(), rand::thread_rng().gen_range(0..keyspace));
+                        pipe.add_command(op.command(&key, &payload)).ignore();
+                    }
+                    let sent = Instant::now();
+                    let _: Value = pipe.query_async(&mut conn).await?;
+                    samples.extend(std::iter::repeat_n(sent.elapsed(), batch));
+                    left -= batch;
+                }
+                Ok::<_, DemoError>(samples)
+            })
+        })
+        .collect();
+    let mut samples = Vec::with_capacity(options.requests);
+    for handle in handles {
+        samples.extend(handle.await.map_err(|e| DemoError::Demo(e.to_string()))??);
+    }
+    Ok(ThroughputRun { elapsed: started.elapsed(), ops: samples.len(), latency: LatencySummary::from_samples(samples) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ops() {
+        assert_eq!(parse_ops("set,get, incr,LPUSH,hset").unwrap(), [Op::Set, Op::Get, Op::Incr, Op::Lpush, Op::Hset]);
+        assert!(parse_ops("set,ping").is_err());
+        assert!(parse_ops(",").is_err());
+    }
+
+    #[test]
+    fn test_shares_add_up() {
+        assert_eq!(shares(10, 3), [4, 3, 3]);
+        assert_eq!(shares(2, 4), [1, 1, 0, 0]);
+        assert_eq!(shares(100_000, 50).iter().sum::<usize>(), 100_000);
+    }
+
+    #[test]
+    fn test_render() {
+        let run = ThroughputRun {
+            elapsed: Duration::from_secs(1),
+            ops: 50_000,
+            latency: LatencySummary { count: 50_000, p50: Duration::from_micros(250), ..Default::default() },
+        };
+        let rendered = CommandBenchReport { runs: vec![(Op::Lpush, run)] }.render();
+        let row = rendered.lines().nth(1).unwrap();
+        assert!(row.contains("LPUSH") && row.contains("50000") && row.contains("0.250ms"), "{}", row);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{DemoError, Result};
+use rand::{Rng, RngCore};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Picks which key of a keyspace the next request goes to, as an index in
+/// `0..key_count()`. Index 0 is the hottest key for skewed distributions.
+pub trait KeyDistribution: Send + Sync {
+    fn sample(&self, rng: &mut dyn RngCore) -> usize;
+
+    fn key_count(&self) -> usize;
+}
+
+/// Every key equally likely.
+pub struct Uniform {
+    count: usize,
+}
+
+impl Uniform {
+    pub fn new(count: usize) -> Self {
+        Self { count: count.max(1) }
+    }
+}
+
+impl KeyDistribution for Uniform {
+    fn sample(&self, rng: &mut dyn RngCore) -> usize {
+        rng.gen_range(0..self.count)
+    }
+
+    fn key_count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Key `i` drawn with probability proportional to `1 / (i + 1)^skew`.
+pub struct Zipfian {
+    cdf: Vec<f64>,
+}
+
+impl Zipfian {
+    pub fn new(count: usize, skew: f64) -> Self {
+        let mut total = 0.0;
+        let mut cdf: Vec<f64> = (1..=count.max(1))
+            .map(|rank| {
+                total += 1.0 / (rank as f64).powf(skew);
+                total
+            })
+            .collect();
+        for p in &mut cdf {
+            *p /= total;
+        }
+        Self { cdf }
+    }
+}
+
+impl KeyDistribution for Zipfian {
+    fn sample(&self, rng: &mut dyn RngCore) -> usize {
+        let p: f64 = rng.gen();
+        self.cdf.partition_point(|c| *c < p).min(self.cdf.len() - 1)
+    }
+
+    fn key_count(&self) -> usize {
+        self.cdf.len()
+    }
+}
+
+/// `hot_fraction` of requests spread over the first `hot_keys` keys, the
+/// rest over all the others: the shape of a viral item or a shared config key.
+pub struct HotSet {
+    count: usize,
+    hot_keys: usize,
+    hot_fraction: f64,
+}
+
+impl HotSet {
+    pub fn new(count: usize, hot_keys: usize, hot_fraction: f64) -> Self {
+        let count = count.max(1);
+        Self { count, hot_keys: hot_keys.clamp(1, count), hot_fraction: hot_fraction.clamp(0.0, 1.0) }
+    }
+}
+
+impl KeyDistribution for HotSet {
+    fn sample(&self, rng: &mut dyn RngCore) -> usize {
+        if self.hot_keys == self.count || rng.gen_bool(self.hot_fraction) {
+            rng.gen_range(0..self.hot_keys)
+        } else {
+            rng.gen_range(self.hot_keys..self.count)
+        }
+    }
+
+    fn key_count(&self) -> usize {
+        self.count
+    }
+}
+
+/// A distribution by name, as written in workload profiles and on the
+/// command line: `uniform`, `zipfian:0.99`, `hotset:10:0.9`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "distribution", rename_all = "lowercase")]
+pub enum KeyPattern {
+    Uniform,
+    Zipfian {
+        #[serde(default = "default_skew")]
+        skew: f64,
+    },
+    HotSet {
+        #[serde(default = "default_hot_keys")]
+        hot_keys: usize,
+        #[serde(default = "default_hot_fraction")]
+        hot_fraction: f64,
+    },
+}
+
+fn default_skew() -> f64 {
+    0.99
+}
+
+fn default_hot_keys() -> usize {
+    10
+}
+
+fn default_hot_fraction() -> f64 {
+    0.9
+}
+
+impl KeyPattern {
+    pub fn build(&self, count: usize) -> Box<dyn KeyDistribution> {
+        match self {
+            KeyPattern::Uniform => Box::new(Uniform::new(count)),
+            KeyPattern::Zipfian { skew } => Box::new(Zipfian::new(count, *skew)),
+            KeyPattern::HotSet { hot_keys, hot_fraction } => Box::new(HotSet::new(count, *hot_keys, *hot_fraction)),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            KeyPattern::Zipfian { skew } if *skew < 0.0 => {
+                Err(DemoError::Configuration("Zipfian skew cannot be negative".to_string()))
+            }
+            KeyPattern::HotSet { hot_fraction, .. } if !(0.0..=1.0).contains(hot_fraction) => {
+                Err(DemoError::Configuration("Hot set fraction must be between 0 and 1".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for KeyPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyPattern::Uniform => write!(f, "uniform"),
+            KeyPattern::Zipfian { skew } => write!(f, "zipfian:{}", skew),
+            KeyPattern::HotSet { hot_keys, hot_fraction } => write!(f, "hotset:{}:{}", hot_keys, hot_fraction),
+        }
+    }
+}
+
+impl FromStr for KeyPattern {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split(':');
+        let name = parts.next().unwrap_or_default().to_lowercase();
+        let mut number = |what: &str| -> Result<Option<f64>> {
+            parts
+                .next()
+                .map(|part| part.parse().map_err(|_| DemoError::Configuration(format!("Invalid {} '{}'", what, part))))
+                .transpose()
+        };
+        let pattern = match name.as_str() {
+            "uniform" => KeyPattern::Uniform,
+            "zipfian" | "zipf" => KeyPattern::Zipfian { skew: number("skew")?.unwrap_or_else(default_skew) },
+            "hotset" | "hot" => KeyPattern::HotSet {
+                hot_keys: number("hot key count")?.map(|n| n as usize).unwrap_or_else(default_hot_keys),
+                hot_fraction: number("hot fraction")?.unwrap_or_else(default_hot_fraction),
+            },
+            _ => {
+                return Err(DemoError::Configuration(format!(
+                    "Unknown key distribution '{}' (uniform, zipfian[:skew], hotset[:keys[:fraction]])",
+                    s
+                )))
+            }
+        };
+        pattern.validate()?;
+        Ok(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Share of `samples` draws landing on the first `top` keys.
+    fn top_share(distribution: &dyn KeyDistribution, top: usize, samples: usize) -> f64 {
+        let mut rng = StdRng::seed_from_u64(7);
+        let hits = (0..samples).filter(|_| distribution.sample(&mut rng) < top).count();
+        hits as f64 / samples as f64
+    }
+
+    #[test]
+    fn test_uniform_is_flat() {
+        let uniform = Uniform::new(100);
+        assert!((top_share(&uniform, 10, 100_000) - 0.10).abs() < 0.01);
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!((0..1000).all(|_| uniform.sample(&mut rng) < 100));
+    }
+
+    #[test]
+    fn test_zipfian_skew() {
+        // With s = 1 over 1000 keys, rank 1 gets 1 / H(1000) ≈ 13.4% and the
+        // top 10 get H(10) / H(1000) ≈ 39%.
+        let zipf = Zipfian::new(1000, 1.0);
+        assert!((top_share(&zipf, 1, 100_000) - 0.134).abs() < 0.01);
+        assert!((top_share(&zipf, 10, 100_000) - 0.39).abs() < 0.01);
+        assert!((top_share(&Zipfian::new(1000, 0.0), 10, 100_000) - 0.01).abs() < 0.005);
+    }
+
+    #[test]
+    fn test_hot_set_share() {
+        let hot = HotSet::new(10_000, 10, 0.9);
+        assert!((top_share(&hot, 10, 100_000) - 0.9).abs() < 0.01);
+        // Cold traffic never lands on hot keys, so key 10 onwards gets the rest.
+        assert!((top_share(&hot, 11, 100_000) - 0.9).abs() < 0.01);
+        assert_eq!(HotSet::new(5, 50, 0.5).hot_keys, 5);
+    }
+
+    #[test]
+    fn test_pattern_parsing() {
+        assert_eq!("uniform".parse::<KeyPattern>().unwrap(), KeyPattern::Uniform);
+        assert_eq!("zipfian".parse::<KeyPattern>().unwrap(), KeyPattern::Zipfian { skew: 0.99 });
+        assert_eq!("zipf:1.2".parse::<KeyPattern>().unwrap(), KeyPattern::Zipfian { skew: 1.2 });
+        assert_eq!(
+            "hotset:5:0.8".parse::<KeyPattern>().unwrap(),
+            KeyPattern::HotSet { hot_keys: 5, hot_fraction: 0.8 }
+        );
+        assert_eq!("hotset:5:0.8".parse::<KeyPattern>().unwrap().to_string(), "hotset:5:0.8");
+        assert!("hotset:5:1.5".parse::<KeyPattern>().is_err());
+        assert!("zipfian:x".parse::<KeyPattern>().is_err());
+        assert!("gaussian".parse::<KeyPattern>().is_err());
+        assert_eq!(KeyPattern::HotSet { hot_keys: 3, hot_fraction: 1.0 }.build(100).key_count(), 100);
+    }
+}
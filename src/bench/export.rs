@@ -0,0 +1,114 @@
+use crate::export::json::peak_rss_bytes;
+use crate::export::{ExportOptions, ExportSummary, JsonExporter, ExportFormat};
+use crate::utils::PartitionedScan;
+use crate::{DemoError, RedisClient, Result};
+use std::time::Instant;
+use tracing::info;
+
+const KEY_PREFIX: &str = "exportbench:";
+
+fn mib(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/// Seeds a large keyspace, plus a few collections far bigger than one read
+/// window, and exports it with a small memory budget. Fails when the
+/// process's peak RSS grows by more than the budget during the export, to
+/// show the exporter's memory use tracks the budget rather than the data size.
+pub struct ExportBench {
+    client: RedisClient,
+}
+
+impl ExportBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn run(&self, keys: usize, memory_budget: usize) -> Result<ExportSummary> {
+        let mut conn = self.client.get_async_connection().await?;
+        let pattern = format!("{}*", KEY_PREFIX);
+
+        println!("\n=== Streaming JSON Export ===\n");
+        println!("1. Seeding {} keys (strings, hashes and sorted sets)...", keys);
+        for start in (0..keys).step_by(1000) {
+            let mut pipe = redis::pipe();
+            for i in start..(start + 1000).min(keys) {
+                let key = format!("{}{:08}", KEY_PREFIX, i);
+                match i % 3 {
+                    0 => pipe.set(&key, format!("value-{}", i)).ignore(),
+                    1 => pipe.hset_multiple(&key, &[("name", format!("user {}", i)), ("plan", "pro".to_string())]).ignore(),
+                    _ => pipe.zadd(&key, "member", i).ignore(),
+                };
+            }
+            let _: () = pipe.query_async(&mut conn).await?;
+        }
+        let elements = (keys / 10).max(1);
+        println!("   ...and a list, hash, set and sorted set of {} elements each", elements);
+        for start in (0..elements).step_by(1000) {
+            let members: Vec<String> = (start..(start + 1000).min(elements)).map(|i| format!("member-{:08}", i)).collect();
+            let fields: Vec<(&String, usize)> = members.iter().zip(start..).collect();
+            let scored: Vec<(usize, &String)> = (start..).zip(members.iter()).collect();
+            let _: () = redis::pipe()
+                .rpush(format!("{}big:list", KEY_PREFIX), &members)
+                .ignore()
+                .hset_multiple(format!("{}big:hash", KEY_PREFIX), &fields)
+                .ignore()
+                .sadd(format!("{}big:set", KEY_PREFIX), &members)
+                .ignore()
+                .zadd_multiple(format!("{}big:zset", KEY_PREFIX), &scored)
+                .ignore()
+                .query_async(&mut conn)
+                .await?;
+        }
+
+        let out = std::env::temp_dir().join(format!("exportbench-{}.ndjson", std::process::id()));
+        let options = ExportOptions {
+            format: ExportFormat::Ndjson,
+            memory_budget,
+            ..ExportOptions::new(&pattern, &out)
+        };
+        println!("\n2. Exporting to {} with a {:.1} MiB budget", out.display(), mib(memory_budget as u64));
+        let rss_before = peak_rss_bytes();
+        let started = Instant::now();
+        let summary = JsonExporter::new(self.client.clone()).run(&options).await?;
+        let elapsed = started.elapsed();
+        println!("   {} keys, {:.1} MiB written in {:.1?}", summary.keys, mib(summary.bytes), elapsed);
+        println!("   peak buffered between fetch and write: {:.2} MiB", mib(summary.peak_buffered as u64));
+        let growth = match (rss_before, peak_rss_bytes()) {
+            (Some(before), Some(after)) => {
+                println!("   process peak RSS: {:.1} MiB before, {:.1} MiB after", mib(before), mib(after));
+                Some(after.saturating_sub(before))
+            }
+            _ => {
+                println!("   (peak RSS isn't available on this platform)");
+                None
+            }
+        };
+
+        println!("\n3. Cleaning up");
+        std::fs::remove_file(&out)?;
+        PartitionedScan::by_prefix(&self.client, &pattern, 4)
+            .run(|mut conn, batch| async move {
+                let _: () = redis::cmd("UNLINK").arg(&batch).query_async(&mut conn).await?;
+                Ok(Vec::<()>::new())
+            })
+            .await?;
+
+        if let Some(growth) = growth {
+            if growth > memory_budget as u64 {
+                println!("\n❌ Peak RSS grew by {:.1} MiB, over the {:.1} MiB budget", mib(growth), mib(memory_budget as u64));
+                return Err(DemoError::Demo(format!(
+                    "export peak RSS grew by {} bytes, over the {} byte budget",
+                    growth, memory_budget
+                )));
+            }
+            println!("\n✅ Peak RSS grew by {:.1} MiB, within the {:.1} MiB budget", mib(growth), mib(memory_budget as u64));
+        }
+
+        println!("\n💡 The export is bounded by its stages, not the keyspace: two key batches");
+        println!("   in flight, collections read a window at a time, and serialized output");
+        println!("   only up to the budget before the fetch stage has to wait for the writer.");
+        info!("Export benchmark completed");
+        Ok(summary)
+    }
+}
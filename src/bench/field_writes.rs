@@ -0,0 +1,158 @@
+use crate::models::User;
+use crate::repository::{Entity, EntityStore};
+use crate::{RedisClient, Result};
+use chrono::Utc;
+use redis::AsyncCommands;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+const DOC_PREFIX: &str = "bench:fields:doc:";
+
+/// What one way of saving the updates cost.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WriteCost {
+    pub saves: usize,
+    /// Field names and values sent, or the whole document for a JSON save.
+    pub bytes: usize,
+    pub elapsed: Duration,
+}
+
+impl WriteCost {
+    pub fn bytes_per_save(&self) -> f64 {
+        self.bytes as f64 / self.saves.max(1) as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldWritesReport {
+    /// Each save re-serializes the user and SETs the JSON document.
+    pub document: WriteCost,
+    /// Each save HSETs only the fields that changed.
+    pub dirty_fields: WriteCost,
+}
+
+impl FieldWritesReport {
+    /// How many times more bytes the document saves wrote.
+    pub fn amplification(&self) -> f64 {
+        match self.dirty_fields.bytes {
+            0 => 0.0,
+            bytes => self.document.bytes as f64 / bytes as f64,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!("   {:<14} {:>8} {:>12} {:>12} {:>12}\n", "", "saves", "bytes", "bytes/save", "total");
+        for (name, cost) in [("JSON document", &self.document), ("dirty fields", &self.dirty_fields)] {
+            out.push_str(&format!(
+                "   {:<14} {:>8} {:>12} {:>12.1} {:>12}\n",
+                name,
+                cost.saves,
+                cost.bytes,
+                cost.bytes_per_save(),
+                format!("{:.2?}", cost.elapsed)
+            ));
+        }
+        out
+    }
+}
+
+/// Records logins for a set of users (`login_count` and `last_login`
+/// change, nothing else) saved as whole JSON documents and through
+/// [`EntityStore::save_changes`], to show the write amplification of the
+/// former.
+pub struct FieldWriteBench {
+    client: RedisClient,
+}
+
+impl FieldWriteBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn run(&self, users: usize, updates: usize) -> Result<FieldWritesReport> {
+        let mut conn = self.client.get_async_connection().await?;
+        let mut store = EntityStore::<User>::new(&self.client).await?;
+
+        println!("\n=== Field-Level Writes ===\n");
+        println!("1. Storing {} users both ways", users);
+        let mut docs = Vec::with_capacity(users);
+        let mut tracked = Vec::with_capacity(users);
+        for n in 0..users {
+            let mut user = User::new(format!("bench{}", n), format!("bench{}@example.com", n), format!("Bench User {}", n));
+            user.city = Some("Springfield".to_string());
+            user.country = Some("US".to_string());
+            user.age = Some(30);
+            let _: () = conn.set(format!("{}{}", DOC_PREFIX, user.id), serde_json::to_string(&user)?).await?;
+            store.put(&user).await?;
+            if let Some(user) = store.load(&user.entity_id()).await? {
+                tracked.push(user);
+            }
+            docs.push(user);
+        }
+
+        println!("\n2. {} logins per user, each saved as a JSON document:", updates);
+        let mut document = WriteCost::default();
+        let started = Instant::now();
+        for _ in 0..updates {
+            for user in docs.iter_mut() {
+                user.login_count += 1;
+                user.last_login = Some(Utc::now());
+                let json = serde_json::to_string(user)?;
+                document.bytes += json.len();
+                document.saves += 1;
+                let _: () = conn.set(format!("{}{}", DOC_PREFIX, user.id), json).await?;
+            }
+        }
+        document.elapsed = started.elapsed();
+        println!("   {} saves, {} bytes", document.saves, document.bytes);
+
+        println!("\n3. The same logins saved as dirty fields:");
+        let mut dirty_fields = WriteCost::default();
+        let started = Instant::now();
+        for _ in 0..updates {
+            for user in tracked.iter_mut() {
+                user.login_count += 1;
+                user.last_login = Some(Utc::now());
+                let stats = store.save_changes(user).await?;
+                dirty_fields.bytes += stats.bytes;
+                dirty_fields.saves += 1;
+            }
+        }
+        dirty_fields.elapsed = started.elapsed();
+        println!("   {} saves, {} bytes", dirty_fields.saves, dirty_fields.bytes);
+
+        let report = FieldWritesReport { document, dirty_fields };
+        println!("\nComparison:");
+        print!("{}", report.render());
+
+        let mut pipe = redis::pipe();
+        for user in &docs {
+            pipe.del(format!("{}{}", DOC_PREFIX, user.id)).ignore();
+            store.delete(&user.entity_id()).await?;
+        }
+        let _: () = pipe.query_async(&mut conn).await?;
+        println!("\n💡 Two fields changed per login, yet the document save rewrote all of");
+        println!("   them: {:.1}x the bytes. That is also what replicas and the AOF receive.", report.amplification());
+        println!("   The price of fields is that a hash holds the user, so readers need");
+        println!("   HGETALL and a decode per field rather than GET of one JSON value.");
+        info!("Field writes benchmark completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amplification_and_render() {
+        let cost = |saves, bytes, millis| WriteCost { saves, bytes, elapsed: Duration::from_millis(millis) };
+        let report = FieldWritesReport { document: cost(100, 25_000, 40), dirty_fields: cost(100, 5_000, 45) };
+        assert_eq!(report.amplification(), 5.0);
+        let rendered = report.render();
+        let rows: Vec<&str> = rendered.lines().collect();
+        assert!(rows[1].contains("JSON document") && rows[1].contains("25000") && rows[1].contains("250.0"), "{}", rendered);
+        assert!(rows[2].contains("dirty fields") && rows[2].contains("50.0"), "{}", rendered);
+        assert_eq!(FieldWritesReport { document: cost(1, 10, 1), dirty_fields: WriteCost::default() }.amplification(), 0.0);
+    }
+}
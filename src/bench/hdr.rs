@@ -0,0 +1,251 @@
+use super::stats::LatencySummary;
+use crate::report::{DiffKind, DiffRecord};
+use crate::{DemoError, Result};
+use hdrhistogram::serialization::interval_log::IntervalLogWriterBuilder;
+use hdrhistogram::serialization::V2DeflateSerializer;
+use hdrhistogram::Histogram;
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+/// Latencies are recorded in microseconds and reported in milliseconds, as
+/// HdrHistogram's own tools do with `outputValueUnitScalingRatio = 1000`.
+const SCALE: f64 = 1000.0;
+
+/// Quantiles `--compare` reports; 1.0 is the max.
+pub const COMPARED_QUANTILES: [f64; 6] = [0.5, 0.9, 0.99, 0.999, 0.9999, 1.0];
+
+/// A microsecond histogram covering 1µs to one minute at 3 significant digits.
+pub fn latency_histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, 60_000_000, 3).expect("valid histogram bounds")
+}
+
+pub fn record(histogram: &mut Histogram<u64>, latency: Duration) {
+    histogram.saturating_record(latency.as_micros().max(1) as u64);
+}
+
+impl LatencySummary {
+    pub fn from_histogram(histogram: &Histogram<u64>) -> Self {
+        if histogram.is_empty() {
+            return Self::default();
+        }
+        let at = |quantile| Duration::from_micros(histogram.value_at_quantile(quantile));
+        Self {
+            count: histogram.len() as usize,
+            mean: Duration::from_secs_f64(histogram.mean() / 1_000_000.0),
+            p50: at(0.50),
+            p95: at(0.95),
+            p99: at(0.99),
+            max: Duration::from_micros(histogram.max()),
+        }
+    }
+}
+
+/// Writes the percentile distribution in the `.hgrm` text format that
+/// HdrHistogram's plotter and `HistogramLogProcessor` produce, in milliseconds.
+pub fn write_percentiles<W: Write>(histogram: &Histogram<u64>, out: &mut W) -> Result<()> {
+    writeln!(out, "{:>12} {:>14} {:>10} {:>14}\n", "Value", "Percentile", "TotalCount", "1/(1-Percentile)")?;
+    let mut total = 0u64;
+    for step in histogram.iter_quantiles(5) {
+        total += step.count_since_last_iteration();
+        let value = step.value_iterated_to() as f64 / SCALE;
+        let quantile = step.quantile_iterated_to();
+        if quantile < 1.0 {
+            writeln!(out, "{:12.3} {:2.12} {:10} {:14.2}", value, quantile, total, 1.0 / (1.0 - quantile))?;
+        } else {
+            writeln!(out, "{:12.3} {:2.12} {:10}", value, quantile, total)?;
+        }
+    }
+    writeln!(
+        out,
+        "#[Mean    = {:12.3}, StdDeviation   = {:12.3}]",
+        histogram.mean() / SCALE,
+        histogram.stdev() / SCALE
+    )?;
+    writeln!(
+        out,
+        "#[Max     = {:12.3}, Total count    = {:12}]",
+        histogram.max() as f64 / SCALE,
+        histogram.len()
+    )?;
+    writeln!(
+        out,
+        "#[Buckets = {:12}, SubBuckets     = {:12}]",
+        histogram.buckets(),
+        histogram.distinct_values()
+    )?;
+    Ok(())
+}
+
+/// `(quantile, value_ms)` rows of a `.hgrm` file.
+pub fn parse_percentiles(text: &str) -> Result<Vec<(f64, f64)>> {
+    let rows: Vec<(f64, f64)> = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let value = fields.next()?.parse().ok()?;
+            let quantile = fields.next()?.parse().ok()?;
+            Some((quantile, value))
+        })
+        .collect();
+    if rows.is_empty() {
+        return Err(DemoError::Demo("No percentile rows found in .hgrm input".to_string()));
+    }
+    Ok(rows)
+}
+
+/// Value at the first row reaching `quantile`.
+fn value_at(rows: &[(f64, f64)], quantile: f64) -> f64 {
+    rows.iter()
+        .find(|(q, _)| *q >= quantile)
+        .or(rows.last())
+        .map(|(_, value)| *value)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PercentileDiff {
+    pub quantile: f64,
+    pub baseline_ms: f64,
+    pub current_ms: f64,
+}
+
+impl PercentileDiff {
+    /// `"99.90"`, or `"max"` for the 100th percentile.
+    pub fn label(&self) -> String {
+        if self.quantile >= 1.0 {
+            "max".to_string()
+        } else {
+            format!("{:.2}", self.quantile * 100.0)
+        }
+    }
+
+    /// Relative change from the baseline, in percent.
+    pub fn change_pct(&self) -> f64 {
+        if self.baseline_ms == 0.0 {
+            return 0.0;
+        }
+        (self.current_ms - self.baseline_ms) / self.baseline_ms * 100.0
+    }
+}
+
+impl From<&PercentileDiff> for DiffRecord {
+    fn from(diff: &PercentileDiff) -> Self {
+        DiffRecord {
+            key: format!("p{}", diff.label()),
+            kind: DiffKind::Changed,
+            left: Some(diff.current_ms.into()),
+            right: Some(diff.baseline_ms.into()),
+            action: None,
+            detail: Some(format!("{:+.1}%", diff.change_pct())),
+        }
+    }
+}
+
+/// Baseline against current latency, one row per compared percentile.
+pub fn render_comparison(diffs: &[PercentileDiff]) -> String {
+    let mut out = format!("{:>10} {:>12} {:>12} {:>9}\n", "percentile", "baseline ms", "current ms", "change");
+    for diff in diffs {
+        out.push_str(&format!(
+            "{:>10} {:>12.3} {:>12.3} {:>+8.1}%\n",
+            diff.label(),
+            diff.baseline_ms,
+            diff.current_ms,
+            diff.change_pct()
+        ));
+    }
+    out
+}
+
+/// Compares two `.hgrm` distributions at [`COMPARED_QUANTILES`].
+pub fn compare(baseline: &[(f64, f64)], current: &[(f64, f64)]) -> Vec<PercentileDiff> {
+    COMPARED_QUANTILES
+        .iter()
+        .map(|&quantile| PercentileDiff {
+            quantile,
+            baseline_ms: value_at(baseline, quantile),
+            current_ms: value_at(current, quantile),
+        })
+        .collect()
+}
+
+/// Writes one histogram per interval in HdrHistogram's interval log format
+/// (`.hlog`), readable by `HistogramLogProcessor` and HdrHistogramVisualizer.
+pub fn write_interval_log<W: Write>(
+    intervals: &[Histogram<u64>],
+    interval: Duration,
+    started_at: SystemTime,
+    out: &mut W,
+) -> Result<()> {
+    let mut serializer = V2DeflateSerializer::new();
+    let mut log = IntervalLogWriterBuilder::new()
+        .add_comment("Latencies recorded by redis-rust-demo in microseconds")
+        .with_start_time(started_at)
+        .with_base_time(started_at)
+        .with_max_value_divisor(SCALE)
+        .begin_log_with(out, &mut serializer)?;
+    for (i, histogram) in intervals.iter().enumerate() {
+        log.write_histogram(histogram, interval * i as u32, interval, None)
+            .map_err(|e| DemoError::Demo(format!("Failed to write interval log: {}", e)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hdrhistogram::serialization::interval_log::{IntervalLogIterator, LogEntry};
+
+    fn histogram_of(millis: impl IntoIterator<Item = u64>) -> Histogram<u64> {
+        let mut histogram = latency_histogram();
+        for ms in millis {
+            record(&mut histogram, Duration::from_millis(ms));
+        }
+        histogram
+    }
+
+    #[test]
+    fn test_summary_from_histogram() {
+        let summary = LatencySummary::from_histogram(&histogram_of(1..=100));
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.p50.as_millis(), 50);
+        assert_eq!(summary.p99.as_millis(), 99);
+        assert_eq!(summary.max.as_millis(), 100);
+        assert_eq!(LatencySummary::from_histogram(&latency_histogram()), LatencySummary::default());
+    }
+
+    #[test]
+    fn test_percentiles_round_trip_and_compare() {
+        let mut hgrm = Vec::new();
+        write_percentiles(&histogram_of(1..=100), &mut hgrm).unwrap();
+        let text = String::from_utf8(hgrm).unwrap();
+        assert!(text.contains("#[Max     =      100.031"));
+
+        let baseline = parse_percentiles(&text).unwrap();
+        assert_eq!(baseline.first().unwrap().0, 0.0);
+        assert_eq!(baseline.last().unwrap().0, 1.0);
+
+        let mut slower = Vec::new();
+        write_percentiles(&histogram_of((1..=100).map(|ms| ms * 2)), &mut slower).unwrap();
+        let current = parse_percentiles(&String::from_utf8(slower).unwrap()).unwrap();
+        let diffs = compare(&baseline, &current);
+        assert_eq!(diffs.len(), COMPARED_QUANTILES.len());
+        assert!(diffs.iter().all(|diff| (diff.change_pct() - 100.0).abs() < 1.0));
+
+        assert!(parse_percentiles("#[Mean = 1]\n").is_err());
+    }
+
+    #[test]
+    fn test_interval_log_is_readable() {
+        let mut log = Vec::new();
+        let intervals = vec![histogram_of([1, 2]), histogram_of([3])];
+        write_interval_log(&intervals, Duration::from_secs(1), SystemTime::UNIX_EPOCH, &mut log).unwrap();
+        let histograms: Vec<_> = IntervalLogIterator::new(&log)
+            .filter_map(|entry| match entry.unwrap() {
+                LogEntry::Interval(interval) => Some(interval.start_timestamp()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(histograms, vec![Duration::ZERO, Duration::from_secs(1)]);
+    }
+}
@@ -0,0 +1,133 @@
+use super::stats::LatencySummary;
+use crate::models::User;
+use crate::repository::fields::{from_fields, Fields};
+use crate::repository::user::{UserRepository, LIST_INDEX};
+use crate::{RedisClient, Result};
+use crate::utils::RedisConnection;
+use redis::AsyncCommands;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HydrationReport {
+    pub users: usize,
+    pub page_size: usize,
+    pub naive: LatencySummary,
+    pub batched: LatencySummary,
+}
+
+impl HydrationReport {
+    /// How many times faster the batched pages were at the median.
+    pub fn speedup(&self) -> f64 {
+        if self.batched.p50.is_zero() {
+            return 0.0;
+        }
+        self.naive.p50.as_secs_f64() / self.batched.p50.as_secs_f64()
+    }
+}
+
+/// The list view as it is usually written first: one ZRANGE, then an
+/// HGETALL per user, i.e. `n + 1` round trips per page.
+pub async fn naive_page(conn: &mut RedisConnection, offset: usize, n: usize) -> Result<Vec<User>> {
+    let ids: Vec<String> = conn.zrange(LIST_INDEX, offset as isize, (offset + n) as isize - 1).await?;
+    let mut users = Vec::with_capacity(ids.len());
+    for id in ids {
+        let fields: Fields = conn.hgetall(format!("user:{}", id)).await?;
+        if !fields.is_empty() {
+            users.push(from_fields(&fields)?);
+        }
+    }
+    Ok(users)
+}
+
+/// Compares paging through users with N HGETALLs against
+/// [`UserRepository::list_page`]'s single pipeline of them.
+pub struct HydrationBench {
+    client: RedisClient,
+}
+
+impl HydrationBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn run(&self, users: usize, page_size: usize) -> Result<HydrationReport> {
+        let page_size = page_size.max(1);
+        let mut repo = UserRepository::new(&self.client, Duration::from_secs(60)).await?;
+        let mut conn = self.client.get_async_connection().await?;
+
+        println!("\n=== List View Hydration: N HGETALLs vs One Pipeline ===\n");
+        println!("1. Seeding {} users...", users);
+        let seeded: Vec<User> = (0..users)
+            .map(|i| User::new(format!("bench_user_{}", i), format!("bench_user_{}@example.com", i), format!("Bench User {}", i)))
+            .collect();
+        for user in &seeded {
+            repo.save(user).await?;
+        }
+
+        println!("\n2. Paging through all users, {} per page:", page_size);
+        let mut naive = Vec::new();
+        let mut offset = 0;
+        loop {
+            let started = Instant::now();
+            let page = naive_page(&mut conn, offset, page_size).await?;
+            naive.push(started.elapsed());
+            if page.len() < page_size {
+                break;
+            }
+            offset += page_size;
+        }
+
+        let mut batched = Vec::new();
+        let mut cursor = None;
+        loop {
+            let started = Instant::now();
+            let page = repo.list_page(cursor.as_ref(), page_size).await?;
+            batched.push(started.elapsed());
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let report = HydrationReport {
+            users,
+            page_size,
+            naive: LatencySummary::from_samples(naive),
+            batched: LatencySummary::from_samples(batched),
+        };
+        println!("   naive   (ZRANGE + {} HGETALLs): {}", page_size, report.naive);
+        println!("   batched (script + 1 pipeline):  {}", report.batched);
+        println!("   → {:.1}x faster per page at p50", report.speedup());
+
+        let mut pipe = redis::pipe();
+        for user in &seeded {
+            pipe.del(&[user.redis_key(), user.username_index_key(), user.email_index_key()])
+                .ignore()
+                .zrem(LIST_INDEX, user.id.to_string())
+                .ignore();
+        }
+        let _: () = pipe.query_async(&mut conn).await?;
+
+        println!("\n💡 Round trips, not Redis work, dominate small reads: a page costs");
+        println!("   n + 1 network hops one HGETALL at a time but two pipelined, whatever n is.");
+        info!("Hydration benchmark completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speedup() {
+        let report = HydrationReport {
+            users: 10,
+            page_size: 5,
+            naive: LatencySummary { p50: Duration::from_millis(6), ..Default::default() },
+            batched: LatencySummary { p50: Duration::from_millis(2), ..Default::default() },
+        };
+        assert_eq!(report.speedup(), 3.0);
+    }
+}
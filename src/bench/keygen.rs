@@ -0,0 +1,214 @@
+use super::workload::ValueSize;
+use crate::{DemoError, RedisClient, Result};
+use rand::distributions::Alphanumeric;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Seq,
+    Rand(u64),
+    Uuid,
+}
+
+/// Key names like `order:{seq}:items`. `{seq}` is the key's sequence
+/// number, `{rand:N}` a random number below N and `{uuid}` a random UUID.
+/// Any other brace is kept as is, so `{user:{seq}}:cart` gives hash-tagged
+/// keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyTemplate {
+    parts: Vec<Part>,
+}
+
+impl KeyTemplate {
+    pub fn render<R: Rng>(&self, seq: u64, rng: &mut R) -> String {
+        let mut key = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => key.push_str(text),
+                Part::Seq => key.push_str(&seq.to_string()),
+                Part::Rand(below) => key.push_str(&rng.gen_range(0..*below).to_string()),
+                Part::Uuid => key.push_str(&uuid::Builder::from_random_bytes(rng.gen()).into_uuid().to_string()),
+            }
+        }
+        key
+    }
+}
+
+impl FromStr for KeyTemplate {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = s;
+        while let Some(at) = rest.find('{') {
+            literal.push_str(&rest[..at]);
+            rest = &rest[at..];
+            let placeholder = rest.find('}').and_then(|end| placeholder(&rest[1..end]).map(|part| (part, end)));
+            match placeholder {
+                Some((part, end)) => {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    parts.push(part?);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    literal.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        literal.push_str(rest);
+        parts.push(Part::Literal(literal));
+        parts.retain(|part| !matches!(part, Part::Literal(text) if text.is_empty()));
+        if !parts.iter().any(|part| matches!(part, Part::Seq | Part::Uuid)) {
+            return Err(DemoError::Configuration(format!(
+                "Key template '{}' needs {{seq}} or {{uuid}}, or every key would have the same few names",
+                s
+            )));
+        }
+        Ok(Self { parts })
+    }
+}
+
+/// The placeholder named between braces, `None` if it isn't one.
+fn placeholder(name: &str) -> Option<Result<Part>> {
+    match name {
+        "seq" => Some(Ok(Part::Seq)),
+        "uuid" => Some(Ok(Part::Uuid)),
+        _ => name.strip_prefix("rand:").map(|below| match below.parse::<u64>() {
+            Ok(below) if below > 0 => Ok(Part::Rand(below)),
+            _ => Err(DemoError::Configuration(format!("{{rand:{}}} needs a positive bound", below))),
+        }),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GenOptions {
+    pub template: KeyTemplate,
+    pub count: u64,
+    /// First `{seq}`.
+    pub start: u64,
+    pub value_size: ValueSize,
+    pub ttl: Option<Duration>,
+    /// Keys per pipelined round trip.
+    pub batch_size: usize,
+    /// Same seed, same names and values.
+    pub seed: Option<u64>,
+}
+
+impl GenOptions {
+    pub fn new(template: KeyTemplate, count: u64) -> Self {
+        Self { template, count, start: 0, value_size: ValueSize::Fixed { size: 64 }, ttl: None, batch_size: 1000, seed: None }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenSummary {
+    pub keys: u64,
+    pub value_bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl GenSummary {
+    pub fn keys_per_second(&self) -> f64 {
+        self.keys as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Writes `count` string keys named from the template, with random
+/// alphanumeric values, as pipelined SETs in batches.
+pub async fn generate(client: &RedisClient, options: &GenOptions) -> Result<GenSummary> {
+    let mut conn = client.get_async_connection().await?;
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let started = Instant::now();
+    let mut summary = GenSummary { keys: 0, value_bytes: 0, elapsed: Duration::ZERO };
+    let end = options.start + options.count;
+    let mut seq = options.start;
+    while seq < end {
+        let batch_end = (seq + options.batch_size.max(1) as u64).min(end);
+        let mut pipe = redis::pipe();
+        for n in seq..batch_end {
+            let key = options.template.render(n, &mut rng);
+            let size = options.value_size.sample(&mut rng);
+            let value: String = (&mut rng).sample_iter(Alphanumeric).take(size).map(char::from).collect();
+            let set = pipe.cmd("SET").arg(key).arg(value);
+            if let Some(ttl) = options.ttl {
+                set.arg("PX").arg(ttl.as_millis() as u64);
+            }
+            set.ignore();
+            summary.value_bytes += size as u64;
+        }
+        let _: () = pipe.query_async(&mut conn).await?;
+        summary.keys += batch_end - seq;
+        seq = batch_end;
+    }
+    summary.elapsed = started.elapsed();
+    info!("Generated {} keys in {:?}", summary.keys, summary.elapsed);
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, seq: u64) -> String {
+        template.parse::<KeyTemplate>().unwrap().render(seq, &mut StdRng::seed_from_u64(1))
+    }
+
+    #[test]
+    fn test_template_placeholders() {
+        assert_eq!(render("order:{seq}:items", 42), "order:42:items");
+        assert_eq!(render("{user:{seq}}:cart", 7), "{user:7}:cart");
+        assert_eq!(render("{seq}", 0), "0");
+        let key = render("shard:{rand:4}:{seq}", 1);
+        assert!(["shard:0:1", "shard:1:1", "shard:2:1", "shard:3:1"].contains(&key.as_str()), "{}", key);
+        assert_eq!(render("session:{uuid}", 0).len(), "session:".len() + 36);
+    }
+
+    #[test]
+    fn test_template_errors() {
+        assert!("static:key".parse::<KeyTemplate>().is_err());
+        assert!("bucket:{rand:8}".parse::<KeyTemplate>().is_err());
+        assert!("k:{rand:0}:{seq}".parse::<KeyTemplate>().is_err());
+        assert!("k:{rand:x}:{seq}".parse::<KeyTemplate>().is_err());
+        assert!("k:{other}:{seq}".parse::<KeyTemplate>().is_ok());
+    }
+
+    #[test]
+    fn test_same_seed_same_keys() {
+        let template: KeyTemplate = "k:{uuid}".parse().unwrap();
+        let keys = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..3).map(|n| template.render(n, &mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(9), keys(9));
+        assert_ne!(keys(9), keys(10));
+    }
+
+    #[tokio::test]
+    async fn test_generate() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut options = GenOptions::new("keygen-test:{seq}".parse().unwrap(), 25);
+        options.batch_size = 10;
+        options.value_size = ValueSize::Fixed { size: 16 };
+        options.ttl = Some(Duration::from_secs(60));
+        let summary = generate(&client, &options).await.unwrap();
+        assert_eq!((summary.keys, summary.value_bytes), (25, 400));
+
+        let mut conn = client.get_async_connection().await.unwrap();
+        let value: String = redis::cmd("GET").arg("keygen-test:24").query_async(&mut conn).await.unwrap();
+        assert_eq!(value.len(), 16);
+        let ttl: i64 = redis::cmd("TTL").arg("keygen-test:0").query_async(&mut conn).await.unwrap();
+        assert!(ttl > 0);
+        let keys: Vec<String> = (0..25).map(|n| format!("keygen-test:{}", n)).collect();
+        let _: () = redis::cmd("DEL").arg(&keys).query_async(&mut conn).await.unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+use super::hdr::{self, latency_histogram};
+use super::stats::{steady_state_start, LatencySummary};
+use super::workload::Workload;
+use crate::integrations::metric_registry::{LOAD_LATENCY, LOAD_LATENCY_MEAN, LOAD_OPS_PER_SECOND, LOAD_REQUESTS};
+use crate::integrations::MetricSet;
+use crate::{DemoError, RedisClient, Result};
+use hdrhistogram::Histogram;
+use redis::AsyncCommands;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::info;
+
+pub(crate) const KEY_PREFIX: &str = "bench:load:";
+/// Intervals that must agree for the run to count as steady.
+const STEADY_WINDOW: usize = 3;
+/// Largest coefficient of variation of interval mean latency within it.
+const STEADY_MAX_CV: f64 = 0.15;
+
+#[derive(Debug, Clone)]
+pub struct LoadOptions {
+    pub requests: usize,
+    pub concurrency: usize,
+    pub workload: Workload,
+    /// Unrecorded requests sent first, to open connections and warm caches.
+    pub warmup: usize,
+    /// Keep going past `requests` until the measured phase lasts this long.
+    pub min_duration: Duration,
+    /// Width of each histogram in the interval log.
+    pub interval: Duration,
+    /// Write the percentile distribution here (`.hgrm`).
+    pub hgrm: Option<PathBuf>,
+    /// Write per-interval histograms here (`.hlog`).
+    pub hlog: Option<PathBuf>,
+    /// A previous run's `.hgrm` to diff against.
+    pub compare: Option<PathBuf>,
+}
+
+impl LoadOptions {
+    pub fn new(requests: usize, concurrency: usize) -> Self {
+        Self {
+            requests,
+            concurrency,
+            workload: Workload::get_set(10_000, 64),
+            warmup: 1000,
+            min_duration: Duration::ZERO,
+            interval: Duration::from_secs(1),
+            hgrm: None,
+            hlog: None,
+            compare: None,
+        }
+    }
+}
+
+pub struct LoadReport {
+    /// Latencies from the steady part of the run.
+    pub total: Histogram<u64>,
+    /// Every interval, including the ones before steady state.
+    pub intervals: Vec<Histogram<u64>>,
+    /// First interval counted in `total`; `None` if the run never settled
+    /// and `total` covers everything.
+    pub steady_from: Option<usize>,
+    /// Time covered by `total`.
+    pub elapsed: Duration,
+    pub started_at: SystemTime,
+}
+
+impl LoadReport {
+    fn new(intervals: Vec<Histogram<u64>>, interval: Duration, elapsed: Duration, started_at: SystemTime) -> Self {
+        let means: Vec<f64> = intervals.iter().map(|histogram| histogram.mean()).collect();
+        // The last interval is cut short by the end of the run, so it does
+        // not get a say in where steady state starts.
+        let steady_from = steady_state_start(&means[..means.len().saturating_sub(1)], STEADY_WINDOW, STEADY_MAX_CV);
+        let skip = steady_from.unwrap_or(0);
+        let mut total = latency_histogram();
+        for histogram in &intervals[skip..] {
+            total.add(histogram).expect("histograms share bounds");
+        }
+        let elapsed = elapsed.saturating_sub(interval * skip as u32);
+        Self { total, intervals, steady_from, elapsed, started_at }
+    }
+
+    pub fn ops_per_sec(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        self.total.len() as f64 / self.elapsed.as_secs_f64()
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary::from_histogram(&self.total)
+    }
+
+    /// Throughput and latency quantiles of the steady part of the run.
+    pub fn record_metrics(&self, metrics: &mut MetricSet) {
+        let summary = self.summary();
+        metrics
+            .gauge(&LOAD_OPS_PER_SECOND, &[], self.ops_per_sec())
+            .gauge(&LOAD_REQUESTS, &[], summary.count as f64)
+            .gauge(&LOAD_LATENCY_MEAN, &[], summary.mean.as_secs_f64());
+        for (quantile, latency) in [("0.5", summary.p50), ("0.95", summary.p95), ("0.99", summary.p99), ("1", summary.max)] {
+            metrics.gauge(&LOAD_LATENCY, &[("quantile", quantile)], latency.as_secs_f64());
+        }
+    }
+}
+
+/// Adds `from` into `into` interval by interval.
+fn merge_intervals(into: &mut Vec<Histogram<u64>>, from: Vec<Histogram<u64>>) {
+    for (i, histogram) in from.into_iter().enumerate() {
+        if i == into.len() {
+            into.push(histogram);
+        } else {
+            into[i].add(&histogram).expect("histograms share bounds");
+        }
+    }
+}
+
+/// Load shaped by a [`Workload`] (GET/SET by default), recorded into HDR
+/// histograms so runs can be saved, plotted and compared.
+pub struct LoadBench {
+    client: RedisClient,
+}
+
+impl LoadBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Sends `requests` (and keeps going until `min_duration`), recording
+    /// each latency in the histogram of the interval it was sent in.
+    async fn drive(&self, opts: &LoadOptions, requests: usize, min_duration: Duration) -> Result<LoadReport> {
+        let conn = self.client.get_async_connection().await?;
+        let issued = Arc::new(AtomicUsize::new(0));
+        let sampler = Arc::new(opts.workload.sampler());
+        // Every value is a prefix of one buffer of the largest size.
+        let values = Arc::new("x".repeat(opts.workload.value_size.max()));
+        let started_at = SystemTime::now();
+        let started = Instant::now();
+
+        let handles: Vec<_> = (0..opts.concurrency.max(1))
+            .map(|_| {
+                let mut conn = conn.clone();
+                let issued = issued.clone();
+                let (sampler, values) = (sampler.clone(), values.clone());
+                let interval = opts.interval;
+                tokio::spawn(async move {
+                    let mut intervals: Vec<Histogram<u64>> = Vec::new();
+                    loop {
+                        let n = issued.fetch_add(1, Ordering::Relaxed);
+                        if n >= requests && started.elapsed() >= min_duration {
+                            break;
+                        }
+                        let (op, key, size) = {
+                            let mut rng = rand::thread_rng();
+                            let op = sampler.op(&mut rng);
+                            let key = format!("{}{}:{}", KEY_PREFIX, op.key_family(), sampler.key(&mut rng));
+                            (op, key, sampler.value_size(&mut rng))
+                        };
+                        let sent = Instant::now();
+                        op.execute(&mut conn, &key, &values[..size]).await?;
+                        let slot = (sent.duration_since(started).as_nanos() / interval.as_nanos().max(1)) as usize;
+                        while intervals.len() <= slot {
+                            intervals.push(latency_histogram());
+                        }
+                        hdr::record(&mut intervals[slot], sent.elapsed());
+                    }
+                    Ok::<_, DemoError>(intervals)
+                })
+            })
+            .collect();
+
+        let mut intervals = Vec::new();
+        for handle in handles {
+            merge_intervals(&mut intervals, handle.await.map_err(|e| DemoError::Demo(e.to_string()))??);
+        }
+        Ok(LoadReport::new(intervals, opts.interval, started.elapsed(), started_at))
+    }
+
+    pub async fn run(&self, opts: &LoadOptions) -> Result<LoadReport> {
+        println!("\n=== Load ===\n");
+        let workload = &opts.workload;
+        println!("Workload '{}': {}", workload.name, workload.describe_mix());
+        if let Some(description) = &workload.description {
+            println!("  {}", description);
+        }
+        println!(
+            "{} requests, {} workers, {} keys ({}), values up to {} bytes\n",
+            opts.requests, opts.concurrency, workload.keys.count, workload.keys.distribution, workload.value_size.max()
+        );
+        if opts.warmup > 0 {
+            println!("Warming up with {} requests...", opts.warmup);
+            self.drive(opts, opts.warmup, Duration::ZERO).await?;
+        }
+        let report = self.drive(opts, opts.requests, opts.min_duration).await?;
+        match report.steady_from {
+            Some(0) => println!("Steady from the first interval"),
+            Some(skip) => println!("Steady after {} × {:?}; earlier intervals excluded", skip, opts.interval),
+            None => println!(
+                "⚠ Never reached steady state (CV ≤ {} over {} intervals); reporting the whole run. Try a longer --min-duration.",
+                STEADY_MAX_CV, STEADY_WINDOW
+            ),
+        }
+        println!("{:.0} ops/s", report.ops_per_sec());
+        println!("{}", report.summary());
+
+        if let Some(path) = &opts.hgrm {
+            hdr::write_percentiles(&report.total, &mut BufWriter::new(File::create(path)?))?;
+            println!("\nPercentile distribution written to {}", path.display());
+        }
+        if let Some(path) = &opts.hlog {
+            let mut out = BufWriter::new(File::create(path)?);
+            hdr::write_interval_log(&report.intervals, opts.interval, report.started_at, &mut out)?;
+            println!("Interval log ({} intervals) written to {}", report.intervals.len(), path.display());
+        }
+        if let Some(path) = &opts.compare {
+            let baseline = hdr::parse_percentiles(&std::fs::read_to_string(path)?)?;
+            let mut current = Vec::new();
+            hdr::write_percentiles(&report.total, &mut current)?;
+            let current = hdr::parse_percentiles(&String::from_utf8_lossy(&current))?;
+            println!("\nAgainst {}:", path.display());
+            print!("{}", hdr::render_comparison(&hdr::compare(&baseline, &current)));
+        }
+
+        let mut conn = self.client.get_async_connection().await?;
+        let mut scanner = self.client.scan_keys(&format!("{}*", KEY_PREFIX)).await?;
+        while let Some(keys) = scanner.next_batch().await? {
+            if !keys.is_empty() {
+                let _: () = conn.del(keys).await?;
+            }
+        }
+        println!("\n💡 Save a run with --hgrm, then pass it to --compare after a change; the");
+        println!("   .hgrm and .hlog files also load into HdrHistogram's plotting tools.");
+        info!("Load benchmark completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_skips_unsteady_intervals() {
+        let interval_of = |ms: u64| {
+            let mut histogram = latency_histogram();
+            hdr::record(&mut histogram, Duration::from_millis(ms));
+            histogram
+        };
+        let intervals = [20, 9, 2, 2, 2, 2, 7].into_iter().map(interval_of).collect();
+        let report = LoadReport::new(intervals, Duration::from_secs(1), Duration::from_millis(6500), SystemTime::UNIX_EPOCH);
+        assert_eq!(report.steady_from, Some(2));
+        assert_eq!(report.total.len(), 5);
+        assert_eq!(report.elapsed, Duration::from_millis(4500));
+
+        let noisy = [20, 2, 20, 2].into_iter().map(interval_of).collect();
+        let report = LoadReport::new(noisy, Duration::from_secs(1), Duration::from_secs(4), SystemTime::UNIX_EPOCH);
+        assert_eq!(report.steady_from, None);
+        assert_eq!(report.total.len(), 4);
+    }
+
+    #[test]
+    fn test_merge_intervals() {
+        let mut first = latency_histogram();
+        hdr::record(&mut first, Duration::from_millis(1));
+        let mut intervals = vec![first.clone()];
+        merge_intervals(&mut intervals, vec![first.clone(), first]);
+        assert_eq!(intervals.len(), 2);
+        assert_eq!(intervals[0].len(), 2);
+        assert_eq!(intervals[1].len(), 1);
+    }
+}
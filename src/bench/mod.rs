@@ -0,0 +1,32 @@
+pub mod adaptive;
+pub mod auto_pipeline;
+pub mod bytes;
+pub mod commands;
+pub mod distribution;
+pub mod export;
+pub mod field_writes;
+pub mod hdr;
+pub mod hydration;
+pub mod keygen;
+pub mod load;
+pub mod scan;
+pub mod soak;
+pub mod stats;
+pub mod strategies;
+pub mod workload;
+
+pub use adaptive::{AdaptiveBench, AdaptiveReport, AimdLimit};
+pub use auto_pipeline::{AutoPipelineBench, AutoPipelineReport};
+pub use bytes::{BytesBench, BytesReport};
+pub use commands::{CommandBench, CommandBenchOptions, CommandBenchReport};
+pub use distribution::{KeyDistribution, KeyPattern};
+pub use export::ExportBench;
+pub use field_writes::{FieldWriteBench, FieldWritesReport};
+pub use hydration::{HydrationBench, HydrationReport};
+pub use keygen::{GenOptions, GenSummary, KeyTemplate};
+pub use load::{LoadBench, LoadOptions, LoadReport};
+pub use scan::{ScanBench, ScanReport};
+pub use soak::{SoakBench, StabilityReport};
+pub use stats::LatencySummary;
+pub use strategies::{StrategyBench, StrategyReport};
+pub use workload::{ValueSize, Workload};
@@ -0,0 +1,141 @@
+use crate::utils::PartitionedScan;
+use crate::{RedisClient, Result};
+use crate::utils::RedisConnection;
+use rand::Rng;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+const KEY_PREFIX: &str = "scanbench:";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanRun {
+    pub workers: usize,
+    pub elapsed: Duration,
+    pub keys: usize,
+    pub biggest: Option<(String, u64)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanReport {
+    pub keys: usize,
+    pub runs: Vec<ScanRun>,
+}
+
+impl ScanReport {
+    /// Speedup of each run over the single-worker one.
+    pub fn speedups(&self) -> Vec<(usize, f64)> {
+        let Some(baseline) = self.runs.first().map(|run| run.elapsed.as_secs_f64()) else {
+            return Vec::new();
+        };
+        self.runs
+            .iter()
+            .map(|run| {
+                let elapsed = run.elapsed.as_secs_f64();
+                (run.workers, if elapsed > 0.0 { baseline / elapsed } else { 0.0 })
+            })
+            .collect()
+    }
+}
+
+/// 1, 2, 4, ... up to and including `max`.
+pub fn worker_counts(max: usize) -> Vec<usize> {
+    let mut counts: Vec<usize> = std::iter::successors(Some(1usize), |n| Some(n * 2)).take_while(|n| *n < max).collect();
+    counts.push(max.max(1));
+    counts
+}
+
+/// A bigkeys-style job: MEMORY USAGE for every key of a batch, pipelined.
+async fn memory_usage(mut conn: RedisConnection, keys: Vec<String>) -> Result<Vec<(String, u64)>> {
+    let mut pipe = redis::pipe();
+    for key in &keys {
+        pipe.cmd("MEMORY").arg("USAGE").arg(key);
+    }
+    let sizes: Vec<Option<u64>> = pipe.query_async(&mut conn).await?;
+    Ok(keys.into_iter().zip(sizes).filter_map(|(key, size)| size.map(|size| (key, size))).collect())
+}
+
+/// Times a full-keyspace job with [`PartitionedScan`] at increasing worker
+/// counts against the same seeded keys.
+pub struct ScanBench {
+    client: RedisClient,
+}
+
+impl ScanBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn run(&self, keys: usize, max_workers: usize) -> Result<ScanReport> {
+        let mut conn = self.client.get_async_connection().await?;
+        let pattern = format!("{}*", KEY_PREFIX);
+
+        println!("\n=== Partitioned SCAN ===\n");
+        println!("1. Seeding {} keys with 10-2000 byte values...", keys);
+        let mut rng = rand::thread_rng();
+        for chunk in (0..keys).collect::<Vec<_>>().chunks(1000) {
+            let mut pipe = redis::pipe();
+            for _ in chunk {
+                let size = rng.gen_range(10..2000);
+                pipe.set(format!("{}{}", KEY_PREFIX, uuid::Uuid::new_v4().simple()), "x".repeat(size)).ignore();
+            }
+            let _: () = pipe.query_async(&mut conn).await?;
+        }
+
+        println!("\n2. MEMORY USAGE for every key, one SCAN per worker:");
+        let mut report = ScanReport { keys, runs: Vec::new() };
+        for workers in worker_counts(max_workers) {
+            let scan = PartitionedScan::by_prefix(&self.client, &pattern, workers);
+            let started = Instant::now();
+            let sizes = scan.run(memory_usage).await?;
+            let run = ScanRun {
+                workers,
+                elapsed: started.elapsed(),
+                keys: sizes.len(),
+                biggest: sizes.into_iter().max_by_key(|(_, size)| *size),
+            };
+            println!("   {:>2} workers: {:>8.1?} ({} keys)", run.workers, run.elapsed, run.keys);
+            report.runs.push(run);
+        }
+        for (workers, speedup) in report.speedups().into_iter().skip(1) {
+            println!("   → {:.1}x with {} workers", speedup, workers);
+        }
+        if let Some((key, size)) = report.runs.last().and_then(|run| run.biggest.clone()) {
+            println!("   biggest key: {} ({} bytes)", key, size);
+        }
+
+        println!("\n3. Cleaning up with the same scanner (UNLINK per batch)");
+        PartitionedScan::by_prefix(&self.client, &pattern, max_workers)
+            .run(|mut conn, batch| async move {
+                let _: () = redis::cmd("UNLINK").arg(&batch).query_async(&mut conn).await?;
+                Ok(Vec::<()>::new())
+            })
+            .await?;
+
+        println!("\n💡 Each worker's SCAN still walks the whole keyspace server-side; MATCH");
+        println!("   only filters what comes back. The win is overlapping the per-key work");
+        println!("   and round trips, so it grows with how much each batch costs. On a");
+        println!("   cluster, PartitionedScan::by_node splits the walk itself across masters.");
+        info!("Partitioned scan benchmark completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_counts() {
+        assert_eq!(worker_counts(1), vec![1]);
+        assert_eq!(worker_counts(8), vec![1, 2, 4, 8]);
+        assert_eq!(worker_counts(6), vec![1, 2, 4, 6]);
+        assert_eq!(worker_counts(0), vec![1]);
+    }
+
+    #[test]
+    fn test_speedups() {
+        let run = |workers, ms| ScanRun { workers, elapsed: Duration::from_millis(ms), keys: 10, biggest: None };
+        let report = ScanReport { keys: 10, runs: vec![run(1, 800), run(4, 200)] };
+        assert_eq!(report.speedups(), vec![(1, 1.0), (4, 4.0)]);
+    }
+}
@@ -0,0 +1,333 @@
+use super::hdr::{self, latency_histogram};
+use super::load::KEY_PREFIX;
+use super::workload::Workload;
+use crate::integrations::metric_registry::{CLIENT_OPEN_FDS, CLIENT_RSS, SERVER_CONNECTED_CLIENTS, SERVER_USED_MEMORY, SOAK_OPS, SOAK_P99};
+use crate::integrations::{MetricSet, MetricsExporter};
+use crate::server::replication::parse_info;
+use crate::utils::RedisConnection;
+use crate::{DemoError, RedisClient, Result};
+use hdrhistogram::Histogram;
+use redis::AsyncCommands;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Growth between the first and last quarter of a run that counts as drift.
+const MAX_GROWTH_PCT: f64 = 20.0;
+/// Extra open file descriptors or server connections that count as a leak.
+const MAX_HANDLE_GROWTH: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ResourceSample {
+    pub elapsed: Duration,
+    pub ops: u64,
+    pub p99: Duration,
+    pub rss_bytes: Option<u64>,
+    pub open_fds: Option<usize>,
+    pub server_clients: u64,
+    pub server_memory: u64,
+}
+
+impl ResourceSample {
+    pub fn record_metrics(&self, metrics: &mut MetricSet) {
+        metrics
+            .gauge(&SOAK_OPS, &[], self.ops as f64)
+            .gauge(&SOAK_P99, &[], self.p99.as_secs_f64())
+            .gauge(&SERVER_CONNECTED_CLIENTS, &[], self.server_clients as f64)
+            .gauge(&SERVER_USED_MEMORY, &[], self.server_memory as f64);
+        if let Some(rss) = self.rss_bytes {
+            metrics.gauge(&CLIENT_RSS, &[], rss as f64);
+        }
+        if let Some(fds) = self.open_fds {
+            metrics.gauge(&CLIENT_OPEN_FDS, &[], fds as f64);
+        }
+    }
+}
+
+/// Resident set size of this process (VmRSS), where the OS reports it.
+pub fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+pub fn open_fds() -> Option<usize> {
+    Some(std::fs::read_dir("/proc/self/fd").ok()?.count())
+}
+
+/// How one metric moved over the run: mean of the first quarter of samples
+/// against mean of the last quarter, which smooths out single spikes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drift {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Drift {
+    pub fn of(values: &[f64]) -> Option<Self> {
+        if values.len() < 4 {
+            return None;
+        }
+        let quarter = values.len() / 4;
+        let mean = |slice: &[f64]| slice.iter().sum::<f64>() / slice.len() as f64;
+        Some(Self { start: mean(&values[..quarter]), end: mean(&values[values.len() - quarter..]) })
+    }
+
+    pub fn growth_pct(&self) -> f64 {
+        if self.start == 0.0 {
+            return 0.0;
+        }
+        (self.end - self.start) / self.start * 100.0
+    }
+}
+
+type Metric = fn(&ResourceSample) -> Option<f64>;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StabilityReport {
+    pub samples: Vec<ResourceSample>,
+}
+
+impl StabilityReport {
+    /// One line per metric that drifted: relative growth for sizes and
+    /// latency, absolute growth for handles, where a few extra is already a
+    /// leak.
+    pub fn findings(&self) -> Vec<String> {
+        let series = |f: Metric| -> Option<Drift> {
+            let values: Option<Vec<f64>> = self.samples.iter().map(f).collect();
+            Drift::of(&values?)
+        };
+        let mut findings = Vec::new();
+        let relative: [(&str, Metric); 3] = [
+            ("client RSS", |s| s.rss_bytes.map(|b| b as f64)),
+            ("server used_memory", |s| Some(s.server_memory as f64)),
+            ("p99 latency", |s| Some(s.p99.as_secs_f64())),
+        ];
+        for (name, metric) in relative {
+            if let Some(drift) = series(metric).filter(|drift| drift.growth_pct() > MAX_GROWTH_PCT) {
+                findings.push(format!("{} grew {:.0}% from the first to the last quarter", name, drift.growth_pct()));
+            }
+        }
+        let absolute: [(&str, Metric); 2] = [
+            ("open file descriptors", |s| s.open_fds.map(|n| n as f64)),
+            ("server connected_clients", |s| Some(s.server_clients as f64)),
+        ];
+        for (name, metric) in absolute {
+            if let Some(drift) = series(metric).filter(|drift| drift.end - drift.start > MAX_HANDLE_GROWTH) {
+                findings.push(format!("{} went from {:.0} to {:.0}: possible leak", name, drift.start, drift.end));
+            }
+        }
+        findings
+    }
+}
+
+/// A moderate, rate-limited workload kept up for a long time while client
+/// and server resources are sampled, to catch slow leaks and drift that a
+/// short benchmark never runs long enough to show.
+pub struct SoakBench {
+    client: RedisClient,
+    metrics: Option<MetricsExporter>,
+}
+
+impl SoakBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client, metrics: None }
+    }
+
+    /// Publishes every sample as it is taken.
+    pub fn with_metrics(mut self, metrics: MetricsExporter) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    async fn sample(
+        conn: &mut RedisConnection,
+        started: Instant,
+        ops: &AtomicU64,
+        window: &Mutex<Histogram<u64>>,
+    ) -> Result<ResourceSample> {
+        let clients: String = redis::cmd("INFO").arg("clients").query_async(conn).await?;
+        let memory: String = redis::cmd("INFO").arg("memory").query_async(conn).await?;
+        let field = |text: &str, name: &str| parse_info(text).get(name).and_then(|v| v.parse().ok()).unwrap_or(0);
+        let p99 = {
+            let mut window = window.lock().expect("latency window poisoned");
+            let p99 = Duration::from_micros(window.value_at_quantile(0.99));
+            window.reset();
+            p99
+        };
+        Ok(ResourceSample {
+            elapsed: started.elapsed(),
+            ops: ops.load(Ordering::Relaxed),
+            p99,
+            rss_bytes: rss_bytes(),
+            open_fds: open_fds(),
+            server_clients: field(&clients, "connected_clients"),
+            server_memory: field(&memory, "used_memory"),
+        })
+    }
+
+    pub async fn run(
+        &self,
+        workload: &Workload,
+        duration: Duration,
+        rate: u64,
+        concurrency: usize,
+        sample_every: Duration,
+    ) -> Result<StabilityReport> {
+        println!("\n=== Soak Test ===\n");
+        println!(
+            "Workload '{}' ({}) at {} ops/s over {} workers for {:?}, sampling every {:?}\n",
+            workload.name,
+            workload.describe_mix(),
+            rate,
+            concurrency,
+            duration,
+            sample_every
+        );
+        println!(
+            "{:>8} {:>10} {:>10} {:>10} {:>6} {:>8} {:>12}",
+            "elapsed", "ops", "p99", "rss MiB", "fds", "clients", "server MiB"
+        );
+
+        let conn = self.client.get_async_connection().await?;
+        let sampler = Arc::new(workload.sampler());
+        let values = Arc::new("x".repeat(workload.value_size.max()));
+        let ops = Arc::new(AtomicU64::new(0));
+        let window = Arc::new(Mutex::new(latency_histogram()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let concurrency = concurrency.max(1);
+        let per_worker = Duration::from_secs_f64(concurrency as f64 / rate.max(1) as f64);
+
+        let workers: Vec<_> = (0..concurrency)
+            .map(|_| {
+                let (mut conn, sampler, values) = (conn.clone(), sampler.clone(), values.clone());
+                let (ops, window, stop) = (ops.clone(), window.clone(), stop.clone());
+                tokio::spawn(async move {
+                    let mut pacing = tokio::time::interval(per_worker);
+                    pacing.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                    while !stop.load(Ordering::Relaxed) {
+                        pacing.tick().await;
+                        let (op, key, size) = {
+                            let mut rng = rand::thread_rng();
+                            let op = sampler.op(&mut rng);
+                            let key = format!("{}{}:{}", KEY_PREFIX, op.key_family(), sampler.key(&mut rng));
+                            (op, key, sampler.value_size(&mut rng))
+                        };
+                        let sent = Instant::now();
+                        op.execute(&mut conn, &key, &values[..size]).await?;
+                        hdr::record(&mut window.lock().expect("latency window poisoned"), sent.elapsed());
+                        ops.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Ok::<_, DemoError>(())
+                })
+            })
+            .collect();
+
+        let mut monitor = self.client.get_async_connection().await?;
+        let started = Instant::now();
+        let mut report = StabilityReport::default();
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + sample_every, sample_every);
+        let mut interrupted = false;
+        while started.elapsed() < duration {
+            tokio::select! {
+                _ = ticks.tick() => {}
+                _ = tokio::signal::ctrl_c() => {
+                    interrupted = true;
+                }
+            }
+            let sample = Self::sample(&mut monitor, started, &ops, &window).await?;
+            println!(
+                "{:>7}s {:>10} {:>10?} {:>10} {:>6} {:>8} {:>12.1}",
+                sample.elapsed.as_secs(),
+                sample.ops,
+                sample.p99,
+                sample.rss_bytes.map(|b| format!("{:.1}", b as f64 / 1_048_576.0)).unwrap_or_else(|| "-".to_string()),
+                sample.open_fds.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string()),
+                sample.server_clients,
+                sample.server_memory as f64 / 1_048_576.0
+            );
+            if let Some(exporter) = &self.metrics {
+                let mut metrics = exporter.metric_set();
+                sample.record_metrics(&mut metrics);
+                exporter.publish(&metrics).await;
+            }
+            report.samples.push(sample);
+            if interrupted {
+                println!("Interrupted; reporting what was sampled so far");
+                break;
+            }
+        }
+
+        stop.store(true, Ordering::Relaxed);
+        for worker in workers {
+            worker.await.map_err(|e| DemoError::Demo(e.to_string()))??;
+        }
+        let mut scanner = self.client.scan_keys(&format!("{}*", KEY_PREFIX)).await?;
+        while let Some(keys) = scanner.next_batch().await? {
+            if !keys.is_empty() {
+                let _: () = monitor.del(keys).await?;
+            }
+        }
+
+        println!("\nStability report ({} samples):", report.samples.len());
+        let findings = report.findings();
+        if report.samples.len() < 4 {
+            println!("  Too few samples to judge drift; run longer or sample more often");
+        } else if findings.is_empty() {
+            println!("  ✅ No leaks or drift detected");
+        }
+        for finding in &findings {
+            println!("  ⚠ {}", finding);
+        }
+        println!("\n💡 Slow leaks hide in short runs: a connection per request that is never");
+        println!("   closed or a cache without eviction only shows up as a trend over hours.");
+        info!("Soak test completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(rss: &[u64], clients: &[u64]) -> StabilityReport {
+        StabilityReport {
+            samples: rss
+                .iter()
+                .zip(clients)
+                .map(|(rss, clients)| ResourceSample {
+                    rss_bytes: Some(*rss),
+                    open_fds: Some(20),
+                    server_clients: *clients,
+                    server_memory: 1000,
+                    p99: Duration::from_millis(1),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_drift() {
+        let drift = Drift::of(&[10.0, 12.0, 11.0, 30.0, 28.0, 50.0, 14.0, 16.0]).unwrap();
+        assert_eq!(drift, Drift { start: 11.0, end: 15.0 });
+        assert!((drift.growth_pct() - 36.36).abs() < 0.01);
+        assert!(Drift::of(&[1.0, 2.0, 3.0]).is_none());
+    }
+
+    #[test]
+    fn test_findings() {
+        let stable = samples(&[100, 104, 98, 101, 102, 99, 103, 100], &[3, 3, 4, 3, 3, 3, 4, 3]);
+        assert!(stable.findings().is_empty());
+
+        let leaking = samples(&[100, 110, 120, 130, 140, 150, 160, 170], &[3, 5, 7, 9, 11, 13, 15, 17]);
+        let findings = leaking.findings();
+        assert_eq!(findings.len(), 2, "{:?}", findings);
+        assert!(findings[0].starts_with("client RSS grew"));
+        assert!(findings[1].contains("connected_clients went from 4 to 16"));
+
+        assert!(StabilityReport::default().findings().is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Latency distribution of one benchmark run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// Nearest-rank percentile of already sorted samples.
+pub fn percentile(sorted: &[Duration], quantile: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (quantile * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl LatencySummary {
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let total: Duration = samples.iter().sum();
+        Self {
+            count: samples.len(),
+            mean: total / samples.len() as u32,
+            p50: percentile(&samples, 0.50),
+            p95: percentile(&samples, 0.95),
+            p99: percentile(&samples, 0.99),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// Standard deviation over mean; 0 for fewer than two values or a zero mean.
+pub fn coefficient_of_variation(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    if mean == 0.0 {
+        return 0.0;
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt() / mean
+}
+
+/// Index of the first of `window` consecutive values whose coefficient of
+/// variation is at most `max_cv`: where a run stops drifting (connection
+/// setup, allocator and server caches warming) and settles.
+pub fn steady_state_start(values: &[f64], window: usize, max_cv: f64) -> Option<usize> {
+    let window = window.max(2);
+    values.windows(window).position(|slice| coefficient_of_variation(slice) <= max_cv)
+}
+
+impl fmt::Display for LatencySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "n={} mean={:?} p50={:?} p95={:?} p99={:?} max={:?}",
+            self.count, self.mean, self.p50, self.p95, self.p99, self.max
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let samples: Vec<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let summary = LatencySummary::from_samples(samples);
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.p50, Duration::from_millis(50));
+        assert_eq!(summary.p95, Duration::from_millis(95));
+        assert_eq!(summary.p99, Duration::from_millis(99));
+        assert_eq!(summary.max, Duration::from_millis(100));
+        assert_eq!(summary.mean, Duration::from_micros(50_500));
+    }
+
+    #[test]
+    fn test_steady_state_detection() {
+        assert_eq!(coefficient_of_variation(&[2.0, 2.0, 2.0]), 0.0);
+        assert!((coefficient_of_variation(&[1.0, 3.0]) - 0.5).abs() < 1e-9);
+        assert_eq!(coefficient_of_variation(&[5.0]), 0.0);
+
+        let means = [9.0, 6.0, 3.1, 3.0, 2.9, 3.0, 3.05];
+        assert_eq!(steady_state_start(&means, 3, 0.05), Some(2));
+        assert_eq!(steady_state_start(&means, 3, 0.0), None);
+        assert_eq!(steady_state_start(&means[..2], 3, 0.05), None);
+    }
+
+    #[test]
+    fn test_empty_and_single_sample() {
+        assert_eq!(LatencySummary::from_samples(Vec::new()), LatencySummary::default());
+        let one = LatencySummary::from_samples(vec![Duration::from_millis(3)]);
+        assert_eq!(one.p50, Duration::from_millis(3));
+        assert_eq!(one.p99, Duration::from_millis(3));
+    }
+}
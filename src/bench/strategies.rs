@@ -0,0 +1,249 @@
+use super::hdr::{self, latency_histogram};
+use super::load::KEY_PREFIX;
+use super::stats::LatencySummary;
+use super::workload::{Workload, WorkloadSampler};
+use crate::{DemoError, RedisClient, Result};
+use hdrhistogram::Histogram;
+use redis::{AsyncCommands, ConnectionLike, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyRun {
+    pub name: &'static str,
+    pub elapsed: Duration,
+    pub latency: LatencySummary,
+}
+
+impl StrategyRun {
+    pub fn ops_per_sec(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        self.latency.count as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StrategyReport {
+    pub runs: Vec<StrategyRun>,
+}
+
+impl StrategyReport {
+    pub fn fastest(&self) -> Option<&StrategyRun> {
+        self.runs.iter().max_by(|a, b| a.ops_per_sec().total_cmp(&b.ops_per_sec()))
+    }
+
+    /// Throughput of each run relative to the fastest.
+    pub fn relative(&self) -> Vec<(&'static str, f64)> {
+        let best = self.fastest().map(StrategyRun::ops_per_sec).unwrap_or_default();
+        self.runs
+            .iter()
+            .map(|run| (run.name, if best > 0.0 { run.ops_per_sec() / best } else { 0.0 }))
+            .collect()
+    }
+}
+
+/// Draws one operation and sends it on a blocking connection.
+fn send_blocking(conn: &mut dyn ConnectionLike, sampler: &WorkloadSampler, values: &str) -> Result<()> {
+    let (op, key, size) = {
+        let mut rng = rand::thread_rng();
+        let op = sampler.op(&mut rng);
+        (op, format!("{}{}:{}", KEY_PREFIX, op.key_family(), sampler.key(&mut rng)), sampler.value_size(&mut rng))
+    };
+    let _: Value = op.command(&key, &values[..size]).query(conn)?;
+    Ok(())
+}
+
+/// Runs `threads` OS threads until `requests` are issued, each getting a
+/// connection from `connect` per request.
+fn run_threads<C, F>(
+    threads: usize,
+    requests: usize,
+    connect: F,
+    sampler: &WorkloadSampler,
+    values: &str,
+) -> Result<Histogram<u64>>
+where
+    F: Fn() -> Result<C> + Sync,
+    C: std::ops::DerefMut<Target = redis::Connection>,
+{
+    let issued = AtomicUsize::new(0);
+    let histograms: Vec<Result<Histogram<u64>>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut histogram = latency_histogram();
+                    while issued.fetch_add(1, Ordering::Relaxed) < requests {
+                        let sent = Instant::now();
+                        send_blocking(&mut *connect()?, sampler, values)?;
+                        hdr::record(&mut histogram, sent.elapsed());
+                    }
+                    Ok(histogram)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().expect("bench thread panicked")).collect()
+    });
+    let mut total = latency_histogram();
+    for histogram in histograms {
+        total.add(histogram?).expect("histograms share bounds");
+    }
+    Ok(total)
+}
+
+/// Sends the same workload through each way this crate can talk to Redis:
+/// one blocking connection shared by threads, a pool of blocking
+/// connections, and the multiplexed async `ConnectionManager`.
+pub struct StrategyBench {
+    client: RedisClient,
+}
+
+impl StrategyBench {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    async fn blocking<F>(
+        &self,
+        name: &'static str,
+        requests: usize,
+        threads: usize,
+        workload: &Workload,
+        run: F,
+    ) -> Result<StrategyRun>
+    where
+        F: FnOnce(usize, usize, &WorkloadSampler, &str) -> Result<Histogram<u64>> + Send + 'static,
+    {
+        let sampler = workload.sampler();
+        let values = "x".repeat(workload.value_size.max());
+        let started = Instant::now();
+        let histogram = tokio::task::spawn_blocking(move || run(threads, requests, &sampler, &values))
+            .await
+            .map_err(|e| DemoError::Demo(e.to_string()))??;
+        Ok(StrategyRun { name, elapsed: started.elapsed(), latency: LatencySummary::from_histogram(&histogram) })
+    }
+
+    async fn multiplexed(&self, requests: usize, tasks: usize, workload: &Workload) -> Result<StrategyRun> {
+        let conn = self.client.get_async_connection().await?;
+        let sampler = Arc::new(workload.sampler());
+        let values = Arc::new("x".repeat(workload.value_size.max()));
+        let issued = Arc::new(AtomicUsize::new(0));
+        let started = Instant::now();
+        let handles: Vec<_> = (0..tasks)
+            .map(|_| {
+                let (mut conn, sampler, values, issued) = (conn.clone(), sampler.clone(), values.clone(), issued.clone());
+                tokio::spawn(async move {
+                    let mut histogram = latency_histogram();
+                    while issued.fetch_add(1, Ordering::Relaxed) < requests {
+                        let (op, key, size) = {
+                            let mut rng = rand::thread_rng();
+                            let op = sampler.op(&mut rng);
+                            let key = format!("{}{}:{}", KEY_PREFIX, op.key_family(), sampler.key(&mut rng));
+                            (op, key, sampler.value_size(&mut rng))
+                        };
+                        let sent = Instant::now();
+                        op.execute(&mut conn, &key, &values[..size]).await?;
+                        hdr::record(&mut histogram, sent.elapsed());
+                    }
+                    Ok::<_, DemoError>(histogram)
+                })
+            })
+            .collect();
+        let mut total = latency_histogram();
+        for handle in handles {
+            total.add(handle.await.map_err(|e| DemoError::Demo(e.to_string()))??).expect("histograms share bounds");
+        }
+        Ok(StrategyRun {
+            name: "async multiplexed",
+            elapsed: started.elapsed(),
+            latency: LatencySummary::from_histogram(&total),
+        })
+    }
+
+    pub async fn run(
+        &self,
+        workload: &Workload,
+        requests: usize,
+        concurrency: usize,
+        with_pool: bool,
+    ) -> Result<StrategyReport> {
+        let concurrency = concurrency.max(1);
+        let mut report = StrategyReport::default();
+
+        println!("\n=== Connection Strategies ===\n");
+        println!(
+            "Workload '{}' ({}), {} requests from {} concurrent callers each way\n",
+            workload.name,
+            workload.describe_mix(),
+            requests,
+            concurrency
+        );
+
+        println!("1. One blocking connection shared by {} threads (behind a Mutex)", concurrency);
+        let shared = Arc::new(Mutex::new(self.client.get_sync_connection()?));
+        let run = self
+            .blocking("sync shared", requests, concurrency, workload, move |threads, requests, sampler, values| {
+                let connect = || shared.lock().map_err(|_| DemoError::Demo("Shared connection poisoned".to_string()));
+                run_threads(threads, requests, connect, sampler, values)
+            })
+            .await?;
+        println!("   {:>10.0} ops/s  {}", run.ops_per_sec(), run.latency);
+        report.runs.push(run);
+
+        if with_pool {
+            println!("\n2. An r2d2 pool of {} blocking connections, one thread each", concurrency);
+            let pool = self.client.sync_pool(concurrency as u32)?;
+            let run = self
+                .blocking("sync pool", requests, concurrency, workload, move |threads, requests, sampler, values| {
+                    run_threads(threads, requests, || Ok(pool.get()?), sampler, values)
+                })
+                .await?;
+            println!("   {:>10.0} ops/s  {}", run.ops_per_sec(), run.latency);
+            report.runs.push(run);
+        }
+
+        println!("\n{}. One async ConnectionManager cloned into {} tasks", if with_pool { 3 } else { 2 }, concurrency);
+        let run = self.multiplexed(requests, concurrency, workload).await?;
+        println!("   {:>10.0} ops/s  {}", run.ops_per_sec(), run.latency);
+        report.runs.push(run);
+
+        println!("\nRelative throughput:");
+        for (name, relative) in report.relative() {
+            println!("   {:<18} {:>5.0}% {}", name, relative * 100.0, "█".repeat((relative * 30.0).round() as usize));
+        }
+
+        let mut conn = self.client.get_async_connection().await?;
+        let mut scanner = self.client.scan_keys(&format!("{}*", KEY_PREFIX)).await?;
+        while let Some(keys) = scanner.next_batch().await? {
+            if !keys.is_empty() {
+                let _: () = conn.del(keys).await?;
+            }
+        }
+        println!("\n💡 A shared blocking connection serializes every caller behind one round");
+        println!("   trip. A pool buys parallelism with one socket per thread. The multiplexed");
+        println!("   async connection keeps many requests in flight on a single socket.");
+        info!("Connection strategy benchmark completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_throughput() {
+        let run = |name, millis| StrategyRun {
+            name,
+            elapsed: Duration::from_millis(millis),
+            latency: LatencySummary { count: 1000, ..Default::default() },
+        };
+        let report = StrategyReport { runs: vec![run("sync shared", 400), run("async multiplexed", 100)] };
+        assert_eq!(report.fastest().unwrap().name, "async multiplexed");
+        assert_eq!(report.relative(), vec![("sync shared", 0.25), ("async multiplexed", 1.0)]);
+        assert!(StrategyReport::default().relative().is_empty());
+    }
+}
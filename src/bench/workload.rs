@@ -0,0 +1,358 @@
+use super::distribution::{KeyDistribution, KeyPattern};
+use crate::utils::RedisConnection;
+use crate::{DemoError, Result};
+use rand::Rng;
+use redis::{Cmd, Value};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Profiles shipped with the crate, usable by name with `--workload`.
+pub const BUNDLED: [(&str, &str); 2] = [
+    ("cache-read-heavy", include_str!("profiles/cache-read-heavy.toml")),
+    ("session-store", include_str!("profiles/session-store.toml")),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Op {
+    Get,
+    Set,
+    Del,
+    Incr,
+    Lpush,
+    Hget,
+    Hset,
+    Zadd,
+    Zrange,
+}
+
+impl Op {
+    /// Ops on incompatible types get their own keys, so a mix of GET and
+    /// HSET never hits WRONGTYPE.
+    pub fn key_family(&self) -> &'static str {
+        match self {
+            Op::Get | Op::Set | Op::Del => "s",
+            Op::Incr => "c",
+            Op::Lpush => "l",
+            Op::Hget | Op::Hset => "h",
+            Op::Zadd | Op::Zrange => "z",
+        }
+    }
+
+    /// The command, so sync and async connections can send the same thing.
+    pub fn command(&self, key: &str, value: &str) -> Cmd {
+        match self {
+            Op::Get => Cmd::get(key),
+            Op::Set => Cmd::set(key, value),
+            Op::Del => Cmd::del(key),
+            Op::Incr => Cmd::incr(key, 1),
+            Op::Lpush => Cmd::lpush(key, value),
+            Op::Hget => Cmd::hget(key, "field"),
+            Op::Hset => Cmd::hset(key, "field", value),
+            // Members drawn from a small range so sorted sets stay bounded.
+            Op::Zadd => Cmd::zadd(key, rand::thread_rng().gen_range(0..1000u32), rand::random::<f64>()),
+            Op::Zrange => Cmd::zrange(key, 0, 9),
+        }
+    }
+
+    pub async fn execute(&self, conn: &mut RedisConnection, key: &str, value: &str) -> Result<()> {
+        let _: Value = self.command(key, value).query_async(conn).await?;
+        Ok(())
+    }
+}
+
+impl FromStr for Op {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "get" => Ok(Op::Get),
+            "set" => Ok(Op::Set),
+            "del" => Ok(Op::Del),
+            "incr" => Ok(Op::Incr),
+            "lpush" => Ok(Op::Lpush),
+            "hget" => Ok(Op::Hget),
+            "hset" => Ok(Op::Hset),
+            "zadd" => Ok(Op::Zadd),
+            "zrange" => Ok(Op::Zrange),
+            other => Err(DemoError::Configuration(format!(
+                "Unknown bench command: {} (use get, set, del, incr, lpush, hget, hset, zadd or zrange)",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ValueSize {
+    Fixed { size: usize },
+    Uniform { min: usize, max: usize },
+    /// `[[size, weight], ...]`, e.g. mostly small values with a few large ones.
+    Weighted { sizes: Vec<(usize, u32)> },
+}
+
+impl ValueSize {
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        match self {
+            ValueSize::Fixed { size } => *size,
+            ValueSize::Uniform { min, max } => rng.gen_range(*min..=*max),
+            ValueSize::Weighted { sizes } => {
+                let total: u32 = sizes.iter().map(|(_, weight)| weight).sum();
+                let mut pick = rng.gen_range(0..total.max(1));
+                for (size, weight) in sizes {
+                    if pick < *weight {
+                        return *size;
+                    }
+                    pick -= weight;
+                }
+                sizes.last().map(|(size, _)| *size).unwrap_or_default()
+            }
+        }
+    }
+
+    pub fn max(&self) -> usize {
+        match self {
+            ValueSize::Fixed { size } => *size,
+            ValueSize::Uniform { max, .. } => *max,
+            ValueSize::Weighted { sizes } => sizes.iter().map(|(size, _)| *size).max().unwrap_or_default(),
+        }
+    }
+}
+
+/// `256` for a fixed size, `64-1024` for sizes drawn uniformly between.
+impl FromStr for ValueSize {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = |text: &str| {
+            text.trim().parse::<usize>().map_err(|_| DemoError::Configuration(format!("Invalid value size: {} (use N or MIN-MAX)", s)))
+        };
+        match s.split_once('-') {
+            None => Ok(ValueSize::Fixed { size: bytes(s)? }),
+            Some((min, max)) => {
+                let (min, max) = (bytes(min)?, bytes(max)?);
+                if min > max {
+                    return Err(DemoError::Configuration(format!("Invalid value size: {} (MIN above MAX)", s)));
+                }
+                Ok(ValueSize::Uniform { min, max })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct KeySpace {
+    pub count: usize,
+    #[serde(flatten)]
+    pub distribution: KeyPattern,
+}
+
+/// A traffic model loaded from TOML:
+///
+/// ```toml
+/// name = "cache-read-heavy"
+/// [mix]            # percentages, summing to 100
+/// get = 90
+/// set = 10
+/// [keys]
+/// count = 100000
+/// distribution = "zipfian"   # or "uniform", or "hotset" with hot_keys/hot_fraction
+/// skew = 0.99
+/// [value_size]
+/// kind = "uniform"
+/// min = 64
+/// max = 1024
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Workload {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub mix: BTreeMap<Op, u32>,
+    pub keys: KeySpace,
+    pub value_size: ValueSize,
+}
+
+impl Workload {
+    /// The load bench's built-in mix: half GET, half SET, uniform keys.
+    pub fn get_set(keyspace: usize, value_size: usize) -> Self {
+        Self {
+            name: "get-set".to_string(),
+            description: None,
+            mix: [(Op::Get, 50), (Op::Set, 50)].into_iter().collect(),
+            keys: KeySpace { count: keyspace, distribution: KeyPattern::Uniform },
+            value_size: ValueSize::Fixed { size: value_size },
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let workload: Self =
+            toml::from_str(text).map_err(|e| DemoError::Configuration(format!("Invalid workload profile: {}", e)))?;
+        workload.validate()?;
+        Ok(workload)
+    }
+
+    /// A bundled profile by name, otherwise a TOML file at that path.
+    pub fn load(name_or_path: &str) -> Result<Self> {
+        if let Some((_, text)) = BUNDLED.iter().find(|(name, _)| *name == name_or_path) {
+            return Self::from_toml(text);
+        }
+        let path = Path::new(name_or_path);
+        if !path.exists() {
+            let names: Vec<_> = BUNDLED.iter().map(|(name, _)| *name).collect();
+            return Err(DemoError::Configuration(format!(
+                "No workload file '{}' (bundled profiles: {})",
+                name_or_path,
+                names.join(", ")
+            )));
+        }
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let total: u32 = self.mix.values().sum();
+        if total != 100 {
+            return Err(DemoError::Configuration(format!("Workload mix must add up to 100%, got {}%", total)));
+        }
+        if self.keys.count == 0 {
+            return Err(DemoError::Configuration("Workload keys.count must be at least 1".to_string()));
+        }
+        self.keys.distribution.validate()?;
+        match &self.value_size {
+            ValueSize::Uniform { min, max } if min > max => {
+                Err(DemoError::Configuration(format!("value_size min {} is above max {}", min, max)))
+            }
+            ValueSize::Weighted { sizes } if sizes.iter().all(|(_, weight)| *weight == 0) => {
+                Err(DemoError::Configuration("value_size needs at least one weighted size".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// `"get 90%, set 10%"`.
+    pub fn describe_mix(&self) -> String {
+        self.mix
+            .iter()
+            .filter(|(_, percent)| **percent > 0)
+            .map(|(op, percent)| format!("{:?} {}%", op, percent).to_lowercase())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn sampler(&self) -> WorkloadSampler {
+        let mut cumulative = 0;
+        let ops = self
+            .mix
+            .iter()
+            .filter(|(_, percent)| **percent > 0)
+            .map(|(op, percent)| {
+                cumulative += percent;
+                (cumulative, *op)
+            })
+            .collect();
+        WorkloadSampler {
+            ops,
+            keys: self.keys.distribution.build(self.keys.count),
+            value_size: self.value_size.clone(),
+        }
+    }
+}
+
+/// Draws the next operation, key index and value size for a workload.
+pub struct WorkloadSampler {
+    ops: Vec<(u32, Op)>,
+    keys: Box<dyn KeyDistribution>,
+    value_size: ValueSize,
+}
+
+impl WorkloadSampler {
+    pub fn op<R: Rng>(&self, rng: &mut R) -> Op {
+        let pick = rng.gen_range(0..100);
+        self.ops.iter().find(|(upto, _)| pick < *upto).map(|(_, op)| *op).unwrap_or(self.ops[self.ops.len() - 1].1)
+    }
+
+    pub fn key<R: Rng>(&self, rng: &mut R) -> usize {
+        self.keys.sample(rng)
+    }
+
+    pub fn value_size<R: Rng>(&self, rng: &mut R) -> usize {
+        self.value_size.sample(rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_op_commands() {
+        let args = |cmd: Cmd| -> Vec<String> {
+            cmd.args_iter()
+                .map(|arg| match arg {
+                    redis::Arg::Simple(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+                    redis::Arg::Cursor => "<cursor>".to_string(),
+                })
+                .collect()
+        };
+        assert_eq!(args(Op::Hset.command("k", "v")), ["HSET", "k", "field", "v"]);
+        assert_eq!(args(Op::Zrange.command("k", "v")), ["ZRANGE", "k", "0", "9"]);
+        assert_eq!(args(Op::Zadd.command("k", "v"))[..2], ["ZADD", "k"]);
+        assert_eq!(args(Op::Lpush.command("k", "v")), ["LPUSH", "k", "v"]);
+        assert_eq!(" LPUSH".parse::<Op>().unwrap(), Op::Lpush);
+        assert!("flushall".parse::<Op>().is_err());
+    }
+
+    #[test]
+    fn test_bundled_profiles_parse() {
+        for (name, _) in BUNDLED {
+            let workload = Workload::load(name).unwrap();
+            assert_eq!(workload.name, name);
+        }
+        assert!(Workload::load("no-such-profile").is_err());
+    }
+
+    #[test]
+    fn test_profile_validation() {
+        let profile = |mix: &str, value_size: &str| {
+            Workload::from_toml(&format!(
+                "name = \"t\"\n[mix]\n{}\n[keys]\ncount = 10\ndistribution = \"hotset\"\nhot_keys = 2\n[value_size]\n{}",
+                mix, value_size
+            ))
+        };
+        let workload = profile("get = 70\nhset = 30", "kind = \"fixed\"\nsize = 8").unwrap();
+        assert_eq!(workload.describe_mix(), "get 70%, hset 30%");
+        assert_eq!(workload.keys.distribution, KeyPattern::HotSet { hot_keys: 2, hot_fraction: 0.9 });
+
+        assert!(profile("get = 70", "kind = \"fixed\"\nsize = 8").is_err());
+        assert!(profile("get = 100", "kind = \"uniform\"\nmin = 9\nmax = 1").is_err());
+        assert!(profile("flushall = 100", "kind = \"fixed\"\nsize = 8").is_err());
+    }
+
+    #[test]
+    fn test_sampler_follows_the_mix() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let workload = Workload {
+            mix: [(Op::Get, 90), (Op::Set, 10)].into_iter().collect(),
+            value_size: ValueSize::Weighted { sizes: vec![(10, 3), (1000, 1)] },
+            ..Workload::get_set(100, 0)
+        };
+        let sampler = workload.sampler();
+        let gets = (0..10_000).filter(|_| sampler.op(&mut rng) == Op::Get).count();
+        assert!((8_800..9_200).contains(&gets), "{}", gets);
+        let large = (0..10_000).filter(|_| sampler.value_size(&mut rng) == 1000).count();
+        assert!((2_300..2_700).contains(&large), "{}", large);
+    }
+
+    #[test]
+    fn test_value_size_from_str() {
+        assert_eq!("256".parse::<ValueSize>().unwrap(), ValueSize::Fixed { size: 256 });
+        assert_eq!("64-1024".parse::<ValueSize>().unwrap(), ValueSize::Uniform { min: 64, max: 1024 });
+        assert!("1024-64".parse::<ValueSize>().is_err());
+        assert!("big".parse::<ValueSize>().is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use super::layer::{BackingStore, Cache, CacheStrategy};
+use crate::integrations::MetricsExporter;
+use crate::utils::Sections;
+use crate::{RedisClient, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Product {
+    pub id: String,
+    pub name: String,
+    pub price_cents: u64,
+}
+
+/// An in-memory "database" that takes `delay` to answer each read.
+pub struct SlowStore {
+    products: Mutex<HashMap<String, Product>>,
+    delay: Duration,
+    loads: AtomicU64,
+}
+
+impl SlowStore {
+    pub fn new(delay: Duration) -> Self {
+        let products = [("p1", "Keyboard", 4_999), ("p2", "Mouse", 1_999), ("p3", "Monitor", 18_999)]
+            .into_iter()
+            .map(|(id, name, price_cents)| (id.to_string(), Product { id: id.to_string(), name: name.to_string(), price_cents }))
+            .collect();
+        Self { products: Mutex::new(products), delay, loads: AtomicU64::new(0) }
+    }
+
+    /// Reads served so far.
+    pub fn loads(&self) -> u64 {
+        self.loads.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl BackingStore<Product> for SlowStore {
+    async fn load(&self, id: &str) -> Result<Option<Product>> {
+        self.loads.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        Ok(self.products.lock().expect("store poisoned").get(id).cloned())
+    }
+
+    async fn save(&self, id: &str, value: &Product) -> Result<()> {
+        self.products.lock().expect("store poisoned").insert(id.to_string(), value.clone());
+        Ok(())
+    }
+}
+
+pub struct CacheDemo {
+    client: RedisClient,
+    metrics: Option<MetricsExporter>,
+}
+
+impl CacheDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client, metrics: None }
+    }
+
+    /// Publish the cache's hit and miss counts after each step.
+    pub fn with_metrics(mut self, metrics: MetricsExporter) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    async fn publish(&self, cache: &Cache<Product>) {
+        if let Some(exporter) = &self.metrics {
+            let mut metrics = exporter.metric_set();
+            cache.record_metrics(&mut metrics);
+            exporter.publish(&metrics).await;
+        }
+    }
+
+    /// Hits and misses in front of a store that takes `delay` per read:
+    /// cold and warm reads, `readers` concurrent misses on one key with
+    /// and without stampede protection, a write under each strategy, and
+    /// an entry expiring after `ttl`.
+    pub async fn run(&self, ttl: Duration, delay: Duration, readers: usize) -> Result<()> {
+        println!("\n=== Caching Layer ===\n");
+        let store = Arc::new(SlowStore::new(delay));
+        let cache: Cache<Product> = Cache::new(&self.client, "demo-products", ttl).await?;
+        for id in ["p1", "p2", "p3"] {
+            cache.invalidate(id).await?;
+        }
+
+        let mut sections = Sections::new();
+        sections.next(&format!("Cold, then warm reads ({:?} per store read):", delay));
+        for pass in ["cold", "warm"] {
+            for id in ["p1", "p2", "p3"] {
+                let started = Instant::now();
+                let hits = cache.stats().hits;
+                let product = cache.get_or_load(id, store.as_ref()).await?.expect("seeded product");
+                let outcome = if cache.stats().hits > hits { "✅ hit " } else { "🐢 miss" };
+                println!("   {} {} {:<8} {:>8.1?}  ({})", pass, outcome, product.name, started.elapsed(), id);
+            }
+        }
+        self.publish(&cache).await;
+
+        sections.next(&format!("{} concurrent readers miss on the same key:", readers));
+        for (label, lock) in [("without a lock", None), ("with a per-key lock", Some(delay * 4 + Duration::from_secs(1)))] {
+            cache.invalidate("p1").await?;
+            let cache = cache.clone().with_stampede_lock(lock);
+            let before = store.loads();
+            let started = Instant::now();
+            let tasks: Vec<_> = (0..readers)
+                .map(|_| {
+                    let (cache, store) = (cache.clone(), store.clone());
+                    tokio::spawn(async move { cache.get_or_load("p1", store.as_ref()).await })
+                })
+                .collect();
+            for task in tasks {
+                task.await.expect("reader panicked")?;
+            }
+            println!("   {:<20} {:>3} store reads in {:.1?}", label, store.loads() - before, started.elapsed());
+        }
+        self.publish(&cache).await;
+
+        sections.next("Updating a price:");
+        for strategy in [CacheStrategy::CacheAside, CacheStrategy::WriteThrough] {
+            let cache = cache.clone().with_strategy(strategy);
+            let mut product = cache.get_or_load("p2", store.as_ref()).await?.expect("seeded product");
+            product.price_cents += 100;
+            cache.put("p2", &product, store.as_ref()).await?;
+            match cache.get("p2").await? {
+                Some(cached) => println!("   {:<14} cache holds the new price ({}¢): next read is a hit", strategy.as_str(), cached.price_cents),
+                None => println!("   {:<14} cached copy dropped: next read reloads from the store", strategy.as_str()),
+            }
+        }
+        self.publish(&cache).await;
+
+        sections.next(&format!("Waiting out the {:?} TTL:", ttl));
+        cache.get_or_load("p3", store.as_ref()).await?;
+        tokio::time::sleep(ttl + Duration::from_millis(50)).await;
+        match cache.get("p3").await? {
+            Some(_) => println!("   p3 is still cached"),
+            None => println!("   ⌛ p3 expired; the next read goes to the store"),
+        }
+        self.publish(&cache).await;
+
+        let stats = cache.stats();
+        println!("\n📊 {} hits, {} misses, {} store reads, {} misses served by another reader's load", stats.hits, stats.misses, stats.loads, stats.coalesced);
+        for id in ["p1", "p2", "p3"] {
+            cache.invalidate(id).await?;
+        }
+        println!("\n💡 Cache-aside keeps the cache to what's read; write-through pays for the");
+        println!("   write up front so readers never see a miss after an update. The per-key");
+        println!("   lock turns a stampede of misses into one store read.");
+        info!("Cache demo completed");
+        Ok(())
+    }
+}
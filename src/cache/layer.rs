@@ -0,0 +1,320 @@
+use crate::integrations::metric_registry::{CACHE_HITS, CACHE_HIT_RATIO, CACHE_MISSES, CACHE_STORE_LOADS};
+use crate::integrations::MetricSet;
+use crate::utils::lock::DistributedLock;
+use crate::utils::RedisConnection;
+use crate::{DemoError, RedisClient, Result};
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How long a reader that lost the race for a key's lock waits for the
+/// winner to fill it before loading the value itself.
+const FILL_WAIT: Duration = Duration::from_secs(2);
+const FILL_POLL: Duration = Duration::from_millis(10);
+
+/// What [`Cache::put`] does with the cached copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStrategy {
+    /// Write the store and drop the cached copy; the next read reloads it.
+    CacheAside,
+    /// Write the store and then the cache, so the next read is a hit.
+    WriteThrough,
+}
+
+impl CacheStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheStrategy::CacheAside => "cache-aside",
+            CacheStrategy::WriteThrough => "write-through",
+        }
+    }
+}
+
+impl FromStr for CacheStrategy {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.replace('_', "-").as_str() {
+            "cache-aside" | "aside" => Ok(CacheStrategy::CacheAside),
+            "write-through" | "through" => Ok(CacheStrategy::WriteThrough),
+            other => Err(DemoError::Configuration(format!("Unknown cache strategy: {} (use cache-aside or write-through)", other))),
+        }
+    }
+}
+
+/// The system of record a [`Cache`] sits in front of.
+#[async_trait]
+pub trait BackingStore<T>: Send + Sync {
+    async fn load(&self, id: &str) -> Result<Option<T>>;
+
+    async fn save(&self, id: &str, value: &T) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Reads that went to the backing store.
+    pub loads: u64,
+    /// Misses that waited for another reader's load instead of loading.
+    pub coalesced: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    loads: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+/// JSON copies of `T` under `cache:<name>:<id>`, expiring after `ttl`.
+/// Cloning shares the connection and the statistics.
+///
+/// With stampede protection on, a miss takes a short [`DistributedLock`]
+/// on the key: one reader loads from the store while the others wait for
+/// the cached copy, so a popular key expiring costs one load, not one per
+/// reader. Values the store doesn't have are not cached.
+pub struct Cache<T> {
+    conn: RedisConnection,
+    name: String,
+    ttl: Duration,
+    strategy: CacheStrategy,
+    stampede_lock: Option<Duration>,
+    counters: Arc<Counters>,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Cache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            name: self.name.clone(),
+            ttl: self.ttl,
+            strategy: self.strategy,
+            stampede_lock: self.stampede_lock,
+            counters: self.counters.clone(),
+            _value: PhantomData,
+        }
+    }
+}
+
+pub fn cache_key(name: &str, id: &str) -> String {
+    format!("cache:{}:{}", name, id)
+}
+
+impl<T: Serialize + DeserializeOwned + Send + Sync> Cache<T> {
+    /// Cache-aside, with stampede protection holding the lock for up to 5s.
+    pub async fn new(client: &RedisClient, name: &str, ttl: Duration) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            name: name.to_string(),
+            ttl: ttl.max(Duration::from_millis(1)),
+            strategy: CacheStrategy::CacheAside,
+            stampede_lock: Some(Duration::from_secs(5)),
+            counters: Arc::new(Counters::default()),
+            _value: PhantomData,
+        })
+    }
+
+    pub fn with_strategy(mut self, strategy: CacheStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// The lock's TTL, which should cover a load; `None` lets every miss
+    /// go to the store.
+    pub fn with_stampede_lock(mut self, ttl: Option<Duration>) -> Self {
+        self.stampede_lock = ttl;
+        self
+    }
+
+    pub fn strategy(&self) -> CacheStrategy {
+        self.strategy
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            loads: self.counters.loads.load(Ordering::Relaxed),
+            coalesced: self.counters.coalesced.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Hit and miss counts so far, labelled with the cache's name.
+    pub fn record_metrics(&self, metrics: &mut MetricSet) {
+        let stats = self.stats();
+        let labels = [("cache", self.name.as_str())];
+        let reads = stats.hits + stats.misses;
+        metrics
+            .gauge(&CACHE_HITS, &labels, stats.hits as f64)
+            .gauge(&CACHE_MISSES, &labels, stats.misses as f64)
+            .gauge(&CACHE_STORE_LOADS, &labels, stats.loads as f64)
+            .gauge(&CACHE_HIT_RATIO, &labels, if reads == 0 { 0.0 } else { stats.hits as f64 / reads as f64 });
+    }
+
+    /// The cached copy only.
+    pub async fn get(&self, id: &str) -> Result<Option<T>> {
+        let cached: Option<String> = self.conn.clone().get(cache_key(&self.name, id)).await?;
+        Ok(match cached {
+            Some(json) => Some(serde_json::from_str(&json)?),
+            None => None,
+        })
+    }
+
+    /// The cached copy, or on a miss the store's, which is then cached.
+    pub async fn get_or_load<S: BackingStore<T> + ?Sized>(&self, id: &str, store: &S) -> Result<Option<T>> {
+        if let Some(value) = self.get(id).await? {
+            self.counters.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Some(value));
+        }
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+        let Some(lock_ttl) = self.stampede_lock else {
+            return self.load(id, store).await;
+        };
+
+        let lock = DistributedLock::with_connection(self.conn.clone(), &cache_key(&self.name, id), lock_ttl);
+        if let Some(guard) = lock.try_acquire().await? {
+            // Someone may have filled it between our miss and the lock.
+            let value = match self.get(id).await? {
+                Some(value) => Some(value),
+                None => self.load(id, store).await?,
+            };
+            guard.release().await?;
+            return Ok(value);
+        }
+
+        let started = Instant::now();
+        while started.elapsed() < FILL_WAIT {
+            tokio::time::sleep(FILL_POLL).await;
+            if let Some(value) = self.get(id).await? {
+                self.counters.coalesced.fetch_add(1, Ordering::Relaxed);
+                return Ok(Some(value));
+            }
+        }
+        debug!("Gave up waiting for {} to be filled", cache_key(&self.name, id));
+        self.load(id, store).await
+    }
+
+    /// Writes `value` to the store, then updates or drops the cached copy
+    /// according to the strategy. The store goes first: if it fails, the
+    /// cache never holds a value the store doesn't.
+    pub async fn put<S: BackingStore<T> + ?Sized>(&self, id: &str, value: &T, store: &S) -> Result<()> {
+        store.save(id, value).await?;
+        match self.strategy {
+            CacheStrategy::CacheAside => self.invalidate(id).await,
+            CacheStrategy::WriteThrough => self.set(id, value).await,
+        }
+    }
+
+    pub async fn invalidate(&self, id: &str) -> Result<()> {
+        let _: () = self.conn.clone().del(cache_key(&self.name, id)).await?;
+        Ok(())
+    }
+
+    async fn set(&self, id: &str, value: &T) -> Result<()> {
+        let json = serde_json::to_string(value)?;
+        let _: () = self.conn.clone().pset_ex(cache_key(&self.name, id), json, self.ttl.as_millis() as u64).await?;
+        Ok(())
+    }
+
+    async fn load<S: BackingStore<T> + ?Sized>(&self, id: &str, store: &S) -> Result<Option<T>> {
+        self.counters.loads.fetch_add(1, Ordering::Relaxed);
+        let value = store.load(id).await?;
+        if let Some(value) = &value {
+            self.set(id, value).await?;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct SlowStore {
+        values: Mutex<HashMap<String, u64>>,
+        loads: AtomicU64,
+    }
+
+    #[async_trait]
+    impl BackingStore<u64> for SlowStore {
+        async fn load(&self, id: &str) -> Result<Option<u64>> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(self.values.lock().unwrap().get(id).copied())
+        }
+
+        async fn save(&self, id: &str, value: &u64) -> Result<()> {
+            self.values.lock().unwrap().insert(id.to_string(), *value);
+            Ok(())
+        }
+    }
+
+    async fn cache(name: &str) -> Cache<u64> {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let cache = Cache::new(&client, name, Duration::from_secs(60)).await.unwrap();
+        cache.invalidate("a").await.unwrap();
+        cache
+    }
+
+    #[test]
+    fn test_strategy_names() {
+        assert_eq!("write-through".parse::<CacheStrategy>().unwrap(), CacheStrategy::WriteThrough);
+        assert_eq!("cache_aside".parse::<CacheStrategy>().unwrap(), CacheStrategy::CacheAside);
+        assert!("write-back".parse::<CacheStrategy>().is_err());
+        assert_eq!(cache_key("users", "42"), "cache:users:42");
+    }
+
+    #[tokio::test]
+    async fn test_strategies_on_write() {
+        let store = SlowStore::default();
+        let aside = cache("test-aside").await;
+        aside.put("a", &1, &store).await.unwrap();
+        assert_eq!(aside.get("a").await.unwrap(), None);
+        assert_eq!(aside.get_or_load("a", &store).await.unwrap(), Some(1));
+        assert_eq!(aside.get_or_load("a", &store).await.unwrap(), Some(1));
+        assert_eq!(aside.stats(), CacheStats { hits: 1, misses: 1, loads: 1, coalesced: 0 });
+
+        let through = cache("test-through").await.with_strategy(CacheStrategy::WriteThrough);
+        through.put("a", &2, &store).await.unwrap();
+        assert_eq!(through.get("a").await.unwrap(), Some(2));
+        aside.invalidate("a").await.unwrap();
+        through.invalidate("a").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses_load_once() {
+        let store = Arc::new(SlowStore::default());
+        store.save("a", &7).await.unwrap();
+        let cache = cache("test-stampede").await;
+
+        let readers: Vec<_> = (0..20)
+            .map(|_| {
+                let (cache, store) = (cache.clone(), store.clone());
+                tokio::spawn(async move { cache.get_or_load("a", store.as_ref()).await.unwrap() })
+            })
+            .collect();
+        for reader in readers {
+            assert_eq!(reader.await.unwrap(), Some(7));
+        }
+        assert_eq!(store.loads.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.stats().coalesced + cache.stats().hits, 19);
+        cache.invalidate("a").await.unwrap();
+    }
+}
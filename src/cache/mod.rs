@@ -0,0 +1,5 @@
+pub mod demo;
+pub mod layer;
+
+pub use demo::CacheDemo;
+pub use layer::{BackingStore, Cache, CacheStats, CacheStrategy};
@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use crate::inspect::edit::KeyEdit;
+use crate::utils::{RedisUrl, TlsOptions};
+use chrono::NaiveDate;
+use clap::{ArgGroup, Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "redis-demo")]
@@ -7,26 +11,676 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
     
-    #[arg(short, long, default_value = "redis://localhost:6379")]
-    pub redis_url: String,
+    #[arg(
+        short,
+        long,
+        default_value = "redis://localhost:6379",
+        value_parser = parse_redis_url,
+        help = "Server to use; repeat it to run ping, advise or info against each one and compare"
+    )]
+    pub redis_url: Vec<String>,
     
     #[arg(short, long)]
     pub verbose: bool,
+    
+    #[arg(long, global = true, help = "Count the keys this run touches (see `stats client-keys`)")]
+    pub key_stats: bool,
+    
+    #[arg(
+        long,
+        global = true,
+        default_value = "demo:",
+        value_parser = parse_key_prefix,
+        help = "Prefix for every key the demos write, so they never touch other data"
+    )]
+    pub key_prefix: String,
+    
+    #[arg(short = 'y', long, global = true, help = "Don't ask before destructive operations")]
+    pub yes: bool,
+    
+    #[arg(long = "allow-db0", global = true, help = "Allow destructive operations on database 0")]
+    pub allow_db0: bool,
+    
+    #[arg(long, global = true, help = "Warn with a timing breakdown when a demo step takes longer than this")]
+    pub budget_ms: Option<u64>,
+    
+    #[arg(long, global = true, value_name = "PATTERN", help = "Record writes to keys matching this glob for `key history`; repeatable")]
+    pub history: Vec<String>,
+    
+    #[arg(long, global = true, help = "Run against an embedded in-process mini Redis (needs --features offline)")]
+    pub offline: bool,
+    
+    #[arg(long, global = true, value_name = "CASSETTE", conflicts_with = "replay", help = "Save every command and reply to a cassette file")]
+    pub record: Option<String>,
+    
+    #[arg(long, global = true, value_name = "CASSETTE", help = "Answer commands from a recorded cassette instead of a server")]
+    pub replay: Option<String>,
+    
+    #[arg(long, global = true, value_name = "PEM", help = "CA certificate to verify rediss:// servers with, instead of the system roots")]
+    pub tls_ca_cert: Option<String>,
+    
+    #[arg(long, global = true, value_name = "PEM", requires = "tls_key", help = "Client certificate for servers that check clients (tls-auth-clients)")]
+    pub tls_cert: Option<String>,
+    
+    #[arg(long, global = true, value_name = "PEM", requires = "tls_cert", help = "Private key of --tls-cert")]
+    pub tls_key: Option<String>,
+    
+    #[arg(long, global = true, help = "Accept any server certificate, like a rediss://...#insecure URL")]
+    pub tls_insecure: bool,
+    
+    #[arg(long, global = true, value_name = "URL", help = "Push bench and replication lag samples to this Prometheus pushgateway")]
+    pub push_gateway: Option<String>,
+    
+    #[arg(long, global = true, value_name = "ADDR", help = "Serve bench and replication lag samples on http://ADDR/metrics while running")]
+    pub metrics_listen: Option<std::net::SocketAddr>,
+    
+    #[arg(long, global = true, value_name = "TOML", help = "Metrics settings: push_gateway, listen, job, instance and [labels]")]
+    pub metrics_config: Option<String>,
+    
+    #[arg(long, global = true, default_value = "text", help = "Log events as text on stdout or as JSON (with demo and step spans) on stderr")]
+    pub log_format: String,
+    
+    #[arg(long, global = true, value_name = "URL", help = "Export demo and step spans to this OTLP/HTTP collector (needs --features otel)")]
+    pub otlp_endpoint: Option<String>,
+}
+
+impl Cli {
+    /// The `--tls-*` flags, for every client built from a `--redis-url`.
+    pub fn tls(&self) -> TlsOptions {
+        TlsOptions {
+            ca_cert: self.tls_ca_cert.as_ref().map(PathBuf::from),
+            client_cert: self.tls_cert.as_ref().zip(self.tls_key.as_ref()).map(|(cert, key)| (cert.into(), key.into())),
+            insecure: self.tls_insecure,
+        }
+    }
+}
+
+/// Rejects an empty `--key-prefix`: demos must never write bare keys.
+pub fn parse_key_prefix(value: &str) -> Result<String, String> {
+    if value.is_empty() {
+        return Err("the key prefix cannot be empty".to_string());
+    }
+    Ok(value.to_string())
+}
+
+/// Checks the URL up front and normalizes it, so a typo fails with a
+/// pointed message instead of a connection error.
+pub fn parse_redis_url(value: &str) -> Result<String, String> {
+    value.parse::<RedisUrl>().map(|url| url.to_string()).map_err(|e| e.to_string())
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
+    #[command(about = "Check the server's CONFIG against best practices for these demos")]
+    Advise,
+    
+    #[command(about = "Operational switches")]
+    Admin {
+        #[command(subcommand)]
+        command: AdminCommands,
+    },
+    
+    #[command(about = "Analytics demonstrations")]
+    Analytics {
+        #[command(subcommand)]
+        command: AnalyticsCommands,
+    },
+    
+    #[command(about = "Per-entity change history")]
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+    
+    #[command(about = "Benchmarks comparing access patterns")]
+    Bench {
+        #[command(subcommand)]
+        command: BenchCommands,
+    },
+    
     #[command(about = "Basic Redis operations demonstrations")]
     Basic {
         #[command(subcommand)]
         operation: BasicOperations,
     },
     
+    #[command(about = "Data integrity checks")]
+    Check {
+        #[command(subcommand)]
+        command: CheckCommands,
+    },
+    
+    #[command(about = "Redis design pattern demonstrations", visible_alias = "patterns")]
+    Pattern {
+        #[command(subcommand)]
+        pattern: PatternCommands,
+    },
+    
     #[command(about = "Test Redis connection")]
     Ping,
     
+    #[command(about = "Open a connection per caller to show maxclients errors and server load, then serve the same callers from a pool")]
+    ConnectionStorm {
+        #[arg(long, default_value_t = 300, help = "Callers talking to Redis at once")]
+        callers: usize,
+        
+        #[arg(long, default_value_t = 10)]
+        pool_size: u32,
+        
+        #[arg(long, help = "Lower the server's maxclients to this for the run (restored afterwards)")]
+        maxclients: Option<u64>,
+    },
+    
+    #[command(about = "TCP proxy adding latency, jitter and dropped connections; point --redis-url at it")]
+    Proxy {
+        #[arg(long, default_value = "7000", help = "Port (on loopback) or host:port to listen on")]
+        listen: String,
+        
+        #[arg(long, default_value = "localhost:6379", help = "Redis server as host:port")]
+        target: String,
+        
+        #[arg(long, default_value = "0ms", value_parser = parse_duration, help = "Delay added to every request")]
+        latency: std::time::Duration,
+        
+        #[arg(long, default_value = "0ms", value_parser = parse_duration, help = "Vary each delay by up to this much either way")]
+        jitter: std::time::Duration,
+        
+        #[arg(long, default_value = "0%", value_parser = parse_percent, help = "Share of requests lost, cutting their connection")]
+        drop: f64,
+    },
+    
+    #[command(about = "Publish and subscribe; run subscribe and publish in two terminals to talk")]
+    Pubsub {
+        #[command(subcommand)]
+        command: PubSubCommands,
+    },
+    
     #[command(about = "Demonstrate common Rust errors and their fixes")]
     RustErrors,
+    
+    #[command(about = "Lua scripting: EVAL, EVALSHA, script caching, compare-and-set and a rate-limit counter")]
+    Scripting,
+    
+    #[command(about = "Write keys one round trip at a time, then in one pipeline, and compare latency and throughput")]
+    Pipeline {
+        #[arg(long, default_value_t = 1000, value_parser = parse_count)]
+        count: usize,
+    },
+    
+    #[command(about = "Daily active users with SETBIT, BITCOUNT, BITOP AND/OR retention and BITPOS")]
+    Bitmaps {
+        #[arg(long, default_value = "10_000", value_parser = parse_count)]
+        users: usize,
+        
+        #[arg(long, default_value_t = 7)]
+        days: usize,
+    },
+    
+    #[command(about = "Compare SET, HyperLogLog and Bloom filter cardinality counting")]
+    CompareCardinality {
+        #[arg(short, long, default_value = "100_000", value_parser = parse_count)]
+        n: usize,
+        
+        #[arg(long, default_value = "0.01", value_parser = parse_probability)]
+        false_positive_rate: f64,
+    },
+    
+    #[command(about = "Live-reloading application configuration stored in a hash")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    
+    #[command(about = "A/B test assignment, exposure tracking and results")]
+    Experiments {
+        #[command(subcommand)]
+        command: ExperimentCommands,
+    },
+    
+    #[command(about = "Export keys to files")]
+    Export {
+        #[command(subcommand)]
+        command: ExportCommands,
+    },
+    
+    #[command(about = "Load keys from an export, or from redis-cli --csv / --json output")]
+    Import {
+        file: String,
+        
+        #[arg(long, default_value = "auto", help = "auto, ndjson (this tool's export), csv or cli-json")]
+        format: String,
+        
+        #[arg(long, default_value_t = 500, help = "Keys per MULTI/EXEC batch")]
+        batch_size: usize,
+    },
+    
+    #[command(about = "Promote the replica of --redis-url and measure write unavailability")]
+    Failover {
+        #[arg(long, default_value = "redis://localhost:6380")]
+        replica_url: String,
+        
+        #[arg(long, default_value = "graceful", help = "graceful (CLIENT PAUSE, wait for offsets) or crash (DEBUG SLEEP the master)")]
+        mode: String,
+        
+        #[arg(long, default_value_t = 5000)]
+        catch_up_timeout_ms: u64,
+    },
+    
+    #[command(about = "Look at what goes over the connection")]
+    Inspect {
+        #[command(subcommand)]
+        command: InspectCommands,
+    },
+    
+    #[command(about = "Compare version, role, memory, clients, keys and ops/sec across every --redis-url")]
+    Info,
+    
+    #[command(about = "Inspect and cancel resumable bulk jobs")]
+    Jobs {
+        #[command(subcommand)]
+        command: JobCommands,
+    },
+    
+    #[command(about = "Keyspace maintenance tasks")]
+    Maintenance {
+        #[command(subcommand)]
+        command: MaintenanceTasks,
+    },
+    
+    #[command(about = "Minute-level counters and their hour/day rollups")]
+    Metrics {
+        #[command(subcommand)]
+        command: MetricsCommands,
+    },
+    
+    #[command(about = "Dashboards for the metrics bench, replication, streams and cache commands export")]
+    Monitor {
+        #[command(subcommand)]
+        command: MonitorCommands,
+    },
+    
+    #[command(about = "Copy entity hashes into SQLite tables and write SQL edits back")]
+    Mirror {
+        #[command(subcommand)]
+        command: MirrorCommands,
+    },
+    
+    #[command(about = "Monthly per-API-key quotas with soft and hard limits")]
+    Quotas {
+        #[command(subcommand)]
+        command: QuotaCommands,
+    },
+    
+    #[command(about = "Fixed window, sliding log and token bucket limiters deciding requests side by side")]
+    RateLimit {
+        #[arg(long, help = "fixed-window, sliding-log or token-bucket; repeatable (all by default)")]
+        algorithm: Vec<String>,
+        
+        #[arg(long, default_value_t = 5, help = "Requests allowed per window (the bucket's capacity)")]
+        limit: u64,
+        
+        #[arg(long, default_value_t = 1000)]
+        window_ms: u64,
+        
+        #[arg(long, default_value_t = 8, help = "Requests sent at once before the steady stream")]
+        burst: usize,
+        
+        #[arg(long, default_value_t = 20)]
+        requests: usize,
+        
+        #[arg(long, default_value_t = 150)]
+        interval_ms: u64,
+    },
+    
+    #[command(about = "Cache-aside and write-through caching in front of a slow store, with stampede protection")]
+    Cache {
+        #[arg(long, default_value_t = 2000)]
+        ttl_ms: u64,
+        
+        #[arg(long, default_value_t = 200, help = "How long the simulated store takes per read")]
+        delay_ms: u64,
+        
+        #[arg(long, default_value_t = 20, help = "Concurrent readers in the stampede step")]
+        readers: usize,
+    },
+    
+    #[command(about = "Users stored with username and email indexes")]
+    User {
+        #[command(subcommand)]
+        command: UserCommands,
+    },
+    
+    #[command(about = "Master/replica replication health")]
+    Replication {
+        #[command(subcommand)]
+        command: ReplicationCommands,
+    },
+    
+    #[command(about = "Manage and run cron-style recurring jobs")]
+    Scheduler {
+        #[command(subcommand)]
+        command: SchedulerCommands,
+    },
+    
+    #[command(about = "Statistics about this client")]
+    Stats {
+        #[command(subcommand)]
+        command: StatsCommands,
+    },
+    
+    #[command(about = "POST job failures, expired sessions and threshold breaches to an HTTP endpoint")]
+    Webhook {
+        #[command(subcommand)]
+        command: WebhookCommands,
+    },
+    
+    #[command(about = "Order workflow state machine persisted in Redis")]
+    Workflow {
+        #[command(subcommand)]
+        command: WorkflowCommands,
+        
+        #[arg(long, global = true, default_value_t = 900, help = "Seconds an unpaid order may stay created")]
+        payment_timeout_secs: u64,
+    },
+    
+    #[command(about = "Redis Streams tooling")]
+    Streams {
+        #[command(subcommand)]
+        command: StreamCommands,
+    },
+    
+    #[command(about = "Show which multi-key operations break on Redis Cluster and how hash tags fix them")]
+    ClusterPitfalls {
+        #[arg(long = "cluster-node", help = "Cluster node URL to verify against (needs --features cluster)")]
+        cluster_nodes: Vec<String>,
+    },
+    
+    #[command(about = "Hash slots: which node owns them and where keys land")]
+    Cluster {
+        #[command(subcommand)]
+        command: ClusterCommands,
+    },
+    
+    #[command(about = "Debug single keys and generate synthetic ones", visible_alias = "keys")]
+    Key {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
+    
+    #[command(about = "Inspect the data model implied by the keyspace")]
+    Model {
+        #[command(subcommand)]
+        command: ModelCommands,
+    },
+}
+
+impl Commands {
+    /// Tools that work on keys the user already has, rather than keys a
+    /// demo wrote, so they address the keyspace without `--key-prefix`.
+    pub fn reads_existing_keys(&self) -> bool {
+        matches!(
+            self,
+            Commands::Export { .. }
+                | Commands::Import { .. }
+                | Commands::Streams { .. }
+                | Commands::Model { .. }
+                | Commands::Mirror { .. }
+                | Commands::Cluster { .. }
+                | Commands::Maintenance { command: MaintenanceTasks::Rename { .. } }
+        )
+    }
+    
+    /// Diagnostics that accept several `--redis-url`s, run against each
+    /// target concurrently and print one comparison table.
+    pub fn fans_out(&self) -> bool {
+        matches!(self, Commands::Ping | Commands::Advise | Commands::Info)
+    }
+    
+    /// Commands that can publish what they sample with `--push-gateway` or
+    /// `--metrics-listen`.
+    pub fn exports_metrics(&self) -> bool {
+        matches!(
+            self,
+            Commands::Bench { command: BenchCommands::Load { .. } | BenchCommands::Soak { .. } }
+                | Commands::Replication { command: ReplicationCommands::Lag { .. } }
+                | Commands::Streams { command: StreamCommands::Lag { .. } }
+                | Commands::Cache { .. }
+                | Commands::Pattern { pattern: PatternCommands::Backpressure { .. } }
+        )
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeyCommands {
+    #[command(about = "Replay a key's values over time from writes recorded with --history")]
+    History {
+        key: String,
+        
+        #[arg(long, default_value_t = 50, help = "Most recent changes to show")]
+        count: usize,
+    },
+    
+    #[command(about = "Live-print keyspace events (set, del, expire, rename, ...) for keys matching a glob")]
+    Watch {
+        pattern: String,
+        
+        #[arg(long, help = "Fetch and print each key's value after the event")]
+        values: bool,
+        
+        #[arg(long, help = "Stop after this many events")]
+        limit: Option<usize>,
+    },
+    
+    #[command(about = "Pretty-print a key's value by type, with size, TTL and encoding")]
+    Show {
+        key: String,
+        
+        #[arg(long, default_value_t = 100, help = "Most elements of a collection to print")]
+        limit: usize,
+    },
+    
+    #[command(about = "Change a key by type: a string, hash fields, list items, zset members or its TTL")]
+    Set {
+        key: String,
+        
+        #[command(subcommand)]
+        change: KeyChange,
+    },
+    
+    #[command(about = "Write a synthetic key population named from a template like 'order:{seq}:items'")]
+    Gen {
+        #[arg(long, help = "Key name with {seq}, {rand:N} and {uuid} placeholders; other braces stay, for hash tags")]
+        template: String,
+        
+        #[arg(long, default_value_t = 1000)]
+        count: u64,
+        
+        #[arg(long, default_value = "64", help = "Value bytes: N, or MIN-MAX for uniform sizes")]
+        value_size: String,
+        
+        #[arg(long, default_value_t = 0, help = "First {seq}")]
+        start: u64,
+        
+        #[arg(long, help = "Expire the keys after this many seconds")]
+        ttl_secs: Option<u64>,
+        
+        #[arg(long, default_value_t = 1000, help = "SETs per pipelined round trip")]
+        batch_size: usize,
+        
+        #[arg(long, help = "Seed for {rand}, {uuid} and values, to recreate the same dataset")]
+        seed: Option<u64>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PubSubCommands {
+    #[command(about = "Subscriber and publisher in one process: SUBSCRIBE, PSUBSCRIBE and PUBLISH side by side")]
+    Demo,
+    
+    #[command(about = "Send a message to a channel")]
+    Publish {
+        channel: String,
+        
+        message: String,
+    },
+    
+    #[command(about = "Print messages from channels until Ctrl-C")]
+    Subscribe {
+        #[arg(required_unless_present = "pattern")]
+        channels: Vec<String>,
+        
+        #[arg(long, help = "Also listen on channels matching this glob (PSUBSCRIBE); repeatable")]
+        pattern: Vec<String>,
+        
+        #[arg(long, help = "Stop after this many messages")]
+        limit: Option<usize>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeyChange {
+    #[command(about = "Set a string value, replacing whatever the key held")]
+    String {
+        value: String,
+        
+        #[arg(long, help = "Check the value is JSON and store it compacted")]
+        json: bool,
+    },
+    
+    #[command(about = "Set hash fields given as field=value")]
+    Hash {
+        #[arg(value_parser = parse_field, required_unless_present = "json")]
+        fields: Vec<(String, String)>,
+        
+        #[arg(long, conflicts_with = "fields", help = "Fields as a JSON object; non-string values are stored as JSON")]
+        json: Option<String>,
+    },
+    
+    #[command(about = "Push items onto a list, at the tail unless --left")]
+    Push {
+        #[arg(required_unless_present = "json")]
+        values: Vec<String>,
+        
+        #[arg(long, help = "Push onto the head")]
+        left: bool,
+        
+        #[arg(long, conflicts_with = "values", help = "Items as a JSON array")]
+        json: Option<String>,
+    },
+    
+    #[command(about = "Insert an item before (or --after) an existing one")]
+    Insert {
+        pivot: String,
+        
+        value: String,
+        
+        #[arg(long)]
+        after: bool,
+    },
+    
+    #[command(about = "Add or rescore sorted set members given as member=score")]
+    Zadd {
+        #[arg(value_parser = parse_member, required_unless_present = "json")]
+        members: Vec<(String, f64)>,
+        
+        #[arg(long, conflicts_with = "members", help = "Members as a JSON object of member to score")]
+        json: Option<String>,
+    },
+    
+    #[command(about = "Set the key's TTL, or remove it with --persist")]
+    #[command(group(ArgGroup::new("ttl").required(true).args(["duration", "persist"])))]
+    Ttl {
+        #[arg(value_parser = parse_duration)]
+        duration: Option<std::time::Duration>,
+        
+        #[arg(long)]
+        persist: bool,
+    },
+}
+
+impl KeyChange {
+    pub fn into_edit(self) -> crate::Result<KeyEdit> {
+        match self {
+            KeyChange::String { value, json } => KeyEdit::string(&value, json),
+            KeyChange::Hash { json: Some(json), .. } => KeyEdit::fields_from_json(&json),
+            KeyChange::Hash { fields, .. } => KeyEdit::SetFields(fields).validated(),
+            KeyChange::Push { json: Some(json), left, .. } => KeyEdit::push_from_json(&json, left),
+            KeyChange::Push { values, left, .. } => KeyEdit::Push { values, left }.validated(),
+            KeyChange::Insert { pivot, value, after } => Ok(KeyEdit::Insert { pivot, value, before: !after }),
+            KeyChange::Zadd { json: Some(json), .. } => KeyEdit::members_from_json(&json),
+            KeyChange::Zadd { members, .. } => KeyEdit::AddMembers(members).validated(),
+            KeyChange::Ttl { duration, .. } => KeyEdit::Expire(duration).validated(),
+        }
+    }
+}
+
+/// Parses a `field=value` hash field.
+pub fn parse_field(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((field, v)) if !field.is_empty() => Ok((field.to_string(), v.to_string())),
+        _ => Err(format!("invalid field: {} (use field=value)", value)),
+    }
+}
+
+/// Parses a `member=score` sorted set member, splitting at the last `=`.
+pub fn parse_member(value: &str) -> Result<(String, f64), String> {
+    let invalid = || format!("invalid member: {} (use member=score)", value);
+    let (member, score) = value.rsplit_once('=').ok_or_else(invalid)?;
+    let score: f64 = score.parse().map_err(|_| invalid())?;
+    match score.is_finite() {
+        true => Ok((member.to_string(), score)),
+        false => Err(invalid()),
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ClusterCommands {
+    #[command(about = "Slot ranges per node from CLUSTER SLOTS, or an even split when not on a cluster")]
+    Slots {
+        #[arg(long, default_value_t = 3, help = "Masters to split the slots between when the server is not a cluster")]
+        nodes: usize,
+    },
+    
+    #[command(about = "The slot each key hashes to, and whether they can share a multi-key command")]
+    Keyslot {
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    
+    #[command(about = "Histogram of which node a generated keyspace lands on, per key template")]
+    Distribution {
+        #[arg(
+            long = "template",
+            default_values_t = ["user:#:profile".to_string(), "{user:#}:profile".to_string(), "{users}:#".to_string()],
+            help = "Key template, repeatable; # becomes 0, 1, 2, ..."
+        )]
+        templates: Vec<String>,
+        
+        #[arg(long, default_value_t = 10_000)]
+        keys: usize,
+        
+        #[arg(long, default_value_t = 3, help = "Masters to split the slots between when the server is not a cluster")]
+        nodes: usize,
+    },
+    
+    #[command(about = "Estimate the slots and keys a rebalance would move after adding or removing masters (nothing is moved)")]
+    #[command(group(ArgGroup::new("change").required(true).args(["add", "remove"])))]
+    Reshard {
+        #[arg(long, help = "Masters to add")]
+        add: Option<usize>,
+        
+        #[arg(long, help = "Master to drain, as host:port (or node-N when not on a cluster)")]
+        remove: Option<String>,
+        
+        #[arg(long, default_value_t = 3, help = "Masters to split the slots between when the server is not a cluster")]
+        nodes: usize,
+        
+        #[arg(long, default_value_t = 256, help = "Moving slots to count keys in (keys to hash when not on a cluster)")]
+        sample: usize,
+        
+        #[arg(long, default_value_t = 2_000, help = "Migration throughput to estimate the duration with")]
+        keys_per_sec: u64,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -42,6 +696,1046 @@ pub enum BasicOperations {
     
     #[command(about = "Hash operations demo")]
     Hashes,
+    
+    #[command(about = "Sorted set operations demo")]
+    SortedSets,
+    
+    #[command(about = "Geospatial operations and geo-fencing alerts demo")]
+    Geo,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AdminCommands {
+    #[command(about = "Turn the global maintenance flag on or off")]
+    Maintenance {
+        #[command(subcommand)]
+        command: MaintenanceCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MaintenanceCommands {
+    #[command(about = "Enable maintenance mode")]
+    On {
+        #[arg(long, default_value = "Scheduled maintenance")]
+        message: String,
+        
+        #[arg(long, help = "Turn maintenance off automatically after this many seconds")]
+        for_secs: Option<u64>,
+    },
+    
+    #[command(about = "Disable maintenance mode")]
+    Off,
+    
+    #[command(about = "Show whether maintenance mode is on")]
+    Status,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AnalyticsCommands {
+    #[command(about = "Per-user counters packed into one BITFIELD value vs a hash")]
+    Stats {
+        #[arg(long, default_value_t = 1000)]
+        users: usize,
+    },
+    
+    #[command(about = "Weekly cohort retention triangle from per-day activity bitmaps")]
+    Retention {
+        #[arg(long, default_value_t = 5000)]
+        users: usize,
+        
+        #[arg(long, default_value_t = 6)]
+        weeks: u32,
+        
+        #[arg(long, default_value = "table", help = "Output format: table or csv")]
+        format: String,
+    },
+    
+    #[command(about = "Visited, signed up and purchased funnel from per-day HyperLogLogs")]
+    Funnel {
+        #[arg(long, default_value_t = 5000)]
+        users: usize,
+        
+        #[arg(long, default_value_t = 28, help = "Days of activity to generate, starting 2025-01-06")]
+        days: u32,
+        
+        #[arg(long, value_parser = parse_date, help = "First day of the report (YYYY-MM-DD)")]
+        from: Option<NaiveDate>,
+        
+        #[arg(long, value_parser = parse_date, help = "Last day of the report (YYYY-MM-DD)")]
+        to: Option<NaiveDate>,
+    },
+}
+
+/// Parses `YYYY-MM-DD` dates.
+pub fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| format!("invalid date: {}", value))
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PatternCommands {
+    #[command(about = "Scheduling calendar packing 15-minute slots with BITFIELD")]
+    Calendar,
+    
+    #[command(about = "Single-use and N-use coupon codes without over-redemption")]
+    Coupons {
+        #[arg(long, default_value_t = 100, help = "Single-use codes to generate")]
+        batch: usize,
+        
+        #[arg(long, default_value_t = 5, help = "Uses allowed on the shared code")]
+        max_uses: u64,
+        
+        #[arg(long, default_value_t = 50, help = "Concurrent users redeeming")]
+        contenders: usize,
+    },
+    
+    #[command(about = "Last-write-wins register and PN-counter with racing writers")]
+    Crdt {
+        #[arg(long, default_value_t = 4)]
+        actors: usize,
+        
+        #[arg(long, default_value_t = 50)]
+        writes: usize,
+        
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+    },
+    
+    #[command(about = "Home timelines with fan-out on write and celebrity merge on read")]
+    Feed {
+        #[arg(long, default_value_t = 50)]
+        users: usize,
+        
+        #[arg(long, default_value_t = 200)]
+        posts: usize,
+    },
+    
+    #[command(about = "Social graph adjacency with SETs, SINTER and depth-limited BFS")]
+    Graph {
+        #[arg(long, default_value_t = 30)]
+        users: usize,
+        
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+    },
+    
+    #[command(about = "Inventory reservations with expiring holds")]
+    Inventory {
+        #[arg(long, default_value_t = 100)]
+        buyers: usize,
+        
+        #[arg(long, default_value_t = 10)]
+        stock: u32,
+        
+        #[arg(long, default_value_t = 2000)]
+        hold_ms: u64,
+    },
+    
+    #[command(about = "Distributed lock with token-checked release and a watchdog, contended by concurrent workers")]
+    Lock {
+        #[arg(long, default_value_t = 2)]
+        workers: usize,
+        
+        #[arg(long, default_value_t = 5, help = "Times each worker takes the lock")]
+        rounds: usize,
+        
+        #[arg(long, default_value_t = 300, help = "Lock TTL in milliseconds")]
+        ttl_ms: u64,
+    },
+    
+    #[command(about = "Maintenance flag checked by simulated components with local caching")]
+    Maintenance {
+        #[arg(long, default_value_t = 2, help = "Seconds a component may trust its cached flag")]
+        grace_secs: u64,
+    },
+    
+    #[command(about = "Soft-deleted users kept in a trash key and restored")]
+    SoftDelete {
+        #[arg(long, default_value_t = 3600, help = "Seconds a deleted user stays restorable")]
+        trash_ttl_secs: u64,
+    },
+    
+    #[command(about = "Bounded work queue where producers back off or get rejected when it is full")]
+    Backpressure {
+        #[arg(long, default_value_t = 500)]
+        jobs: usize,
+        
+        #[arg(long, default_value_t = 20)]
+        max_len: usize,
+        
+        #[arg(long, help = "Reject jobs when full instead of waiting")]
+        reject: bool,
+    },
+    
+    #[command(about = "Priority queue aging so low-priority jobs aren't starved")]
+    PriorityAging {
+        #[arg(long, default_value_t = 3)]
+        low_jobs: usize,
+        
+        #[arg(long, default_value_t = 40)]
+        rounds: usize,
+        
+        #[arg(long, default_value_t = 1.0, help = "Priority added to every waiting job per tick")]
+        boost: f64,
+    },
+    
+    #[command(about = "Waitlist with live positions and batch admissions over pub/sub")]
+    Waitlist {
+        #[arg(long, default_value_t = 30)]
+        users: usize,
+        
+        #[arg(long, default_value_t = 5)]
+        batch: usize,
+    },
+    
+    #[command(about = "Vote counter with duplicate and burst protection")]
+    Voting {
+        #[command(subcommand)]
+        command: VotingCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum VotingCommands {
+    #[command(about = "Simulate honest voters and a spammer against the vote counter")]
+    Simulate {
+        #[arg(long, default_value = "50", value_parser = parse_positive_count)]
+        users: usize,
+        
+        #[arg(long, default_value = "5", value_parser = parse_positive_count)]
+        items: usize,
+        
+        #[arg(long, default_value = "100", value_parser = parse_positive_count)]
+        burst: usize,
+    },
+}
+
+/// Parses counts such as `1_000_000` or `1000000`.
+pub fn parse_count(value: &str) -> Result<usize, String> {
+    value
+        .replace('_', "")
+        .parse()
+        .map_err(|_| format!("invalid count: {}", value))
+}
+
+/// Like [`parse_count`], for counts that must be at least 1.
+pub fn parse_positive_count(value: &str) -> Result<usize, String> {
+    match parse_count(value)? {
+        0 => Err(format!("must be at least 1: {}", value)),
+        count => Ok(count),
+    }
+}
+
+/// Parses a period in milliseconds, which must be at least 1.
+pub fn parse_millis(value: &str) -> Result<u64, String> {
+    match value.replace('_', "").parse() {
+        Ok(0) => Err(format!("must be at least 1ms: {}", value)),
+        Ok(millis) => Ok(millis),
+        Err(_) => Err(format!("invalid milliseconds: {}", value)),
+    }
+}
+
+/// Parses rates such as `1%` or `0.5%` into a fraction.
+pub fn parse_percent(value: &str) -> Result<f64, String> {
+    let invalid = || format!("invalid percentage: {} (use e.g. 1% or 0.5%)", value);
+    let percent: f64 = value.trim().strip_suffix('%').ok_or_else(invalid)?.trim().parse().map_err(|_| invalid())?;
+    match (0.0..=100.0).contains(&percent) {
+        true => Ok(percent / 100.0),
+        false => Err(invalid()),
+    }
+}
+
+/// Parses a probability strictly between 0 and 1, such as `0.01`.
+pub fn parse_probability(value: &str) -> Result<f64, String> {
+    let invalid = || format!("invalid probability: {} (use a number between 0 and 1, e.g. 0.01)", value);
+    let probability: f64 = value.trim().parse().map_err(|_| invalid())?;
+    match probability > 0.0 && probability < 1.0 {
+        true => Ok(probability),
+        false => Err(invalid()),
+    }
+}
+
+/// Parses durations such as `1h`, `90s`, `500ms` or `1h30m`.
+pub fn parse_duration(value: &str) -> Result<std::time::Duration, String> {
+    let invalid = || format!("invalid duration: {} (use e.g. 1h30m, 90s or 500ms)", value);
+    let mut total = std::time::Duration::ZERO;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let amount: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        total += match &rest[..unit] {
+            "ms" => std::time::Duration::from_millis(amount),
+            "s" => std::time::Duration::from_secs(amount),
+            "m" => std::time::Duration::from_secs(amount * 60),
+            "h" => std::time::Duration::from_secs(amount * 3600),
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit..];
+    }
+    Ok(total)
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    #[command(about = "Print the configuration and every change until the time is up")]
+    Watch {
+        #[arg(long, default_value = "app")]
+        name: String,
+        
+        #[arg(long, default_value_t = 60)]
+        seconds: u64,
+        
+        #[arg(long, default_value = "poll", help = "Reload strategy: poll or events (keyspace notifications)")]
+        strategy: String,
+    },
+    
+    #[command(about = "Change one configuration field")]
+    Set {
+        #[arg(long, default_value = "app")]
+        name: String,
+        
+        #[arg(long)]
+        field: String,
+        
+        #[arg(long)]
+        value: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuditCommands {
+    #[command(about = "Show the audit stream of one entity, newest first")]
+    Show {
+        #[arg(help = "Entity kind, e.g. user")]
+        entity: String,
+        
+        id: String,
+        
+        #[arg(long, default_value_t = 20)]
+        count: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BenchCommands {
+    #[command(about = "Page through users with N HGETALLs vs one pipeline")]
+    Hydration {
+        #[arg(long, default_value_t = 1000)]
+        users: usize,
+        
+        #[arg(long, default_value_t = 50)]
+        page_size: usize,
+    },
+    
+    #[command(about = "Mixed GET/SET load recorded into HDR histograms")]
+    Load {
+        #[arg(long, default_value = "100_000", value_parser = parse_count)]
+        requests: usize,
+        
+        #[arg(long, default_value_t = 50)]
+        concurrency: usize,
+        
+        #[arg(long, default_value = "10_000", value_parser = parse_count)]
+        keyspace: usize,
+        
+        #[arg(long, default_value_t = 64)]
+        value_size: usize,
+        
+        #[arg(long, help = "uniform, zipfian[:skew] or hotset[:keys[:fraction]]; overrides the workload's")]
+        distribution: Option<String>,
+        
+        #[arg(long, help = "Bundled profile name or TOML workload file; overrides --keyspace/--value-size")]
+        workload: Option<String>,
+        
+        #[arg(long, default_value_t = 1000, help = "Unrecorded requests sent before measuring")]
+        warmup: usize,
+        
+        #[arg(long, default_value_t = 0, help = "Minimum measured seconds, however many requests that takes")]
+        min_duration: u64,
+        
+        #[arg(long, default_value = "1000", value_parser = parse_millis, help = "Interval log resolution")]
+        interval_ms: u64,
+        
+        #[arg(long, help = "Write the percentile distribution (.hgrm)")]
+        hgrm: Option<String>,
+        
+        #[arg(long, help = "Write per-interval histograms (.hlog)")]
+        hlog: Option<String>,
+        
+        #[arg(long, help = "Diff against a previous run's .hgrm")]
+        compare: Option<String>,
+    },
+    
+    #[command(about = "redis-benchmark style: each command in turn from concurrent clients, with throughput and p50/p95/p99")]
+    Commands {
+        #[arg(short, long, default_value = "set,get,incr,lpush,hset", help = "Commands to run, in order: get, set, del, incr, lpush, hget, hset, zadd, zrange")]
+        tests: String,
+        
+        #[arg(short, long, default_value_t = 50, help = "Connections sending at once")]
+        clients: usize,
+        
+        #[arg(short = 'n', long, default_value = "100_000", value_parser = parse_count, help = "Requests per command")]
+        requests: usize,
+        
+        #[arg(short = 'P', long, default_value_t = 1, help = "Commands per round trip")]
+        pipeline: usize,
+        
+        #[arg(short = 'd', long, default_value_t = 64, help = "Value bytes for SET, LPUSH and HSET")]
+        payload_size: usize,
+        
+        #[arg(long, default_value = "10_000", value_parser = parse_count)]
+        keyspace: usize,
+    },
+    
+    #[command(about = "Diff two .hgrm percentile distributions saved with `bench load --hgrm`")]
+    Compare {
+        baseline: String,
+        
+        current: String,
+        
+        #[arg(long, default_value = "text", help = "Output format: text or jsonl")]
+        format: String,
+    },
+    
+    #[command(about = "Same workload through sync, pooled and async multiplexed connections")]
+    Strategies {
+        #[arg(long, default_value = "20_000", value_parser = parse_count)]
+        requests: usize,
+        
+        #[arg(long, default_value_t = 16)]
+        concurrency: usize,
+        
+        #[arg(long, help = "Bundled profile name or TOML workload file (GET/SET by default)")]
+        workload: Option<String>,
+        
+        #[arg(long, help = "Also run through an r2d2 pool of blocking connections")]
+        with_pool: bool,
+    },
+    
+    #[command(about = "Run a moderate workload for a long time and report leaks or drift")]
+    Soak {
+        #[arg(long, default_value = "1h", value_parser = parse_duration)]
+        duration: std::time::Duration,
+        
+        #[arg(long, default_value_t = 1000, help = "Target ops/s across all workers")]
+        rate: u64,
+        
+        #[arg(long, default_value_t = 8)]
+        concurrency: usize,
+        
+        #[arg(long, help = "Bundled profile name or TOML workload file (GET/SET by default)")]
+        workload: Option<String>,
+        
+        #[arg(long, default_value = "10s", value_parser = parse_duration)]
+        sample_every: std::time::Duration,
+    },
+    
+    #[command(about = "Raise concurrency until p99 exceeds a target and report max throughput")]
+    Adaptive {
+        #[arg(long, default_value_t = 5.0)]
+        target_p99_ms: f64,
+        
+        #[arg(long, default_value_t = 2000)]
+        step_ms: u64,
+        
+        #[arg(long, default_value_t = 1024)]
+        max_concurrency: usize,
+        
+        #[arg(long, default_value_t = 64)]
+        value_size: usize,
+    },
+    
+    #[command(about = "Concurrent single commands with and without auto-pipelining")]
+    AutoPipeline {
+        #[arg(long, default_value_t = 200)]
+        tasks: usize,
+        
+        #[arg(long, default_value_t = 200)]
+        ops: usize,
+        
+        #[arg(long, default_value_t = 200, help = "Coalescing window in microseconds")]
+        window_us: u64,
+    },
+    
+    #[command(about = "GET a large value as String vs bytes::Bytes")]
+    Bytes {
+        #[arg(long, default_value = "1_048_576", value_parser = parse_count)]
+        value_size: usize,
+        
+        #[arg(long, default_value_t = 200)]
+        iterations: usize,
+    },
+    
+    #[command(about = "Save user logins as whole JSON documents vs only the dirty hash fields")]
+    FieldWrites {
+        #[arg(long, default_value_t = 200)]
+        users: usize,
+        
+        #[arg(long, default_value_t = 10, help = "Logins saved per user")]
+        updates: usize,
+    },
+    
+    #[command(about = "Export seeded keys with a fixed memory budget")]
+    Export {
+        #[arg(long, default_value = "1_000_000", value_parser = parse_count)]
+        keys: usize,
+        
+        #[arg(long, default_value_t = 8)]
+        memory_budget_mb: usize,
+    },
+    
+    #[command(about = "A bigkeys-style job with 1..N concurrent SCAN partitions")]
+    Scan {
+        #[arg(long, default_value = "100_000", value_parser = parse_count)]
+        keys: usize,
+        
+        #[arg(long, default_value_t = 8)]
+        workers: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ExportCommands {
+    #[command(about = "Stream matching keys with their types and TTLs to a JSON file")]
+    Json {
+        #[arg(long, default_value = "*")]
+        pattern: String,
+        
+        #[arg(long)]
+        out: String,
+        
+        #[arg(long, default_value = "json", help = "json (one array), ndjson (one key per line) or resp (commands for redis-cli --pipe)")]
+        format: String,
+        
+        #[arg(long, default_value_t = 500)]
+        batch_size: usize,
+        
+        #[arg(long, default_value_t = 64, help = "Serialized data buffered ahead of the file writer")]
+        memory_budget_mb: usize,
+    },
+    
+    #[command(about = "Write a sorted set (rank, member, score) or a stream (id, fields) as a table for pandas or Excel")]
+    Table {
+        key: String,
+        
+        #[arg(long)]
+        out: String,
+        
+        #[arg(long, help = "csv or parquet (needs --features parquet); defaults to the --out extension")]
+        format: Option<String>,
+        
+        #[arg(long, help = "Highest score first, as a leaderboard")]
+        rev: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CheckCommands {
+    #[command(about = "Validate entity invariants (user indexes, cart products)")]
+    Consistency {
+        #[arg(long, help = "Apply the suggested repairs")]
+        repair: bool,
+        
+        #[arg(long, default_value = "text", help = "Output format: text or jsonl (one record per violation)")]
+        format: String,
+    },
+    
+    #[command(about = "Scan stored entities and report documents that fail their validation rules")]
+    Validate {
+        #[arg(long, default_value = "user", help = "Entity kind to scan: user")]
+        entity: String,
+        
+        #[arg(long, default_value = "text", help = "Output format: text or jsonl (one record per violation)")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ExperimentCommands {
+    #[command(about = "Simulate traffic across control and treatment")]
+    Simulate {
+        #[arg(long, default_value = "checkout")]
+        name: String,
+        
+        #[arg(long, default_value_t = 10_000)]
+        users: usize,
+        
+        #[arg(long, default_value_t = 0.10)]
+        control_rate: f64,
+        
+        #[arg(long, default_value_t = 0.12)]
+        treatment_rate: f64,
+        
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+    },
+    
+    #[command(about = "Conversion rates per variant with a significance indicator")]
+    Results {
+        #[arg(long, default_value = "checkout")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InspectCommands {
+    #[command(about = "Send a command over a raw socket and show the annotated RESP bytes both ways")]
+    Wire {
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true, help = "The command and its arguments, e.g. GET user:1")]
+        command: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum JobCommands {
+    #[command(about = "All bulk jobs with their progress, newest first")]
+    List,
+    
+    #[command(about = "Progress, rate and position of one job")]
+    Status { id: String },
+    
+    #[command(about = "Ask a running job to stop at its next checkpoint")]
+    Cancel { id: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MaintenanceTasks {
+    #[command(about = "Find and remove username:/email: index keys whose user no longer exists")]
+    GcIndexes {
+        #[arg(long, help = "Only report orphans")]
+        dry_run: bool,
+        
+        #[arg(long, default_value_t = 500)]
+        batch_size: usize,
+        
+        #[arg(long, default_value_t = 10, help = "Pause between batches in milliseconds")]
+        pause_ms: u64,
+        
+        #[arg(long, value_name = "JOB_ID", help = "Continue an interrupted run from its last checkpoint")]
+        resume: Option<String>,
+    },
+    
+    #[command(about = "Rename every key matching a glob to a new prefix, keeping TTLs")]
+    Rename {
+        #[arg(long, help = "Keys to rename, e.g. 'userv1:*'; the part before the first wildcard is replaced")]
+        from: String,
+        
+        #[arg(long, help = "Prefix that replaces it, e.g. 'user:'")]
+        to_prefix: String,
+        
+        #[arg(long, help = "Move the keys into this database (DUMP/RESTORE) instead of renaming in place")]
+        to_db: Option<i64>,
+        
+        #[arg(long, default_value = "skip", help = "When the new name exists: skip, overwrite or fail")]
+        on_collision: String,
+        
+        #[arg(long, help = "Only count what would be renamed and what would collide")]
+        dry_run: bool,
+        
+        #[arg(long, default_value_t = 500)]
+        batch_size: usize,
+        
+        #[arg(long, default_value_t = 10, help = "Pause between batches in milliseconds")]
+        pause_ms: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MetricsCommands {
+    #[command(about = "Record per-minute traffic, compact it via the scheduler and verify totals")]
+    Rollup {
+        #[arg(long, default_value_t = 3, help = "Days of history to generate")]
+        days: i64,
+        
+        #[arg(long, default_value_t = 6, help = "Hours of minute-level data to keep")]
+        retention_hours: i64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum QuotaCommands {
+    #[command(about = "Create or update an API key's plan")]
+    Set {
+        #[arg(long)]
+        key: String,
+        
+        #[arg(long, help = "Requests per calendar month")]
+        limit: u64,
+        
+        #[arg(long, default_value_t = 0.8, help = "Fraction of the limit that triggers a soft warning")]
+        soft_ratio: f64,
+        
+        #[arg(long, default_value_t = 0, help = "Burst limit per second (0 = unlimited)")]
+        per_second: u32,
+    },
+    
+    #[command(about = "Show an API key's plan, usage and remaining quota")]
+    Show {
+        #[arg(long)]
+        key: String,
+        
+        #[arg(long, help = "Billing period as YYYY-MM (current month by default)")]
+        period: Option<String>,
+    },
+    
+    #[command(about = "Reset an API key's usage for a period")]
+    Reset {
+        #[arg(long)]
+        key: String,
+        
+        #[arg(long, help = "Billing period as YYYY-MM (current month by default)")]
+        period: Option<String>,
+    },
+    
+    #[command(about = "Drive a bursty client through a small plan and print the warnings")]
+    Simulate {
+        #[arg(long, default_value = "demo-key")]
+        key: String,
+        
+        #[arg(long, default_value_t = 150)]
+        requests: usize,
+    },
+    
+    #[command(about = "Two tenants writing under per-namespace storage quotas, one refusing and one warning")]
+    Namespaces {
+        #[arg(long, default_value_t = 8, help = "Rounds of four 256-byte records per tenant")]
+        rounds: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SchedulerCommands {
+    #[command(about = "List recurring jobs and their next run")]
+    List,
+    
+    #[command(about = "Add or replace a recurring job")]
+    Add {
+        #[arg(long)]
+        name: String,
+        
+        #[arg(long, help = "Five-field cron expression or @hourly/@daily/...")]
+        cron: String,
+        
+        #[arg(long, default_value = "log")]
+        handler: String,
+        
+        #[arg(long, default_value = "skip", help = "Missed runs: skip, run-once or run-all")]
+        catch_up: String,
+    },
+    
+    #[command(about = "Remove a recurring job")]
+    Remove {
+        #[arg(long)]
+        name: String,
+    },
+    
+    #[command(about = "Run the scheduler loop for a while")]
+    Run {
+        #[arg(long, default_value_t = 120)]
+        seconds: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReplicationCommands {
+    #[command(about = "Bytes each replica trails --redis-url (a master) by")]
+    Lag {
+        #[arg(long, help = "Redraw the panel until Ctrl-C")]
+        watch: bool,
+        
+        #[arg(long, default_value_t = 1000)]
+        interval_ms: u64,
+        
+        #[arg(long, help = "Alert when a replica is more than this many bytes behind")]
+        max_lag_bytes: Option<u64>,
+        
+        #[arg(long, help = "Alert when a replica has not acknowledged for this many seconds")]
+        max_ack_secs: Option<u64>,
+        
+        #[arg(long, help = "Post breaches to this Slack or Discord webhook, or any URL as event JSON")]
+        alert_webhook: Option<String>,
+        
+        #[arg(long, help = "Chat message text with {subject}, {message}, {event}, {at} and {suppressed}")]
+        alert_template: Option<String>,
+        
+        #[arg(long, default_value_t = 300, help = "Seconds before the same breach is posted again")]
+        alert_cooldown_secs: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MonitorCommands {
+    #[command(about = "Grafana dashboard JSON with a panel for every exported metric")]
+    Dashboard {
+        #[arg(long, help = "Write here instead of stdout")]
+        out: Option<String>,
+        
+        #[arg(long, default_value = "Redis Rust Demo")]
+        title: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UserCommands {
+    #[command(about = "Create a user, refusing a username or email that is taken")]
+    Create {
+        username: String,
+        
+        email: String,
+        
+        #[arg(long, help = "Full name (defaults to the username)")]
+        name: Option<String>,
+        
+        #[arg(long)]
+        age: Option<u8>,
+        
+        #[arg(long)]
+        city: Option<String>,
+        
+        #[arg(long)]
+        country: Option<String>,
+    },
+    
+    #[command(about = "Look a user up by id, username or email")]
+    #[command(group(ArgGroup::new("lookup").required(true).args(["id", "username", "email"])))]
+    Get {
+        #[arg(long)]
+        id: Option<uuid::Uuid>,
+        
+        #[arg(long)]
+        username: Option<String>,
+        
+        #[arg(long)]
+        email: Option<String>,
+    },
+    
+    #[command(about = "Users in creation order, a page at a time")]
+    List {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        
+        #[arg(long, help = "Cursor printed after the previous page")]
+        after: Option<String>,
+    },
+    
+    #[command(about = "Move a user to the trash; it can be restored until the trash copy expires")]
+    Delete {
+        id: uuid::Uuid,
+        
+        #[arg(long, default_value_t = 86_400)]
+        trash_ttl_secs: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StatsCommands {
+    #[command(about = "Keys the last --key-stats run touched most, with a per-family heatmap")]
+    ClientKeys {
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WorkflowCommands {
+    #[command(about = "Create an order in the created state")]
+    Create {
+        #[arg(long)]
+        id: String,
+    },
+    
+    #[command(about = "Move an order to its next state")]
+    Advance {
+        #[arg(long)]
+        id: String,
+        
+        #[arg(long, help = "Target state: paid, shipped, delivered or cancelled")]
+        to: String,
+        
+        #[arg(long, default_value = "manual")]
+        reason: String,
+    },
+    
+    #[command(about = "Show an order's state, pending timeout and history")]
+    Inspect {
+        #[arg(long)]
+        id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StreamCommands {
+    #[command(about = "Archive stream entries to rotating NDJSON or columnar files, resuming from the last run")]
+    Archive {
+        #[arg(long)]
+        stream: String,
+        
+        #[arg(long)]
+        out: String,
+        
+        #[arg(long, help = "Keep tailing the stream until Ctrl-C")]
+        follow: bool,
+        
+        #[arg(long, help = "Consume through this consumer group instead of a saved position")]
+        group: Option<String>,
+        
+        #[arg(long, default_value = "archiver")]
+        consumer: String,
+        
+        #[arg(long, default_value_t = 500)]
+        batch_size: usize,
+        
+        #[arg(long, default_value_t = 64 * 1024 * 1024, help = "Start a new file after this many bytes")]
+        rotate_bytes: u64,
+        
+        #[arg(long, default_value = "ndjson", help = "ndjson (a line per entry) or columnar (Parquet-lite: a line of columns per batch)")]
+        format: String,
+    },
+    
+    #[command(about = "Republish historical stream entries with their original pacing")]
+    #[command(group(ArgGroup::new("target").required(true).args(["to_channel", "to_stream"])))]
+    Replay {
+        #[arg(long)]
+        stream: String,
+        
+        #[arg(long, default_value = "-", help = "First entry id (inclusive)")]
+        from: String,
+        
+        #[arg(long, default_value = "+", help = "Last entry id (inclusive)")]
+        to: String,
+        
+        #[arg(long, default_value = "1x", value_parser = parse_speed, help = "Playback speed, e.g. 2x, 0.5x or max")]
+        speed: f64,
+        
+        #[arg(long)]
+        to_channel: Option<String>,
+        
+        #[arg(long)]
+        to_stream: Option<String>,
+    },
+    
+    #[command(about = "Report consumer group pending counts, oldest pending age and lag")]
+    Lag {
+        #[arg(long)]
+        stream: String,
+        
+        #[arg(long, default_value = "table", help = "Output format: table or prometheus")]
+        format: String,
+        
+        #[arg(long, help = "JSON file with max_pending, max_oldest_pending_ms and max_lag thresholds")]
+        config: Option<String>,        
+        #[arg(long, help = "Post breaches to this Slack or Discord webhook, or any URL as event JSON")]
+        alert_webhook: Option<String>,
+        
+        #[arg(long, help = "Chat message text with {subject}, {message}, {event}, {at} and {suppressed}")]
+        alert_template: Option<String>,
+        
+        #[arg(long, default_value_t = 300, help = "Seconds before the same breach is posted again")]
+        alert_cooldown_secs: u64,
+    },
+}
+
+/// Parses replay speeds such as `2x`, `0.5` or `max` (no pacing).
+pub fn parse_speed(value: &str) -> Result<f64, String> {
+    if value.eq_ignore_ascii_case("max") {
+        return Ok(f64::INFINITY);
+    }
+    match value.trim_end_matches(['x', 'X']).parse::<f64>() {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(speed),
+        _ => Err(format!("invalid speed: {}", value)),
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ModelCommands {
+    #[command(about = "Infer entities and relationships from key names and emit a diagram")]
+    Graph {
+        #[arg(short, long, default_value = "*")]
+        pattern: String,
+        
+        #[arg(short, long, help = "Output file (stdout if omitted)")]
+        out: Option<String>,
+        
+        #[arg(short, long, help = "dot or d2 (inferred from --out extension by default)")]
+        format: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WebhookCommands {
+    #[command(about = "Send events to --url as they happen, until Ctrl-C")]
+    Watch {
+        #[arg(long)]
+        url: String,
+        
+        #[arg(long, help = "Sign each delivery with HMAC-SHA256 in X-Webhook-Signature")]
+        secret: Option<String>,
+        
+        #[arg(long = "event", help = "job-failed, session-expired or threshold-breached; repeatable (all by default)")]
+        events: Vec<String>,
+        
+        #[arg(long, default_value = "session:*", help = "Keys whose expiry is a session-expired event")]
+        sessions: String,
+        
+        #[arg(long, help = "Threshold breach when a replica is more than this many bytes behind")]
+        max_lag_bytes: Option<u64>,
+        
+        #[arg(long, help = "Threshold breach when a replica has not acknowledged for this many seconds")]
+        max_ack_secs: Option<u64>,
+        
+        #[arg(long, default_value = "1000", value_parser = parse_millis, help = "How often jobs and replication are checked")]
+        interval_ms: u64,
+        
+        #[arg(long, help = "Stop after this many events")]
+        limit: Option<usize>,
+    },
+    
+    #[command(about = "Send one event to check an endpoint and its signature verification")]
+    Send {
+        #[arg(long)]
+        url: String,
+        
+        #[arg(long)]
+        secret: Option<String>,
+        
+        #[arg(long, default_value = "threshold-breached")]
+        event: String,
+        
+        #[arg(long, default_value = "webhook-test")]
+        subject: String,
+        
+        #[arg(default_value = "Test delivery from redis-demo")]
+        message: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MirrorCommands {
+    #[command(about = "Materialize the entity hashes under one prefix as a table (needs --features sqlite)")]
+    Sqlite {
+        #[arg(short, long, help = "Entity keys, e.g. 'user:*'; the table is named after the prefix")]
+        pattern: String,
+        
+        #[arg(long, default_value = "mirror.sqlite", help = "SQLite file, created if missing")]
+        db: String,
+        
+        #[arg(long, default_value_t = 500, help = "Keys per SCAN and MGET")]
+        batch_size: usize,
+    },
+    
+    #[command(about = "Write rows edited in SQL back to Redis, reporting keys that changed on both sides")]
+    SyncBack {
+        #[arg(long, default_value = "mirror.sqlite")]
+        db: String,
+        
+        #[arg(long, help = "Report what would be written without writing")]
+        dry_run: bool,
+    },
 }
 
 #[cfg(test)]
@@ -2,6 +2,7 @@
 mod tests {
     use super::super::*;
     use clap::CommandFactory;
+    use crate::inspect::KeyEdit;
     
     #[test]
     fn test_cli_creation() {
@@ -14,8 +15,113 @@ mod tests {
         let args = vec!["redis-demo", "ping"];
         let cli = Cli::try_parse_from(args).unwrap();
         assert!(matches!(cli.command, Commands::Ping));
-        assert_eq!(cli.redis_url, "redis://localhost:6379");
+        assert_eq!(cli.redis_url, ["redis://localhost:6379"]);
         assert!(!cli.verbose);
+        assert_eq!(cli.key_prefix, "demo:");
+    }
+    
+    #[test]
+    fn test_key_prefix_is_mandatory() {
+        let cli = Cli::try_parse_from(["redis-demo", "basic", "strings", "--key-prefix", "ci:42:"]).unwrap();
+        assert_eq!(cli.key_prefix, "ci:42:");
+        assert!(!cli.command.reads_existing_keys());
+        assert!(Cli::try_parse_from(["redis-demo", "--key-prefix", "", "ping"]).is_err());
+    }
+    
+    #[test]
+    fn test_jsonl_report_formats() {
+        let cli = Cli::try_parse_from(["redis-demo", "check", "consistency", "--format", "jsonl"]).unwrap();
+        assert!(matches!(cli.command, Commands::Check { command: CheckCommands::Consistency { repair: false, ref format } } if format == "jsonl"));
+        let cli = Cli::try_parse_from(["redis-demo", "bench", "compare", "before.hgrm", "after.hgrm"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::Compare { baseline, current, format } } => {
+                assert_eq!((baseline.as_str(), current.as_str(), format.as_str()), ("before.hgrm", "after.hgrm", "text"));
+            }
+            _ => panic!("Expected bench compare"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_jobs() {
+        let cli = Cli::try_parse_from(["redis-demo", "jobs", "list"]).unwrap();
+        assert!(matches!(cli.command, Commands::Jobs { command: JobCommands::List }));
+        let cli = Cli::try_parse_from(["redis-demo", "jobs", "cancel", "gc-indexes-1"]).unwrap();
+        assert!(matches!(cli.command, Commands::Jobs { command: JobCommands::Cancel { ref id } } if id == "gc-indexes-1"));
+        assert!(Cli::try_parse_from(["redis-demo", "jobs", "status"]).is_err());
+    }
+    
+    #[test]
+    fn test_budget_ms_is_global() {
+        let cli = Cli::try_parse_from(["redis-demo", "basic", "lists", "--budget-ms", "250"]).unwrap();
+        assert_eq!(cli.budget_ms, Some(250));
+        assert_eq!(Cli::try_parse_from(["redis-demo", "ping"]).unwrap().budget_ms, None);
+    }
+    
+    #[test]
+    fn test_key_history() {
+        let cli = Cli::try_parse_from(["redis-demo", "basic", "strings", "--history", "user:*", "--history", "session:*"]).unwrap();
+        assert_eq!(cli.history, ["user:*", "session:*"]);
+        let cli = Cli::try_parse_from(["redis-demo", "key", "history", "user:1"]).unwrap();
+        match cli.command {
+            Commands::Key { command: KeyCommands::History { key, count } } => {
+                assert_eq!(key, "user:1");
+                assert_eq!(count, 50);
+            }
+            _ => panic!("Expected key history"),
+        }
+        let cli = Cli::try_parse_from(["redis-demo", "key", "watch", "user:*", "--values"]).unwrap();
+        assert!(matches!(cli.command, Commands::Key { command: KeyCommands::Watch { values: true, limit: None, .. } }));
+        let cli = Cli::try_parse_from(["redis-demo", "key", "show", "user:1", "--limit", "5"]).unwrap();
+        assert!(matches!(cli.command, Commands::Key { command: KeyCommands::Show { limit: 5, .. } }));
+    }
+    
+    #[test]
+    fn test_key_set_changes() {
+        let cli = Cli::try_parse_from(["redis-demo", "key", "set", "user:1", "hash", "name=Amy", "age=30"]).unwrap();
+        match cli.command {
+            Commands::Key { command: KeyCommands::Set { change, .. } } => assert_eq!(
+                change.into_edit().unwrap(),
+                KeyEdit::SetFields(vec![("name".to_string(), "Amy".to_string()), ("age".to_string(), "30".to_string())])
+            ),
+            _ => panic!("Expected key set"),
+        }
+        let cli = Cli::try_parse_from(["redis-demo", "key", "set", "board", "zadd", "--json", r#"{"amy": 3}"#]).unwrap();
+        assert!(matches!(cli.command, Commands::Key { command: KeyCommands::Set { change: KeyChange::Zadd { json: Some(_), .. }, .. } }));
+        assert!(Cli::try_parse_from(["redis-demo", "key", "set", "board", "zadd", "amy=high"]).is_err());
+        assert!(Cli::try_parse_from(["redis-demo", "key", "set", "user:1", "hash"]).is_err());
+        assert!(Cli::try_parse_from(["redis-demo", "key", "set", "user:1", "ttl"]).is_err());
+        assert!(Cli::try_parse_from(["redis-demo", "key", "set", "user:1", "ttl", "90s", "--persist"]).is_err());
+    }
+    
+    #[test]
+    fn test_inspect_wire_takes_the_rest_as_the_command() {
+        let cli = Cli::try_parse_from(["redis-demo", "inspect", "wire", "SET", "k", "-1", "EX", "10"]).unwrap();
+        match cli.command {
+            Commands::Inspect { command: InspectCommands::Wire { command } } => assert_eq!(command, ["SET", "k", "-1", "EX", "10"]),
+            _ => panic!("Expected inspect wire"),
+        }
+        assert!(Cli::try_parse_from(["redis-demo", "inspect", "wire"]).is_err());
+    }
+    
+    #[test]
+    fn test_record_and_replay_exclude_each_other() {
+        let cli = Cli::try_parse_from(["redis-demo", "basic", "hashes", "--replay", "hashes.jsonl"]).unwrap();
+        assert_eq!((cli.record, cli.replay.as_deref()), (None, Some("hashes.jsonl")));
+        assert!(Cli::try_parse_from(["redis-demo", "--record", "a.jsonl", "--replay", "a.jsonl", "ping"]).is_err());
+    }
+    
+    #[test]
+    fn test_offline_is_global() {
+        assert!(Cli::try_parse_from(["redis-demo", "basic", "sets", "--offline"]).unwrap().offline);
+        assert!(!Cli::try_parse_from(["redis-demo", "ping"]).unwrap().offline);
+    }
+    
+    #[test]
+    fn test_confirmation_flags() {
+        let cli = Cli::try_parse_from(["redis-demo", "maintenance", "gc-indexes", "-y", "--allow-db0"]).unwrap();
+        assert!(cli.yes && cli.allow_db0);
+        let cli = Cli::try_parse_from(["redis-demo", "admin", "maintenance", "off"]).unwrap();
+        assert!(!cli.yes && !cli.allow_db0);
     }
     
     #[test]
@@ -23,10 +129,182 @@ mod tests {
         let args = vec!["redis-demo", "--redis-url", "redis://custom:6380", "--verbose", "ping"];
         let cli = Cli::try_parse_from(args).unwrap();
         assert!(matches!(cli.command, Commands::Ping));
-        assert_eq!(cli.redis_url, "redis://custom:6380");
+        assert_eq!(cli.redis_url, ["redis://custom:6380"]);
         assert!(cli.verbose);
     }
     
+    #[test]
+    fn test_several_redis_urls() {
+        let cli = Cli::try_parse_from(["redis-demo", "-r", "redis://a", "--redis-url", "redis://b:6380", "info"]).unwrap();
+        assert_eq!(cli.redis_url, ["redis://a:6379", "redis://b:6380"]);
+        assert!(cli.command.fans_out());
+        let cli = Cli::try_parse_from(["redis-demo", "-r", "redis://a", "-r", "redis://b", "basic", "strings"]).unwrap();
+        assert!(!cli.command.fans_out());
+    }
+    
+    #[test]
+    fn test_metrics_flags() {
+        let cli = Cli::try_parse_from([
+            "redis-demo", "bench", "soak", "--push-gateway", "http://localhost:9091", "--metrics-listen", "0.0.0.0:9187",
+        ])
+        .unwrap();
+        assert_eq!(cli.push_gateway.as_deref(), Some("http://localhost:9091"));
+        assert_eq!(cli.metrics_listen, Some("0.0.0.0:9187".parse().unwrap()));
+        assert!(cli.command.exports_metrics());
+        let cli = Cli::try_parse_from(["redis-demo", "--metrics-config", "metrics.toml", "replication", "lag"]).unwrap();
+        assert_eq!(cli.metrics_config.as_deref(), Some("metrics.toml"));
+        assert!(cli.command.exports_metrics());
+        assert!(!Cli::try_parse_from(["redis-demo", "ping"]).unwrap().command.exports_metrics());
+        assert!(Cli::try_parse_from(["redis-demo", "pattern", "backpressure"]).unwrap().command.exports_metrics());
+        assert!(Cli::try_parse_from(["redis-demo", "bench", "soak", "--metrics-listen", "localhost"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_rate_limit() {
+        let cli = Cli::try_parse_from(["redis-demo", "rate-limit", "--algorithm", "sliding-log", "--algorithm", "token-bucket", "--limit", "10"]).unwrap();
+        match cli.command {
+            Commands::RateLimit { algorithm, limit, window_ms, burst, requests, interval_ms } => {
+                assert_eq!(algorithm, ["sliding-log", "token-bucket"]);
+                assert_eq!((limit, window_ms, burst, requests, interval_ms), (10, 1000, 8, 20, 150));
+            }
+            _ => panic!("Expected RateLimit command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_cache() {
+        let cli = Cli::try_parse_from(["redis-demo", "cache", "--ttl-ms", "500", "--readers", "50"]).unwrap();
+        match cli.command {
+            Commands::Cache { ttl_ms, delay_ms, readers } => assert_eq!((ttl_ms, delay_ms, readers), (500, 200, 50)),
+            _ => panic!("Expected Cache command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_monitor_dashboard() {
+        let cli = Cli::try_parse_from(["redis-demo", "monitor", "dashboard", "--out", "dashboard.json"]).unwrap();
+        match cli.command {
+            Commands::Monitor { command: MonitorCommands::Dashboard { out, title } } => {
+                assert_eq!((out.as_deref(), title.as_str()), (Some("dashboard.json"), "Redis Rust Demo"));
+            }
+            _ => panic!("Expected monitor dashboard"),
+        }
+        let cli = Cli::try_parse_from(["redis-demo", "--push-gateway", "http://localhost:9091", "cache"]).unwrap();
+        assert!(cli.command.exports_metrics());
+    }
+    
+    #[test]
+    fn test_cli_parsing_user() {
+        let cli = Cli::try_parse_from(["redis-demo", "user", "create", "amy", "amy@example.com", "--city", "Oslo"]).unwrap();
+        match cli.command {
+            Commands::User { command: UserCommands::Create { username, email, name, city, .. } } => {
+                assert_eq!((username.as_str(), email.as_str(), name, city.as_deref()), ("amy", "amy@example.com", None, Some("Oslo")));
+            }
+            _ => panic!("Expected user create"),
+        }
+        let cli = Cli::try_parse_from(["redis-demo", "user", "get", "--email", "amy@example.com"]).unwrap();
+        assert!(matches!(cli.command, Commands::User { command: UserCommands::Get { email: Some(_), id: None, username: None } }));
+        assert!(Cli::try_parse_from(["redis-demo", "user", "get"]).is_err());
+        assert!(Cli::try_parse_from(["redis-demo", "user", "get", "--id", "amy", "--username", "amy"]).is_err());
+        assert!(Cli::try_parse_from(["redis-demo", "user", "delete", "not-a-uuid"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_log_format() {
+        let cli = Cli::try_parse_from(["redis-demo", "basic", "strings"]).unwrap();
+        assert_eq!((cli.log_format.as_str(), cli.otlp_endpoint), ("text", None));
+        let cli = Cli::try_parse_from(["redis-demo", "cache", "--log-format", "json", "--otlp-endpoint", "http://localhost:4318"]).unwrap();
+        assert_eq!(cli.log_format, "json");
+        assert_eq!(cli.otlp_endpoint.as_deref(), Some("http://localhost:4318"));
+    }
+    
+    #[test]
+    fn test_cli_parsing_keys_gen() {
+        let cli = Cli::try_parse_from(["redis-demo", "keys", "gen", "--template", "order:{seq}:items", "--count", "10000", "--value-size", "256"]).unwrap();
+        match cli.command {
+            Commands::Key { command: KeyCommands::Gen { template, count, value_size, start, ttl_secs, .. } } => {
+                assert_eq!((template.as_str(), count, value_size.as_str(), start, ttl_secs), ("order:{seq}:items", 10000, "256", 0, None));
+            }
+            _ => panic!("Expected keys gen"),
+        }
+        assert!(Cli::try_parse_from(["redis-demo", "key", "gen", "--count", "10"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_commands() {
+        let cli = Cli::try_parse_from(["redis-demo", "bench", "commands", "-t", "set,lpush", "-c", "8", "-n", "10_000", "-P", "16", "-d", "256"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::Commands { tests, clients, requests, pipeline, payload_size, keyspace } } => {
+                assert_eq!((tests.as_str(), clients, requests, pipeline, payload_size, keyspace), ("set,lpush", 8, 10_000, 16, 256, 10_000));
+            }
+            _ => panic!("Expected bench commands"),
+        }
+        let cli = Cli::try_parse_from(["redis-demo", "bench", "commands", "--clients", "4", "--pipeline", "32", "--payload-size", "16"]).unwrap();
+        assert!(matches!(cli.command, Commands::Bench { command: BenchCommands::Commands { clients: 4, pipeline: 32, payload_size: 16, .. } }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_pipeline() {
+        let cli = Cli::try_parse_from(["redis-demo", "pipeline", "--count", "10_000"]).unwrap();
+        assert!(matches!(cli.command, Commands::Pipeline { count: 10_000 }));
+        let cli = Cli::try_parse_from(["redis-demo", "pipeline"]).unwrap();
+        assert!(matches!(cli.command, Commands::Pipeline { count: 1000 }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_bitmaps() {
+        let cli = Cli::try_parse_from(["redis-demo", "bitmaps", "--users", "1_000_000", "--days", "30"]).unwrap();
+        assert!(matches!(cli.command, Commands::Bitmaps { users: 1_000_000, days: 30 }));
+        let cli = Cli::try_parse_from(["redis-demo", "bitmaps"]).unwrap();
+        assert!(matches!(cli.command, Commands::Bitmaps { users: 10_000, days: 7 }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_scripting() {
+        let cli = Cli::try_parse_from(["redis-demo", "scripting"]).unwrap();
+        assert!(matches!(cli.command, Commands::Scripting));
+    }
+    
+    #[test]
+    fn test_cli_parsing_connection_storm() {
+        let cli = Cli::try_parse_from(["redis-demo", "connection-storm", "--callers", "500", "--maxclients", "200"]).unwrap();
+        assert!(matches!(cli.command, Commands::ConnectionStorm { callers: 500, pool_size: 10, maxclients: Some(200) }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_proxy() {
+        let args = ["redis-demo", "proxy", "--listen", "7000", "--target", "localhost:63759", "--latency", "50ms", "--jitter", "20ms", "--drop", "1%"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Proxy { listen, target, latency, jitter, drop } => {
+                assert_eq!((listen.as_str(), target.as_str()), ("7000", "localhost:63759"));
+                assert_eq!((latency, jitter), (std::time::Duration::from_millis(50), std::time::Duration::from_millis(20)));
+                assert_eq!(drop, 0.01);
+            }
+            _ => panic!("Expected proxy"),
+        }
+    }
+    
+    #[test]
+    fn test_cluster_reshard_needs_a_change() {
+        let cli = Cli::try_parse_from(["redis-demo", "cluster", "reshard", "--remove", "10.0.0.1:7000"]).unwrap();
+        match cli.command {
+            Commands::Cluster { command: ClusterCommands::Reshard { add, remove, keys_per_sec, .. } } => {
+                assert_eq!((add, remove.as_deref(), keys_per_sec), (None, Some("10.0.0.1:7000"), 2_000));
+            }
+            _ => panic!("Expected cluster reshard"),
+        }
+        assert!(Cli::try_parse_from(["redis-demo", "cluster", "reshard"]).is_err());
+        assert!(Cli::try_parse_from(["redis-demo", "cluster", "reshard", "--add", "1", "--remove", "node-1"]).is_err());
+    }
+    
+    #[test]
+    fn test_redis_url_is_validated() {
+        let cli = Cli::try_parse_from(["redis-demo", "-r", "redis://custom/2", "ping"]).unwrap();
+        assert_eq!(cli.redis_url, ["redis://custom:6379/2"]);
+        let error = Cli::try_parse_from(["redis-demo", "-r", "redis://custom/99", "ping"]).err().unwrap();
+        assert!(error.to_string().contains("db must be 0-15"), "{}", error);
+    }
+    
     #[test]
     fn test_cli_parsing_basic_strings() {
         let args = vec!["redis-demo", "basic", "strings"];
@@ -75,6 +353,30 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_cli_parsing_basic_sorted_sets() {
+        let args = vec!["redis-demo", "basic", "sorted-sets"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Basic { operation } => {
+                assert!(matches!(operation, BasicOperations::SortedSets));
+            }
+            _ => panic!("Expected Basic command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_basic_geo() {
+        let args = vec!["redis-demo", "basic", "geo"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Basic { operation } => {
+                assert!(matches!(operation, BasicOperations::Geo));
+            }
+            _ => panic!("Expected Basic command"),
+        }
+    }
+    
     #[test]
     fn test_basic_operations_debug() {
         let op = BasicOperations::Strings;
@@ -88,4 +390,826 @@ mod tests {
         let cli = Cli::try_parse_from(args).unwrap();
         assert!(matches!(cli.command, Commands::RustErrors));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_cli_parsing_model_graph() {
+        let args = vec!["redis-demo", "model", "graph", "--pattern", "user:*", "--out", "model.dot"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Model { command: ModelCommands::Graph { pattern, out, format } } => {
+                assert_eq!(pattern, "user:*");
+                assert_eq!(out, Some("model.dot".to_string()));
+                assert!(format.is_none());
+            }
+            _ => panic!("Expected Model command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_compare_cardinality() {
+        let args = vec!["redis-demo", "compare-cardinality", "--n", "1_000_000"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::CompareCardinality { n, false_positive_rate } => {
+                assert_eq!(n, 1_000_000);
+                assert_eq!(false_positive_rate, 0.01);
+            }
+            _ => panic!("Expected CompareCardinality command"),
+        }
+    }
+    
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("1_000").unwrap(), 1000);
+        assert!(parse_count("ten").is_err());
+        assert_eq!(parse_positive_count("1").unwrap(), 1);
+        assert!(parse_positive_count("0").is_err());
+        assert!(Cli::try_parse_from(["redis-demo", "patterns", "voting", "simulate", "--items", "0"]).is_err());
+    }
+    
+    #[test]
+    fn test_parse_percent() {
+        assert_eq!(parse_percent("1%").unwrap(), 0.01);
+        assert_eq!(parse_percent("0.5 %").unwrap(), 0.005);
+        assert!(parse_percent("1").is_err());
+        assert!(parse_percent("150%").is_err());
+    }
+    
+    #[test]
+    fn test_parse_probability() {
+        assert_eq!(parse_probability("0.01").unwrap(), 0.01);
+        for bad in ["0", "1", "-0.5", "1.5", "NaN", "1%"] {
+            assert!(parse_probability(bad).is_err(), "{}", bad);
+        }
+        assert!(Cli::try_parse_from(["redis-demo", "compare-cardinality", "--false-positive-rate", "0"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_inventory() {
+        let args = vec!["redis-demo", "pattern", "inventory", "--buyers", "50"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::Inventory { buyers, stock, hold_ms } } => {
+                assert_eq!(buyers, 50);
+                assert_eq!(stock, 10);
+                assert_eq!(hold_ms, 2000);
+            }
+            _ => panic!("Expected Pattern command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_analytics_stats() {
+        let args = vec!["redis-demo", "analytics", "stats", "--users", "10"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Analytics { command: AnalyticsCommands::Stats { users } } => assert_eq!(users, 10),
+            _ => panic!("Expected Analytics stats command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_analytics_retention() {
+        let args = vec!["redis-demo", "analytics", "retention", "--format", "csv"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Analytics { command: AnalyticsCommands::Retention { users, weeks, format } } => {
+                assert_eq!(users, 5000);
+                assert_eq!(weeks, 6);
+                assert_eq!(format, "csv");
+            }
+            _ => panic!("Expected Analytics retention command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_analytics_funnel() {
+        let args = vec!["redis-demo", "analytics", "funnel", "--from", "2025-01-13"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Analytics { command: AnalyticsCommands::Funnel { users, days, from, to } } => {
+                assert_eq!(users, 5000);
+                assert_eq!(days, 28);
+                assert_eq!(from, NaiveDate::from_ymd_opt(2025, 1, 13));
+                assert_eq!(to, None);
+            }
+            _ => panic!("Expected Analytics funnel command"),
+        }
+        assert!(Cli::try_parse_from(["redis-demo", "analytics", "funnel", "--to", "13/01/2025"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_streams_archive() {
+        let args = vec![
+            "redis-demo", "streams", "archive", "--stream", "events", "--out", "events.ndjson", "--follow",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Streams { command: StreamCommands::Archive { stream, out, follow, group, batch_size, format, .. } } => {
+                assert_eq!(stream, "events");
+                assert_eq!(out, "events.ndjson");
+                assert!(follow);
+                assert!(group.is_none());
+                assert_eq!(batch_size, 500);
+                assert_eq!(format, "ndjson");
+            }
+            _ => panic!("Expected Streams archive command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_streams_replay() {
+        let args = vec![
+            "redis-demo", "streams", "replay", "--stream", "events", "--from", "1700000000000-0",
+            "--speed", "2x", "--to-channel", "replays",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Streams { command: StreamCommands::Replay { from, to, speed, to_channel, to_stream, .. } } => {
+                assert_eq!(from, "1700000000000-0");
+                assert_eq!(to, "+");
+                assert_eq!(speed, 2.0);
+                assert_eq!(to_channel.as_deref(), Some("replays"));
+                assert!(to_stream.is_none());
+            }
+            _ => panic!("Expected Streams replay command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_streams_replay_requires_target() {
+        let args = vec!["redis-demo", "streams", "replay", "--stream", "events"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_streams_lag() {
+        let args = vec!["redis-demo", "streams", "lag", "--stream", "events", "--format", "prometheus"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Streams { command: StreamCommands::Lag { stream, format, config, .. } } => {
+                assert_eq!(stream, "events");
+                assert_eq!(format, "prometheus");
+                assert!(config.is_none());
+            }
+            _ => panic!("Expected Streams lag command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_scheduler_add() {
+        let args = vec!["redis-demo", "scheduler", "add", "--name", "digest", "--cron", "0 8 * * 1-5"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Scheduler { command: SchedulerCommands::Add { name, cron, handler, catch_up } } => {
+                assert_eq!(name, "digest");
+                assert_eq!(cron, "0 8 * * 1-5");
+                assert_eq!(handler, "log");
+                assert_eq!(catch_up, "skip");
+            }
+            _ => panic!("Expected Scheduler add command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_admin_maintenance() {
+        let args = vec!["redis-demo", "admin", "maintenance", "on", "--message", "Upgrading"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Admin { command: AdminCommands::Maintenance { command: MaintenanceCommands::On { message, for_secs } } } => {
+                assert_eq!(message, "Upgrading");
+                assert_eq!(for_secs, None);
+            }
+            _ => panic!("Expected Admin maintenance on command"),
+        }
+        let cli = Cli::try_parse_from(vec!["redis-demo", "admin", "maintenance", "off"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Admin { command: AdminCommands::Maintenance { command: MaintenanceCommands::Off } }
+        ));
+    }
+    
+    #[test]
+    fn test_cli_parsing_config() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "config", "watch", "--strategy", "events"]).unwrap();
+        match cli.command {
+            Commands::Config { command: ConfigCommands::Watch { name, seconds, strategy } } => {
+                assert_eq!(name, "app");
+                assert_eq!(seconds, 60);
+                assert_eq!(strategy, "events");
+            }
+            _ => panic!("Expected Config watch command"),
+        }
+        let args = vec!["redis-demo", "config", "set", "--field", "dark_mode", "--value", "true"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Commands::Config { command: ConfigCommands::Set { .. } }));
+        assert!(Cli::try_parse_from(vec!["redis-demo", "config", "set", "--field", "x"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_experiments() {
+        let args = vec!["redis-demo", "experiments", "simulate", "--users", "500", "--treatment-rate", "0.2"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Experiments { command: ExperimentCommands::Simulate { name, users, control_rate, treatment_rate, .. } } => {
+                assert_eq!(name, "checkout");
+                assert_eq!(users, 500);
+                assert_eq!(control_rate, 0.10);
+                assert_eq!(treatment_rate, 0.2);
+            }
+            _ => panic!("Expected Experiments simulate command"),
+        }
+        let cli = Cli::try_parse_from(vec!["redis-demo", "experiments", "results", "--name", "pricing"]).unwrap();
+        assert!(matches!(cli.command, Commands::Experiments { command: ExperimentCommands::Results { .. } }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_soft_delete() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "pattern", "soft-delete", "--trash-ttl-secs", "60"]).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::SoftDelete { trash_ttl_secs } } => assert_eq!(trash_ttl_secs, 60),
+            _ => panic!("Expected Pattern soft-delete command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_audit_show() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "audit", "show", "user", "42"]).unwrap();
+        match cli.command {
+            Commands::Audit { command: AuditCommands::Show { entity, id, count } } => {
+                assert_eq!(entity, "user");
+                assert_eq!(id, "42");
+                assert_eq!(count, 20);
+            }
+            _ => panic!("Expected Audit show command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_hydration() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "hydration", "--page-size", "20"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::Hydration { users, page_size } } => {
+                assert_eq!(users, 1000);
+                assert_eq!(page_size, 20);
+            }
+            _ => panic!("Expected Bench hydration command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_export_json() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "export", "json", "--out", "dump.json"]).unwrap();
+        match cli.command {
+            Commands::Export { command: ExportCommands::Json { pattern, out, format, batch_size, memory_budget_mb } } => {
+                assert_eq!(pattern, "*");
+                assert_eq!(out, "dump.json");
+                assert_eq!(format, "json");
+                assert_eq!(batch_size, 500);
+                assert_eq!(memory_budget_mb, 64);
+            }
+            _ => panic!("Expected Export json command"),
+        }
+        
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "export"]).unwrap();
+        assert!(matches!(cli.command, Commands::Bench { command: BenchCommands::Export { keys: 1_000_000, memory_budget_mb: 8 } }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_import() {
+        let cli = Cli::try_parse_from(["redis-demo", "import", "dump.csv"]).unwrap();
+        match &cli.command {
+            Commands::Import { file, format, batch_size } => {
+                assert_eq!(file, "dump.csv");
+                assert_eq!(format, "auto");
+                assert_eq!(*batch_size, 500);
+            }
+            _ => panic!("Expected Import command"),
+        }
+        assert!(cli.command.reads_existing_keys());
+    }
+    
+    #[test]
+    fn test_cli_parsing_export_table() {
+        let cli = Cli::try_parse_from(["redis-demo", "export", "table", "leaderboard", "--out", "scores.csv", "--rev"]).unwrap();
+        match cli.command {
+            Commands::Export { command: ExportCommands::Table { key, format, rev, .. } } => {
+                assert_eq!(key, "leaderboard");
+                assert!(format.is_none());
+                assert!(rev);
+            }
+            _ => panic!("Expected Export table command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_load() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "load", "--requests", "1_000", "--compare", "base.hgrm"]).unwrap();
+        match cli.command {
+            Commands::Bench {
+                command: BenchCommands::Load { requests, concurrency, warmup, min_duration, distribution, hgrm, compare, .. },
+            } => {
+                assert_eq!((requests, concurrency, warmup, min_duration), (1000, 50, 1000, 0));
+                assert_eq!(distribution, None);
+                assert_eq!(hgrm, None);
+                assert_eq!(compare.as_deref(), Some("base.hgrm"));
+            }
+            _ => panic!("Expected Bench load command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_load_warmup() {
+        let cli = Cli::try_parse_from(vec![
+            "redis-demo", "bench", "load", "--warmup", "0", "--min-duration", "30", "--workload", "profile.toml",
+            "--distribution", "zipfian:1.1",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::Load { warmup, min_duration, workload, distribution, .. } } => {
+                assert_eq!(distribution.as_deref(), Some("zipfian:1.1"));
+                assert_eq!((warmup, min_duration), (0, 30));
+                assert_eq!(workload.as_deref(), Some("profile.toml"));
+            }
+            _ => panic!("Expected Bench load command"),
+        }
+        assert!(Cli::try_parse_from(["redis-demo", "bench", "load", "--interval-ms", "0"]).is_err());
+        assert_eq!(parse_millis("1_500").unwrap(), 1500);
+    }
+    
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s").unwrap(), std::time::Duration::from_secs(90));
+        assert_eq!(parse_duration("1h30m").unwrap(), std::time::Duration::from_secs(5400));
+        assert_eq!(parse_duration("250ms").unwrap(), std::time::Duration::from_millis(250));
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("1d").is_err());
+        assert!(parse_duration("").is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_strategies() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "strategies", "--with-pool", "--concurrency", "4"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::Strategies { requests, concurrency, workload, with_pool } } => {
+                assert_eq!((requests, concurrency, with_pool), (20_000, 4, true));
+                assert_eq!(workload, None);
+            }
+            _ => panic!("Expected Bench strategies command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_soak() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "soak", "--duration", "2h"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::Soak { duration, rate, sample_every, workload, .. } } => {
+                assert_eq!(duration, std::time::Duration::from_secs(7200));
+                assert_eq!(sample_every, std::time::Duration::from_secs(10));
+                assert_eq!((rate, workload), (1000, None));
+            }
+            _ => panic!("Expected Bench soak command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_adaptive() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "adaptive", "--target-p99-ms", "2.5"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::Adaptive { target_p99_ms, step_ms, max_concurrency, value_size } } => {
+                assert_eq!(target_p99_ms, 2.5);
+                assert_eq!((step_ms, max_concurrency, value_size), (2000, 1024, 64));
+            }
+            _ => panic!("Expected Bench adaptive command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_auto_pipeline() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "auto-pipeline", "--window-us", "50"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::AutoPipeline { tasks, ops, window_us } } => {
+                assert_eq!((tasks, ops, window_us), (200, 200, 50));
+            }
+            _ => panic!("Expected Bench auto-pipeline command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_bytes() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "bytes", "--value-size", "4_096"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::Bytes { value_size, iterations } } => {
+                assert_eq!(value_size, 4096);
+                assert_eq!(iterations, 200);
+            }
+            _ => panic!("Expected Bench bytes command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_field_writes() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "field-writes", "--updates", "3"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::FieldWrites { users, updates } } => {
+                assert_eq!(users, 200);
+                assert_eq!(updates, 3);
+            }
+            _ => panic!("Expected Bench field-writes command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_bench_scan() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "bench", "scan", "--keys", "1_000"]).unwrap();
+        match cli.command {
+            Commands::Bench { command: BenchCommands::Scan { keys, workers } } => {
+                assert_eq!(keys, 1000);
+                assert_eq!(workers, 8);
+            }
+            _ => panic!("Expected Bench scan command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_key_stats_flag() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "ping", "--key-stats"]).unwrap();
+        assert!(cli.key_stats);
+        
+        let cli = Cli::try_parse_from(vec!["redis-demo", "stats", "client-keys", "--top", "5"]).unwrap();
+        assert!(!cli.key_stats);
+        match cli.command {
+            Commands::Stats { command: StatsCommands::ClientKeys { top } } => assert_eq!(top, 5),
+            _ => panic!("Expected Stats client-keys command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_failover() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "failover", "--mode", "crash"]).unwrap();
+        match cli.command {
+            Commands::Failover { replica_url, mode, catch_up_timeout_ms } => {
+                assert_eq!(replica_url, "redis://localhost:6380");
+                assert_eq!(mode, "crash");
+                assert_eq!(catch_up_timeout_ms, 5000);
+            }
+            _ => panic!("Expected Failover command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_replication_lag() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "replication", "lag", "--watch", "--max-lag-bytes", "1024"]).unwrap();
+        match cli.command {
+            Commands::Replication { command: ReplicationCommands::Lag { watch, interval_ms, max_lag_bytes, max_ack_secs, alert_webhook, .. } } => {
+                assert!(watch);
+                assert_eq!(interval_ms, 1000);
+                assert_eq!(max_lag_bytes, Some(1024));
+                assert_eq!(max_ack_secs, None);
+                assert!(alert_webhook.is_none());
+            }
+            _ => panic!("Expected Replication lag command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_lag_alert_webhook() {
+        let args = vec![
+            "redis-demo", "replication", "lag", "--alert-webhook", "https://hooks.slack.com/services/T0/B0/x",
+            "--alert-template", "{subject}: {message}", "--alert-cooldown-secs", "60",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Replication { command: ReplicationCommands::Lag { alert_webhook, alert_template, alert_cooldown_secs, .. } } => {
+                assert_eq!(alert_webhook.as_deref(), Some("https://hooks.slack.com/services/T0/B0/x"));
+                assert_eq!(alert_template.as_deref(), Some("{subject}: {message}"));
+                assert_eq!(alert_cooldown_secs, 60);
+            }
+            _ => panic!("Expected Replication lag command"),
+        }
+        
+        let cli = Cli::try_parse_from(vec!["redis-demo", "streams", "lag", "--stream", "events", "--alert-webhook", "http://localhost:9000/hook"]).unwrap();
+        match cli.command {
+            Commands::Streams { command: StreamCommands::Lag { alert_webhook, alert_cooldown_secs, .. } } => {
+                assert_eq!(alert_webhook.as_deref(), Some("http://localhost:9000/hook"));
+                assert_eq!(alert_cooldown_secs, 300);
+            }
+            _ => panic!("Expected Streams lag command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_advise() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "advise"]).unwrap();
+        assert!(matches!(cli.command, Commands::Advise));
+    }
+    
+    #[test]
+    fn test_cli_parsing_check_consistency() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "check", "consistency", "--repair"]).unwrap();
+        match cli.command {
+            Commands::Check { command: CheckCommands::Consistency { repair, .. } } => assert!(repair),
+            _ => panic!("Expected Check consistency command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_check_validate() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "check", "validate", "--format", "jsonl"]).unwrap();
+        match cli.command {
+            Commands::Check { command: CheckCommands::Validate { entity, format } } => {
+                assert_eq!(entity, "user");
+                assert_eq!(format, "jsonl");
+            }
+            _ => panic!("Expected Check validate command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_maintenance_gc_indexes() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "maintenance", "gc-indexes", "--dry-run"]).unwrap();
+        match cli.command {
+            Commands::Maintenance { command: MaintenanceTasks::GcIndexes { dry_run, batch_size, pause_ms, resume } } => {
+                assert!(dry_run);
+                assert_eq!(batch_size, 500);
+                assert_eq!(pause_ms, 10);
+                assert!(resume.is_none());
+            }
+            _ => panic!("Expected Maintenance gc-indexes command"),
+        }
+        
+        let cli = Cli::try_parse_from(["redis-demo", "maintenance", "gc-indexes", "--resume", "gc-indexes-20261017-00ff"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Maintenance { command: MaintenanceTasks::GcIndexes { resume: Some(ref id), .. } } if id == "gc-indexes-20261017-00ff"
+        ));
+    }
+    
+    #[test]
+    fn test_cli_parsing_pubsub() {
+        let cli = Cli::try_parse_from(["redis-demo", "pubsub", "subscribe", "news", "--pattern", "news.*"]).unwrap();
+        match cli.command {
+            Commands::Pubsub { command: PubSubCommands::Subscribe { channels, pattern, limit } } => {
+                assert_eq!(channels, ["news"]);
+                assert_eq!(pattern, ["news.*"]);
+                assert!(limit.is_none());
+            }
+            _ => panic!("Expected Pubsub subscribe command"),
+        }
+        assert!(Cli::try_parse_from(["redis-demo", "pubsub", "subscribe", "--pattern", "news.*"]).is_ok());
+        assert!(Cli::try_parse_from(["redis-demo", "pubsub", "subscribe"]).is_err());
+        let cli = Cli::try_parse_from(["redis-demo", "pubsub", "publish", "news", "hi there"]).unwrap();
+        assert!(matches!(cli.command, Commands::Pubsub { command: PubSubCommands::Publish { .. } }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_maintenance_rename() {
+        let cli = Cli::try_parse_from(["redis-demo", "maintenance", "rename", "--from", "userv1:*", "--to-prefix", "user:", "--to-db", "3"]).unwrap();
+        match &cli.command {
+            Commands::Maintenance { command: MaintenanceTasks::Rename { from, to_db, on_collision, dry_run, .. } } => {
+                assert_eq!(from, "userv1:*");
+                assert_eq!(*to_db, Some(3));
+                assert_eq!(on_collision, "skip");
+                assert!(!dry_run);
+            }
+            _ => panic!("Expected Maintenance rename command"),
+        }
+        assert!(cli.command.reads_existing_keys());
+        assert!(Cli::try_parse_from(["redis-demo", "maintenance", "rename", "--from", "userv1:*"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_metrics_rollup() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "metrics", "rollup", "--days", "7"]).unwrap();
+        match cli.command {
+            Commands::Metrics { command: MetricsCommands::Rollup { days, retention_hours } } => {
+                assert_eq!(days, 7);
+                assert_eq!(retention_hours, 6);
+            }
+            _ => panic!("Expected Metrics rollup command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_quotas() {
+        let args = vec!["redis-demo", "quotas", "set", "--key", "acme", "--limit", "1000"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Quotas { command: QuotaCommands::Set { key, limit, soft_ratio, per_second } } => {
+                assert_eq!(key, "acme");
+                assert_eq!(limit, 1000);
+                assert_eq!(soft_ratio, 0.8);
+                assert_eq!(per_second, 0);
+            }
+            _ => panic!("Expected Quotas set command"),
+        }
+        let args = vec!["redis-demo", "quotas", "reset", "--key", "acme", "--period", "2025-03"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Commands::Quotas { command: QuotaCommands::Reset { period: Some(_), .. } }));
+        let cli = Cli::try_parse_from(vec!["redis-demo", "quotas", "namespaces"]).unwrap();
+        assert!(matches!(cli.command, Commands::Quotas { command: QuotaCommands::Namespaces { rounds: 8 } }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_scheduler_list_and_remove() {
+        let cli = Cli::try_parse_from(vec!["redis-demo", "scheduler", "list"]).unwrap();
+        assert!(matches!(cli.command, Commands::Scheduler { command: SchedulerCommands::List }));
+        let cli = Cli::try_parse_from(vec!["redis-demo", "scheduler", "remove", "--name", "digest"]).unwrap();
+        assert!(matches!(cli.command, Commands::Scheduler { command: SchedulerCommands::Remove { .. } }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_workflow_advance() {
+        let args = vec!["redis-demo", "workflow", "advance", "--id", "1001", "--to", "paid"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Workflow { command: WorkflowCommands::Advance { id, to, reason }, payment_timeout_secs } => {
+                assert_eq!(id, "1001");
+                assert_eq!(to, "paid");
+                assert_eq!(reason, "manual");
+                assert_eq!(payment_timeout_secs, 900);
+            }
+            _ => panic!("Expected Workflow advance command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_cluster_pitfalls() {
+        let args = vec![
+            "redis-demo", "cluster-pitfalls", "--cluster-node", "redis://a:7000", "--cluster-node", "redis://b:7001",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::ClusterPitfalls { cluster_nodes } => assert_eq!(cluster_nodes.len(), 2),
+            _ => panic!("Expected ClusterPitfalls command"),
+        }
+    }
+    
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(parse_speed("2x").unwrap(), 2.0);
+        assert_eq!(parse_speed("0.5").unwrap(), 0.5);
+        assert_eq!(parse_speed("max").unwrap(), f64::INFINITY);
+        assert!(parse_speed("0x").is_err());
+        assert!(parse_speed("fast").is_err());
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_backpressure() {
+        let args = vec!["redis-demo", "pattern", "backpressure", "--max-len", "5", "--reject"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::Backpressure { jobs, max_len, reject } } => {
+                assert_eq!(jobs, 500);
+                assert_eq!(max_len, 5);
+                assert!(reject);
+            }
+            _ => panic!("Expected Pattern backpressure command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_priority_aging() {
+        let args = vec!["redis-demo", "pattern", "priority-aging", "--boost", "0.5"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::PriorityAging { low_jobs, rounds, boost } } => {
+                assert_eq!(low_jobs, 3);
+                assert_eq!(rounds, 40);
+                assert_eq!(boost, 0.5);
+            }
+            _ => panic!("Expected Pattern priority-aging command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_coupons() {
+        let args = vec!["redis-demo", "pattern", "coupons", "--max-uses", "3"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::Coupons { batch, max_uses, contenders } } => {
+                assert_eq!(batch, 100);
+                assert_eq!(max_uses, 3);
+                assert_eq!(contenders, 50);
+            }
+            _ => panic!("Expected Pattern coupons command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_lock() {
+        let args = vec!["redis-demo", "pattern", "lock", "--ttl-ms", "1000"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::Lock { workers, rounds, ttl_ms } } => {
+                assert_eq!(workers, 2);
+                assert_eq!(rounds, 5);
+                assert_eq!(ttl_ms, 1000);
+            }
+            _ => panic!("Expected Pattern lock command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_crdt() {
+        let args = vec!["redis-demo", "pattern", "crdt", "--actors", "8"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::Crdt { actors, writes, seed } } => {
+                assert_eq!(actors, 8);
+                assert_eq!(writes, 50);
+                assert_eq!(seed, 42);
+            }
+            _ => panic!("Expected Pattern crdt command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_waitlist() {
+        let args = vec!["redis-demo", "pattern", "waitlist", "--batch", "10"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::Waitlist { users, batch } } => {
+                assert_eq!(users, 30);
+                assert_eq!(batch, 10);
+            }
+            _ => panic!("Expected Pattern waitlist command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_feed() {
+        let args = vec!["redis-demo", "pattern", "feed", "--posts", "20"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::Feed { users, posts } } => {
+                assert_eq!(users, 50);
+                assert_eq!(posts, 20);
+            }
+            _ => panic!("Expected Pattern feed command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_graph() {
+        let args = vec!["redis-demo", "pattern", "graph", "--depth", "3"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::Graph { users, depth } } => {
+                assert_eq!(users, 30);
+                assert_eq!(depth, 3);
+            }
+            _ => panic!("Expected Pattern graph command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_pattern_voting_simulate() {
+        let args = vec!["redis-demo", "patterns", "voting", "simulate", "--burst", "30"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Pattern { pattern: PatternCommands::Voting { command: VotingCommands::Simulate { users, items, burst } } } => {
+                assert_eq!(users, 50);
+                assert_eq!(items, 5);
+                assert_eq!(burst, 30);
+            }
+            _ => panic!("Expected Pattern voting command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parsing_patterns_alias() {
+        let args = vec!["redis-demo", "patterns", "inventory"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Commands::Pattern { .. }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_mirror() {
+        let cli = Cli::try_parse_from(["redis-demo", "mirror", "sqlite", "--pattern", "user:*", "--db", "out.sqlite"]).unwrap();
+        assert!(cli.command.reads_existing_keys());
+        match cli.command {
+            Commands::Mirror { command: MirrorCommands::Sqlite { pattern, db, batch_size } } => {
+                assert_eq!((pattern.as_str(), db.as_str(), batch_size), ("user:*", "out.sqlite", 500));
+            }
+            _ => panic!("Expected Mirror sqlite command"),
+        }
+        let cli = Cli::try_parse_from(["redis-demo", "mirror", "sync-back", "--dry-run"]).unwrap();
+        assert!(matches!(cli.command, Commands::Mirror { command: MirrorCommands::SyncBack { dry_run: true, .. } }));
+    }
+    
+    #[test]
+    fn test_cli_parsing_webhook_watch() {
+        let args = ["redis-demo", "webhook", "watch", "--url", "http://localhost:9000/hook", "--event", "job-failed", "--event", "session-expired", "--max-lag-bytes", "1024"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Webhook { command: WebhookCommands::Watch { url, secret, events, sessions, max_lag_bytes, interval_ms, .. } } => {
+                assert_eq!(url, "http://localhost:9000/hook");
+                assert_eq!(secret, None);
+                assert_eq!(events, ["job-failed", "session-expired"]);
+                assert_eq!((sessions.as_str(), max_lag_bytes, interval_ms), ("session:*", Some(1024), 1000));
+            }
+            _ => panic!("Expected Webhook watch command"),
+        }
+        assert!(Cli::try_parse_from(["redis-demo", "webhook", "watch", "--url", "http://localhost:9000/hook", "--interval-ms", "0"]).is_err());
+    }
+}
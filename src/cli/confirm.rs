@@ -0,0 +1,76 @@
+use crate::{DemoError, Result};
+use std::io::{BufRead, IsTerminal, Write};
+
+/// The global `--yes` and `--allow-db0` flags.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ConfirmOptions {
+    pub yes: bool,
+    pub allow_db0: bool,
+}
+
+/// Asks on the terminal before a destructive `action` against database
+/// `db`. Database 0, where real data usually lives, is refused outright
+/// unless `--allow-db0` is set; `--yes` skips the question but not that
+/// check. Without a terminal to ask on, `--yes` is required.
+pub fn confirm(action: &str, db: i64, options: ConfirmOptions) -> Result<bool> {
+    if !options.yes && !std::io::stdin().is_terminal() {
+        check_db(action, db, options)?;
+        return Err(DemoError::Configuration(format!("Not a terminal: pass --yes to {}", action)));
+    }
+    confirm_with(action, db, options, &mut std::io::stdin().lock(), &mut std::io::stderr())
+}
+
+fn check_db(action: &str, db: i64, options: ConfirmOptions) -> Result<()> {
+    if db == 0 && !options.allow_db0 {
+        return Err(DemoError::Configuration(format!(
+            "Refusing to {} on database 0; pass --allow-db0 if you mean it",
+            action
+        )));
+    }
+    Ok(())
+}
+
+/// [`confirm`] reading the answer from `input` and asking on `output`.
+pub fn confirm_with(
+    action: &str,
+    db: i64,
+    options: ConfirmOptions,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<bool> {
+    check_db(action, db, options)?;
+    if options.yes {
+        return Ok(true);
+    }
+    write!(output, "About to {} on database {}. Continue? [y/N] ", action, db)?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ask(answer: &str, db: i64, options: ConfirmOptions) -> Result<bool> {
+        let mut output = Vec::new();
+        confirm_with("delete orphans", db, options, &mut answer.as_bytes(), &mut output)
+    }
+
+    #[test]
+    fn test_answers() {
+        assert!(ask("y\n", 15, ConfirmOptions::default()).unwrap());
+        assert!(ask("YES\n", 15, ConfirmOptions::default()).unwrap());
+        assert!(!ask("\n", 15, ConfirmOptions::default()).unwrap());
+        assert!(!ask("", 15, ConfirmOptions::default()).unwrap());
+        assert!(ask("", 15, ConfirmOptions { yes: true, allow_db0: false }).unwrap());
+    }
+
+    #[test]
+    fn test_db0_needs_explicit_permission() {
+        let yes = ConfirmOptions { yes: true, allow_db0: false };
+        assert!(ask("y\n", 0, yes).unwrap_err().to_string().contains("--allow-db0"));
+        assert!(ask("y\n", 0, ConfirmOptions { allow_db0: true, ..yes }).unwrap());
+    }
+}
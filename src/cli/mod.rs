@@ -1,3 +1,5 @@
 pub mod commands;
+pub mod confirm;
 
-pub use commands::{Cli, Commands, BasicOperations};
\ No newline at end of file
+pub use commands::{Cli, Commands, AdminCommands, AnalyticsCommands, AuditCommands, BasicOperations, BenchCommands, CheckCommands, ClusterCommands, ConfigCommands, ExperimentCommands, ExportCommands, InspectCommands, JobCommands, KeyChange, KeyCommands, MaintenanceCommands, MaintenanceTasks, MetricsCommands, MirrorCommands, ModelCommands, MonitorCommands, PatternCommands, PubSubCommands, QuotaCommands, ReplicationCommands, SchedulerCommands, StatsCommands, StreamCommands, UserCommands, VotingCommands, WebhookCommands, WorkflowCommands};
+pub use confirm::{confirm, ConfirmOptions};
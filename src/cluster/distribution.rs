@@ -0,0 +1,124 @@
+use super::slots::SlotMap;
+use crate::utils::cluster::{hash_tag, key_slot};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const BAR_WIDTH: usize = 30;
+
+/// `count` keys from `template`, with `#` replaced by 0, 1, 2, ...
+pub fn seeded_keys(template: &str, count: usize) -> Vec<String> {
+    (0..count).map(|i| template.replace('#', &i.to_string())).collect()
+}
+
+/// Where a set of keys lands: per node, and how concentrated by slot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    /// Every master of the map with its key count, busiest first.
+    pub per_node: Vec<(String, usize)>,
+    pub total: usize,
+    pub distinct_slots: usize,
+    /// The slot holding the most keys, and how many.
+    pub hottest_slot: Option<(u16, usize)>,
+    /// Keys in slots no master serves.
+    pub unserved: usize,
+}
+
+impl Distribution {
+    pub fn of<S: AsRef<str>>(keys: &[S], map: &SlotMap) -> Self {
+        let mut slots: HashMap<u16, usize> = HashMap::new();
+        for key in keys {
+            *slots.entry(key_slot(key.as_ref())).or_default() += 1;
+        }
+        let mut per_node: Vec<(String, usize)> = map.nodes().iter().map(|(node, _)| (node.to_string(), 0)).collect();
+        let mut unserved = 0;
+        for (slot, count) in &slots {
+            match map.owner(*slot).and_then(|owner| per_node.iter_mut().find(|(node, _)| node == owner)) {
+                Some((_, total)) => *total += count,
+                None => unserved += count,
+            }
+        }
+        per_node.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let distinct_slots = slots.len();
+        let hottest_slot = slots.into_iter().max_by_key(|(slot, count)| (*count, std::cmp::Reverse(*slot)));
+        Self { per_node, total: keys.len(), distinct_slots, hottest_slot, unserved }
+    }
+
+    /// Keys on the busiest node over what it would hold with a perfectly
+    /// even spread: 1.0 is ideal, N means one node does all the work.
+    pub fn imbalance(&self) -> f64 {
+        let busiest = self.per_node.first().map_or(0, |(_, count)| *count);
+        match self.total {
+            0 => 1.0,
+            total => busiest as f64 * self.per_node.len() as f64 / total as f64,
+        }
+    }
+
+    /// A bar per node scaled to the busiest, then the concentration figures.
+    pub fn render(&self) -> String {
+        let busiest = self.per_node.first().map_or(0, |(_, count)| *count).max(1);
+        let width = self.per_node.iter().map(|(node, _)| node.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for (node, count) in &self.per_node {
+            let bar = "█".repeat(((*count as f64 / busiest as f64) * BAR_WIDTH as f64).ceil() as usize);
+            let share = match self.total {
+                0 => 0.0,
+                total => *count as f64 * 100.0 / total as f64,
+            };
+            let _ = writeln!(out, "{:<width$}  {:<bw$}  {:>7} keys ({:>5.1}%)", node, bar, count, share, width = width, bw = BAR_WIDTH);
+        }
+        if self.unserved > 0 {
+            let _ = writeln!(out, "⚠️  {} keys hash to slots no master serves", self.unserved);
+        }
+        let _ = write!(out, "{} keys in {} distinct slots", self.total, self.distinct_slots);
+        if let Some((slot, count)) = self.hottest_slot {
+            let _ = write!(out, "; hottest slot {} holds {}", slot, count);
+        }
+        let _ = writeln!(out, "; busiest node at {:.2}x an even share", self.imbalance());
+        out
+    }
+}
+
+/// One line per key: its slot, what was hashed, and who owns the slot.
+pub fn describe_key(key: &str, map: &SlotMap) -> String {
+    let slot = key_slot(key);
+    let hashed = match hash_tag(key) {
+        tag if tag.len() == key.len() => "whole key hashed".to_string(),
+        tag => format!("hash tag \"{}\"", tag),
+    };
+    let owner = map.owner(slot).unwrap_or("no master");
+    format!("{}  →  slot {:>5}  ({}, on {})", key, slot, hashed, owner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_keys() {
+        assert_eq!(seeded_keys("{user:#}:cart", 2), ["{user:0}:cart", "{user:1}:cart"]);
+    }
+
+    #[test]
+    fn test_plain_keys_spread_and_a_global_tag_does_not() {
+        let map = SlotMap::even(3).unwrap();
+        let spread = Distribution::of(&seeded_keys("user:#:profile", 3000), &map);
+        assert_eq!(spread.per_node.len(), 3);
+        assert!(spread.imbalance() < 1.1, "{}", spread.render());
+        assert!(spread.distinct_slots > 2500);
+
+        let hotspot = Distribution::of(&seeded_keys("{users}:#", 3000), &map);
+        assert_eq!(hotspot.distinct_slots, 1);
+        assert_eq!(hotspot.per_node[0].1, 3000);
+        assert_eq!(hotspot.per_node[2].1, 0);
+        assert_eq!(hotspot.imbalance(), 3.0);
+        assert_eq!(hotspot.hottest_slot, Some((key_slot("users"), 3000)));
+        assert!(hotspot.render().ends_with("busiest node at 3.00x an even share\n"));
+    }
+
+    #[test]
+    fn test_describe_key() {
+        let map = SlotMap::even(3).unwrap();
+        assert_eq!(describe_key("foo", &map), "foo  →  slot 12182  (whole key hashed, on node-3)");
+        assert!(describe_key("{user:1}:cart", &map).contains("hash tag \"user:1\""));
+    }
+}
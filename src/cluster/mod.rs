@@ -0,0 +1,7 @@
+pub mod distribution;
+pub mod reshard;
+pub mod slots;
+
+pub use distribution::{describe_key, seeded_keys, Distribution};
+pub use reshard::{Change, KeyEstimate, ReshardPlan, SlotMove};
+pub use slots::{SlotMap, SlotRange};
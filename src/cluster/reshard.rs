@@ -0,0 +1,243 @@
+use super::slots::SlotMap;
+use crate::utils::cluster::{key_slot, SLOT_COUNT};
+use crate::utils::error::{DemoError, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::time::Duration;
+
+/// A proposed topology change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// Add this many empty masters, named `new-1`, `new-2`, ...
+    Add(usize),
+    /// Drain a master so it can be removed.
+    Remove(String),
+}
+
+/// Slots that would go from one master to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotMove {
+    pub from: String,
+    pub to: String,
+    pub slots: Vec<u16>,
+}
+
+/// What a rebalance after `change` would move, without moving anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReshardPlan {
+    pub change: Change,
+    pub moves: Vec<SlotMove>,
+    /// Masters after the change.
+    pub masters: usize,
+}
+
+impl ReshardPlan {
+    /// Moves the fewest slots that leave every master with an even share,
+    /// as `redis-cli --cluster rebalance` aims to: masters over their share
+    /// give away their highest slots to masters under it.
+    pub fn new(map: &SlotMap, change: Change) -> Result<Self> {
+        let current: Vec<String> = map.nodes().iter().map(|(node, _)| node.to_string()).collect();
+        let mut after = current.clone();
+        match &change {
+            Change::Add(0) => return Err(DemoError::Configuration("Add at least one master".to_string())),
+            Change::Add(count) => after.extend((1..=*count).map(|i| format!("new-{}", i))),
+            Change::Remove(node) => {
+                if !current.contains(node) {
+                    return Err(DemoError::Configuration(format!("{} is not a master; masters are {}", node, current.join(", "))));
+                }
+                if current.len() == 1 {
+                    return Err(DemoError::Configuration("Can't remove the only master".to_string()));
+                }
+                after.retain(|master| master != node);
+            }
+        }
+
+        let total = usize::from(SLOT_COUNT);
+        let share = |i: usize| total / after.len() + usize::from(i < total % after.len());
+        let mut surplus: Vec<(String, Vec<u16>)> = Vec::new();
+        let mut deficit: Vec<(String, usize)> = Vec::new();
+        for (node, ranges) in map.nodes() {
+            let mut slots: Vec<u16> = ranges.iter().flat_map(|range| range.start..=range.end).collect();
+            slots.sort_unstable();
+            let target = after.iter().position(|master| master == node).map_or(0, share);
+            match slots.len().checked_sub(target) {
+                Some(0) => {}
+                Some(extra) => surplus.push((node.to_string(), slots.split_off(slots.len() - extra))),
+                None => deficit.push((node.to_string(), target - slots.len())),
+            }
+        }
+        for (i, master) in after.iter().enumerate().filter(|(_, master)| !current.contains(master)) {
+            deficit.push((master.clone(), share(i)));
+        }
+
+        let mut moves = Vec::new();
+        let mut recipients = deficit.into_iter().filter(|(_, needed)| *needed > 0);
+        let mut recipient = recipients.next();
+        for (from, mut slots) in surplus {
+            while let Some((to, needed)) = recipient.as_mut() {
+                if slots.is_empty() {
+                    break;
+                }
+                let taken: Vec<u16> = slots.drain(..(*needed).min(slots.len())).collect();
+                *needed -= taken.len();
+                moves.push(SlotMove { from: from.clone(), to: to.clone(), slots: taken });
+                if *needed == 0 {
+                    recipient = recipients.next();
+                }
+            }
+        }
+        Ok(Self { change, moves, masters: after.len() })
+    }
+
+    pub fn moved_slots(&self) -> Vec<u16> {
+        self.moves.iter().flat_map(|moved| moved.slots.iter().copied()).collect()
+    }
+}
+
+/// Keys expected to move, extrapolated from a sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyEstimate {
+    pub keys: f64,
+    /// What the sample was: slots counted with CLUSTER COUNTKEYSINSLOT, or
+    /// keys hashed locally.
+    pub sampled: usize,
+    pub method: &'static str,
+}
+
+impl KeyEstimate {
+    /// From `CLUSTER COUNTKEYSINSLOT` on a sample of the moving slots.
+    pub fn from_slot_counts(counts: &[u64], moving_slots: usize) -> Self {
+        let mean = match counts.len() {
+            0 => 0.0,
+            n => counts.iter().sum::<u64>() as f64 / n as f64,
+        };
+        Self { keys: mean * moving_slots as f64, sampled: counts.len(), method: "slots counted with CLUSTER COUNTKEYSINSLOT" }
+    }
+
+    /// From sampled key names, for a server that isn't a cluster: the share
+    /// hashing into moving slots, scaled to the database size.
+    pub fn from_key_sample<S: AsRef<str>>(sample: &[S], moving: &HashSet<u16>, db_size: u64) -> Self {
+        let hits = sample.iter().filter(|key| moving.contains(&key_slot(key.as_ref()))).count();
+        let keys = match sample.len() {
+            0 => 0.0,
+            n => hits as f64 / n as f64 * db_size as f64,
+        };
+        Self { keys, sampled: sample.len(), method: "keys sampled and hashed locally" }
+    }
+
+    pub fn migration_time(&self, keys_per_sec: u64) -> Duration {
+        Duration::from_secs_f64(self.keys / keys_per_sec.max(1) as f64)
+    }
+}
+
+/// Which `count` of `slots` to sample, spread evenly rather than at random
+/// so reruns agree.
+pub fn sample_slots(slots: &[u16], count: usize) -> Vec<u16> {
+    match slots.len() {
+        0 => Vec::new(),
+        n if n <= count => slots.to_vec(),
+        n => (0..count).map(|i| slots[i * n / count]).collect(),
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{:.1}s", duration.as_secs_f64()),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+pub fn render(plan: &ReshardPlan, estimate: &KeyEstimate, keys_per_sec: u64) -> String {
+    let moved = plan.moved_slots().len();
+    let mut out = String::new();
+    let mut totals: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for moved in &plan.moves {
+        *totals.entry((&moved.from, &moved.to)).or_default() += moved.slots.len();
+    }
+    for ((from, to), slots) in &totals {
+        let keys = match moved {
+            0 => 0.0,
+            moved => estimate.keys * *slots as f64 / moved as f64,
+        };
+        let _ = writeln!(out, "   {} → {}  {:>5} slots  ~{:.0} keys", from, to, slots, keys);
+    }
+    let _ = writeln!(
+        out,
+        "\nSlots moving: {} of {} ({:.1}%), leaving {} masters",
+        moved,
+        SLOT_COUNT,
+        moved as f64 * 100.0 / f64::from(SLOT_COUNT),
+        plan.masters
+    );
+    let _ = writeln!(out, "Keys moving:  ~{:.0} (from {} {})", estimate.keys, estimate.sampled, estimate.method);
+    let _ = writeln!(
+        out,
+        "Migration:    ~{} at {} keys/s",
+        format_duration(estimate.migration_time(keys_per_sec)),
+        keys_per_sec
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adding_a_master_takes_an_even_share_from_each() {
+        let map = SlotMap::even(3).unwrap();
+        let plan = ReshardPlan::new(&map, Change::Add(1)).unwrap();
+        assert_eq!(plan.masters, 4);
+        assert_eq!(plan.moved_slots().len(), 4096);
+        assert!(plan.moves.iter().all(|moved| moved.to == "new-1"));
+        let given: Vec<_> = plan.moves.iter().map(|moved| (moved.from.as_str(), moved.slots.len())).collect();
+        assert_eq!(given, [("node-1", 1365), ("node-2", 1366), ("node-3", 1365)]);
+        // The highest slots of each range go.
+        assert_eq!(plan.moves[0].slots.last(), Some(&5460));
+    }
+
+    #[test]
+    fn test_removing_a_master_drains_it() {
+        let map = SlotMap::even(4).unwrap();
+        let plan = ReshardPlan::new(&map, Change::Remove("node-2".to_string())).unwrap();
+        assert_eq!(plan.moved_slots().len(), 4096);
+        assert!(plan.moves.iter().all(|moved| moved.from == "node-2"));
+        let received: Vec<_> = plan.moves.iter().map(|moved| (moved.to.as_str(), moved.slots.len())).collect();
+        assert_eq!(received, [("node-1", 1366), ("node-3", 1365), ("node-4", 1365)]);
+        assert!(ReshardPlan::new(&map, Change::Remove("node-9".to_string())).is_err());
+        assert!(ReshardPlan::new(&SlotMap::even(1).unwrap(), Change::Remove("node-1".to_string())).is_err());
+        assert!(ReshardPlan::new(&map, Change::Add(0)).is_err());
+    }
+
+    #[test]
+    fn test_estimates() {
+        let estimate = KeyEstimate::from_slot_counts(&[10, 30], 4096);
+        assert_eq!(estimate.keys, 81920.0);
+        assert_eq!(estimate.migration_time(8192), Duration::from_secs(10));
+
+        let map = SlotMap::even(3).unwrap();
+        let plan = ReshardPlan::new(&map, Change::Add(1)).unwrap();
+        let moving: HashSet<u16> = plan.moved_slots().into_iter().collect();
+        let sample = crate::cluster::seeded_keys("user:#", 4000);
+        let estimate = KeyEstimate::from_key_sample(&sample, &moving, 1_000_000);
+        assert!((200_000.0..300_000.0).contains(&estimate.keys), "{:?}", estimate);
+    }
+
+    #[test]
+    fn test_sample_slots_spreads_evenly() {
+        let slots: Vec<u16> = (0..100).collect();
+        assert_eq!(sample_slots(&slots, 4), [0, 25, 50, 75]);
+        assert_eq!(sample_slots(&slots[..3], 4), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_render() {
+        let plan = ReshardPlan::new(&SlotMap::even(3).unwrap(), Change::Add(1)).unwrap();
+        let rendered = render(&plan, &KeyEstimate::from_slot_counts(&[2], 4096), 1000);
+        assert!(rendered.contains("   node-2 → new-1   1366 slots  ~2732 keys"), "{}", rendered);
+        assert!(rendered.contains("Slots moving: 4096 of 16384 (25.0%), leaving 4 masters"));
+        assert!(rendered.contains("Migration:    ~8.2s at 1000 keys/s"));
+    }
+}
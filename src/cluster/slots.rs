@@ -0,0 +1,221 @@
+use crate::utils::cluster::SLOT_COUNT;
+use crate::utils::error::{DemoError, Result};
+use crate::utils::RedisConnection;
+use redis::Value;
+use std::fmt::Write;
+
+const BAR_WIDTH: usize = 30;
+
+/// A contiguous run of slots and the master serving it, as one entry of
+/// `CLUSTER SLOTS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotRange {
+    pub start: u16,
+    pub end: u16,
+    pub node: String,
+    pub replicas: Vec<String>,
+}
+
+impl SlotRange {
+    pub fn slots(&self) -> u32 {
+        u32::from(self.end - self.start) + 1
+    }
+
+    pub fn contains(&self, slot: u16) -> bool {
+        (self.start..=self.end).contains(&slot)
+    }
+}
+
+/// Which node owns which slots, either read from a live cluster or laid out
+/// the way `redis-cli --cluster create` splits slots between fresh masters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotMap {
+    pub ranges: Vec<SlotRange>,
+    /// False for a map made up by [`SlotMap::even`].
+    pub live: bool,
+}
+
+impl SlotMap {
+    /// `nodes` masters sharing the slots in equal contiguous ranges, named
+    /// `node-1`, `node-2`, ...
+    pub fn even(nodes: usize) -> Result<Self> {
+        let total = usize::from(SLOT_COUNT);
+        if !(1..=total).contains(&nodes) {
+            return Err(DemoError::Configuration(format!("A cluster needs 1-{} masters, not {}", total, nodes)));
+        }
+        // Same rounding as redis-cli, so the ranges match a real new cluster.
+        let per_node = total as f64 / nodes as f64;
+        let mut ranges = Vec::with_capacity(nodes);
+        let mut start = 0;
+        for i in 0..nodes {
+            let end = match i + 1 == nodes {
+                true => total - 1,
+                false => ((i as f64 * per_node + per_node - 1.0).round() as usize).max(start),
+            };
+            ranges.push(SlotRange { start: start as u16, end: end as u16, node: format!("node-{}", i + 1), replicas: Vec::new() });
+            start = end + 1;
+        }
+        Ok(Self { ranges, live: false })
+    }
+
+    /// Parses a `CLUSTER SLOTS` reply: `[start, end, [ip, port, id, ...],
+    /// replica...]` per range.
+    pub fn from_reply(reply: &Value) -> Result<Self> {
+        let bad = |what: &str| DemoError::Demo(format!("Unexpected CLUSTER SLOTS reply: {}", what));
+        let Value::Bulk(entries) = reply else {
+            return Err(bad("not an array"));
+        };
+        let mut ranges = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let Value::Bulk(fields) = entry else {
+                return Err(bad("range is not an array"));
+            };
+            let slot = |value: Option<&Value>| match value {
+                Some(Value::Int(n)) if (0..i64::from(SLOT_COUNT)).contains(n) => Ok(*n as u16),
+                _ => Err(bad("slot is not a number in range")),
+            };
+            let (start, end) = (slot(fields.first())?, slot(fields.get(1))?);
+            if start > end {
+                return Err(bad("range ends before it starts"));
+            }
+            let mut nodes = fields.iter().skip(2).map(|node| endpoint(node).ok_or_else(|| bad("node without host and port")));
+            let node = nodes.next().ok_or_else(|| bad("range without a master"))??;
+            ranges.push(SlotRange { start, end, node, replicas: nodes.collect::<Result<_>>()? });
+        }
+        ranges.sort_by_key(|range| range.start);
+        Ok(Self { ranges, live: true })
+    }
+
+    /// The live map, or `None` when the server isn't running in cluster
+    /// mode.
+    pub async fn fetch(conn: &mut RedisConnection) -> Result<Option<Self>> {
+        match redis::cmd("CLUSTER").arg("SLOTS").query_async::<_, Value>(conn).await {
+            Ok(reply) => Self::from_reply(&reply).map(Some),
+            // "ERR This instance has cluster support disabled"
+            Err(e) if e.kind() == redis::ErrorKind::ResponseError => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn owner(&self, slot: u16) -> Option<&str> {
+        self.ranges.iter().find(|range| range.contains(slot)).map(|range| range.node.as_str())
+    }
+
+    /// Each master with its ranges, in order of first slot.
+    pub fn nodes(&self) -> Vec<(&str, Vec<&SlotRange>)> {
+        let mut nodes: Vec<(&str, Vec<&SlotRange>)> = Vec::new();
+        for range in &self.ranges {
+            match nodes.iter_mut().find(|(node, _)| *node == range.node) {
+                Some((_, ranges)) => ranges.push(range),
+                None => nodes.push((&range.node, vec![range])),
+            }
+        }
+        nodes
+    }
+
+    /// Slots no master serves; any key hashing there fails with CLUSTERDOWN.
+    pub fn uncovered(&self) -> u32 {
+        u32::from(SLOT_COUNT).saturating_sub(self.ranges.iter().map(SlotRange::slots).sum())
+    }
+
+    /// One line per master: its ranges, slot count and share as a bar.
+    pub fn render(&self) -> String {
+        let nodes = self.nodes();
+        let width = nodes.iter().map(|(node, _)| node.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for (node, ranges) in &nodes {
+            let slots: u32 = ranges.iter().map(|range| range.slots()).sum();
+            let share = f64::from(slots) / f64::from(SLOT_COUNT);
+            let spans: Vec<_> = ranges.iter().map(|range| format!("{}-{}", range.start, range.end)).collect();
+            let bar = "█".repeat((share * BAR_WIDTH as f64).round() as usize);
+            let _ = writeln!(
+                out,
+                "{:<width$}  {:<bw$}  {:>5} slots ({:>5.1}%)  {}",
+                node,
+                bar,
+                slots,
+                share * 100.0,
+                spans.join(", "),
+                width = width,
+                bw = BAR_WIDTH
+            );
+            let replicas: Vec<_> = ranges.iter().flat_map(|range| &range.replicas).collect();
+            if !replicas.is_empty() {
+                let _ = writeln!(out, "{:<width$}  replicas: {}", "", replicas.iter().map(|r| r.as_str()).collect::<Vec<_>>().join(", "), width = width);
+            }
+        }
+        if self.uncovered() > 0 {
+            let _ = writeln!(out, "⚠️  {} slots have no master: keys hashing there fail with CLUSTERDOWN", self.uncovered());
+        }
+        out
+    }
+}
+
+/// `host:port` from a `[ip, port, id, ...]` node entry.
+fn endpoint(node: &Value) -> Option<String> {
+    let Value::Bulk(fields) = node else {
+        return None;
+    };
+    let host = match fields.first()? {
+        Value::Data(host) => String::from_utf8_lossy(host).into_owned(),
+        Value::Status(host) => host.clone(),
+        _ => return None,
+    };
+    match fields.get(1)? {
+        Value::Int(port) => Some(format!("{}:{}", host, port)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(host: &str, port: i64) -> Value {
+        Value::Bulk(vec![Value::Data(host.as_bytes().to_vec()), Value::Int(port), Value::Data(b"id".to_vec())])
+    }
+
+    #[test]
+    fn test_even_split_matches_redis_cli() {
+        let map = SlotMap::even(3).unwrap();
+        let spans: Vec<_> = map.ranges.iter().map(|range| (range.start, range.end)).collect();
+        assert_eq!(spans, [(0, 5460), (5461, 10922), (10923, 16383)]);
+        assert_eq!(map.uncovered(), 0);
+        assert_eq!(map.owner(12182), Some("node-3"));
+        assert!(SlotMap::even(0).is_err());
+    }
+
+    #[test]
+    fn test_parse_cluster_slots() {
+        let reply = Value::Bulk(vec![
+            Value::Bulk(vec![Value::Int(8192), Value::Int(16383), node("10.0.0.2", 7001), node("10.0.0.4", 7003)]),
+            Value::Bulk(vec![Value::Int(0), Value::Int(8191), node("10.0.0.1", 7000)]),
+            Value::Bulk(vec![Value::Int(100), Value::Int(100), node("10.0.0.2", 7001)]),
+        ]);
+        let map = SlotMap::from_reply(&reply).unwrap();
+        assert!(map.live);
+        assert_eq!(map.ranges[0].node, "10.0.0.1:7000");
+        assert_eq!(map.ranges[2].replicas, ["10.0.0.4:7003"]);
+        let nodes = map.nodes();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1].1.len(), 2);
+        assert!(SlotMap::from_reply(&Value::Bulk(vec![Value::Bulk(vec![Value::Int(0), Value::Int(1)])])).is_err());
+    }
+
+    #[test]
+    fn test_reject_reversed_and_out_of_range_slots() {
+        for (start, end) in [(200, 100), (0, 16384), (-1, 10)] {
+            let reply = Value::Bulk(vec![Value::Bulk(vec![Value::Int(start), Value::Int(end), node("a", 1)])]);
+            assert!(SlotMap::from_reply(&reply).is_err(), "{}-{}", start, end);
+        }
+    }
+
+    #[test]
+    fn test_render_flags_uncovered_slots() {
+        let reply = Value::Bulk(vec![Value::Bulk(vec![Value::Int(0), Value::Int(8191), node("a", 1)])]);
+        let rendered = SlotMap::from_reply(&reply).unwrap().render();
+        assert!(rendered.starts_with("a:1  ███████████████"), "{}", rendered);
+        assert!(rendered.contains(" 8192 slots ( 50.0%)  0-8191"));
+        assert!(rendered.contains("8192 slots have no master"));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::report::{DiffKind, DiffRecord};
+use crate::utils::{KeyScanner, KeyType};
+use crate::{RedisClient, Result};
+use crate::utils::RedisConnection;
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use std::fmt::{self, Write};
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Harmless leftovers, e.g. a cart line for a discontinued product.
+    Warning,
+    /// Broken lookups, e.g. a user that can't be found by username.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A fix that can be applied without human judgement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    SetKey { key: String, value: String },
+    DeleteKey { key: String },
+    RemoveHashField { key: String, field: String },
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Repair::SetKey { key, value } => write!(f, "SET {} {}", key, value),
+            Repair::DeleteKey { key } => write!(f, "DEL {}", key),
+            Repair::RemoveHashField { key, field } => write!(f, "HDEL {} {}", key, field),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub invariant: &'static str,
+    pub key: String,
+    pub severity: Severity,
+    pub message: String,
+    pub repair: Option<Repair>,
+}
+
+impl From<&Violation> for DiffRecord {
+    /// Keyed by what the repair would touch, so a missing index shows up
+    /// under the index key rather than the user that owns it.
+    fn from(violation: &Violation) -> Self {
+        let (key, kind, left, right) = match &violation.repair {
+            Some(Repair::SetKey { key, value }) => (key.clone(), DiffKind::Missing, None, Some(value.as_str().into())),
+            Some(Repair::DeleteKey { key }) => (key.clone(), DiffKind::Extra, None, None),
+            Some(Repair::RemoveHashField { key, field }) => (key.clone(), DiffKind::Extra, Some(field.as_str().into()), None),
+            None => (violation.key.clone(), DiffKind::Invalid, None, None),
+        };
+        DiffRecord {
+            key,
+            kind,
+            left,
+            right,
+            action: violation.repair.as_ref().map(Repair::to_string),
+            detail: Some(format!("[{}] {}: {}", violation.severity, violation.invariant, violation.message)),
+        }
+    }
+}
+
+/// A rule an entity's keys must satisfy. The checker scans `pattern` for
+/// keys of `key_type` and hands them over in batches.
+#[async_trait]
+pub trait Invariant: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn pattern(&self) -> &str;
+
+    fn key_type(&self) -> KeyType;
+
+    async fn check(&self, conn: &mut RedisConnection, keys: &[String]) -> Result<Vec<Violation>>;
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConsistencyReport {
+    pub checked: usize,
+    pub violations: Vec<Violation>,
+    pub repaired: usize,
+}
+
+impl ConsistencyReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.violations.iter().filter(|v| v.severity == severity).count()
+    }
+}
+
+pub fn render_report(report: &ConsistencyReport) -> String {
+    let mut out = String::new();
+    for violation in &report.violations {
+        let _ = writeln!(
+            out,
+            "   [{}] {} {}: {}",
+            violation.severity, violation.invariant, violation.key, violation.message
+        );
+        if let Some(repair) = &violation.repair {
+            let _ = writeln!(out, "      repair: {}", repair);
+        }
+    }
+    let _ = writeln!(
+        out,
+        "{} keys checked, {} errors, {} warnings, {} repaired",
+        report.checked,
+        report.count(Severity::Error),
+        report.count(Severity::Warning),
+        report.repaired
+    );
+    out
+}
+
+/// Runs a set of invariants over the keyspace and optionally applies the
+/// repairs they suggest.
+pub struct ConsistencyChecker {
+    conn: RedisConnection,
+    invariants: Vec<Box<dyn Invariant>>,
+}
+
+impl ConsistencyChecker {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            invariants: Vec::new(),
+        })
+    }
+
+    pub fn with_invariant(mut self, invariant: Box<dyn Invariant>) -> Self {
+        self.invariants.push(invariant);
+        self
+    }
+
+    pub async fn run(&mut self, repair: bool) -> Result<ConsistencyReport> {
+        let mut report = ConsistencyReport::default();
+        for invariant in &self.invariants {
+            let mut scanner = KeyScanner::new(self.conn.clone(), invariant.pattern(), Some(invariant.key_type()));
+            while let Some(batch) = scanner.next_batch().await? {
+                if batch.is_empty() {
+                    continue;
+                }
+                report.checked += batch.len();
+                report.violations.extend(invariant.check(&mut self.conn, &batch).await?);
+            }
+        }
+
+        if repair {
+            for repair in report.violations.iter().filter_map(|v| v.repair.as_ref()) {
+                apply(&mut self.conn, repair).await?;
+                report.repaired += 1;
+            }
+        }
+        info!(
+            "Consistency check: {} keys, {} violations, {} repaired",
+            report.checked,
+            report.violations.len(),
+            report.repaired
+        );
+        Ok(report)
+    }
+}
+
+async fn apply(conn: &mut RedisConnection, repair: &Repair) -> Result<()> {
+    match repair {
+        Repair::SetKey { key, value } => {
+            let _: () = conn.set(key, value).await?;
+        }
+        Repair::DeleteKey { key } => {
+            let _: () = conn.del(key).await?;
+        }
+        Repair::RemoveHashField { key, field } => {
+            let _: () = conn.hdel(key, field).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations_as_diff_records() {
+        let violation = Violation {
+            invariant: "user-indexes",
+            key: "user:42".to_string(),
+            severity: Severity::Error,
+            message: "username:bob is missing".to_string(),
+            repair: Some(Repair::SetKey { key: "username:bob".to_string(), value: "42".to_string() }),
+        };
+        let record = DiffRecord::from(&violation);
+        assert_eq!(record.key, "username:bob");
+        assert_eq!(record.kind, DiffKind::Missing);
+        assert_eq!(record.right, Some("42".into()));
+        assert_eq!(record.action.as_deref(), Some("SET username:bob 42"));
+
+        let unfixable = DiffRecord::from(&Violation { repair: None, ..violation });
+        assert_eq!((unfixable.key.as_str(), unfixable.kind, unfixable.action), ("user:42", DiffKind::Invalid, None));
+    }
+}
@@ -0,0 +1,296 @@
+use super::checker::{Invariant, Repair, Severity, Violation};
+use crate::models::User;
+use crate::repository::fields::{from_fields, Fields};
+use crate::repository::Entity;
+use crate::utils::KeyType;
+use crate::Result;
+use crate::utils::RedisConnection;
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use std::marker::PhantomData;
+
+/// Every `user:<id>` hash is reachable through each of its
+/// [`Entity::index_keys`], and they point back at it.
+pub struct UserIndexesPresent;
+
+/// Violations for one entity given what each of its `index_keys`
+/// currently holds, in the same order.
+pub fn index_violations<T: Entity>(invariant: &'static str, entity: &T, targets: &[Option<String>]) -> Vec<Violation> {
+    let id = entity.entity_id();
+    let key = T::key_for(&id);
+    entity
+        .index_keys()
+        .into_iter()
+        .zip(targets)
+        .filter_map(|(index_key, target)| match target {
+            Some(target) if *target == id => None,
+            Some(other) => Some(Violation {
+                invariant,
+                key: key.clone(),
+                severity: Severity::Error,
+                // Two entities claiming the same value needs a human to decide.
+                message: format!("{} points at {} {}", index_key, T::KIND, other),
+                repair: None,
+            }),
+            None => Some(Violation {
+                invariant,
+                key: key.clone(),
+                severity: Severity::Error,
+                message: format!("{} is missing", index_key),
+                repair: Some(Repair::SetKey { key: index_key, value: id.clone() }),
+            }),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Invariant for UserIndexesPresent {
+    fn name(&self) -> &'static str {
+        "user-indexes"
+    }
+
+    fn pattern(&self) -> &str {
+        "user:*"
+    }
+
+    fn key_type(&self) -> KeyType {
+        KeyType::Hash
+    }
+
+    async fn check(&self, conn: &mut RedisConnection, keys: &[String]) -> Result<Vec<Violation>> {
+        let (keys, hashes) = entity_hashes(conn, keys).await?;
+
+        let mut violations = Vec::new();
+        let mut users = Vec::new();
+        for (key, fields) in keys.into_iter().zip(hashes) {
+            if fields.is_empty() {
+                continue;
+            }
+            match from_fields::<User>(&fields) {
+                Ok(user) => users.push(user),
+                Err(e) => violations.push(Violation {
+                    invariant: self.name(),
+                    key: key.clone(),
+                    severity: Severity::Warning,
+                    message: format!("not a user hash: {}", e),
+                    repair: None,
+                }),
+            }
+        }
+        if users.is_empty() {
+            return Ok(violations);
+        }
+
+        let index_keys: Vec<Vec<String>> = users.iter().map(Entity::index_keys).collect();
+        let all: Vec<&String> = index_keys.iter().flatten().collect();
+        if all.is_empty() {
+            return Ok(violations);
+        }
+        let targets: Vec<Option<String>> = redis::cmd("MGET").arg(&all).query_async(conn).await?;
+        let mut targets = targets.into_iter();
+        for (user, keys) in users.iter().zip(&index_keys) {
+            let mine: Vec<Option<String>> = targets.by_ref().take(keys.len()).collect();
+            violations.extend(index_violations(self.name(), user, &mine));
+        }
+        Ok(violations)
+    }
+}
+
+/// Every `product:<id>` field of a `cart:<session>` hash refers to an
+/// existing product.
+pub struct CartProductsExist;
+
+/// Violations for one cart given which of its product fields still exist.
+pub fn cart_violations(cart_key: &str, products: &[(String, bool)]) -> Vec<Violation> {
+    products
+        .iter()
+        .filter(|(_, exists)| !exists)
+        .map(|(product, _)| Violation {
+            invariant: "cart-products",
+            key: cart_key.to_string(),
+            severity: Severity::Warning,
+            message: format!("references missing {}", product),
+            repair: Some(Repair::RemoveHashField { key: cart_key.to_string(), field: product.clone() }),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Invariant for CartProductsExist {
+    fn name(&self) -> &'static str {
+        "cart-products"
+    }
+
+    fn pattern(&self) -> &str {
+        "cart:*"
+    }
+
+    fn key_type(&self) -> KeyType {
+        KeyType::Hash
+    }
+
+    async fn check(&self, conn: &mut RedisConnection, keys: &[String]) -> Result<Vec<Violation>> {
+        let mut violations = Vec::new();
+        for key in keys {
+            let fields: Vec<String> = conn.hkeys(key).await?;
+            let products: Vec<String> = fields.into_iter().filter(|field| field.starts_with("product:")).collect();
+            if products.is_empty() {
+                continue;
+            }
+            let mut pipe = redis::pipe();
+            for product in &products {
+                pipe.exists(product);
+            }
+            let exists: Vec<bool> = pipe.query_async(conn).await?;
+            let products: Vec<(String, bool)> = products.into_iter().zip(exists).collect();
+            violations.extend(cart_violations(key, &products));
+        }
+        Ok(violations)
+    }
+}
+
+/// Every `<KIND>:<id>` hash deserializes and passes [`Entity::validate`],
+/// catching drift from hand edits and old writers.
+pub struct EntitiesValid<T: Entity> {
+    pattern: String,
+    _entity: PhantomData<T>,
+}
+
+impl<T: Entity> EntitiesValid<T> {
+    pub fn new() -> Self {
+        Self { pattern: T::key_for("*"), _entity: PhantomData }
+    }
+}
+
+impl<T: Entity> Default for EntitiesValid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Only `<KIND>:<id>` itself among `keys`, not `<KIND>:<id>:sessions` and
+/// friends, with the hash each holds; empty for one deleted since the scan.
+async fn entity_hashes<'a>(conn: &mut RedisConnection, keys: &'a [String]) -> Result<(Vec<&'a String>, Vec<Fields>)> {
+    let keys: Vec<&String> = keys.iter().filter(|key| key.split(':').count() == 2).collect();
+    if keys.is_empty() {
+        return Ok((keys, Vec::new()));
+    }
+    let mut pipe = redis::pipe();
+    for key in &keys {
+        pipe.hgetall(*key);
+    }
+    let hashes: Vec<Fields> = pipe.query_async(conn).await?;
+    Ok((keys, hashes))
+}
+
+/// The violation for one stored hash, if it isn't a valid `T`.
+pub fn validation_violation<T: Entity>(key: &str, fields: &Fields) -> Option<Violation> {
+    let message = match from_fields::<T>(fields) {
+        Ok(entity) => {
+            let report = entity.validate();
+            if report.is_valid() {
+                return None;
+            }
+            report.to_string()
+        }
+        Err(e) => format!("not a {} hash: {}", T::KIND, e),
+    };
+    // Only the owner knows what the value should have been.
+    Some(Violation { invariant: "schema", key: key.to_string(), severity: Severity::Error, message, repair: None })
+}
+
+#[async_trait]
+impl<T: Entity + 'static> Invariant for EntitiesValid<T> {
+    fn name(&self) -> &'static str {
+        "schema"
+    }
+
+    fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    fn key_type(&self) -> KeyType {
+        KeyType::Hash
+    }
+
+    async fn check(&self, conn: &mut RedisConnection, keys: &[String]) -> Result<Vec<Violation>> {
+        let (keys, hashes) = entity_hashes(conn, keys).await?;
+        Ok(keys
+            .into_iter()
+            .zip(hashes)
+            .filter(|(_, fields)| !fields.is_empty())
+            .filter_map(|(key, fields)| validation_violation::<T>(key, &fields))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consistency::{render_report, ConsistencyReport};
+    use crate::repository::fields::to_fields;
+
+    fn alice() -> User {
+        User::new("alice".to_string(), "alice@example.com".to_string(), "Alice".to_string())
+    }
+
+    #[test]
+    fn test_user_with_both_indexes_is_consistent() {
+        let user = alice();
+        let id = user.id.to_string();
+        assert!(index_violations("user-indexes", &user, &[Some(id.clone()), Some(id)]).is_empty());
+    }
+
+    #[test]
+    fn test_missing_index_is_repairable() {
+        let user = alice();
+        let id = user.id.to_string();
+        let violations = index_violations("user-indexes", &user, &[Some(id.clone()), None]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].severity, Severity::Error);
+        assert_eq!(
+            violations[0].repair,
+            Some(Repair::SetKey { key: "email:alice@example.com".to_string(), value: id })
+        );
+    }
+
+    #[test]
+    fn test_index_owned_by_other_user_needs_a_human() {
+        let user = alice();
+        let id = user.id.to_string();
+        let violations = index_violations("user-indexes", &user, &[Some("someone-else".to_string()), Some(id)]);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].repair.is_none());
+        assert!(violations[0].message.contains("someone-else"));
+    }
+
+    #[test]
+    fn test_cart_violations_and_report() {
+        let products = vec![("product:1".to_string(), true), ("product:2".to_string(), false)];
+        let violations = cart_violations("cart:s1", &products);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].severity, Severity::Warning);
+
+        let report = ConsistencyReport { checked: 1, violations, repaired: 0 };
+        let rendered = render_report(&report);
+        assert!(rendered.contains("[warning] cart-products cart:s1: references missing product:2"));
+        assert!(rendered.contains("repair: HDEL cart:s1 product:2"));
+        assert!(rendered.ends_with("1 keys checked, 0 errors, 1 warnings, 0 repaired\n"));
+    }
+
+    #[test]
+    fn test_validation_violations() {
+        let mut user = alice();
+        assert!(validation_violation::<User>("user:1", &to_fields(&user).unwrap()).is_none());
+
+        user.email = "alice".to_string();
+        let violation = validation_violation::<User>("user:1", &to_fields(&user).unwrap()).unwrap();
+        assert_eq!((violation.invariant, violation.severity), ("schema", Severity::Error));
+        assert_eq!(violation.message, "email \"alice\" is not an email address");
+
+        let fields: Fields = [("id".to_string(), "7".to_string())].into_iter().collect();
+        let violation = validation_violation::<User>("user:2", &fields).unwrap();
+        assert!(violation.message.starts_with("not a user hash"), "{}", violation.message);
+        assert_eq!(EntitiesValid::<User>::new().pattern(), "user:*");
+    }
+}
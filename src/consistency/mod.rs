@@ -0,0 +1,5 @@
+pub mod checker;
+pub mod invariants;
+
+pub use checker::{render_report, ConsistencyChecker, ConsistencyReport, Invariant, Repair, Severity, Violation};
+pub use invariants::{CartProductsExist, EntitiesValid, UserIndexesPresent};
@@ -0,0 +1,449 @@
+use crate::demos::cardinality::{format_bytes, memory_usage};
+use crate::models::{ActivityLog, FunnelStep};
+use crate::utils::bit_index::BitIndexMap;
+use crate::utils::compact_stats::{self, StatLane};
+use crate::utils::Sections;
+use crate::{DemoError, RedisClient, Result};
+use chrono::{Duration, NaiveDate};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use redis::AsyncCommands;
+use std::fmt::Write;
+use std::str::FromStr;
+use tracing::info;
+
+pub fn compact_stats_key(user: usize) -> String {
+    format!("analytics:stats:user{}", user)
+}
+
+pub fn hash_stats_key(user: usize) -> String {
+    format!("analytics:stats_hash:user{}", user)
+}
+
+pub fn funnel_key(step: FunnelStep, day: NaiveDate) -> String {
+    format!("analytics:funnel:{}:{}", step.name(), day.format("%Y-%m-%d"))
+}
+
+/// Every day from `from` to `to`, inclusive.
+pub fn days_in_range(from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+    from.iter_days().take_while(|day| *day <= to).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunnelRow {
+    pub step: FunnelStep,
+    pub users: u64,
+    /// Share of the previous step's users that reached this step.
+    pub step_rate: f64,
+    /// Share of the first step's users that reached this step.
+    pub overall_rate: f64,
+}
+
+pub fn funnel_rows(counts: &[(FunnelStep, u64)]) -> Vec<FunnelRow> {
+    let rate = |part: u64, whole: u64| if whole == 0 { 0.0 } else { part as f64 / whole as f64 };
+    let first = counts.first().map_or(0, |(_, users)| *users);
+    let mut previous = first;
+    counts
+        .iter()
+        .map(|(step, users)| {
+            let row = FunnelRow {
+                step: *step,
+                users: *users,
+                step_rate: rate(*users, previous),
+                overall_rate: rate(*users, first),
+            };
+            previous = *users;
+            row
+        })
+        .collect()
+}
+
+pub fn render_funnel(rows: &[FunnelRow]) -> String {
+    let mut out = format!("   {:<12} {:>8} {:>10} {:>10}\n", "step", "users", "step %", "overall %");
+    for row in rows {
+        let _ = writeln!(
+            out,
+            "   {:<12} {:>8} {:>9.1}% {:>9.1}%",
+            row.step.name(),
+            row.users,
+            row.step_rate * 100.0,
+            row.overall_rate * 100.0
+        );
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionFormat {
+    Table,
+    Csv,
+}
+
+impl FromStr for RetentionFormat {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Ok(RetentionFormat::Table),
+            "csv" => Ok(RetentionFormat::Csv),
+            other => Err(DemoError::Configuration(format!("Unknown retention format: {}", other))),
+        }
+    }
+}
+
+pub fn active_day_key(day: NaiveDate) -> String {
+    format!("analytics:active:{}", day.format("%Y-%m-%d"))
+}
+
+pub fn active_week_key(week: NaiveDate) -> String {
+    format!("analytics:active_week:{}", week.format("%Y-%m-%d"))
+}
+
+pub fn cohort_key(week: NaiveDate) -> String {
+    format!("analytics:cohort:{}", week.format("%Y-%m-%d"))
+}
+
+const SEEN_KEY: &str = "analytics:retention:seen";
+const SCRATCH_KEY: &str = "analytics:retention:scratch";
+
+/// Users first active in the week starting `week`, and how many of them were
+/// active again `n` weeks later (`retained[0]` is the cohort itself).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CohortRow {
+    pub week: NaiveDate,
+    pub size: u64,
+    pub retained: Vec<u64>,
+}
+
+impl CohortRow {
+    pub fn rate(&self, weeks_later: usize) -> Option<f64> {
+        let retained = *self.retained.get(weeks_later)?;
+        Some(if self.size == 0 { 0.0 } else { retained as f64 / self.size as f64 })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionTriangle {
+    pub rows: Vec<CohortRow>,
+}
+
+impl RetentionTriangle {
+    pub fn render(&self, format: RetentionFormat) -> String {
+        match format {
+            RetentionFormat::Table => self.render_table(),
+            RetentionFormat::Csv => self.render_csv(),
+        }
+    }
+
+    pub fn render_table(&self) -> String {
+        let weeks = self.rows.len();
+        let mut out = format!("{:<12} {:>7}", "cohort", "users");
+        for week in 0..weeks {
+            let _ = write!(out, " {:>7}", format!("wk{}", week));
+        }
+        out.push('\n');
+        for row in &self.rows {
+            let _ = write!(out, "{:<12} {:>7}", row.week.format("%Y-%m-%d"), row.size);
+            for week in 0..row.retained.len() {
+                let _ = write!(out, " {:>6.1}%", row.rate(week).unwrap_or(0.0) * 100.0);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// One line per cohort with raw counts, easy to pivot in a spreadsheet.
+    pub fn render_csv(&self) -> String {
+        let weeks = self.rows.len();
+        let mut out = String::from("cohort,users");
+        for week in 0..weeks {
+            let _ = write!(out, ",week_{}", week);
+        }
+        out.push('\n');
+        for row in &self.rows {
+            let _ = write!(out, "{},{}", row.week.format("%Y-%m-%d"), row.size);
+            for retained in &row.retained {
+                let _ = write!(out, ",{}", retained);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+pub struct AnalyticsDemo {
+    client: RedisClient,
+}
+
+impl AnalyticsDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Records simulated activity for `users` into both a packed BITFIELD
+    /// value and a hash of counters per user, then compares their footprint.
+    pub async fn stats(&self, users: usize) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let mut rng = StdRng::seed_from_u64(42);
+        self.cleanup(users).await?;
+
+        println!("\n=== Compact Per-User Stats with BITFIELD ===\n");
+
+        let mut sections = Sections::new();
+        sections.next(&format!("Lane layout ({} bits per user):", compact_stats::total_bits()));
+        for lane in StatLane::ALL {
+            println!("   {:<12} {:<4} at bit {:>2} (max {})", lane.name(), lane.encoding(), lane.offset(), lane.max());
+        }
+
+        sections.next(&format!("Recording activity for {} users (BITFIELD OVERFLOW SAT INCRBY vs HINCRBY):", users));
+        for chunk in (0..users).collect::<Vec<_>>().chunks(500) {
+            let mut pipe = redis::pipe();
+            for user in chunk {
+                for lane in StatLane::ALL {
+                    let by: i64 = rng.gen_range(0..=lane.max().min(500) as i64);
+                    compact_stats::incr_in(&mut pipe, &compact_stats_key(*user), lane, by);
+                    pipe.hincr(hash_stats_key(*user), lane.name(), by).ignore();
+                }
+            }
+            let _: () = pipe.query_async(&mut conn).await?;
+        }
+
+        sections.next("Typed read of one user (single BITFIELD GET x6):");
+        let stats = compact_stats::get_all(&mut conn, &compact_stats_key(0)).await?;
+        println!("   {:?}", stats);
+
+        sections.next("Saturating instead of wrapping:");
+        let key = compact_stats_key(0);
+        compact_stats::set(&mut conn, &key, StatLane::Warnings, 250).await?;
+        let warnings = compact_stats::incr(&mut conn, &key, StatLane::Warnings, 10).await?;
+        println!("   warnings 250 + 10 on a u8 lane => {}", warnings);
+
+        sections.next("Memory comparison:");
+        let sample = users.min(1000);
+        let (mut compact_bytes, mut hash_bytes) = (0, 0);
+        for user in 0..sample {
+            compact_bytes += memory_usage(&mut conn, &compact_stats_key(user)).await?;
+            hash_bytes += memory_usage(&mut conn, &hash_stats_key(user)).await?;
+        }
+        let per_user = |total: u64| total / sample.max(1) as u64;
+        println!("   BITFIELD string: {} per user ({} for {} users)", format_bytes(per_user(compact_bytes)), format_bytes(compact_bytes), sample);
+        println!("   Hash of counters: {} per user ({} for {} users)", format_bytes(per_user(hash_bytes)), format_bytes(hash_bytes), sample);
+        if compact_bytes > 0 {
+            println!("   => hash uses {:.1}x the memory", hash_bytes as f64 / compact_bytes as f64);
+        }
+
+        self.cleanup(users).await?;
+        info!("Compact stats demo completed");
+        Ok(())
+    }
+
+    /// Loads generated activity into one HLL per step per day, then answers
+    /// funnel questions for any date range with multi-key PFCOUNT.
+    pub async fn funnel(&self, users: usize, days: u32, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Vec<FunnelRow>> {
+        let mut conn = self.client.get_async_connection().await?;
+        let start = NaiveDate::from_ymd_opt(2025, 1, 6).expect("valid date");
+        let log = ActivityLog::generate(users, start, days, 42);
+        let all_days = days_in_range(log.start, log.end());
+        self.cleanup_funnel(&all_days).await?;
+
+        println!("\n=== Funnel Analysis with HyperLogLog ===\n");
+
+        let mut sections = Sections::new();
+        sections.next(&format!("Recording {} events for {} users over {} days (PFADD per step per day)", log.events.len(), users, days));
+        for chunk in log.events.chunks(1000) {
+            let mut pipe = redis::pipe();
+            for event in chunk {
+                pipe.pfadd(funnel_key(event.step, event.day), format!("user{}", event.user)).ignore();
+            }
+            let _: () = pipe.query_async(&mut conn).await?;
+        }
+
+        let from = from.unwrap_or(log.start).max(log.start);
+        let to = to.unwrap_or(log.end()).min(log.end());
+        let range = days_in_range(from, to);
+        sections.next(&format!("Funnel for {} to {} (PFCOUNT over {} daily keys per step):", from, to, range.len()));
+        let mut counts = Vec::new();
+        for step in FunnelStep::ALL {
+            let keys: Vec<String> = range.iter().map(|day| funnel_key(step, *day)).collect();
+            let users: u64 = if keys.is_empty() { 0 } else { conn.pfcount(keys).await? };
+            counts.push((step, users));
+        }
+        let rows = funnel_rows(&counts);
+        print!("{}", render_funnel(&rows));
+
+        sections.next("Estimate vs exact:");
+        for (step, estimate) in &counts {
+            let exact = log.users_with(*step, from, to);
+            let error = if exact == 0 { 0.0 } else { (*estimate as f64 - exact as f64) / exact as f64 * 100.0 };
+            println!("   {:<12} estimate {:>6}  exact {:>6}  ({:+.2}%)", step.name(), estimate, exact, error);
+        }
+
+        println!("\n💡 Each HLL is at most 12 KB however many users it sees, and unions are");
+        println!("   exact merges, so weekly or monthly funnels need no extra storage.");
+        println!("   Steps are counted independently: a user counted as purchased in the range");
+        println!("   may have visited before it started.");
+
+        self.cleanup_funnel(&all_days).await?;
+        info!("Funnel demo completed");
+        Ok(rows)
+    }
+
+    /// Builds weekly cohorts from per-day activity bitmaps: a cohort is the
+    /// users active in a week but in no earlier one, and each cell is a
+    /// BITOP AND of the cohort with a later week's activity.
+    pub async fn retention(&self, users: usize, weeks: u32) -> Result<RetentionTriangle> {
+        let mut conn = self.client.get_async_connection().await?;
+        let start = NaiveDate::from_ymd_opt(2025, 1, 6).expect("valid date");
+        let log = ActivityLog::generate(users, start, weeks * 7, 42);
+        let week_starts: Vec<NaiveDate> = (0..weeks).map(|week| start + Duration::weeks(week as i64)).collect();
+        let mut index = BitIndexMap::new(conn.clone(), "analytics");
+        self.cleanup_retention(&mut index, &log).await?;
+
+        let ids: Vec<String> = (0..users).map(|user| format!("user{}", user)).collect();
+        let mut offsets = Vec::with_capacity(users);
+        for chunk in ids.chunks(1000) {
+            offsets.extend(index.assign(chunk).await?);
+        }
+
+        for chunk in log.events.chunks(1000) {
+            let mut pipe = redis::pipe();
+            for event in chunk.iter().filter(|event| event.step == FunnelStep::Visited) {
+                pipe.setbit(active_day_key(event.day), offsets[event.user] as usize, true).ignore();
+            }
+            let _: () = pipe.query_async(&mut conn).await?;
+        }
+
+        for week in &week_starts {
+            let days: Vec<String> = days_in_range(*week, *week + Duration::days(6))
+                .into_iter()
+                .map(active_day_key)
+                .collect();
+            let _: () = conn.bit_or(active_week_key(*week), days).await?;
+        }
+
+        let mut triangle = RetentionTriangle::default();
+        for (i, week) in week_starts.iter().enumerate() {
+            // cohort = active AND NOT seen, written as active XOR (active AND seen)
+            // because BITOP NOT only flips bits within the source's length.
+            let active = active_week_key(*week);
+            let _: () = conn.bit_and(SCRATCH_KEY, &[active.as_str(), SEEN_KEY]).await?;
+            let _: () = conn.bit_xor(cohort_key(*week), &[active.as_str(), SCRATCH_KEY]).await?;
+            let _: () = conn.bit_or(SEEN_KEY, &[SEEN_KEY, active.as_str()]).await?;
+
+            let size: u64 = conn.bitcount(cohort_key(*week)).await?;
+            let mut retained = vec![size];
+            for later in &week_starts[i + 1..] {
+                let _: () = conn.bit_and(SCRATCH_KEY, &[cohort_key(*week), active_week_key(*later)]).await?;
+                retained.push(conn.bitcount(SCRATCH_KEY).await?);
+            }
+            triangle.rows.push(CohortRow { week: *week, size, retained });
+        }
+
+        self.cleanup_retention(&mut index, &log).await?;
+        info!("Retention report for {} weeks completed", weeks);
+        Ok(triangle)
+    }
+
+    async fn cleanup_retention(&self, index: &mut BitIndexMap, log: &ActivityLog) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let mut keys: Vec<String> = days_in_range(log.start, log.end()).into_iter().map(active_day_key).collect();
+        for week in log.start.iter_weeks().take_while(|week| *week <= log.end()) {
+            keys.push(active_week_key(week));
+            keys.push(cohort_key(week));
+        }
+        keys.push(SEEN_KEY.to_string());
+        keys.push(SCRATCH_KEY.to_string());
+        let _: () = conn.del(keys).await?;
+        index.clear().await
+    }
+
+    async fn cleanup_funnel(&self, days: &[NaiveDate]) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let keys: Vec<String> = days
+            .iter()
+            .flat_map(|day| FunnelStep::ALL.map(|step| funnel_key(step, *day)))
+            .collect();
+        if !keys.is_empty() {
+            let _: () = conn.del(keys).await?;
+        }
+        Ok(())
+    }
+
+    async fn cleanup(&self, users: usize) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let keys: Vec<String> = (0..users)
+            .flat_map(|user| [compact_stats_key(user), hash_stats_key(user)])
+            .collect();
+        for chunk in keys.chunks(1000) {
+            let _: () = conn.del(chunk).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_days_in_range_is_inclusive() {
+        let from = NaiveDate::from_ymd_opt(2025, 1, 30).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 2, 2).unwrap();
+        let days = days_in_range(from, to);
+        assert_eq!(days.len(), 4);
+        assert_eq!(funnel_key(FunnelStep::SignedUp, days[2]), "analytics:funnel:signed_up:2025-02-01");
+        assert!(days_in_range(to, from).is_empty());
+    }
+
+    fn sample_triangle() -> RetentionTriangle {
+        let week = |day| NaiveDate::from_ymd_opt(2025, 1, day).unwrap();
+        RetentionTriangle {
+            rows: vec![
+                CohortRow { week: week(6), size: 200, retained: vec![200, 50, 20] },
+                CohortRow { week: week(13), size: 100, retained: vec![100, 40] },
+                CohortRow { week: week(20), size: 0, retained: vec![0] },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_retention_rates() {
+        let triangle = sample_triangle();
+        assert_eq!(triangle.rows[0].rate(1), Some(0.25));
+        assert_eq!(triangle.rows[1].rate(1), Some(0.4));
+        assert_eq!(triangle.rows[1].rate(2), None);
+        assert_eq!(triangle.rows[2].rate(0), Some(0.0));
+    }
+
+    #[test]
+    fn test_retention_rendering() {
+        let triangle = sample_triangle();
+        let csv = triangle.render(RetentionFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "cohort,users,week_0,week_1,week_2");
+        assert_eq!(lines[1], "2025-01-06,200,200,50,20");
+        assert_eq!(lines[3], "2025-01-20,0,0");
+
+        let table = triangle.render(RetentionFormat::Table);
+        assert!(table.lines().nth(1).unwrap().contains("25.0%"));
+        assert_eq!("CSV".parse::<RetentionFormat>().unwrap(), RetentionFormat::Csv);
+        assert!("json".parse::<RetentionFormat>().is_err());
+    }
+
+    #[test]
+    fn test_funnel_rows_rates() {
+        let rows = funnel_rows(&[
+            (FunnelStep::Visited, 1000),
+            (FunnelStep::SignedUp, 200),
+            (FunnelStep::Purchased, 50),
+        ]);
+        assert_eq!(rows[0].step_rate, 1.0);
+        assert_eq!(rows[1].step_rate, 0.2);
+        assert_eq!(rows[2].step_rate, 0.25);
+        assert_eq!(rows[2].overall_rate, 0.05);
+
+        let empty = funnel_rows(&[(FunnelStep::Visited, 0), (FunnelStep::SignedUp, 0)]);
+        assert_eq!(empty[1].overall_rate, 0.0);
+        assert!(render_funnel(&rows).contains("25.0%"));
+    }
+}
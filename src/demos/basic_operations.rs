@@ -1,4 +1,5 @@
 use crate::{RedisClient, Result};
+use crate::utils::{KeyType, ScopedKeys, Sections};
 use redis::AsyncCommands;
 use tracing::info;
 
@@ -17,22 +18,23 @@ impl BasicOpsDemo {
         println!("\n=== String Operations Demo ===\n");
         
         // SET and GET
-        println!("1. SET and GET:");
-        conn.set("message", "Hello, Redis!").await?;
+        let mut sections = Sections::new();
+        sections.next("SET and GET:");
+        let _: () = conn.set("message", "Hello, Redis!").await?;
         let value: String = conn.get("message").await?;
         println!("   SET message 'Hello, Redis!'");
         println!("   GET message => '{}'", value);
         
         // SET with expiration
-        println!("\n2. SET with expiration (EX):");
-        conn.set_ex("temp_key", "This will expire", 5).await?;
+        sections.next("SET with expiration (EX):");
+        let _: () = conn.set_ex("temp_key", "This will expire", 5).await?;
         let ttl: i64 = conn.ttl("temp_key").await?;
         println!("   SET temp_key 'This will expire' EX 5");
         println!("   TTL temp_key => {} seconds", ttl);
         
         // INCR and DECR
-        println!("\n3. INCR and DECR:");
-        conn.set("counter", 10).await?;
+        sections.next("INCR and DECR:");
+        let _: () = conn.set("counter", 10).await?;
         let incr_result: i64 = conn.incr("counter", 1).await?;
         println!("   SET counter 10");
         println!("   INCR counter => {}", incr_result);
@@ -41,8 +43,8 @@ impl BasicOpsDemo {
         println!("   DECRBY counter 3 => {}", decr_result);
         
         // MSET and MGET
-        println!("\n4. MSET and MGET (multiple keys):");
-        conn.mset(&[
+        sections.next("MSET and MGET (multiple keys):");
+        let _: () = conn.mset(&[
             ("key1", "value1"),
             ("key2", "value2"),
             ("key3", "value3"),
@@ -56,8 +58,8 @@ impl BasicOpsDemo {
         println!("   MGET key1 key2 key3 key4 => {:?}", values);
         
         // APPEND
-        println!("\n5. APPEND:");
-        conn.set("greeting", "Hello").await?;
+        sections.next("APPEND:");
+        let _: () = conn.set("greeting", "Hello").await?;
         let len: usize = conn.append("greeting", ", World!").await?;
         let final_value: String = conn.get("greeting").await?;
         println!("   SET greeting 'Hello'");
@@ -65,17 +67,17 @@ impl BasicOpsDemo {
         println!("   GET greeting => '{}'", final_value);
         
         // STRLEN
-        println!("\n6. STRLEN:");
+        sections.next("STRLEN:");
         let str_len: usize = conn.strlen("greeting").await?;
         println!("   STRLEN greeting => {}", str_len);
         
         // GETRANGE
-        println!("\n7. GETRANGE (substring):");
+        sections.next("GETRANGE (substring):");
         let substring: String = conn.getrange("greeting", 0, 4).await?;
         println!("   GETRANGE greeting 0 4 => '{}'", substring);
         
         // EXISTS and DEL
-        println!("\n8. EXISTS and DEL:");
+        sections.next("EXISTS and DEL:");
         let exists: bool = conn.exists("message").await?;
         println!("   EXISTS message => {}", exists);
         
@@ -91,23 +93,25 @@ impl BasicOpsDemo {
         
         println!("\n=== Key Management Demo ===\n");
         
-        // Create some test keys
-        conn.set("user:1000:name", "Alice").await?;
-        conn.set("user:1000:email", "alice@example.com").await?;
-        conn.set("user:1001:name", "Bob").await?;
-        conn.set("session:abc123", "active").await?;
-        conn.set_ex("temp:data", "temporary", 10).await?;
+        // Create some test keys; the guard deletes exactly these afterwards
+        let keys = ScopedKeys::new(conn.clone());
+        let _: () = conn.set(keys.track("user:1000:name"), "Alice").await?;
+        let _: () = conn.set(keys.track("user:1000:email"), "alice@example.com").await?;
+        let _: () = conn.set(keys.track("user:1001:name"), "Bob").await?;
+        let _: () = conn.set(keys.track("session:abc123"), "active").await?;
+        let _: () = conn.set_ex(keys.track("temp:data"), "temporary", 10).await?;
         
         // KEYS pattern (not recommended for production)
-        println!("1. KEYS pattern:");
-        let keys: Vec<String> = redis::cmd("KEYS")
+        let mut sections = Sections::new();
+        sections.next("KEYS pattern:");
+        let matched: Vec<String> = redis::cmd("KEYS")
             .arg("user:*")
             .query_async(&mut conn)
             .await?;
-        println!("   KEYS user:* => {:?}", keys);
+        println!("   KEYS user:* => {:?}", matched);
         
         // SCAN (recommended for production)
-        println!("\n2. SCAN (production-safe):");
+        sections.next("SCAN (production-safe):");
         let mut scan_keys = Vec::new();
         let mut cursor = 0;
         loop {
@@ -130,7 +134,7 @@ impl BasicOpsDemo {
         println!("   SCAN with MATCH user:* => Found {} keys", scan_keys.len());
         
         // TYPE
-        println!("\n3. TYPE:");
+        sections.next("TYPE:");
         let key_type: String = redis::cmd("TYPE")
             .arg("user:1000:name")
             .query_async(&mut conn)
@@ -138,34 +142,37 @@ impl BasicOpsDemo {
         println!("   TYPE user:1000:name => {}", key_type);
         
         // EXPIRE and TTL
-        println!("\n4. EXPIRE and TTL:");
-        conn.expire("session:abc123", 60).await?;
+        sections.next("EXPIRE and TTL:");
+        let _: () = conn.expire("session:abc123", 60).await?;
         let ttl: i64 = conn.ttl("session:abc123").await?;
         println!("   EXPIRE session:abc123 60");
         println!("   TTL session:abc123 => {} seconds", ttl);
         
         // PERSIST
-        println!("\n5. PERSIST (remove expiration):");
-        conn.persist("session:abc123").await?;
+        sections.next("PERSIST (remove expiration):");
+        let _: () = conn.persist("session:abc123").await?;
         let ttl_after: i64 = conn.ttl("session:abc123").await?;
         println!("   PERSIST session:abc123");
         println!("   TTL session:abc123 => {} (-1 means no expiration)", ttl_after);
         
         // RENAME
-        println!("\n6. RENAME:");
-        conn.rename("user:1001:name", "user:1001:fullname").await?;
+        sections.next("RENAME:");
+        let _: () = conn.rename("user:1001:name", keys.track("user:1001:fullname")).await?;
         let renamed_value: String = conn.get("user:1001:fullname").await?;
         println!("   RENAME user:1001:name user:1001:fullname");
         println!("   GET user:1001:fullname => '{}'", renamed_value);
-        
-        // Clean up
-        let pattern_keys: Vec<String> = redis::cmd("KEYS")
-            .arg("*")
-            .query_async(&mut conn)
+
+        // SCAN with TYPE filter
+        sections.next("SCAN with TYPE filter:");
+        let _: () = conn.hset(keys.track("user:1000:profile"), "city", "Paris").await?;
+        let hash_keys = self.client
+            .scan_keys_of_type("user:*", KeyType::Hash)
+            .await?
+            .collect_all()
             .await?;
-        if !pattern_keys.is_empty() {
-            conn.del(pattern_keys).await?;
-        }
+        println!("   SCAN 0 MATCH user:* TYPE hash => {:?}", hash_keys);
+
+        keys.finish().await?;
         
         info!("Key operations demo completed");
         Ok(())
@@ -178,16 +185,11 @@ mod tests {
     use redis::AsyncCommands;
     
     async fn get_test_client() -> RedisClient {
-        RedisClient::new("redis://localhost:6379/15").unwrap()
+        RedisClient::new("redis://localhost:6379/15").unwrap().with_key_prefix("test:")
     }
     
     async fn cleanup_test_keys(client: &RedisClient) {
-        let mut conn = client.get_async_connection().await.unwrap();
-        // Use FLUSHDB to ensure clean state for tests
-        let _: String = redis::cmd("FLUSHDB")
-            .query_async(&mut conn)
-            .await
-            .unwrap_or_default();
+        client.delete_prefixed_keys().await.unwrap_or_default();
     }
     
     #[tokio::test]
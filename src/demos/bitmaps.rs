@@ -0,0 +1,165 @@
+use crate::utils::bitmaps::{bitmap_bytes, day_at, day_key, day_offset, days_from, set_offsets};
+use crate::utils::{ScopedKeys, Sections};
+use crate::{RedisClient, Result};
+use chrono::NaiveDate;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use redis::AsyncCommands;
+use tracing::info;
+
+const DAU_PREFIX: &str = "bitmap:dau";
+const RETAINED_KEY: &str = "bitmap:retained";
+const EVERY_DAY_KEY: &str = "bitmap:every_day";
+const REACH_KEY: &str = "bitmap:reach";
+
+/// How likely each kind of user is to show up on a given day, and what
+/// share of users are that kind.
+const USER_MIX: [(f64, f64); 3] = [(0.2, 0.9), (0.4, 0.4), (0.4, 0.1)];
+
+/// Seeded activity for `users` users over `days` days: the ids active on
+/// each day, in increasing order.
+pub fn simulate_activity(users: usize, days: usize, seed: u64) -> Vec<Vec<usize>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let chances: Vec<f64> = (0..users)
+        .map(|_| {
+            let mut pick = rng.gen::<f64>();
+            for (share, chance) in USER_MIX {
+                if pick < share {
+                    return chance;
+                }
+                pick -= share;
+            }
+            USER_MIX[USER_MIX.len() - 1].1
+        })
+        .collect();
+    (0..days)
+        .map(|_| (0..users).filter(|user| rng.gen_bool(chances[*user])).collect())
+        .collect()
+}
+
+/// Daily active users tracked with one bitmap per day and one bit per
+/// user id.
+pub struct BitmapDemo {
+    client: RedisClient,
+}
+
+impl BitmapDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn demonstrate(&self, users: usize, days: usize) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let start = NaiveDate::from_ymd_opt(2025, 3, 3).expect("valid date");
+        let dates = days_from(start, days.max(2));
+        let day_keys: Vec<String> = dates.iter().map(|day| day_key(DAU_PREFIX, *day)).collect();
+        let scope = ScopedKeys::new(conn.clone());
+        scope.track_all(day_keys.iter().map(String::as_str));
+        scope.track_all([RETAINED_KEY, EVERY_DAY_KEY, REACH_KEY]);
+
+        println!("\n=== Bitmap Demo: daily active users ===\n");
+        let activity = simulate_activity(users, dates.len(), 7);
+
+        let mut sections = Sections::new();
+        sections.next("SETBIT: one bit per user id, per day:");
+        for (key, active) in day_keys.iter().zip(&activity) {
+            for chunk in active.chunks(1000) {
+                let mut pipe = redis::pipe();
+                for user in chunk {
+                    pipe.setbit(key, *user, true).ignore();
+                }
+                let _: () = pipe.query_async(&mut conn).await?;
+            }
+        }
+        if let Some(user) = activity[0].first() {
+            println!("   SETBIT {} {} 1   (user {} was active)", day_keys[0], user, user);
+        }
+        let bytes: usize = conn.strlen(&day_keys[0]).await?;
+        println!("   {} users fit in {} bytes per day (highest id / 8 + 1 = {})", users, bytes, bitmap_bytes(users.saturating_sub(1)));
+
+        sections.next("BITCOUNT: daily active users:");
+        let mut daily = Vec::with_capacity(dates.len());
+        for (day, key) in dates.iter().zip(&day_keys) {
+            let dau: usize = conn.bitcount(key).await?;
+            println!("   {}  {:>7} active  ({:.1}%)", day, dau, percent(dau, users));
+            daily.push(dau);
+        }
+
+        sections.next("BITOP AND: retention across days:");
+        let _: () = conn.bit_and(RETAINED_KEY, &day_keys[..2]).await?;
+        let retained: usize = conn.bitcount(RETAINED_KEY).await?;
+        println!("   BITOP AND {} {} {}", RETAINED_KEY, day_keys[0], day_keys[1]);
+        println!("   {} of {} day-one users came back the next day ({:.1}%)", retained, daily[0], percent(retained, daily[0]));
+        let _: () = conn.bit_and(EVERY_DAY_KEY, &day_keys).await?;
+        let every_day: usize = conn.bitcount(EVERY_DAY_KEY).await?;
+        println!("   {} users were active on all {} days", every_day, dates.len());
+
+        sections.next("BITOP OR: reach over the whole period:");
+        let _: () = conn.bit_or(REACH_KEY, &day_keys).await?;
+        let reach: usize = conn.bitcount(REACH_KEY).await?;
+        println!("   {} distinct users active at least once", reach);
+        println!("   Summing the DAUs would claim {}: repeat visitors counted every day", daily.iter().sum::<usize>());
+
+        sections.next("BITPOS: first set or clear bit:");
+        let first: i64 = redis::cmd("BITPOS").arg(&day_keys[0]).arg(1).query_async(&mut conn).await?;
+        println!("   BITPOS {} 1 => {} (lowest user id active on {})", day_keys[0], first, dates[0]);
+        // Past the end of the string every bit reads as 0, so this finds a
+        // user even when every stored bit is set.
+        let missed: i64 = redis::cmd("BITPOS").arg(EVERY_DAY_KEY).arg(0).query_async(&mut conn).await?;
+        println!("   BITPOS {} 0 => {} (lowest user id that missed a day)", EVERY_DAY_KEY, missed);
+
+        sections.next("One bitmap per user: a bit per day since an epoch:");
+        if let Some(user) = activity[0].first().copied() {
+            let epoch = NaiveDate::from_ymd_opt(2025, 1, 1).expect("valid date");
+            let history_key = format!("bitmap:user:{}:days", user);
+            scope.track(history_key.as_str());
+            let mut pipe = redis::pipe();
+            for (day, active) in dates.iter().zip(&activity) {
+                if let (true, Some(offset)) = (active.binary_search(&user).is_ok(), day_offset(epoch, *day)) {
+                    pipe.setbit(&history_key, offset, true).ignore();
+                }
+            }
+            let _: () = pipe.query_async(&mut conn).await?;
+            let active_days: usize = conn.bitcount(&history_key).await?;
+            let first_day: i64 = redis::cmd("BITPOS").arg(&history_key).arg(1).query_async(&mut conn).await?;
+            let raw: Vec<u8> = conn.get(&history_key).await?;
+            let seen: Vec<String> = set_offsets(&raw).into_iter().map(|offset| day_at(epoch, offset).format("%m-%d").to_string()).collect();
+            println!("   User {}: {} active days, first on {} (BITPOS {} 1 => {})", user, active_days, day_at(epoch, first_day as usize), history_key, first_day);
+            println!("   GET and decode locally: {}", seen.join(" "));
+        }
+
+        println!("\n💡 A bitmap costs one bit per possible id whether or not it is set, so a");
+        println!("   million users are 122 KB a day and BITOP answers retention and reach");
+        println!("   with no per-user work on the client. Ids must be small and dense:");
+        println!("   map UUIDs to offsets first (see utils::bit_index) or the strings balloon.");
+
+        scope.finish().await?;
+        info!("Bitmap demo completed");
+        Ok(())
+    }
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        return 0.0;
+    }
+    part as f64 * 100.0 / whole as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_activity() {
+        let activity = simulate_activity(1000, 3, 7);
+        assert_eq!(activity, simulate_activity(1000, 3, 7));
+        assert_eq!(activity.len(), 3);
+        for day in &activity {
+            assert!(day.windows(2).all(|pair| pair[0] < pair[1]));
+            // 0.2 * 0.9 + 0.4 * 0.4 + 0.4 * 0.1 = 38% expected
+            assert!((300..460).contains(&day.len()), "{}", day.len());
+        }
+        assert!(simulate_activity(0, 2, 7).iter().all(Vec::is_empty));
+    }
+}
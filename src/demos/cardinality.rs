@@ -0,0 +1,299 @@
+use crate::{RedisClient, Result};
+use crate::utils::RedisConnection;
+use redis::AsyncCommands;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+const BATCH_SIZE: usize = 10_000;
+const SET_KEY: &str = "cardinality:set";
+const HLL_KEY: &str = "cardinality:hll";
+const BLOOM_KEY: &str = "cardinality:bloom";
+
+/// Bloom filter laid out over a plain Redis bitmap, so the comparison runs
+/// without the RedisBloom module. Bit positions use Kirsch-Mitzenmacher
+/// double hashing over a mixed FNV-1a hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilter {
+    pub bits: u64,
+    pub hashes: u32,
+}
+
+impl BloomFilter {
+    /// Sizes the filter for `capacity` items at the target false-positive
+    /// rate, which must be strictly between 0 and 1: at 0 no size is
+    /// enough and at 1 or above the size comes out negative.
+    pub fn with_rate(capacity: usize, false_positive_rate: f64) -> Self {
+        assert!(
+            false_positive_rate > 0.0 && false_positive_rate < 1.0,
+            "false-positive rate must be between 0 and 1, not {}",
+            false_positive_rate
+        );
+        let n = capacity.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-(n * false_positive_rate.ln()) / (ln2 * ln2)).ceil().max(8.0) as u64;
+        let hashes = ((bits as f64 / n) * ln2).round().max(1.0) as u32;
+        Self { bits, hashes }
+    }
+
+    pub fn positions(&self, item: &str) -> Vec<u64> {
+        let h1 = mix64(fnv1a(item.as_bytes()));
+        let h2 = mix64(h1 ^ 0x9e37_79b9_7f4a_7c15) | 1;
+        (0..self.hashes as u64)
+            .map(|i| h1.wrapping_add(i.wrapping_mul(h2)) % self.bits)
+            .collect()
+    }
+
+    /// Estimates how many distinct items were inserted from the number of set bits.
+    pub fn estimate_count(&self, set_bits: u64) -> f64 {
+        let m = self.bits as f64;
+        let x = (set_bits as f64).min(m - 1.0);
+        -(m / self.hashes as f64) * (1.0 - x / m).ln()
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// SplitMix64 finalizer; spreads FNV's weak low bits across the whole word.
+fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[derive(Debug, Clone)]
+pub struct CardinalityRow {
+    pub structure: &'static str,
+    pub memory_bytes: u64,
+    pub estimated: f64,
+    pub error_rate: f64,
+    pub inserts_per_sec: f64,
+}
+
+impl CardinalityRow {
+    fn new(structure: &'static str, n: usize, memory_bytes: u64, estimated: f64, elapsed: Duration) -> Self {
+        Self {
+            structure,
+            memory_bytes,
+            estimated,
+            error_rate: (estimated - n as f64).abs() / n.max(1) as f64,
+            inserts_per_sec: n as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        }
+    }
+}
+
+pub fn render_report(n: usize, rows: &[CardinalityRow], bloom_false_positive_rate: f64) -> String {
+    let mut out = format!("Cardinality of {} unique members\n\n", n);
+    out.push_str(&format!(
+        "{:<12} {:>14} {:>14} {:>10} {:>14}\n",
+        "Structure", "Memory", "Estimate", "Error", "Inserts/sec"
+    ));
+    for row in rows {
+        out.push_str(&format!(
+            "{:<12} {:>14} {:>14.0} {:>9.3}% {:>14.0}\n",
+            row.structure,
+            format_bytes(row.memory_bytes),
+            row.estimated,
+            row.error_rate * 100.0,
+            row.inserts_per_sec
+        ));
+    }
+    out.push_str(&format!(
+        "\nBloom filter measured false-positive rate: {:.3}%\n",
+        bloom_false_positive_rate * 100.0
+    ));
+    out
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+pub struct CardinalityDemo {
+    client: RedisClient,
+}
+
+impl CardinalityDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Inserts `n` unique members into a SET, a HyperLogLog and a bitmap
+    /// Bloom filter, then prints memory, accuracy and throughput side by side.
+    pub async fn compare(&self, n: usize, false_positive_rate: f64) -> Result<Vec<CardinalityRow>> {
+        let mut conn = self.client.get_async_connection().await?;
+        let _: () = conn.del(&[SET_KEY, HLL_KEY, BLOOM_KEY]).await?;
+
+        println!("\n=== Probabilistic Counting Comparison ===\n");
+        println!("Inserting {} members in batches of {}...", n, BATCH_SIZE);
+
+        let mut rows = Vec::new();
+
+        let started = Instant::now();
+        for batch in member_batches(n) {
+            let _: () = conn.sadd(SET_KEY, &batch).await?;
+        }
+        let exact: usize = conn.scard(SET_KEY).await?;
+        let memory = memory_usage(&mut conn, SET_KEY).await?;
+        rows.push(CardinalityRow::new("SET", n, memory, exact as f64, started.elapsed()));
+
+        let started = Instant::now();
+        for batch in member_batches(n) {
+            let _: () = conn.pfadd(HLL_KEY, &batch).await?;
+        }
+        let estimate: usize = conn.pfcount(HLL_KEY).await?;
+        let memory = memory_usage(&mut conn, HLL_KEY).await?;
+        rows.push(CardinalityRow::new("HyperLogLog", n, memory, estimate as f64, started.elapsed()));
+
+        let bloom = BloomFilter::with_rate(n, false_positive_rate);
+        let started = Instant::now();
+        for batch in member_batches(n) {
+            let mut pipe = redis::pipe();
+            for member in &batch {
+                for position in bloom.positions(member) {
+                    pipe.setbit(BLOOM_KEY, position as usize, true).ignore();
+                }
+            }
+            let _: () = pipe.query_async(&mut conn).await?;
+        }
+        let elapsed = started.elapsed();
+        let set_bits: u64 = redis::cmd("BITCOUNT").arg(BLOOM_KEY).query_async(&mut conn).await?;
+        let memory = memory_usage(&mut conn, BLOOM_KEY).await?;
+        rows.push(CardinalityRow::new("Bloom", n, memory, bloom.estimate_count(set_bits), elapsed));
+
+        let measured_fpr = self.measure_false_positives(&mut conn, &bloom, n.clamp(1, 10_000)).await?;
+        println!("\n{}", render_report(n, &rows, measured_fpr));
+
+        let _: () = conn.del(&[SET_KEY, HLL_KEY, BLOOM_KEY]).await?;
+        info!("Cardinality comparison completed for {} members", n);
+        Ok(rows)
+    }
+
+    /// Probes members that were never inserted and counts how many the
+    /// filter wrongly reports as present.
+    async fn measure_false_positives(
+        &self,
+        conn: &mut RedisConnection,
+        bloom: &BloomFilter,
+        probes: usize,
+    ) -> Result<f64> {
+        let mut pipe = redis::pipe();
+        for i in 0..probes {
+            for position in bloom.positions(&format!("absent:{}", i)) {
+                pipe.getbit(BLOOM_KEY, position as usize);
+            }
+        }
+        let bits: Vec<u8> = pipe.query_async(conn).await?;
+        let false_positives = bits
+            .chunks(bloom.hashes as usize)
+            .filter(|chunk| chunk.iter().all(|bit| *bit == 1))
+            .count();
+        Ok(false_positives as f64 / probes as f64)
+    }
+}
+
+fn member_batches(n: usize) -> impl Iterator<Item = Vec<String>> {
+    (0..n).step_by(BATCH_SIZE).map(move |start| {
+        (start..(start + BATCH_SIZE).min(n))
+            .map(|i| format!("member:{}", i))
+            .collect()
+    })
+}
+
+pub(crate) async fn memory_usage(conn: &mut RedisConnection, key: &str) -> Result<u64> {
+    let bytes: Option<u64> = redis::cmd("MEMORY")
+        .arg("USAGE")
+        .arg(key)
+        .arg("SAMPLES")
+        .arg(0)
+        .query_async(conn)
+        .await?;
+    Ok(bytes.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bloom_sizing() {
+        let bloom = BloomFilter::with_rate(1_000_000, 0.01);
+        // ~9.6 bits per item and 7 hash functions for a 1% target.
+        assert!((9_500_000..9_700_000).contains(&bloom.bits));
+        assert_eq!(bloom.hashes, 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "between 0 and 1")]
+    fn test_bloom_rejects_rate_of_one() {
+        BloomFilter::with_rate(1000, 1.0);
+    }
+
+    #[test]
+    fn test_bloom_positions_are_stable_and_in_range() {
+        let bloom = BloomFilter::with_rate(1000, 0.01);
+        let first = bloom.positions("member:42");
+        assert_eq!(first, bloom.positions("member:42"));
+        assert_eq!(first.len(), bloom.hashes as usize);
+        assert!(first.iter().all(|p| *p < bloom.bits));
+        assert_ne!(first, bloom.positions("member:43"));
+    }
+
+    #[test]
+    fn test_bloom_estimate_count_in_memory() {
+        let n = 5000;
+        let bloom = BloomFilter::with_rate(n, 0.01);
+        let mut bits = vec![false; bloom.bits as usize];
+        for i in 0..n {
+            for p in bloom.positions(&format!("member:{}", i)) {
+                bits[p as usize] = true;
+            }
+        }
+        let set_bits = bits.iter().filter(|b| **b).count() as u64;
+        let estimate = bloom.estimate_count(set_bits);
+        assert!((estimate - n as f64).abs() / (n as f64) < 0.05);
+    }
+
+    #[test]
+    fn test_member_batches_cover_range() {
+        let batches: Vec<Vec<String>> = member_batches(25_001).collect();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[2].len(), 5001);
+        assert_eq!(batches[2].last().unwrap(), "member:25000");
+        assert_eq!(batches.iter().map(Vec::len).sum::<usize>(), 25_001);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(2048), "2.0 KB");
+        assert_eq!(format_bytes(12 * 1024 * 1024), "12.0 MB");
+    }
+
+    #[test]
+    fn test_render_report_contains_rows() {
+        let rows = vec![CardinalityRow::new("SET", 100, 4096, 100.0, Duration::from_millis(10))];
+        let report = render_report(100, &rows, 0.01);
+        assert!(report.contains("SET"));
+        assert!(report.contains("4.0 KB"));
+        assert!(report.contains("0.000%"));
+        assert!(report.contains("1.000%"));
+    }
+}
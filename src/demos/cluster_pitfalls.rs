@@ -0,0 +1,195 @@
+use crate::utils::cluster::{key_slot, same_slot};
+use crate::Result;
+use tracing::info;
+
+/// One multi-key operation, shown in a naive form and with hash tags.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiKeyCase {
+    pub operation: &'static str,
+    pub naive_keys: Vec<String>,
+    pub tagged_keys: Vec<String>,
+}
+
+pub fn cases() -> Vec<MultiKeyCase> {
+    let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+    vec![
+        MultiKeyCase {
+            operation: "MSET",
+            naive_keys: keys(&["user:1:name", "user:1:email"]),
+            tagged_keys: keys(&["{user:1}:name", "{user:1}:email"]),
+        },
+        MultiKeyCase {
+            operation: "SINTERSTORE",
+            naive_keys: keys(&["friends:common", "friends:alice", "friends:bob"]),
+            tagged_keys: keys(&["{friends}:common", "{friends}:alice", "{friends}:bob"]),
+        },
+        MultiKeyCase {
+            operation: "MULTI/EXEC",
+            naive_keys: keys(&["cart:42", "inventory:sku-7"]),
+            tagged_keys: keys(&["{order:42}:cart", "{order:42}:inventory"]),
+        },
+        MultiKeyCase {
+            operation: "RENAME",
+            naive_keys: keys(&["report:tmp", "report:final"]),
+            tagged_keys: keys(&["{report}:tmp", "{report}:final"]),
+        },
+    ]
+}
+
+pub fn describe_keys(keys: &[String]) -> String {
+    keys.iter()
+        .map(|key| format!("{} (slot {})", key, key_slot(key)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub struct ClusterPitfallsDemo;
+
+impl ClusterPitfallsDemo {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn demonstrate(&self, cluster_nodes: &[String]) -> Result<()> {
+        println!("\n=== Multi-Key Atomicity on Redis Cluster ===\n");
+        println!("Cluster shards keys across 16384 hash slots. A command touching keys in");
+        println!("different slots fails with CROSSSLOT, and MULTI/EXEC or Lua can't span slots.\n");
+
+        for (step, case) in cases().iter().enumerate() {
+            println!("{}. {}", step + 1, case.operation);
+            println!("   naive:  {}", describe_keys(&case.naive_keys));
+            println!(
+                "           => {}",
+                if same_slot(&case.naive_keys) { "same slot (works by luck)" } else { "CROSSSLOT on a cluster" }
+            );
+            println!("   tagged: {}", describe_keys(&case.tagged_keys));
+            println!("           => same slot, always safe");
+        }
+
+        println!("\n💡 Hash tags trade distribution for atomicity: everything sharing a tag lives");
+        println!("   on one shard, so tag by the entity you need atomic updates for (a user, an");
+        println!("   order), not by something global that would turn one node into a hotspot.");
+
+        self.verify_on_cluster(cluster_nodes).await?;
+        info!("Cluster pitfalls demo completed");
+        Ok(())
+    }
+
+    /// Runs every case's own command on the cluster and checks the naive
+    /// keys are refused (CROSSSLOT, or MOVED/EXECABORT when the slots live
+    /// on different nodes) while the tagged ones go through.
+    #[cfg(feature = "cluster")]
+    async fn verify_on_cluster(&self, cluster_nodes: &[String]) -> Result<()> {
+        use crate::utils::DisplaySafe;
+        use crate::DemoError;
+        use redis::AsyncCommands;
+
+        if cluster_nodes.is_empty() {
+            println!("\n(Pass --cluster-node redis://host:port to verify against a live cluster.)");
+            return Ok(());
+        }
+
+        let nodes: Vec<String> = cluster_nodes.iter().map(|node| DisplaySafe(node).to_string()).collect();
+        println!("\nVerifying against cluster {}:", nodes.join(", "));
+        let client = redis::cluster::ClusterClient::new(cluster_nodes.to_vec())?;
+        let mut conn = client.get_async_connection().await?;
+
+        let mut unexpected = Vec::new();
+        for case in cases() {
+            for (label, keys) in [("naive", &case.naive_keys), ("tagged", &case.tagged_keys)] {
+                let result = run_case(&mut conn, case.operation, keys).await;
+                let expected = match (&result, same_slot(keys)) {
+                    (Ok(()), true) => true,
+                    (Err(e), false) => matches!(e.code(), Some("CROSSSLOT" | "MOVED" | "ASK" | "EXECABORT")),
+                    _ => false,
+                };
+                let outcome = match &result {
+                    Ok(()) => "OK".to_string(),
+                    Err(e) => e.to_string(),
+                };
+                println!("   {} {:<12} {:<7} => {}", if expected { "✅" } else { "❌" }, case.operation, label, outcome);
+                if !expected {
+                    unexpected.push(format!("{} ({})", case.operation, label));
+                }
+                for key in keys {
+                    let _: redis::RedisResult<()> = conn.del(key).await;
+                }
+            }
+        }
+        if !unexpected.is_empty() {
+            return Err(DemoError::Demo(format!("Cluster didn't behave as expected for {}", unexpected.join(", "))));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "cluster"))]
+    async fn verify_on_cluster(&self, cluster_nodes: &[String]) -> Result<()> {
+        if !cluster_nodes.is_empty() {
+            println!("\n⚠️  Rebuild with `--features cluster` to verify against a live cluster.");
+        }
+        Ok(())
+    }
+}
+
+/// Sends `operation` over `keys` as-is to the primary of the first key's
+/// slot, the way a single-node client would. The cluster client would
+/// otherwise split MSET per slot and hide the error.
+#[cfg(feature = "cluster")]
+async fn run_case(conn: &mut redis::cluster_async::ClusterConnection, operation: &str, keys: &[String]) -> redis::RedisResult<()> {
+    use redis::cluster_routing::{Route, RoutingInfo, SingleNodeRoutingInfo, SlotAddr};
+
+    let route = SingleNodeRoutingInfo::SpecificNode(Route::new(key_slot(&keys[0]), SlotAddr::Master));
+    let mut cmd = redis::cmd(operation);
+    match operation {
+        "MSET" => {
+            for key in keys {
+                cmd.arg(key).arg("1");
+            }
+        }
+        "RENAME" => {
+            redis::cmd("SET").arg(&keys[0]).arg("draft").query_async::<_, ()>(conn).await?;
+            cmd.arg(keys);
+        }
+        "MULTI/EXEC" => {
+            let mut pipe = redis::pipe();
+            pipe.atomic();
+            for key in keys {
+                pipe.set(key, "1").ignore();
+            }
+            // An atomic pipeline's only reply is EXEC's, after MULTI and the
+            // queued commands.
+            conn.route_pipeline(&pipe, keys.len() + 1, 1, route).await?;
+            return Ok(());
+        }
+        _ => {
+            cmd.arg(keys);
+        }
+    }
+    conn.route_command(&cmd, RoutingInfo::SingleNode(route)).await?;
+    Ok(())
+}
+
+impl Default for ClusterPitfallsDemo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naive_cases_cross_slots_and_tagged_cases_do_not() {
+        for case in cases() {
+            assert!(!same_slot(&case.naive_keys), "{} naive keys share a slot", case.operation);
+            assert!(same_slot(&case.tagged_keys), "{} tagged keys differ", case.operation);
+        }
+    }
+
+    #[test]
+    fn test_describe_keys() {
+        let described = describe_keys(&["foo".to_string()]);
+        assert_eq!(described, "foo (slot 12182)");
+    }
+}
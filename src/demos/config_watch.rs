@@ -0,0 +1,74 @@
+use crate::utils::config_watch::{self, Config, ReloadStrategy};
+use crate::utils::key_watch::NotifyChange;
+use crate::{RedisClient, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Settings a running service might want to change without a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DemoSettings {
+    pub max_connections: u32,
+    pub rate_limit_per_sec: u32,
+    pub dark_mode: bool,
+    pub banner: Option<String>,
+}
+
+impl Default for DemoSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: 100,
+            rate_limit_per_sec: 50,
+            dark_mode: false,
+            banner: None,
+        }
+    }
+}
+
+pub struct ConfigWatchDemo {
+    client: RedisClient,
+}
+
+impl ConfigWatchDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Plays the consumer process: prints the configuration, then every new
+    /// version as it arrives, for `seconds`. Seeds defaults if nothing is stored.
+    /// `change` is the confirmed notification setting events need.
+    pub async fn watch(&self, name: &str, seconds: u64, strategy: ReloadStrategy, change: Option<NotifyChange>) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let mut config = Config::<DemoSettings>::watch(&self.client, name, strategy, change).await?;
+        if config.version() == 0 {
+            config_watch::publish(&mut conn, name, &DemoSettings::default()).await?;
+            config.changed().await?;
+        }
+
+        println!("\n=== Live Configuration: {} ({:?}) ===\n", name, strategy);
+        println!("   v{}: {:?}", config.version(), config.current());
+        println!("\n   Change it from another terminal, e.g.");
+        println!("   redis-demo config set --name {} --field max_connections --value 250\n", name);
+
+        let deadline = Instant::now() + Duration::from_secs(seconds);
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match tokio::time::timeout(remaining, config.changed()).await {
+                Ok(updated) => println!("   🔄 v{}: {:?}", config.version(), updated?),
+                Err(_) => break,
+            }
+        }
+
+        info!("Config watch completed");
+        Ok(())
+    }
+
+    /// Plays the producer process: updates one field and bumps the version.
+    pub async fn set(&self, name: &str, field: &str, value: &str) -> Result<u64> {
+        let mut conn = self.client.get_async_connection().await?;
+        let version = config_watch::set_field(&mut conn, name, field, value).await?;
+        println!("✅ {}.{} = {} (version {})", name, field, value, version);
+        Ok(version)
+    }
+}
@@ -0,0 +1,304 @@
+use crate::server::fleet::format_bytes;
+use crate::server::replication::parse_info;
+use crate::utils::{RedisConnection, Sections};
+use crate::{DemoError, RedisClient, Result};
+use futures::future::join_all;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StormOptions {
+    /// Callers, each wanting to talk to Redis at once.
+    pub callers: usize,
+    /// Connections the pooled client shares between them.
+    pub pool_size: u32,
+    /// Lower the server's `maxclients` to this for the run, so the storm
+    /// hits it; the old value is put back afterwards.
+    pub maxclients: Option<u64>,
+}
+
+impl Default for StormOptions {
+    fn default() -> Self {
+        Self { callers: 300, pool_size: 10, maxclients: None }
+    }
+}
+
+/// The INFO fields a storm moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ServerLoad {
+    pub connected_clients: u64,
+    pub used_memory: u64,
+    pub rejected_connections: u64,
+}
+
+impl ServerLoad {
+    pub fn parse(info: &str) -> Self {
+        let fields = parse_info(info);
+        let field = |name: &str| fields.get(name).and_then(|value| value.parse().ok()).unwrap_or(0);
+        Self {
+            connected_clients: field("connected_clients"),
+            used_memory: field("used_memory"),
+            rejected_connections: field("rejected_connections"),
+        }
+    }
+
+    pub async fn fetch(conn: &mut RedisConnection) -> Result<Self> {
+        let info: String = redis::cmd("INFO").query_async(conn).await?;
+        Ok(Self::parse(&info))
+    }
+}
+
+/// How one way of connecting served every caller.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StormRun {
+    pub name: &'static str,
+    pub callers: usize,
+    pub failed: usize,
+    pub first_error: Option<String>,
+    /// Connections the server had on top of the ones before the run.
+    pub extra_connections: u64,
+    pub rejected: u64,
+    pub memory_delta: i64,
+    pub elapsed: Duration,
+}
+
+impl StormRun {
+    fn measure(name: &'static str, callers: usize, before: ServerLoad, during: ServerLoad, elapsed: Duration) -> Self {
+        Self {
+            name,
+            callers,
+            failed: 0,
+            first_error: None,
+            extra_connections: during.connected_clients.saturating_sub(before.connected_clients),
+            rejected: during.rejected_connections.saturating_sub(before.rejected_connections),
+            memory_delta: during.used_memory as i64 - before.used_memory as i64,
+            elapsed,
+        }
+    }
+
+    fn with_errors(mut self, errors: &[String]) -> Self {
+        self.failed = errors.len();
+        self.first_error = errors.first().cloned();
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StormReport {
+    pub runs: Vec<StormRun>,
+    /// Storm connections found in CLIENT LIST and closed with CLIENT KILL.
+    pub killed: usize,
+}
+
+impl StormReport {
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "   {:<22} {:>8} {:>8} {:>12} {:>9} {:>11} {:>10}\n",
+            "", "callers", "failed", "connections", "rejected", "memory", "elapsed"
+        );
+        for run in &self.runs {
+            let memory = match run.memory_delta {
+                delta if delta < 0 => format!("-{}", format_bytes(delta.unsigned_abs())),
+                delta => format!("+{}", format_bytes(delta as u64)),
+            };
+            out.push_str(&format!(
+                "   {:<22} {:>8} {:>8} {:>12} {:>9} {:>11} {:>8}ms\n",
+                run.name,
+                run.callers,
+                run.failed,
+                run.extra_connections,
+                run.rejected,
+                memory,
+                run.elapsed.as_millis()
+            ));
+        }
+        out
+    }
+}
+
+/// `(id, name)` of every connection in CLIENT LIST output.
+pub fn parse_client_list(text: &str) -> Vec<(u64, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let field = |name: &str| line.split(' ').find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='));
+            Some((field("id")?.parse().ok()?, field("name").unwrap_or_default().to_string()))
+        })
+        .collect()
+}
+
+pub fn is_maxclients_error(message: &str) -> bool {
+    message.contains("max number of clients reached")
+}
+
+pub struct ConnectionStormDemo {
+    client: RedisClient,
+}
+
+impl ConnectionStormDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn demonstrate(&self, options: &StormOptions) -> Result<StormReport> {
+        let mut conn = self.client.get_async_connection().await?;
+
+        println!("\n=== Connection Storm Demo ===\n");
+        let previous = match options.maxclients {
+            Some(limit) => {
+                let previous: Vec<String> = redis::cmd("CONFIG").arg("GET").arg("maxclients").query_async(&mut conn).await?;
+                let _: () = redis::cmd("CONFIG").arg("SET").arg("maxclients").arg(limit).query_async(&mut conn).await?;
+                println!("maxclients lowered from {} to {} for this run\n", previous.get(1).map_or("?", String::as_str), limit);
+                previous.get(1).cloned()
+            }
+            None => None,
+        };
+
+        let result = self.storm_and_pool(&mut conn, options).await;
+
+        if let Some(previous) = previous {
+            let _: () = redis::cmd("CONFIG").arg("SET").arg("maxclients").arg(&previous).query_async(&mut conn).await?;
+            println!("\nmaxclients restored to {}", previous);
+        }
+        let report = result?;
+
+        println!("\nSummary:");
+        print!("{}", report.render());
+        println!("\n💡 A connection per caller costs the server a socket, a client struct and");
+        println!("   buffers for each one, and past maxclients new callers are turned away.");
+        println!("   A pool (or one multiplexed connection) serves the same callers with a");
+        println!("   fixed, small number of sockets.");
+        info!("Connection storm demo completed");
+        Ok(report)
+    }
+
+    async fn storm_and_pool(&self, conn: &mut RedisConnection, options: &StormOptions) -> Result<StormReport> {
+        let mut report = StormReport::default();
+        let storm_name = format!("storm-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+
+        let mut sections = Sections::new();
+        sections.next(&format!("Storm: {} callers each opening a connection:", options.callers));
+        let before = ServerLoad::fetch(conn).await?;
+        println!("   Before: {} clients connected, {} used", before.connected_clients, format_bytes(before.used_memory));
+        let started = Instant::now();
+        let attempts = join_all((0..options.callers).map(|n| {
+            let name = format!("{}-{}", storm_name, n);
+            async move {
+                let mut storm = self.client.get_dedicated_connection().await?;
+                let _: () = redis::cmd("CLIENT").arg("SETNAME").arg(&name).query_async(&mut storm).await?;
+                let _: () = redis::cmd("PING").query_async(&mut storm).await?;
+                Ok::<_, DemoError>(storm)
+            }
+        }))
+        .await;
+        let elapsed = started.elapsed();
+        let (open, errors): (Vec<_>, Vec<_>) = attempts.into_iter().partition(|attempt| attempt.is_ok());
+        let errors: Vec<String> = errors.into_iter().filter_map(|attempt| attempt.err()).map(|e| e.to_string()).collect();
+        let during = ServerLoad::fetch(conn).await?;
+        let storm = StormRun::measure("connection per caller", options.callers, before, during, elapsed).with_errors(&errors);
+        println!("   During: {} clients connected, {} used", during.connected_clients, format_bytes(during.used_memory));
+        println!("   {} connected, {} failed", open.len(), errors.len());
+        if let Some(error) = &storm.first_error {
+            let kind = if is_maxclients_error(error) { "maxclients" } else { "error" };
+            println!("   ❌ {}: {}", kind, error);
+        }
+        if storm.rejected > 0 {
+            println!("   Server rejected {} connections (INFO stats rejected_connections)", storm.rejected);
+        }
+        if storm.extra_connections > 0 && storm.memory_delta > 0 {
+            println!("   ≈ {} per connection", format_bytes(storm.memory_delta as u64 / storm.extra_connections));
+        }
+        report.runs.push(storm);
+
+        sections.next("Cleaning up with CLIENT KILL:");
+        let list: String = redis::cmd("CLIENT").arg("LIST").arg("TYPE").arg("normal").query_async(conn).await?;
+        for (id, _) in parse_client_list(&list).into_iter().filter(|(_, name)| name.starts_with(&storm_name)) {
+            let killed: u64 = redis::cmd("CLIENT").arg("KILL").arg("ID").arg(id).query_async(conn).await?;
+            report.killed += killed as usize;
+        }
+        drop(open);
+        println!("   CLIENT KILL ID ... closed {} connections named {}-*", report.killed, storm_name);
+
+        sections.next(&format!("Pooled: the same callers sharing an r2d2 pool of {}:", options.pool_size));
+        let before = ServerLoad::fetch(conn).await?;
+        let started = Instant::now();
+        let pool = Arc::new(self.client.sync_pool(options.pool_size)?);
+        let calls = join_all((0..options.callers).map(|_| {
+            let pool = pool.clone();
+            tokio::task::spawn_blocking(move || -> Result<()> {
+                let mut pooled = pool.get()?;
+                let _: () = redis::cmd("PING").query(&mut *pooled)?;
+                Ok(())
+            })
+        }))
+        .await;
+        let elapsed = started.elapsed();
+        let errors: Vec<String> = calls
+            .into_iter()
+            .filter_map(|call| match call {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(e) => Some(e.to_string()),
+            })
+            .collect();
+        let during = ServerLoad::fetch(conn).await?;
+        let pooled = StormRun::measure("r2d2 pool", options.callers, before, during, elapsed).with_errors(&errors);
+        println!("   {} callers served over {} connections, {} failed", options.callers, pooled.extra_connections, pooled.failed);
+        drop(pool);
+        report.runs.push(pooled);
+
+        sections.next("Multiplexed: the same callers sharing one async connection:");
+        let before = ServerLoad::fetch(conn).await?;
+        let started = Instant::now();
+        let shared = self.client.get_async_connection().await?;
+        let calls = join_all((0..options.callers).map(|_| {
+            let mut shared = shared.clone();
+            async move { redis::cmd("PING").query_async::<_, ()>(&mut shared).await }
+        }))
+        .await;
+        let elapsed = started.elapsed();
+        let errors: Vec<String> = calls.into_iter().filter_map(|call| call.err()).map(|e| e.to_string()).collect();
+        let during = ServerLoad::fetch(conn).await?;
+        let multiplexed = StormRun::measure("multiplexed", options.callers, before, during, elapsed).with_errors(&errors);
+        println!("   {} callers served over {} connections, {} failed", options.callers, multiplexed.extra_connections, multiplexed.failed);
+        if multiplexed.failed > 0 {
+            warn!("{} multiplexed calls failed", multiplexed.failed);
+        }
+        report.runs.push(multiplexed);
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_load_from_info() {
+        let info = "# Clients\r\nconnected_clients:12\r\n# Memory\r\nused_memory:1048576\r\n# Stats\r\nrejected_connections:3\r\n";
+        assert_eq!(ServerLoad::parse(info), ServerLoad { connected_clients: 12, used_memory: 1_048_576, rejected_connections: 3 });
+        assert_eq!(ServerLoad::parse(""), ServerLoad::default());
+    }
+
+    #[test]
+    fn test_parse_client_list() {
+        let list = "id=3 addr=127.0.0.1:50010 laddr=127.0.0.1:6379 fd=8 name= age=10 idle=0\n\
+                    id=7 addr=127.0.0.1:50012 laddr=127.0.0.1:6379 fd=9 name=storm-ab12cd34-0 age=1 idle=1\n";
+        assert_eq!(parse_client_list(list), [(3, String::new()), (7, "storm-ab12cd34-0".to_string())]);
+        assert!(is_maxclients_error("ERR max number of clients reached"));
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let before = ServerLoad { connected_clients: 2, used_memory: 1_000_000, rejected_connections: 0 };
+        let during = ServerLoad { connected_clients: 102, used_memory: 3_000_000, rejected_connections: 5 };
+        let run = StormRun::measure("connection per caller", 105, before, during, Duration::from_millis(40))
+            .with_errors(&vec!["ERR max number of clients reached".to_string(); 5]);
+        assert_eq!((run.extra_connections, run.rejected, run.memory_delta, run.failed), (100, 5, 2_000_000, 5));
+        let report = StormReport { runs: vec![run], killed: 100 };
+        let rendered = report.render();
+        assert!(rendered.contains("connection per caller"), "{}", rendered);
+        assert!(rendered.contains("40ms"), "{}", rendered);
+    }
+}
@@ -0,0 +1,378 @@
+use crate::utils::{KeyScanner, KeyType};
+use crate::{DemoError, RedisClient, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::str::FromStr;
+use tracing::info;
+
+const ID_PLACEHOLDER: &str = "{id}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Dot,
+    D2,
+}
+
+impl DiagramFormat {
+    /// Picks the format from an output path's extension, defaulting to DOT.
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".d2") {
+            DiagramFormat::D2
+        } else {
+            DiagramFormat::Dot
+        }
+    }
+}
+
+impl FromStr for DiagramFormat {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "dot" | "graphviz" => Ok(DiagramFormat::Dot),
+            "d2" => Ok(DiagramFormat::D2),
+            other => Err(DemoError::Configuration(format!("Unknown diagram format: {}", other))),
+        }
+    }
+}
+
+/// A group of keys sharing the same naming template, e.g. `user:{id}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyFamily {
+    pub template: String,
+    pub key_type: KeyType,
+    pub count: usize,
+    pub sample_key: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    /// `user:{id}` owns `user:{id}:sessions`.
+    Owns,
+    /// `username:{value}` stores the id of a `user:{id}`.
+    Indexes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relation {
+    pub from: String,
+    pub to: String,
+    pub kind: RelationKind,
+    pub label: String,
+}
+
+/// Data model inferred purely from key naming conventions.
+#[derive(Debug, Default)]
+pub struct DataModel {
+    families: BTreeMap<String, KeyFamily>,
+    relations: Vec<Relation>,
+    keys: HashSet<String>,
+}
+
+/// Treats segments containing a digit (numeric ids, `abc123`, dates) or
+/// shaped like a UUID as identifiers rather than literal names.
+pub fn is_id_segment(segment: &str) -> bool {
+    segment.bytes().any(|b| b.is_ascii_digit())
+        || (segment.len() == 36 && segment.matches('-').count() == 4)
+}
+
+/// Replaces identifier segments in a key with `{id}`.
+pub fn key_template(key: &str) -> String {
+    key.split(':')
+        .map(|segment| if is_id_segment(segment) { ID_PLACEHOLDER } else { segment })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+impl DataModel {
+    pub fn from_keys(keys: &[(String, KeyType)]) -> Self {
+        let mut model = DataModel::default();
+
+        for (key, key_type) in keys {
+            model.keys.insert(key.clone());
+            model
+                .families
+                .entry(key_template(key))
+                .and_modify(|family| family.count += 1)
+                .or_insert_with(|| KeyFamily {
+                    template: key_template(key),
+                    key_type: *key_type,
+                    count: 1,
+                    sample_key: key.clone(),
+                });
+        }
+
+        // Collapse literal-valued families such as `username:alice` and
+        // `username:bob` into a single `username:{value}` family.
+        let mut by_prefix: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for template in model.families.keys() {
+            let segments: Vec<&str> = template.split(':').collect();
+            if segments.len() == 2 && segments[1] != ID_PLACEHOLDER {
+                by_prefix.entry(segments[0].to_string()).or_default().push(template.clone());
+            }
+        }
+        for (prefix, templates) in by_prefix {
+            if templates.len() < 2 {
+                continue;
+            }
+            let mut merged: Option<KeyFamily> = None;
+            for template in templates {
+                let family = model.families.remove(&template).unwrap();
+                merged = Some(match merged {
+                    Some(mut acc) => {
+                        acc.count += family.count;
+                        acc
+                    }
+                    None => family,
+                });
+            }
+            let mut merged = merged.unwrap();
+            merged.template = format!("{}:{{value}}", prefix);
+            model.families.insert(merged.template.clone(), merged);
+        }
+
+        for template in model.families.keys() {
+            let segments: Vec<&str> = template.split(':').collect();
+            if segments.len() > 2 && segments[1] == ID_PLACEHOLDER {
+                model.relations.push(Relation {
+                    from: segments[..2].join(":"),
+                    to: template.clone(),
+                    kind: RelationKind::Owns,
+                    label: segments[2..].join(":"),
+                });
+            }
+        }
+
+        model
+    }
+
+    pub fn families(&self) -> impl Iterator<Item = &KeyFamily> {
+        self.families.values()
+    }
+
+    pub fn relations(&self) -> &[Relation] {
+        &self.relations
+    }
+
+    /// Two-segment string families whose value might reference another entity.
+    pub fn index_candidates(&self) -> Vec<(String, String)> {
+        self.families
+            .values()
+            .filter(|family| family.key_type == KeyType::String && family.template.split(':').count() == 2)
+            .map(|family| (family.template.clone(), family.sample_key.clone()))
+            .collect()
+    }
+
+    /// Records an index relation if `value` names the id of a known entity.
+    pub fn link_index(&mut self, template: &str, value: &str) {
+        let target = self
+            .families
+            .keys()
+            .filter(|candidate| candidate.as_str() != template && candidate.ends_with(":{id}"))
+            .filter(|candidate| candidate.split(':').count() == 2)
+            .find(|candidate| {
+                let prefix = candidate.trim_end_matches(":{id}");
+                self.keys.contains(&format!("{}:{}", prefix, value))
+            })
+            .cloned();
+
+        if let Some(target) = target {
+            self.relations.push(Relation {
+                from: template.to_string(),
+                to: target,
+                kind: RelationKind::Indexes,
+                label: "index".to_string(),
+            });
+        }
+    }
+
+    pub fn render(&self, format: DiagramFormat) -> String {
+        match format {
+            DiagramFormat::Dot => self.to_dot(),
+            DiagramFormat::D2 => self.to_d2(),
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph redis_model {\n    rankdir=LR;\n    node [shape=box];\n");
+        for family in self.families.values() {
+            let _ = writeln!(
+                out,
+                "    \"{}\" [label=\"{}\\n{} ({} keys)\"];",
+                family.template, family.template, family.key_type, family.count
+            );
+        }
+        for relation in &self.relations {
+            let style = match relation.kind {
+                RelationKind::Owns => "solid",
+                RelationKind::Indexes => "dashed",
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [label=\"{}\", style={}];",
+                relation.from, relation.to, relation.label, style
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_d2(&self) -> String {
+        let mut out = String::from("direction: right\n");
+        for family in self.families.values() {
+            let _ = writeln!(
+                out,
+                "\"{}\": \"{}\\n{} ({} keys)\"",
+                family.template, family.template, family.key_type, family.count
+            );
+        }
+        for relation in &self.relations {
+            let _ = write!(out, "\"{}\" -> \"{}\": {}", relation.from, relation.to, relation.label);
+            if relation.kind == RelationKind::Indexes {
+                out.push_str(" {style.stroke-dash: 3}");
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+pub struct DataModelDemo {
+    client: RedisClient,
+}
+
+impl DataModelDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Scans keys matching `pattern`, infers the data model and renders it.
+    pub async fn graph(&self, pattern: &str, format: DiagramFormat) -> Result<String> {
+        let mut conn = self.client.get_async_connection().await?;
+        let mut scanner = KeyScanner::new(conn.clone(), pattern, None);
+        let mut seen = HashSet::new();
+        let mut keys = Vec::new();
+
+        while let Some(batch) = scanner.next_batch().await? {
+            let batch: Vec<String> = batch.into_iter().filter(|key| seen.insert(key.clone())).collect();
+            if batch.is_empty() {
+                continue;
+            }
+            let mut pipe = redis::pipe();
+            for key in &batch {
+                pipe.cmd("TYPE").arg(key);
+            }
+            let types: Vec<String> = pipe.query_async(&mut conn).await?;
+            for (key, key_type) in batch.into_iter().zip(types) {
+                // Keys may expire between SCAN and TYPE ("none").
+                if let Ok(key_type) = key_type.parse::<KeyType>() {
+                    keys.push((key, key_type));
+                }
+            }
+        }
+
+        let mut model = DataModel::from_keys(&keys);
+        for (template, sample_key) in model.index_candidates() {
+            let value: Option<String> = redis::cmd("GET").arg(&sample_key).query_async(&mut conn).await?;
+            if let Some(value) = value {
+                model.link_index(&template, &value);
+            }
+        }
+
+        info!(
+            "Inferred {} key families and {} relations from {} keys",
+            model.families().count(),
+            model.relations().len(),
+            keys.len()
+        );
+        Ok(model.render(format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_keys() -> Vec<(String, KeyType)> {
+        vec![
+            ("user:1000".to_string(), KeyType::Hash),
+            ("user:1001".to_string(), KeyType::Hash),
+            ("user:1000:sessions".to_string(), KeyType::Set),
+            ("username:alice".to_string(), KeyType::String),
+            ("username:bob".to_string(), KeyType::String),
+            ("config".to_string(), KeyType::Hash),
+        ]
+    }
+
+    #[test]
+    fn test_key_template() {
+        assert_eq!(key_template("user:1000"), "user:{id}");
+        assert_eq!(key_template("user:1000:sessions"), "user:{id}:sessions");
+        assert_eq!(key_template("username:alice"), "username:alice");
+        assert_eq!(
+            key_template("user:6f1c2a9e-8d3b-4e5f-9a7b-1c2d3e4f5a6b"),
+            "user:{id}"
+        );
+    }
+
+    #[test]
+    fn test_families_are_grouped() {
+        let model = DataModel::from_keys(&sample_keys());
+        let templates: Vec<&str> = model.families().map(|f| f.template.as_str()).collect();
+        assert_eq!(
+            templates,
+            vec!["config", "user:{id}", "user:{id}:sessions", "username:{value}"]
+        );
+        let users = model.families().find(|f| f.template == "user:{id}").unwrap();
+        assert_eq!(users.count, 2);
+    }
+
+    #[test]
+    fn test_owns_relation_inferred() {
+        let model = DataModel::from_keys(&sample_keys());
+        assert_eq!(
+            model.relations(),
+            &[Relation {
+                from: "user:{id}".to_string(),
+                to: "user:{id}:sessions".to_string(),
+                kind: RelationKind::Owns,
+                label: "sessions".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_index_relation_linked_by_value() {
+        let mut model = DataModel::from_keys(&sample_keys());
+        let candidates = model.index_candidates();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].0, "username:{value}");
+
+        model.link_index("username:{value}", "9999");
+        assert_eq!(model.relations().len(), 1);
+
+        model.link_index("username:{value}", "1000");
+        let index = model.relations().last().unwrap();
+        assert_eq!(index.kind, RelationKind::Indexes);
+        assert_eq!(index.to, "user:{id}");
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let model = DataModel::from_keys(&sample_keys());
+        let dot = model.render(DiagramFormat::Dot);
+        assert!(dot.starts_with("digraph redis_model {"));
+        assert!(dot.contains("\"user:{id}\" -> \"user:{id}:sessions\""));
+
+        let d2 = model.render(DiagramFormat::D2);
+        assert!(d2.contains("\"user:{id}\" -> \"user:{id}:sessions\": sessions"));
+    }
+
+    #[test]
+    fn test_diagram_format_selection() {
+        assert_eq!(DiagramFormat::from_path("model.d2"), DiagramFormat::D2);
+        assert_eq!(DiagramFormat::from_path("model.dot"), DiagramFormat::Dot);
+        assert_eq!("graphviz".parse::<DiagramFormat>().unwrap(), DiagramFormat::Dot);
+        assert!("svg".parse::<DiagramFormat>().is_err());
+    }
+}
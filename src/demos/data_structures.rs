@@ -1,4 +1,5 @@
 use crate::{RedisClient, Result};
+use crate::utils::{lists, zset, Sections};
 use redis::AsyncCommands;
 use tracing::info;
 use std::collections::HashMap;
@@ -18,24 +19,25 @@ impl ListDemo {
         println!("\n=== List Operations Demo ===\n");
         
         // LPUSH and RPUSH
-        println!("1. LPUSH and RPUSH:");
-        conn.lpush("mylist", vec!["first", "second"]).await?;
-        conn.rpush("mylist", vec!["third", "fourth"]).await?;
+        let mut sections = Sections::new();
+        sections.next("LPUSH and RPUSH:");
+        let _: () = conn.lpush("mylist", vec!["first", "second"]).await?;
+        let _: () = conn.rpush("mylist", vec!["third", "fourth"]).await?;
         println!("   LPUSH mylist first second");
         println!("   RPUSH mylist third fourth");
         
         // LRANGE
-        println!("\n2. LRANGE (view list):");
+        sections.next("LRANGE (view list):");
         let list: Vec<String> = conn.lrange("mylist", 0, -1).await?;
         println!("   LRANGE mylist 0 -1 => {:?}", list);
         
         // LLEN
-        println!("\n3. LLEN (list length):");
+        sections.next("LLEN (list length):");
         let len: usize = conn.llen("mylist").await?;
         println!("   LLEN mylist => {}", len);
         
         // LPOP and RPOP
-        println!("\n4. LPOP and RPOP:");
+        sections.next("LPOP and RPOP:");
         let left_val: Option<String> = conn.lpop("mylist", None).await?;
         let right_val: Option<String> = conn.rpop("mylist", None).await?;
         println!("   LPOP mylist => {:?}", left_val);
@@ -45,25 +47,25 @@ impl ListDemo {
         println!("   List after pops: {:?}", list_after);
         
         // LINDEX
-        println!("\n5. LINDEX (get by index):");
+        sections.next("LINDEX (get by index):");
         let element: Option<String> = conn.lindex("mylist", 0).await?;
         println!("   LINDEX mylist 0 => {:?}", element);
         
         // LINSERT
-        println!("\n6. LINSERT:");
-        conn.linsert_before("mylist", "third", "inserted").await?;
+        sections.next("LINSERT:");
+        let _: () = conn.linsert_before("mylist", "third", "inserted").await?;
         let list_inserted: Vec<String> = conn.lrange("mylist", 0, -1).await?;
         println!("   LINSERT mylist BEFORE third inserted");
         println!("   List after insert: {:?}", list_inserted);
         
         // Message Queue Pattern
-        println!("\n7. Message Queue Pattern:");
-        conn.del("queue:tasks").await?;
+        sections.next("Message Queue Pattern:");
+        let _: () = conn.del("queue:tasks").await?;
         
         // Producer
         println!("   Producer adding tasks:");
         for i in 1..=5 {
-            conn.rpush("queue:tasks", format!("task-{}", i)).await?;
+            let _: () = conn.rpush("queue:tasks", format!("task-{}", i)).await?;
             println!("     Added task-{}", i);
         }
         
@@ -74,8 +76,8 @@ impl ListDemo {
         }
         
         // BLPOP (blocking pop)
-        println!("\n8. BLPOP (blocking pop with timeout):");
-        conn.rpush("queue:priority", "urgent-task").await?;
+        sections.next("BLPOP (blocking pop with timeout):");
+        let _: () = conn.rpush("queue:priority", "urgent-task").await?;
         
         let result: Option<(String, String)> = redis::cmd("BLPOP")
             .arg("queue:priority")
@@ -89,10 +91,33 @@ impl ListDemo {
             println!("   Received '{}' from queue '{}'", value, queue);
         }
         
+        // LPOS
+        sections.next("LPOS (find element positions):");
+        let _: () = conn.rpush("tags", vec!["rust", "redis", "rust", "tokio", "redis", "rust"]).await?;
+        let all_positions = lists::positions_of(&mut conn, "tags", "rust").await?;
+        let last_position = lists::position_of(&mut conn, "tags", "rust", -1).await?;
+        println!("   RPUSH tags rust redis rust tokio redis rust");
+        println!("   LPOS tags rust COUNT 0 => {:?}", all_positions);
+        println!("   LPOS tags rust RANK -1 => {:?}", last_position);
+
+        // Conditional LREM
+        sections.next("Conditional LREM (remove only if unchanged):");
+        let stale = lists::remove_if_at(&mut conn, "tags", 1, "rust").await?;
+        let removed = lists::remove_if_at(&mut conn, "tags", 1, "redis").await?;
+        println!("   Remove index 1 if 'rust' => {}", stale);
+        println!("   Remove index 1 if 'redis' => {}", removed);
+
+        // De-duplication via Lua
+        sections.next("De-duplicate list (Lua, keeps first occurrence):");
+        let duplicates = lists::dedupe_list(&mut conn, "tags").await?;
+        let deduped: Vec<String> = conn.lrange("tags", 0, -1).await?;
+        println!("   Removed {} duplicates => {:?}", duplicates, deduped);
+
         // Clean up
-        conn.del("mylist").await?;
-        conn.del("queue:tasks").await?;
-        conn.del("queue:priority").await?;
+        let _: () = conn.del("mylist").await?;
+        let _: () = conn.del("queue:tasks").await?;
+        let _: () = conn.del("queue:priority").await?;
+        let _: () = conn.del("tags").await?;
         
         info!("List operations demo completed");
         Ok(())
@@ -114,63 +139,64 @@ impl SetDemo {
         println!("\n=== Set Operations Demo ===\n");
         
         // SADD
-        println!("1. SADD (add members):");
-        conn.sadd("fruits", vec!["apple", "banana", "orange"]).await?;
-        conn.sadd("fruits", "apple").await?; // Duplicate, won't be added
-        conn.sadd("vegetables", vec!["carrot", "broccoli", "spinach"]).await?;
+        let mut sections = Sections::new();
+        sections.next("SADD (add members):");
+        let _: () = conn.sadd("fruits", vec!["apple", "banana", "orange"]).await?;
+        let _: () = conn.sadd("fruits", "apple").await?; // Duplicate, won't be added
+        let _: () = conn.sadd("vegetables", vec!["carrot", "broccoli", "spinach"]).await?;
         println!("   SADD fruits apple banana orange");
         println!("   SADD vegetables carrot broccoli spinach");
         
         // SMEMBERS
-        println!("\n2. SMEMBERS (get all members):");
+        sections.next("SMEMBERS (get all members):");
         let fruits: Vec<String> = conn.smembers("fruits").await?;
         println!("   SMEMBERS fruits => {:?}", fruits);
         
         // SCARD
-        println!("\n3. SCARD (set cardinality):");
+        sections.next("SCARD (set cardinality):");
         let count: usize = conn.scard("fruits").await?;
         println!("   SCARD fruits => {}", count);
         
         // SISMEMBER
-        println!("\n4. SISMEMBER (check membership):");
+        sections.next("SISMEMBER (check membership):");
         let is_member: bool = conn.sismember("fruits", "apple").await?;
         let not_member: bool = conn.sismember("fruits", "potato").await?;
         println!("   SISMEMBER fruits apple => {}", is_member);
         println!("   SISMEMBER fruits potato => {}", not_member);
         
         // SREM
-        println!("\n5. SREM (remove members):");
-        conn.srem("fruits", "banana").await?;
+        sections.next("SREM (remove members):");
+        let _: () = conn.srem("fruits", "banana").await?;
         let fruits_after: Vec<String> = conn.smembers("fruits").await?;
         println!("   SREM fruits banana");
         println!("   Fruits after removal: {:?}", fruits_after);
         
         // Set operations
-        conn.sadd("healthy", vec!["apple", "carrot", "spinach"]).await?;
+        let _: () = conn.sadd("healthy", vec!["apple", "carrot", "spinach"]).await?;
         
         // SUNION
-        println!("\n6. SUNION (union of sets):");
+        sections.next("SUNION (union of sets):");
         let union: Vec<String> = conn.sunion(&["fruits", "vegetables"]).await?;
         println!("   SUNION fruits vegetables => {:?}", union);
         
         // SINTER
-        println!("\n7. SINTER (intersection):");
+        sections.next("SINTER (intersection):");
         let inter: Vec<String> = conn.sinter(&["fruits", "healthy"]).await?;
         println!("   SINTER fruits healthy => {:?}", inter);
         
         // SDIFF
-        println!("\n8. SDIFF (difference):");
+        sections.next("SDIFF (difference):");
         let diff: Vec<String> = conn.sdiff(&["vegetables", "healthy"]).await?;
         println!("   SDIFF vegetables healthy => {:?}", diff);
         
         // Unique visitors pattern
-        println!("\n9. Unique Visitors Pattern:");
+        sections.next("Unique Visitors Pattern:");
         let today = "2024-01-15";
         let yesterday = "2024-01-14";
         
         // Simulate visitor IDs
-        conn.sadd(format!("visitors:{}", today), vec!["user1", "user2", "user3"]).await?;
-        conn.sadd(format!("visitors:{}", yesterday), vec!["user2", "user4", "user5"]).await?;
+        let _: () = conn.sadd(format!("visitors:{}", today), vec!["user1", "user2", "user3"]).await?;
+        let _: () = conn.sadd(format!("visitors:{}", yesterday), vec!["user2", "user4", "user5"]).await?;
         
         let today_count: usize = conn.scard(format!("visitors:{}", today)).await?;
         let returning: Vec<String> = conn.sinter(&[
@@ -182,8 +208,8 @@ impl SetDemo {
         println!("   Returning visitors: {:?}", returning);
         
         // SPOP and SRANDMEMBER
-        println!("\n10. SPOP and SRANDMEMBER:");
-        conn.sadd("lottery", vec!["ticket1", "ticket2", "ticket3", "ticket4"]).await?;
+        sections.next("SPOP and SRANDMEMBER:");
+        let _: () = conn.sadd("lottery", vec!["ticket1", "ticket2", "ticket3", "ticket4"]).await?;
         
         let random: Option<String> = conn.srandmember("lottery").await?;
         println!("   SRANDMEMBER lottery => {:?} (stays in set)", random);
@@ -192,8 +218,8 @@ impl SetDemo {
         println!("   SPOP lottery => {:?} (removed from set)", popped);
         
         // Clean up
-        conn.del(vec!["fruits", "vegetables", "healthy", "lottery"]).await?;
-        conn.del(vec![format!("visitors:{}", today), format!("visitors:{}", yesterday)]).await?;
+        let _: () = conn.del(vec!["fruits", "vegetables", "healthy", "lottery"]).await?;
+        let _: () = conn.del(vec![format!("visitors:{}", today), format!("visitors:{}", yesterday)]).await?;
         
         info!("Set operations demo completed");
         Ok(())
@@ -215,27 +241,28 @@ impl HashDemo {
         println!("\n=== Hash Operations Demo ===\n");
         
         // HSET and HGET
-        println!("1. HSET and HGET:");
-        conn.hset("user:1000", "name", "Alice Johnson").await?;
-        conn.hset("user:1000", "email", "alice@example.com").await?;
-        conn.hset("user:1000", "age", 28).await?;
+        let mut sections = Sections::new();
+        sections.next("HSET and HGET:");
+        let _: () = conn.hset("user:1000", "name", "Alice Johnson").await?;
+        let _: () = conn.hset("user:1000", "email", "alice@example.com").await?;
+        let _: () = conn.hset("user:1000", "age", 28).await?;
         
         let name: String = conn.hget("user:1000", "name").await?;
         println!("   HSET user:1000 name 'Alice Johnson'");
         println!("   HGET user:1000 name => '{}'", name);
         
         // HMSET (set multiple fields)
-        println!("\n2. HMSET (multiple fields):");
+        sections.next("HMSET (multiple fields):");
         let user_data = vec![
             ("city", "New York"),
             ("country", "USA"),
             ("occupation", "Software Engineer"),
         ];
-        conn.hset_multiple("user:1000", &user_data).await?;
+        let _: () = conn.hset_multiple("user:1000", &user_data).await?;
         println!("   HMSET user:1000 city 'New York' country 'USA' occupation 'Software Engineer'");
         
         // HGETALL
-        println!("\n3. HGETALL (get all fields):");
+        sections.next("HGETALL (get all fields):");
         let user: HashMap<String, String> = conn.hgetall("user:1000").await?;
         println!("   HGETALL user:1000:");
         for (field, value) in &user {
@@ -243,43 +270,43 @@ impl HashDemo {
         }
         
         // HKEYS and HVALS
-        println!("\n4. HKEYS and HVALS:");
+        sections.next("HKEYS and HVALS:");
         let keys: Vec<String> = conn.hkeys("user:1000").await?;
         let vals: Vec<String> = conn.hvals("user:1000").await?;
         println!("   HKEYS user:1000 => {:?}", keys);
         println!("   HVALS user:1000 => {:?}", vals);
         
         // HEXISTS
-        println!("\n5. HEXISTS:");
+        sections.next("HEXISTS:");
         let has_email: bool = conn.hexists("user:1000", "email").await?;
         let has_phone: bool = conn.hexists("user:1000", "phone").await?;
         println!("   HEXISTS user:1000 email => {}", has_email);
         println!("   HEXISTS user:1000 phone => {}", has_phone);
         
         // HINCRBY
-        println!("\n6. HINCRBY (increment field):");
-        conn.hincr("user:1000", "login_count", 1).await?;
-        conn.hincr("user:1000", "login_count", 2).await?;
+        sections.next("HINCRBY (increment field):");
+        let _: () = conn.hincr("user:1000", "login_count", 1).await?;
+        let _: () = conn.hincr("user:1000", "login_count", 2).await?;
         let count: i64 = conn.hget("user:1000", "login_count").await?;
         println!("   HINCRBY user:1000 login_count 1");
         println!("   HINCRBY user:1000 login_count 2");
         println!("   login_count => {}", count);
         
         // HDEL
-        println!("\n7. HDEL (delete fields):");
-        conn.hdel("user:1000", "occupation").await?;
+        sections.next("HDEL (delete fields):");
+        let _: () = conn.hdel("user:1000", "occupation").await?;
         let exists_after: bool = conn.hexists("user:1000", "occupation").await?;
         println!("   HDEL user:1000 occupation");
         println!("   Field exists after deletion: {}", exists_after);
         
         // Shopping Cart Pattern
-        println!("\n8. Shopping Cart Pattern:");
+        sections.next("Shopping Cart Pattern:");
         let cart_key = "cart:session123";
         
         // Add items to cart
-        conn.hset(cart_key, "product:101", 2).await?; // 2 units
-        conn.hset(cart_key, "product:102", 1).await?; // 1 unit
-        conn.hset(cart_key, "product:103", 3).await?; // 3 units
+        let _: () = conn.hset(cart_key, "product:101", 2).await?; // 2 units
+        let _: () = conn.hset(cart_key, "product:102", 1).await?; // 1 unit
+        let _: () = conn.hset(cart_key, "product:103", 3).await?; // 3 units
         
         println!("   Shopping cart contents:");
         let cart: HashMap<String, i32> = conn.hgetall(cart_key).await?;
@@ -288,7 +315,7 @@ impl HashDemo {
         }
         
         // Update quantity
-        conn.hincr(cart_key, "product:101", 1).await?;
+        let _: () = conn.hincr(cart_key, "product:101", 1).await?;
         let new_qty: i32 = conn.hget(cart_key, "product:101").await?;
         println!("   Updated product:101 quantity => {} units", new_qty);
         
@@ -298,13 +325,90 @@ impl HashDemo {
         println!("   Total items in cart: {}", total_items);
         
         // Clean up
-        conn.del(vec!["user:1000", cart_key]).await?;
+        let _: () = conn.del(vec!["user:1000", cart_key]).await?;
         
         info!("Hash operations demo completed");
         Ok(())
     }
 }
 
+pub struct SortedSetDemo {
+    client: RedisClient,
+}
+
+impl SortedSetDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn demonstrate(&self) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        
+        println!("\n=== Sorted Set Operations Demo ===\n");
+        
+        // ZADD and ZRANGE
+        let mut sections = Sections::new();
+        sections.next("ZADD and ZRANGE WITHSCORES:");
+        let _: () = conn.zadd_multiple("scores", &[(120, "alice"), (95, "bob"), (150, "carol")]).await?;
+        let ranked: Vec<(String, i64)> = conn.zrevrange_withscores("scores", 0, -1).await?;
+        println!("   ZADD scores 120 alice 95 bob 150 carol");
+        println!("   ZREVRANGE scores 0 -1 WITHSCORES => {:?}", ranked);
+        
+        // ZINCRBY and ZRANK
+        sections.next("ZINCRBY and ZREVRANK:");
+        let new_score: i64 = conn.zincr("scores", "bob", 60).await?;
+        let rank: Option<usize> = conn.zrevrank("scores", "bob").await?;
+        println!("   ZINCRBY scores 60 bob => {}", new_score);
+        println!("   ZREVRANK scores bob => {:?}", rank);
+        
+        // ZRANGEBYLEX pagination
+        sections.next("ZRANGEBYLEX keyset pagination (same score, encoded members):");
+        let members: Vec<(i64, String)> = (1..=7)
+            .map(|i| (0, zset::encode_lex_member(1_700_000_000 + (i % 3), &format!("order-{}", i))))
+            .collect();
+        let _: () = conn.zadd_multiple("orders:by_time", &members).await?;
+        let mut cursor: Option<String> = None;
+        let mut page_number = 1;
+        loop {
+            let page = zset::range_by_lex_page(&mut conn, "orders:by_time", cursor.as_deref(), 3).await?;
+            let ids: Vec<&str> = page
+                .members
+                .iter()
+                .filter_map(|member| zset::decode_lex_member(member).map(|(_, id)| id))
+                .collect();
+            println!("   Page {} => {:?}", page_number, ids);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+            page_number += 1;
+        }
+        
+        // Multi-field ordering in a single score
+        sections.next("Feed ordered by (pinned, timestamp) in one score:");
+        let posts = [
+            ("post:1", false, 1_700_000_000_000u64),
+            ("post:2", true, 1_690_000_000_000u64),
+            ("post:3", false, 1_700_000_500_000u64),
+            ("post:4", false, 1_699_000_000_000u64),
+        ];
+        for (id, pinned, timestamp) in posts {
+            let _: () = conn.zadd("feed:home", id, zset::composite_score(pinned, timestamp)).await?;
+        }
+        let feed: Vec<(String, f64)> = conn.zrevrange_withscores("feed:home", 0, -1).await?;
+        for (id, score) in &feed {
+            let (pinned, timestamp) = zset::decode_composite_score(*score);
+            println!("   {} pinned={} timestamp={}", id, pinned, timestamp);
+        }
+        
+        // Clean up
+        let _: () = conn.del(vec!["scores", "orders:by_time", "feed:home"]).await?;
+        
+        info!("Sorted set operations demo completed");
+        Ok(())
+    }
+}
+
 #[path = "data_structures_tests.rs"]
 #[cfg(test)]
 mod data_structures_tests;
\ No newline at end of file
@@ -5,16 +5,12 @@ mod list_tests {
     use redis::AsyncCommands;
     
     async fn get_test_client() -> RedisClient {
-        RedisClient::new("redis://localhost:6379/15").unwrap()
+        RedisClient::new("redis://localhost:6379/15").unwrap().with_key_prefix("test:")
     }
     
     async fn cleanup_keys(client: &RedisClient, keys: &[&str]) {
         let mut conn = client.get_async_connection().await.unwrap();
-        // First do a FLUSHDB to ensure clean state
-        let _: String = redis::cmd("FLUSHDB")
-            .query_async(&mut conn)
-            .await
-            .unwrap_or_default();
+        let _: () = conn.del(keys).await.unwrap_or_default();
     }
     
     #[tokio::test]
@@ -84,16 +80,12 @@ mod set_tests {
     use redis::AsyncCommands;
     
     async fn get_test_client() -> RedisClient {
-        RedisClient::new("redis://localhost:6379/15").unwrap()
+        RedisClient::new("redis://localhost:6379/15").unwrap().with_key_prefix("test:")
     }
     
     async fn cleanup_keys(client: &RedisClient, keys: &[&str]) {
         let mut conn = client.get_async_connection().await.unwrap();
-        // First do a FLUSHDB to ensure clean state
-        let _: String = redis::cmd("FLUSHDB")
-            .query_async(&mut conn)
-            .await
-            .unwrap_or_default();
+        let _: () = conn.del(keys).await.unwrap_or_default();
     }
     
     #[tokio::test]
@@ -191,16 +183,12 @@ mod hash_tests {
     use std::collections::HashMap;
     
     async fn get_test_client() -> RedisClient {
-        RedisClient::new("redis://localhost:6379/15").unwrap()
+        RedisClient::new("redis://localhost:6379/15").unwrap().with_key_prefix("test:")
     }
     
     async fn cleanup_keys(client: &RedisClient, keys: &[&str]) {
         let mut conn = client.get_async_connection().await.unwrap();
-        // First do a FLUSHDB to ensure clean state
-        let _: String = redis::cmd("FLUSHDB")
-            .query_async(&mut conn)
-            .await
-            .unwrap_or_default();
+        let _: () = conn.del(keys).await.unwrap_or_default();
     }
     
     #[tokio::test]
@@ -294,4 +282,70 @@ mod hash_tests {
         let result = demo.demonstrate().await;
         assert!(result.is_ok());
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod sorted_set_tests {
+    use crate::demos::SortedSetDemo;
+    use crate::utils::zset;
+    use crate::RedisClient;
+    use redis::AsyncCommands;
+    
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap().with_key_prefix("test:")
+    }
+    
+    #[tokio::test]
+    async fn test_lex_pagination_visits_every_member_once() {
+        let client = get_test_client().await;
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del("test_lex").await.unwrap();
+        
+        for i in 0..10u64 {
+            let _: () = conn.zadd("test_lex", zset::encode_lex_member(i / 2, &i.to_string()), 0).await.unwrap();
+        }
+        
+        let mut cursor: Option<String> = None;
+        let mut seen = Vec::new();
+        loop {
+            let page = zset::range_by_lex_page(&mut conn, "test_lex", cursor.as_deref(), 4).await.unwrap();
+            seen.extend(page.members);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        
+        assert_eq!(seen.len(), 10);
+        let mut sorted = seen.clone();
+        sorted.sort();
+        assert_eq!(seen, sorted);
+        
+        let _: () = conn.del("test_lex").await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_pinned_feed_ordering() {
+        let client = get_test_client().await;
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del("test_feed").await.unwrap();
+        
+        let _: () = conn.zadd("test_feed", "old_pinned", zset::composite_score(true, 10)).await.unwrap();
+        let _: () = conn.zadd("test_feed", "new", zset::composite_score(false, 30)).await.unwrap();
+        let _: () = conn.zadd("test_feed", "older", zset::composite_score(false, 20)).await.unwrap();
+        
+        let feed: Vec<String> = conn.zrevrange("test_feed", 0, -1).await.unwrap();
+        assert_eq!(feed, vec!["old_pinned", "new", "older"]);
+        
+        let _: () = conn.del("test_feed").await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_sorted_set_demo_full() {
+        let client = get_test_client().await;
+        let demo = SortedSetDemo::new(client.clone());
+        
+        let result = demo.demonstrate().await;
+        assert!(result.is_ok());
+    }
+}
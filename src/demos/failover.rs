@@ -0,0 +1,257 @@
+use crate::server::replication::{ReplicationInfo, Role};
+use crate::utils::error::{DemoError, Result};
+use crate::RedisClient;
+use crate::utils::{RedisConnection, Sections};
+use redis::{AsyncCommands, ConnectionAddr};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::info;
+
+const MARKER_KEY: &str = "failover:marker";
+const PROBE_KEY: &str = "failover:probe";
+
+/// How long a probe write may take before it counts as failed.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(250);
+const PROBE_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailoverMode {
+    /// Pause writes on the master, let the replica catch up, then promote.
+    Graceful,
+    /// Freeze the master with DEBUG SLEEP and promote the replica right away,
+    /// as a watchdog would after losing the master.
+    Crash,
+}
+
+impl FromStr for FailoverMode {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "graceful" => Ok(FailoverMode::Graceful),
+            "crash" => Ok(FailoverMode::Crash),
+            other => Err(DemoError::Configuration(format!("Unknown failover mode: {}", other))),
+        }
+    }
+}
+
+/// Longest stretch without a successful write, given the times (since the
+/// probe started) at which writes were acknowledged and when it stopped.
+pub fn longest_gap(acked_at: &[Duration], stopped_at: Duration) -> Duration {
+    let mut previous = Duration::ZERO;
+    let mut longest = Duration::ZERO;
+    for &at in acked_at.iter().chain(std::iter::once(&stopped_at)) {
+        longest = longest.max(at.saturating_sub(previous));
+        previous = at;
+    }
+    longest
+}
+
+fn tcp_host_port(client: &RedisClient) -> Result<(String, u16)> {
+    match &client.get_connection_info().addr {
+        ConnectionAddr::Tcp(host, port) | ConnectionAddr::TcpTls { host, port, .. } => Ok((host.clone(), *port)),
+        ConnectionAddr::Unix(_) => Err(DemoError::Configuration("Failover needs TCP addresses for REPLICAOF".to_string())),
+    }
+}
+
+struct ProbeReport {
+    acked: u64,
+    failed: u64,
+    acked_at: Vec<Duration>,
+    stopped_at: Duration,
+}
+
+/// Walks through a manual failover of a master/replica pair while a client
+/// keeps writing, and measures how long writes were unavailable.
+pub struct FailoverDemo {
+    master: RedisClient,
+    replica: RedisClient,
+}
+
+impl FailoverDemo {
+    pub fn new(master: RedisClient, replica: RedisClient) -> Self {
+        Self { master, replica }
+    }
+
+    /// Continuously INCRs the probe key against whichever server `target`
+    /// points at (0 = old master, 1 = promoted replica).
+    fn spawn_probe(
+        connections: [RedisConnection; 2],
+        mut target: watch::Receiver<usize>,
+        mut stop: watch::Receiver<bool>,
+    ) -> tokio::task::JoinHandle<ProbeReport> {
+        tokio::spawn(async move {
+            let [mut old, mut new] = connections;
+            let started = Instant::now();
+            let mut report = ProbeReport { acked: 0, failed: 0, acked_at: Vec::new(), stopped_at: Duration::ZERO };
+            while !*stop.borrow() {
+                let conn = if *target.borrow_and_update() == 0 { &mut old } else { &mut new };
+                match tokio::time::timeout(PROBE_TIMEOUT, conn.incr::<_, _, u64>(PROBE_KEY, 1)).await {
+                    Ok(Ok(_)) => {
+                        report.acked += 1;
+                        report.acked_at.push(started.elapsed());
+                    }
+                    _ => report.failed += 1,
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(PROBE_INTERVAL) => {}
+                    _ = stop.changed() => {}
+                }
+            }
+            report.stopped_at = started.elapsed();
+            report
+        })
+    }
+
+    async fn wait_for_catch_up(&self, master: &mut RedisConnection, replica: &mut RedisConnection, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let master_offset = ReplicationInfo::fetch(master).await?.master_repl_offset;
+            let replica_offset = match ReplicationInfo::fetch(replica).await?.role {
+                Role::Replica { offset, .. } => offset,
+                Role::Master => return Err(DemoError::Demo("Replica was promoted by someone else".to_string())),
+            };
+            if replica_offset >= master_offset {
+                println!("   replica caught up at offset {}", replica_offset);
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(DemoError::Demo(format!(
+                    "Replica still {} bytes behind after {:?}",
+                    master_offset - replica_offset,
+                    timeout
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    pub async fn demonstrate(&self, mode: FailoverMode, catch_up_timeout: Duration) -> Result<()> {
+        let mut master = self.master.get_async_connection().await?;
+        let mut replica = self.replica.get_async_connection().await?;
+        let (master_host, master_port) = tcp_host_port(&self.master)?;
+        let (replica_host, replica_port) = tcp_host_port(&self.replica)?;
+
+        println!("\n=== Manual Failover ===\n");
+
+        let mut sections = Sections::new();
+        sections.next("Checking the topology with INFO replication");
+        let master_info = ReplicationInfo::fetch(&mut master).await?;
+        let replica_info = ReplicationInfo::fetch(&mut replica).await?;
+        println!("   {}:{} is {}, offset {}", master_host, master_port, master_info.role, master_info.master_repl_offset);
+        println!("   {}:{} is {}, offset {}", replica_host, replica_port, replica_info.role, replica_info.master_repl_offset);
+        if !master_info.is_master() {
+            return Err(DemoError::Configuration(format!("{}:{} is not a master", master_host, master_port)));
+        }
+        match &replica_info.role {
+            Role::Replica { link_up: true, master_port: port, .. } if *port == master_port => {}
+            _ => {
+                return Err(DemoError::Configuration(format!(
+                    "{}:{} is not an in-sync replica of {}:{} (try REPLICAOF {} {} on it)",
+                    replica_host, replica_port, master_host, master_port, master_host, master_port
+                )))
+            }
+        }
+        if mode == FailoverMode::Crash {
+            redis::cmd("DEBUG").arg("SLEEP").arg(0).query_async::<_, ()>(&mut master).await.map_err(|e| {
+                DemoError::Configuration(format!("DEBUG is unavailable ({}); start the master with --enable-debug-command yes", e))
+            })?;
+        }
+
+        sections.next(&format!("Writing a marker key and starting a client that INCRs every {:?}", PROBE_INTERVAL));
+        let marker = uuid::Uuid::new_v4().to_string();
+        let _: () = master.set(MARKER_KEY, &marker).await?;
+        let _: () = master.del(PROBE_KEY).await?;
+        let (target_tx, target_rx) = watch::channel(0usize);
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let probe = Self::spawn_probe(
+            [self.master.get_async_connection().await?, self.replica.get_async_connection().await?],
+            target_rx,
+            stop_rx,
+        );
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let sleeper = match mode {
+            FailoverMode::Graceful => {
+                sections.next("CLIENT PAUSE WRITE on the master, then waiting for the replica to catch up");
+                let pause_ms = catch_up_timeout.as_millis() as u64 + 1000;
+                let _: () = redis::cmd("CLIENT").arg("PAUSE").arg(pause_ms).arg("WRITE").query_async(&mut master).await?;
+                self.wait_for_catch_up(&mut master, &mut replica, catch_up_timeout).await?;
+                None
+            }
+            FailoverMode::Crash => {
+                sections.next("DEBUG SLEEP 2 on the master: it stops answering without warning");
+                let mut frozen = self.master.get_async_connection().await?;
+                let sleeper = tokio::spawn(async move {
+                    redis::cmd("DEBUG").arg("SLEEP").arg(2).query_async::<_, ()>(&mut frozen).await
+                });
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                println!("   (a watchdog would notice the missed heartbeats here)");
+                Some(sleeper)
+            }
+        };
+
+        sections.next(&format!("REPLICAOF NO ONE on {}:{} and pointing the client at it", replica_host, replica_port));
+        let _: () = redis::cmd("REPLICAOF").arg("NO").arg("ONE").query_async(&mut replica).await?;
+        target_tx.send_replace(1);
+
+        sections.next(&format!("REPLICAOF {} {} on the old master", replica_host, replica_port));
+        if let Some(sleeper) = sleeper {
+            sleeper.await.map_err(|e| DemoError::Demo(e.to_string()))??;
+        }
+        let _: () = redis::cmd("REPLICAOF").arg(&replica_host).arg(replica_port).query_async(&mut master).await?;
+        if mode == FailoverMode::Graceful {
+            let _: () = redis::cmd("CLIENT").arg("UNPAUSE").query_async(&mut master).await?;
+        }
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        stop_tx.send_replace(true);
+        let report = probe.await.map_err(|e| DemoError::Demo(e.to_string()))?;
+
+        sections.next("Validating data continuity on the new master");
+        let seen: Option<String> = replica.get(MARKER_KEY).await?;
+        println!("   marker: {}", if seen.as_deref() == Some(marker.as_str()) { "✅ present" } else { "❌ missing" });
+        let counter: u64 = replica.get::<_, Option<u64>>(PROBE_KEY).await?.unwrap_or(0);
+        println!("   probe counter {} vs {} acknowledged writes ({} failed or timed out)", counter, report.acked, report.failed);
+        if counter < report.acked {
+            println!("   ❌ {} acknowledged writes were lost with the old master", report.acked - counter);
+        } else {
+            println!("   ✅ no acknowledged write was lost");
+        }
+        println!("   write unavailability: {:?}", longest_gap(&report.acked_at, report.stopped_at));
+
+        println!("\n💡 Replication is asynchronous: a write the old master acknowledged but had");
+        println!("   not shipped yet is gone after promotion. Pausing writes and waiting for the");
+        println!("   offsets to match (what Sentinel and CLUSTER FAILOVER do) makes a planned");
+        println!("   failover lossless; WAIT narrows the window for unplanned ones.");
+        println!("   Run again with the two URLs swapped to fail back.");
+
+        let _: () = replica.del(&[MARKER_KEY, PROBE_KEY]).await?;
+        info!("Failover demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&v| Duration::from_millis(v)).collect()
+    }
+
+    #[test]
+    fn test_longest_gap() {
+        assert_eq!(longest_gap(&ms(&[10, 20, 30, 480, 490]), Duration::from_millis(500)), Duration::from_millis(450));
+        assert_eq!(longest_gap(&ms(&[10, 20]), Duration::from_millis(900)), Duration::from_millis(880));
+        assert_eq!(longest_gap(&[], Duration::from_millis(40)), Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_mode_from_str() {
+        assert_eq!("Crash".parse::<FailoverMode>().unwrap(), FailoverMode::Crash);
+        assert_eq!("graceful".parse::<FailoverMode>().unwrap(), FailoverMode::Graceful);
+        assert!("sentinel".parse::<FailoverMode>().is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::{RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use chrono::Utc;
+use futures::StreamExt;
+use redis::{AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+pub const ALERT_CHANNEL: &str = "geofence:alerts";
+const FLEET_KEY: &str = "geo:fleet";
+const EARTH_RADIUS_M: f64 = 6_372_797.560_856;
+
+/// Stores the new inside/outside state for an object and returns the
+/// previous one in a single atomic step, so concurrent position updates
+/// can't both decide they are the first to cross the fence.
+const SWAP_STATE_SCRIPT: &str = r#"
+local previous = redis.call('HGET', KEYS[1], ARGV[1])
+redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+return previous
+"#;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geofence {
+    pub name: String,
+    pub longitude: f64,
+    pub latitude: f64,
+    pub radius_m: f64,
+}
+
+impl Geofence {
+    pub fn state_key(&self) -> String {
+        format!("geofence:{}:state", self.name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FenceTransition {
+    Entered,
+    Exited,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeofenceAlert {
+    pub fence: String,
+    pub object: String,
+    pub transition: FenceTransition,
+    pub longitude: f64,
+    pub latitude: f64,
+    pub timestamp: i64,
+}
+
+const INSIDE: &str = "inside";
+const OUTSIDE: &str = "outside";
+
+/// Decides whether a state change deserves an alert. An object seen for the
+/// first time only alerts if it starts inside the fence.
+pub fn detect_transition(previous: Option<&str>, inside: bool) -> Option<FenceTransition> {
+    match (previous, inside) {
+        (Some(INSIDE), false) => Some(FenceTransition::Exited),
+        (Some(INSIDE), true) | (Some(_), false) | (None, false) => None,
+        (Some(_), true) | (None, true) => Some(FenceTransition::Entered),
+    }
+}
+
+/// Great-circle distance in meters, using the same Earth radius as Redis.
+pub fn haversine_m(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+}
+
+/// Linear interpolation between two coordinates for `step` of `steps`.
+pub fn interpolate(from: (f64, f64), to: (f64, f64), step: usize, steps: usize) -> (f64, f64) {
+    let t = if steps == 0 { 1.0 } else { step as f64 / steps as f64 };
+    (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+}
+
+pub struct GeoDemo {
+    client: RedisClient,
+}
+
+impl GeoDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn demonstrate(&self) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+
+        println!("\n=== Geospatial Operations Demo ===\n");
+
+        // GEOADD
+        let mut sections = Sections::new();
+        sections.next("GEOADD (add locations):");
+        let _: () = conn
+            .geo_add(
+                "landmarks",
+                &[
+                    (2.2945, 48.8584, "eiffel_tower"),
+                    (2.3376, 48.8606, "louvre"),
+                    (2.3499, 48.8530, "notre_dame"),
+                ],
+            )
+            .await?;
+        println!("   GEOADD landmarks 2.2945 48.8584 eiffel_tower 2.3376 48.8606 louvre ...");
+
+        // GEODIST
+        sections.next("GEODIST (distance between members):");
+        let distance: Option<f64> = redis::cmd("GEODIST")
+            .arg("landmarks")
+            .arg("eiffel_tower")
+            .arg("louvre")
+            .arg("km")
+            .query_async(&mut conn)
+            .await?;
+        println!("   GEODIST landmarks eiffel_tower louvre km => {:?}", distance);
+
+        // GEOSEARCH
+        sections.next("GEOSEARCH (members within a radius):");
+        let nearby: Vec<String> = redis::cmd("GEOSEARCH")
+            .arg("landmarks")
+            .arg("FROMMEMBER")
+            .arg("notre_dame")
+            .arg("BYRADIUS")
+            .arg(2)
+            .arg("km")
+            .arg("ASC")
+            .query_async(&mut conn)
+            .await?;
+        println!("   GEOSEARCH landmarks FROMMEMBER notre_dame BYRADIUS 2 km ASC => {:?}", nearby);
+
+        let _: () = conn.del("landmarks").await?;
+
+        sections.next("Geo-fencing with pub/sub alerts:");
+        let fence = Geofence {
+            name: "city_center".to_string(),
+            longitude: 2.3522,
+            latitude: 48.8566,
+            radius_m: 1_000.0,
+        };
+        let alerts = self.geofence_simulation(&fence, 10).await?;
+        println!("   {} alerts published on '{}'", alerts.len(), ALERT_CHANNEL);
+
+        info!("Geospatial operations demo completed");
+        Ok(())
+    }
+
+    /// Moves a few couriers across the fence, publishing an alert on every
+    /// enter/exit while a subscriber task echoes what it receives.
+    pub async fn geofence_simulation(&self, fence: &Geofence, steps: usize) -> Result<Vec<GeofenceAlert>> {
+        let mut conn = self.client.get_async_connection().await?;
+        let _: () = conn.del(&[FLEET_KEY, fence.state_key().as_str()]).await?;
+
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(ALERT_CHANNEL).await?;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let subscriber = tokio::spawn(async move {
+            let mut messages = pubsub.into_on_message();
+            while let Some(message) = messages.next().await {
+                let payload: String = match message.get_payload() {
+                    Ok(payload) => payload,
+                    Err(_) => continue,
+                };
+                if tx.send(payload).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let routes = [
+            ("courier:1", (2.3300, 48.8566), (2.3750, 48.8566)),
+            ("courier:2", (2.3510, 48.8560), (2.3530, 48.8570)),
+            ("courier:3", (2.2900, 48.8800), (2.3000, 48.8850)),
+        ];
+
+        let mut alerts = Vec::new();
+        for step in 0..=steps {
+            for (object, from, to) in routes {
+                let (longitude, latitude) = interpolate(from, to, step, steps);
+                if let Some(alert) = self
+                    .update_position(&mut conn, fence, object, longitude, latitude)
+                    .await?
+                {
+                    println!(
+                        "   step {:>2}: {} {:?} {} at ({:.4}, {:.4})",
+                        step, alert.object, alert.transition, alert.fence, longitude, latitude
+                    );
+                    alerts.push(alert);
+                }
+            }
+        }
+
+        for _ in 0..alerts.len() {
+            match tokio::time::timeout(Duration::from_secs(2), rx.recv()).await {
+                Ok(Some(payload)) => println!("   📣 subscriber received {}", payload),
+                _ => {
+                    warn!("Subscriber did not receive all geofence alerts");
+                    break;
+                }
+            }
+        }
+        subscriber.abort();
+
+        let _: () = conn.del(&[FLEET_KEY, fence.state_key().as_str()]).await?;
+        Ok(alerts)
+    }
+
+    /// Records a new position and publishes an alert if the object crossed the fence.
+    pub async fn update_position(
+        &self,
+        conn: &mut RedisConnection,
+        fence: &Geofence,
+        object: &str,
+        longitude: f64,
+        latitude: f64,
+    ) -> Result<Option<GeofenceAlert>> {
+        let _: () = conn.geo_add(FLEET_KEY, &[(longitude, latitude, object)]).await?;
+
+        let inside_members: Vec<String> = redis::cmd("GEOSEARCH")
+            .arg(FLEET_KEY)
+            .arg("FROMLONLAT")
+            .arg(fence.longitude)
+            .arg(fence.latitude)
+            .arg("BYRADIUS")
+            .arg(fence.radius_m)
+            .arg("m")
+            .query_async(conn)
+            .await?;
+        let inside = inside_members.iter().any(|member| member == object);
+
+        let previous: Option<String> = Script::new(SWAP_STATE_SCRIPT)
+            .key(fence.state_key())
+            .arg(object)
+            .arg(if inside { INSIDE } else { OUTSIDE })
+            .invoke_async(conn)
+            .await?;
+
+        let Some(transition) = detect_transition(previous.as_deref(), inside) else {
+            return Ok(None);
+        };
+
+        let alert = GeofenceAlert {
+            fence: fence.name.clone(),
+            object: object.to_string(),
+            transition,
+            longitude,
+            latitude,
+            timestamp: Utc::now().timestamp_millis(),
+        };
+        let _: () = conn.publish(ALERT_CHANNEL, serde_json::to_string(&alert)?).await?;
+        Ok(Some(alert))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_transition() {
+        assert_eq!(detect_transition(None, true), Some(FenceTransition::Entered));
+        assert_eq!(detect_transition(None, false), None);
+        assert_eq!(detect_transition(Some(OUTSIDE), true), Some(FenceTransition::Entered));
+        assert_eq!(detect_transition(Some(INSIDE), false), Some(FenceTransition::Exited));
+        assert_eq!(detect_transition(Some(INSIDE), true), None);
+        assert_eq!(detect_transition(Some(OUTSIDE), false), None);
+    }
+
+    #[test]
+    fn test_haversine_known_distance() {
+        // Eiffel Tower to the Louvre is roughly 3.2 km.
+        let distance = haversine_m(2.2945, 48.8584, 2.3376, 48.8606);
+        assert!((3_100.0..3_300.0).contains(&distance), "got {}", distance);
+        assert_eq!(haversine_m(2.0, 48.0, 2.0, 48.0), 0.0);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(interpolate((0.0, 0.0), (10.0, 20.0), 0, 10), (0.0, 0.0));
+        assert_eq!(interpolate((0.0, 0.0), (10.0, 20.0), 5, 10), (5.0, 10.0));
+        assert_eq!(interpolate((0.0, 0.0), (10.0, 20.0), 10, 10), (10.0, 20.0));
+        assert_eq!(interpolate((0.0, 0.0), (10.0, 20.0), 0, 0), (10.0, 20.0));
+    }
+
+    #[test]
+    fn test_alert_serialization() {
+        let alert = GeofenceAlert {
+            fence: "center".to_string(),
+            object: "courier:1".to_string(),
+            transition: FenceTransition::Entered,
+            longitude: 2.35,
+            latitude: 48.85,
+            timestamp: 0,
+        };
+        let json = serde_json::to_string(&alert).unwrap();
+        assert!(json.contains("\"transition\":\"Entered\""));
+        let decoded: GeofenceAlert = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, alert);
+    }
+
+    #[tokio::test]
+    async fn test_geofence_simulation_alerts_once_per_crossing() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let demo = GeoDemo::new(client);
+        let fence = Geofence {
+            name: "test_fence".to_string(),
+            longitude: 2.3522,
+            latitude: 48.8566,
+            radius_m: 1_000.0,
+        };
+
+        let alerts = demo.geofence_simulation(&fence, 10).await.unwrap();
+        let courier_1: Vec<FenceTransition> = alerts
+            .iter()
+            .filter(|alert| alert.object == "courier:1")
+            .map(|alert| alert.transition)
+            .collect();
+        assert_eq!(courier_1, vec![FenceTransition::Entered, FenceTransition::Exited]);
+        assert_eq!(alerts.iter().filter(|alert| alert.object == "courier:2").count(), 1);
+        assert!(alerts.iter().all(|alert| alert.object != "courier:3"));
+    }
+}
@@ -1,7 +1,33 @@
+pub mod analytics;
 pub mod basic_operations;
+pub mod bitmaps;
+pub mod cardinality;
+pub mod cluster_pitfalls;
+pub mod config_watch;
+pub mod connection_storm;
+pub mod data_model;
 pub mod data_structures;
+pub mod failover;
+pub mod geo;
+pub mod patterns;
+pub mod pipeline;
+pub mod pubsub;
 pub mod rust_errors_demo;
+pub mod scripting;
+pub mod streams;
 
+pub use analytics::AnalyticsDemo;
 pub use basic_operations::BasicOpsDemo;
-pub use data_structures::{ListDemo, SetDemo, HashDemo};
-pub use rust_errors_demo::RustErrorsDemo;
\ No newline at end of file
+pub use bitmaps::BitmapDemo;
+pub use cardinality::CardinalityDemo;
+pub use cluster_pitfalls::ClusterPitfallsDemo;
+pub use config_watch::ConfigWatchDemo;
+pub use connection_storm::{ConnectionStormDemo, StormOptions};
+pub use data_model::{DataModelDemo, DiagramFormat};
+pub use data_structures::{ListDemo, SetDemo, HashDemo, SortedSetDemo};
+pub use failover::{FailoverDemo, FailoverMode};
+pub use geo::GeoDemo;
+pub use pipeline::{PipelineDemo, PipelineReport};
+pub use pubsub::PubSubDemo;
+pub use rust_errors_demo::RustErrorsDemo;
+pub use scripting::ScriptingDemo;
\ No newline at end of file
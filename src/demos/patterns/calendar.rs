@@ -0,0 +1,397 @@
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use redis::{AsyncCommands, Script};
+use tracing::info;
+
+pub const SLOT_MINUTES: usize = 15;
+pub const SLOTS_PER_DAY: usize = 24 * 60 / SLOT_MINUTES;
+
+/// KEYS: day bitfield
+/// ARGV: first slot, slot count, 1 to book / 0 to free
+///
+/// Reads and writes the range in u32 lanes (Lua numbers lose precision past
+/// 2^53, and BITFIELD caps unsigned fields at u63). Booking checks every lane
+/// before writing any, so a conflicting request leaves the day untouched.
+const SET_RANGE_SCRIPT: &str = r#"
+local start = tonumber(ARGV[1])
+local count = tonumber(ARGV[2])
+local book = ARGV[3] == '1'
+if book then
+    local offset, remaining = start, count
+    while remaining > 0 do
+        local width = math.min(remaining, 32)
+        local taken = redis.call('BITFIELD', KEYS[1], 'GET', 'u' .. width, offset)[1]
+        if taken ~= 0 then
+            return 0
+        end
+        offset, remaining = offset + width, remaining - width
+    end
+end
+local offset, remaining = start, count
+while remaining > 0 do
+    local width = math.min(remaining, 32)
+    local value = 0
+    if book then
+        value = 2 ^ width - 1
+    end
+    redis.call('BITFIELD', KEYS[1], 'SET', 'u' .. width, offset, string.format('%d', value))
+    offset, remaining = offset + width, remaining - width
+end
+return 1
+"#;
+
+pub fn day_key(resource: &str, date: &str) -> String {
+    format!("calendar:{}:{}", resource, date)
+}
+
+/// Half-open range of 15-minute slots within one day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl SlotRange {
+    /// Parses `HH:MM` boundaries; both must fall on a slot boundary.
+    pub fn parse(start: &str, end: &str) -> Result<Self> {
+        let range = Self {
+            start: parse_slot(start)?,
+            end: parse_slot(end)?,
+        };
+        if range.start >= range.end {
+            return Err(DemoError::Configuration(format!("Empty slot range {}-{}", start, end)));
+        }
+        Ok(range)
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}-{}", slot_label(self.start), slot_label(self.end))
+    }
+}
+
+fn parse_slot(time: &str) -> Result<usize> {
+    let invalid = || DemoError::Configuration(format!("Invalid slot time '{}', expected HH:MM", time));
+    let (hours, minutes) = time.split_once(':').ok_or_else(invalid)?;
+    let hours: usize = hours.parse().map_err(|_| invalid())?;
+    let minutes: usize = minutes.parse().map_err(|_| invalid())?;
+    if !minutes.is_multiple_of(SLOT_MINUTES) || minutes >= 60 || hours * 60 + minutes > 24 * 60 {
+        return Err(invalid());
+    }
+    Ok((hours * 60 + minutes) / SLOT_MINUTES)
+}
+
+pub fn slot_label(slot: usize) -> String {
+    let minutes = slot * SLOT_MINUTES;
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Expands the raw day string into one bool per slot. Bit 0 is the most
+/// significant bit of the first byte, matching BITFIELD/SETBIT offsets.
+pub fn decode_day(bytes: &[u8]) -> Vec<bool> {
+    (0..SLOTS_PER_DAY)
+        .map(|slot| {
+            bytes
+                .get(slot / 8)
+                .map(|byte| byte & (0x80 >> (slot % 8)) != 0)
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+pub fn booked_slots_in(day: &[bool], range: SlotRange) -> Vec<usize> {
+    (range.start..range.end).filter(|slot| day[*slot]).collect()
+}
+
+/// First run of `len` free slots at or after `from`.
+pub fn first_free(day: &[bool], len: usize, from: usize) -> Option<SlotRange> {
+    let mut run_start = from;
+    for (slot, booked) in day.iter().enumerate().skip(from) {
+        if *booked {
+            run_start = slot + 1;
+        } else if slot + 1 - run_start == len {
+            return Some(SlotRange { start: run_start, end: slot + 1 });
+        }
+    }
+    None
+}
+
+/// One row per hour-block of the day: `█` booked, `·` free.
+pub fn render_day(resource: &str, day: &[bool]) -> String {
+    let mut out = format!("   {:<10}", resource);
+    for (slot, booked) in day.iter().enumerate() {
+        if slot > 0 && slot % 4 == 0 {
+            out.push(' ');
+        }
+        out.push(if *booked { '█' } else { '·' });
+    }
+    out
+}
+
+pub fn render_hour_header() -> String {
+    let mut out = format!("   {:<10}", "");
+    for hour in 0..24 {
+        out.push_str(&format!("{:<5}", format!("{:02}", hour)));
+    }
+    out.trim_end().to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Booking {
+    Booked,
+    Conflict(Vec<usize>),
+}
+
+/// Availability calendar packing 96 slots per resource per day into a
+/// 12-byte string manipulated with BITFIELD.
+pub struct Calendar {
+    conn: RedisConnection,
+    set_range: Script,
+}
+
+impl Calendar {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            set_range: Script::new(SET_RANGE_SCRIPT),
+        })
+    }
+
+    pub async fn book(&mut self, resource: &str, date: &str, range: SlotRange) -> Result<Booking> {
+        if self.apply(resource, date, range, true).await? {
+            return Ok(Booking::Booked);
+        }
+        let day = self.day(resource, date).await?;
+        Ok(Booking::Conflict(booked_slots_in(&day, range)))
+    }
+
+    pub async fn free(&mut self, resource: &str, date: &str, range: SlotRange) -> Result<()> {
+        self.apply(resource, date, range, false).await?;
+        Ok(())
+    }
+
+    async fn apply(&mut self, resource: &str, date: &str, range: SlotRange, book: bool) -> Result<bool> {
+        let applied: i64 = self
+            .set_range
+            .key(day_key(resource, date))
+            .arg(range.start)
+            .arg(range.len())
+            .arg(if book { 1 } else { 0 })
+            .invoke_async(&mut self.conn)
+            .await?;
+        Ok(applied == 1)
+    }
+
+    pub async fn is_free(&mut self, resource: &str, date: &str, slot: usize) -> Result<bool> {
+        let values: Vec<u8> = redis::cmd("BITFIELD")
+            .arg(day_key(resource, date))
+            .arg("GET")
+            .arg("u1")
+            .arg(slot)
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(values.first() == Some(&0))
+    }
+
+    pub async fn day(&mut self, resource: &str, date: &str) -> Result<Vec<bool>> {
+        let bytes: Vec<u8> = self.conn.get(day_key(resource, date)).await?;
+        Ok(decode_day(&bytes))
+    }
+
+    pub async fn booked_count(&mut self, resource: &str, date: &str) -> Result<usize> {
+        let count: usize = redis::cmd("BITCOUNT")
+            .arg(day_key(resource, date))
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(count)
+    }
+
+    /// ORs the resources' days together with BITOP and finds the first slot
+    /// range where all of them are free.
+    pub async fn first_common_free(
+        &mut self,
+        resources: &[&str],
+        date: &str,
+        len: usize,
+        from: usize,
+    ) -> Result<Option<SlotRange>> {
+        let dest = format!("calendar:tmp:{}", date);
+        let sources: Vec<String> = resources.iter().map(|resource| day_key(resource, date)).collect();
+        let (bytes,): (Vec<u8>,) = redis::pipe()
+            .atomic()
+            .cmd("BITOP")
+            .arg("OR")
+            .arg(&dest)
+            .arg(&sources)
+            .ignore()
+            .get(&dest)
+            .del(&dest)
+            .ignore()
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(first_free(&decode_day(&bytes), len, from))
+    }
+
+    pub async fn clear(&mut self, resource: &str, date: &str) -> Result<()> {
+        let _: () = self.conn.del(day_key(resource, date)).await?;
+        Ok(())
+    }
+}
+
+pub struct CalendarDemo {
+    client: RedisClient,
+}
+
+impl CalendarDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn demonstrate(&self) -> Result<()> {
+        let date = "2024-03-15";
+        let rooms = ["room:atlas", "room:boreal"];
+        let mut calendar = Calendar::new(&self.client).await?;
+        for room in rooms {
+            calendar.clear(room, date).await?;
+        }
+
+        println!("\n=== Scheduling Calendar with BITFIELD ===\n");
+        println!("{} slots of {} minutes per day => {} bytes per resource", SLOTS_PER_DAY, SLOT_MINUTES, SLOTS_PER_DAY / 8);
+
+        println!();
+        let mut sections = Sections::new();
+        sections.next("Booking meetings (BITFIELD GET checks, then BITFIELD SET u32 lanes):");
+        let bookings = [
+            (rooms[0], "09:00", "10:30"),
+            (rooms[0], "13:00", "14:00"),
+            (rooms[1], "08:00", "09:30"),
+            (rooms[1], "11:00", "12:00"),
+            (rooms[1], "15:00", "17:00"),
+        ];
+        for (room, start, end) in bookings {
+            let range = SlotRange::parse(start, end)?;
+            println!("   {} {} => {:?}", room, range.label(), calendar.book(room, date, range).await?);
+        }
+
+        sections.next("Conflict detection:");
+        let clash = SlotRange::parse("10:00", "11:00")?;
+        match calendar.book(rooms[0], date, clash).await? {
+            Booking::Conflict(slots) => println!(
+                "   {} {} rejected, overlapping slots: {:?}",
+                rooms[0],
+                clash.label(),
+                slots.iter().map(|slot| slot_label(*slot)).collect::<Vec<_>>()
+            ),
+            Booking::Booked => println!("   {} {} unexpectedly booked", rooms[0], clash.label()),
+        }
+
+        sections.next("BITFIELD GET u1 (single slot lookup):");
+        let slot = SlotRange::parse("09:15", "09:30")?.start;
+        println!("   {} free at {} => {}", rooms[0], slot_label(slot), calendar.is_free(rooms[0], date, slot).await?);
+
+        sections.next(&format!("Freeing {} 13:00-14:00:", rooms[0]));
+        calendar.free(rooms[0], date, SlotRange::parse("13:00", "14:00")?).await?;
+
+        sections.next("Day view:");
+        println!("{}", render_hour_header());
+        for room in rooms {
+            let day = calendar.day(room, date).await?;
+            println!("{}", render_day(room, &day));
+            println!("   {:<10}{} slots booked (BITCOUNT)", "", calendar.booked_count(room, date).await?);
+        }
+
+        sections.next("First hour both rooms are free after 08:00 (BITOP OR):");
+        let from = SlotRange::parse("08:00", "08:15")?.start;
+        match calendar.first_common_free(&rooms, date, 4, from).await? {
+            Some(range) => println!("   => {}", range.label()),
+            None => println!("   => no common slot"),
+        }
+
+        for room in rooms {
+            calendar.clear(room, date).await?;
+        }
+        info!("Calendar demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    #[test]
+    fn test_slot_range_parse() {
+        let range = SlotRange::parse("09:00", "10:30").unwrap();
+        assert_eq!(range, SlotRange { start: 36, end: 42 });
+        assert_eq!(range.label(), "09:00-10:30");
+        assert_eq!(SlotRange::parse("00:00", "24:00").unwrap().len(), SLOTS_PER_DAY);
+        assert!(SlotRange::parse("09:10", "10:00").is_err());
+        assert!(SlotRange::parse("10:00", "09:00").is_err());
+        assert!(SlotRange::parse("nine", "10:00").is_err());
+    }
+
+    #[test]
+    fn test_decode_day_uses_msb_first_offsets() {
+        let mut bytes = vec![0u8; SLOTS_PER_DAY / 8];
+        bytes[0] = 0b1000_0001;
+        bytes[11] = 0b0000_0001;
+        let day = decode_day(&bytes);
+        assert!(day[0] && day[7] && day[95]);
+        assert_eq!(day.iter().filter(|b| **b).count(), 3);
+        assert_eq!(decode_day(&[]), vec![false; SLOTS_PER_DAY]);
+    }
+
+    #[test]
+    fn test_first_free_and_conflicts() {
+        let mut day = vec![false; SLOTS_PER_DAY];
+        day[2..5].iter_mut().for_each(|slot| *slot = true);
+        assert_eq!(first_free(&day, 2, 0), Some(SlotRange { start: 0, end: 2 }));
+        assert_eq!(first_free(&day, 3, 0), Some(SlotRange { start: 5, end: 8 }));
+        assert_eq!(first_free(&day, 97, 0), None);
+        assert_eq!(booked_slots_in(&day, SlotRange { start: 0, end: 4 }), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_render_day() {
+        let mut day = vec![false; SLOTS_PER_DAY];
+        day[0] = true;
+        let row = render_day("room", &day);
+        assert!(row.starts_with("   room      █··· ····"));
+        assert_eq!(row.chars().filter(|c| *c == '█').count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_book_conflict_and_free() {
+        let client = get_test_client().await;
+        let mut calendar = Calendar::new(&client).await.unwrap();
+        calendar.clear("test_room", "2024-01-01").await.unwrap();
+
+        let morning = SlotRange::parse("08:00", "18:00").unwrap();
+        assert_eq!(calendar.book("test_room", "2024-01-01", morning).await.unwrap(), Booking::Booked);
+        assert_eq!(calendar.booked_count("test_room", "2024-01-01").await.unwrap(), 40);
+
+        let overlap = SlotRange::parse("17:30", "19:00").unwrap();
+        assert_eq!(
+            calendar.book("test_room", "2024-01-01", overlap).await.unwrap(),
+            Booking::Conflict(vec![70, 71])
+        );
+        assert_eq!(calendar.booked_count("test_room", "2024-01-01").await.unwrap(), 40);
+
+        calendar.free("test_room", "2024-01-01", morning).await.unwrap();
+        assert!(calendar.is_free("test_room", "2024-01-01", 40).await.unwrap());
+        assert_eq!(calendar.book("test_room", "2024-01-01", overlap).await.unwrap(), Booking::Booked);
+
+        calendar.clear("test_room", "2024-01-01").await.unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+use crate::utils::id_gen::{encode_crockford, IdGenerator};
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use chrono::Utc;
+use rand::Rng;
+use redis::{AsyncCommands, Script};
+use std::collections::HashMap;
+use tracing::info;
+
+/// KEYS: remaining counter, redemption log list, redeemers set
+/// ARGV: user id, timestamp ms
+///
+/// Returns remaining uses after redeeming, -1 when exhausted, -2 for an
+/// unknown code and -3 if the user already redeemed it.
+const REDEEM_SCRIPT: &str = r#"
+local remaining = redis.call('GET', KEYS[1])
+if not remaining then
+    return -2
+end
+if redis.call('SISMEMBER', KEYS[3], ARGV[1]) == 1 then
+    return -3
+end
+if tonumber(remaining) <= 0 then
+    return -1
+end
+remaining = redis.call('DECR', KEYS[1])
+redis.call('SADD', KEYS[3], ARGV[1])
+redis.call('LPUSH', KEYS[2], ARGV[1] .. '|' .. ARGV[2])
+return remaining
+"#;
+
+/// Random characters appended to each id so codes can't be guessed by
+/// counting.
+const RANDOM_SUFFIX: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedeemOutcome {
+    Redeemed { remaining: u64 },
+    Exhausted,
+    AlreadyRedeemed,
+    UnknownCode,
+}
+
+impl RedeemOutcome {
+    pub fn from_script_reply(reply: i64) -> Result<Self> {
+        match reply {
+            -1 => Ok(RedeemOutcome::Exhausted),
+            -2 => Ok(RedeemOutcome::UnknownCode),
+            -3 => Ok(RedeemOutcome::AlreadyRedeemed),
+            remaining if remaining >= 0 => Ok(RedeemOutcome::Redeemed { remaining: remaining as u64 }),
+            other => Err(DemoError::Demo(format!("Unexpected redeem script reply: {}", other))),
+        }
+    }
+}
+
+pub fn coupon_key(code: &str) -> String {
+    format!("coupon:{}", code)
+}
+
+pub fn remaining_key(code: &str) -> String {
+    format!("coupon:{}:remaining", code)
+}
+
+pub fn log_key(code: &str) -> String {
+    format!("coupon:{}:redemptions", code)
+}
+
+pub fn redeemers_key(code: &str) -> String {
+    format!("coupon:{}:redeemers", code)
+}
+
+/// Builds a code from a unique id plus a random suffix, e.g. `SPRING-00C4H-7QK2M`.
+pub fn coupon_code<R: Rng>(campaign: &str, id: u64, rng: &mut R) -> String {
+    let suffix: u64 = rng.gen_range(0..32u64.pow(RANDOM_SUFFIX as u32));
+    format!(
+        "{}-{}-{}",
+        campaign.to_ascii_uppercase(),
+        encode_crockford(id, 5),
+        encode_crockford(suffix, RANDOM_SUFFIX)
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redemption {
+    pub user: String,
+    pub at_ms: i64,
+}
+
+/// Single-use and N-use coupon codes whose redemptions can never exceed
+/// their limit, with one redemption per user per code.
+pub struct CouponService {
+    conn: RedisConnection,
+    ids: IdGenerator,
+    script: Script,
+}
+
+impl CouponService {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        let conn = client.get_async_connection().await?;
+        Ok(Self {
+            ids: IdGenerator::new(conn.clone(), "coupons", 100),
+            conn,
+            script: Script::new(REDEEM_SCRIPT),
+        })
+    }
+
+    /// Creates `count` codes for `campaign`, each redeemable `max_uses` times.
+    pub async fn create_batch(&mut self, campaign: &str, count: usize, max_uses: u64) -> Result<Vec<String>> {
+        let ids = self.ids.next_ids(count).await?;
+        let codes: Vec<String> = {
+            let mut rng = rand::thread_rng();
+            ids.iter().map(|id| coupon_code(campaign, *id, &mut rng)).collect()
+        };
+        for chunk in codes.chunks(500) {
+            let mut pipe = redis::pipe();
+            for code in chunk {
+                pipe.hset_multiple(coupon_key(code), &[("campaign", campaign.to_string()), ("max_uses", max_uses.to_string())])
+                    .ignore()
+                    .set(remaining_key(code), max_uses)
+                    .ignore();
+            }
+            let _: () = pipe.query_async(&mut self.conn).await?;
+        }
+        Ok(codes)
+    }
+
+    pub async fn redeem(&mut self, code: &str, user: &str) -> Result<RedeemOutcome> {
+        let reply: i64 = self
+            .script
+            .key(remaining_key(code))
+            .key(log_key(code))
+            .key(redeemers_key(code))
+            .arg(user)
+            .arg(Utc::now().timestamp_millis())
+            .invoke_async(&mut self.conn)
+            .await?;
+        RedeemOutcome::from_script_reply(reply)
+    }
+
+    pub async fn remaining(&mut self, code: &str) -> Result<Option<u64>> {
+        Ok(self.conn.get(remaining_key(code)).await?)
+    }
+
+    /// Redemptions, newest first.
+    pub async fn redemptions(&mut self, code: &str) -> Result<Vec<Redemption>> {
+        let entries: Vec<String> = self.conn.lrange(log_key(code), 0, -1).await?;
+        entries
+            .into_iter()
+            .map(|entry| {
+                let (user, at_ms) = entry
+                    .rsplit_once('|')
+                    .ok_or_else(|| DemoError::Demo(format!("Invalid redemption entry: {}", entry)))?;
+                Ok(Redemption {
+                    user: user.to_string(),
+                    at_ms: at_ms
+                        .parse()
+                        .map_err(|_| DemoError::Demo(format!("Invalid redemption entry: {}", entry)))?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn delete(&mut self, codes: &[String]) -> Result<()> {
+        let keys: Vec<String> = codes
+            .iter()
+            .flat_map(|code| [coupon_key(code), remaining_key(code), log_key(code), redeemers_key(code)])
+            .collect();
+        for chunk in keys.chunks(1000) {
+            let _: () = self.conn.del(chunk).await?;
+        }
+        Ok(())
+    }
+}
+
+pub struct CouponDemo {
+    client: RedisClient,
+}
+
+impl CouponDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Generates a batch of codes, then lets `contenders` users race for a
+    /// single-use code and an N-use code at the same time.
+    pub async fn demonstrate(&self, batch: usize, max_uses: u64, contenders: usize) -> Result<HashMap<String, usize>> {
+        let mut service = CouponService::new(&self.client).await?;
+
+        println!("\n=== Coupon Redemption ===\n");
+        let mut sections = Sections::new();
+        sections.next(&format!("Generating {} single-use codes and one {}-use code:", batch, max_uses));
+        let mut codes = service.create_batch("spring", batch.max(1), 1).await?;
+        let multi = service.create_batch("vip", 1, max_uses).await?;
+        for code in codes.iter().take(3) {
+            println!("   {}", code);
+        }
+        println!("   ... and {}", multi[0]);
+        let targets = [codes[0].clone(), multi[0].clone()];
+        codes.extend(multi);
+
+        sections.next(&format!("{} users redeeming both codes concurrently:", contenders));
+        let mut handles = Vec::new();
+        for user in 0..contenders {
+            let client = self.client.clone();
+            let targets = targets.clone();
+            handles.push(tokio::spawn(async move {
+                let mut service = CouponService::new(&client).await?;
+                let user = format!("user{}", user);
+                let mut won = Vec::new();
+                for code in &targets {
+                    if let RedeemOutcome::Redeemed { .. } = service.redeem(code, &user).await? {
+                        won.push(code.clone());
+                    }
+                    // Retrying must not grant a second redemption.
+                    service.redeem(code, &user).await?;
+                }
+                Ok::<_, DemoError>(won)
+            }));
+        }
+
+        let mut winners: HashMap<String, usize> = HashMap::new();
+        for handle in handles {
+            let won = handle
+                .await
+                .map_err(|e| DemoError::Demo(format!("Redeemer task failed: {}", e)))??;
+            for code in won {
+                *winners.entry(code).or_default() += 1;
+            }
+        }
+
+        for (code, limit) in [(&targets[0], 1), (&targets[1], max_uses)] {
+            let redeemed = winners.get(code).copied().unwrap_or(0);
+            println!(
+                "   {} redeemed {} times (limit {}, remaining {:?})",
+                code,
+                redeemed,
+                limit,
+                service.remaining(code).await?
+            );
+        }
+
+        sections.next(&format!("Redemption log for {}:", targets[1]));
+        for redemption in service.redemptions(&targets[1]).await?.iter().take(5) {
+            println!("   {} at {}", redemption.user, redemption.at_ms);
+        }
+
+        service.delete(&codes).await?;
+        info!("Coupon demo completed");
+        Ok(winners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_coupon_code_shape() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let code = coupon_code("spring", 42, &mut rng);
+        let parts: Vec<&str> = code.split('-').collect();
+        assert_eq!(parts[0], "SPRING");
+        assert_eq!(parts[1], "0001A");
+        assert_eq!(parts[2].len(), RANDOM_SUFFIX);
+        assert_ne!(code, coupon_code("spring", 42, &mut rng));
+    }
+
+    #[test]
+    fn test_outcome_from_script_reply() {
+        assert_eq!(RedeemOutcome::from_script_reply(0).unwrap(), RedeemOutcome::Redeemed { remaining: 0 });
+        assert_eq!(RedeemOutcome::from_script_reply(-1).unwrap(), RedeemOutcome::Exhausted);
+        assert_eq!(RedeemOutcome::from_script_reply(-2).unwrap(), RedeemOutcome::UnknownCode);
+        assert_eq!(RedeemOutcome::from_script_reply(-3).unwrap(), RedeemOutcome::AlreadyRedeemed);
+        assert!(RedeemOutcome::from_script_reply(-4).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_redemptions_never_exceed_limit() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut service = CouponService::new(&client).await.unwrap();
+        let codes = service.create_batch("test", 1, 3).await.unwrap();
+
+        let mut handles = Vec::new();
+        for user in 0..40 {
+            let client = client.clone();
+            let code = codes[0].clone();
+            handles.push(tokio::spawn(async move {
+                let mut service = CouponService::new(&client).await.unwrap();
+                service.redeem(&code, &format!("user{}", user % 20)).await.unwrap()
+            }));
+        }
+        let mut redeemed = 0;
+        for handle in handles {
+            if let RedeemOutcome::Redeemed { .. } = handle.await.unwrap() {
+                redeemed += 1;
+            }
+        }
+
+        assert_eq!(redeemed, 3);
+        assert_eq!(service.remaining(&codes[0]).await.unwrap(), Some(0));
+        assert_eq!(service.redemptions(&codes[0]).await.unwrap().len(), 3);
+        assert_eq!(service.redeem("NOPE", "user1").await.unwrap(), RedeemOutcome::UnknownCode);
+
+        service.delete(&codes).await.unwrap();
+    }
+}
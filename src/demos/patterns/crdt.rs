@@ -0,0 +1,336 @@
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::{RedisConnection, ScopedKeys, Sections};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use redis::{AsyncCommands, Script};
+use std::collections::HashMap;
+use tracing::info;
+
+/// KEYS: register hash
+/// ARGV: value, timestamp, actor
+/// Applies the write only if (timestamp, actor) beats the stored pair, so the
+/// outcome is the same no matter which order racing writes arrive in.
+const LWW_SET_SCRIPT: &str = r#"
+local ts = tonumber(ARGV[2])
+local current = redis.call('HMGET', KEYS[1], 'ts', 'actor')
+local current_ts = tonumber(current[1])
+if current_ts and (current_ts > ts or (current_ts == ts and current[2] >= ARGV[3])) then
+    return 0
+end
+redis.call('HSET', KEYS[1], 'value', ARGV[1], 'ts', ts, 'actor', ARGV[3])
+return 1
+"#;
+
+/// KEYS: destination counter hash, source counter hash
+/// Merges per-actor fields by taking the maximum, which is idempotent,
+/// commutative and associative.
+const PN_MERGE_SCRIPT: &str = r#"
+local source = redis.call('HGETALL', KEYS[2])
+for i = 1, #source, 2 do
+    local current = tonumber(redis.call('HGET', KEYS[1], source[i]) or '0')
+    if tonumber(source[i + 1]) > current then
+        redis.call('HSET', KEYS[1], source[i], source[i + 1])
+    end
+end
+return #source / 2
+"#;
+
+pub fn register_key(name: &str) -> String {
+    format!("crdt:lww:{}", name)
+}
+
+pub fn counter_key(name: &str, replica: &str) -> String {
+    format!("crdt:pn:{}:{}", name, replica)
+}
+
+/// Whether a write stamped `(ts, actor)` beats the current stamp. Ties on the
+/// timestamp fall back to the actor id so every replica picks the same winner.
+pub fn lww_wins(candidate: (u64, &str), current: Option<(u64, &str)>) -> bool {
+    match current {
+        Some(current) => candidate > current,
+        None => true,
+    }
+}
+
+/// Value of a PN-counter: sum of every actor's `:p` field minus its `:n` field.
+pub fn pn_value(fields: &HashMap<String, i64>) -> i64 {
+    fields
+        .iter()
+        .map(|(field, count)| if field.ends_with(":n") { -count } else { *count })
+        .sum()
+}
+
+/// Field-wise maximum of two counter states, the PN-counter merge rule.
+pub fn pn_merge(a: &HashMap<String, i64>, b: &HashMap<String, i64>) -> HashMap<String, i64> {
+    let mut merged = a.clone();
+    for (field, count) in b {
+        let entry = merged.entry(field.clone()).or_insert(0);
+        *entry = (*entry).max(*count);
+    }
+    merged
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LwwValue {
+    pub value: String,
+    pub ts: u64,
+    pub actor: String,
+}
+
+/// Last-write-wins register and PN-counter over plain Redis hashes.
+pub struct CrdtStore {
+    conn: RedisConnection,
+    lww_script: Script,
+    merge_script: Script,
+}
+
+impl CrdtStore {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            lww_script: Script::new(LWW_SET_SCRIPT),
+            merge_script: Script::new(PN_MERGE_SCRIPT),
+        })
+    }
+
+    /// Returns true if this write became the register's value.
+    pub async fn lww_set(&mut self, name: &str, value: &str, ts: u64, actor: &str) -> Result<bool> {
+        let applied: i64 = self
+            .lww_script
+            .key(register_key(name))
+            .arg(value)
+            .arg(ts)
+            .arg(actor)
+            .invoke_async(&mut self.conn)
+            .await?;
+        Ok(applied == 1)
+    }
+
+    pub async fn lww_get(&mut self, name: &str) -> Result<Option<LwwValue>> {
+        let fields: HashMap<String, String> = self.conn.hgetall(register_key(name)).await?;
+        match (fields.get("value"), fields.get("ts"), fields.get("actor")) {
+            (Some(value), Some(ts), Some(actor)) => Ok(Some(LwwValue {
+                value: value.clone(),
+                ts: ts
+                    .parse()
+                    .map_err(|_| DemoError::Demo(format!("Invalid LWW timestamp: {}", ts)))?,
+                actor: actor.clone(),
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Adds `delta` to the actor's own field on one replica. Each actor only
+    /// ever grows its own fields, which is what makes max-merging safe.
+    pub async fn pn_add(&mut self, name: &str, replica: &str, actor: &str, delta: i64) -> Result<()> {
+        let (field, amount) = if delta >= 0 {
+            (format!("{}:p", actor), delta)
+        } else {
+            (format!("{}:n", actor), -delta)
+        };
+        let _: () = self.conn.hincr(counter_key(name, replica), field, amount).await?;
+        Ok(())
+    }
+
+    /// Folds `source`'s state into `destination`.
+    pub async fn pn_merge(&mut self, name: &str, destination: &str, source: &str) -> Result<()> {
+        let _: i64 = self
+            .merge_script
+            .key(counter_key(name, destination))
+            .key(counter_key(name, source))
+            .invoke_async(&mut self.conn)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn pn_state(&mut self, name: &str, replica: &str) -> Result<HashMap<String, i64>> {
+        Ok(self.conn.hgetall(counter_key(name, replica)).await?)
+    }
+
+    pub async fn pn_get(&mut self, name: &str, replica: &str) -> Result<i64> {
+        Ok(pn_value(&self.pn_state(name, replica).await?))
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConvergenceReport {
+    pub lww_expected: Option<LwwValue>,
+    pub lww_actual: Option<LwwValue>,
+    pub counter_expected: i64,
+    pub counter_values: Vec<i64>,
+}
+
+impl ConvergenceReport {
+    pub fn converged(&self) -> bool {
+        self.lww_expected == self.lww_actual
+            && self.counter_values.iter().all(|value| *value == self.counter_expected)
+    }
+}
+
+pub struct CrdtDemo {
+    client: RedisClient,
+}
+
+impl CrdtDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Races `actors` writers against one LWW register and one PN-counter
+    /// replicated `actors` times, then merges replicas in different orders
+    /// and asserts everything converged.
+    pub async fn simulate(&self, actors: usize, writes: usize, seed: u64) -> Result<ConvergenceReport> {
+        let actors = actors.max(2);
+        let name = "demo";
+        let replicas: Vec<String> = (0..actors).map(|i| format!("replica{}", i)).collect();
+        let scope = self.scoped_keys(name, &replicas).await?;
+        scope.purge().await?;
+
+        println!("\n=== Conflict-Free Registers and Counters ===\n");
+        println!("{} actors, {} writes each, racing without coordination", actors, writes);
+
+        let mut handles = Vec::new();
+        for (actor, replica) in replicas.iter().enumerate() {
+            let client = self.client.clone();
+            let replica = replica.clone();
+            handles.push(tokio::spawn(async move {
+                let mut store = CrdtStore::new(&client).await?;
+                let mut rng = StdRng::seed_from_u64(seed + actor as u64);
+                let actor_id = format!("actor{}", actor);
+                let mut best: Option<LwwValue> = None;
+                let mut total = 0;
+                for write in 0..writes {
+                    // Timestamps come from skewed clocks, so collisions and
+                    // out-of-order arrivals are expected.
+                    let ts = rng.gen_range(0..(writes as u64 * 2));
+                    let value = format!("{}-{}", actor_id, write);
+                    store.lww_set(name, &value, ts, &actor_id).await?;
+                    let current = best.as_ref().map(|b| (b.ts, b.actor.as_str()));
+                    if lww_wins((ts, &actor_id), current) {
+                        best = Some(LwwValue { value, ts, actor: actor_id.clone() });
+                    }
+
+                    let delta = if rng.gen_bool(0.7) { 1 } else { -1 };
+                    store.pn_add(name, &replica, &actor_id, delta).await?;
+                    total += delta;
+                }
+                Ok::<_, DemoError>((best, total))
+            }));
+        }
+
+        let mut report = ConvergenceReport::default();
+        for handle in handles {
+            let (best, total) = handle
+                .await
+                .map_err(|e| DemoError::Demo(format!("Actor task failed: {}", e)))??;
+            if let Some(best) = best {
+                let current = report.lww_expected.as_ref().map(|b| (b.ts, b.actor.as_str()));
+                if lww_wins((best.ts, &best.actor), current) {
+                    report.lww_expected = Some(best);
+                }
+            }
+            report.counter_expected += total;
+        }
+
+        let mut store = CrdtStore::new(&self.client).await?;
+        report.lww_actual = store.lww_get(name).await?;
+
+        println!();
+        let mut sections = Sections::new();
+        sections.next("LWW register:");
+        if let Some(actual) = &report.lww_actual {
+            println!("   Winner: '{}' (ts={}, actor={})", actual.value, actual.ts, actual.actor);
+        }
+
+        sections.next("PN-counter replicas before merging:");
+        for replica in &replicas {
+            println!("   {:<10} => {}", replica, store.pn_get(name, replica).await?);
+        }
+
+        // Gossip forwards then backwards so every replica sees every other;
+        // a different order would still reach the same state.
+        for pair in replicas.windows(2) {
+            store.pn_merge(name, &pair[1], &pair[0]).await?;
+        }
+        for pair in replicas.windows(2).rev() {
+            store.pn_merge(name, &pair[0], &pair[1]).await?;
+        }
+        for replica in &replicas {
+            report.counter_values.push(store.pn_get(name, replica).await?);
+        }
+
+        sections.next("PN-counter replicas after gossip:");
+        for (replica, value) in replicas.iter().zip(&report.counter_values) {
+            println!("   {:<10} => {}", replica, value);
+        }
+        println!("   Expected total: {}", report.counter_expected);
+        println!("\n   Converged: {}", report.converged());
+
+        scope.finish().await?;
+        if !report.converged() {
+            return Err(DemoError::Demo("Replicas did not converge".to_string()));
+        }
+        info!("CRDT simulation completed");
+        Ok(report)
+    }
+
+    async fn scoped_keys(&self, name: &str, replicas: &[String]) -> Result<ScopedKeys> {
+        let scope = ScopedKeys::new(self.client.get_async_connection().await?);
+        scope.track_all(replicas.iter().map(|replica| counter_key(name, replica)));
+        scope.track(register_key(name));
+        Ok(scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    fn state(fields: &[(&str, i64)]) -> HashMap<String, i64> {
+        fields.iter().map(|(field, count)| (field.to_string(), *count)).collect()
+    }
+
+    #[test]
+    fn test_lww_tie_breaks_on_actor() {
+        assert!(lww_wins((5, "a"), None));
+        assert!(lww_wins((6, "a"), Some((5, "b"))));
+        assert!(!lww_wins((4, "z"), Some((5, "a"))));
+        assert!(lww_wins((5, "b"), Some((5, "a"))));
+        assert!(!lww_wins((5, "a"), Some((5, "a"))));
+    }
+
+    #[test]
+    fn test_pn_merge_is_commutative_and_idempotent() {
+        let a = state(&[("x:p", 3), ("x:n", 1), ("y:p", 2)]);
+        let b = state(&[("x:p", 5), ("y:n", 4)]);
+        assert_eq!(pn_merge(&a, &b), pn_merge(&b, &a));
+        assert_eq!(pn_merge(&a, &a), a);
+        assert_eq!(pn_value(&pn_merge(&a, &b)), 5 - 1 + 2 - 4);
+    }
+
+    #[tokio::test]
+    async fn test_lww_ignores_older_writes() {
+        let client = get_test_client().await;
+        let mut store = CrdtStore::new(&client).await.unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del(register_key("test")).await.unwrap();
+
+        assert!(store.lww_set("test", "new", 10, "a").await.unwrap());
+        assert!(!store.lww_set("test", "old", 9, "z").await.unwrap());
+        assert!(store.lww_set("test", "tie", 10, "b").await.unwrap());
+        assert_eq!(store.lww_get("test").await.unwrap().unwrap().value, "tie");
+
+        let _: () = conn.del(register_key("test")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_simulation_converges() {
+        let client = get_test_client().await;
+        let report = CrdtDemo::new(client).simulate(4, 20, 7).await.unwrap();
+        assert!(report.converged());
+    }
+}
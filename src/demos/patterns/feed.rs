@@ -0,0 +1,447 @@
+use crate::models::SocialGraph;
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use redis::AsyncCommands;
+use std::collections::HashSet;
+use tracing::info;
+
+const CELEBRITIES_KEY: &str = "feed:celebrities";
+const POST_SEQ_KEY: &str = "feed:post_seq";
+
+pub fn timeline_key(user: &str) -> String {
+    format!("feed:timeline:{}", user)
+}
+
+pub fn posts_key(author: &str) -> String {
+    format!("feed:posts:{}", author)
+}
+
+pub fn post_key(id: u64) -> String {
+    format!("feed:post:{}", id)
+}
+
+pub fn followers_key(user: &str) -> String {
+    format!("feed:followers:{}", user)
+}
+
+pub fn following_key(user: &str) -> String {
+    format!("feed:following:{}", user)
+}
+
+/// Entry in a timeline: post id scored by its publish time in milliseconds.
+pub type FeedEntry = (String, f64);
+
+/// The last entry of a page. Several posts can share a publish time, so
+/// the score alone can't say which of them were already read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedCursor {
+    pub score: f64,
+    pub member: String,
+}
+
+impl FeedCursor {
+    /// Whether `entry` comes after the cursor in newest-first order, ties
+    /// on score ordered by post id as [`merge_timelines`] and ZREVRANGE
+    /// order them.
+    pub fn precedes(&self, entry: &FeedEntry) -> bool {
+        entry.1 < self.score || (entry.1 == self.score && entry.0 < self.member)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedPage {
+    pub entries: Vec<FeedEntry>,
+    /// The last entry; pass back to read older posts.
+    pub next_cursor: Option<FeedCursor>,
+}
+
+/// Merges newest-first sources into one newest-first page. Ties break on
+/// post id so repeated reads return the same order, and a post that arrived
+/// through two paths (e.g. backfill plus fan-out-on-read) is kept once.
+pub fn merge_timelines(sources: Vec<Vec<FeedEntry>>, limit: usize) -> Vec<FeedEntry> {
+    let mut merged: Vec<FeedEntry> = sources.into_iter().flatten().collect();
+    merged.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+    let mut seen = HashSet::new();
+    merged.retain(|(id, _)| seen.insert(id.clone()));
+    merged.truncate(limit);
+    merged
+}
+
+/// Home timelines with hybrid delivery: regular authors fan out to follower
+/// ZSETs on write (capped at `timeline_cap`), while celebrities are merged in
+/// on read so one post doesn't trigger millions of writes.
+pub struct FeedStore {
+    conn: RedisConnection,
+    timeline_cap: usize,
+    celebrity_threshold: usize,
+}
+
+impl FeedStore {
+    pub async fn new(client: &RedisClient, timeline_cap: usize, celebrity_threshold: usize) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            timeline_cap,
+            celebrity_threshold,
+        })
+    }
+
+    /// Records the follow and flags the followee as a celebrity once it
+    /// crosses the follower threshold.
+    pub async fn follow(&mut self, follower: &str, followee: &str) -> Result<()> {
+        let (_, _, followers): ((), (), usize) = redis::pipe()
+            .sadd(following_key(follower), followee)
+            .sadd(followers_key(followee), follower)
+            .scard(followers_key(followee))
+            .query_async(&mut self.conn)
+            .await?;
+        if followers >= self.celebrity_threshold {
+            let _: () = self.conn.sadd(CELEBRITIES_KEY, followee).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn is_celebrity(&mut self, user: &str) -> Result<bool> {
+        let celebrity: bool = self.conn.sismember(CELEBRITIES_KEY, user).await?;
+        Ok(celebrity)
+    }
+
+    /// Stores the post and, unless the author is a celebrity, pushes it to
+    /// every follower timeline. Returns the post id.
+    pub async fn publish(&mut self, author: &str, body: &str, timestamp_ms: u64) -> Result<String> {
+        let id: u64 = self.conn.incr(POST_SEQ_KEY, 1).await?;
+        let post_id = id.to_string();
+        let cap = self.timeline_cap as isize;
+
+        let _: () = redis::pipe()
+            .hset_multiple(
+                post_key(id),
+                &[("author", author), ("body", body), ("ts", &timestamp_ms.to_string())],
+            )
+            .ignore()
+            .zadd(posts_key(author), &post_id, timestamp_ms)
+            .ignore()
+            .zremrangebyrank(posts_key(author), 0, -(cap + 1))
+            .ignore()
+            .query_async(&mut self.conn)
+            .await?;
+
+        if self.is_celebrity(author).await? {
+            return Ok(post_id);
+        }
+
+        let followers: Vec<String> = self.conn.smembers(followers_key(author)).await?;
+        if followers.is_empty() {
+            return Ok(post_id);
+        }
+        let mut pipe = redis::pipe();
+        for follower in &followers {
+            let key = timeline_key(follower);
+            pipe.zadd(&key, &post_id, timestamp_ms)
+                .ignore()
+                .zremrangebyrank(&key, 0, -(cap + 1))
+                .ignore();
+        }
+        let _: () = pipe.query_async(&mut self.conn).await?;
+        Ok(post_id)
+    }
+
+    /// Reads one page of `user`'s home timeline after `before`, merging
+    /// the materialized timeline with followed celebrities' posts.
+    pub async fn timeline(&mut self, user: &str, before: Option<&FeedCursor>, limit: usize) -> Result<FeedPage> {
+        let celebrities: Vec<String> = self
+            .conn
+            .sinter(&[following_key(user), CELEBRITIES_KEY.to_string()])
+            .await?;
+
+        let mut sources = Vec::with_capacity(celebrities.len() + 1);
+        let mut keys = vec![timeline_key(user)];
+        keys.extend(celebrities.iter().map(|celebrity| posts_key(celebrity)));
+        for key in keys {
+            sources.push(self.entries_after(&key, before, limit).await?);
+        }
+
+        let entries = merge_timelines(sources, limit);
+        let next_cursor = match entries.len() == limit {
+            true => entries.last().map(|(member, score)| FeedCursor { score: *score, member: member.clone() }),
+            false => None,
+        };
+        Ok(FeedPage { entries, next_cursor })
+    }
+
+    /// Up to `limit` entries of one ZSET after `cursor`, newest first. The
+    /// score bound is inclusive so posts tied with the cursor's are read,
+    /// then the ones up to and including the cursor's post are skipped.
+    async fn entries_after(&mut self, key: &str, cursor: Option<&FeedCursor>, limit: usize) -> Result<Vec<FeedEntry>> {
+        let max = cursor.map_or("+inf".to_string(), |cursor| cursor.score.to_string());
+        let mut entries = Vec::with_capacity(limit);
+        let mut offset = 0;
+        loop {
+            let batch: Vec<FeedEntry> = self
+                .conn
+                .zrevrangebyscore_limit_withscores(key, &max, "-inf", offset, limit as isize)
+                .await?;
+            let fetched = batch.len();
+            entries.extend(batch.into_iter().filter(|entry| cursor.is_none_or(|cursor| cursor.precedes(entry))));
+            if entries.len() >= limit || fetched < limit {
+                break;
+            }
+            offset += limit as isize;
+        }
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    /// Copies the followee's recent posts into the follower's timeline so a
+    /// new follow shows history immediately instead of only future posts.
+    pub async fn backfill(&mut self, follower: &str, followee: &str) -> Result<usize> {
+        if self.is_celebrity(followee).await? {
+            return Ok(0);
+        }
+        let recent: Vec<FeedEntry> = self
+            .conn
+            .zrevrange_withscores(posts_key(followee), 0, self.timeline_cap as isize - 1)
+            .await?;
+        if recent.is_empty() {
+            return Ok(0);
+        }
+
+        let key = timeline_key(follower);
+        let items: Vec<(f64, &str)> = recent.iter().map(|(id, score)| (*score, id.as_str())).collect();
+        let _: () = redis::pipe()
+            .zadd_multiple(&key, &items)
+            .ignore()
+            .zremrangebyrank(&key, 0, -(self.timeline_cap as isize + 1))
+            .ignore()
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(recent.len())
+    }
+
+    pub async fn post_body(&mut self, post_id: &str) -> Result<Option<(String, String)>> {
+        let id: u64 = post_id
+            .parse()
+            .map_err(|_| DemoError::Demo(format!("Invalid post id: {}", post_id)))?;
+        let (author, body): (Option<String>, Option<String>) =
+            self.conn.hget(post_key(id), &["author", "body"]).await?;
+        Ok(author.zip(body))
+    }
+
+    /// Deletes every key the store created for the given users.
+    pub async fn clear(&mut self, users: &[String]) -> Result<()> {
+        let mut keys = vec![CELEBRITIES_KEY.to_string(), POST_SEQ_KEY.to_string()];
+        let last_id: Option<u64> = self.conn.get(POST_SEQ_KEY).await?;
+        keys.extend((1..=last_id.unwrap_or(0)).map(post_key));
+        for user in users {
+            keys.extend([timeline_key(user), posts_key(user), followers_key(user), following_key(user)]);
+        }
+        for chunk in keys.chunks(1000) {
+            let _: () = self.conn.del(chunk).await?;
+        }
+        Ok(())
+    }
+}
+
+pub struct FeedDemo {
+    client: RedisClient,
+}
+
+impl FeedDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn demonstrate(&self, users: usize, posts: usize) -> Result<()> {
+        let graph = SocialGraph::generate(users.max(4), 2, 4, 42);
+        let threshold = (graph.users.len() / 2).max(2);
+        let mut store = FeedStore::new(&self.client, 100, threshold).await?;
+        store.clear(&graph.users).await?;
+
+        println!("\n=== News Feed / Timeline Pattern ===\n");
+
+        let mut sections = Sections::new();
+        sections.next(&format!("Seeding social graph: {} users, {} follows", graph.users.len(), graph.follows.len()));
+        // Hold back one follow to demonstrate backfill later.
+        let (late_follower, late_followee) = graph
+            .follows
+            .iter()
+            .find(|(_, followee)| !graph.celebrities.contains(followee))
+            .cloned()
+            .unwrap_or_else(|| (graph.users[2].clone(), graph.users[3].clone()));
+        for (follower, followee) in &graph.follows {
+            if (follower, followee) != (&late_follower, &late_followee) {
+                store.follow(follower, followee).await?;
+            }
+        }
+        for celebrity in &graph.celebrities {
+            println!(
+                "   {} has {} followers => celebrity: {}",
+                celebrity,
+                graph.followers(celebrity).len(),
+                store.is_celebrity(celebrity).await?
+            );
+        }
+
+        sections.next(&format!("Publishing {} posts (fan-out-on-write for regular users):", posts));
+        let base_ts = 1_700_000_000_000u64;
+        for i in 0..posts {
+            let author = &graph.users[i % graph.users.len()];
+            store
+                .publish(author, &format!("post #{} by {}", i, author), base_ts + i as u64 * 1000)
+                .await?;
+        }
+        let reader = &late_follower;
+        let mut conn = self.client.get_async_connection().await?;
+        let materialized: usize = conn.zcard(timeline_key(reader)).await?;
+        println!("   {}'s materialized timeline holds {} posts", reader, materialized);
+
+        sections.next(&format!("Reading {}'s home timeline in pages of 5 (celebrity posts merged on read):", reader));
+        let mut cursor = None;
+        for page_no in 1..=3 {
+            let page = store.timeline(reader, cursor.as_ref(), 5).await?;
+            println!("   page {}:", page_no);
+            for (post_id, _) in &page.entries {
+                if let Some((author, body)) = store.post_body(post_id).await? {
+                    println!("     [{}] {} - {}", post_id, author, body);
+                }
+            }
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        sections.next(&format!("{} follows {} and backfills their recent posts:", late_follower, late_followee));
+        store.follow(&late_follower, &late_followee).await?;
+        let copied = store.backfill(&late_follower, &late_followee).await?;
+        let after: usize = conn.zcard(timeline_key(&late_follower)).await?;
+        println!("   Backfilled {} posts, timeline now holds {}", copied, after);
+
+        store.clear(&graph.users).await?;
+        info!("Feed demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    fn entry(id: &str, score: f64) -> FeedEntry {
+        (id.to_string(), score)
+    }
+
+    #[test]
+    fn test_merge_timelines_orders_newest_first() {
+        let merged = merge_timelines(
+            vec![
+                vec![entry("5", 50.0), entry("1", 10.0)],
+                vec![entry("7", 70.0), entry("3", 30.0)],
+            ],
+            3,
+        );
+        assert_eq!(merged, vec![entry("7", 70.0), entry("5", 50.0), entry("3", 30.0)]);
+    }
+
+    #[test]
+    fn test_merge_timelines_dedups_and_breaks_ties() {
+        let merged = merge_timelines(
+            vec![vec![entry("2", 20.0), entry("1", 20.0)], vec![entry("2", 20.0)]],
+            10,
+        );
+        assert_eq!(merged, vec![entry("2", 20.0), entry("1", 20.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_and_celebrity_merge() {
+        let client = get_test_client().await;
+        let users: Vec<String> = ["t_alice", "t_bob", "t_carol", "t_star"].iter().map(|u| u.to_string()).collect();
+        let mut store = FeedStore::new(&client, 3, 3).await.unwrap();
+        store.clear(&users).await.unwrap();
+
+        for user in ["t_alice", "t_bob", "t_carol"] {
+            store.follow(user, "t_star").await.unwrap();
+        }
+        store.follow("t_alice", "t_bob").await.unwrap();
+        assert!(store.is_celebrity("t_star").await.unwrap());
+
+        for ts in 1..=5u64 {
+            store.publish("t_bob", "hi", ts * 10).await.unwrap();
+        }
+        let star_post = store.publish("t_star", "big news", 100).await.unwrap();
+
+        let mut conn = client.get_async_connection().await.unwrap();
+        let capped: usize = conn.zcard(timeline_key("t_alice")).await.unwrap();
+        assert_eq!(capped, 3);
+        let star_fanned_out: usize = conn.zcard(timeline_key("t_carol")).await.unwrap();
+        assert_eq!(star_fanned_out, 0);
+
+        let page = store.timeline("t_alice", None, 2).await.unwrap();
+        assert_eq!(page.entries[0].0, star_post);
+        assert_eq!(page.entries[1].1, 50.0);
+        let next = store.timeline("t_alice", page.next_cursor.as_ref(), 2).await.unwrap();
+        assert_eq!(next.entries.iter().map(|e| e.1).collect::<Vec<_>>(), vec![40.0, 30.0]);
+
+        store.clear(&users).await.unwrap();
+    }
+
+    #[test]
+    fn test_cursor_keeps_posts_tied_on_score() {
+        let cursor = FeedCursor { score: 20.0, member: "5".to_string() };
+        assert!(!cursor.precedes(&entry("6", 20.0)));
+        assert!(!cursor.precedes(&entry("5", 20.0)));
+        assert!(cursor.precedes(&entry("4", 20.0)));
+        assert!(cursor.precedes(&entry("9", 10.0)));
+    }
+
+    #[tokio::test]
+    async fn test_pages_split_posts_with_equal_scores() {
+        let client = get_test_client().await;
+        let users: Vec<String> = ["t_tie_reader", "t_tie_writer"].iter().map(|u| u.to_string()).collect();
+        let mut store = FeedStore::new(&client, 10, 100).await.unwrap();
+        store.clear(&users).await.unwrap();
+        store.follow("t_tie_reader", "t_tie_writer").await.unwrap();
+
+        // Five posts in the same millisecond, read two at a time.
+        let mut posted = Vec::new();
+        for _ in 0..5 {
+            posted.push(store.publish("t_tie_writer", "same instant", 1000).await.unwrap());
+        }
+        let mut read = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = store.timeline("t_tie_reader", cursor.as_ref(), 2).await.unwrap();
+            read.extend(page.entries.into_iter().map(|(id, _)| id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        posted.sort_by(|a, b| b.cmp(a));
+        assert_eq!(read, posted);
+
+        store.clear(&users).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_backfill_on_follow() {
+        let client = get_test_client().await;
+        let users: Vec<String> = ["t_reader", "t_writer"].iter().map(|u| u.to_string()).collect();
+        let mut store = FeedStore::new(&client, 10, 100).await.unwrap();
+        store.clear(&users).await.unwrap();
+
+        for ts in 1..=4u64 {
+            store.publish("t_writer", "old post", ts).await.unwrap();
+        }
+        store.follow("t_reader", "t_writer").await.unwrap();
+        assert_eq!(store.backfill("t_reader", "t_writer").await.unwrap(), 4);
+        let page = store.timeline("t_reader", None, 10).await.unwrap();
+        assert_eq!(page.entries.len(), 4);
+        assert!(page.next_cursor.is_none());
+
+        store.clear(&users).await.unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+use crate::models::SocialGraph;
+use crate::{RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use redis::AsyncCommands;
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+
+pub fn following_key(user: &str) -> String {
+    format!("graph:following:{}", user)
+}
+
+pub fn followers_key(user: &str) -> String {
+    format!("graph:followers:{}", user)
+}
+
+/// Adds every unseen neighbor to `visited` and returns them, sorted so the
+/// traversal order doesn't depend on SMEMBERS ordering.
+pub fn next_frontier(visited: &mut HashSet<String>, neighbor_lists: Vec<Vec<String>>) -> Vec<String> {
+    let mut frontier: Vec<String> = neighbor_lists
+        .into_iter()
+        .flatten()
+        .filter(|user| visited.insert(user.clone()))
+        .collect();
+    frontier.sort();
+    frontier
+}
+
+/// Adjacency lists stored as one SET per direction, which keeps "who do I
+/// follow" and "who follows me" both O(1) to look up and lets SINTER answer
+/// mutual-follow questions server-side.
+pub struct GraphStore {
+    conn: RedisConnection,
+}
+
+impl GraphStore {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+        })
+    }
+
+    pub async fn load(&mut self, graph: &SocialGraph) -> Result<()> {
+        let mut pipe = redis::pipe();
+        for (follower, followee) in &graph.follows {
+            pipe.sadd(following_key(follower), followee)
+                .ignore()
+                .sadd(followers_key(followee), follower)
+                .ignore();
+        }
+        let _: () = pipe.query_async(&mut self.conn).await?;
+        Ok(())
+    }
+
+    pub async fn following(&mut self, user: &str) -> Result<Vec<String>> {
+        let mut users: Vec<String> = self.conn.smembers(following_key(user)).await?;
+        users.sort();
+        Ok(users)
+    }
+
+    /// Users that `user` follows and who follow back.
+    pub async fn mutuals(&mut self, user: &str) -> Result<Vec<String>> {
+        let mut users: Vec<String> = self
+            .conn
+            .sinter(&[following_key(user), followers_key(user)])
+            .await?;
+        users.sort();
+        Ok(users)
+    }
+
+    /// Accounts followed by both `a` and `b`.
+    pub async fn common_following(&mut self, a: &str, b: &str) -> Result<Vec<String>> {
+        let mut users: Vec<String> = self.conn.sinter(&[following_key(a), following_key(b)]).await?;
+        users.sort();
+        Ok(users)
+    }
+
+    /// Breadth-first walk over "following" edges up to `max_depth` hops.
+    /// Each level is one pipelined round trip of SMEMBERS calls, so the cost
+    /// grows with depth times frontier size - the reason deep traversals
+    /// belong in a graph database. Returns the users first reached at each depth.
+    pub async fn reachable(&mut self, start: &str, max_depth: usize) -> Result<Vec<Vec<String>>> {
+        let mut visited = HashSet::from([start.to_string()]);
+        let mut frontier = vec![start.to_string()];
+        let mut levels = Vec::new();
+
+        for _ in 0..max_depth {
+            if frontier.is_empty() {
+                break;
+            }
+            let mut pipe = redis::pipe();
+            for user in &frontier {
+                pipe.smembers(following_key(user));
+            }
+            let neighbor_lists: Vec<Vec<String>> = pipe.query_async(&mut self.conn).await?;
+            frontier = next_frontier(&mut visited, neighbor_lists);
+            if !frontier.is_empty() {
+                levels.push(frontier.clone());
+            }
+        }
+        Ok(levels)
+    }
+
+    /// Friends-of-friends not yet followed, ranked by how many of the user's
+    /// followees follow them.
+    pub async fn suggestions(&mut self, user: &str, limit: usize) -> Result<Vec<(String, usize)>> {
+        let following = self.following(user).await?;
+        if following.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut pipe = redis::pipe();
+        for followee in &following {
+            pipe.smembers(following_key(followee));
+        }
+        let lists: Vec<Vec<String>> = pipe.query_async(&mut self.conn).await?;
+
+        let already: HashSet<&String> = following.iter().collect();
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for candidate in lists.into_iter().flatten() {
+            if candidate != user && !already.contains(&candidate) {
+                *counts.entry(candidate).or_default() += 1;
+            }
+        }
+        let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+        Ok(ranked)
+    }
+
+    pub async fn clear(&mut self, users: &[String]) -> Result<()> {
+        let keys: Vec<String> = users
+            .iter()
+            .flat_map(|user| [following_key(user), followers_key(user)])
+            .collect();
+        for chunk in keys.chunks(1000) {
+            let _: () = self.conn.del(chunk).await?;
+        }
+        Ok(())
+    }
+}
+
+pub struct GraphDemo {
+    client: RedisClient,
+}
+
+impl GraphDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn demonstrate(&self, users: usize, max_depth: usize) -> Result<()> {
+        let graph = SocialGraph::generate(users.max(4), 2, 3, 7);
+        let mut store = GraphStore::new(&self.client).await?;
+        store.clear(&graph.users).await?;
+
+        println!("\n=== Graph Adjacency Modeling ===\n");
+
+        let mut sections = Sections::new();
+        sections.next(&format!("SADD graph:following:<u> / graph:followers:<u> for {} edges", graph.follows.len()));
+        store.load(&graph).await?;
+
+        let me = &graph.users[graph.users.len() / 2];
+        let other = &graph.users[graph.users.len() / 2 + 1];
+        sections.next(&format!("SMEMBERS graph:following:{} => {:?}", me, store.following(me).await?));
+
+        sections.next("SINTER following followers (mutual follows):");
+        println!("   {} => {:?}", me, store.mutuals(me).await?);
+        println!("   Followed by both {} and {} => {:?}", me, other, store.common_following(me, other).await?);
+
+        sections.next(&format!("BFS over following edges (depth <= {}):", max_depth));
+        let levels = store.reachable(me, max_depth).await?;
+        for (depth, level) in levels.iter().enumerate() {
+            println!("   depth {}: {} users", depth + 1, level.len());
+        }
+        let reached: usize = levels.iter().map(Vec::len).sum();
+        println!("   {} of {} users reachable", reached, graph.users.len() - 1);
+
+        sections.next(&format!("Friends-of-friends suggestions for {}:", me));
+        for (candidate, paths) in store.suggestions(me, 5).await? {
+            println!("   {} (followed by {} of your follows)", candidate, paths);
+        }
+
+        println!("\n💡 When to reach for a graph database instead:");
+        println!("   - SETs are ideal for 1-2 hop questions: followers, mutuals, suggestions.");
+        println!("   - Every extra BFS level is another round trip and a frontier that can");
+        println!("     grow exponentially; shortest paths, weighted edges or pattern queries");
+        println!("     (\"friends who live in X and like Y\") are better served by a graph");
+        println!("     engine such as RedisGraph/FalkorDB with Cypher and server-side traversal.");
+
+        store.clear(&graph.users).await?;
+        info!("Graph adjacency demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    fn names(users: &[&str]) -> Vec<String> {
+        users.iter().map(|u| u.to_string()).collect()
+    }
+
+    #[test]
+    fn test_next_frontier_skips_visited() {
+        let mut visited = HashSet::from(["a".to_string()]);
+        let frontier = next_frontier(&mut visited, vec![names(&["c", "a", "b"]), names(&["b", "d"])]);
+        assert_eq!(frontier, names(&["b", "c", "d"]));
+        assert!(next_frontier(&mut visited, vec![names(&["d", "a"])]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mutuals_and_depth_limited_bfs() {
+        let client = get_test_client().await;
+        let users = names(&["g_a", "g_b", "g_c", "g_d"]);
+        let graph = SocialGraph {
+            users: users.clone(),
+            celebrities: Vec::new(),
+            follows: vec![
+                ("g_a".into(), "g_b".into()),
+                ("g_b".into(), "g_a".into()),
+                ("g_b".into(), "g_c".into()),
+                ("g_c".into(), "g_d".into()),
+            ],
+        };
+        let mut store = GraphStore::new(&client).await.unwrap();
+        store.clear(&users).await.unwrap();
+        store.load(&graph).await.unwrap();
+
+        assert_eq!(store.mutuals("g_a").await.unwrap(), names(&["g_b"]));
+        assert_eq!(store.reachable("g_a", 2).await.unwrap(), vec![names(&["g_b"]), names(&["g_c"])]);
+        assert_eq!(store.reachable("g_a", 5).await.unwrap().len(), 3);
+        assert_eq!(store.suggestions("g_a", 5).await.unwrap(), vec![("g_c".to_string(), 1)]);
+
+        store.clear(&users).await.unwrap();
+    }
+}
@@ -0,0 +1,363 @@
+use crate::{RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use redis::{AsyncCommands, Script};
+use std::time::Duration;
+use tracing::info;
+use uuid::Uuid;
+
+/// Returns expired holds to the available counter. Shared prefix of every
+/// script below so stock is reclaimed lazily on each operation.
+const RECLAIM_EXPIRED: &str = r#"
+local now = redis.call('TIME')
+local now_ms = tonumber(now[1]) * 1000 + math.floor(tonumber(now[2]) / 1000)
+local expired = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', now_ms)
+for _, hold in ipairs(expired) do
+    local qty = tonumber(redis.call('HGET', KEYS[3], hold) or '0')
+    redis.call('INCRBY', KEYS[1], qty)
+    redis.call('HDEL', KEYS[3], hold)
+    redis.call('ZREM', KEYS[2], hold)
+end
+"#;
+
+/// KEYS: available, holds zset, hold quantities hash
+/// ARGV: hold id, quantity, hold duration ms
+const RESERVE_BODY: &str = r#"
+local qty = tonumber(ARGV[2])
+local available = tonumber(redis.call('GET', KEYS[1]) or '0')
+if available < qty then
+    return 0
+end
+redis.call('DECRBY', KEYS[1], qty)
+redis.call('ZADD', KEYS[2], now_ms + tonumber(ARGV[3]), ARGV[1])
+redis.call('HSET', KEYS[3], ARGV[1], qty)
+return 1
+"#;
+
+/// KEYS: available, holds zset, hold quantities hash, sold counter
+/// ARGV: hold id
+const CONFIRM_BODY: &str = r#"
+local qty = redis.call('HGET', KEYS[3], ARGV[1])
+if not qty then
+    return 0
+end
+redis.call('HDEL', KEYS[3], ARGV[1])
+redis.call('ZREM', KEYS[2], ARGV[1])
+redis.call('INCRBY', KEYS[4], qty)
+return 1
+"#;
+
+/// KEYS: available, holds zset, hold quantities hash
+/// ARGV: hold id
+const CANCEL_BODY: &str = r#"
+local qty = redis.call('HGET', KEYS[3], ARGV[1])
+if not qty then
+    return 0
+end
+redis.call('INCRBY', KEYS[1], qty)
+redis.call('HDEL', KEYS[3], ARGV[1])
+redis.call('ZREM', KEYS[2], ARGV[1])
+return 1
+"#;
+
+/// Reservation handle returned to a buyer; confirm or cancel it before it expires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hold {
+    pub sku: String,
+    pub id: String,
+    pub quantity: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StockLevels {
+    pub available: i64,
+    pub held: i64,
+    pub sold: i64,
+}
+
+/// Stock counter plus a ZSET of time-limited holds, all mutated through Lua
+/// so reservations can never oversell and abandoned checkouts release stock.
+pub struct InventoryStore {
+    conn: RedisConnection,
+    reserve: Script,
+    confirm: Script,
+    cancel: Script,
+    reclaim: Script,
+}
+
+pub fn available_key(sku: &str) -> String {
+    format!("inventory:{}:available", sku)
+}
+
+pub fn holds_key(sku: &str) -> String {
+    format!("inventory:{}:holds", sku)
+}
+
+pub fn hold_quantities_key(sku: &str) -> String {
+    format!("inventory:{}:hold_qty", sku)
+}
+
+pub fn sold_key(sku: &str) -> String {
+    format!("inventory:{}:sold", sku)
+}
+
+impl InventoryStore {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            reserve: Script::new(&format!("{}{}", RECLAIM_EXPIRED, RESERVE_BODY)),
+            confirm: Script::new(&format!("{}{}", RECLAIM_EXPIRED, CONFIRM_BODY)),
+            cancel: Script::new(&format!("{}{}", RECLAIM_EXPIRED, CANCEL_BODY)),
+            reclaim: Script::new(&format!("{}return #expired", RECLAIM_EXPIRED)),
+        })
+    }
+
+    /// Resets a SKU to `quantity` units with no holds or sales.
+    pub async fn stock(&mut self, sku: &str, quantity: u32) -> Result<()> {
+        let _: () = redis::pipe()
+            .atomic()
+            .del(&[holds_key(sku), hold_quantities_key(sku), sold_key(sku)])
+            .set(available_key(sku), quantity)
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Places a hold on `quantity` units for `hold_for`, or `None` if not enough stock.
+    pub async fn reserve(&mut self, sku: &str, quantity: u32, hold_for: Duration) -> Result<Option<Hold>> {
+        let id = Uuid::new_v4().to_string();
+        let reserved: i64 = self
+            .reserve
+            .key(available_key(sku))
+            .key(holds_key(sku))
+            .key(hold_quantities_key(sku))
+            .arg(&id)
+            .arg(quantity)
+            .arg(hold_for.as_millis() as u64)
+            .invoke_async(&mut self.conn)
+            .await?;
+
+        Ok((reserved == 1).then(|| Hold {
+            sku: sku.to_string(),
+            id,
+            quantity,
+        }))
+    }
+
+    /// Turns a live hold into a sale. Returns false if the hold already expired.
+    pub async fn confirm(&mut self, hold: &Hold) -> Result<bool> {
+        let confirmed: i64 = self
+            .confirm
+            .key(available_key(&hold.sku))
+            .key(holds_key(&hold.sku))
+            .key(hold_quantities_key(&hold.sku))
+            .key(sold_key(&hold.sku))
+            .arg(&hold.id)
+            .invoke_async(&mut self.conn)
+            .await?;
+        Ok(confirmed == 1)
+    }
+
+    /// Releases a hold back to available stock.
+    pub async fn cancel(&mut self, hold: &Hold) -> Result<bool> {
+        let cancelled: i64 = self
+            .cancel
+            .key(available_key(&hold.sku))
+            .key(holds_key(&hold.sku))
+            .key(hold_quantities_key(&hold.sku))
+            .arg(&hold.id)
+            .invoke_async(&mut self.conn)
+            .await?;
+        Ok(cancelled == 1)
+    }
+
+    /// Eagerly reclaims expired holds; returns how many were released.
+    pub async fn release_expired(&mut self, sku: &str) -> Result<usize> {
+        let released: usize = self
+            .reclaim
+            .key(available_key(sku))
+            .key(holds_key(sku))
+            .key(hold_quantities_key(sku))
+            .invoke_async(&mut self.conn)
+            .await?;
+        Ok(released)
+    }
+
+    pub async fn levels(&mut self, sku: &str) -> Result<StockLevels> {
+        let (available, held, sold): (Option<i64>, Vec<i64>, Option<i64>) = redis::pipe()
+            .get(available_key(sku))
+            .hvals(hold_quantities_key(sku))
+            .get(sold_key(sku))
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(StockLevels {
+            available: available.unwrap_or(0),
+            held: held.iter().sum(),
+            sold: sold.unwrap_or(0),
+        })
+    }
+
+    pub async fn clear(&mut self, sku: &str) -> Result<()> {
+        let _: () = self
+            .conn
+            .del(&[available_key(sku), holds_key(sku), hold_quantities_key(sku), sold_key(sku)])
+            .await?;
+        Ok(())
+    }
+}
+
+pub struct InventoryDemo {
+    client: RedisClient,
+}
+
+impl InventoryDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn demonstrate(&self, buyers: usize, stock: u32, hold_ms: u64) -> Result<()> {
+        let sku = "sku:demo-sneakers";
+        let mut store = InventoryStore::new(&self.client).await?;
+
+        println!("\n=== Inventory Reservation Pattern ===\n");
+
+        let mut sections = Sections::new();
+        sections.next(&format!("Stocking {} units of {}", stock, sku));
+        store.stock(sku, stock).await?;
+
+        sections.next(&format!("{} buyers race to reserve one unit each:", buyers));
+        let mut handles = Vec::with_capacity(buyers);
+        for buyer in 0..buyers {
+            let client = self.client.clone();
+            handles.push(tokio::spawn(async move {
+                let mut store = InventoryStore::new(&client).await?;
+                let hold = store.reserve(sku, 1, Duration::from_millis(hold_ms)).await?;
+                Ok::<_, crate::DemoError>((buyer, hold))
+            }));
+        }
+        let mut holds = Vec::new();
+        for handle in handles {
+            let (buyer, hold) = handle
+                .await
+                .map_err(|e| crate::DemoError::Demo(format!("Buyer task failed: {}", e)))??;
+            if let Some(hold) = hold {
+                holds.push((buyer, hold));
+            }
+        }
+        let levels = store.levels(sku).await?;
+        println!("   Successful holds: {} (rejected: {})", holds.len(), buyers - holds.len());
+        println!("   Levels => {:?}", levels);
+
+        sections.next("Half of the winners check out, one cancels, the rest abandon:");
+        let confirm_count = holds.len() / 2;
+        for (buyer, hold) in holds.iter().take(confirm_count) {
+            let confirmed = store.confirm(hold).await?;
+            println!("   buyer {:>3} confirm => {}", buyer, confirmed);
+        }
+        if let Some((buyer, hold)) = holds.get(confirm_count) {
+            let cancelled = store.cancel(hold).await?;
+            println!("   buyer {:>3} cancel  => {}", buyer, cancelled);
+        }
+        println!("   Levels => {:?}", store.levels(sku).await?);
+
+        sections.next(&format!("Waiting {} ms for abandoned holds to expire...", hold_ms));
+        tokio::time::sleep(Duration::from_millis(hold_ms + 100)).await;
+        let released = store.release_expired(sku).await?;
+        println!("   Released {} expired holds", released);
+        println!("   Levels => {:?}", store.levels(sku).await?);
+
+        if let Some((buyer, hold)) = holds.last() {
+            if holds.len() > confirm_count + 1 {
+                let late = store.confirm(hold).await?;
+                println!("   buyer {:>3} late confirm => {} (hold expired)", buyer, late);
+            }
+        }
+
+        store.clear(sku).await?;
+        info!("Inventory reservation demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    #[test]
+    fn test_key_layout() {
+        assert_eq!(available_key("sku:1"), "inventory:sku:1:available");
+        assert_eq!(holds_key("sku:1"), "inventory:sku:1:holds");
+        assert_eq!(hold_quantities_key("sku:1"), "inventory:sku:1:hold_qty");
+        assert_eq!(sold_key("sku:1"), "inventory:sku:1:sold");
+    }
+
+    #[tokio::test]
+    async fn test_no_oversell_under_concurrent_buyers() {
+        let client = get_test_client().await;
+        let sku = "test_inventory:oversell";
+        let mut store = InventoryStore::new(&client).await.unwrap();
+        store.stock(sku, 10).await.unwrap();
+
+        let mut handles = Vec::new();
+        for _ in 0..100 {
+            let client = client.clone();
+            handles.push(tokio::spawn(async move {
+                let mut store = InventoryStore::new(&client).await.unwrap();
+                store.reserve(sku, 1, Duration::from_secs(30)).await.unwrap()
+            }));
+        }
+        let mut holds = Vec::new();
+        for handle in handles {
+            if let Some(hold) = handle.await.unwrap() {
+                holds.push(hold);
+            }
+        }
+        assert_eq!(holds.len(), 10);
+
+        for hold in &holds {
+            assert!(store.confirm(hold).await.unwrap());
+            assert!(!store.confirm(hold).await.unwrap());
+        }
+        let levels = store.levels(sku).await.unwrap();
+        assert_eq!(levels, StockLevels { available: 0, held: 0, sold: 10 });
+
+        store.clear(sku).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_hold_returns_stock() {
+        let client = get_test_client().await;
+        let sku = "test_inventory:expiry";
+        let mut store = InventoryStore::new(&client).await.unwrap();
+        store.stock(sku, 1).await.unwrap();
+
+        let hold = store.reserve(sku, 1, Duration::from_millis(200)).await.unwrap().unwrap();
+        assert!(store.reserve(sku, 1, Duration::from_secs(5)).await.unwrap().is_none());
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let second = store.reserve(sku, 1, Duration::from_secs(5)).await.unwrap();
+        assert!(second.is_some());
+        assert!(!store.confirm(&hold).await.unwrap());
+
+        store.clear(sku).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_releases_stock() {
+        let client = get_test_client().await;
+        let sku = "test_inventory:cancel";
+        let mut store = InventoryStore::new(&client).await.unwrap();
+        store.stock(sku, 3).await.unwrap();
+
+        let hold = store.reserve(sku, 2, Duration::from_secs(30)).await.unwrap().unwrap();
+        assert_eq!(store.levels(sku).await.unwrap().held, 2);
+        assert!(store.cancel(&hold).await.unwrap());
+        assert!(!store.cancel(&hold).await.unwrap());
+        assert_eq!(store.levels(sku).await.unwrap().available, 3);
+
+        store.clear(sku).await.unwrap();
+    }
+}
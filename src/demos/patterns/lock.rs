@@ -0,0 +1,78 @@
+use crate::utils::lock::lock_key;
+use crate::utils::{DistributedLock, Sections};
+use crate::{DemoError, RedisClient, Result};
+use redis::AsyncCommands;
+use std::time::Duration;
+use tracing::info;
+
+const COUNTER_KEY: &str = "lock-demo:counter";
+const LOCK_NAME: &str = "lock-demo";
+
+pub struct LockDemo {
+    client: RedisClient,
+}
+
+impl LockDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// `workers` tasks bump a counter with a read-modify-write that is only
+    /// safe under the lock, then a holder outlives its TTL with and without
+    /// the watchdog. Returns the final counter.
+    pub async fn demonstrate(&self, workers: usize, rounds: usize, ttl: Duration) -> Result<u64> {
+        let mut conn = self.client.get_async_connection().await?;
+        let _: () = conn.del(&[COUNTER_KEY, &lock_key(LOCK_NAME)]).await?;
+        let lock = DistributedLock::new(&self.client, LOCK_NAME, ttl).await?;
+
+        println!("\n=== Distributed Lock ===\n");
+        let mut sections = Sections::new();
+        sections.next(&format!("{} workers each incrementing a counter {} times with GET + SET under the lock:", workers, rounds));
+        let mut handles = Vec::new();
+        for worker in 0..workers {
+            let lock = lock.clone();
+            let mut conn = conn.clone();
+            handles.push(tokio::spawn(async move {
+                for _ in 0..rounds {
+                    let guard = lock.acquire(Duration::from_secs(10)).await?;
+                    let value: u64 = conn.get::<_, Option<u64>>(COUNTER_KEY).await?.unwrap_or(0);
+                    // Widen the race window: without the lock, others would read the same value here.
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    let _: () = conn.set(COUNTER_KEY, value + 1).await?;
+                    println!("   worker {} holds {} → counter {}", worker, &guard.token()[..8], value + 1);
+                    guard.release().await?;
+                }
+                Ok::<_, DemoError>(())
+            }));
+        }
+        for handle in handles {
+            handle.await.map_err(|e| DemoError::Demo(format!("Lock worker failed: {}", e)))??;
+        }
+        let counter: u64 = conn.get(COUNTER_KEY).await?;
+        let expected = (workers * rounds) as u64;
+        let verdict = if counter == expected { "✅ no lost updates" } else { "❌ updates were lost" };
+        println!("   counter = {} (expected {}) {}", counter, expected, verdict);
+
+        sections.next(&format!("Work taking 3× the {:?} TTL without the watchdog:", ttl));
+        let mut slow = lock.acquire(ttl).await?;
+        tokio::time::sleep(ttl * 3).await;
+        let rival = lock.try_acquire().await?;
+        println!("   a rival {} the lock while the slow holder was still working", if rival.is_some() { "took" } else { "did not get" });
+        println!("   slow holder extend → {}, release → {} (the rival's lock is untouched)", slow.extend().await?, slow.release().await?);
+        if let Some(rival) = rival {
+            rival.release().await?;
+        }
+
+        sections.next(&format!("The same work with the watchdog extending every {:?}:", crate::utils::lock::watchdog_interval(ttl)));
+        let mut slow = lock.acquire(ttl).await?;
+        slow.keep_alive();
+        tokio::time::sleep(ttl * 3).await;
+        let rival = lock.try_acquire().await?;
+        println!("   a rival {} the lock", if rival.is_some() { "took" } else { "did not get" });
+        println!("   lost: {}, release → {}", slow.is_lost(), slow.release().await?);
+
+        let _: () = conn.del(COUNTER_KEY).await?;
+        info!("Lock demo completed");
+        Ok(counter)
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+pub const MAINTENANCE_KEY: &str = "maintenance:mode";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceNotice {
+    pub message: String,
+    pub since: DateTime<Utc>,
+}
+
+/// Turns maintenance mode on. With `duration`, the flag expires by itself so
+/// a forgotten switch can't keep the system down.
+pub async fn enable(conn: &mut RedisConnection, message: &str, duration: Option<Duration>) -> Result<MaintenanceNotice> {
+    let notice = MaintenanceNotice { message: message.to_string(), since: Utc::now() };
+    let payload = serde_json::to_string(&notice)?;
+    match duration {
+        Some(duration) => {
+            let _: () = conn.set_ex(MAINTENANCE_KEY, payload, duration.as_secs().max(1)).await?;
+        }
+        None => {
+            let _: () = conn.set(MAINTENANCE_KEY, payload).await?;
+        }
+    }
+    Ok(notice)
+}
+
+/// Turns maintenance mode off. Returns whether it was on.
+pub async fn disable(conn: &mut RedisConnection) -> Result<bool> {
+    let removed: u64 = conn.del(MAINTENANCE_KEY).await?;
+    Ok(removed > 0)
+}
+
+pub async fn status(conn: &mut RedisConnection) -> Result<Option<MaintenanceNotice>> {
+    let payload: Option<String> = conn.get(MAINTENANCE_KEY).await?;
+    Ok(match payload {
+        Some(payload) => Some(serde_json::from_str(&payload)?),
+        None => None,
+    })
+}
+
+struct CachedState {
+    notice: Option<MaintenanceNotice>,
+    checked_at: Option<Instant>,
+}
+
+/// Per-process view of the maintenance flag. Every request checks the gate,
+/// but Redis is only asked once per `grace` period, so a flip reaches all
+/// components within `grace` while costing one GET per process per period.
+#[derive(Clone)]
+pub struct MaintenanceGate {
+    conn: RedisConnection,
+    grace: Duration,
+    state: Arc<Mutex<CachedState>>,
+}
+
+impl MaintenanceGate {
+    pub async fn new(client: &RedisClient, grace: Duration) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            grace,
+            state: Arc::new(Mutex::new(CachedState { notice: None, checked_at: None })),
+        })
+    }
+
+    /// The active notice, if any. If Redis is unreachable the last known
+    /// state is kept rather than failing every request.
+    pub async fn check(&self) -> Option<MaintenanceNotice> {
+        let mut state = self.state.lock().await;
+        let fresh = state.checked_at.is_some_and(|at| at.elapsed() < self.grace);
+        if !fresh {
+            let mut conn = self.conn.clone();
+            match status(&mut conn).await {
+                Ok(notice) => state.notice = notice,
+                Err(e) => warn!("Maintenance check failed, keeping cached state: {}", e),
+            }
+            state.checked_at = Some(Instant::now());
+        }
+        state.notice.clone()
+    }
+}
+
+pub struct MaintenanceDemo {
+    client: RedisClient,
+}
+
+impl MaintenanceDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Runs a simulated web server and worker that both consult the gate,
+    /// flips maintenance on and off underneath them and reports how long
+    /// each took to notice.
+    pub async fn demonstrate(&self, grace: Duration) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        disable(&mut conn).await?;
+
+        println!("\n=== Maintenance Mode Switch ===\n");
+        println!("Components re-check {} at most every {:?}\n", MAINTENANCE_KEY, grace);
+
+        let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+        let mut handles = Vec::new();
+        for (component, tick) in [("web", Duration::from_millis(100)), ("worker", Duration::from_millis(250))] {
+            let gate = MaintenanceGate::new(&self.client, grace).await?;
+            let mut stop = stop_rx.clone();
+            handles.push(tokio::spawn(async move {
+                let mut down = false;
+                let mut served = 0u64;
+                loop {
+                    match gate.check().await {
+                        Some(notice) if !down => {
+                            down = true;
+                            let lag = (Utc::now() - notice.since).num_milliseconds();
+                            println!("   [{:<6}] entering maintenance after {}ms: {}", component, lag, notice.message);
+                        }
+                        None if down => {
+                            down = false;
+                            println!("   [{:<6}] back in service", component);
+                        }
+                        None => served += 1,
+                        Some(_) => {}
+                    }
+                    tokio::select! {
+                        _ = tokio::time::sleep(tick) => {}
+                        _ = stop.changed() => return served,
+                    }
+                }
+            }));
+        }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let mut sections = Sections::new();
+        sections.next("Operator: redis-demo admin maintenance on --message \"Database upgrade\"");
+        enable(&mut conn, "Database upgrade", Some(Duration::from_secs(60))).await?;
+        tokio::time::sleep(grace + Duration::from_secs(1)).await;
+
+        sections.next("Operator: redis-demo admin maintenance off");
+        disable(&mut conn).await?;
+        tokio::time::sleep(grace + Duration::from_secs(1)).await;
+
+        let _ = stop_tx.send(true);
+        for handle in handles {
+            let served = handle.await.unwrap_or(0);
+            println!("   handled {} requests while in service", served);
+        }
+
+        println!("\n💡 The grace period bounds how stale a component can be; shorten it for");
+        println!("   faster reaction, lengthen it to cut Redis reads. Setting the flag with a");
+        println!("   TTL makes sure maintenance ends even if nobody turns it off.");
+        info!("Maintenance mode demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    #[test]
+    fn test_notice_round_trips_as_json() {
+        let notice = MaintenanceNotice { message: "upgrade".to_string(), since: Utc::now() };
+        let payload = serde_json::to_string(&notice).unwrap();
+        assert_eq!(serde_json::from_str::<MaintenanceNotice>(&payload).unwrap(), notice);
+    }
+
+    #[tokio::test]
+    async fn test_gate_caches_within_grace() {
+        let client = get_test_client().await;
+        let mut conn = client.get_async_connection().await.unwrap();
+        disable(&mut conn).await.unwrap();
+
+        let gate = MaintenanceGate::new(&client, Duration::from_millis(300)).await.unwrap();
+        assert!(gate.check().await.is_none());
+
+        enable(&mut conn, "test", Some(Duration::from_secs(5))).await.unwrap();
+        // Still cached as off until the grace period passes.
+        assert!(gate.check().await.is_none());
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        assert_eq!(gate.check().await.unwrap().message, "test");
+
+        assert!(disable(&mut conn).await.unwrap());
+        assert!(!disable(&mut conn).await.unwrap());
+    }
+}
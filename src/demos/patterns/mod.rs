@@ -0,0 +1,25 @@
+pub mod calendar;
+pub mod coupons;
+pub mod crdt;
+pub mod feed;
+pub mod graph;
+pub mod inventory;
+pub mod lock;
+pub mod maintenance;
+pub mod soft_delete;
+pub mod voting;
+pub mod waitlist;
+pub mod workflow;
+
+pub use calendar::{Calendar, CalendarDemo};
+pub use coupons::{CouponDemo, CouponService, RedeemOutcome};
+pub use crdt::{CrdtDemo, CrdtStore};
+pub use feed::{FeedDemo, FeedStore};
+pub use graph::{GraphDemo, GraphStore};
+pub use inventory::{InventoryDemo, InventoryStore};
+pub use lock::LockDemo;
+pub use maintenance::{MaintenanceDemo, MaintenanceGate};
+pub use soft_delete::SoftDeleteDemo;
+pub use voting::{VoteOutcome, VotingDemo, VotingService};
+pub use waitlist::{Waitlist, WaitlistDemo, WaitlistEvent};
+pub use workflow::{OrderState, WorkflowStore, WorkflowTimeoutHandler};
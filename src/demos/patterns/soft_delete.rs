@@ -0,0 +1,91 @@
+use crate::models::User;
+use crate::repository::audit::{audit_key, history};
+use crate::repository::user::{trash_key, RestoreOutcome, UserRepository};
+use crate::repository::AuditTrail;
+use crate::utils::Sections;
+use crate::{RedisClient, Result};
+use chrono::Utc;
+use redis::AsyncCommands;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+pub struct SoftDeleteDemo {
+    client: RedisClient,
+}
+
+impl SoftDeleteDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Deletes a user by mistake, restores it from the trash, then shows the
+    /// trash expiring and a restore being refused after someone else took
+    /// the username.
+    pub async fn demonstrate(&self, trash_ttl: Duration) -> Result<()> {
+        let mut repo = UserRepository::new(&self.client, trash_ttl)
+            .await?
+            .with_middleware(Arc::new(AuditTrail));
+        repo.set_actor("signup");
+        let mut conn = self.client.get_async_connection().await?;
+
+        println!("\n=== Soft Delete and Restore ===\n");
+
+        let alice = User::new("alice_sd".to_string(), "alice_sd@example.com".to_string(), "Alice".to_string());
+        let bob = User::new("bob_sd".to_string(), "bob_sd@example.com".to_string(), "Bob".to_string());
+        repo.save(&alice).await?;
+        repo.save(&bob).await?;
+        let mut sections = Sections::new();
+        sections.next(&format!("Saved {} and {}", alice.username, bob.username));
+
+        sections.next(&format!("Support agent deletes {} by mistake:", alice.username));
+        repo.set_actor("support-agent");
+        repo.delete(alice.id).await?;
+        let ttl: i64 = conn.ttl(trash_key(alice.id)).await?;
+        println!("   lookup by username: {:?}", repo.find_by_username(&alice.username).await?.map(|u| u.id));
+        println!("   {} kept for {}s", trash_key(alice.id), ttl);
+
+        sections.next("Restoring:");
+        match repo.restore(alice.id).await? {
+            RestoreOutcome::Restored(user) => println!("   ✅ {} is back with all indexes", user.username),
+            other => println!("   ❌ {:?}", other),
+        }
+
+        sections.next(&format!("Deleting {} and reusing the username before restoring:", bob.username));
+        repo.delete(bob.id).await?;
+        let impostor = User::new(bob.username.clone(), "other@example.com".to_string(), "Other Bob".to_string());
+        repo.save(&impostor).await?;
+        println!("   restore → {:?}", repo.restore(bob.id).await?);
+
+        sections.next("Purging expired trash:");
+        for (id, expires_at) in repo.trashed().await? {
+            println!("   {} expires in {}ms", id, expires_at - Utc::now().timestamp_millis());
+        }
+        // Pretend the retention period is over.
+        let purged = repo.purge_expired(i64::MAX).await?;
+        println!("   purged {} user(s) permanently", purged.len());
+        println!("   restore → {:?}", repo.restore(bob.id).await?);
+
+        sections.next(&format!("Audit trail of {} (redis-demo audit show user {}):", alice.username, alice.id));
+        for entry in history(&mut conn, "user", &alice.id.to_string(), 10).await?.iter().rev() {
+            println!("   {:<8} by {:<14} {} fields changed", entry.op, entry.actor, entry.diff.as_object().map_or(0, |d| d.len()));
+        }
+
+        for user in [&alice, &impostor] {
+            repo.delete(user.id).await?;
+        }
+        repo.purge_expired(i64::MAX).await?;
+        let audit_keys: Vec<String> = [&alice, &bob, &impostor]
+            .iter()
+            .map(|user| audit_key("user", &user.id.to_string()))
+            .collect();
+        let _: () = conn.del(audit_keys).await?;
+
+        println!("\n💡 Moving the user hash and dropping its indexes in one script keeps");
+        println!("   deleted users out of lookups; the trash TTL bounds how long they can");
+        println!("   be recovered, and restore re-checks that the indexes are still free.");
+        println!("   The audit stream records who did it and what changed at each step.");
+        info!("Soft delete demo completed");
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::{RedisConnection, ScopedKeys};
+use redis::{AsyncCommands, Script};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::info;
+
+/// KEYS: item counter, item voters set, per-user rate window counter
+/// ARGV: user id, max attempts per window, window length ms
+///
+/// Every attempt counts against the user's window (so spamming the same
+/// item is throttled too); only first votes from a user bump the counter.
+const VOTE_SCRIPT: &str = r#"
+local attempts = redis.call('INCR', KEYS[3])
+if attempts == 1 then
+    redis.call('PEXPIRE', KEYS[3], ARGV[3])
+end
+if attempts > tonumber(ARGV[2]) then
+    return -2
+end
+if redis.call('SADD', KEYS[2], ARGV[1]) == 0 then
+    return -1
+end
+return redis.call('INCR', KEYS[1])
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteOutcome {
+    Accepted { total: i64 },
+    AlreadyVoted,
+    RateLimited,
+}
+
+impl VoteOutcome {
+    pub fn from_script_reply(reply: i64) -> Result<Self> {
+        match reply {
+            -2 => Ok(VoteOutcome::RateLimited),
+            -1 => Ok(VoteOutcome::AlreadyVoted),
+            total if total > 0 => Ok(VoteOutcome::Accepted { total }),
+            other => Err(DemoError::Demo(format!("Unexpected vote script reply: {}", other))),
+        }
+    }
+}
+
+pub fn count_key(item: &str) -> String {
+    format!("votes:{}:count", item)
+}
+
+pub fn voters_key(item: &str) -> String {
+    format!("votes:{}:voters", item)
+}
+
+pub fn rate_key(user: &str) -> String {
+    format!("votes:rate:{}", user)
+}
+
+/// Like/vote counter that rejects repeat votes and throttles bursts per user.
+pub struct VotingService {
+    conn: RedisConnection,
+    script: Script,
+    max_attempts: u32,
+    window: Duration,
+}
+
+impl VotingService {
+    pub async fn new(client: &RedisClient, max_attempts: u32, window: Duration) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            script: Script::new(VOTE_SCRIPT),
+            max_attempts,
+            window,
+        })
+    }
+
+    pub async fn vote(&mut self, user: &str, item: &str) -> Result<VoteOutcome> {
+        let reply: i64 = self
+            .script
+            .key(count_key(item))
+            .key(voters_key(item))
+            .key(rate_key(user))
+            .arg(user)
+            .arg(self.max_attempts)
+            .arg(self.window.as_millis() as u64)
+            .invoke_async(&mut self.conn)
+            .await?;
+        VoteOutcome::from_script_reply(reply)
+    }
+
+    pub async fn count(&mut self, item: &str) -> Result<i64> {
+        let count: Option<i64> = self.conn.get(count_key(item)).await?;
+        Ok(count.unwrap_or(0))
+    }
+
+    pub async fn has_voted(&mut self, user: &str, item: &str) -> Result<bool> {
+        let voted: bool = self.conn.sismember(voters_key(item), user).await?;
+        Ok(voted)
+    }
+
+    /// Counter and voter set must agree; a mismatch means a lost or double count.
+    pub async fn verify(&mut self, item: &str) -> Result<bool> {
+        let voters: i64 = self.conn.scard(voters_key(item)).await?;
+        Ok(voters == self.count(item).await?)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SimulationReport {
+    pub accepted: usize,
+    pub already_voted: usize,
+    pub rate_limited: usize,
+    pub totals: HashMap<String, i64>,
+    pub consistent: bool,
+}
+
+impl SimulationReport {
+    fn record(&mut self, outcome: VoteOutcome) {
+        match outcome {
+            VoteOutcome::Accepted { .. } => self.accepted += 1,
+            VoteOutcome::AlreadyVoted => self.already_voted += 1,
+            VoteOutcome::RateLimited => self.rate_limited += 1,
+        }
+    }
+
+    fn merge(&mut self, other: SimulationReport) {
+        self.accepted += other.accepted;
+        self.already_voted += other.already_voted;
+        self.rate_limited += other.rate_limited;
+    }
+}
+
+pub struct VotingDemo {
+    client: RedisClient,
+}
+
+impl VotingDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Honest users vote once per item (and occasionally double-click), while
+    /// one spammer bursts votes. Counts must equal distinct voters afterwards.
+    pub async fn simulate(&self, users: usize, items: usize, spam_burst: usize) -> Result<SimulationReport> {
+        if items == 0 {
+            return Err(DemoError::Configuration("The voting simulation needs at least one item".to_string()));
+        }
+        let item_names: Vec<String> = (1..=items).map(|i| format!("post:{}", i)).collect();
+        let scope = self.scoped_keys(&item_names, users).await?;
+        scope.purge().await?;
+
+        println!("\n=== Vote Counter with Abuse Protection ===\n");
+        println!("{} users voting on {} items, one spammer bursting {} votes", users, items, spam_burst);
+
+        let mut handles = Vec::new();
+        for user in 0..users {
+            let client = self.client.clone();
+            let item_names = item_names.clone();
+            handles.push(tokio::spawn(async move {
+                // Room for a double-click on every item, so only the spammer
+                // ever hits the limit.
+                let limit = 2 * item_names.len() as u32;
+                let mut service = VotingService::new(&client, limit, Duration::from_secs(10)).await?;
+                let user_id = format!("user:{}", user);
+                let mut report = SimulationReport::default();
+                for item in &item_names {
+                    report.record(service.vote(&user_id, item).await?);
+                    // Every third user double-clicks.
+                    if user % 3 == 0 {
+                        report.record(service.vote(&user_id, item).await?);
+                    }
+                }
+                Ok::<_, DemoError>(report)
+            }));
+        }
+
+        let spam_client = self.client.clone();
+        let target = item_names[0].clone();
+        handles.push(tokio::spawn(async move {
+            let mut service = VotingService::new(&spam_client, 20, Duration::from_secs(10)).await?;
+            let mut report = SimulationReport::default();
+            // Only the first vote counts; the rest are duplicates until the
+            // window fills up, after which they are rejected before SADD.
+            for _ in 0..spam_burst {
+                report.record(service.vote("spammer", &target).await?);
+            }
+            Ok::<_, DemoError>(report)
+        }));
+
+        let mut report = SimulationReport::default();
+        for handle in handles {
+            let partial = handle
+                .await
+                .map_err(|e| DemoError::Demo(format!("Voter task failed: {}", e)))??;
+            report.merge(partial);
+        }
+
+        let mut service = VotingService::new(&self.client, 20, Duration::from_secs(10)).await?;
+        report.consistent = true;
+        for item in &item_names {
+            let total = service.count(item).await?;
+            report.consistent &= service.verify(item).await?;
+            report.totals.insert(item.clone(), total);
+        }
+
+        println!("\n   Accepted votes:    {}", report.accepted);
+        println!("   Duplicate votes:   {}", report.already_voted);
+        println!("   Rate-limited:      {}", report.rate_limited);
+        for item in &item_names {
+            println!("   {:<10} => {} votes", item, report.totals[item]);
+        }
+        println!("   Counters consistent with voter sets: {}", report.consistent);
+
+        scope.finish().await?;
+        info!("Voting simulation completed");
+        Ok(report)
+    }
+
+    async fn scoped_keys(&self, items: &[String], users: usize) -> Result<ScopedKeys> {
+        let scope = ScopedKeys::new(self.client.get_async_connection().await?);
+        scope.track_all(items.iter().flat_map(|item| [count_key(item), voters_key(item)]));
+        scope.track_all((0..users).map(|user| rate_key(&format!("user:{}", user))));
+        scope.track(rate_key("spammer"));
+        Ok(scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    #[test]
+    fn test_outcome_from_script_reply() {
+        assert_eq!(VoteOutcome::from_script_reply(-2).unwrap(), VoteOutcome::RateLimited);
+        assert_eq!(VoteOutcome::from_script_reply(-1).unwrap(), VoteOutcome::AlreadyVoted);
+        assert_eq!(VoteOutcome::from_script_reply(7).unwrap(), VoteOutcome::Accepted { total: 7 });
+        assert!(VoteOutcome::from_script_reply(0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_repeat_votes_rejected() {
+        let client = get_test_client().await;
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn
+            .del(&[count_key("test_vote"), voters_key("test_vote"), rate_key("test_voter")])
+            .await
+            .unwrap();
+
+        let mut service = VotingService::new(&client, 10, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(
+            service.vote("test_voter", "test_vote").await.unwrap(),
+            VoteOutcome::Accepted { total: 1 }
+        );
+        assert_eq!(service.vote("test_voter", "test_vote").await.unwrap(), VoteOutcome::AlreadyVoted);
+        assert!(service.has_voted("test_voter", "test_vote").await.unwrap());
+        assert_eq!(service.count("test_vote").await.unwrap(), 1);
+
+        let _: () = conn
+            .del(&[count_key("test_vote"), voters_key("test_vote"), rate_key("test_voter")])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_burst_is_rate_limited() {
+        let client = get_test_client().await;
+        let mut conn = client.get_async_connection().await.unwrap();
+        let items: Vec<String> = (0..10).map(|i| format!("test_burst:{}", i)).collect();
+        let mut keys: Vec<String> = items.iter().flat_map(|i| [count_key(i), voters_key(i)]).collect();
+        keys.push(rate_key("test_burster"));
+        let _: () = conn.del(&keys).await.unwrap();
+
+        let mut service = VotingService::new(&client, 3, Duration::from_secs(10)).await.unwrap();
+        let mut outcomes = Vec::new();
+        for item in &items {
+            outcomes.push(service.vote("test_burster", item).await.unwrap());
+        }
+        let accepted = outcomes.iter().filter(|o| matches!(o, VoteOutcome::Accepted { .. })).count();
+        let limited = outcomes.iter().filter(|o| **o == VoteOutcome::RateLimited).count();
+        assert_eq!(accepted, 3);
+        assert_eq!(limited, 7);
+
+        let _: () = conn.del(&keys).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_simulation_counts_stay_accurate() {
+        let client = get_test_client().await;
+        let demo = VotingDemo::new(client);
+        let report = demo.simulate(30, 3, 50).await.unwrap();
+
+        assert!(report.consistent);
+        assert_eq!(report.rate_limited, 30);
+        assert_eq!(report.totals["post:1"], 31);
+        assert_eq!(report.totals["post:2"], 30);
+    }
+
+    #[tokio::test]
+    async fn test_simulation_with_many_items_limits_only_the_spammer() {
+        let client = get_test_client().await;
+        let demo = VotingDemo::new(client);
+        let report = demo.simulate(6, 12, 0).await.unwrap();
+
+        assert!(report.consistent);
+        assert_eq!(report.rate_limited, 0);
+        assert_eq!(report.already_voted, 2 * 12);
+        assert!(report.totals.values().all(|&total| total == 6));
+    }
+}
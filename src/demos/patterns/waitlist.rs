@@ -0,0 +1,244 @@
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use chrono::Utc;
+use futures::StreamExt;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::info;
+
+/// Users this close to the front get an individual position update after
+/// every admission; everyone else only sees the broadcast `Advanced` event.
+const NOTIFY_FRONT: isize = 10;
+
+pub fn waitlist_key(name: &str) -> String {
+    format!("waitlist:{}", name)
+}
+
+pub fn events_channel(name: &str) -> String {
+    format!("waitlist:{}:events", name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WaitlistEvent {
+    Admitted { users: Vec<String> },
+    Advanced { by: u64, remaining: u64 },
+    Position { user: String, position: u64 },
+}
+
+/// Position a waiting user moves to once `admitted` users ahead of them left.
+pub fn position_after_admit(position: u64, admitted: u64) -> u64 {
+    position.saturating_sub(admitted).max(1)
+}
+
+/// First-come-first-served waitlist ordered by join time. Positions are
+/// 1-based.
+pub struct Waitlist {
+    conn: RedisConnection,
+    name: String,
+}
+
+impl Waitlist {
+    pub async fn new(client: &RedisClient, name: &str) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            name: name.to_string(),
+        })
+    }
+
+    /// Joins at `joined_ms` and returns the user's position. Joining twice
+    /// keeps the original place in line.
+    pub async fn join(&mut self, user: &str, joined_ms: i64) -> Result<u64> {
+        let key = waitlist_key(&self.name);
+        let (rank,): (Option<u64>,) = redis::pipe()
+            .atomic()
+            .cmd("ZADD")
+            .arg(&key)
+            .arg("NX")
+            .arg(joined_ms)
+            .arg(user)
+            .ignore()
+            .zrank(&key, user)
+            .query_async(&mut self.conn)
+            .await?;
+        rank.map(|rank| rank + 1)
+            .ok_or_else(|| DemoError::Demo(format!("{} vanished from the waitlist", user)))
+    }
+
+    pub async fn position(&mut self, user: &str) -> Result<Option<u64>> {
+        let rank: Option<u64> = self.conn.zrank(waitlist_key(&self.name), user).await?;
+        Ok(rank.map(|rank| rank + 1))
+    }
+
+    pub async fn len(&mut self) -> Result<u64> {
+        Ok(self.conn.zcard(waitlist_key(&self.name)).await?)
+    }
+
+    pub async fn leave(&mut self, user: &str) -> Result<bool> {
+        let removed: u64 = self.conn.zrem(waitlist_key(&self.name), user).await?;
+        Ok(removed > 0)
+    }
+
+    /// Admits up to `count` users from the front (ZPOPMIN), then broadcasts
+    /// the admissions, how far the line moved and the new positions of the
+    /// users now closest to the front.
+    pub async fn admit(&mut self, count: usize) -> Result<Vec<String>> {
+        let key = waitlist_key(&self.name);
+        let popped: Vec<(String, f64)> = self.conn.zpopmin(&key, count as isize).await?;
+        let admitted: Vec<String> = popped.into_iter().map(|(user, _)| user).collect();
+        if admitted.is_empty() {
+            return Ok(admitted);
+        }
+
+        let remaining = self.len().await?;
+        let front: Vec<String> = self.conn.zrange(&key, 0, NOTIFY_FRONT - 1).await?;
+        let mut events = vec![
+            WaitlistEvent::Admitted { users: admitted.clone() },
+            WaitlistEvent::Advanced { by: admitted.len() as u64, remaining },
+        ];
+        events.extend(
+            front
+                .into_iter()
+                .enumerate()
+                .map(|(rank, user)| WaitlistEvent::Position { user, position: rank as u64 + 1 }),
+        );
+
+        let channel = events_channel(&self.name);
+        let mut pipe = redis::pipe();
+        for event in &events {
+            pipe.publish(&channel, serde_json::to_string(event)?).ignore();
+        }
+        let _: () = pipe.query_async(&mut self.conn).await?;
+        Ok(admitted)
+    }
+
+    pub async fn clear(&mut self) -> Result<()> {
+        let _: () = self.conn.del(waitlist_key(&self.name)).await?;
+        Ok(())
+    }
+}
+
+pub struct WaitlistDemo {
+    client: RedisClient,
+}
+
+impl WaitlistDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// `users` join, then batches of `batch` are admitted while one waiting
+    /// user's client follows along over pub/sub.
+    pub async fn demonstrate(&self, users: usize, batch: usize) -> Result<()> {
+        let users = users.max(1);
+        let name = "launch";
+        let mut waitlist = Waitlist::new(&self.client, name).await?;
+        waitlist.clear().await?;
+
+        println!("\n=== Waitlist with Live Positions ===\n");
+
+        let mut sections = Sections::new();
+        sections.next(&format!("{} users join:", users));
+        let start = Utc::now().timestamp_millis();
+        for user in 0..users {
+            let position = waitlist.join(&format!("user{}", user), start + user as i64).await?;
+            if user < 3 || user + 1 == users {
+                println!("   user{} is #{}", user, position);
+            }
+        }
+        let watched = format!("user{}", users * 2 / 3);
+        let joined_again = waitlist.join(&watched, Utc::now().timestamp_millis()).await?;
+        println!("   {} rejoins and keeps #{}", watched, joined_again);
+
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(events_channel(name)).await?;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let follower = watched.clone();
+        let subscriber = tokio::spawn(async move {
+            let mut messages = pubsub.into_on_message();
+            let mut position = joined_again;
+            while let Some(message) = messages.next().await {
+                let event = message
+                    .get_payload::<String>()
+                    .ok()
+                    .and_then(|payload| serde_json::from_str::<WaitlistEvent>(&payload).ok());
+                let update = match event {
+                    Some(WaitlistEvent::Admitted { users }) if users.contains(&follower) => {
+                        format!("🎉 {} admitted!", follower)
+                    }
+                    Some(WaitlistEvent::Advanced { by, .. }) => {
+                        position = position_after_admit(position, by);
+                        format!("{} estimates #{} (line moved {})", follower, position, by)
+                    }
+                    Some(WaitlistEvent::Position { user, position: exact }) if user == follower => {
+                        position = exact;
+                        format!("{} is now #{} (exact)", follower, exact)
+                    }
+                    _ => continue,
+                };
+                if tx.send(update).is_err() {
+                    break;
+                }
+            }
+        });
+
+        sections.next(&format!("Admitting {} at a time ({}'s client listening):", batch, watched));
+        let mut round = 0;
+        while waitlist.len().await? > 0 {
+            round += 1;
+            let admitted = waitlist.admit(batch.max(1)).await?;
+            println!("   round {}: admitted {}..{}", round, admitted[0], admitted[admitted.len() - 1]);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            while let Ok(update) = rx.try_recv() {
+                println!("      📣 {}", update);
+            }
+            if let Some(position) = waitlist.position(&watched).await? {
+                println!("      ZRANK says {} is #{}", watched, position);
+            }
+        }
+        subscriber.abort();
+
+        waitlist.clear().await?;
+        info!("Waitlist demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_after_admit() {
+        assert_eq!(position_after_admit(12, 5), 7);
+        assert_eq!(position_after_admit(3, 5), 1);
+    }
+
+    #[test]
+    fn test_event_json_shape() {
+        let event = WaitlistEvent::Advanced { by: 5, remaining: 20 };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"type":"advanced","by":5,"remaining":20}"#);
+        assert_eq!(serde_json::from_str::<WaitlistEvent>(&json).unwrap(), event);
+    }
+
+    #[tokio::test]
+    async fn test_join_position_and_admit() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut waitlist = Waitlist::new(&client, "test").await.unwrap();
+        waitlist.clear().await.unwrap();
+
+        assert_eq!(waitlist.join("a", 100).await.unwrap(), 1);
+        assert_eq!(waitlist.join("c", 300).await.unwrap(), 2);
+        assert_eq!(waitlist.join("b", 200).await.unwrap(), 2);
+        assert_eq!(waitlist.join("a", 999).await.unwrap(), 1);
+
+        assert_eq!(waitlist.admit(2).await.unwrap(), vec!["a", "b"]);
+        assert_eq!(waitlist.position("c").await.unwrap(), Some(1));
+        assert_eq!(waitlist.position("a").await.unwrap(), None);
+        assert!(waitlist.leave("c").await.unwrap());
+        assert!(waitlist.admit(2).await.unwrap().is_empty());
+    }
+}
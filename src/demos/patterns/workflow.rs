@@ -0,0 +1,391 @@
+use crate::scheduler::recurring::{JobHandler, RecurringJob};
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::RedisConnection;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use redis::{AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const TIMEOUTS_KEY: &str = "workflow:timeouts";
+
+/// KEYS: order hash, history list, timeouts zset
+/// ARGV: order id, expected state ('' for a new order), new state,
+///       now ms, history entry, timeout deadline ms ('' for none)
+///
+/// Compare-and-set on the state field, so two workers advancing the same
+/// order can't both succeed from the same starting state.
+const TRANSITION_SCRIPT: &str = r#"
+local current = redis.call('HGET', KEYS[1], 'state') or ''
+if current ~= ARGV[2] then
+    return redis.error_reply('STALE ' .. current)
+end
+redis.call('HSET', KEYS[1], 'state', ARGV[3], 'updated_at', ARGV[4])
+redis.call('HINCRBY', KEYS[1], 'version', 1)
+redis.call('RPUSH', KEYS[2], ARGV[5])
+if ARGV[6] == '' then
+    redis.call('ZREM', KEYS[3], ARGV[1])
+else
+    redis.call('ZADD', KEYS[3], ARGV[6], ARGV[1])
+end
+return 1
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderState {
+    Created,
+    Paid,
+    Shipped,
+    Delivered,
+    Cancelled,
+}
+
+impl OrderState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderState::Created => "created",
+            OrderState::Paid => "paid",
+            OrderState::Shipped => "shipped",
+            OrderState::Delivered => "delivered",
+            OrderState::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn allowed_next(self) -> &'static [OrderState] {
+        match self {
+            OrderState::Created => &[OrderState::Paid, OrderState::Cancelled],
+            OrderState::Paid => &[OrderState::Shipped, OrderState::Cancelled],
+            OrderState::Shipped => &[OrderState::Delivered],
+            OrderState::Delivered | OrderState::Cancelled => &[],
+        }
+    }
+
+    pub fn can_transition_to(self, next: OrderState) -> bool {
+        self.allowed_next().contains(&next)
+    }
+
+    /// State entered automatically if the order sits in `self` too long.
+    pub fn timeout_target(self) -> Option<OrderState> {
+        match self {
+            OrderState::Created => Some(OrderState::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OrderState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrderState {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "created" => Ok(OrderState::Created),
+            "paid" => Ok(OrderState::Paid),
+            "shipped" => Ok(OrderState::Shipped),
+            "delivered" => Ok(OrderState::Delivered),
+            "cancelled" | "canceled" => Ok(OrderState::Cancelled),
+            other => Err(DemoError::Configuration(format!("Unknown order state: {}", other))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transition {
+    pub from: Option<OrderState>,
+    pub to: OrderState,
+    pub at: DateTime<Utc>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderWorkflow {
+    pub id: String,
+    pub state: OrderState,
+    pub version: u64,
+    pub updated_at: DateTime<Utc>,
+    pub history: Vec<Transition>,
+    pub timeout_at: Option<DateTime<Utc>>,
+}
+
+pub fn order_key(id: &str) -> String {
+    format!("workflow:order:{}", id)
+}
+
+pub fn history_key(id: &str) -> String {
+    format!("workflow:order:{}:history", id)
+}
+
+/// Persists order workflows: current state in a hash, an append-only
+/// history list, and pending timeouts in a ZSET swept by the scheduler.
+pub struct WorkflowStore {
+    conn: RedisConnection,
+    transition: Script,
+    payment_timeout: Duration,
+}
+
+impl WorkflowStore {
+    pub async fn new(client: &RedisClient, payment_timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            transition: Script::new(TRANSITION_SCRIPT),
+            payment_timeout,
+        })
+    }
+
+    pub async fn create(&mut self, id: &str, now: DateTime<Utc>) -> Result<()> {
+        self.apply(id, None, OrderState::Created, now, "order placed").await
+    }
+
+    /// Moves an order to `to`, rejecting transitions the state machine doesn't allow.
+    pub async fn advance(&mut self, id: &str, to: OrderState, now: DateTime<Utc>, reason: &str) -> Result<()> {
+        let current = self
+            .inspect(id)
+            .await?
+            .ok_or_else(|| DemoError::Demo(format!("Order '{}' not found", id)))?;
+        if !current.state.can_transition_to(to) {
+            return Err(DemoError::Demo(format!(
+                "Order '{}' cannot go from {} to {} (allowed: {:?})",
+                id,
+                current.state,
+                to,
+                current.state.allowed_next()
+            )));
+        }
+        self.apply(id, Some(current.state), to, now, reason).await
+    }
+
+    async fn apply(
+        &mut self,
+        id: &str,
+        from: Option<OrderState>,
+        to: OrderState,
+        now: DateTime<Utc>,
+        reason: &str,
+    ) -> Result<()> {
+        let entry = Transition {
+            from,
+            to,
+            at: now,
+            reason: reason.to_string(),
+        };
+        let deadline = to
+            .timeout_target()
+            .map(|_| (now.timestamp_millis() + self.payment_timeout.as_millis() as i64).to_string())
+            .unwrap_or_default();
+        let applied: redis::RedisResult<i64> = self
+            .transition
+            .key(order_key(id))
+            .key(history_key(id))
+            .key(TIMEOUTS_KEY)
+            .arg(id)
+            .arg(from.map(OrderState::as_str).unwrap_or(""))
+            .arg(to.as_str())
+            .arg(now.timestamp_millis())
+            .arg(serde_json::to_string(&entry)?)
+            .arg(deadline)
+            .invoke_async(&mut self.conn)
+            .await;
+        match applied {
+            Ok(_) => Ok(()),
+            Err(e) if e.code() == Some("STALE") => Err(DemoError::Demo(format!(
+                "Order '{}' changed concurrently (expected {})",
+                id,
+                from.map_or("no order", OrderState::as_str)
+            ))),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn inspect(&mut self, id: &str) -> Result<Option<OrderWorkflow>> {
+        let (fields, history, timeout): (Vec<Option<String>>, Vec<String>, Option<i64>) = redis::pipe()
+            .cmd("HMGET")
+            .arg(order_key(id))
+            .arg(&["state", "version", "updated_at"])
+            .lrange(history_key(id), 0, -1)
+            .zscore(TIMEOUTS_KEY, id)
+            .query_async(&mut self.conn)
+            .await?;
+        let [Some(state), version, updated_at] = &fields[..] else {
+            return Ok(None);
+        };
+        let millis = |ms: i64| DateTime::from_timestamp_millis(ms).unwrap_or_default();
+        Ok(Some(OrderWorkflow {
+            id: id.to_string(),
+            state: state.parse()?,
+            version: version.as_deref().and_then(|v| v.parse().ok()).unwrap_or(0),
+            updated_at: millis(updated_at.as_deref().and_then(|v| v.parse().ok()).unwrap_or(0)),
+            history: history
+                .iter()
+                .map(|entry| serde_json::from_str(entry))
+                .collect::<std::result::Result<_, _>>()?,
+            timeout_at: timeout.map(millis),
+        }))
+    }
+
+    /// Applies every timeout transition whose deadline has passed. Orders
+    /// that moved on in the meantime are skipped by the compare-and-set.
+    pub async fn sweep_timeouts(&mut self, now: DateTime<Utc>) -> Result<Vec<String>> {
+        let expired: Vec<String> = self
+            .conn
+            .zrangebyscore(TIMEOUTS_KEY, "-inf", now.timestamp_millis())
+            .await?;
+        let mut timed_out = Vec::new();
+        for id in expired {
+            let Some(order) = self.inspect(&id).await? else {
+                let _: () = self.conn.zrem(TIMEOUTS_KEY, &id).await?;
+                continue;
+            };
+            let Some(target) = order.state.timeout_target() else {
+                let _: () = self.conn.zrem(TIMEOUTS_KEY, &id).await?;
+                continue;
+            };
+            let reason = format!("timed out in {}", order.state);
+            match self.apply(&id, Some(order.state), target, now, &reason).await {
+                Ok(()) => timed_out.push(id),
+                Err(DemoError::Demo(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(timed_out)
+    }
+
+    pub async fn delete(&mut self, id: &str) -> Result<()> {
+        let _: () = redis::pipe()
+            .del(&[order_key(id), history_key(id)])
+            .ignore()
+            .zrem(TIMEOUTS_KEY, id)
+            .ignore()
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Scheduler handler (`workflow_timeouts`) that sweeps expired orders, e.g.
+/// `scheduler add --name workflow-timeouts --cron "* * * * *" --handler workflow_timeouts`.
+pub struct WorkflowTimeoutHandler {
+    client: RedisClient,
+    payment_timeout: Duration,
+}
+
+impl WorkflowTimeoutHandler {
+    pub fn new(client: RedisClient, payment_timeout: Duration) -> Self {
+        Self { client, payment_timeout }
+    }
+}
+
+#[async_trait]
+impl JobHandler for WorkflowTimeoutHandler {
+    async fn run(&self, _job: &RecurringJob, _scheduled_for: DateTime<Utc>) -> Result<()> {
+        let mut store = WorkflowStore::new(&self.client, self.payment_timeout).await?;
+        for id in store.sweep_timeouts(Utc::now()).await? {
+            println!("   ⏰ order {} cancelled after payment timeout", id);
+        }
+        Ok(())
+    }
+}
+
+pub fn render_workflow(order: &OrderWorkflow) -> String {
+    let mut out = format!(
+        "Order {}: {} (version {}, updated {})\n",
+        order.id,
+        order.state,
+        order.version,
+        order.updated_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    if let Some(timeout) = order.timeout_at {
+        out.push_str(&format!("  times out at {}\n", timeout.format("%Y-%m-%d %H:%M:%S")));
+    }
+    let next: Vec<&str> = order.state.allowed_next().iter().map(|s| s.as_str()).collect();
+    out.push_str(&format!("  allowed next: {}\n  history:\n", if next.is_empty() { "none".to_string() } else { next.join(", ") }));
+    for transition in &order.history {
+        out.push_str(&format!(
+            "    {} {} -> {} ({})\n",
+            transition.at.format("%H:%M:%S"),
+            transition.from.map_or("-", OrderState::as_str),
+            transition.to,
+            transition.reason
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 12, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_allowed_transitions() {
+        assert!(OrderState::Created.can_transition_to(OrderState::Paid));
+        assert!(OrderState::Paid.can_transition_to(OrderState::Shipped));
+        assert!(!OrderState::Created.can_transition_to(OrderState::Shipped));
+        assert!(!OrderState::Delivered.can_transition_to(OrderState::Cancelled));
+        assert_eq!(OrderState::Created.timeout_target(), Some(OrderState::Cancelled));
+        assert_eq!(OrderState::Paid.timeout_target(), None);
+    }
+
+    #[test]
+    fn test_state_from_str() {
+        assert_eq!("PAID".parse::<OrderState>().unwrap(), OrderState::Paid);
+        assert_eq!("canceled".parse::<OrderState>().unwrap(), OrderState::Cancelled);
+        assert!("lost".parse::<OrderState>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_advance_and_history() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut store = WorkflowStore::new(&client, Duration::from_secs(60)).await.unwrap();
+        store.delete("test_order_1").await.unwrap();
+
+        store.create("test_order_1", at(0)).await.unwrap();
+        assert!(store.create("test_order_1", at(0)).await.is_err());
+        assert!(store.advance("test_order_1", OrderState::Shipped, at(1), "skip").await.is_err());
+        store.advance("test_order_1", OrderState::Paid, at(1), "card").await.unwrap();
+        store.advance("test_order_1", OrderState::Shipped, at(2), "dhl").await.unwrap();
+
+        let order = store.inspect("test_order_1").await.unwrap().unwrap();
+        assert_eq!(order.state, OrderState::Shipped);
+        assert_eq!(order.version, 3);
+        assert_eq!(order.history.len(), 3);
+        assert_eq!(order.history[1].from, Some(OrderState::Created));
+        assert!(order.timeout_at.is_none());
+
+        store.delete("test_order_1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sweep_cancels_unpaid_orders_only() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut store = WorkflowStore::new(&client, Duration::from_secs(5 * 60)).await.unwrap();
+        for id in ["test_unpaid", "test_paid"] {
+            store.delete(id).await.unwrap();
+            store.create(id, at(0)).await.unwrap();
+        }
+        store.advance("test_paid", OrderState::Paid, at(1), "card").await.unwrap();
+
+        assert!(store.sweep_timeouts(at(4)).await.unwrap().is_empty());
+        assert_eq!(store.sweep_timeouts(at(6)).await.unwrap(), vec!["test_unpaid".to_string()]);
+
+        let unpaid = store.inspect("test_unpaid").await.unwrap().unwrap();
+        assert_eq!(unpaid.state, OrderState::Cancelled);
+        assert_eq!(unpaid.history.last().unwrap().reason, "timed out in created");
+        assert_eq!(store.inspect("test_paid").await.unwrap().unwrap().state, OrderState::Paid);
+
+        for id in ["test_unpaid", "test_paid"] {
+            store.delete(id).await.unwrap();
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{RedisClient, Result};
+use crate::utils::Sections;
+use chrono::{DateTime, Local};
+use futures::StreamExt;
+use redis::AsyncCommands;
+use std::time::Duration;
+use tracing::info;
+
+/// One message as a subscriber sees it. Channels are namespaced under the
+/// client's key prefix like keys are, and shown without it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Received {
+    pub at: DateTime<Local>,
+    pub channel: String,
+    /// The PSUBSCRIBE pattern that matched, for pattern subscriptions.
+    pub pattern: Option<String>,
+    pub payload: String,
+}
+
+impl Received {
+    pub fn new(channel: &str, pattern: Option<&str>, payload: String, prefix: Option<&str>, at: DateTime<Local>) -> Self {
+        let strip = |name: &str| prefix.and_then(|prefix| name.strip_prefix(prefix)).unwrap_or(name).to_string();
+        Self { at, channel: strip(channel), pattern: pattern.map(strip), payload }
+    }
+
+    pub fn render(&self) -> String {
+        let via = self.pattern.as_ref().map(|pattern| format!(" (via {})", pattern)).unwrap_or_default();
+        format!("{}  {}{}: {}", self.at.format("%H:%M:%S%.3f"), self.channel, via, self.payload)
+    }
+}
+
+pub struct PubSubDemo {
+    client: RedisClient,
+}
+
+impl PubSubDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    fn channel(&self, name: &str) -> String {
+        format!("{}{}", self.client.key_prefix().unwrap_or(""), name)
+    }
+
+    /// PUBLISH is fire-and-forget: the reply is how many subscribers got the
+    /// message, and nobody listening means it is gone.
+    pub async fn publish(&self, channel: &str, message: &str) -> Result<usize> {
+        let mut conn = self.client.get_async_connection().await?;
+        let receivers: usize = conn.publish(self.channel(channel), message).await?;
+        match receivers {
+            0 => println!("📣 {}: nobody is subscribed, so the message is dropped", channel),
+            n => println!("📣 {}: delivered to {} subscriber(s)", channel, n),
+        }
+        Ok(receivers)
+    }
+
+    /// Prints messages on `channels` and on channels matching `patterns`
+    /// until Ctrl-C or `limit`, then unsubscribes. Returns how many arrived.
+    pub async fn subscribe(&self, channels: &[String], patterns: &[String], limit: Option<usize>) -> Result<usize> {
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        for channel in channels {
+            pubsub.subscribe(self.channel(channel)).await?;
+        }
+        for pattern in patterns {
+            pubsub.psubscribe(self.channel(pattern)).await?;
+        }
+        let mut listening: Vec<&str> = channels.iter().map(String::as_str).collect();
+        listening.extend(patterns.iter().map(String::as_str));
+        println!("Subscribed to {} (Ctrl-C to stop)\n", listening.join(", "));
+
+        let prefix = self.client.key_prefix();
+        let mut received = 0;
+        {
+            let mut messages = pubsub.on_message();
+            while limit.is_none_or(|limit| received < limit) {
+                let message = tokio::select! {
+                    message = messages.next() => message,
+                    _ = tokio::signal::ctrl_c() => None,
+                };
+                let Some(message) = message else {
+                    break;
+                };
+                let pattern: Option<String> = message.from_pattern().then(|| message.get_pattern().ok()).flatten();
+                let payload: String = message.get_payload()?;
+                println!("{}", Received::new(message.get_channel_name(), pattern.as_deref(), payload, prefix, Local::now()).render());
+                received += 1;
+            }
+        }
+
+        for channel in channels {
+            pubsub.unsubscribe(self.channel(channel)).await?;
+        }
+        for pattern in patterns {
+            pubsub.punsubscribe(self.channel(pattern)).await?;
+        }
+        Ok(received)
+    }
+
+    /// Both sides in one process: a subscriber on `news` and on the pattern
+    /// `news.*` while this task publishes, showing that a message on a
+    /// channel matching both arrives twice and one nobody listens to is lost.
+    pub async fn demonstrate(&self) -> Result<()> {
+        println!("\n=== Pub/Sub Demo ===\n");
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(self.channel("news")).await?;
+        pubsub.psubscribe(self.channel("news.*")).await?;
+        let mut sections = Sections::new();
+        sections.next("Subscriber listening on 'news' and the pattern 'news.*'");
+
+        let prefix = self.client.key_prefix().map(str::to_string);
+        let listener = tokio::spawn(async move {
+            let mut messages = pubsub.into_on_message();
+            let mut received = Vec::new();
+            while let Ok(Some(message)) = tokio::time::timeout(Duration::from_millis(500), messages.next()).await {
+                let pattern: Option<String> = message.from_pattern().then(|| message.get_pattern().ok()).flatten();
+                let payload: String = message.get_payload().unwrap_or_default();
+                received.push(Received::new(message.get_channel_name(), pattern.as_deref(), payload, prefix.as_deref(), Local::now()));
+            }
+            received
+        });
+
+        sections.next("Publishing:");
+        for (channel, message) in [("news", "hello subscribers"), ("news.sport", "3-1 at half time"), ("weather", "rain later")] {
+            self.publish(channel, message).await?;
+        }
+
+        sections.next("What the subscriber received:");
+        let received = listener.await.map_err(|e| crate::DemoError::Demo(format!("Subscriber task failed: {}", e)))?;
+        for message in &received {
+            println!("   {}", message.render());
+        }
+        println!("\n   'weather' had no subscribers, so it never arrived anywhere.");
+        println!("   For a chat between two terminals:");
+        println!("   redis-demo pubsub subscribe news --pattern 'news.*'");
+        println!("   redis-demo pubsub publish news 'hi there'");
+
+        info!("Pub/Sub demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_received_strips_prefix_and_renders() {
+        let at = Local.with_ymd_and_hms(2025, 3, 1, 12, 0, 5).unwrap();
+        let message = Received::new("demo:news.sport", Some("demo:news.*"), "3-1".to_string(), Some("demo:"), at);
+        assert_eq!(message.channel, "news.sport");
+        assert_eq!(message.render(), "12:00:05.000  news.sport (via news.*): 3-1");
+        let message = Received::new("news", None, "hi".to_string(), None, at);
+        assert_eq!(message.render(), "12:00:05.000  news: hi");
+    }
+}
@@ -0,0 +1,175 @@
+use crate::utils::{RedisConnection, ScopedKeys, ScriptManager, Sections};
+use crate::{RedisClient, Result};
+use redis::{AsyncCommands, Script};
+use std::time::Duration;
+use tracing::info;
+
+const CAS: &str = "cas";
+const RATE_LIMIT: &str = "rate_limit";
+
+/// SETs the key only if it still holds the value the caller last read.
+const CAS_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('SET', KEYS[1], ARGV[2])
+    return 1
+end
+return 0
+"#;
+
+/// Fixed-window counter: the first hit of a window sets its expiry, and the
+/// count is checked in the same step so no request slips in between.
+const RATE_LIMIT_SCRIPT: &str = r#"
+local count = redis.call('INCR', KEYS[1])
+if count == 1 then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+local allowed = 0
+if count <= tonumber(ARGV[1]) then
+    allowed = 1
+end
+return {allowed, count, redis.call('PTTL', KEYS[1])}
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateDecision {
+    pub allowed: bool,
+    pub count: u64,
+    pub resets_in_ms: i64,
+}
+
+pub struct ScriptingDemo {
+    client: RedisClient,
+    scripts: ScriptManager,
+}
+
+impl ScriptingDemo {
+    pub fn new(client: RedisClient) -> Self {
+        let mut scripts = ScriptManager::new();
+        scripts.register(CAS, CAS_SCRIPT);
+        scripts.register(RATE_LIMIT, RATE_LIMIT_SCRIPT);
+        Self { client, scripts }
+    }
+
+    /// Replaces `key`'s value with `new` if it is still `expected`.
+    pub async fn compare_and_set(&self, conn: &mut RedisConnection, key: &str, expected: &str, new: &str) -> Result<bool> {
+        let swapped: i64 = self.scripts.call(CAS)?.key(key).arg(expected).arg(new).invoke(conn).await?;
+        Ok(swapped == 1)
+    }
+
+    /// Counts a request against `limit` per `window`.
+    pub async fn rate_limit(&self, conn: &mut RedisConnection, key: &str, limit: u64, window: Duration) -> Result<RateDecision> {
+        let (allowed, count, resets_in_ms): (i64, u64, i64) =
+            self.scripts.call(RATE_LIMIT)?.key(key).arg(limit).arg(window.as_millis() as u64).invoke(conn).await?;
+        Ok(RateDecision { allowed: allowed == 1, count, resets_in_ms })
+    }
+
+    pub async fn demonstrate_basics(&self) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let scope = ScopedKeys::new(conn.clone());
+        scope.track_all(["scripting:counter", "scripting:greeting"]);
+
+        println!("\n=== Lua Scripting Demo: EVAL and EVALSHA ===\n");
+
+        let mut sections = Sections::new();
+        sections.next("EVAL (the script body goes with every call):");
+        let incr = "return redis.call('INCRBY', KEYS[1], ARGV[1])";
+        let counter: i64 = redis::cmd("EVAL").arg(incr).arg(1).arg("scripting:counter").arg(5).query_async(&mut conn).await?;
+        println!("   EVAL \"{}\" 1 scripting:counter 5 => {}", incr, counter);
+
+        sections.next("SCRIPT LOAD + EVALSHA (only the SHA1 goes over the wire):");
+        let sha: String = redis::cmd("SCRIPT").arg("LOAD").arg(incr).query_async(&mut conn).await?;
+        println!("   SCRIPT LOAD => {}", sha);
+        let counter: i64 = redis::cmd("EVALSHA").arg(&sha).arg(1).arg("scripting:counter").arg(10).query_async(&mut conn).await?;
+        println!("   EVALSHA {} 1 scripting:counter 10 => {}", &sha[..12], counter);
+        let exists: Vec<bool> = redis::cmd("SCRIPT").arg("EXISTS").arg(&sha).arg("0000000000000000000000000000000000000000").query_async(&mut conn).await?;
+        println!("   SCRIPT EXISTS {} 0000... => {:?}", &sha[..12], exists);
+
+        sections.next("redis::Script (EVALSHA first, EVAL's body only on NOSCRIPT):");
+        let greet = Script::new("redis.call('SET', KEYS[1], 'Hello, ' .. ARGV[1]); return redis.call('GET', KEYS[1])");
+        let greeting: String = greet.key("scripting:greeting").arg("Lua").invoke_async(&mut conn).await?;
+        println!("   Script {} => {:?}", &greet.get_hash()[..12], greeting);
+
+        scope.finish().await?;
+        Ok(())
+    }
+
+    pub async fn demonstrate_script_manager(&self) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let scope = ScopedKeys::new(conn.clone());
+        scope.track_all(["scripting:config", "scripting:ratelimit:api"]);
+
+        println!("\n=== Lua Scripting Demo: ScriptManager ===\n");
+
+        let mut sections = Sections::new();
+        sections.next("Loading the registered scripts:");
+        self.scripts.load_all(&mut conn).await?;
+        for name in [CAS, RATE_LIMIT] {
+            println!("   {} => {}", name, self.scripts.sha(name).unwrap_or_default());
+        }
+
+        sections.next("Atomic compare-and-set:");
+        let _: () = conn.set("scripting:config", "v1").await?;
+        let read: String = conn.get("scripting:config").await?;
+        println!("   Two writers both read {:?}", read);
+        let first = self.compare_and_set(&mut conn, "scripting:config", &read, "v2-from-a").await?;
+        println!("   Writer A: v1 -> v2-from-a => {}", if first { "✅ swapped" } else { "❌ stale" });
+        let second = self.compare_and_set(&mut conn, "scripting:config", &read, "v2-from-b").await?;
+        println!("   Writer B: v1 -> v2-from-b => {}", if second { "✅ swapped" } else { "❌ stale, re-read and retry" });
+        let value: String = conn.get("scripting:config").await?;
+        println!("   scripting:config = {:?}", value);
+
+        sections.next("Rate-limit counter (5 requests per 10s window):");
+        for request in 1..=7 {
+            let decision = self.rate_limit(&mut conn, "scripting:ratelimit:api", 5, Duration::from_secs(10)).await?;
+            println!(
+                "   request {}: {} (count {}, window resets in {}ms)",
+                request,
+                if decision.allowed { "✅ allowed" } else { "⛔ limited" },
+                decision.count,
+                decision.resets_in_ms
+            );
+        }
+
+        sections.next("Reloading after NOSCRIPT:");
+        let mut fresh = ScriptManager::new();
+        let sha = fresh.register("fresh", &format!("-- {}\nreturn 'loaded on demand'", uuid::Uuid::new_v4())).to_string();
+        println!("   A script the server has never seen: {}", &sha[..12]);
+        let reply: String = fresh.call("fresh")?.invoke(&mut conn).await?;
+        println!("   EVALSHA => NOSCRIPT, SCRIPT LOAD, EVALSHA => {:?} ({} reload)", reply, fresh.reloads());
+        let _: String = fresh.call("fresh")?.invoke(&mut conn).await?;
+        println!("   Called again => still {} reload, the server has it cached now", fresh.reloads());
+
+        scope.finish().await?;
+        info!("Scripting demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_compare_and_set_and_rate_limit() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let demo = ScriptingDemo::new(client);
+        let _: () = conn.del(&["scripting-test:cas", "scripting-test:rate"]).await.unwrap();
+
+        let _: () = conn.set("scripting-test:cas", "a").await.unwrap();
+        assert!(demo.compare_and_set(&mut conn, "scripting-test:cas", "a", "b").await.unwrap());
+        assert!(!demo.compare_and_set(&mut conn, "scripting-test:cas", "a", "c").await.unwrap());
+        let value: String = conn.get("scripting-test:cas").await.unwrap();
+        assert_eq!(value, "b");
+
+        let window = Duration::from_secs(10);
+        let mut decisions = Vec::new();
+        for _ in 0..3 {
+            decisions.push(demo.rate_limit(&mut conn, "scripting-test:rate", 2, window).await.unwrap());
+        }
+        assert_eq!(decisions.iter().map(|d| (d.allowed, d.count)).collect::<Vec<_>>(), [(true, 1), (true, 2), (false, 3)]);
+        assert!(decisions[2].resets_in_ms > 0);
+
+        let _: () = conn.del(&["scripting-test:cas", "scripting-test:rate"]).await.unwrap();
+    }
+}
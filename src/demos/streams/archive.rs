@@ -0,0 +1,529 @@
+use super::{entry_fields, parse_stream_id};
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::RedisConnection;
+use redis::streams::{StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::{info, warn};
+
+/// Key holding the last archived id for standalone (non-group) archiving.
+pub fn position_key(stream: &str) -> String {
+    format!("streams:archive:{}:position", stream)
+}
+
+/// One NDJSON line of an archive file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedEntry {
+    pub stream: String,
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub fields: BTreeMap<String, String>,
+}
+
+/// One line of a columnar ("Parquet-lite") archive file: a flushed batch
+/// laid out a column at a time, like a Parquet row group. There is one
+/// field column per field name in the batch, `None` where an entry lacks
+/// that field. Scanning one field only touches its column, and repeated
+/// field names are written once per batch instead of once per entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnarBatch {
+    pub stream: String,
+    pub ids: Vec<String>,
+    pub timestamp_ms: Vec<u64>,
+    pub fields: BTreeMap<String, Vec<Option<String>>>,
+}
+
+impl ColumnarBatch {
+    pub fn from_entries(stream: &str, entries: &[ArchivedEntry]) -> Self {
+        let mut fields: BTreeMap<String, Vec<Option<String>>> = BTreeMap::new();
+        for name in entries.iter().flat_map(|entry| entry.fields.keys()) {
+            fields.entry(name.clone()).or_default();
+        }
+        for (name, column) in fields.iter_mut() {
+            column.extend(entries.iter().map(|entry| entry.fields.get(name).cloned()));
+        }
+        Self {
+            stream: stream.to_string(),
+            ids: entries.iter().map(|entry| entry.id.clone()).collect(),
+            timestamp_ms: entries.iter().map(|entry| entry.timestamp_ms).collect(),
+            fields,
+        }
+    }
+
+    /// Back to one entry per row, in the order they were written.
+    pub fn into_entries(self) -> Vec<ArchivedEntry> {
+        let Self { stream, ids, timestamp_ms, fields } = self;
+        ids.into_iter()
+            .zip(timestamp_ms)
+            .enumerate()
+            .map(|(row, (id, timestamp_ms))| ArchivedEntry {
+                stream: stream.clone(),
+                id,
+                timestamp_ms,
+                fields: fields
+                    .iter()
+                    .filter_map(|(name, column)| column.get(row).cloned().flatten().map(|value| (name.clone(), value)))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// How an archive file lays entries out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveFormat {
+    /// One JSON object per entry.
+    #[default]
+    Ndjson,
+    /// One [`ColumnarBatch`] per flushed batch.
+    Columnar,
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "ndjson" => Ok(ArchiveFormat::Ndjson),
+            "columnar" | "parquet-lite" => Ok(ArchiveFormat::Columnar),
+            other => Err(DemoError::Configuration(format!("Unknown archive format: {} (use ndjson or columnar)", other))),
+        }
+    }
+}
+
+/// Every entry in an archive file of either format.
+pub fn read_archive(path: &Path, format: ArchiveFormat) -> Result<Vec<ArchivedEntry>> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut entries = Vec::new();
+    for line in contents.lines().filter(|line| !line.is_empty()) {
+        match format {
+            ArchiveFormat::Ndjson => entries.push(serde_json::from_str(line)?),
+            ArchiveFormat::Columnar => entries.extend(serde_json::from_str::<ColumnarBatch>(line)?.into_entries()),
+        }
+    }
+    Ok(entries)
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveOptions {
+    pub stream: String,
+    pub out: PathBuf,
+    pub follow: bool,
+    /// Read through this consumer group (created on demand) and XACK after
+    /// each flushed batch, instead of tracking a position key.
+    pub group: Option<String>,
+    pub consumer: String,
+    pub batch_size: usize,
+    pub rotate_bytes: u64,
+    pub block_ms: usize,
+    pub format: ArchiveFormat,
+}
+
+impl ArchiveOptions {
+    pub fn new(stream: &str, out: impl Into<PathBuf>) -> Self {
+        Self {
+            stream: stream.to_string(),
+            out: out.into(),
+            follow: false,
+            group: None,
+            consumer: "archiver".to_string(),
+            batch_size: 500,
+            rotate_bytes: 64 * 1024 * 1024,
+            block_ms: 1000,
+            format: ArchiveFormat::Ndjson,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveSummary {
+    pub entries: usize,
+    pub files: Vec<PathBuf>,
+    pub last_id: Option<String>,
+}
+
+/// `events.ndjson` stays the first file; rotations become `events.1.ndjson`,
+/// `events.2.ndjson`, ...
+pub fn rotated_path(base: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return base.to_path_buf();
+    }
+    let stem = base.file_stem().and_then(|s| s.to_str()).unwrap_or("archive");
+    let name = match base.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.{}.{}", stem, index, ext),
+        None => format!("{}.{}", stem, index),
+    };
+    base.with_file_name(name)
+}
+
+/// Append-only writer of JSON lines that starts a new file once the
+/// current one exceeds `max_bytes`. On open it resumes in the newest
+/// existing file, which should have been written in the same format.
+pub struct RotatingWriter {
+    base: PathBuf,
+    max_bytes: u64,
+    format: ArchiveFormat,
+    index: usize,
+    written: u64,
+    file: BufWriter<File>,
+    opened: Vec<PathBuf>,
+}
+
+impl RotatingWriter {
+    pub fn open(base: &Path, max_bytes: u64) -> Result<Self> {
+        let mut index = 0;
+        while rotated_path(base, index + 1).exists() {
+            index += 1;
+        }
+        let path = rotated_path(base, index);
+        let (file, written) = Self::open_append(&path)?;
+        Ok(Self {
+            base: base.to_path_buf(),
+            max_bytes,
+            format: ArchiveFormat::Ndjson,
+            index,
+            written,
+            file,
+            opened: vec![path],
+        })
+    }
+
+    pub fn with_format(mut self, format: ArchiveFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    fn open_append(path: &Path) -> Result<(BufWriter<File>, u64)> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok((BufWriter::new(file), written))
+    }
+
+    pub fn write_entry(&mut self, entry: &ArchivedEntry) -> Result<()> {
+        self.write_line(serde_json::to_vec(entry)?)
+    }
+
+    /// Writes a batch in the writer's format: a line per entry, or one
+    /// columnar line for the whole batch. Columnar batches never straddle
+    /// two files.
+    pub fn write_batch(&mut self, stream: &str, entries: &[ArchivedEntry]) -> Result<()> {
+        match self.format {
+            ArchiveFormat::Ndjson => entries.iter().try_for_each(|entry| self.write_entry(entry)),
+            ArchiveFormat::Columnar if entries.is_empty() => Ok(()),
+            ArchiveFormat::Columnar => self.write_line(serde_json::to_vec(&ColumnarBatch::from_entries(stream, entries))?),
+        }
+    }
+
+    fn write_line(&mut self, mut line: Vec<u8>) -> Result<()> {
+        if self.written >= self.max_bytes && self.written > 0 {
+            self.rotate()?;
+        }
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.file.flush()?;
+        self.index += 1;
+        let path = rotated_path(&self.base, self.index);
+        let (file, written) = Self::open_append(&path)?;
+        self.file = file;
+        self.written = written;
+        self.opened.push(path);
+        Ok(())
+    }
+
+    /// Flushes buffered lines and fsyncs, so a position saved afterwards
+    /// never points past data that could still be lost.
+    pub fn flush(&mut self) -> Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        Ok(())
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.opened
+    }
+}
+
+/// Copies stream entries to NDJSON or columnar files, resuming where the previous run
+/// stopped. Entries are written and flushed before the position is saved
+/// (or the group acknowledged), so a crash can repeat a batch but never lose one.
+pub struct StreamArchiver {
+    client: RedisClient,
+    options: ArchiveOptions,
+}
+
+impl StreamArchiver {
+    pub fn new(client: RedisClient, options: ArchiveOptions) -> Self {
+        Self { client, options }
+    }
+
+    pub async fn run(&self) -> Result<ArchiveSummary> {
+        let mut conn = self.client.get_async_connection().await?;
+        let mut writer = RotatingWriter::open(&self.options.out, self.options.rotate_bytes)?.with_format(self.options.format);
+        let mut summary = ArchiveSummary::default();
+
+        let mut cursor = match &self.options.group {
+            Some(group) => {
+                self.ensure_group(&mut conn, group).await?;
+                // Re-archive anything delivered to us but never acknowledged.
+                "0".to_string()
+            }
+            None => {
+                let saved: Option<String> = conn.get(position_key(&self.options.stream)).await?;
+                saved.unwrap_or_else(|| "0".to_string())
+            }
+        };
+        info!("Archiving stream '{}' from {}", self.options.stream, cursor);
+
+        loop {
+            let reply = tokio::select! {
+                reply = self.read_batch(&mut conn, &cursor) => reply?,
+                _ = tokio::signal::ctrl_c(), if self.options.follow => {
+                    info!("Interrupted, stopping archiver");
+                    break;
+                }
+            };
+            let entries: Vec<_> = reply.keys.into_iter().flat_map(|key| key.ids).collect();
+
+            if entries.is_empty() {
+                if self.options.group.is_some() && cursor != ">" {
+                    cursor = ">".to_string();
+                    continue;
+                }
+                if self.options.follow {
+                    continue;
+                }
+                break;
+            }
+
+            let ids: Vec<String> = entries.iter().map(|entry| entry.id.clone()).collect();
+            let archived: Vec<ArchivedEntry> = entries
+                .iter()
+                .map(|entry| ArchivedEntry {
+                    stream: self.options.stream.clone(),
+                    id: entry.id.clone(),
+                    timestamp_ms: parse_stream_id(&entry.id).map(|(ms, _)| ms).unwrap_or(0),
+                    fields: entry_fields(entry),
+                })
+                .collect();
+            writer.write_batch(&self.options.stream, &archived)?;
+            writer.flush()?;
+
+            let last_id = ids.last().cloned().unwrap_or_default();
+            match &self.options.group {
+                Some(group) => {
+                    let _: () = conn.xack(&self.options.stream, group, &ids).await?;
+                    if cursor != ">" {
+                        cursor = last_id.clone();
+                    }
+                }
+                None => {
+                    let _: () = conn.set(position_key(&self.options.stream), &last_id).await?;
+                    cursor = last_id.clone();
+                }
+            }
+            summary.entries += entries.len();
+            summary.last_id = Some(last_id);
+        }
+
+        writer.flush()?;
+        summary.files = writer.files().to_vec();
+        Ok(summary)
+    }
+
+    async fn read_batch(&self, conn: &mut RedisConnection, cursor: &str) -> Result<StreamReadReply> {
+        let mut options = StreamReadOptions::default().count(self.options.batch_size);
+        // Draining pending entries must not block, it returns immediately when done.
+        if self.options.follow && (self.options.group.is_none() || cursor == ">") {
+            options = options.block(self.options.block_ms);
+        }
+        if let Some(group) = &self.options.group {
+            options = options.group(group, &self.options.consumer);
+        }
+        let reply: Option<StreamReadReply> = conn
+            .xread_options(&[&self.options.stream], &[cursor], &options)
+            .await?;
+        Ok(reply.unwrap_or_default())
+    }
+
+    async fn ensure_group(&self, conn: &mut RedisConnection, group: &str) -> Result<()> {
+        let created: redis::RedisResult<()> = conn
+            .xgroup_create_mkstream(&self.options.stream, group, "0")
+            .await;
+        match created {
+            Ok(()) => Ok(()),
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            Err(e) => Err(DemoError::Redis(e)),
+        }
+    }
+
+    /// Forgets the saved position so the next standalone run starts over.
+    pub async fn reset_position(&self) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let _: () = conn.del(position_key(&self.options.stream)).await?;
+        Ok(())
+    }
+}
+
+pub fn print_summary(summary: &ArchiveSummary) {
+    println!("✅ Archived {} entries", summary.entries);
+    if let Some(last_id) = &summary.last_id {
+        println!("   Last id: {}", last_id);
+    }
+    for file in &summary.files {
+        println!("   File: {}", file.display());
+    }
+    if summary.entries == 0 {
+        warn!("No new entries to archive");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_base(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("archive-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    fn entry(id: &str) -> ArchivedEntry {
+        ArchivedEntry {
+            stream: "events".to_string(),
+            id: id.to_string(),
+            timestamp_ms: parse_stream_id(id).unwrap().0,
+            fields: BTreeMap::from([("type".to_string(), "click".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_rotated_path() {
+        let base = Path::new("/tmp/out/events.ndjson");
+        assert_eq!(rotated_path(base, 0), PathBuf::from("/tmp/out/events.ndjson"));
+        assert_eq!(rotated_path(base, 3), PathBuf::from("/tmp/out/events.3.ndjson"));
+        assert_eq!(rotated_path(Path::new("dump"), 1), PathBuf::from("dump.1"));
+    }
+
+    #[test]
+    fn test_columnar_batch_round_trip() {
+        let mut sparse = entry("2-0");
+        sparse.fields = BTreeMap::from([("user".to_string(), "7".to_string())]);
+        let entries = vec![entry("1-0"), sparse, entry("3-0")];
+        let batch = ColumnarBatch::from_entries("events", &entries);
+        assert_eq!(batch.ids, ["1-0", "2-0", "3-0"]);
+        assert_eq!(batch.fields["type"], [Some("click".to_string()), None, Some("click".to_string())]);
+        assert_eq!(batch.fields["user"], [None, Some("7".to_string()), None]);
+        assert_eq!(batch.into_entries(), entries);
+
+        assert_eq!("parquet-lite".parse::<ArchiveFormat>().unwrap(), ArchiveFormat::Columnar);
+        assert!("parquet".parse::<ArchiveFormat>().is_err());
+    }
+
+    #[test]
+    fn test_columnar_writer_writes_a_line_per_batch() {
+        let base = temp_base("events.cols");
+        let mut writer = RotatingWriter::open(&base, 1 << 20).unwrap().with_format(ArchiveFormat::Columnar);
+        writer.write_batch("events", &[entry("1-0"), entry("2-0")]).unwrap();
+        writer.write_batch("events", &[]).unwrap();
+        writer.write_batch("events", &[entry("3-0")]).unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&base).unwrap().lines().count(), 2);
+        let ids: Vec<String> = read_archive(&base, ArchiveFormat::Columnar).unwrap().into_iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, ["1-0", "2-0", "3-0"]);
+
+        std::fs::remove_dir_all(base.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_writer_rotates_and_resumes_in_newest_file() {
+        let base = temp_base("events.ndjson");
+        let mut writer = RotatingWriter::open(&base, 100).unwrap();
+        for i in 1..=5 {
+            writer.write_entry(&entry(&format!("{}-0", i))).unwrap();
+        }
+        writer.flush().unwrap();
+        let files = writer.files().to_vec();
+        assert!(files.len() > 1);
+        drop(writer);
+
+        let mut reopened = RotatingWriter::open(&base, 100).unwrap();
+        assert_eq!(reopened.files(), &files[files.len() - 1..]);
+        reopened.write_entry(&entry("6-0")).unwrap();
+        reopened.flush().unwrap();
+
+        let lines: Vec<ArchivedEntry> = (0..)
+            .map(|i| rotated_path(&base, i))
+            .take_while(|path| path.exists())
+            .flat_map(|path| {
+                std::fs::read_to_string(path)
+                    .unwrap()
+                    .lines()
+                    .map(|line| serde_json::from_str(line).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[5], entry("6-0"));
+
+        std::fs::remove_dir_all(base.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_archive_resumes_from_saved_position() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let stream = "archive_test:events";
+        let _: () = conn.del(&[stream.to_string(), position_key(stream)]).await.unwrap();
+        for i in 0..3 {
+            let _: String = conn.xadd(stream, "*", &[("n", i)]).await.unwrap();
+        }
+
+        let base = temp_base("events.ndjson");
+        let archiver = StreamArchiver::new(client.clone(), ArchiveOptions::new(stream, &base));
+        assert_eq!(archiver.run().await.unwrap().entries, 3);
+
+        let _: String = conn.xadd(stream, "*", &[("n", 3)]).await.unwrap();
+        let second = archiver.run().await.unwrap();
+        assert_eq!(second.entries, 1);
+
+        let lines = std::fs::read_to_string(&base).unwrap();
+        assert_eq!(lines.lines().count(), 4);
+
+        let _: () = conn.del(&[stream.to_string(), position_key(stream)]).await.unwrap();
+        std::fs::remove_dir_all(base.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_archive_through_consumer_group_acks() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let stream = "archive_test:group";
+        let _: () = conn.del(stream).await.unwrap();
+        for i in 0..5 {
+            let _: String = conn.xadd(stream, "*", &[("n", i)]).await.unwrap();
+        }
+
+        let base = temp_base("group.ndjson");
+        let mut options = ArchiveOptions::new(stream, &base);
+        options.group = Some("archivers".to_string());
+        let summary = StreamArchiver::new(client.clone(), options).run().await.unwrap();
+        assert_eq!(summary.entries, 5);
+
+        let pending: redis::streams::StreamPendingReply = conn.xpending(stream, "archivers").await.unwrap();
+        assert_eq!(pending.count(), 0);
+
+        let _: () = conn.del(stream).await.unwrap();
+        std::fs::remove_dir_all(base.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,396 @@
+use super::parse_stream_id;
+use crate::integrations::metric_registry::{
+    STREAM_CONSUMER_PENDING, STREAM_GROUP_LAG, STREAM_GROUP_OLDEST_PENDING, STREAM_GROUP_PENDING, STREAM_LENGTH,
+};
+use crate::integrations::MetricSet;
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::RedisConnection;
+use chrono::Utc;
+use redis::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LagFormat {
+    Table,
+    Prometheus,
+}
+
+impl FromStr for LagFormat {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Ok(LagFormat::Table),
+            "prometheus" | "prom" => Ok(LagFormat::Prometheus),
+            other => Err(DemoError::Configuration(format!("Unknown lag format: {}", other))),
+        }
+    }
+}
+
+/// Alert thresholds, loaded from a JSON file such as
+/// `{"max_pending": 1000, "max_oldest_pending_ms": 60000, "max_lag": 5000}`.
+/// Omitted fields disable that check.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LagThresholds {
+    pub max_pending: Option<u64>,
+    pub max_oldest_pending_ms: Option<u64>,
+    pub max_lag: Option<u64>,
+}
+
+impl LagThresholds {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsumerLag {
+    pub name: String,
+    pub pending: u64,
+    pub idle_ms: u64,
+    /// Age of the consumer's oldest unacknowledged entry, from its id timestamp.
+    pub oldest_pending_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupLag {
+    pub group: String,
+    pub last_delivered_id: String,
+    pub pending: u64,
+    /// Entries not yet delivered to the group. Reported by Redis 7+; `None`
+    /// on older servers or when Redis can't compute it after deletions.
+    pub lag: Option<u64>,
+    pub oldest_pending_ms: Option<u64>,
+    pub consumers: Vec<ConsumerLag>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LagReport {
+    pub stream: String,
+    pub length: u64,
+    pub groups: Vec<GroupLag>,
+}
+
+/// Turns a flat `[field, value, field, value, ...]` XINFO reply into a map.
+fn info_map(value: &Value) -> HashMap<String, Value> {
+    let Value::Bulk(items) = value else {
+        return HashMap::new();
+    };
+    items
+        .chunks(2)
+        .filter_map(|pair| match pair {
+            [field, value] => redis::from_redis_value::<String>(field)
+                .ok()
+                .map(|field| (field, value.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn field_u64(map: &HashMap<String, Value>, field: &str) -> Option<u64> {
+    map.get(field).and_then(|value| redis::from_redis_value(value).ok())
+}
+
+fn field_string(map: &HashMap<String, Value>, field: &str) -> String {
+    map.get(field)
+        .and_then(|value| redis::from_redis_value(value).ok())
+        .unwrap_or_default()
+}
+
+pub fn age_ms(id: &str, now_ms: u64) -> Option<u64> {
+    parse_stream_id(id).map(|(ms, _)| now_ms.saturating_sub(ms))
+}
+
+/// Threshold violations as human-readable messages.
+pub fn evaluate(report: &LagReport, thresholds: &LagThresholds) -> Vec<String> {
+    let mut alerts = Vec::new();
+    for group in &report.groups {
+        if let Some(max) = thresholds.max_pending.filter(|max| group.pending > *max) {
+            alerts.push(format!("group '{}' has {} pending entries (max {})", group.group, group.pending, max));
+        }
+        if let (Some(max), Some(age)) = (thresholds.max_oldest_pending_ms, group.oldest_pending_ms) {
+            if age > max {
+                alerts.push(format!("group '{}' oldest pending entry is {} ms old (max {})", group.group, age, max));
+            }
+        }
+        if let (Some(max), Some(lag)) = (thresholds.max_lag, group.lag) {
+            if lag > max {
+                alerts.push(format!("group '{}' is {} entries behind (max {})", group.group, lag, max));
+            }
+        }
+    }
+    alerts
+}
+
+fn display_opt(value: Option<u64>) -> String {
+    value.map_or("n/a".to_string(), |v| v.to_string())
+}
+
+pub fn render_table(report: &LagReport) -> String {
+    let mut out = format!("Stream '{}' ({} entries)\n\n", report.stream, report.length);
+    let _ = writeln!(
+        out,
+        "{:<20} {:<20} {:>10} {:>12} {:>16} {:>10}",
+        "Group", "Consumer", "Pending", "Behind", "Oldest pending", "Idle"
+    );
+    for group in &report.groups {
+        let _ = writeln!(
+            out,
+            "{:<20} {:<20} {:>10} {:>12} {:>16} {:>10}",
+            group.group,
+            "*",
+            group.pending,
+            display_opt(group.lag),
+            group.oldest_pending_ms.map_or("-".to_string(), |ms| ms.to_string()),
+            "-"
+        );
+        for consumer in &group.consumers {
+            let _ = writeln!(
+                out,
+                "{:<20} {:<20} {:>10} {:>12} {:>16} {:>10}",
+                "",
+                consumer.name,
+                consumer.pending,
+                "",
+                consumer.oldest_pending_ms.map_or("-".to_string(), |ms| ms.to_string()),
+                consumer.idle_ms
+            );
+        }
+    }
+    out
+}
+
+/// Gauges for the stream, each group and each consumer. Groups whose lag
+/// Redis can't report are left out of `redis_stream_group_lag`.
+pub fn record_metrics(report: &LagReport, metrics: &mut MetricSet) {
+    let stream = report.stream.as_str();
+    metrics.gauge(&STREAM_LENGTH, &[("stream", stream)], report.length as f64);
+    for group in &report.groups {
+        let labels = [("stream", stream), ("group", group.group.as_str())];
+        metrics
+            .gauge(&STREAM_GROUP_PENDING, &labels, group.pending as f64)
+            .gauge(&STREAM_GROUP_OLDEST_PENDING, &labels, group.oldest_pending_ms.unwrap_or(0) as f64);
+        if let Some(lag) = group.lag {
+            metrics.gauge(&STREAM_GROUP_LAG, &labels, lag as f64);
+        }
+        for consumer in &group.consumers {
+            let labels = [("stream", stream), ("group", group.group.as_str()), ("consumer", consumer.name.as_str())];
+            metrics.gauge(&STREAM_CONSUMER_PENDING, &labels, consumer.pending as f64);
+        }
+    }
+}
+
+/// Prometheus text exposition of the report, suitable for a scrape endpoint
+/// or the node_exporter textfile collector.
+pub fn render_prometheus(report: &LagReport) -> String {
+    let mut metrics = MetricSet::default();
+    record_metrics(report, &mut metrics);
+    metrics.render()
+}
+
+pub struct StreamLagMonitor {
+    client: RedisClient,
+}
+
+impl StreamLagMonitor {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn report(&self, stream: &str) -> Result<LagReport> {
+        let mut conn = self.client.get_async_connection().await?;
+        let now_ms = Utc::now().timestamp_millis() as u64;
+
+        let length: u64 = redis::cmd("XLEN").arg(stream).query_async(&mut conn).await?;
+        let groups: Vec<Value> = redis::cmd("XINFO")
+            .arg("GROUPS")
+            .arg(stream)
+            .query_async(&mut conn)
+            .await?;
+
+        let mut report = LagReport {
+            stream: stream.to_string(),
+            length,
+            groups: Vec::new(),
+        };
+        for group in &groups {
+            let info = info_map(group);
+            let name = field_string(&info, "name");
+            let consumers = self.consumers(&mut conn, stream, &name, now_ms).await?;
+            let oldest = oldest_pending_id(&mut conn, stream, &name, None).await?;
+            report.groups.push(GroupLag {
+                last_delivered_id: field_string(&info, "last-delivered-id"),
+                pending: field_u64(&info, "pending").unwrap_or(0),
+                lag: field_u64(&info, "lag"),
+                oldest_pending_ms: oldest.and_then(|id| age_ms(&id, now_ms)),
+                consumers,
+                group: name,
+            });
+        }
+        Ok(report)
+    }
+
+    async fn consumers(
+        &self,
+        conn: &mut RedisConnection,
+        stream: &str,
+        group: &str,
+        now_ms: u64,
+    ) -> Result<Vec<ConsumerLag>> {
+        let consumers: Vec<Value> = redis::cmd("XINFO")
+            .arg("CONSUMERS")
+            .arg(stream)
+            .arg(group)
+            .query_async(conn)
+            .await?;
+        let mut result = Vec::with_capacity(consumers.len());
+        for consumer in &consumers {
+            let info = info_map(consumer);
+            let name = field_string(&info, "name");
+            let pending = field_u64(&info, "pending").unwrap_or(0);
+            let oldest = if pending > 0 {
+                oldest_pending_id(conn, stream, group, Some(&name)).await?
+            } else {
+                None
+            };
+            result.push(ConsumerLag {
+                pending,
+                idle_ms: field_u64(&info, "idle").unwrap_or(0),
+                oldest_pending_ms: oldest.and_then(|id| age_ms(&id, now_ms)),
+                name,
+            });
+        }
+        Ok(result)
+    }
+}
+
+/// First entry of the extended XPENDING form, which is the oldest pending id.
+async fn oldest_pending_id(
+    conn: &mut RedisConnection,
+    stream: &str,
+    group: &str,
+    consumer: Option<&str>,
+) -> Result<Option<String>> {
+    let mut cmd = redis::cmd("XPENDING");
+    cmd.arg(stream).arg(group).arg("-").arg("+").arg(1);
+    if let Some(consumer) = consumer {
+        cmd.arg(consumer);
+    }
+    let entries: Vec<(String, String, u64, u64)> = cmd.query_async(conn).await?;
+    Ok(entries.into_iter().next().map(|(id, ..)| id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use redis::AsyncCommands;
+
+    fn sample_report() -> LagReport {
+        LagReport {
+            stream: "events".to_string(),
+            length: 100,
+            groups: vec![GroupLag {
+                group: "billing".to_string(),
+                last_delivered_id: "1700000000000-0".to_string(),
+                pending: 12,
+                lag: Some(40),
+                oldest_pending_ms: Some(90_000),
+                consumers: vec![ConsumerLag {
+                    name: "worker-1".to_string(),
+                    pending: 12,
+                    idle_ms: 500,
+                    oldest_pending_ms: Some(90_000),
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_info_map_parses_flat_reply() {
+        let reply = Value::Bulk(vec![
+            Value::Data(b"name".to_vec()),
+            Value::Data(b"billing".to_vec()),
+            Value::Data(b"pending".to_vec()),
+            Value::Int(3),
+            Value::Data(b"lag".to_vec()),
+            Value::Nil,
+        ]);
+        let map = info_map(&reply);
+        assert_eq!(field_string(&map, "name"), "billing");
+        assert_eq!(field_u64(&map, "pending"), Some(3));
+        assert_eq!(field_u64(&map, "lag"), None);
+    }
+
+    #[test]
+    fn test_evaluate_thresholds() {
+        let report = sample_report();
+        assert!(evaluate(&report, &LagThresholds::default()).is_empty());
+
+        let thresholds = LagThresholds {
+            max_pending: Some(10),
+            max_oldest_pending_ms: Some(60_000),
+            max_lag: Some(50),
+        };
+        let alerts = evaluate(&report, &thresholds);
+        assert_eq!(alerts.len(), 2);
+        assert!(alerts[0].contains("12 pending"));
+        assert!(alerts[1].contains("90000 ms"));
+    }
+
+    #[test]
+    fn test_thresholds_from_partial_json() {
+        let thresholds: LagThresholds = serde_json::from_str(r#"{"max_lag": 5}"#).unwrap();
+        assert_eq!(thresholds.max_lag, Some(5));
+        assert_eq!(thresholds.max_pending, None);
+    }
+
+    #[test]
+    fn test_renderers() {
+        let report = sample_report();
+        let table = render_table(&report);
+        assert!(table.contains("billing"));
+        assert!(table.contains("worker-1"));
+
+        let prom = render_prometheus(&report);
+        assert!(prom.contains("redis_stream_group_pending{stream=\"events\",group=\"billing\"} 12"));
+        assert!(prom.contains("redis_stream_group_lag{stream=\"events\",group=\"billing\"} 40"));
+        assert!(prom.contains("consumer=\"worker-1\"} 12"));
+    }
+
+    #[test]
+    fn test_lag_format_from_str() {
+        assert_eq!("TABLE".parse::<LagFormat>().unwrap(), LagFormat::Table);
+        assert_eq!("prom".parse::<LagFormat>().unwrap(), LagFormat::Prometheus);
+        assert!("csv".parse::<LagFormat>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_report_counts_pending_per_consumer() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let stream = "lag_test:events";
+        let _: () = conn.del(stream).await.unwrap();
+        for i in 0..10 {
+            let _: String = conn.xadd(stream, "*", &[("n", i)]).await.unwrap();
+        }
+        let _: () = conn.xgroup_create(stream, "workers", "0").await.unwrap();
+        let opts = redis::streams::StreamReadOptions::default().group("workers", "w1").count(4);
+        let _: redis::streams::StreamReadReply = conn.xread_options(&[stream], &[">"], &opts).await.unwrap();
+
+        let report = StreamLagMonitor::new(client.clone()).report(stream).await.unwrap();
+        assert_eq!(report.length, 10);
+        let group = &report.groups[0];
+        assert_eq!(group.pending, 4);
+        assert_eq!(group.consumers[0].name, "w1");
+        assert_eq!(group.consumers[0].pending, 4);
+        assert!(group.oldest_pending_ms.is_some());
+
+        let _: () = conn.del(stream).await.unwrap();
+    }
+}
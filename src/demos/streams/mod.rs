@@ -0,0 +1,43 @@
+pub mod archive;
+pub mod lag;
+pub mod replay;
+
+pub use archive::{ArchiveFormat, ArchiveOptions, ArchiveSummary, StreamArchiver};
+pub use lag::{LagFormat, LagReport, LagThresholds, StreamLagMonitor};
+pub use replay::{ReplayOptions, ReplayTarget, StreamReplayer};
+
+use redis::streams::StreamId;
+use std::collections::BTreeMap;
+
+/// Splits a stream entry id like `1700000000000-3` into (milliseconds, sequence).
+pub fn parse_stream_id(id: &str) -> Option<(u64, u64)> {
+    let (ms, seq) = id.split_once('-')?;
+    Some((ms.parse().ok()?, seq.parse().ok()?))
+}
+
+/// Entry fields as strings, sorted by name for stable output. Non-string
+/// values (which XADD never produces) are skipped.
+pub fn entry_fields(entry: &StreamId) -> BTreeMap<String, String> {
+    entry
+        .map
+        .iter()
+        .filter_map(|(field, value)| {
+            redis::from_redis_value::<String>(value)
+                .ok()
+                .map(|value| (field.clone(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_id() {
+        assert_eq!(parse_stream_id("1700000000000-3"), Some((1_700_000_000_000, 3)));
+        assert_eq!(parse_stream_id("0-0"), Some((0, 0)));
+        assert_eq!(parse_stream_id("$"), None);
+        assert_eq!(parse_stream_id("12-x"), None);
+    }
+}
@@ -0,0 +1,191 @@
+use super::archive::ArchivedEntry;
+use super::{entry_fields, parse_stream_id};
+use crate::{RedisClient, Result};
+use redis::streams::StreamRangeReply;
+use redis::AsyncCommands;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::info;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayTarget {
+    /// PUBLISH each entry as JSON.
+    Channel(String),
+    /// XADD each entry (with a fresh id) to another stream.
+    Stream(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    pub stream: String,
+    pub from: String,
+    pub to: String,
+    /// Playback speed multiplier; `f64::INFINITY` replays without pacing.
+    pub speed: f64,
+    pub target: ReplayTarget,
+    pub batch_size: usize,
+}
+
+impl ReplayOptions {
+    pub fn new(stream: &str, target: ReplayTarget) -> Self {
+        Self {
+            stream: stream.to_string(),
+            from: "-".to_string(),
+            to: "+".to_string(),
+            speed: 1.0,
+            target,
+            batch_size: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplaySummary {
+    pub entries: usize,
+    /// Time covered by the original entries.
+    pub original_span: Duration,
+    pub elapsed: Duration,
+}
+
+/// When an entry should be emitted, relative to the start of the replay.
+/// Offsets are computed from the first entry rather than the previous one,
+/// so sleep overshoot doesn't accumulate into drift over long replays.
+pub fn schedule_offset(first_ms: u64, entry_ms: u64, speed: f64) -> Duration {
+    if !speed.is_finite() || speed <= 0.0 {
+        return Duration::ZERO;
+    }
+    Duration::from_secs_f64(entry_ms.saturating_sub(first_ms) as f64 / 1000.0 / speed)
+}
+
+/// Exclusive XRANGE start for the page after `id` (Redis 6.2+).
+pub fn exclusive_start(id: &str) -> String {
+    format!("({}", id)
+}
+
+/// Re-emits historical stream entries at their original pace (scaled by
+/// `speed`), e.g. to reproduce an incident against a staging consumer.
+pub struct StreamReplayer {
+    client: RedisClient,
+    options: ReplayOptions,
+}
+
+impl StreamReplayer {
+    pub fn new(client: RedisClient, options: ReplayOptions) -> Self {
+        Self { client, options }
+    }
+
+    pub async fn run(&self) -> Result<ReplaySummary> {
+        let mut conn = self.client.get_async_connection().await?;
+        let started = Instant::now();
+        let mut summary = ReplaySummary::default();
+        let mut first_ms = None;
+        let mut start = self.options.from.clone();
+
+        info!(
+            "Replaying '{}' [{}, {}] at {}x to {:?}",
+            self.options.stream, self.options.from, self.options.to, self.options.speed, self.options.target
+        );
+
+        loop {
+            let page: StreamRangeReply = conn
+                .xrange_count(&self.options.stream, &start, &self.options.to, self.options.batch_size)
+                .await?;
+            let Some(last) = page.ids.last() else {
+                break;
+            };
+            start = exclusive_start(&last.id);
+            let page_len = page.ids.len();
+
+            for entry in page.ids {
+                let entry_ms = parse_stream_id(&entry.id).map(|(ms, _)| ms).unwrap_or(0);
+                let first = *first_ms.get_or_insert(entry_ms);
+                tokio::time::sleep_until(started + schedule_offset(first, entry_ms, self.options.speed)).await;
+
+                let fields = entry_fields(&entry);
+                match &self.options.target {
+                    ReplayTarget::Channel(channel) => {
+                        let payload = ArchivedEntry {
+                            stream: self.options.stream.clone(),
+                            id: entry.id.clone(),
+                            timestamp_ms: entry_ms,
+                            fields,
+                        };
+                        let _: () = conn.publish(channel, serde_json::to_string(&payload)?).await?;
+                    }
+                    ReplayTarget::Stream(target) => {
+                        let mut items: Vec<(String, String)> = fields.into_iter().collect();
+                        items.push(("replayed_from".to_string(), entry.id.clone()));
+                        let _: String = conn.xadd(target, "*", &items).await?;
+                    }
+                }
+                summary.entries += 1;
+                summary.original_span = Duration::from_millis(entry_ms.saturating_sub(first));
+            }
+
+            if page_len < self.options.batch_size {
+                break;
+            }
+        }
+
+        summary.elapsed = started.elapsed();
+        Ok(summary)
+    }
+}
+
+pub fn print_summary(summary: &ReplaySummary) {
+    println!("✅ Replayed {} entries", summary.entries);
+    println!(
+        "   Original span {:.2}s replayed in {:.2}s",
+        summary.original_span.as_secs_f64(),
+        summary.elapsed.as_secs_f64()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_offset_scales_with_speed() {
+        assert_eq!(schedule_offset(1_000, 3_000, 1.0), Duration::from_secs(2));
+        assert_eq!(schedule_offset(1_000, 3_000, 2.0), Duration::from_secs(1));
+        assert_eq!(schedule_offset(1_000, 3_000, 0.5), Duration::from_secs(4));
+        assert_eq!(schedule_offset(1_000, 3_000, f64::INFINITY), Duration::ZERO);
+        assert_eq!(schedule_offset(3_000, 1_000, 1.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_exclusive_start() {
+        assert_eq!(exclusive_start("1700000000000-1"), "(1700000000000-1");
+    }
+
+    #[tokio::test]
+    async fn test_replay_to_stream_preserves_order_and_pacing() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let (source, target) = ("replay_test:source", "replay_test:target");
+        let _: () = conn.del(&[source, target]).await.unwrap();
+        for (i, id) in ["1000-0", "1200-0", "1400-0"].iter().enumerate() {
+            let _: String = conn.xadd(source, *id, &[("n", i)]).await.unwrap();
+        }
+
+        let mut options = ReplayOptions::new(source, ReplayTarget::Stream(target.to_string()));
+        options.speed = 2.0;
+        options.batch_size = 2;
+        let summary = StreamReplayer::new(client.clone(), options).run().await.unwrap();
+
+        assert_eq!(summary.entries, 3);
+        assert_eq!(summary.original_span, Duration::from_millis(400));
+        assert!(summary.elapsed >= Duration::from_millis(200));
+
+        let replayed: StreamRangeReply = conn.xrange_all(target).await.unwrap();
+        let origins: Vec<String> = replayed
+            .ids
+            .iter()
+            .map(|entry| entry.get::<String>("replayed_from").unwrap())
+            .collect();
+        assert_eq!(origins, vec!["1000-0", "1200-0", "1400-0"]);
+
+        let _: () = conn.del(&[source, target]).await.unwrap();
+    }
+}
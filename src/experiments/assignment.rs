@@ -0,0 +1,101 @@
+use crate::{DemoError, Result};
+
+/// Number of buckets traffic is split into; weights are relative, so any
+/// positive integers work.
+const BUCKETS: u64 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub name: String,
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Experiment {
+    pub name: String,
+    pub variants: Vec<Variant>,
+}
+
+impl Experiment {
+    pub fn new(name: &str, variants: &[(&str, u32)]) -> Result<Self> {
+        if variants.is_empty() || variants.iter().all(|(_, weight)| *weight == 0) {
+            return Err(DemoError::Configuration(format!(
+                "Experiment '{}' needs at least one variant with a positive weight",
+                name
+            )));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            variants: variants
+                .iter()
+                .map(|(name, weight)| Variant { name: name.to_string(), weight: *weight })
+                .collect(),
+        })
+    }
+
+    /// Even split between `control` and `treatment`.
+    pub fn ab(name: &str) -> Self {
+        Self::new(name, &[("control", 1), ("treatment", 1)]).expect("static variants are valid")
+    }
+
+    pub fn variant_for(&self, user: &str) -> &Variant {
+        assign(self, user)
+    }
+}
+
+/// FNV-1a, chosen because it is stable across processes and Rust versions,
+/// unlike `DefaultHasher`.
+pub fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Deterministically picks a variant for `user`. The experiment name is part
+/// of the hash so users land in independent buckets across experiments.
+pub fn assign<'a>(experiment: &'a Experiment, user: &str) -> &'a Variant {
+    let bucket = fnv1a(format!("{}:{}", experiment.name, user).as_bytes()) % BUCKETS;
+    let total: u64 = experiment.variants.iter().map(|variant| variant.weight as u64).sum();
+    let mut threshold = 0;
+    for variant in &experiment.variants {
+        threshold += variant.weight as u64 * BUCKETS / total;
+        if bucket < threshold {
+            return variant;
+        }
+    }
+    // Rounding can leave the last few buckets unclaimed.
+    experiment.variants.iter().rev().find(|variant| variant.weight > 0).expect("validated in new")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_assignment_is_deterministic() {
+        let experiment = Experiment::ab("checkout");
+        for user in ["alice", "bob", "carol"] {
+            assert_eq!(experiment.variant_for(user), experiment.variant_for(user));
+        }
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_assignment_respects_weights() {
+        let experiment = Experiment::new("pricing", &[("a", 1), ("b", 3), ("off", 0)]).unwrap();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for user in 0..20_000 {
+            *counts.entry(&experiment.variant_for(&format!("user{}", user)).name).or_default() += 1;
+        }
+        let share_b = counts["b"] as f64 / 20_000.0;
+        assert!((share_b - 0.75).abs() < 0.02, "b got {}", share_b);
+        assert!(!counts.contains_key("off"));
+    }
+
+    #[test]
+    fn test_invalid_experiment() {
+        assert!(Experiment::new("empty", &[]).is_err());
+        assert!(Experiment::new("zero", &[("a", 0)]).is_err());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod assignment;
+pub mod tally;
+
+pub use assignment::{assign, Experiment, Variant};
+pub use tally::{significance, ExperimentDemo, ExperimentStore, Significance, VariantResult};
@@ -0,0 +1,326 @@
+use crate::experiments::assignment::{assign, Experiment};
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use redis::{AsyncCommands, Script};
+use std::collections::HashMap;
+use std::fmt::Write;
+use tracing::info;
+
+/// Two-sided critical value for 95% confidence.
+const Z_95: f64 = 1.96;
+
+/// KEYS: exposures HLL, conversions HLL, scratch key
+/// ARGV: user
+///
+/// Counts `user` as converted only if they were exposed: adding them to a
+/// copy of the exposures HLL must change nothing. Returns 1 when newly
+/// counted, 0 for a repeat conversion and -1 without an exposure.
+const CONVERT_SCRIPT: &str = r#"
+redis.call('COPY', KEYS[1], KEYS[3], 'REPLACE')
+local unseen = redis.call('PFADD', KEYS[3], ARGV[1])
+redis.call('DEL', KEYS[3])
+if unseen == 1 then
+    return -1
+end
+return redis.call('PFADD', KEYS[2], ARGV[1])
+"#;
+
+pub fn variants_key(experiment: &str) -> String {
+    format!("experiments:{}:variants", experiment)
+}
+
+pub fn exposures_key(experiment: &str, variant: &str) -> String {
+    format!("experiments:{}:{}:exposures", experiment, variant)
+}
+
+pub fn conversions_key(experiment: &str, variant: &str) -> String {
+    format!("experiments:{}:{}:conversions", experiment, variant)
+}
+
+fn probe_key(experiment: &str, variant: &str) -> String {
+    format!("experiments:{}:{}:probe", experiment, variant)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantResult {
+    pub variant: String,
+    /// Distinct users exposed, estimated by HyperLogLog.
+    pub exposures: u64,
+    /// Distinct exposed users who converted, estimated the same way.
+    pub conversions: u64,
+}
+
+impl VariantResult {
+    pub fn rate(&self) -> f64 {
+        if self.exposures == 0 {
+            0.0
+        } else {
+            self.conversions as f64 / self.exposures as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Significance {
+    /// Relative change of the treatment rate over the control rate.
+    pub lift: f64,
+    pub z: f64,
+    pub significant: bool,
+}
+
+/// Two-proportion z-test. Returns `None` until both variants have traffic
+/// and at least one conversion between them, and when the estimates put
+/// conversions at or above exposures.
+pub fn significance(control: &VariantResult, treatment: &VariantResult) -> Option<Significance> {
+    let (n1, n2) = (control.exposures as f64, treatment.exposures as f64);
+    if n1 == 0.0 || n2 == 0.0 {
+        return None;
+    }
+    let pooled = (control.conversions + treatment.conversions) as f64 / (n1 + n2);
+    if pooled >= 1.0 {
+        return None;
+    }
+    let std_err = (pooled * (1.0 - pooled) * (1.0 / n1 + 1.0 / n2)).sqrt();
+    if std_err == 0.0 || !std_err.is_finite() {
+        return None;
+    }
+    let z = (treatment.rate() - control.rate()) / std_err;
+    let lift = if control.rate() == 0.0 { 0.0 } else { treatment.rate() / control.rate() - 1.0 };
+    Some(Significance { lift, z, significant: z.abs() >= Z_95 })
+}
+
+pub fn render_results(results: &[VariantResult]) -> String {
+    let mut out = format!("   {:<12} {:>10} {:>12} {:>8}\n", "variant", "exposed", "conversions", "rate");
+    for result in results {
+        let _ = writeln!(
+            out,
+            "   {:<12} {:>10} {:>12} {:>7.2}%",
+            result.variant,
+            result.exposures,
+            result.conversions,
+            result.rate() * 100.0
+        );
+    }
+    if let [control, rest @ ..] = results {
+        for treatment in rest {
+            match significance(control, treatment) {
+                Some(sig) => {
+                    let _ = writeln!(
+                        out,
+                        "   {} vs {}: lift {:+.1}%, z = {:.2} => {}",
+                        treatment.variant,
+                        control.variant,
+                        sig.lift * 100.0,
+                        sig.z,
+                        if sig.significant { "significant at 95%" } else { "not significant yet" }
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "   {} vs {}: not enough data", treatment.variant, control.variant);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Exposure and conversion HLLs per experiment variant.
+pub struct ExperimentStore {
+    conn: RedisConnection,
+    convert: Script,
+}
+
+impl ExperimentStore {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self { conn: client.get_async_connection().await?, convert: Script::new(CONVERT_SCRIPT) })
+    }
+
+    /// Saves the variant weights so `results` can be run from another process.
+    pub async fn register(&mut self, experiment: &Experiment) -> Result<()> {
+        let key = variants_key(&experiment.name);
+        let fields: Vec<(String, String)> = experiment
+            .variants
+            .iter()
+            .enumerate()
+            // Prefix with the position so variant order survives the hash.
+            .map(|(i, variant)| (format!("{}:{}", i, variant.name), variant.weight.to_string()))
+            .collect();
+        let _: () = redis::pipe()
+            .atomic()
+            .del(&key)
+            .ignore()
+            .hset_multiple(&key, &fields)
+            .ignore()
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn load(&mut self, name: &str) -> Result<Option<Experiment>> {
+        let fields: HashMap<String, u32> = self.conn.hgetall(variants_key(name)).await?;
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        let mut variants: Vec<(usize, String, u32)> = Vec::new();
+        for (field, weight) in fields {
+            let (position, variant) = field
+                .split_once(':')
+                .and_then(|(position, variant)| Some((position.parse().ok()?, variant.to_string())))
+                .ok_or_else(|| DemoError::Demo(format!("Invalid variant field: {}", field)))?;
+            variants.push((position, variant, weight));
+        }
+        variants.sort();
+        let variants: Vec<(&str, u32)> = variants.iter().map(|(_, name, weight)| (name.as_str(), *weight)).collect();
+        Ok(Some(Experiment::new(name, &variants)?))
+    }
+
+    /// Records that `user` saw the experiment and returns their variant.
+    pub async fn expose(&mut self, experiment: &Experiment, user: &str) -> Result<String> {
+        let variant = assign(experiment, user).name.clone();
+        let _: () = self.conn.pfadd(exposures_key(&experiment.name, &variant), user).await?;
+        Ok(variant)
+    }
+
+    /// Records a conversion for `user`. Returns false, counting nothing,
+    /// for a repeat conversion or a user who was never exposed.
+    pub async fn convert(&mut self, experiment: &Experiment, user: &str) -> Result<bool> {
+        let variant = &assign(experiment, user).name;
+        let counted: i64 = self
+            .convert
+            .key(exposures_key(&experiment.name, variant))
+            .key(conversions_key(&experiment.name, variant))
+            .key(probe_key(&experiment.name, variant))
+            .arg(user)
+            .invoke_async(&mut self.conn)
+            .await?;
+        Ok(counted == 1)
+    }
+
+    pub async fn results(&mut self, experiment: &Experiment) -> Result<Vec<VariantResult>> {
+        let mut results = Vec::new();
+        for variant in &experiment.variants {
+            let exposures: u64 = self.conn.pfcount(exposures_key(&experiment.name, &variant.name)).await?;
+            let conversions: u64 = self.conn.pfcount(conversions_key(&experiment.name, &variant.name)).await?;
+            results.push(VariantResult { variant: variant.name.clone(), exposures, conversions });
+        }
+        Ok(results)
+    }
+
+    pub async fn clear(&mut self, experiment: &Experiment) -> Result<()> {
+        let mut keys = vec![variants_key(&experiment.name)];
+        for variant in &experiment.variants {
+            keys.push(exposures_key(&experiment.name, &variant.name));
+            keys.push(conversions_key(&experiment.name, &variant.name));
+            keys.push(probe_key(&experiment.name, &variant.name));
+        }
+        let _: () = self.conn.del(keys).await?;
+        Ok(())
+    }
+}
+
+pub struct ExperimentDemo {
+    client: RedisClient,
+}
+
+impl ExperimentDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Sends `users` visitors through a 50/50 control/treatment split with
+    /// the given true conversion rates. Data is kept so `results` can be
+    /// re-run against it.
+    pub async fn simulate(&self, name: &str, users: usize, control_rate: f64, treatment_rate: f64, seed: u64) -> Result<Vec<VariantResult>> {
+        let mut store = ExperimentStore::new(&self.client).await?;
+        let experiment = Experiment::ab(name);
+        store.clear(&experiment).await?;
+        store.register(&experiment).await?;
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        println!("\n=== A/B Test: {} ===\n", name);
+        let mut sections = Sections::new();
+        sections.next(&format!("Sending {} users (true rates: control {:.1}%, treatment {:.1}%)", users, control_rate * 100.0, treatment_rate * 100.0));
+        for user in 0..users {
+            let user = format!("user{}", user);
+            let variant = store.expose(&experiment, &user).await?;
+            let rate = if variant == "control" { control_rate } else { treatment_rate };
+            // Returning visitors are exposed again but only counted once.
+            if rng.gen_bool(0.2) {
+                store.expose(&experiment, &user).await?;
+            }
+            if rng.gen_bool(rate.clamp(0.0, 1.0)) {
+                store.convert(&experiment, &user).await?;
+            }
+        }
+
+        sections.next("Results:");
+        let results = store.results(&experiment).await?;
+        print!("{}", render_results(&results));
+
+        info!("Experiment simulation completed");
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(variant: &str, exposures: u64, conversions: u64) -> VariantResult {
+        VariantResult { variant: variant.to_string(), exposures, conversions }
+    }
+
+    #[test]
+    fn test_significance_detects_large_difference() {
+        let sig = significance(&result("control", 5000, 500), &result("treatment", 5000, 650)).unwrap();
+        assert!(sig.significant);
+        assert!((sig.lift - 0.3).abs() < 1e-9);
+        assert!(sig.z > 4.0);
+    }
+
+    #[test]
+    fn test_significance_small_samples() {
+        let sig = significance(&result("control", 100, 10), &result("treatment", 100, 12)).unwrap();
+        assert!(!sig.significant);
+        assert!(significance(&result("control", 0, 0), &result("treatment", 10, 1)).is_none());
+        assert!(significance(&result("control", 10, 0), &result("treatment", 10, 0)).is_none());
+    }
+
+    #[test]
+    fn test_significance_with_more_conversions_than_exposures() {
+        assert!(significance(&result("control", 10, 15), &result("treatment", 10, 8)).is_none());
+        assert!(significance(&result("control", 10, 10), &result("treatment", 10, 10)).is_none());
+        let out = render_results(&[result("control", 10, 15), result("treatment", 10, 8)]);
+        assert!(out.contains("treatment vs control: not enough data"));
+    }
+
+    #[test]
+    fn test_render_results() {
+        let out = render_results(&[result("control", 1000, 100), result("treatment", 1000, 100)]);
+        assert!(out.contains("10.00%"));
+        assert!(out.contains("treatment vs control: lift +0.0%"));
+        assert!(out.contains("not significant yet"));
+    }
+
+    #[tokio::test]
+    async fn test_register_and_load_round_trip() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut store = ExperimentStore::new(&client).await.unwrap();
+        let experiment = Experiment::new("tally_test", &[("b", 1), ("a", 2)]).unwrap();
+        store.register(&experiment).await.unwrap();
+        assert_eq!(store.load("tally_test").await.unwrap(), Some(experiment.clone()));
+
+        store.expose(&experiment, "u1").await.unwrap();
+        store.expose(&experiment, "u1").await.unwrap();
+        assert!(store.convert(&experiment, "u1").await.unwrap());
+        assert!(!store.convert(&experiment, "u1").await.unwrap());
+        assert!(!store.convert(&experiment, "never-exposed").await.unwrap());
+        let results = store.results(&experiment).await.unwrap();
+        assert_eq!(results.iter().map(|r| r.exposures).sum::<u64>(), 1);
+        assert_eq!(results.iter().map(|r| r.conversions).sum::<u64>(), 1);
+
+        store.clear(&experiment).await.unwrap();
+    }
+}
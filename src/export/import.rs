@@ -0,0 +1,298 @@
+use super::record::{ExportedKey, KeyValue};
+use crate::utils::RedisConnection;
+use crate::{DemoError, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// This crate's export, one key per line or one JSON array. Keys are
+    /// replaced, with their type and TTL.
+    Records,
+    /// `redis-cli --csv` rows: `"key","value"` becomes SET, `"key","field",
+    /// "value",...` becomes HSET.
+    Csv,
+    /// `redis-cli --json` rows, one `{"key": value}` object per line: a
+    /// string becomes SET, an object or a flat field/value array HSET.
+    CliJson,
+}
+
+impl FromStr for ImportFormat {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "ndjson" | "jsonl" | "json" | "records" => Ok(ImportFormat::Records),
+            "csv" => Ok(ImportFormat::Csv),
+            "cli-json" => Ok(ImportFormat::CliJson),
+            other => Err(DemoError::Configuration(format!("Unknown import format: {} (use ndjson, csv or cli-json)", other))),
+        }
+    }
+}
+
+impl ImportFormat {
+    /// Guesses from the first non-blank line: a quoted value is CSV, an
+    /// array or an object with `key` and `type` is an export, any other
+    /// object is redis-cli JSON.
+    pub fn detect(text: &str) -> Result<Self> {
+        let Some(first) = text.lines().map(str::trim).find(|line| !line.is_empty()) else {
+            return Err(DemoError::Configuration("The file is empty".to_string()));
+        };
+        if first.starts_with('"') {
+            return Ok(ImportFormat::Csv);
+        }
+        if first.starts_with('[') {
+            return Ok(ImportFormat::Records);
+        }
+        match serde_json::from_str::<Value>(first) {
+            Ok(Value::Object(object)) if object.contains_key("key") && object.contains_key("type") => Ok(ImportFormat::Records),
+            Ok(Value::Object(_)) => Ok(ImportFormat::CliJson),
+            _ => Err(DemoError::Configuration(format!(
+                "Can't tell the format from the first line ({}); pass --format",
+                first.chars().take(40).collect::<String>()
+            ))),
+        }
+    }
+
+    /// Whether a row stands for the whole key (DEL first) or for values to
+    /// write into it, as SET and HSET do.
+    pub fn replaces_keys(self) -> bool {
+        self == ImportFormat::Records
+    }
+}
+
+fn at_line(number: usize, message: impl std::fmt::Display) -> DemoError {
+    DemoError::Configuration(format!("line {}: {}", number, message))
+}
+
+/// Parses every row of `text`, reporting the first bad one by line number.
+pub fn parse(text: &str, format: ImportFormat) -> Result<Vec<ExportedKey>> {
+    if format == ImportFormat::Records && text.trim_start().starts_with('[') {
+        return serde_json::from_str(text).map_err(|e| at_line(e.line(), e));
+    }
+    let mut keys = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let key = match format {
+            ImportFormat::Records => serde_json::from_str(line).map_err(|e| e.to_string()),
+            ImportFormat::Csv => csv_row(line).and_then(row_to_key),
+            ImportFormat::CliJson => cli_json_row(line),
+        };
+        keys.push(key.map_err(|e| at_line(index + 1, e))?);
+    }
+    Ok(keys)
+}
+
+/// One `redis-cli --csv` line: comma-separated values, strings quoted with
+/// the escapes redis-cli writes (`\"`, `\\`, `\n`, `\xHH`, ...), numbers bare.
+pub fn csv_row(line: &str) -> std::result::Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut bytes = Vec::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('n') => bytes.push(b'\n'),
+                        Some('r') => bytes.push(b'\r'),
+                        Some('t') => bytes.push(b'\t'),
+                        Some('a') => bytes.push(0x07),
+                        Some('b') => bytes.push(0x08),
+                        Some('x') => {
+                            let hex: String = chars.by_ref().take(2).collect();
+                            bytes.push(u8::from_str_radix(&hex, 16).map_err(|_| format!("bad escape \\x{}", hex))?);
+                        }
+                        Some(c) => bytes.extend(c.to_string().as_bytes()),
+                        None => return Err("unterminated quote".to_string()),
+                    },
+                    Some(c) => bytes.extend(c.to_string().as_bytes()),
+                    None => return Err("unterminated quote".to_string()),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ',' {
+                    break;
+                }
+                bytes.extend(c.to_string().as_bytes());
+                chars.next();
+            }
+        }
+        fields.push(String::from_utf8_lossy(&bytes).into_owned());
+        match chars.next() {
+            Some(',') => continue,
+            None => return Ok(fields),
+            Some(c) => return Err(format!("expected a comma, found '{}'", c)),
+        }
+    }
+}
+
+/// `key, value` is a string; `key` then field/value pairs is a hash.
+fn row_to_key(mut fields: Vec<String>) -> std::result::Result<ExportedKey, String> {
+    let key = fields.remove(0);
+    let value = match fields.len() {
+        1 => KeyValue::String(fields.remove(0)),
+        n if n >= 2 && n % 2 == 0 => KeyValue::Hash(pairs(fields)),
+        _ => return Err(format!("{} needs a value, or field/value pairs", key)),
+    };
+    Ok(ExportedKey { key, ttl_ms: None, value })
+}
+
+fn pairs(fields: Vec<String>) -> BTreeMap<String, String> {
+    let mut fields = fields.into_iter();
+    let mut hash = BTreeMap::new();
+    while let (Some(field), Some(value)) = (fields.next(), fields.next()) {
+        hash.insert(field, value);
+    }
+    hash
+}
+
+/// Strings as they are, anything else as JSON.
+fn scalar(value: Value) -> String {
+    match value {
+        Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
+
+fn cli_json_row(line: &str) -> std::result::Result<ExportedKey, String> {
+    let Value::Object(object) = serde_json::from_str(line).map_err(|e| e.to_string())? else {
+        return Err("expected a {\"key\": value} object".to_string());
+    };
+    let mut entries = object.into_iter();
+    let (Some((key, value)), None) = (entries.next(), entries.next()) else {
+        return Err("expected exactly one key per line".to_string());
+    };
+    let value = match value {
+        Value::Object(fields) => KeyValue::Hash(fields.into_iter().map(|(field, value)| (field, scalar(value))).collect()),
+        Value::Array(items) if items.len() % 2 == 0 => KeyValue::Hash(pairs(items.into_iter().map(scalar).collect())),
+        Value::Array(_) => return Err(format!("{}: a field/value array needs an even length", key)),
+        Value::Null => return Err(format!("{}: null has nothing to import", key)),
+        other => KeyValue::String(scalar(other)),
+    };
+    Ok(ExportedKey { key, ttl_ms: None, value })
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub keys: usize,
+    /// Rows skipped because the collection was empty; Redis has no empty keys.
+    pub empty: usize,
+}
+
+/// Writes parsed rows back with one pipelined MULTI per batch.
+pub struct Importer {
+    conn: RedisConnection,
+}
+
+impl Importer {
+    pub fn new(conn: RedisConnection) -> Self {
+        Self { conn }
+    }
+
+    pub async fn run(&mut self, keys: &[ExportedKey], format: ImportFormat, batch_size: usize) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        for batch in keys.chunks(batch_size.max(1)) {
+            let mut pipe = redis::pipe();
+            pipe.atomic();
+            for key in batch {
+                match queue(&mut pipe, key, format.replaces_keys()) {
+                    true => summary.keys += 1,
+                    false => summary.empty += 1,
+                }
+            }
+            let _: () = pipe.query_async(&mut self.conn).await?;
+        }
+        info!("Imported {} keys ({:?})", summary.keys, format);
+        Ok(summary)
+    }
+}
+
+/// Queues the writes for one key; `false` if there was nothing to write.
+fn queue(pipe: &mut redis::Pipeline, record: &ExportedKey, replace: bool) -> bool {
+    let key = &record.key;
+    if record.value.is_empty() && !matches!(record.value, KeyValue::String(_)) {
+        return false;
+    }
+    if replace {
+        pipe.del(key).ignore();
+    }
+    match &record.value {
+        KeyValue::String(value) => {
+            pipe.set(key, value).ignore();
+        }
+        KeyValue::Hash(fields) => {
+            pipe.hset_multiple(key, &fields.iter().collect::<Vec<_>>()).ignore();
+        }
+        KeyValue::List(items) => {
+            pipe.rpush(key, items).ignore();
+        }
+        KeyValue::Set(members) => {
+            pipe.sadd(key, members).ignore();
+        }
+        KeyValue::ZSet(members) => {
+            let scored: Vec<(f64, &String)> = members.iter().map(|(member, score)| (*score, member)).collect();
+            pipe.zadd_multiple(key, &scored).ignore();
+        }
+        KeyValue::Stream(entries) => {
+            for entry in entries {
+                pipe.xadd(key, &entry.id, &entry.fields.iter().collect::<Vec<_>>()).ignore();
+            }
+        }
+    }
+    if let Some(ttl_ms) = record.ttl_ms {
+        pipe.pexpire(key, ttl_ms as i64).ignore();
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(ImportFormat::detect("\n\"user:1\",\"Amy\"\n").unwrap(), ImportFormat::Csv);
+        assert_eq!(ImportFormat::detect(r#"{"key":"k","type":"string","value":"v"}"#).unwrap(), ImportFormat::Records);
+        assert_eq!(ImportFormat::detect("[\n  {\"key\": \"k\"}\n]").unwrap(), ImportFormat::Records);
+        assert_eq!(ImportFormat::detect(r#"{"user:1":{"name":"Amy"}}"#).unwrap(), ImportFormat::CliJson);
+        assert!(ImportFormat::detect("user:1 Amy").is_err());
+        assert!(ImportFormat::detect("  \n").is_err());
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        assert_eq!(csv_row(r#""a \"quoted\"\n","\xe2\x9c\x93",42"#).unwrap(), ["a \"quoted\"\n", "✓", "42"]);
+        assert!(csv_row(r#""open"#).is_err());
+        let keys = parse("\"greeting\",\"hi\"\n\n\"user:1\",\"name\",\"Amy\",\"age\",\"30\"\n", ImportFormat::Csv).unwrap();
+        assert_eq!(keys[0].value, KeyValue::String("hi".to_string()));
+        assert_eq!(keys[1].value, KeyValue::Hash(pairs(["name", "Amy", "age", "30"].map(String::from).to_vec())));
+        let error = parse("\"ok\",\"v\"\n\"user:1\",\"name\",\"Amy\",\"age\"\n", ImportFormat::Csv).unwrap_err();
+        assert!(error.to_string().contains("line 2: user:1 needs a value"), "{}", error);
+    }
+
+    #[test]
+    fn test_cli_json_rows() {
+        let keys = parse("{\"n\": 5}\n{\"user:1\": {\"name\": \"Amy\", \"tags\": [1]}}\n{\"h\": [\"f\", 1]}", ImportFormat::CliJson).unwrap();
+        assert_eq!(keys[0].value, KeyValue::String("5".to_string()));
+        assert_eq!(keys[1].value, KeyValue::Hash(pairs(["name", "Amy", "tags", "[1]"].map(String::from).to_vec())));
+        assert_eq!(keys[2].value, KeyValue::Hash(pairs(["f", "1"].map(String::from).to_vec())));
+        assert!(parse(r#"{"a": "1", "b": "2"}"#, ImportFormat::CliJson).is_err());
+    }
+
+    #[test]
+    fn test_records_round_trip_an_export() {
+        let exported = ExportedKey { key: "s".to_string(), ttl_ms: Some(500), value: KeyValue::Set(vec!["a".to_string()]) };
+        let line = serde_json::to_string(&exported).unwrap();
+        assert_eq!(parse(&line, ImportFormat::Records).unwrap(), parse(&format!("[{}]", line), ImportFormat::Records).unwrap());
+        assert_eq!(parse(&line, ImportFormat::Records).unwrap(), [exported]);
+    }
+}
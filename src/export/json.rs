@@ -0,0 +1,416 @@
+use super::mass_insert::{del_command, expire_command, restore_commands, write_commands};
+use super::record::{decode, decode_value, fetch_raw, fetch_window, KeyValue, RawKey};
+use crate::utils::scan::{KeyScanner, KeyType};
+use crate::{DemoError, RedisClient, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use redis::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, info};
+
+/// The budget is handed out in KiB so it fits the semaphore's u32 permits.
+const PERMIT_BYTES: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON array, written element by element.
+    Array,
+    /// One object per line.
+    Ndjson,
+    /// The commands that recreate each key, in the protocol `redis-cli
+    /// --pipe` mass-inserts. Values are copied byte for byte.
+    Resp,
+}
+
+impl FromStr for ExportFormat {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" | "array" => Ok(ExportFormat::Array),
+            "ndjson" | "jsonl" => Ok(ExportFormat::Ndjson),
+            "resp" => Ok(ExportFormat::Resp),
+            other => Err(DemoError::Configuration(format!("Unknown export format: {}", other))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub pattern: String,
+    pub out: PathBuf,
+    pub format: ExportFormat,
+    /// Keys per SCAN batch and per fetch pipeline, and elements per read of
+    /// a collection. Bigger collections are streamed a window at a time.
+    pub batch_size: usize,
+    /// Most serialized bytes held between the fetch and write stages.
+    pub memory_budget: usize,
+}
+
+impl ExportOptions {
+    pub fn new(pattern: &str, out: impl Into<PathBuf>) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            out: out.into(),
+            format: ExportFormat::Array,
+            batch_size: 500,
+            memory_budget: 64 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub keys: u64,
+    pub bytes: u64,
+    /// High-water mark of serialized bytes waiting to be written.
+    pub peak_buffered: usize,
+}
+
+/// Peak resident set size of this process (VmHWM), where the OS reports it.
+pub fn peak_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Output bytes, separators included, plus the share of the budget they
+/// hold until written. `keys` counts the records that start in it: a big
+/// collection spans a chunk per window.
+struct Chunk {
+    data: Vec<u8>,
+    keys: u64,
+    _permit: OwnedSemaphorePermit,
+}
+
+/// The JSON elements of one window, without the enclosing brackets, so
+/// windows can be joined with commas into one array or object.
+fn json_elements(value: &KeyValue) -> Result<Vec<u8>> {
+    let json = match value {
+        KeyValue::String(_) => return Err(DemoError::Demo("Strings are not read in windows".to_string())),
+        KeyValue::Hash(fields) => serde_json::to_vec(fields)?,
+        KeyValue::List(items) | KeyValue::Set(items) => serde_json::to_vec(items)?,
+        KeyValue::ZSet(members) => serde_json::to_vec(members)?,
+        KeyValue::Stream(entries) => serde_json::to_vec(entries)?,
+    };
+    Ok(json[1..json.len() - 1].to_vec())
+}
+
+/// Turns fetched keys into the bytes of `format`: whole keys in one go,
+/// big collections as an opening, a piece per window and a closing.
+struct Encoder {
+    format: ExportFormat,
+    records: u64,
+    /// Whether the open record already has an element, so the next one
+    /// needs a comma.
+    open_has_elements: bool,
+}
+
+impl Encoder {
+    fn new(format: ExportFormat) -> Self {
+        Self { format, records: 0, open_has_elements: false }
+    }
+
+    fn separator(&mut self) -> &'static [u8] {
+        self.records += 1;
+        match (self.format, self.records) {
+            (ExportFormat::Array, 1) => b"\n",
+            (ExportFormat::Array, _) => b",\n",
+            _ => b"",
+        }
+    }
+
+    fn terminator(&self) -> &'static [u8] {
+        match self.format {
+            ExportFormat::Ndjson => b"\n",
+            _ => b"",
+        }
+    }
+
+    fn whole(&mut self, raw: RawKey<'_>, out: &mut Vec<u8>) -> Result<()> {
+        if self.format == ExportFormat::Resp {
+            let commands = restore_commands(raw)?;
+            if !commands.is_empty() {
+                self.records += 1;
+                out.extend(commands);
+            }
+            return Ok(());
+        }
+        out.extend_from_slice(self.separator());
+        serde_json::to_writer(&mut *out, &decode(raw)?)?;
+        out.extend_from_slice(self.terminator());
+        Ok(())
+    }
+
+    fn open(&mut self, key: &str, key_type: KeyType, ttl_ms: Option<u64>, out: &mut Vec<u8>) -> Result<()> {
+        self.open_has_elements = false;
+        if self.format == ExportFormat::Resp {
+            self.records += 1;
+            out.extend(del_command(key));
+            return Ok(());
+        }
+        out.extend_from_slice(self.separator());
+        out.extend_from_slice(b"{\"key\":");
+        serde_json::to_writer(&mut *out, key)?;
+        if let Some(ttl_ms) = ttl_ms {
+            out.extend_from_slice(format!(",\"ttl_ms\":{}", ttl_ms).as_bytes());
+        }
+        let bracket = if key_type == KeyType::Hash { "{" } else { "[" };
+        out.extend_from_slice(format!(",\"type\":\"{}\",\"value\":{}", key_type, bracket).as_bytes());
+        Ok(())
+    }
+
+    fn window(&mut self, key: &str, key_type: KeyType, value: Value, out: &mut Vec<u8>) -> Result<()> {
+        if self.format == ExportFormat::Resp {
+            out.extend(write_commands(key, key_type, value)?);
+            return Ok(());
+        }
+        let elements = json_elements(&decode_value(key_type, &value)?)?;
+        if !elements.is_empty() {
+            if self.open_has_elements {
+                out.push(b',');
+            }
+            out.extend(elements);
+            self.open_has_elements = true;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self, key: &str, key_type: KeyType, ttl_ms: Option<u64>, out: &mut Vec<u8>) {
+        if self.format == ExportFormat::Resp {
+            out.extend(expire_command(key, ttl_ms));
+            return;
+        }
+        out.extend_from_slice(if key_type == KeyType::Hash { b"}}" } else { b"]}" });
+        out.extend_from_slice(self.terminator());
+    }
+}
+
+/// Three-stage export: a SCAN task feeds a bounded channel of key batches, a
+/// fetch task pipelines the reads and serializes each record, and the caller's
+/// task appends them to the file.
+///
+/// Backpressure is twofold: the key channel holds a couple of batches, and
+/// the fetch stage has to acquire its batch's serialized size from a
+/// semaphore sized to the memory budget before handing it over. A slow disk
+/// therefore stalls fetching, which stalls scanning.
+///
+/// No collection is read whole: each fetch reads at most `batch_size`
+/// elements of a key (LRANGE and ZRANGE windows, HSCAN and SSCAN, XRANGE
+/// with COUNT), and a bigger one is written a window at a time. SCAN may
+/// repeat a hash field or set member across windows; importing keeps one.
+pub struct JsonExporter {
+    client: RedisClient,
+}
+
+impl JsonExporter {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn run(&self, options: &ExportOptions) -> Result<ExportSummary> {
+        let budget_permits = (options.memory_budget / PERMIT_BYTES).clamp(1, u32::MAX as usize);
+        let budget = Arc::new(Semaphore::new(budget_permits));
+        let (keys_tx, mut keys_rx) = mpsc::channel::<Vec<String>>(2);
+        let (chunks_tx, mut chunks_rx) = mpsc::channel::<Chunk>(budget_permits.min(1024));
+
+        let mut scanner = KeyScanner::new(self.client.get_async_connection().await?, &options.pattern, None)
+            .with_count(options.batch_size);
+        let scan = tokio::spawn(async move {
+            while let Some(batch) = scanner.next_batch().await? {
+                if !batch.is_empty() && keys_tx.send(batch).await.is_err() {
+                    break;
+                }
+            }
+            Ok::<_, DemoError>(())
+        });
+
+        let mut conn = self.client.get_async_connection().await?;
+        let fetch_budget = budget.clone();
+        let window = options.batch_size.max(1);
+        let mut encoder = Encoder::new(options.format);
+        let fetch = tokio::spawn(async move {
+            // Hands `data` to the writer once the budget has room for it.
+            // Returns false when the writer is gone.
+            let hand_over = |data: Vec<u8>, keys: u64| {
+                let budget = fetch_budget.clone();
+                let chunks_tx = chunks_tx.clone();
+                async move {
+                    // A single chunk bigger than the whole budget still has
+                    // to go through; it just goes through alone.
+                    let permits = data.len().div_ceil(PERMIT_BYTES).clamp(1, budget_permits) as u32;
+                    let permit = budget.acquire_many_owned(permits).await.map_err(|e| DemoError::Demo(e.to_string()))?;
+                    Ok::<_, DemoError>(chunks_tx.send(Chunk { data, keys, _permit: permit }).await.is_ok())
+                }
+            };
+            // Records already counted in a chunk.
+            let mut counted = 0;
+            while let Some(keys) = keys_rx.recv().await {
+                let mut data = Vec::new();
+                for raw in fetch_raw(&mut conn, &keys, window).await? {
+                    let Some(mut from) = raw.next.clone() else {
+                        encoder.whole(raw, &mut data)?;
+                        continue;
+                    };
+                    let (key, key_type, ttl_ms) = (raw.key, raw.key_type, raw.ttl_ms);
+                    encoder.open(key, key_type, ttl_ms, &mut data)?;
+                    encoder.window(key, key_type, raw.value, &mut data)?;
+                    loop {
+                        let started = encoder.records - std::mem::replace(&mut counted, encoder.records);
+                        if !hand_over(std::mem::take(&mut data), started).await? {
+                            return Ok(());
+                        }
+                        let (value, next) = fetch_window(&mut conn, key, key_type, &from, window).await?;
+                        encoder.window(key, key_type, value, &mut data)?;
+                        match next {
+                            Some(next) => from = next,
+                            None => break,
+                        }
+                    }
+                    encoder.close(key, key_type, ttl_ms, &mut data);
+                }
+                let started = encoder.records - std::mem::replace(&mut counted, encoder.records);
+                if !hand_over(data, started).await? {
+                    break;
+                }
+            }
+            Ok::<_, DemoError>(())
+        });
+
+        let mut out = BufWriter::new(File::create(&options.out)?);
+        let mut summary = ExportSummary::default();
+        if options.format == ExportFormat::Array {
+            out.write_all(b"[")?;
+        }
+        while let Some(chunk) = chunks_rx.recv().await {
+            let buffered = (budget_permits - budget.available_permits()) * PERMIT_BYTES;
+            summary.peak_buffered = summary.peak_buffered.max(buffered);
+            out.write_all(&chunk.data)?;
+            summary.keys += chunk.keys;
+            summary.bytes += chunk.data.len() as u64;
+            debug!("Exported {} keys so far", summary.keys);
+        }
+        if options.format == ExportFormat::Array {
+            out.write_all(b"\n]\n")?;
+        }
+        out.flush()?;
+
+        scan.await.map_err(|e| DemoError::Demo(e.to_string()))??;
+        fetch.await.map_err(|e| DemoError::Demo(e.to_string()))??;
+        info!("Exported {} keys ({} bytes) to {}", summary.keys, summary.bytes, options.out.display());
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::record::ExportedKey;
+    use redis::AsyncCommands;
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("JSON".parse::<ExportFormat>().unwrap(), ExportFormat::Array);
+        assert_eq!("jsonl".parse::<ExportFormat>().unwrap(), ExportFormat::Ndjson);
+        assert_eq!("resp".parse::<ExportFormat>().unwrap(), ExportFormat::Resp);
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn test_peak_rss() {
+        if cfg!(target_os = "linux") {
+            assert!(peak_rss_bytes().unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn test_windowed_records_match_whole_ones() {
+        let data = |s: &str| Value::Data(s.as_bytes().to_vec());
+        let fields = |pairs: &[&str]| Value::Bulk(pairs.iter().map(|s| data(s)).collect());
+        for format in [ExportFormat::Array, ExportFormat::Ndjson] {
+            let mut encoder = Encoder::new(format);
+            let mut out = b"[".to_vec();
+            let string = RawKey { key: "s", key_type: KeyType::String, ttl_ms: None, value: data("v"), next: None };
+            encoder.whole(string, &mut out).unwrap();
+            encoder.open("h", KeyType::Hash, Some(500), &mut out).unwrap();
+            encoder.window("h", KeyType::Hash, fields(&["a", "1"]), &mut out).unwrap();
+            encoder.window("h", KeyType::Hash, fields(&[]), &mut out).unwrap();
+            encoder.window("h", KeyType::Hash, fields(&["b", "2"]), &mut out).unwrap();
+            encoder.close("h", KeyType::Hash, Some(500), &mut out);
+            encoder.open("l", KeyType::List, None, &mut out).unwrap();
+            encoder.window("l", KeyType::List, fields(&["x"]), &mut out).unwrap();
+            encoder.window("l", KeyType::List, fields(&["y", "z"]), &mut out).unwrap();
+            encoder.close("l", KeyType::List, None, &mut out);
+            assert_eq!(encoder.records, 3);
+
+            let text = String::from_utf8(out).unwrap();
+            let records: Vec<ExportedKey> = match format {
+                ExportFormat::Array => serde_json::from_str(&format!("{}\n]", text)).unwrap(),
+                _ => text[1..].lines().map(|line| serde_json::from_str(line).unwrap()).collect(),
+            };
+            let hash = [("a", "1"), ("b", "2")].map(|(f, v)| (f.to_string(), v.to_string())).into_iter().collect();
+            assert_eq!(records[1], ExportedKey { key: "h".to_string(), ttl_ms: Some(500), value: KeyValue::Hash(hash) });
+            assert_eq!(records[2].value, KeyValue::List(vec!["x".to_string(), "y".to_string(), "z".to_string()]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_big_collections_are_exported_in_windows() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let keys = ["export_window:l", "export_window:h", "export_window:z", "export_window:x"];
+        let _: () = conn.del(&keys).await.unwrap();
+        let items: Vec<String> = (0..25).map(|i| format!("item-{:02}", i)).collect();
+        let _: () = conn.rpush(keys[0], &items).await.unwrap();
+        let pairs: Vec<(String, String)> = items.iter().map(|item| (item.clone(), "v".to_string())).collect();
+        let _: () = conn.hset_multiple(keys[1], &pairs).await.unwrap();
+        let scored: Vec<(usize, String)> = items.iter().cloned().enumerate().collect();
+        let _: () = conn.zadd_multiple(keys[2], &scored).await.unwrap();
+        for item in &items {
+            let _: String = conn.xadd(keys[3], "*", &[("item", item)]).await.unwrap();
+        }
+
+        let out = std::env::temp_dir().join(format!("export_window_{}.ndjson", uuid::Uuid::new_v4()));
+        let options = ExportOptions { format: ExportFormat::Ndjson, batch_size: 4, ..ExportOptions::new("export_window:*", &out) };
+        assert_eq!(JsonExporter::new(client).run(&options).await.unwrap().keys, 4);
+
+        let text = std::fs::read_to_string(&out).unwrap();
+        let records: Vec<ExportedKey> = text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records.len(), 4);
+        for record in records {
+            assert_eq!(record.value.len(), 25, "{}", record.key);
+            if let KeyValue::List(list) = &record.value {
+                assert_eq!(list, &items);
+            }
+        }
+
+        std::fs::remove_file(out).unwrap();
+        let _: () = conn.del(&keys).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_array_parses_back() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.set("export_test:s", "v").await.unwrap();
+        let _: () = conn.hset("export_test:h", "f", "v").await.unwrap();
+        let _: () = conn.zadd("export_test:z", "m", 3).await.unwrap();
+
+        let out = std::env::temp_dir().join(format!("export_test_{}.json", uuid::Uuid::new_v4()));
+        let mut options = ExportOptions::new("export_test:*", &out);
+        options.memory_budget = 1;
+        let summary = JsonExporter::new(client).run(&options).await.unwrap();
+        assert_eq!(summary.keys, 3);
+
+        let mut records: Vec<ExportedKey> = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        records.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(records[0].key, "export_test:h");
+
+        std::fs::remove_file(out).unwrap();
+        let _: () = conn.del(&["export_test:s", "export_test:h", "export_test:z"]).await.unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+use super::record::RawKey;
+use crate::resp::Frame;
+use crate::utils::scan::KeyType;
+use crate::{DemoError, Result};
+use redis::Value;
+
+/// The bytes of a bulk or simple string reply, or of an integer as text,
+/// untouched: values don't have to be UTF-8.
+fn bytes(value: Value) -> Result<Vec<u8>> {
+    match value {
+        Value::Data(data) => Ok(data),
+        Value::Status(status) => Ok(status.into_bytes()),
+        Value::Int(n) => Ok(n.to_string().into_bytes()),
+        other => Err(DemoError::Demo(format!("Expected a bulk string, got {:?}", other))),
+    }
+}
+
+fn items(value: Value) -> Result<Vec<Value>> {
+    match value {
+        Value::Bulk(items) => Ok(items),
+        other => Err(DemoError::Demo(format!("Expected an array, got {:?}", other))),
+    }
+}
+
+/// The commands that recreate one key, in the protocol `redis-cli --pipe`
+/// reads: DEL, the type's write command with the raw values, and PEXPIRE
+/// when it has a TTL. Empty collections give nothing to write.
+pub fn restore_commands(raw: RawKey<'_>) -> Result<Vec<u8>> {
+    let commands = write_commands(raw.key, raw.key_type, raw.value)?;
+    if commands.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut out = del_command(raw.key);
+    out.extend(commands);
+    out.extend(expire_command(raw.key, raw.ttl_ms));
+    Ok(out)
+}
+
+pub fn del_command(key: &str) -> Vec<u8> {
+    Frame::command([b"DEL".as_slice(), key.as_bytes()]).to_bytes()
+}
+
+/// PEXPIRE for a key with a TTL; nothing for a persistent one.
+pub fn expire_command(key: &str, ttl_ms: Option<u64>) -> Vec<u8> {
+    ttl_ms.map_or_else(Vec::new, |ttl_ms| Frame::command(["PEXPIRE", key, ttl_ms.to_string().as_str()]).to_bytes())
+}
+
+/// The type's write commands for `value`, the whole of a key or one window
+/// of it, without the DEL before or the PEXPIRE after.
+pub fn write_commands(key_name: &str, key_type: KeyType, value: Value) -> Result<Vec<u8>> {
+    let key = key_name.as_bytes().to_vec();
+    let mut commands = Vec::new();
+    let mut write = |name: &str, args: Vec<Vec<u8>>| {
+        let mut command = vec![name.as_bytes().to_vec(), key.clone()];
+        command.extend(args);
+        commands.push(Frame::command(command));
+    };
+    match key_type {
+        KeyType::String => write("SET", vec![bytes(value)?]),
+        KeyType::Hash | KeyType::List | KeyType::Set => {
+            let values = items(value)?.into_iter().map(bytes).collect::<Result<Vec<_>>>()?;
+            let name = match key_type {
+                KeyType::Hash => "HSET",
+                KeyType::List => "RPUSH",
+                _ => "SADD",
+            };
+            if !values.is_empty() {
+                write(name, values);
+            }
+        }
+        KeyType::ZSet => {
+            // WITHSCORES replies member, score, ...; ZADD wants score, member
+            let flat = items(value)?.into_iter().map(bytes).collect::<Result<Vec<_>>>()?;
+            let swapped: Vec<Vec<u8>> = flat.chunks(2).flat_map(|pair| pair.iter().rev().cloned()).collect();
+            if !swapped.is_empty() {
+                write("ZADD", swapped);
+            }
+        }
+        KeyType::Stream => {
+            for entry in items(value)? {
+                let mut parts = items(entry)?.into_iter();
+                let (Some(id), Some(fields)) = (parts.next(), parts.next()) else {
+                    return Err(DemoError::Demo(format!("Malformed stream entry in {}", key_name)));
+                };
+                let mut args = vec![bytes(id)?];
+                for field in items(fields)? {
+                    args.push(bytes(field)?);
+                }
+                write("XADD", args);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    for command in &commands {
+        command.encode(&mut out);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resp::parse::parse_all;
+
+    fn commands(bytes: &[u8]) -> Vec<Vec<Vec<u8>>> {
+        parse_all(bytes).unwrap().iter().map(|frame| frame.as_command().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_binary_string_with_ttl() {
+        let value = vec![0, 255, b'\r', b'\n'];
+        let raw = RawKey { key: "bin", key_type: KeyType::String, ttl_ms: Some(1500), value: Value::Data(value.clone()), next: None };
+        let bytes = restore_commands(raw).unwrap();
+        assert!(bytes.starts_with(b"*2\r\n$3\r\nDEL\r\n$3\r\nbin\r\n*3\r\n$3\r\nSET\r\n$3\r\nbin\r\n$4\r\n\x00\xff\r\n\r\n"));
+        let parsed = commands(&bytes);
+        assert_eq!(parsed[1][2], value);
+        assert_eq!(parsed[2], [b"PEXPIRE".to_vec(), b"bin".to_vec(), b"1500".to_vec()]);
+    }
+
+    #[test]
+    fn test_collections() {
+        let data = |s: &str| Value::Data(s.as_bytes().to_vec());
+        let zset = RawKey { key: "z", key_type: KeyType::ZSet, ttl_ms: None, value: Value::Bulk(vec![data("amy"), data("1.5")]), next: None };
+        assert_eq!(commands(&restore_commands(zset).unwrap())[1], [b"ZADD".to_vec(), b"z".to_vec(), b"1.5".to_vec(), b"amy".to_vec()]);
+
+        let entry = Value::Bulk(vec![data("1-0"), Value::Bulk(vec![data("f"), data("v")])]);
+        let stream = RawKey { key: "s", key_type: KeyType::Stream, ttl_ms: None, value: Value::Bulk(vec![entry]), next: None };
+        let parsed = commands(&restore_commands(stream).unwrap());
+        assert_eq!(parsed[1], ["XADD", "s", "1-0", "f", "v"].map(|s| s.as_bytes().to_vec()));
+
+        let empty = RawKey { key: "h", key_type: KeyType::Hash, ttl_ms: None, value: Value::Bulk(Vec::new()), next: None };
+        assert!(restore_commands(empty).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,10 @@
+pub mod import;
+pub mod json;
+pub mod mass_insert;
+pub mod record;
+pub mod table;
+
+pub use import::{ImportFormat, ImportSummary, Importer};
+pub use json::{ExportOptions, ExportSummary, JsonExporter, ExportFormat};
+pub use table::{Column, Table, TableFormat};
+pub use record::{fetch_key, fetch_raw, ExportedKey, KeyValue, RawKey, StreamEntry};
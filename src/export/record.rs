@@ -0,0 +1,331 @@
+use crate::demos::streams::entry_fields;
+use crate::utils::bytes::{into_string, query_owned};
+use crate::utils::scan::KeyType;
+use crate::Result;
+use crate::utils::RedisConnection;
+use redis::streams::StreamRangeReply;
+use redis::{Cmd, FromRedisValue, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamEntry {
+    pub id: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+/// A key's value, tagged with its Redis type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum KeyValue {
+    String(String),
+    Hash(BTreeMap<String, String>),
+    List(Vec<String>),
+    /// Members sorted, so exports of the same data diff cleanly.
+    Set(Vec<String>),
+    /// `(member, score)` in score order.
+    ZSet(Vec<(String, f64)>),
+    Stream(Vec<StreamEntry>),
+}
+
+impl KeyValue {
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            KeyValue::String(_) => KeyType::String,
+            KeyValue::Hash(_) => KeyType::Hash,
+            KeyValue::List(_) => KeyType::List,
+            KeyValue::Set(_) => KeyType::Set,
+            KeyValue::ZSet(_) => KeyType::ZSet,
+            KeyValue::Stream(_) => KeyType::Stream,
+        }
+    }
+
+    /// Elements held: bytes for a string, fields, items, members or entries
+    /// otherwise.
+    pub fn len(&self) -> usize {
+        match self {
+            KeyValue::String(value) => value.len(),
+            KeyValue::Hash(fields) => fields.len(),
+            KeyValue::List(items) | KeyValue::Set(items) => items.len(),
+            KeyValue::ZSet(members) => members.len(),
+            KeyValue::Stream(entries) => entries.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// One exported key: `{"key": "...", "ttl_ms": 1200, "type": "hash", "value": {...}}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedKey {
+    pub key: String,
+    /// Remaining time to live; `None` for persistent keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_ms: Option<u64>,
+    #[serde(flatten)]
+    pub value: KeyValue,
+}
+
+pub(crate) fn decode_value(key_type: KeyType, value: &Value) -> Result<KeyValue> {
+    Ok(match key_type {
+        KeyType::String => KeyValue::String(String::from_redis_value(value)?),
+        KeyType::Hash => KeyValue::Hash(BTreeMap::from_redis_value(value)?),
+        KeyType::List => KeyValue::List(Vec::from_redis_value(value)?),
+        KeyType::Set => {
+            let mut members: Vec<String> = Vec::from_redis_value(value)?;
+            members.sort_unstable();
+            // SSCAN may return a member twice.
+            members.dedup();
+            KeyValue::Set(members)
+        }
+        KeyType::ZSet => KeyValue::ZSet(Vec::from_redis_value(value)?),
+        KeyType::Stream => KeyValue::Stream(
+            StreamRangeReply::from_redis_value(value)?
+                .ids
+                .iter()
+                .map(|entry| StreamEntry { id: entry.id.clone(), fields: entry_fields(entry) })
+                .collect(),
+        ),
+    })
+}
+
+/// Elements per read of a collection. Bigger ones take a round trip per
+/// window rather than one reply holding all of them.
+pub const WINDOW: usize = 1000;
+
+/// Where the next window of a collection picks up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resume {
+    /// LRANGE or ZRANGE from this index.
+    Index(usize),
+    /// HSCAN or SSCAN from this cursor.
+    Cursor(u64),
+    /// XRANGE after this entry id.
+    After(String),
+}
+
+/// Reads about `count` elements of `key` from `from`, the start when
+/// `None`. Strings are read whole.
+fn window_command(key: &str, key_type: KeyType, from: Option<&Resume>, count: usize) -> Cmd {
+    let index = match from {
+        Some(Resume::Index(index)) => *index,
+        _ => 0,
+    };
+    let cursor = match from {
+        Some(Resume::Cursor(cursor)) => *cursor,
+        _ => 0,
+    };
+    let mut cmd = match key_type {
+        KeyType::String => redis::cmd("GET"),
+        KeyType::List => redis::cmd("LRANGE"),
+        KeyType::ZSet => redis::cmd("ZRANGE"),
+        KeyType::Hash => redis::cmd("HSCAN"),
+        KeyType::Set => redis::cmd("SSCAN"),
+        KeyType::Stream => redis::cmd("XRANGE"),
+    };
+    cmd.arg(key);
+    match key_type {
+        KeyType::String => {}
+        KeyType::List => {
+            cmd.arg(index).arg(index + count - 1);
+        }
+        KeyType::ZSet => {
+            cmd.arg(index).arg(index + count - 1).arg("WITHSCORES");
+        }
+        KeyType::Hash | KeyType::Set => {
+            cmd.arg(cursor).arg("COUNT").arg(count);
+        }
+        KeyType::Stream => {
+            let start = match from {
+                Some(Resume::After(id)) => format!("({}", id),
+                _ => "-".to_string(),
+            };
+            cmd.arg(start).arg("+").arg("COUNT").arg(count);
+        }
+    }
+    cmd
+}
+
+/// Splits a window's reply into its elements, shaped like the reply of
+/// the full read (HGETALL, SMEMBERS, ...), and where the next window starts.
+fn split_window(key_type: KeyType, from: Option<&Resume>, count: usize, reply: Value) -> Result<(Value, Option<Resume>)> {
+    let next = match (key_type, &reply) {
+        (KeyType::List | KeyType::ZSet, Value::Bulk(items)) => {
+            let start = match from {
+                Some(Resume::Index(index)) => *index,
+                _ => 0,
+            };
+            let per_element = if key_type == KeyType::ZSet { 2 } else { 1 };
+            (items.len() / per_element >= count).then_some(Resume::Index(start + count))
+        }
+        (KeyType::Hash | KeyType::Set, _) => {
+            let (cursor, elements): (u64, Value) = FromRedisValue::from_redis_value(&reply)?;
+            return Ok((elements, (cursor != 0).then_some(Resume::Cursor(cursor))));
+        }
+        (KeyType::Stream, Value::Bulk(entries)) if entries.len() >= count => match entries.last() {
+            Some(Value::Bulk(entry)) => Some(Resume::After(String::from_redis_value(&entry[0])?)),
+            _ => None,
+        },
+        _ => None,
+    };
+    Ok((reply, next))
+}
+
+/// One key as the server returned it: its type, remaining TTL and the
+/// undecoded reply of the type's read command.
+pub struct RawKey<'a> {
+    pub key: &'a str,
+    pub key_type: KeyType,
+    pub ttl_ms: Option<u64>,
+    /// The first window of a collection; all of it unless `next` is set.
+    pub value: Value,
+    /// Where the rest starts, for [`fetch_window`].
+    pub next: Option<Resume>,
+}
+
+/// Reads a batch of keys in two pipelined round trips: TYPE and PTTL for
+/// all keys, then the first `window` elements of each (strings whole).
+/// Keys that disappeared in between, or have a type this crate does not
+/// export, are left out.
+pub async fn fetch_raw<'a>(conn: &mut RedisConnection, keys: &'a [String], window: usize) -> Result<Vec<RawKey<'a>>> {
+    let mut pipe = redis::pipe();
+    for key in keys {
+        pipe.cmd("TYPE").arg(key).cmd("PTTL").arg(key);
+    }
+    let meta: Vec<(String, i64)> = pipe.query_async(conn).await?;
+
+    let mut typed = Vec::with_capacity(keys.len());
+    let mut pipe = redis::pipe();
+    for (key, (type_name, pttl)) in keys.iter().zip(meta) {
+        let Ok(key_type) = type_name.parse::<KeyType>() else {
+            continue;
+        };
+        pipe.add_command(window_command(key, key_type, None, window));
+        typed.push((key, key_type, (pttl >= 0).then_some(pttl as u64)));
+    }
+    if typed.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Owned replies, so big string values are moved rather than copied.
+    let values = query_owned(conn, &pipe).await?;
+
+    let mut raw = Vec::with_capacity(typed.len());
+    for ((key, key_type, ttl_ms), reply) in typed.into_iter().zip(values) {
+        // Nil: deleted between the two round trips.
+        if reply == Value::Nil {
+            continue;
+        }
+        let (value, next) = split_window(key_type, None, window, reply)?;
+        raw.push(RawKey { key, key_type, ttl_ms, value, next });
+    }
+    Ok(raw)
+}
+
+/// The `count` elements of `key` after `from`, where [`fetch_raw`] or the
+/// previous window stopped, and where the one after starts. Keys written
+/// to in between may have elements skipped or repeated, as with SCAN.
+pub async fn fetch_window(conn: &mut RedisConnection, key: &str, key_type: KeyType, from: &Resume, count: usize) -> Result<(Value, Option<Resume>)> {
+    let reply: Value = window_command(key, key_type, Some(from), count).query_async(conn).await?;
+    split_window(key_type, Some(from), count, reply)
+}
+
+/// A fetched key decoded as it stands: a [`RawKey`] with `next` set holds
+/// only its first window.
+pub fn decode(raw: RawKey<'_>) -> Result<ExportedKey> {
+    let value = match raw.key_type {
+        KeyType::String => KeyValue::String(into_string(raw.value)?.unwrap_or_default()),
+        key_type => decode_value(key_type, &raw.value)?,
+    };
+    Ok(ExportedKey { key: raw.key.to_string(), ttl_ms: raw.ttl_ms, value })
+}
+
+/// [`fetch_raw`] decoded into [`ExportedKey`]s, reading every window of the
+/// big ones.
+pub async fn fetch_batch(conn: &mut RedisConnection, keys: &[String]) -> Result<Vec<ExportedKey>> {
+    let mut exported = Vec::with_capacity(keys.len());
+    for mut raw in fetch_raw(conn, keys, WINDOW).await? {
+        while let Some(from) = raw.next.take() {
+            let (more, next) = fetch_window(conn, raw.key, raw.key_type, &from, WINDOW).await?;
+            if let (Value::Bulk(elements), Value::Bulk(more)) = (&mut raw.value, more) {
+                elements.extend(more);
+            }
+            raw.next = next;
+        }
+        exported.push(decode(raw)?);
+    }
+    Ok(exported)
+}
+
+/// One key through [`fetch_batch`]; `None` if it doesn't exist or has a
+/// type this crate doesn't read.
+pub async fn fetch_key(conn: &mut RedisConnection, key: &str) -> Result<Option<ExportedKey>> {
+    Ok(fetch_batch(conn, &[key.to_string()]).await?.pop())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_json_shape() {
+        let record = ExportedKey {
+            key: "user:1".to_string(),
+            ttl_ms: None,
+            value: KeyValue::Hash([("name".to_string(), "Ada".to_string())].into_iter().collect()),
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(json, r#"{"key":"user:1","type":"hash","value":{"name":"Ada"}}"#);
+        assert_eq!(serde_json::from_str::<ExportedKey>(&json).unwrap(), record);
+
+        let zset = ExportedKey { key: "scores".to_string(), ttl_ms: Some(500), value: KeyValue::ZSet(vec![("a".to_string(), 1.5)]) };
+        let json = serde_json::to_string(&zset).unwrap();
+        assert_eq!(json, r#"{"key":"scores","ttl_ms":500,"type":"zset","value":[["a",1.5]]}"#);
+        assert_eq!(serde_json::from_str::<ExportedKey>(&json).unwrap().value.key_type(), KeyType::ZSet);
+    }
+
+    #[test]
+    fn test_decode_value() {
+        let members = Value::Bulk(vec![Value::Data(b"b".to_vec()), Value::Data(b"a".to_vec())]);
+        assert_eq!(decode_value(KeyType::Set, &members).unwrap(), KeyValue::Set(vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(
+            decode_value(KeyType::List, &members).unwrap(),
+            KeyValue::List(vec!["b".to_string(), "a".to_string()])
+        );
+        let scored = Value::Bulk(vec![Value::Data(b"m".to_vec()), Value::Data(b"2".to_vec())]);
+        assert_eq!(decode_value(KeyType::ZSet, &scored).unwrap(), KeyValue::ZSet(vec![("m".to_string(), 2.0)]));
+    }
+
+    #[test]
+    fn test_windows() {
+        let data = |s: &str| Value::Data(s.as_bytes().to_vec());
+        let args = |cmd: Cmd| {
+            cmd.args_iter()
+                .map(|arg| match arg {
+                    redis::Arg::Simple(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+                    redis::Arg::Cursor => "*".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        assert_eq!(args(window_command("l", KeyType::List, Some(&Resume::Index(4)), 2)), "LRANGE l 4 5");
+        assert_eq!(args(window_command("h", KeyType::Hash, None, 2)), "HSCAN h 0 COUNT 2");
+        let after = Resume::After("1-0".to_string());
+        assert_eq!(args(window_command("s", KeyType::Stream, Some(&after), 2)), "XRANGE s (1-0 + COUNT 2");
+
+        let full = Value::Bulk(vec![data("a"), data("b")]);
+        assert_eq!(split_window(KeyType::List, None, 2, full.clone()).unwrap().1, Some(Resume::Index(2)));
+        assert_eq!(split_window(KeyType::List, Some(&Resume::Index(2)), 3, full.clone()).unwrap().1, None);
+        assert_eq!(split_window(KeyType::ZSet, None, 1, full.clone()).unwrap().1, Some(Resume::Index(1)));
+
+        let scan = Value::Bulk(vec![data("17"), full.clone()]);
+        assert_eq!(split_window(KeyType::Set, None, 10, scan).unwrap(), (full.clone(), Some(Resume::Cursor(17))));
+        let last = Value::Bulk(vec![data("0"), full.clone()]);
+        assert_eq!(split_window(KeyType::Hash, None, 10, last).unwrap(), (full, None));
+
+        let entry = Value::Bulk(vec![data("5-1"), Value::Bulk(vec![data("f"), data("v")])]);
+        let entries = Value::Bulk(vec![entry]);
+        assert_eq!(split_window(KeyType::Stream, None, 1, entries).unwrap().1, Some(Resume::After("5-1".to_string())));
+    }
+}
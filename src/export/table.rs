@@ -0,0 +1,221 @@
+use super::record::{ExportedKey, KeyValue, StreamEntry};
+use crate::{DemoError, Result};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    /// Typed columns; needs the `parquet` feature.
+    Parquet,
+}
+
+impl FromStr for TableFormat {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(TableFormat::Csv),
+            "parquet" if cfg!(feature = "parquet") => Ok(TableFormat::Parquet),
+            "parquet" => Err(DemoError::Configuration("Parquet export needs a build with --features parquet".to_string())),
+            other => Err(DemoError::Configuration(format!("Unknown table format: {} (use csv or parquet)", other))),
+        }
+    }
+}
+
+impl TableFormat {
+    /// From the output file's extension, CSV unless it ends in `.parquet`.
+    pub fn for_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("parquet") => "parquet".parse(),
+            _ => Ok(TableFormat::Csv),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Int(Vec<i64>),
+    Float(Vec<f64>),
+    /// `None` where a stream entry doesn't have the field.
+    Text(Vec<Option<String>>),
+}
+
+impl Column {
+    fn cell(&self, row: usize) -> String {
+        match self {
+            Column::Int(values) => values[row].to_string(),
+            Column::Float(values) => values[row].to_string(),
+            Column::Text(values) => values[row].as_deref().map(csv_field).unwrap_or_default(),
+        }
+    }
+}
+
+/// Quotes a field when it holds a comma, quote or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+/// A sorted set or stream as named columns of one length, ready for a
+/// spreadsheet or a dataframe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub names: Vec<String>,
+    pub columns: Vec<Column>,
+}
+
+impl Table {
+    /// `rank, member, score`, lowest score first, or highest with `descending`
+    /// as a leaderboard reads. Rank starts at 1.
+    pub fn from_zset(members: &[(String, f64)], descending: bool) -> Self {
+        let mut ordered: Vec<&(String, f64)> = members.iter().collect();
+        if descending {
+            ordered.reverse();
+        }
+        Self {
+            names: ["rank", "member", "score"].map(String::from).to_vec(),
+            columns: vec![
+                Column::Int((1..=ordered.len() as i64).collect()),
+                Column::Text(ordered.iter().map(|(member, _)| Some(member.clone())).collect()),
+                Column::Float(ordered.iter().map(|(_, score)| *score).collect()),
+            ],
+        }
+    }
+
+    /// `id, timestamp_ms`, then one column per field name seen in any entry.
+    pub fn from_stream(entries: &[StreamEntry]) -> Self {
+        let fields: BTreeSet<&String> = entries.iter().flat_map(|entry| entry.fields.keys()).collect();
+        let mut names = vec!["id".to_string(), "timestamp_ms".to_string()];
+        let mut columns = vec![
+            Column::Text(entries.iter().map(|entry| Some(entry.id.clone())).collect()),
+            Column::Int(entries.iter().map(|entry| entry.id.split('-').next().and_then(|ms| ms.parse().ok()).unwrap_or(0)).collect()),
+        ];
+        for field in fields {
+            names.push(field.clone());
+            columns.push(Column::Text(entries.iter().map(|entry| entry.fields.get(field).cloned()).collect()));
+        }
+        Self { names, columns }
+    }
+
+    pub fn from_key(key: &ExportedKey, descending: bool) -> Result<Self> {
+        match &key.value {
+            KeyValue::ZSet(members) => Ok(Self::from_zset(members, descending)),
+            KeyValue::Stream(entries) => Ok(Self::from_stream(entries)),
+            other => Err(DemoError::Configuration(format!(
+                "{} is a {}; only sorted sets and streams export as tables",
+                key.key,
+                other.key_type()
+            ))),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        match self.columns.first() {
+            Some(Column::Int(values)) => values.len(),
+            Some(Column::Float(values)) => values.len(),
+            Some(Column::Text(values)) => values.len(),
+            None => 0,
+        }
+    }
+
+    /// A header line, then one line per row; missing fields are empty.
+    pub fn render_csv(&self) -> String {
+        let header: Vec<String> = self.names.iter().map(|name| csv_field(name)).collect();
+        let mut out = header.join(",");
+        out.push('\n');
+        for row in 0..self.rows() {
+            let cells: Vec<String> = self.columns.iter().map(|column| column.cell(row)).collect();
+            let _ = writeln!(out, "{}", cells.join(","));
+        }
+        out
+    }
+
+    pub fn write(&self, path: &Path, format: TableFormat) -> Result<()> {
+        match format {
+            TableFormat::Csv => Ok(std::fs::write(path, self.render_csv())?),
+            #[cfg(feature = "parquet")]
+            TableFormat::Parquet => self.write_parquet(path),
+            #[cfg(not(feature = "parquet"))]
+            TableFormat::Parquet => "parquet".parse::<TableFormat>().map(|_| ()),
+        }
+    }
+
+    /// One row group with Int64, Float64 and nullable Utf8 columns.
+    #[cfg(feature = "parquet")]
+    fn write_parquet(&self, path: &Path) -> Result<()> {
+        use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray};
+        use arrow_schema::{DataType, Field, Schema};
+        use std::sync::Arc;
+
+        let parquet_error = |e: &dyn std::fmt::Display| DemoError::Demo(format!("Parquet export failed: {}", e));
+        let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = self
+            .names
+            .iter()
+            .zip(&self.columns)
+            .map(|(name, column)| match column {
+                Column::Int(values) => (Field::new(name, DataType::Int64, false), Arc::new(Int64Array::from(values.clone())) as ArrayRef),
+                Column::Float(values) => (Field::new(name, DataType::Float64, false), Arc::new(Float64Array::from(values.clone())) as ArrayRef),
+                Column::Text(values) => (Field::new(name, DataType::Utf8, true), Arc::new(StringArray::from(values.clone())) as ArrayRef),
+            })
+            .unzip();
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).map_err(|e| parquet_error(&e))?;
+        let mut writer = parquet::arrow::ArrowWriter::try_new(std::fs::File::create(path)?, batch.schema(), None).map_err(|e| parquet_error(&e))?;
+        writer.write(&batch).map_err(|e| parquet_error(&e))?;
+        writer.close().map_err(|e| parquet_error(&e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, fields: &[(&str, &str)]) -> StreamEntry {
+        StreamEntry { id: id.to_string(), fields: fields.iter().map(|(f, v)| (f.to_string(), v.to_string())).collect() }
+    }
+
+    #[test]
+    fn test_zset_as_leaderboard_csv() {
+        let members = vec![("bo".to_string(), 3.0), ("amy, jr".to_string(), 7.5)];
+        let table = Table::from_zset(&members, true);
+        assert_eq!(table.render_csv(), "rank,member,score\n1,\"amy, jr\",7.5\n2,bo,3\n");
+    }
+
+    #[test]
+    fn test_stream_columns_are_the_union_of_fields() {
+        let entries = [entry("1700000000000-0", &[("user", "1"), ("action", "login")]), entry("1700000000001-0", &[("user", "2"), ("note", "say \"hi\"")])];
+        let table = Table::from_stream(&entries);
+        assert_eq!(
+            table.render_csv(),
+            "id,timestamp_ms,action,note,user\n1700000000000-0,1700000000000,login,,1\n1700000000001-0,1700000000001,,\"say \"\"hi\"\"\",2\n"
+        );
+        let key = ExportedKey { key: "h".to_string(), ttl_ms: None, value: KeyValue::Hash(Default::default()) };
+        assert!(Table::from_key(&key, false).unwrap_err().to_string().contains("h is a hash"));
+    }
+
+    #[test]
+    fn test_format_for_path() {
+        assert_eq!(TableFormat::for_path(Path::new("scores.csv")).unwrap(), TableFormat::Csv);
+        assert_eq!(TableFormat::for_path(Path::new("scores.parquet")).is_ok(), cfg!(feature = "parquet"));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_round_trip() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let path = std::env::temp_dir().join(format!("table_test_{}.parquet", uuid::Uuid::new_v4()));
+        let table = Table::from_zset(&[("amy".to_string(), 1.5), ("bo".to_string(), 2.0)], false);
+        table.write(&path, TableFormat::Parquet).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap()).unwrap().build().unwrap();
+        let batches: Vec<_> = reader.collect::<std::result::Result<_, _>>().unwrap();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0].schema().field(2).name(), "score");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,212 @@
+use crate::utils::config_watch::encode_fields;
+use crate::utils::scan::KeyType;
+use crate::utils::RedisConnection;
+use crate::{DemoError, Result};
+use redis::AsyncCommands;
+use serde_json::Value;
+use std::time::Duration;
+
+/// One type-aware change to a key, checked against the key's current type
+/// before anything is sent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyEdit {
+    SetString(String),
+    SetFields(Vec<(String, String)>),
+    Push { values: Vec<String>, left: bool },
+    Insert { pivot: String, value: String, before: bool },
+    AddMembers(Vec<(String, f64)>),
+    /// `None` removes the TTL.
+    Expire(Option<Duration>),
+}
+
+fn invalid(message: String) -> DemoError {
+    DemoError::Configuration(message)
+}
+
+fn parse_json(json: &str) -> Result<Value> {
+    serde_json::from_str(json).map_err(|e| invalid(format!("--json is not valid JSON: {}", e)))
+}
+
+impl KeyEdit {
+    /// A string value; with `json`, it must parse and is stored compacted.
+    pub fn string(value: &str, json: bool) -> Result<Self> {
+        match json {
+            true => Ok(KeyEdit::SetString(parse_json(value)?.to_string())),
+            false => Ok(KeyEdit::SetString(value.to_string())),
+        }
+    }
+
+    /// Hash fields from a JSON object: strings stored raw, anything else as
+    /// JSON.
+    pub fn fields_from_json(json: &str) -> Result<Self> {
+        let value = parse_json(json)?;
+        if !value.is_object() {
+            return Err(invalid("Hash fields need a JSON object, e.g. {\"name\": \"Amy\"}".to_string()));
+        }
+        KeyEdit::SetFields(encode_fields(&value)?).validated()
+    }
+
+    /// List items from a JSON array, encoded like hash fields.
+    pub fn push_from_json(json: &str, left: bool) -> Result<Self> {
+        let Value::Array(items) = parse_json(json)? else {
+            return Err(invalid("List items need a JSON array, e.g. [\"a\", \"b\"]".to_string()));
+        };
+        let values = items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(text) => text,
+                other => other.to_string(),
+            })
+            .collect();
+        KeyEdit::Push { values, left }.validated()
+    }
+
+    /// Sorted set members from a JSON object of member to score.
+    pub fn members_from_json(json: &str) -> Result<Self> {
+        let Value::Object(members) = parse_json(json)? else {
+            return Err(invalid("Members need a JSON object of member to score, e.g. {\"amy\": 10}".to_string()));
+        };
+        let members = members
+            .into_iter()
+            .map(|(member, score)| match score.as_f64() {
+                Some(score) => Ok((member, score)),
+                None => Err(invalid(format!("Score for '{}' is not a number: {}", member, score))),
+            })
+            .collect::<Result<_>>()?;
+        KeyEdit::AddMembers(members).validated()
+    }
+
+    /// Checks what doesn't depend on the key: something to write, finite
+    /// scores and a positive TTL.
+    pub fn validated(self) -> Result<Self> {
+        match &self {
+            KeyEdit::SetFields(fields) if fields.is_empty() => Err(invalid("No fields to set".to_string())),
+            KeyEdit::Push { values, .. } if values.is_empty() => Err(invalid("No items to push".to_string())),
+            KeyEdit::AddMembers(members) if members.is_empty() => Err(invalid("No members to add".to_string())),
+            KeyEdit::AddMembers(members) => match members.iter().find(|(_, score)| !score.is_finite()) {
+                Some((member, score)) => Err(invalid(format!("Score for '{}' must be a finite number, not {}", member, score))),
+                None => Ok(self),
+            },
+            KeyEdit::Expire(Some(ttl)) if ttl.is_zero() => Err(invalid("A TTL must be positive; use `ttl --persist` to remove one".to_string())),
+            _ => Ok(self),
+        }
+    }
+
+    /// The type the key has afterwards; `None` for a TTL change.
+    pub fn key_type(&self) -> Option<KeyType> {
+        match self {
+            KeyEdit::SetString(_) => Some(KeyType::String),
+            KeyEdit::SetFields(_) => Some(KeyType::Hash),
+            KeyEdit::Push { .. } | KeyEdit::Insert { .. } => Some(KeyType::List),
+            KeyEdit::AddMembers(_) => Some(KeyType::ZSet),
+            KeyEdit::Expire(_) => None,
+        }
+    }
+
+    /// Refuses edits that would fail with WRONGTYPE, or that need a key
+    /// that isn't there. Setting a string replaces any value, like SET.
+    pub fn check(&self, key: &str, current: Option<KeyType>) -> Result<()> {
+        match (self, current) {
+            (KeyEdit::Insert { .. } | KeyEdit::Expire(_), None) => Err(invalid(format!("{} does not exist", key))),
+            (KeyEdit::SetString(_), _) | (KeyEdit::Expire(_), _) | (_, None) => Ok(()),
+            (edit, Some(current)) if edit.key_type() == Some(current) => Ok(()),
+            (edit, Some(current)) => Err(invalid(format!(
+                "{} holds a {}, so it can't take a {} change; delete it first or pick another key",
+                key,
+                current,
+                edit.key_type().map_or("TTL", |kind| kind.as_str())
+            ))),
+        }
+    }
+
+    /// What the edit does, for the confirmation prompt.
+    pub fn describe(&self, key: &str) -> String {
+        match self {
+            KeyEdit::SetString(value) => format!("set {} to a {}-byte string", key, value.len()),
+            KeyEdit::SetFields(fields) => format!("set {} field(s) on {}", fields.len(), key),
+            KeyEdit::Push { values, left } => format!("push {} item(s) onto the {} of {}", values.len(), if *left { "head" } else { "tail" }, key),
+            KeyEdit::Insert { pivot, before, .. } => format!("insert an item {} '{}' in {}", if *before { "before" } else { "after" }, pivot, key),
+            KeyEdit::AddMembers(members) => format!("add or rescore {} member(s) of {}", members.len(), key),
+            KeyEdit::Expire(Some(ttl)) => format!("expire {} in {}s", key, ttl.as_secs()),
+            KeyEdit::Expire(None) => format!("remove the TTL of {}", key),
+        }
+    }
+
+    pub async fn apply(&self, conn: &mut RedisConnection, key: &str) -> Result<()> {
+        match self {
+            KeyEdit::SetString(value) => conn.set(key, value).await?,
+            KeyEdit::SetFields(fields) => conn.hset_multiple(key, fields).await?,
+            KeyEdit::Push { values, left: true } => conn.lpush(key, values).await?,
+            KeyEdit::Push { values, left: false } => conn.rpush(key, values).await?,
+            KeyEdit::Insert { pivot, value, before } => {
+                let length: i64 = match before {
+                    true => conn.linsert_before(key, pivot, value).await?,
+                    false => conn.linsert_after(key, pivot, value).await?,
+                };
+                if length < 0 {
+                    return Err(invalid(format!("'{}' is not an item of {}", pivot, key)));
+                }
+            }
+            KeyEdit::AddMembers(members) => {
+                let scored: Vec<(f64, &str)> = members.iter().map(|(member, score)| (*score, member.as_str())).collect();
+                conn.zadd_multiple(key, &scored).await?
+            }
+            KeyEdit::Expire(Some(ttl)) => conn.pexpire(key, ttl.as_millis() as i64).await?,
+            KeyEdit::Expire(None) => conn.persist(key).await?,
+        }
+        Ok(())
+    }
+}
+
+/// The key's current type, `None` if it doesn't exist.
+pub async fn current_type(conn: &mut RedisConnection, key: &str) -> Result<Option<KeyType>> {
+    let kind: String = redis::cmd("TYPE").arg(key).query_async(conn).await?;
+    match kind.as_str() {
+        "none" => Ok(None),
+        kind => kind.parse().map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_inputs() {
+        assert_eq!(KeyEdit::string(r#"{ "a": 1 }"#, true).unwrap(), KeyEdit::SetString(r#"{"a":1}"#.to_string()));
+        assert!(KeyEdit::string("{oops", true).unwrap_err().to_string().contains("not valid JSON"));
+        assert_eq!(
+            KeyEdit::fields_from_json(r#"{"name": "Amy", "age": 30}"#).unwrap(),
+            KeyEdit::SetFields(vec![("age".to_string(), "30".to_string()), ("name".to_string(), "Amy".to_string())])
+        );
+        assert!(KeyEdit::fields_from_json("[1]").is_err());
+        assert_eq!(
+            KeyEdit::push_from_json(r#"["a", 2]"#, true).unwrap(),
+            KeyEdit::Push { values: vec!["a".to_string(), "2".to_string()], left: true }
+        );
+        assert_eq!(KeyEdit::members_from_json(r#"{"amy": 1.5}"#).unwrap(), KeyEdit::AddMembers(vec![("amy".to_string(), 1.5)]));
+        assert!(KeyEdit::members_from_json(r#"{"amy": "high"}"#).unwrap_err().to_string().contains("not a number"));
+        assert!(KeyEdit::members_from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_validated() {
+        assert!(KeyEdit::AddMembers(vec![("m".to_string(), f64::NAN)]).validated().is_err());
+        assert!(KeyEdit::Expire(Some(Duration::ZERO)).validated().is_err());
+        assert!(KeyEdit::Expire(None).validated().is_ok());
+    }
+
+    #[test]
+    fn test_check_against_current_type() {
+        let fields = KeyEdit::SetFields(vec![("f".to_string(), "v".to_string())]);
+        assert!(fields.check("k", None).is_ok());
+        assert!(fields.check("k", Some(KeyType::Hash)).is_ok());
+        let wrong = fields.check("k", Some(KeyType::List)).unwrap_err().to_string();
+        assert!(wrong.contains("k holds a list, so it can't take a hash change"), "{}", wrong);
+        assert!(KeyEdit::SetString("v".to_string()).check("k", Some(KeyType::ZSet)).is_ok());
+        assert!(KeyEdit::Expire(None).check("k", None).unwrap_err().to_string().contains("does not exist"));
+        let insert = KeyEdit::Insert { pivot: "a".to_string(), value: "b".to_string(), before: true };
+        assert!(insert.check("k", None).is_err());
+        assert!(insert.check("k", Some(KeyType::List)).is_ok());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::export::record::{fetch_key, ExportedKey, KeyValue};
+use crate::server::fleet::format_bytes;
+use crate::utils::RedisConnection;
+use crate::Result;
+use std::fmt::Write;
+
+/// A key's value plus what the server says about how it's stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyDetails {
+    pub key: ExportedKey,
+    /// OBJECT ENCODING, e.g. `listpack` or `hashtable`.
+    pub encoding: Option<String>,
+    /// MEMORY USAGE in bytes.
+    pub memory: Option<u64>,
+}
+
+impl KeyDetails {
+    /// The value by type, then the metadata. Servers that don't answer
+    /// OBJECT ENCODING or MEMORY USAGE just leave those out.
+    pub async fn fetch(conn: &mut RedisConnection, key: &str) -> Result<Option<Self>> {
+        let Some(exported) = fetch_key(conn, key).await? else {
+            return Ok(None);
+        };
+        let encoding = redis::cmd("OBJECT").arg("ENCODING").arg(key).query_async(conn).await.ok().flatten();
+        let memory = redis::cmd("MEMORY").arg("USAGE").arg(key).query_async(conn).await.ok().flatten();
+        Ok(Some(Self { key: exported, encoding, memory }))
+    }
+
+    /// A metadata header, then up to `limit` elements.
+    pub fn render(&self, limit: usize) -> String {
+        let value = &self.key.value;
+        let unit = match value {
+            KeyValue::String(_) => "bytes",
+            KeyValue::Hash(_) => "fields",
+            KeyValue::List(_) => "items",
+            KeyValue::Set(_) | KeyValue::ZSet(_) => "members",
+            KeyValue::Stream(_) => "entries",
+        };
+        let mut meta = vec![value.key_type().to_string(), format!("{} {}", value.len(), unit)];
+        meta.extend(self.encoding.as_ref().map(|encoding| format!("encoding {}", encoding)));
+        meta.extend(self.memory.map(|bytes| format!("{} in memory", format_bytes(bytes))));
+        meta.push(match self.key.ttl_ms {
+            Some(ms) => format!("expires in {}", format_ttl(ms)),
+            None => "no TTL".to_string(),
+        });
+
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", self.key.key);
+        let _ = writeln!(out, "   {}\n", meta.join("  ·  "));
+        let lines: Vec<String> = match value {
+            KeyValue::String(text) => pretty_string(text).lines().map(str::to_string).collect(),
+            KeyValue::Hash(fields) => aligned(fields.iter().map(|(field, value)| (field.clone(), value.clone()))),
+            KeyValue::List(items) => aligned(items.iter().enumerate().map(|(i, item)| (i.to_string(), item.clone()))),
+            KeyValue::Set(members) => members.clone(),
+            KeyValue::ZSet(members) => aligned(members.iter().map(|(member, score)| (score.to_string(), member.clone()))),
+            KeyValue::Stream(entries) => entries
+                .iter()
+                .map(|entry| {
+                    let fields: Vec<_> = entry.fields.iter().map(|(field, value)| format!("{}={}", field, value)).collect();
+                    format!("{}  {}", entry.id, fields.join(" "))
+                })
+                .collect(),
+        };
+        let shown = match value {
+            // A string is one value however many lines it pretty-prints to.
+            KeyValue::String(_) => lines.len(),
+            _ => lines.len().min(limit),
+        };
+        for line in &lines[..shown] {
+            let _ = writeln!(out, "   {}", line);
+        }
+        if shown < lines.len() {
+            let _ = writeln!(out, "   … {} more", lines.len() - shown);
+        }
+        out
+    }
+}
+
+/// JSON objects and arrays indented; anything else as it is.
+pub fn pretty_string(text: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(json @ (serde_json::Value::Object(_) | serde_json::Value::Array(_))) => serde_json::to_string_pretty(&json).unwrap_or_else(|_| text.to_string()),
+        _ => text.to_string(),
+    }
+}
+
+/// `label  value` with the labels padded to the widest.
+fn aligned(rows: impl Iterator<Item = (String, String)>) -> Vec<String> {
+    let rows: Vec<(String, String)> = rows.collect();
+    let width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    rows.into_iter().map(|(label, value)| format!("{:<width$}  {}", label, value, width = width)).collect()
+}
+
+fn format_ttl(ms: u64) -> String {
+    let secs = ms / 1000;
+    match secs {
+        0..=59 => format!("{:.1}s", ms as f64 / 1000.0),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        3600..=86399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(value: KeyValue, ttl_ms: Option<u64>) -> KeyDetails {
+        KeyDetails { key: ExportedKey { key: "k".to_string(), ttl_ms, value }, encoding: Some("listpack".to_string()), memory: Some(2048) }
+    }
+
+    #[test]
+    fn test_pretty_string() {
+        assert_eq!(pretty_string(r#"{"a":[1,2]}"#), "{\n  \"a\": [\n    1,\n    2\n  ]\n}");
+        assert_eq!(pretty_string("42"), "42");
+        assert_eq!(pretty_string("plain text"), "plain text");
+    }
+
+    #[test]
+    fn test_render_hash_with_metadata() {
+        let fields = [("name", "Amy"), ("age", "30")].into_iter().map(|(f, v)| (f.to_string(), v.to_string())).collect();
+        let rendered = details(KeyValue::Hash(fields), Some(90_000)).render(10);
+        assert_eq!(rendered, "k\n   hash  ·  2 fields  ·  encoding listpack  ·  2.0 KiB in memory  ·  expires in 1m 30s\n\n   age   30\n   name  Amy\n");
+    }
+
+    #[test]
+    fn test_render_limits_collections_but_not_strings() {
+        let items = (0..5).map(|i| i.to_string()).collect();
+        let rendered = details(KeyValue::List(items), None).render(2);
+        assert!(rendered.contains("no TTL"));
+        assert!(rendered.ends_with("   0  0\n   1  1\n   … 3 more\n"), "{}", rendered);
+
+        let rendered = details(KeyValue::String(r#"{"a":1,"b":2}"#.to_string()), None).render(1);
+        assert!(rendered.ends_with("   {\n     \"a\": 1,\n     \"b\": 2\n   }\n"), "{}", rendered);
+    }
+}
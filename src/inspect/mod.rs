@@ -0,0 +1,7 @@
+pub mod edit;
+pub mod key;
+pub mod wire;
+
+pub use edit::KeyEdit;
+pub use key::KeyDetails;
+pub use wire::{annotate, encode_command, render, Exchange, WireInspector, WireLine};
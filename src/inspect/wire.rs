@@ -0,0 +1,256 @@
+use crate::resp::{Frame, ParseError};
+use crate::{DemoError, Result};
+use redis::{ConnectionAddr, ConnectionInfo};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Payload bytes shown per bulk string before the rest is summarized.
+const SHOWN_BYTES: usize = 64;
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Encodes a command the way every client sends it: an array of bulk
+/// strings, whatever the argument types.
+pub fn encode_command(args: &[String]) -> Vec<u8> {
+    Frame::command(args).to_bytes()
+}
+
+/// One line of annotated protocol: the raw bytes, escaped, and what they
+/// mean. `depth` is the nesting level inside arrays.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WireLine {
+    pub depth: usize,
+    pub raw: String,
+    pub note: String,
+}
+
+/// `GET\r\n` rather than an unreadable mix of text and control bytes.
+fn escape(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for &b in bytes {
+        match b {
+            b'\r' => out.push_str("\\r"),
+            b'\n' => out.push_str("\\n"),
+            b'\\' => out.push_str("\\\\"),
+            0x20..=0x7e => out.push(b as char),
+            _ => out.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    out
+}
+
+fn plural(n: usize, one: &str) -> String {
+    format!("{} {}{}", n, one, if n == 1 { "" } else { "s" })
+}
+
+/// The header line of a length-prefixed frame, e.g. `$5\r\n`.
+fn header(frame: &Frame, len: usize) -> String {
+    escape(format!("{}{}\r\n", frame.prefix() as char, len).as_bytes())
+}
+
+/// The payload line of a bulk frame, cut short past [`SHOWN_BYTES`].
+fn payload(depth: usize, bytes: &[u8], lines: &mut Vec<WireLine>) {
+    let (shown, note) = match bytes.len() > SHOWN_BYTES {
+        true => (&bytes[..SHOWN_BYTES], format!("payload (first {} of {} bytes)", SHOWN_BYTES, bytes.len())),
+        false => (bytes, "payload".to_string()),
+    };
+    let mut raw = escape(shown);
+    if bytes.len() > SHOWN_BYTES {
+        raw.push('…');
+    }
+    raw.push_str("\\r\\n");
+    lines.push(WireLine { depth, raw, note });
+}
+
+/// Appends a line per header and payload of `frame` and its children.
+fn walk(frame: &Frame, depth: usize, lines: &mut Vec<WireLine>) {
+    let mut line = |raw: String, note: String| lines.push(WireLine { depth, raw, note });
+    let items = match frame {
+        Frame::Bulk(bytes) | Frame::BulkError(bytes) => {
+            line(header(frame, bytes.len()), format!("{}, {}", frame.type_name(), plural(bytes.len(), "byte")));
+            return payload(depth, bytes, lines);
+        }
+        Frame::Verbatim { format, text } => {
+            let len = format.len() + 1 + text.len();
+            line(header(frame, len), format!("{} ({}), {}", frame.type_name(), format, plural(len, "byte")));
+            let mut bytes = format!("{}:", format).into_bytes();
+            bytes.extend_from_slice(text);
+            return payload(depth, &bytes, lines);
+        }
+        Frame::Array(items) | Frame::Set(items) | Frame::Push(items) => {
+            line(header(frame, items.len()), format!("{} of {}", frame.type_name(), plural(items.len(), "element")));
+            items.iter().collect::<Vec<_>>()
+        }
+        Frame::Map(pairs) | Frame::Attribute(pairs) => {
+            line(header(frame, pairs.len()), format!("{} of {}", frame.type_name(), plural(pairs.len(), "pair")));
+            pairs.iter().flat_map(|(key, value)| [key, value]).collect()
+        }
+        scalar => {
+            let note = match scalar {
+                Frame::Simple(text) => format!("simple string \"{}\"", text),
+                Frame::Error(text) => format!("error: {}", text),
+                Frame::Integer(n) => format!("integer {}", n),
+                Frame::NullBulk => "null bulk string (nil)".to_string(),
+                Frame::NullArray => "null array (nil)".to_string(),
+                Frame::Null => "null (nil)".to_string(),
+                Frame::Double(value) => format!("double {}", value),
+                Frame::Boolean(value) => format!("boolean {}", value),
+                Frame::BigNumber(digits) => format!("big number {}", digits),
+                _ => unreachable!("aggregates and bulk frames are handled above"),
+            };
+            return line(escape(&scalar.to_bytes()), note);
+        }
+    };
+    for item in items {
+        walk(item, depth + 1, lines);
+    }
+}
+
+/// Annotates one complete frame at the start of `buf`, RESP2 or RESP3.
+/// `Ok(None)` means more bytes are needed.
+pub fn annotate(buf: &[u8]) -> std::result::Result<Option<(Vec<WireLine>, usize)>, ParseError> {
+    Ok(Frame::parse(buf)?.map(|(frame, end)| {
+        let mut lines = Vec::new();
+        walk(&frame, 0, &mut lines);
+        (lines, end)
+    }))
+}
+
+/// Lines as an indented two-column listing.
+pub fn render(lines: &[WireLine]) -> String {
+    let width = lines.iter().map(|line| line.depth * 2 + line.raw.chars().count()).max().unwrap_or(0);
+    let mut out = String::new();
+    for line in lines {
+        let raw = format!("{}{}", "  ".repeat(line.depth), line.raw);
+        out.push_str(&format!("   {:<width$}   {}\n", raw, line.note, width = width));
+    }
+    out
+}
+
+/// The bytes of one request and its reply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    pub sent: Vec<u8>,
+    pub received: Vec<u8>,
+}
+
+/// Talks RESP to a server over a plain socket, bypassing the redis crate,
+/// so the exact bytes in both directions can be shown.
+pub struct WireInspector {
+    stream: TcpStream,
+}
+
+impl WireInspector {
+    /// Connects, then sends the AUTH and SELECT the URL asks for without
+    /// showing them, so passwords never end up on screen.
+    pub async fn connect(info: &ConnectionInfo) -> Result<Self> {
+        let ConnectionAddr::Tcp(host, port) = &info.addr else {
+            return Err(DemoError::Configuration("The wire inspector only speaks plain TCP (no TLS or Unix sockets)".to_string()));
+        };
+        let mut inspector = Self { stream: TcpStream::connect((host.as_str(), *port)).await? };
+        let mut setup = Vec::new();
+        if let Some(password) = &info.redis.password {
+            setup.push(["AUTH".to_string()].into_iter().chain(info.redis.username.clone()).chain([password.clone()]).collect());
+        }
+        if info.redis.db != 0 {
+            setup.push(vec!["SELECT".to_string(), info.redis.db.to_string()]);
+        }
+        for args in setup {
+            let exchange: Exchange = inspector.send(&args).await?;
+            if exchange.received.first() == Some(&b'-') {
+                return Err(DemoError::Configuration(format!("{} failed: {}", args[0], String::from_utf8_lossy(&exchange.received).trim())));
+            }
+        }
+        Ok(inspector)
+    }
+
+    /// Sends `args` as one command and reads back one complete reply.
+    pub async fn send(&mut self, args: &[String]) -> Result<Exchange> {
+        let sent = encode_command(args);
+        self.stream.write_all(&sent).await?;
+        let mut received = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            match annotate(&received).map_err(|e| DemoError::Demo(format!("Unparseable reply: {}", e)))? {
+                Some((_, end)) if end == received.len() => return Ok(Exchange { sent, received }),
+                Some(_) => return Err(DemoError::Demo("Server sent more than one reply".to_string())),
+                None => {}
+            }
+            let read = tokio::time::timeout(REPLY_TIMEOUT, self.stream.read(&mut chunk))
+                .await
+                .map_err(|_| DemoError::Demo("Timed out waiting for a complete reply".to_string()))??;
+            if read == 0 {
+                return Err(DemoError::Demo("Server closed the connection mid-reply".to_string()));
+            }
+            received.extend_from_slice(&chunk[..read]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_encode_command() {
+        assert_eq!(encode_command(&args(&["SET", "k", "héllo"])), b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$6\r\nh\xc3\xa9llo\r\n");
+    }
+
+    #[test]
+    fn test_annotate_nested_reply() {
+        let reply = b"*3\r\n$1\r\na\r\n*2\r\n:7\r\n$-1\r\n+OK\r\n";
+        let (lines, end) = annotate(reply).unwrap().unwrap();
+        assert_eq!(end, reply.len());
+        let notes: Vec<_> = lines.iter().map(|line| (line.depth, line.note.as_str())).collect();
+        assert_eq!(
+            notes,
+            [
+                (0, "array of 3 elements"),
+                (1, "bulk string, 1 byte"),
+                (1, "payload"),
+                (1, "array of 2 elements"),
+                (2, "integer 7"),
+                (2, "null bulk string (nil)"),
+                (1, "simple string \"OK\""),
+            ]
+        );
+        assert_eq!(lines[0].raw, "*3\\r\\n");
+        for cut in 0..reply.len() {
+            assert_eq!(annotate(&reply[..cut]).unwrap(), None, "cut at {}", cut);
+        }
+        assert!(annotate(b"?\r\n").is_err());
+    }
+
+    #[test]
+    fn test_annotate_resp3_reply() {
+        let reply = b"%2\r\n+proto\r\n:3\r\n$4\r\nmode\r\n=8\r\ntxt:solo\r\n";
+        let (lines, _) = annotate(reply).unwrap().unwrap();
+        let notes: Vec<_> = lines.iter().map(|line| (line.depth, line.note.as_str())).collect();
+        assert_eq!(
+            notes,
+            [
+                (0, "map of 2 pairs"),
+                (1, "simple string \"proto\""),
+                (1, "integer 3"),
+                (1, "bulk string, 4 bytes"),
+                (1, "payload"),
+                (1, "verbatim string (txt), 8 bytes"),
+                (1, "payload"),
+            ]
+        );
+        assert_eq!(lines[6].raw, "txt:solo\\r\\n");
+    }
+
+    #[test]
+    fn test_long_payloads_are_summarized() {
+        let reply = format!("$100\r\n{}\r\n", "x".repeat(100));
+        let (lines, _) = annotate(reply.as_bytes()).unwrap().unwrap();
+        assert_eq!(lines[1].note, "payload (first 64 of 100 bytes)");
+        assert!(lines[1].raw.ends_with("x…\\r\\n"));
+        assert!(render(&lines).contains("   $100\\r\\n"));
+    }
+}
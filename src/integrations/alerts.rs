@@ -0,0 +1,190 @@
+use super::chat::{AlertTemplate, ChatFormat, ChatSink};
+use super::events::Event;
+use super::webhook::{WebhookConfig, WebhookHost, WebhookSink};
+use crate::utils::RedisConnection;
+use crate::{DemoError, RedisClient, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::json;
+use std::time::Duration;
+
+/// Somewhere threshold breaches get reported to.
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    fn url(&self) -> &str;
+
+    /// Delivers one alert, retrying per the sink's own policy.
+    async fn send(&self, event: &Event) -> Result<()>;
+}
+
+#[async_trait]
+impl AlertSink for WebhookSink {
+    fn url(&self) -> &str {
+        WebhookSink::url(self)
+    }
+
+    async fn send(&self, event: &Event) -> Result<()> {
+        WebhookSink::send(self, event).await.map(|_| ())
+    }
+}
+
+pub fn cooldown_key(subject: &str) -> String {
+    format!("alerts:cooldown:{}", subject)
+}
+
+pub fn suppressed_key(subject: &str) -> String {
+    format!("alerts:suppressed:{}", subject)
+}
+
+/// At most one alert per subject per `cooldown`, counted in Redis so that
+/// every process and every run of a one-shot command shares it. Alerts held
+/// back are counted and the count goes out with the next one.
+pub struct AlertLimiter {
+    conn: RedisConnection,
+    cooldown: Duration,
+}
+
+impl AlertLimiter {
+    pub fn new(conn: RedisConnection, cooldown: Duration) -> Self {
+        Self { conn, cooldown }
+    }
+
+    /// `Some(suppressed)` if an alert about `subject` may go out now, with
+    /// how many were held back since the last one; `None` while cooling
+    /// down. A zero cooldown lets everything through.
+    pub async fn admit(&mut self, subject: &str) -> Result<Option<u64>> {
+        if self.cooldown.is_zero() {
+            return Ok(Some(0));
+        }
+        let cooldown_ms = self.cooldown.as_millis() as u64;
+        let set: Option<String> = redis::cmd("SET")
+            .arg(cooldown_key(subject))
+            .arg(Utc::now().timestamp_millis())
+            .arg("NX")
+            .arg("PX")
+            .arg(cooldown_ms)
+            .query_async(&mut self.conn)
+            .await?;
+        match set {
+            Some(_) => {
+                let (suppressed,): (Option<u64>,) =
+                    redis::pipe().atomic().get(suppressed_key(subject)).del(suppressed_key(subject)).ignore().query_async(&mut self.conn).await?;
+                Ok(Some(suppressed.unwrap_or(0)))
+            }
+            None => {
+                // Outlives the cooldown so the count is still there for the next alert.
+                let _: () = redis::pipe()
+                    .incr(suppressed_key(subject), 1)
+                    .ignore()
+                    .pexpire(suppressed_key(subject), (cooldown_ms * 2) as i64)
+                    .ignore()
+                    .query_async(&mut self.conn)
+                    .await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Undoes an [`admit`](Self::admit) whose alert couldn't be delivered,
+    /// so the next check tries again instead of waiting out the cooldown.
+    pub async fn refund(&mut self, subject: &str, suppressed: u64) -> Result<()> {
+        if self.cooldown.is_zero() {
+            return Ok(());
+        }
+        let mut pipe = redis::pipe();
+        pipe.del(cooldown_key(subject)).ignore();
+        if suppressed > 0 {
+            pipe.incr(suppressed_key(subject), suppressed).ignore();
+        }
+        let _: () = pipe.query_async(&mut self.conn).await?;
+        Ok(())
+    }
+}
+
+/// Sends the alerts a check raised, one message per check, through the
+/// cooldown of an [`AlertLimiter`].
+pub struct AlertNotifier {
+    sink: Box<dyn AlertSink>,
+    limiter: AlertLimiter,
+}
+
+impl AlertNotifier {
+    pub fn new(sink: Box<dyn AlertSink>, limiter: AlertLimiter) -> Self {
+        Self { sink, limiter }
+    }
+
+    /// A Slack or Discord sink when `url` is one of their webhooks (see
+    /// [`ChatFormat::detect`]), otherwise a [`WebhookSink`] posting the
+    /// event JSON. `template` only applies to chat messages.
+    pub async fn for_url(client: &RedisClient, url: &str, template: Option<&str>, cooldown: Duration) -> Result<Self> {
+        let sink: Box<dyn AlertSink> = match (ChatFormat::detect(url), template) {
+            (Some(format), None) => Box::new(ChatSink::new(url, format)?),
+            (Some(format), Some(template)) => Box::new(ChatSink::new(url, format)?.with_template(template.parse::<AlertTemplate>()?)),
+            (None, None) => Box::new(WebhookSink::new(WebhookConfig::new(url))?),
+            (None, Some(_)) => {
+                return Err(DemoError::Configuration(
+                    "--alert-template only applies to Slack and Discord webhooks; other URLs get the event as JSON".to_string(),
+                ))
+            }
+        };
+        let limiter = AlertLimiter::new(client.get_async_connection().await?, cooldown);
+        Ok(Self::new(sink, limiter))
+    }
+
+    /// Reports `alerts` about `subject` unless it was reported within the
+    /// cooldown. Returns whether a message went out.
+    pub async fn notify(&mut self, subject: &str, alerts: &[String]) -> Result<bool> {
+        if alerts.is_empty() {
+            return Ok(false);
+        }
+        let Some(suppressed) = self.limiter.admit(subject).await? else {
+            println!("🔕 Alert for {} held back (cooldown)", subject);
+            return Ok(false);
+        };
+        let message: Vec<String> = alerts.iter().map(|alert| format!("• {}", alert)).collect();
+        let mut event = Event::threshold_breached(subject, &message.join("\n"), Utc::now());
+        event.data = json!({ "alerts": alerts, "suppressed": suppressed });
+        if let Err(e) = self.sink.send(&event).await {
+            self.limiter.refund(subject, suppressed).await?;
+            return Err(e);
+        }
+        println!("{}", sent_line(subject, self.sink.url()));
+        Ok(true)
+    }
+}
+
+/// Chat webhooks carry their token in the path, so only the host is shown.
+fn sent_line(subject: &str, url: &str) -> String {
+    format!("📣 Alert for {} sent to {}", subject, WebhookHost(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use redis::AsyncCommands;
+
+    #[test]
+    fn test_sent_line_hides_the_webhook_path() {
+        let line = sent_line("orders", "https://discord.com/api/webhooks/123/SECRET");
+        assert_eq!(line, "📣 Alert for orders sent to https://discord.com");
+        assert!(!line.contains("/api") && !line.contains("SECRET"));
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_holds_back_and_counts_repeats() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let subject = "test-alerts";
+        let _: () = conn.del(&[cooldown_key(subject), suppressed_key(subject)]).await.unwrap();
+        let mut limiter = AlertLimiter::new(conn, Duration::from_millis(200));
+
+        assert_eq!(limiter.admit(subject).await.unwrap(), Some(0));
+        assert_eq!(limiter.admit(subject).await.unwrap(), None);
+        assert_eq!(limiter.admit(subject).await.unwrap(), None);
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(limiter.admit(subject).await.unwrap(), Some(2));
+
+        limiter.refund(subject, 2).await.unwrap();
+        assert_eq!(limiter.admit(subject).await.unwrap(), Some(2));
+    }
+}
@@ -0,0 +1,213 @@
+use super::alerts::AlertSink;
+use super::events::Event;
+use super::webhook::{deliver, http_client};
+use crate::utils::RetryPolicy;
+use crate::{DemoError, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Discord rejects messages longer than this.
+const DISCORD_MAX_CHARS: usize = 2000;
+
+/// Which incoming-webhook payload a chat service expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatFormat {
+    /// `{"text": ...}`, which Mattermost and Rocket.Chat accept too.
+    Slack,
+    /// `{"content": ...}`.
+    Discord,
+}
+
+impl ChatFormat {
+    /// Recognises Slack and Discord webhook URLs; anything else isn't a chat
+    /// service this knows.
+    pub fn detect(url: &str) -> Option<Self> {
+        let host = url.split_once("://").map_or(url, |(_, rest)| rest).split(['/', ':']).next().unwrap_or("");
+        match host {
+            "hooks.slack.com" => Some(ChatFormat::Slack),
+            "discord.com" | "discordapp.com" | "ptb.discord.com" | "canary.discord.com" => Some(ChatFormat::Discord),
+            _ => None,
+        }
+    }
+
+    /// Bold in the service's own markdown.
+    pub fn default_template(&self) -> &'static str {
+        match self {
+            ChatFormat::Slack => "⚠️ *{subject}*\n{message}",
+            ChatFormat::Discord => "⚠️ **{subject}**\n{message}",
+        }
+    }
+
+    pub fn payload(&self, text: &str) -> serde_json::Value {
+        match self {
+            ChatFormat::Slack => json!({ "text": text }),
+            ChatFormat::Discord => {
+                let text = match text.char_indices().nth(DISCORD_MAX_CHARS - 1) {
+                    Some((cut, _)) => format!("{}…", &text[..cut]),
+                    None => text.to_string(),
+                };
+                json!({ "content": text })
+            }
+        }
+    }
+}
+
+/// Placeholders an [`AlertTemplate`] may use.
+pub const PLACEHOLDERS: [&str; 5] = ["event", "subject", "message", "at", "suppressed"];
+
+/// Chat message text with `{subject}`, `{message}`, `{event}`, `{at}` and
+/// `{suppressed}` filled in from the event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlertTemplate(String);
+
+impl AlertTemplate {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// When alerts were held back by the cooldown and the template doesn't
+    /// place `{suppressed}` itself, a line saying how many is added.
+    pub fn render(&self, event: &Event) -> String {
+        let suppressed = event.data.get("suppressed").and_then(|value| value.as_u64()).unwrap_or(0);
+        let mut text = String::new();
+        let mut rest = self.0.as_str();
+        // One pass, so braces in the filled-in values are left alone.
+        while let Some((start, end)) = rest.find('{').and_then(|start| Some((start, start + rest[start..].find('}')?))) {
+            text.push_str(&rest[..start]);
+            match &rest[start + 1..end] {
+                "event" => text.push_str(event.kind.as_str()),
+                "subject" => text.push_str(&event.subject),
+                "message" => text.push_str(&event.message),
+                "at" => text.push_str(&event.at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+                "suppressed" => text.push_str(&suppressed.to_string()),
+                _ => text.push_str(&rest[start..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        text.push_str(rest);
+        if suppressed > 0 && !self.0.contains("{suppressed}") {
+            text.push_str(&format!("\n_{} similar alert{} suppressed since the last one_", suppressed, if suppressed == 1 { "" } else { "s" }));
+        }
+        text
+    }
+}
+
+impl FromStr for AlertTemplate {
+    type Err = DemoError;
+
+    /// Refuses placeholders it wouldn't fill, so a typo shows up now rather
+    /// than in the middle of an incident.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else { break };
+            let name = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(DemoError::Configuration(format!(
+                    "Unknown placeholder {{{}}} in alert template (use {})",
+                    name,
+                    PLACEHOLDERS.map(|name| format!("{{{}}}", name)).join(", ")
+                )));
+            }
+            rest = &rest[start + end + 1..];
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+/// Posts alerts as messages to a Slack or Discord incoming webhook.
+pub struct ChatSink {
+    http: reqwest::Client,
+    url: String,
+    format: ChatFormat,
+    template: AlertTemplate,
+    retry: RetryPolicy,
+}
+
+impl ChatSink {
+    pub fn new(url: &str, format: ChatFormat) -> Result<Self> {
+        Ok(Self {
+            http: http_client(url, Duration::from_secs(5))?,
+            url: url.to_string(),
+            format,
+            template: AlertTemplate(format.default_template().to_string()),
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    pub fn with_template(mut self, template: AlertTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn format(&self) -> ChatFormat {
+        self.format
+    }
+}
+
+#[async_trait]
+impl AlertSink for ChatSink {
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn send(&self, event: &Event) -> Result<()> {
+        let body = serde_json::to_vec(&self.format.payload(&self.template.render(event)))?;
+        self.retry
+            .run(|| {
+                let request = self.http.post(&self.url).header(reqwest::header::CONTENT_TYPE, "application/json").body(body.clone());
+                deliver(request, &self.url)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn event() -> Event {
+        let at = DateTime::parse_from_rfc3339("2025-03-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        Event::threshold_breached("replication", "• {at} replica 10.0.0.2:6379 is 5000 bytes behind (max 100)", at)
+    }
+
+    #[test]
+    fn test_detects_chat_services() {
+        assert_eq!(ChatFormat::detect("https://hooks.slack.com/services/T0/B0/x"), Some(ChatFormat::Slack));
+        assert_eq!(ChatFormat::detect("https://discord.com/api/webhooks/1/abc"), Some(ChatFormat::Discord));
+        assert_eq!(ChatFormat::detect("https://discord.com.example.org/hook"), None);
+        assert_eq!(ChatFormat::detect("http://localhost:9000/hook"), None);
+    }
+
+    #[test]
+    fn test_template_renders_and_rejects_typos() {
+        let template: AlertTemplate = "[{event}] {subject} at {at}: {message}".parse().unwrap();
+        assert_eq!(
+            template.render(&event()),
+            "[threshold_breached] replication at 2025-03-01T12:00:00Z: • {at} replica 10.0.0.2:6379 is 5000 bytes behind (max 100)"
+        );
+
+        let mut repeated = event();
+        repeated.data = json!({ "suppressed": 3 });
+        let default = AlertTemplate(ChatFormat::Slack.default_template().to_string());
+        assert!(default.render(&repeated).ends_with("\n_3 similar alerts suppressed since the last one_"));
+        assert_eq!("{suppressed} held back".parse::<AlertTemplate>().unwrap().render(&repeated), "3 held back");
+
+        assert!("{subjet}: {message}".parse::<AlertTemplate>().is_err());
+    }
+
+    #[test]
+    fn test_payloads() {
+        assert_eq!(ChatFormat::Slack.payload("hi"), json!({ "text": "hi" }));
+        let long = ChatFormat::Discord.payload(&"é".repeat(3000));
+        assert_eq!(long["content"].as_str().unwrap().chars().count(), DISCORD_MAX_CHARS);
+    }
+}
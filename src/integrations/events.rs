@@ -0,0 +1,274 @@
+use super::webhook::{WebhookHost, WebhookSink};
+use crate::jobs::{bulk, Checkpoint, JobState};
+use crate::server::ReplicaLagThresholds;
+use crate::utils::glob::glob_match;
+use crate::utils::key_watch::{NotifyChange, NotifyGuard};
+use crate::{DemoError, RedisClient, Result};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// A bulk job (see `jobs list`) ended in the failed state.
+    JobFailed,
+    /// A key matching the session pattern reached its TTL.
+    SessionExpired,
+    /// A monitored value crossed its alert threshold.
+    ThresholdBreached,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 3] = [EventKind::JobFailed, EventKind::SessionExpired, EventKind::ThresholdBreached];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::JobFailed => "job_failed",
+            EventKind::SessionExpired => "session_expired",
+            EventKind::ThresholdBreached => "threshold_breached",
+        }
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.replace('-', "_").as_str() {
+            "job_failed" => Ok(EventKind::JobFailed),
+            "session_expired" => Ok(EventKind::SessionExpired),
+            "threshold_breached" => Ok(EventKind::ThresholdBreached),
+            other => Err(DemoError::Configuration(format!(
+                "Unknown event: {} (use job-failed, session-expired or threshold-breached)",
+                other
+            ))),
+        }
+    }
+}
+
+/// What a webhook receives, as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Event {
+    pub id: Uuid,
+    #[serde(rename = "event")]
+    pub kind: EventKind,
+    pub at: DateTime<Utc>,
+    /// The job id, key or replica the event is about.
+    pub subject: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub data: Value,
+}
+
+impl Event {
+    pub fn new(kind: EventKind, subject: &str, message: String, at: DateTime<Utc>) -> Self {
+        Self { id: Uuid::new_v4(), kind, at, subject: subject.to_string(), message, data: Value::Null }
+    }
+
+    pub fn job_failed(job: &Checkpoint, at: DateTime<Utc>) -> Self {
+        let message = format!("{} job failed: {}", job.kind, job.error.as_deref().unwrap_or("no error recorded"));
+        Self { data: serde_json::to_value(job).unwrap_or_default(), ..Self::new(EventKind::JobFailed, &job.id, message, at) }
+    }
+
+    pub fn session_expired(key: &str, at: DateTime<Utc>) -> Self {
+        Self::new(EventKind::SessionExpired, key, format!("{} expired", key), at)
+    }
+
+    pub fn threshold_breached(source: &str, alert: &str, at: DateTime<Utc>) -> Self {
+        Self::new(EventKind::ThresholdBreached, source, alert.to_string(), at)
+    }
+}
+
+/// Turns repeated polls into events, each failure or breach reported once.
+#[derive(Debug, Default)]
+pub struct Detector {
+    failed_jobs: Option<HashSet<String>>,
+    alerts: HashSet<(String, String)>,
+}
+
+impl Detector {
+    /// Jobs that failed since the last call. The first call only notes the
+    /// jobs that had already failed, so starting a watcher doesn't replay
+    /// old failures.
+    pub fn failed_jobs(&mut self, jobs: &[Checkpoint], at: DateTime<Utc>) -> Vec<Event> {
+        let failed = jobs.iter().filter(|job| job.state == JobState::Failed);
+        let Some(seen) = &mut self.failed_jobs else {
+            self.failed_jobs = Some(failed.map(|job| job.id.clone()).collect());
+            return Vec::new();
+        };
+        failed.filter(|job| seen.insert(job.id.clone())).map(|job| Event::job_failed(job, at)).collect()
+    }
+
+    /// The alerts of `source` that weren't raised by its previous check.
+    /// One that clears and comes back is reported again.
+    pub fn breaches(&mut self, source: &str, alerts: &[String], at: DateTime<Utc>) -> Vec<Event> {
+        let fresh: Vec<Event> = alerts
+            .iter()
+            .filter(|alert| !self.alerts.contains(&(source.to_string(), alert.to_string())))
+            .map(|alert| Event::threshold_breached(source, alert, at))
+            .collect();
+        self.alerts.retain(|(from, _)| from != source);
+        self.alerts.extend(alerts.iter().map(|alert| (source.to_string(), alert.clone())));
+        fresh
+    }
+}
+
+/// Flags session expiry needs: expirations on keyevent channels.
+pub const EXPIRED_FLAGS: &str = "Ex";
+
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    pub kinds: Vec<EventKind>,
+    /// Keys whose expiry is a [`EventKind::SessionExpired`].
+    pub sessions: String,
+    /// Replication is only checked when a limit is set.
+    pub thresholds: ReplicaLagThresholds,
+    /// How often jobs and replication are polled.
+    pub interval: Duration,
+    /// Stop after this many events.
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WatchSummary {
+    pub delivered: usize,
+    pub failed: usize,
+}
+
+/// Sends the selected events to `sink` as they happen, until Ctrl-C or
+/// `limit`. A delivery that still fails after its retries is reported and
+/// the watch goes on. Session expiry needs keyspace notifications:
+/// `change`, from `key_watch::notify_change` with [`EXPIRED_FLAGS`] once
+/// confirmed, is made for the duration and undone afterwards, on errors too,
+/// like `key watch` does.
+pub async fn watch(client: &RedisClient, sink: &WebhookSink, options: &WatchOptions, change: Option<NotifyChange>) -> Result<WatchSummary> {
+    let guard = NotifyGuard::apply(client, change).await?;
+    let summary = send_events(client, sink, options).await?;
+    guard.restore().await?;
+    Ok(summary)
+}
+
+async fn send_events(client: &RedisClient, sink: &WebhookSink, options: &WatchOptions) -> Result<WatchSummary> {
+    let mut conn = client.get_async_connection().await?;
+    let sessions = options.kinds.contains(&EventKind::SessionExpired);
+
+    let prefix = client.key_prefix().unwrap_or("").to_string();
+    let mut messages = match sessions {
+        true => {
+            let mut pubsub = client.get_async_pubsub().await?;
+            pubsub.subscribe(format!("__keyevent@{}__:expired", client.get_connection_info().redis.db)).await?;
+            Some(pubsub.into_on_message())
+        }
+        false => None,
+    };
+    let kinds: Vec<&str> = options.kinds.iter().map(EventKind::as_str).collect();
+    println!("Sending {} to {} (Ctrl-C to stop)\n", kinds.join(", "), WebhookHost(sink.url()));
+
+    let mut detector = Detector::default();
+    let mut ticker = tokio::time::interval(options.interval);
+    let mut summary = WatchSummary::default();
+    while options.limit.is_none_or(|limit| summary.delivered + summary.failed < limit) {
+        let events = tokio::select! {
+            message = async {
+                match &mut messages {
+                    Some(messages) => messages.next().await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                let Some(message) = message else { break };
+                let key: String = message.get_payload()?;
+                match key.strip_prefix(prefix.as_str()) {
+                    Some(key) if glob_match(options.sessions.as_bytes(), key.as_bytes()) => vec![Event::session_expired(key, Utc::now())],
+                    _ => Vec::new(),
+                }
+            }
+            _ = ticker.tick() => poll(client, &mut conn, &mut detector, options).await?,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        for event in events {
+            match sink.send(&event).await {
+                Ok(attempts) => {
+                    summary.delivered += 1;
+                    let retried = if attempts > 1 { format!(" after {} attempts", attempts) } else { String::new() };
+                    println!("📤 {} {}: {}{}", event.kind.as_str(), event.subject, event.message, retried);
+                }
+                Err(e) => {
+                    summary.failed += 1;
+                    println!("❌ {} {} not delivered: {}", event.kind.as_str(), event.subject, e);
+                }
+            }
+        }
+    }
+    Ok(summary)
+}
+
+async fn poll(
+    client: &RedisClient,
+    conn: &mut crate::utils::RedisConnection,
+    detector: &mut Detector,
+    options: &WatchOptions,
+) -> Result<Vec<Event>> {
+    let mut events = Vec::new();
+    if options.kinds.contains(&EventKind::JobFailed) {
+        events.extend(detector.failed_jobs(&bulk::list(conn).await?, Utc::now()));
+    }
+    if options.kinds.contains(&EventKind::ThresholdBreached) && options.thresholds != ReplicaLagThresholds::default() {
+        let alerts = client.replication_lag().await?.evaluate(&options.thresholds);
+        events.extend(detector.breaches("replication", &alerts, Utc::now()));
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, state: JobState) -> Checkpoint {
+        let mut job = Checkpoint::new(id, "gc-indexes", 1, Utc::now());
+        job.state = state;
+        job.error = Some("connection reset".to_string());
+        job
+    }
+
+    #[test]
+    fn test_failed_jobs_are_reported_once() {
+        let mut detector = Detector::default();
+        let now = Utc::now();
+        assert!(detector.failed_jobs(&[job("old", JobState::Failed)], now).is_empty());
+        let jobs = [job("old", JobState::Failed), job("new", JobState::Failed), job("ok", JobState::Completed)];
+        let events = detector.failed_jobs(&jobs, now);
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].subject.as_str(), events[0].message.as_str()), ("new", "gc-indexes job failed: connection reset"));
+        assert!(detector.failed_jobs(&jobs, now).is_empty());
+    }
+
+    #[test]
+    fn test_breaches_fire_when_raised() {
+        let mut detector = Detector::default();
+        let now = Utc::now();
+        let lagging = vec!["replica 10.0.0.2:6379 is 5000 bytes behind (max 100)".to_string()];
+        assert_eq!(detector.breaches("replication", &lagging, now).len(), 1);
+        assert!(detector.breaches("replication", &lagging, now).is_empty());
+        assert!(detector.breaches("replication", &[], now).is_empty());
+        assert_eq!(detector.breaches("replication", &lagging, now)[0].kind, EventKind::ThresholdBreached);
+    }
+
+    #[test]
+    fn test_event_json_and_kinds() {
+        let at = DateTime::parse_from_rfc3339("2025-03-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let json = serde_json::to_value(Event::session_expired("session:abc", at)).unwrap();
+        assert_eq!(json["event"], "session_expired");
+        assert_eq!(json["subject"], "session:abc");
+        assert_eq!(json["at"], "2025-03-01T12:00:00Z");
+        assert!(json.get("data").is_none());
+        assert_eq!("threshold-breached".parse::<EventKind>().unwrap(), EventKind::ThresholdBreached);
+        assert!("expired".parse::<EventKind>().is_err());
+        assert_eq!(crate::utils::key_watch::notify_flags("", EXPIRED_FLAGS), Some("Ex".to_string()));
+    }
+}
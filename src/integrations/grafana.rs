@@ -0,0 +1,148 @@
+use super::metric_registry::{Gauge, Section, GAUGES};
+use serde_json::{json, Value};
+
+pub const DASHBOARD_UID: &str = "redis-rust-demo";
+const PANEL_WIDTH: u64 = 8;
+const PANEL_HEIGHT: u64 = 8;
+const GRID_WIDTH: u64 = 24;
+
+/// A Grafana dashboard with a row per [`Section`] and a time series panel
+/// per gauge in the metric registry, reading from a Prometheus data source
+/// picked on the dashboard. Import it, or drop it in a provisioning folder.
+pub fn dashboard(title: &str) -> Value {
+    let mut panels = Vec::new();
+    let mut y = 0;
+    for section in Section::ALL {
+        let gauges: Vec<&Gauge> = GAUGES.iter().filter(|gauge| gauge.section == section).collect();
+        if gauges.is_empty() {
+            continue;
+        }
+        panels.push(json!({
+            "type": "row",
+            "id": panels.len() + 1,
+            "title": section.title(),
+            "collapsed": false,
+            "gridPos": { "h": 1, "w": GRID_WIDTH, "x": 0, "y": y },
+            "panels": [],
+        }));
+        y += 1;
+        for (i, gauge) in gauges.iter().enumerate() {
+            let x = i as u64 * PANEL_WIDTH % GRID_WIDTH;
+            if i > 0 && x == 0 {
+                y += PANEL_HEIGHT;
+            }
+            panels.push(panel(gauge, panels.len() + 1, x, y));
+        }
+        y += PANEL_HEIGHT;
+    }
+
+    json!({
+        "uid": DASHBOARD_UID,
+        "title": title,
+        "tags": ["redis", "redis-rust-demo"],
+        "editable": true,
+        "schemaVersion": 39,
+        "version": 1,
+        "time": { "from": "now-30m", "to": "now" },
+        "refresh": "10s",
+        "templating": {
+            "list": [
+                {
+                    "name": "datasource",
+                    "label": "Data source",
+                    "type": "datasource",
+                    "query": "prometheus",
+                },
+                {
+                    "name": "job",
+                    "label": "Job",
+                    "type": "query",
+                    "datasource": { "type": "prometheus", "uid": "${datasource}" },
+                    "query": "label_values(job)",
+                    "refresh": 2,
+                    "includeAll": true,
+                    "multi": true,
+                    "current": { "text": "All", "value": "$__all" },
+                },
+            ],
+        },
+        "panels": panels,
+    })
+}
+
+fn panel(gauge: &Gauge, id: usize, x: u64, y: u64) -> Value {
+    json!({
+        "type": "timeseries",
+        "id": id,
+        "title": gauge.title,
+        "description": gauge.help,
+        "datasource": { "type": "prometheus", "uid": "${datasource}" },
+        "gridPos": { "h": PANEL_HEIGHT, "w": PANEL_WIDTH, "x": x, "y": y },
+        "fieldConfig": { "defaults": { "unit": gauge.unit }, "overrides": [] },
+        "options": { "legend": { "displayMode": "list", "placement": "bottom" } },
+        "targets": [{
+            "refId": "A",
+            "expr": format!("{}{{job=~\"$job\"}}", gauge.name),
+            "legendFormat": legend(gauge),
+        }],
+    })
+}
+
+/// `{{replica}}`, `{{group}} {{consumer}}`, or Grafana's automatic legend
+/// for gauges with a single series.
+fn legend(gauge: &Gauge) -> String {
+    match gauge.labels.is_empty() {
+        true => "__auto".to_string(),
+        false => gauge.labels.iter().map(|label| format!("{{{{{}}}}}", label)).collect::<Vec<_>>().join(" "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::metric_registry::{lookup, REPLICA_LAG, STREAM_CONSUMER_PENDING};
+    use std::collections::HashSet;
+
+    fn metric_panels(dashboard: &Value) -> Vec<&Value> {
+        dashboard["panels"].as_array().unwrap().iter().filter(|panel| panel["type"] == "timeseries").collect()
+    }
+
+    #[test]
+    fn test_one_panel_per_registered_gauge() {
+        let dashboard = dashboard("Redis");
+        let panels = metric_panels(&dashboard);
+        assert_eq!(panels.len(), GAUGES.len());
+        for panel in panels {
+            let expr = panel["targets"][0]["expr"].as_str().unwrap();
+            let name = expr.split('{').next().unwrap();
+            assert_eq!(panel["title"], lookup(name).unwrap().title, "{}", expr);
+        }
+        let rows: Vec<&str> = dashboard["panels"].as_array().unwrap().iter().filter(|panel| panel["type"] == "row").map(|row| row["title"].as_str().unwrap()).collect();
+        assert_eq!(rows, Section::ALL.map(|section| section.title()));
+    }
+
+    #[test]
+    fn test_panels_do_not_overlap() {
+        let dashboard = dashboard("Redis");
+        let mut cells = HashSet::new();
+        for panel in dashboard["panels"].as_array().unwrap() {
+            let grid = &panel["gridPos"];
+            let (x, y, w, h) = (grid["x"].as_u64().unwrap(), grid["y"].as_u64().unwrap(), grid["w"].as_u64().unwrap(), grid["h"].as_u64().unwrap());
+            assert!(x + w <= GRID_WIDTH);
+            for cell in (x..x + w).flat_map(|x| (y..y + h).map(move |y| (x, y))) {
+                assert!(cells.insert(cell), "{} overlaps at {:?}", panel["title"], cell);
+            }
+        }
+        let ids: HashSet<u64> = dashboard["panels"].as_array().unwrap().iter().map(|panel| panel["id"].as_u64().unwrap()).collect();
+        assert_eq!(ids.len(), dashboard["panels"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn test_legends_and_registry_names() {
+        assert_eq!(legend(&REPLICA_LAG), "{{replica}}");
+        assert_eq!(legend(&STREAM_CONSUMER_PENDING), "{{group}} {{consumer}}");
+        let names: HashSet<&str> = GAUGES.iter().map(|gauge| gauge.name).collect();
+        assert_eq!(names.len(), GAUGES.len());
+        assert!(GAUGES.iter().all(|gauge| gauge.name.starts_with("redis_")));
+    }
+}
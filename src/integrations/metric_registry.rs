@@ -0,0 +1,164 @@
+//! Every gauge this crate exports. Samples are recorded through these
+//! constants and the Grafana dashboard is generated from [`GAUGES`], so a
+//! panel can't point at a metric nothing writes.
+
+/// The dashboard row a gauge's panel goes in, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+    Latency,
+    Soak,
+    Replication,
+    Streams,
+    Cache,
+    Queue,
+}
+
+impl Section {
+    pub const ALL: [Section; 6] = [Section::Latency, Section::Soak, Section::Replication, Section::Streams, Section::Cache, Section::Queue];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Section::Latency => "Command latency (bench load)",
+            Section::Soak => "Soak (bench soak)",
+            Section::Replication => "Replication (replication lag)",
+            Section::Streams => "Stream lag (streams lag)",
+            Section::Cache => "Cache (cache)",
+            Section::Queue => "Work queue (pattern backpressure)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gauge {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub section: Section,
+    /// Panel title on the dashboard.
+    pub title: &'static str,
+    /// Grafana unit id: `s`, `bytes`, `ops`, `percentunit`, `short`, ...
+    pub unit: &'static str,
+    /// Labels that tell the series apart, shown in the legend.
+    pub labels: &'static [&'static str],
+}
+
+const fn gauge(name: &'static str, help: &'static str, section: Section, title: &'static str, unit: &'static str, labels: &'static [&'static str]) -> Gauge {
+    Gauge { name, help, section, title, unit, labels }
+}
+
+pub const LOAD_OPS_PER_SECOND: Gauge =
+    gauge("redis_demo_load_ops_per_second", "Throughput over the steady part of the run", Section::Latency, "Throughput", "ops", &[]);
+pub const LOAD_REQUESTS: Gauge = gauge("redis_demo_load_requests", "Requests measured", Section::Latency, "Requests measured", "short", &[]);
+pub const LOAD_LATENCY_MEAN: Gauge =
+    gauge("redis_demo_load_latency_mean_seconds", "Mean request latency", Section::Latency, "Mean latency", "s", &[]);
+pub const LOAD_LATENCY: Gauge =
+    gauge("redis_demo_load_latency_seconds", "Request latency by quantile", Section::Latency, "Latency quantiles", "s", &["quantile"]);
+
+pub const SOAK_OPS: Gauge = gauge("redis_demo_soak_ops", "Operations completed since the soak started", Section::Soak, "Operations", "short", &[]);
+pub const SOAK_P99: Gauge =
+    gauge("redis_demo_soak_p99_seconds", "p99 latency over the last sample window", Section::Soak, "p99 latency", "s", &[]);
+pub const SERVER_CONNECTED_CLIENTS: Gauge =
+    gauge("redis_demo_server_connected_clients", "connected_clients from INFO", Section::Soak, "Server connections", "short", &[]);
+pub const SERVER_USED_MEMORY: Gauge =
+    gauge("redis_demo_server_used_memory_bytes", "used_memory from INFO", Section::Soak, "Server memory", "bytes", &[]);
+pub const CLIENT_RSS: Gauge =
+    gauge("redis_demo_client_rss_bytes", "Resident set size of the benchmark process", Section::Soak, "Client RSS", "bytes", &[]);
+pub const CLIENT_OPEN_FDS: Gauge =
+    gauge("redis_demo_client_open_fds", "Open file descriptors of the benchmark process", Section::Soak, "Client file descriptors", "short", &[]);
+
+pub const MASTER_REPL_OFFSET: Gauge =
+    gauge("redis_demo_master_repl_offset", "Replication offset of the master", Section::Replication, "Master offset", "bytes", &[]);
+pub const CONNECTED_REPLICAS: Gauge =
+    gauge("redis_demo_connected_replicas", "Replicas connected to the master", Section::Replication, "Connected replicas", "short", &[]);
+pub const REPLICA_LAG: Gauge = gauge(
+    "redis_demo_replica_lag_bytes",
+    "Replication bytes the replica has not acknowledged",
+    Section::Replication,
+    "Replica lag",
+    "bytes",
+    &["replica"],
+);
+pub const REPLICA_LAST_ACK: Gauge = gauge(
+    "redis_demo_replica_last_ack_seconds",
+    "Seconds since the replica last acknowledged",
+    Section::Replication,
+    "Last acknowledgement",
+    "s",
+    &["replica"],
+);
+pub const REPLICA_ONLINE: Gauge =
+    gauge("redis_demo_replica_online", "1 while the replica is online", Section::Replication, "Replica online", "short", &["replica"]);
+
+pub const STREAM_LENGTH: Gauge = gauge("redis_stream_length", "Entries in the stream", Section::Streams, "Stream length", "short", &["stream"]);
+pub const STREAM_GROUP_PENDING: Gauge = gauge(
+    "redis_stream_group_pending",
+    "Entries delivered to the group but not acknowledged",
+    Section::Streams,
+    "Pending per group",
+    "short",
+    &["stream", "group"],
+);
+pub const STREAM_GROUP_LAG: Gauge = gauge(
+    "redis_stream_group_lag",
+    "Entries not yet delivered to the group",
+    Section::Streams,
+    "Undelivered per group",
+    "short",
+    &["stream", "group"],
+);
+pub const STREAM_GROUP_OLDEST_PENDING: Gauge = gauge(
+    "redis_stream_group_oldest_pending_ms",
+    "Age of the group's oldest unacknowledged entry",
+    Section::Streams,
+    "Oldest pending entry",
+    "ms",
+    &["stream", "group"],
+);
+pub const STREAM_CONSUMER_PENDING: Gauge = gauge(
+    "redis_stream_consumer_pending",
+    "Entries delivered to the consumer but not acknowledged",
+    Section::Streams,
+    "Pending per consumer",
+    "short",
+    &["group", "consumer"],
+);
+
+pub const CACHE_HITS: Gauge = gauge("redis_demo_cache_hits", "Reads answered from the cache", Section::Cache, "Hits", "short", &["cache"]);
+pub const CACHE_MISSES: Gauge = gauge("redis_demo_cache_misses", "Reads the cache couldn't answer", Section::Cache, "Misses", "short", &["cache"]);
+pub const CACHE_STORE_LOADS: Gauge =
+    gauge("redis_demo_cache_store_loads", "Reads that went to the backing store", Section::Cache, "Store reads", "short", &["cache"]);
+pub const CACHE_HIT_RATIO: Gauge =
+    gauge("redis_demo_cache_hit_ratio", "Hits over all reads", Section::Cache, "Hit rate", "percentunit", &["cache"]);
+
+pub const QUEUE_DEPTH: Gauge = gauge("redis_demo_queue_depth", "Jobs waiting in the work queue", Section::Queue, "Queue depth", "short", &["queue"]);
+
+pub const GAUGES: &[Gauge] = &[
+    LOAD_OPS_PER_SECOND,
+    LOAD_REQUESTS,
+    LOAD_LATENCY_MEAN,
+    LOAD_LATENCY,
+    SOAK_OPS,
+    SOAK_P99,
+    SERVER_CONNECTED_CLIENTS,
+    SERVER_USED_MEMORY,
+    CLIENT_RSS,
+    CLIENT_OPEN_FDS,
+    MASTER_REPL_OFFSET,
+    CONNECTED_REPLICAS,
+    REPLICA_LAG,
+    REPLICA_LAST_ACK,
+    REPLICA_ONLINE,
+    STREAM_LENGTH,
+    STREAM_GROUP_PENDING,
+    STREAM_GROUP_LAG,
+    STREAM_GROUP_OLDEST_PENDING,
+    STREAM_CONSUMER_PENDING,
+    CACHE_HITS,
+    CACHE_MISSES,
+    CACHE_STORE_LOADS,
+    CACHE_HIT_RATIO,
+    QUEUE_DEPTH,
+];
+
+pub fn lookup(name: &str) -> Option<&'static Gauge> {
+    GAUGES.iter().find(|gauge| gauge.name == name)
+}
@@ -0,0 +1,13 @@
+pub mod alerts;
+pub mod chat;
+pub mod events;
+pub mod grafana;
+pub mod metric_registry;
+pub mod prometheus;
+pub mod webhook;
+
+pub use alerts::{AlertLimiter, AlertNotifier, AlertSink};
+pub use chat::{AlertTemplate, ChatFormat, ChatSink};
+pub use events::{Event, EventKind, WatchOptions, WatchSummary};
+pub use prometheus::{MetricSet, MetricsConfig, MetricsExporter};
+pub use webhook::{WebhookConfig, WebhookHost, WebhookSink};
@@ -0,0 +1,311 @@
+use super::metric_registry::{lookup, Gauge};
+use super::webhook::{deliver, http_client};
+use crate::utils::RetryPolicy;
+use crate::{DemoError, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+pub const DEFAULT_JOB: &str = "redis-rust-demo";
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Where sampled metrics go; read from a TOML file and the command line,
+/// the flags winning.
+///
+/// ```toml
+/// push_gateway = "http://localhost:9091"
+/// listen = "0.0.0.0:9187"
+/// job = "workshop"
+/// instance = "laptop-3"
+///
+/// [labels]
+/// room = "b"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Pushgateway base URL; each sample replaces the job's metrics there.
+    pub push_gateway: Option<String>,
+    /// Serve `/metrics` on this address while the command runs.
+    pub listen: Option<SocketAddr>,
+    /// `DEFAULT_JOB` when unset.
+    pub job: Option<String>,
+    /// Groups the push with the job, so several machines can push the same
+    /// job side by side.
+    pub instance: Option<String>,
+    /// Added to every sample.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl MetricsConfig {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents).map_err(|e| DemoError::Configuration(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.push_gateway.is_some() || self.listen.is_some()
+    }
+
+    /// `<gateway>/metrics/job/<job>[/instance/<instance>]`.
+    pub fn push_url(&self) -> Option<Result<String>> {
+        let gateway = self.push_gateway.as_ref()?;
+        let mut url = format!("{}/metrics", gateway.trim_end_matches('/'));
+        let groups = [("job", Some(self.job.as_deref().unwrap_or(DEFAULT_JOB))), ("instance", self.instance.as_deref())];
+        for (label, value) in groups {
+            let Some(value) = value else { continue };
+            if value.is_empty() || value.contains('/') {
+                return Some(Err(DemoError::Configuration(format!("Pushgateway {} must be non-empty and without '/': '{}'", label, value))));
+            }
+            let _ = write!(url, "/{}/{}", label, value);
+        }
+        Some(Ok(url))
+    }
+}
+
+/// Gauges in the Prometheus text exposition format, grouped by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricSet {
+    labels: Vec<(String, String)>,
+    families: Vec<Family>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Family {
+    name: String,
+    help: String,
+    samples: Vec<(String, f64)>,
+}
+
+impl MetricSet {
+    /// `labels` go on every sample.
+    pub fn new(labels: &BTreeMap<String, String>) -> Self {
+        Self { labels: labels.iter().map(|(name, value)| (name.clone(), value.clone())).collect(), families: Vec::new() }
+    }
+
+    pub fn gauge(&mut self, gauge: &Gauge, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        debug_assert!(lookup(gauge.name).is_some(), "{} is missing from the metric registry", gauge.name);
+        let mut rendered = String::new();
+        let all = self.labels.iter().map(|(name, value)| (name.as_str(), value.as_str())).chain(labels.iter().copied());
+        for (i, (label, value)) in all.enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            let _ = write!(rendered, "{}{}=\"{}\"", separator, label, escape(value));
+        }
+        match self.families.iter_mut().find(|family| family.name == gauge.name) {
+            Some(family) => family.samples.push((rendered, value)),
+            None => self.families.push(Family { name: gauge.name.to_string(), help: gauge.help.to_string(), samples: vec![(rendered, value)] }),
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.families.is_empty()
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for family in &self.families {
+            let _ = writeln!(out, "# HELP {} {}", family.name, family.help);
+            let _ = writeln!(out, "# TYPE {} gauge", family.name);
+            for (labels, value) in &family.samples {
+                match labels.is_empty() {
+                    true => {
+                        let _ = writeln!(out, "{} {}", family.name, value);
+                    }
+                    false => {
+                        let _ = writeln!(out, "{}{{{}}} {}", family.name, labels, value);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Publishes samples to a pushgateway and/or a `/metrics` endpoint that
+/// Prometheus scrapes, so Grafana can chart a demo while it runs.
+pub struct MetricsExporter {
+    config: MetricsConfig,
+    push: Option<(reqwest::Client, String)>,
+    retry: RetryPolicy,
+    latest: Arc<Mutex<String>>,
+    listen: Option<(SocketAddr, JoinHandle<()>)>,
+}
+
+impl MetricsExporter {
+    /// `None` when the config neither pushes nor listens.
+    pub async fn start(config: MetricsConfig) -> Result<Option<Self>> {
+        if !config.is_enabled() {
+            return Ok(None);
+        }
+        let push = match config.push_url().transpose()? {
+            Some(url) => Some((http_client(&url, Duration::from_secs(5))?, url)),
+            None => None,
+        };
+        let latest = Arc::new(Mutex::new(String::new()));
+        let listen = match config.listen {
+            Some(addr) => {
+                let listener = TcpListener::bind(addr).await?;
+                let addr = listener.local_addr()?;
+                println!("📈 Serving metrics on http://{}/metrics", addr);
+                Some((addr, tokio::spawn(serve(listener, latest.clone()))))
+            }
+            None => None,
+        };
+        if let Some((_, url)) = &push {
+            println!("📈 Pushing metrics to {}", url);
+        }
+        // A dashboard is no reason to slow the demo down with long retries.
+        let retry = RetryPolicy::default().with_max_attempts(2);
+        Ok(Some(Self { config, push, retry, latest, listen }))
+    }
+
+    /// A set carrying the configured labels, to fill in and [`publish`](Self::publish).
+    pub fn metric_set(&self) -> MetricSet {
+        MetricSet::new(&self.config.labels)
+    }
+
+    /// Where the scrape endpoint ended up listening (useful with port 0).
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listen.as_ref().map(|(addr, _)| *addr)
+    }
+
+    /// Makes `metrics` the current sample. A push that fails is logged and
+    /// dropped: the next sample replaces it anyway.
+    pub async fn publish(&self, metrics: &MetricSet) {
+        let body = metrics.render();
+        *self.latest.lock().expect("metrics buffer poisoned") = body.clone();
+        let Some((http, url)) = &self.push else { return };
+        let pushed = self
+            .retry
+            .run(|| deliver(http.put(url).header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE).body(body.clone()), url))
+            .await;
+        if let Err(e) = pushed {
+            warn!("Pushing metrics to {} failed: {}", url, e);
+        }
+    }
+}
+
+impl Drop for MetricsExporter {
+    fn drop(&mut self) {
+        if let Some((_, server)) = self.listen.take() {
+            server.abort();
+        }
+    }
+}
+
+/// Just enough HTTP/1.1 for a Prometheus scrape: `GET /metrics` gets the
+/// latest sample, anything else a 404, one request per connection.
+async fn serve(listener: TcpListener, latest: Arc<Mutex<String>>) {
+    loop {
+        let (mut socket, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Metrics endpoint accept failed: {}", e);
+                continue;
+            }
+        };
+        let latest = latest.clone();
+        tokio::spawn(async move {
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 8192 {
+                match socket.read(&mut buffer).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(read) => request.extend_from_slice(&buffer[..read]),
+                }
+            }
+            let line = String::from_utf8_lossy(&request);
+            let mut parts = line.split_whitespace();
+            let (status, body) = match (parts.next(), parts.next()) {
+                (Some("GET"), Some("/metrics")) => ("200 OK", latest.lock().expect("metrics buffer poisoned").clone()),
+                _ => ("404 Not Found", "Try /metrics\n".to_string()),
+            };
+            debug!("Metrics endpoint: {} from {}", status, peer);
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                CONTENT_TYPE,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+            let _ = socket.shutdown().await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::metric_registry::{CONNECTED_REPLICAS, REPLICA_LAG, SOAK_OPS};
+
+    #[test]
+    fn test_render_groups_samples_and_escapes_labels() {
+        let labels = BTreeMap::from([("room".to_string(), "b".to_string())]);
+        let mut metrics = MetricSet::new(&labels);
+        metrics
+            .gauge(&REPLICA_LAG, &[("replica", "10.0.0.2:6379")], 5000.0)
+            .gauge(&CONNECTED_REPLICAS, &[], 2.0)
+            .gauge(&REPLICA_LAG, &[("replica", "a\"b")], 0.5);
+        assert_eq!(
+            metrics.render(),
+            "# HELP redis_demo_replica_lag_bytes Replication bytes the replica has not acknowledged\n\
+             # TYPE redis_demo_replica_lag_bytes gauge\n\
+             redis_demo_replica_lag_bytes{room=\"b\",replica=\"10.0.0.2:6379\"} 5000\n\
+             redis_demo_replica_lag_bytes{room=\"b\",replica=\"a\\\"b\"} 0.5\n\
+             # HELP redis_demo_connected_replicas Replicas connected to the master\n\
+             # TYPE redis_demo_connected_replicas gauge\n\
+             redis_demo_connected_replicas{room=\"b\"} 2\n"
+        );
+        let mut bare = MetricSet::default();
+        assert_eq!(
+            bare.gauge(&SOAK_OPS, &[], 1.0).render(),
+            "# HELP redis_demo_soak_ops Operations completed since the soak started\n# TYPE redis_demo_soak_ops gauge\nredis_demo_soak_ops 1\n"
+        );
+    }
+
+    #[test]
+    fn test_config_file_and_push_url() {
+        let config: MetricsConfig = toml::from_str(
+            "push_gateway = \"http://localhost:9091/\"\ninstance = \"laptop-3\"\n[labels]\nroom = \"b\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.push_url().unwrap().unwrap(), "http://localhost:9091/metrics/job/redis-rust-demo/instance/laptop-3");
+        assert_eq!(config.labels["room"], "b");
+        assert!(config.is_enabled());
+
+        let bad = MetricsConfig { push_gateway: Some("http://localhost:9091".to_string()), job: Some("a/b".to_string()), ..Default::default() };
+        assert!(bad.push_url().unwrap().is_err());
+        assert!(MetricsConfig::default().push_url().is_none());
+        assert!(toml::from_str::<MetricsConfig>("pushgateway = \"x\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scrape_endpoint_serves_the_latest_sample() {
+        let config = MetricsConfig { listen: Some("127.0.0.1:0".parse().unwrap()), ..Default::default() };
+        let exporter = MetricsExporter::start(config).await.unwrap().unwrap();
+        let mut metrics = exporter.metric_set();
+        metrics.gauge(&SOAK_OPS, &[], 42.0);
+        exporter.publish(&metrics).await;
+
+        let base = format!("http://{}", exporter.local_addr().unwrap());
+        let response = reqwest::get(format!("{}/metrics", base)).await.unwrap();
+        assert_eq!(response.headers()["content-type"], CONTENT_TYPE);
+        assert!(response.text().await.unwrap().ends_with("redis_demo_soak_ops 42\n"));
+        assert_eq!(reqwest::get(format!("{}/other", base)).await.unwrap().status(), 404);
+    }
+}
@@ -0,0 +1,194 @@
+use super::events::Event;
+use crate::utils::RetryPolicy;
+use crate::{DemoError, Result};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt;
+use std::time::Duration;
+use tracing::debug;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+/// The event's id, the same on every retry, for receivers to drop repeats.
+pub const ID_HEADER: &str = "X-Webhook-Id";
+
+/// A webhook URL as safe to print or log: scheme and host only. Slack,
+/// Discord and most receivers carry the secret token in the path or query.
+pub struct WebhookHost<'a>(pub &'a str);
+
+impl fmt::Display for WebhookHost<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((scheme, rest)) = self.0.split_once("://") else {
+            return f.write_str("***");
+        };
+        let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+        let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        write!(f, "{}://{}", scheme, host)
+    }
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `<timestamp>.<body>` under `secret`.
+/// Signing the timestamp with the body lets receivers refuse old
+/// deliveries replayed at them.
+pub fn sign(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    let digest = mac(secret, timestamp, body).finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+/// The receiving side of [`sign`], comparing in constant time.
+pub fn verify(secret: &[u8], timestamp: i64, body: &[u8], signature: &str) -> bool {
+    // Non-ASCII would put a char boundary inside the two-byte slices below.
+    let Some(hex) = signature.strip_prefix("sha256=").filter(|hex| hex.len() == 64 && hex.is_ascii()) else {
+        return false;
+    };
+    let Ok(expected) = (0..hex.len()).step_by(2).map(|at| u8::from_str_radix(&hex[at..at + 2], 16)).collect::<std::result::Result<Vec<u8>, _>>() else {
+        return false;
+    };
+    mac(secret, timestamp, body).verify_slice(&expected).is_ok()
+}
+
+fn mac(secret: &[u8], timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Signs each delivery when set; see [`sign`].
+    pub secret: Option<String>,
+    /// Per attempt.
+    pub timeout: Duration,
+    /// Connection failures, 429 and 5xx are retried; other statuses aren't.
+    pub retry: RetryPolicy,
+}
+
+impl WebhookConfig {
+    pub fn new(url: &str) -> Self {
+        Self { url: url.to_string(), secret: None, timeout: Duration::from_secs(5), retry: RetryPolicy::default() }
+    }
+
+    pub fn with_secret(mut self, secret: &str) -> Self {
+        self.secret = Some(secret.to_string());
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+/// POSTs events as JSON to one endpoint.
+pub struct WebhookSink {
+    http: reqwest::Client,
+    config: WebhookConfig,
+}
+
+impl WebhookSink {
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let http = http_client(&config.url, config.timeout)?;
+        Ok(Self { http, config })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.config.url
+    }
+
+    /// Delivers `event`, retrying per the config. Returns how many attempts
+    /// it took.
+    pub async fn send(&self, event: &Event) -> Result<u32> {
+        let body = serde_json::to_vec(event)?;
+        let mut attempts = 0;
+        self.config
+            .retry
+            .run(|| {
+                attempts += 1;
+                self.post(event, &body)
+            })
+            .await?;
+        Ok(attempts)
+    }
+
+    async fn post(&self, event: &Event, body: &[u8]) -> Result<()> {
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut request = self
+            .http
+            .post(&self.config.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.kind.as_str())
+            .header(ID_HEADER, event.id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        if let Some(secret) = &self.config.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret.as_bytes(), timestamp, body));
+        }
+        deliver(request.body(body.to_vec()), &self.config.url).await
+    }
+}
+
+/// An HTTP client for `url`, refusing anything but http:// and https://.
+pub(crate) fn http_client(url: &str, timeout: Duration) -> Result<reqwest::Client> {
+    match url.split_once("://") {
+        Some(("http" | "https", rest)) if !rest.is_empty() => {}
+        _ => return Err(DemoError::Configuration(format!("Webhook URL must be http:// or https://, not '{}'", WebhookHost(url)))),
+    }
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| DemoError::Configuration(format!("Webhook client: {}", e)))
+}
+
+/// Sends `request`, turning a failure or any status but 2xx into
+/// `DemoError::Http` so [`RetryPolicy`] can tell what is worth retrying.
+pub(crate) async fn deliver(request: reqwest::RequestBuilder, url: &str) -> Result<()> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| DemoError::Http { status: e.status().map(|status| status.as_u16()), message: e.to_string() })?;
+    let status = response.status();
+    debug!("Webhook {} answered {}", WebhookHost(url), status);
+    match status.is_success() {
+        true => Ok(()),
+        false => Err(DemoError::Http { status: Some(status.as_u16()), message: format!("{} answered {}", WebhookHost(url), status) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_host_hides_the_token() {
+        let url = "https://hooks.slack.com/services/T000/B000/XXXXSECRET?token=abc";
+        assert_eq!(WebhookHost(url).to_string(), "https://hooks.slack.com");
+        assert_eq!(WebhookHost("http://user:pw@localhost:8080/hook").to_string(), "http://localhost:8080");
+        assert_eq!(WebhookHost("not a url").to_string(), "***");
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let body = br#"{"event":"job_failed"}"#;
+        let signature = sign(b"s3cret", 1_700_000_000, body);
+        assert!(signature.starts_with("sha256=") && signature.len() == 71);
+        assert!(verify(b"s3cret", 1_700_000_000, body, &signature));
+        assert!(!verify(b"s3cret", 1_700_000_001, body, &signature));
+        assert!(!verify(b"other", 1_700_000_000, body, &signature));
+        assert!(!verify(b"s3cret", 1_700_000_000, body, "sha256=zz"));
+        // 64 bytes, but 'é' straddles a two-byte hex pair.
+        let multibyte = format!("sha256=0{}{}", 'é', "0".repeat(61));
+        assert_eq!(multibyte.len(), 71);
+        assert!(!verify(b"s3cret", 1_700_000_000, body, &multibyte));
+    }
+
+    #[test]
+    fn test_rejects_non_http_urls() {
+        assert!(WebhookSink::new(WebhookConfig::new("ftp://example.com")).is_err());
+        assert!(WebhookSink::new(WebhookConfig::new("http://")).is_err());
+        assert!(WebhookSink::new(WebhookConfig::new("http://localhost:9000/hook")).is_ok());
+    }
+}
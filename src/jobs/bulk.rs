@@ -0,0 +1,318 @@
+use crate::utils::RedisConnection;
+use crate::{DemoError, RedisClient, Result};
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::{debug, info};
+
+/// Every job ever started, scored by start time in milliseconds.
+pub const JOBS_KEY: &str = "bulkjobs";
+/// How long a finished job's checkpoint is kept for inspection.
+const FINISHED_TTL_SECS: u64 = 7 * 24 * 3600;
+
+pub fn job_key(id: &str) -> String {
+    format!("bulkjob:{}", id)
+}
+
+/// Set by `jobs cancel`; the running job checks it at every checkpoint.
+pub fn cancel_key(id: &str) -> String {
+    format!("bulkjob:{}:cancel", id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JobState::Running => "running",
+            JobState::Completed => "completed",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        })
+    }
+}
+
+/// Where a bulk job got to. A job works through numbered phases (one per
+/// key pattern, say), each a SCAN from cursor 0 until it wraps back to 0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: String,
+    pub kind: String,
+    pub state: JobState,
+    pub phase: usize,
+    pub phases: usize,
+    pub cursor: u64,
+    /// Keys looked at so far, across every run of the job.
+    pub processed: u64,
+    /// Keys the job changed or deleted so far.
+    pub changed: u64,
+    /// Keys the job expects to process, when it can tell up front.
+    #[serde(default)]
+    pub total: Option<u64>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Checkpoint {
+    pub fn new(id: &str, kind: &str, phases: usize, now: DateTime<Utc>) -> Self {
+        Self {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            state: JobState::Running,
+            phase: 0,
+            phases: phases.max(1),
+            cursor: 0,
+            processed: 0,
+            changed: 0,
+            total: None,
+            started_at: now,
+            updated_at: now,
+            error: None,
+        }
+    }
+
+    /// The cursor to start `phase` from: `None` when an earlier run already
+    /// finished it.
+    pub fn start_cursor(&self, phase: usize) -> Option<u64> {
+        match phase.cmp(&self.phase) {
+            std::cmp::Ordering::Less => None,
+            std::cmp::Ordering::Equal => Some(self.cursor),
+            std::cmp::Ordering::Greater => Some(0),
+        }
+    }
+
+    /// Percent done: from `total` when the job knows it, otherwise by
+    /// finished phases, which only moves when a whole SCAN pass ends.
+    pub fn progress_pct(&self) -> f64 {
+        if self.state == JobState::Completed {
+            return 100.0;
+        }
+        let done = match self.total {
+            Some(total) if total > 0 => self.processed as f64 / total as f64,
+            _ => self.phase as f64 / self.phases as f64,
+        };
+        (done * 100.0).min(99.9)
+    }
+
+    /// Keys processed per second between the start and the last checkpoint.
+    pub fn rate(&self) -> f64 {
+        let secs = (self.updated_at - self.started_at).num_milliseconds() as f64 / 1000.0;
+        if secs <= 0.0 {
+            return 0.0;
+        }
+        self.processed as f64 / secs
+    }
+}
+
+/// A long-running keyspace operation whose progress is saved in Redis after
+/// every batch, so an interrupted run picks up at the last checkpoint with
+/// `--resume <job-id>`. A batch interrupted before its checkpoint is done
+/// again on resume, so the work must be safe to repeat.
+pub struct BulkJob {
+    conn: RedisConnection,
+    checkpoint: Checkpoint,
+}
+
+impl BulkJob {
+    pub async fn start(client: &RedisClient, kind: &str, phases: usize) -> Result<Self> {
+        let now = Utc::now();
+        let id = format!("{}-{}-{:04x}", kind, now.format("%Y%m%d%H%M%S"), rand::random::<u16>());
+        let checkpoint = Checkpoint::new(&id, kind, phases, now);
+        let mut job = Self { conn: client.get_async_connection().await?, checkpoint };
+        let _: () = job.conn.zadd(JOBS_KEY, &id, now.timestamp_millis()).await?;
+        job.save().await?;
+        info!("Started bulk job {}", id);
+        Ok(job)
+    }
+
+    /// Picks up a job of `kind` that was interrupted or failed.
+    pub async fn resume(client: &RedisClient, id: &str, kind: &str) -> Result<Self> {
+        let mut conn = client.get_async_connection().await?;
+        let mut checkpoint = Self::load(&mut conn, id)
+            .await?
+            .ok_or_else(|| DemoError::Configuration(format!("No bulk job '{}'", id)))?;
+        if checkpoint.kind != kind {
+            return Err(DemoError::Configuration(format!("Job '{}' is a {} job, not {}", id, checkpoint.kind, kind)));
+        }
+        if checkpoint.state == JobState::Completed {
+            return Err(DemoError::Configuration(format!("Job '{}' already completed", id)));
+        }
+        checkpoint.state = JobState::Running;
+        checkpoint.error = None;
+        let _: () = conn.del(cancel_key(id)).await?;
+        let mut job = Self { conn, checkpoint };
+        job.save().await?;
+        info!("Resuming bulk job {} at phase {} cursor {}", id, job.checkpoint.phase, job.checkpoint.cursor);
+        Ok(job)
+    }
+
+    pub async fn load(conn: &mut RedisConnection, id: &str) -> Result<Option<Checkpoint>> {
+        let json: Option<String> = conn.get(job_key(id)).await?;
+        Ok(json.map(|json| serde_json::from_str(&json)).transpose()?)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.checkpoint.id
+    }
+
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+
+    async fn save(&mut self) -> Result<()> {
+        self.checkpoint.updated_at = Utc::now();
+        let json = serde_json::to_string(&self.checkpoint)?;
+        let key = job_key(&self.checkpoint.id);
+        if self.checkpoint.state == JobState::Running {
+            let _: () = self.conn.set(key, json).await?;
+        } else {
+            let _: () = self.conn.set_ex(key, json, FINISHED_TTL_SECS).await?;
+        }
+        Ok(())
+    }
+
+    pub fn set_total(&mut self, total: u64) {
+        self.checkpoint.total = Some(total);
+    }
+
+    /// Records a finished batch: the cursor to continue `phase` from (0 once
+    /// the phase's scan is complete) and what the batch did. Fails once
+    /// cancellation was requested, leaving the job resumable.
+    pub async fn advance(&mut self, phase: usize, cursor: u64, processed: u64, changed: u64) -> Result<()> {
+        let checkpoint = &mut self.checkpoint;
+        (checkpoint.phase, checkpoint.cursor) = if cursor == 0 { (phase + 1, 0) } else { (phase, cursor) };
+        checkpoint.processed += processed;
+        checkpoint.changed += changed;
+        debug!("Job {} checkpoint: phase {} cursor {}", checkpoint.id, checkpoint.phase, checkpoint.cursor);
+        let cancelled: bool = self.conn.exists(cancel_key(&self.checkpoint.id)).await?;
+        if cancelled {
+            self.checkpoint.state = JobState::Cancelled;
+        }
+        self.save().await?;
+        if cancelled {
+            return Err(DemoError::Demo(format!("Job {} was cancelled", self.checkpoint.id)));
+        }
+        Ok(())
+    }
+
+    pub async fn complete(&mut self) -> Result<()> {
+        self.checkpoint.state = JobState::Completed;
+        self.save().await
+    }
+
+    /// Marks the job failed, unless it stopped because it was cancelled.
+    pub async fn fail(&mut self, error: &DemoError) -> Result<()> {
+        if self.checkpoint.state == JobState::Cancelled {
+            return Ok(());
+        }
+        self.checkpoint.state = JobState::Failed;
+        self.checkpoint.error = Some(error.to_string());
+        self.save().await
+    }
+}
+
+/// Every job whose checkpoint is still kept, newest first. Ids whose
+/// checkpoint expired are dropped from the index.
+pub async fn list(conn: &mut RedisConnection) -> Result<Vec<Checkpoint>> {
+    let ids: Vec<String> = conn.zrevrange(JOBS_KEY, 0, -1).await?;
+    let mut jobs = Vec::with_capacity(ids.len());
+    for id in ids {
+        match BulkJob::load(conn, &id).await? {
+            Some(checkpoint) => jobs.push(checkpoint),
+            None => {
+                let _: () = conn.zrem(JOBS_KEY, &id).await?;
+            }
+        }
+    }
+    Ok(jobs)
+}
+
+/// Asks a running job to stop at its next checkpoint. Returns the job's
+/// state, or `None` if there is no such job; only running jobs are flagged.
+pub async fn request_cancel(conn: &mut RedisConnection, id: &str) -> Result<Option<JobState>> {
+    let Some(checkpoint) = BulkJob::load(conn, id).await? else {
+        return Ok(None);
+    };
+    if checkpoint.state == JobState::Running {
+        let _: () = conn.set_ex(cancel_key(id), 1, FINISHED_TTL_SECS).await?;
+    }
+    Ok(Some(checkpoint.state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_cursor_skips_finished_phases() {
+        let mut checkpoint = Checkpoint::new("gc-1", "gc-indexes", 2, Utc::now());
+        checkpoint.phase = 1;
+        checkpoint.cursor = 4096;
+        assert_eq!(checkpoint.start_cursor(0), None);
+        assert_eq!(checkpoint.start_cursor(1), Some(4096));
+        assert_eq!(checkpoint.start_cursor(2), Some(0));
+    }
+
+    #[test]
+    fn test_progress_and_rate() {
+        let started = Utc::now();
+        let mut checkpoint = Checkpoint::new("gc-1", "gc-indexes", 2, started);
+        checkpoint.phase = 1;
+        assert_eq!(checkpoint.progress_pct(), 50.0);
+        checkpoint.total = Some(400);
+        checkpoint.processed = 100;
+        assert_eq!(checkpoint.progress_pct(), 25.0);
+        checkpoint.processed = 500;
+        assert_eq!(checkpoint.progress_pct(), 99.9);
+        checkpoint.state = JobState::Completed;
+        assert_eq!(checkpoint.progress_pct(), 100.0);
+
+        assert_eq!(checkpoint.rate(), 0.0);
+        checkpoint.updated_at = started + chrono::Duration::seconds(10);
+        assert_eq!(checkpoint.rate(), 50.0);
+    }
+
+    #[test]
+    fn test_checkpoint_json() {
+        let checkpoint = Checkpoint::new("gc-1", "gc-indexes", 2, Utc::now());
+        let json = serde_json::to_string(&checkpoint).unwrap();
+        assert!(json.contains(r#""state":"running""#) && !json.contains("error"));
+        assert_eq!(serde_json::from_str::<Checkpoint>(&json).unwrap(), checkpoint);
+    }
+
+    #[tokio::test]
+    async fn test_resume_continues_from_checkpoint() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap().with_key_prefix("test:");
+        let mut job = BulkJob::start(&client, "test-job", 2).await.unwrap();
+        job.advance(0, 0, 10, 1).await.unwrap();
+        job.advance(1, 512, 5, 0).await.unwrap();
+        let id = job.id().to_string();
+        drop(job);
+
+        let mut resumed = BulkJob::resume(&client, &id, "test-job").await.unwrap();
+        assert_eq!((resumed.checkpoint().phase, resumed.checkpoint().cursor), (1, 512));
+        assert_eq!((resumed.checkpoint().processed, resumed.checkpoint().changed), (15, 1));
+        assert!(BulkJob::resume(&client, &id, "other-job").await.is_err());
+
+        let mut conn = client.get_async_connection().await.unwrap();
+        assert_eq!(request_cancel(&mut conn, &id).await.unwrap(), Some(JobState::Running));
+        assert!(resumed.advance(1, 0, 1, 0).await.is_err());
+        assert_eq!(BulkJob::load(&mut conn, &id).await.unwrap().unwrap().state, JobState::Cancelled);
+        assert_eq!(list(&mut conn).await.unwrap()[0].id, id);
+
+        let mut resumed = BulkJob::resume(&client, &id, "test-job").await.unwrap();
+        resumed.complete().await.unwrap();
+        assert!(BulkJob::resume(&client, &id, "test-job").await.is_err());
+        client.delete_prefixed_keys().await.unwrap();
+    }
+}
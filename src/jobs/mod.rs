@@ -0,0 +1,5 @@
+pub mod bulk;
+pub mod status;
+
+pub use bulk::{BulkJob, Checkpoint, JobState};
+pub use status::{render_status, render_table};
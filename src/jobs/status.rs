@@ -0,0 +1,76 @@
+use super::bulk::Checkpoint;
+use std::fmt::Write;
+
+/// One line per job for `jobs list`.
+pub fn render_table(jobs: &[Checkpoint]) -> String {
+    let mut out = format!(
+        "{:<36} {:<10} {:>7} {:>10} {:>9} {:>10}  {}\n",
+        "job", "state", "done", "processed", "changed", "keys/s", "started"
+    );
+    for job in jobs {
+        let _ = writeln!(
+            out,
+            "{:<36} {:<10} {:>6.1}% {:>10} {:>9} {:>10.0}  {}",
+            job.id,
+            job.state.to_string(),
+            job.progress_pct(),
+            job.processed,
+            job.changed,
+            job.rate(),
+            job.started_at.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    out
+}
+
+/// Everything known about one job, for `jobs status`.
+pub fn render_status(job: &Checkpoint) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{} ({})", job.id, job.kind);
+    let _ = writeln!(out, "   state:     {}", job.state);
+    let _ = writeln!(out, "   progress:  {:.1}% (phase {} of {})", job.progress_pct(), (job.phase + 1).min(job.phases), job.phases);
+    match job.total {
+        Some(total) => {
+            let _ = writeln!(out, "   processed: {} of {}", job.processed, total);
+        }
+        None => {
+            let _ = writeln!(out, "   processed: {}", job.processed);
+        }
+    }
+    let _ = writeln!(out, "   changed:   {}", job.changed);
+    let _ = writeln!(out, "   rate:      {:.0} keys/s", job.rate());
+    let _ = writeln!(out, "   cursor:    {}", job.cursor);
+    let _ = writeln!(out, "   started:   {}", job.started_at.format("%Y-%m-%d %H:%M:%S UTC"));
+    let _ = writeln!(out, "   updated:   {}", job.updated_at.format("%Y-%m-%d %H:%M:%S UTC"));
+    if let Some(error) = &job.error {
+        let _ = writeln!(out, "   error:     {}", error);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::JobState;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_render() {
+        let started = Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap();
+        let mut job = Checkpoint::new("gc-indexes-20261017090000-00ff", "gc-indexes", 2, started);
+        job.phase = 1;
+        job.processed = 600;
+        job.changed = 3;
+        job.updated_at = started + chrono::Duration::seconds(60);
+        job.state = JobState::Cancelled;
+
+        let table = render_table(&[job.clone()]);
+        let row = table.lines().nth(1).unwrap();
+        assert!(row.starts_with("gc-indexes-20261017090000-00ff"));
+        assert!(row.contains("cancelled") && row.contains("50.0%") && row.contains("2026-10-17 09:00:00"));
+
+        let status = render_status(&job);
+        assert!(status.contains("phase 2 of 2"));
+        assert!(status.contains("rate:      10 keys/s"));
+    }
+}
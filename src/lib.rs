@@ -1,6 +1,32 @@
+// Lets `#[derive(RedisEntity)]` name this crate the same way inside and out.
+extern crate self as redis_rust_demo;
+
+pub mod bench;
+pub mod cache;
 pub mod cli;
+pub mod cluster;
+pub mod consistency;
 pub mod demos;
+pub mod experiments;
+pub mod export;
+pub mod inspect;
+pub mod integrations;
+pub mod jobs;
+pub mod maintenance;
+pub mod metrics;
+pub mod mirror;
 pub mod models;
+#[cfg(feature = "offline")]
+pub mod offline;
+pub mod proxy;
+pub mod queue;
+pub mod quotas;
+pub mod ratelimit;
+pub mod report;
+pub mod repository;
+pub mod resp;
+pub mod scheduler;
+pub mod server;
 pub mod utils;
 
 pub use utils::{DemoError, RedisClient, Result};
\ No newline at end of file
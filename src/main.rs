@@ -1,26 +1,136 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use redis_rust_demo::{RedisClient, Result};
-use redis_rust_demo::cli::{Cli, Commands, BasicOperations};
-use redis_rust_demo::demos::{BasicOpsDemo, ListDemo, SetDemo, HashDemo, RustErrorsDemo};
-use tracing::{info, error};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use redis_rust_demo::cli::{confirm, Cli, Commands, ConfirmOptions, AdminCommands, AnalyticsCommands, AuditCommands, BasicOperations, BenchCommands, CheckCommands, ClusterCommands, ConfigCommands, ExperimentCommands, ExportCommands, InspectCommands, JobCommands, KeyCommands, MaintenanceCommands, MaintenanceTasks, MetricsCommands, ModelCommands, MonitorCommands, PatternCommands, PubSubCommands, QuotaCommands, ReplicationCommands, SchedulerCommands, StatsCommands, StreamCommands, UserCommands, VotingCommands, WebhookCommands, WorkflowCommands};
+use redis_rust_demo::demos::{
+    AnalyticsDemo, BasicOpsDemo, BitmapDemo, CardinalityDemo, ClusterPitfallsDemo, ConfigWatchDemo, ConnectionStormDemo, DataModelDemo, DiagramFormat, FailoverDemo, FailoverMode, GeoDemo, PipelineDemo, PubSubDemo, HashDemo, ListDemo,
+    RustErrorsDemo, ScriptingDemo, SetDemo, StormOptions, SortedSetDemo,
+};
+use redis_rust_demo::bench::{commands, hdr, keygen, AdaptiveBench, AutoPipelineBench, BytesBench, CommandBench, CommandBenchOptions, ExportBench, FieldWriteBench, GenOptions, HydrationBench, KeyTemplate, LoadBench, LoadOptions, ScanBench, SoakBench, StrategyBench, Workload};
+use redis_rust_demo::cluster::{self, reshard, Change, Distribution, KeyEstimate, ReshardPlan, SlotMap};
+use redis_rust_demo::consistency::{render_report, CartProductsExist, ConsistencyChecker, ConsistencyReport, EntitiesValid, Invariant, Severity, UserIndexesPresent};
+use redis_rust_demo::demos::analytics::RetentionFormat;
+use redis_rust_demo::experiments::{tally, ExperimentDemo, ExperimentStore};
+use redis_rust_demo::export::{self, ExportOptions, ImportFormat, Importer, JsonExporter, Table, TableFormat};
+use redis_rust_demo::inspect::{self, WireInspector};
+use redis_rust_demo::integrations::{self, grafana, AlertNotifier, Event, EventKind, MetricsConfig, MetricsExporter, WebhookConfig, WebhookHost, WebhookSink};
+use redis_rust_demo::jobs::{self, BulkJob, JobState};
+use redis_rust_demo::maintenance::{BatchRename, GcOptions, IndexGc, IndexSpec, RenameOptions, RenamePlan};
+use redis_rust_demo::metrics::{ClientKeySnapshot, RollupDemo, RollupHandler};
+use redis_rust_demo::report::{write_jsonl, DiffRecord, ReportFormat};
+use redis_rust_demo::models::User;
+use redis_rust_demo::repository::{audit, UserRepository};
+use redis_rust_demo::server::{advise, fan_out, fleet, render_suggestions, ReplicaLagThresholds, ServerConfig, TargetResult};
+use redis_rust_demo::quotas::{monthly, NamespaceQuotaDemo, QuotaDemo, QuotaManager, QuotaPlan};
+use redis_rust_demo::cache::CacheDemo;
+use redis_rust_demo::ratelimit::{Algorithm, RateLimit, RateLimitDemo};
+use redis_rust_demo::scheduler::{HandlerRegistry, RecurringJob, RecurringScheduler};
+use redis_rust_demo::proxy::{self, LatencyProxy, NetworkConditions};
+use redis_rust_demo::queue::{PriorityAgingDemo, QueueDemo};
+use redis_rust_demo::demos::streams::{
+    archive, lag, replay, ArchiveOptions, LagFormat, LagThresholds, ReplayOptions, ReplayTarget, StreamArchiver, StreamLagMonitor, StreamReplayer,
+};
+use redis_rust_demo::demos::patterns::{
+    CalendarDemo, CouponDemo, CrdtDemo, FeedDemo, GraphDemo, InventoryDemo, LockDemo, MaintenanceDemo, SoftDeleteDemo, VotingDemo, WaitlistDemo, WorkflowStore, WorkflowTimeoutHandler,
+};
+use redis_rust_demo::demos::patterns::maintenance;
+use redis_rust_demo::demos::patterns::workflow::render_workflow;
+use redis_rust_demo::utils::cluster::same_slot;
+use redis_rust_demo::utils::config_watch::{self, ReloadStrategy};
+use redis_rust_demo::utils::key_history::{self, KeyHistory};
+use redis_rust_demo::utils::key_stats::KeyStats;
+use redis_rust_demo::utils::key_watch::{self, WatchOptions};
+use redis_rust_demo::utils::{telemetry, Cassette, DemoSteps, DisplaySafe, RedisUrl, TimingBudget, TlsOptions};
+use std::sync::Arc;
+use tracing::{info, error, Instrument};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "redis_rust_demo=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-    
     // Parse CLI arguments
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    
+    // Initialize tracing; held until the end so batched spans get flushed
+    let _telemetry = telemetry::init(cli.log_format.parse()?, cli.otlp_endpoint.as_deref())?;
+    
+    // Everything the command does happens inside its demo span
+    let steps = DemoSteps::new(&command_path(&matches));
+    let span = steps.span().clone();
+    run(cli, steps).instrument(span).await
+}
+
+/// `bench load`, `patterns feed`: the subcommand names that were given.
+fn command_path(matches: &clap::ArgMatches) -> String {
+    let mut path = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        path.push(name);
+        current = sub;
+    }
+    path.join(" ")
+}
+
+async fn run(cli: Cli, steps: DemoSteps) -> Result<()> {
+    // Several --redis-url: run the diagnostic against each and compare
+    if cli.redis_url.len() > 1 {
+        return compare_targets(&cli.command, &cli.redis_url, &cli.tls()).await;
+    }
+    
+    // With --offline, serve the demos from an embedded server for this run
+    #[cfg(feature = "offline")]
+    let offline = match cli.offline {
+        true => Some(redis_rust_demo::offline::MiniRedis::start().await?),
+        false => None,
+    };
+    #[cfg(feature = "offline")]
+    let redis_url = offline.as_ref().map_or_else(|| cli.redis_url[0].clone(), |server| server.url());
+    #[cfg(not(feature = "offline"))]
+    let redis_url = match cli.offline {
+        true => return Err(redis_rust_demo::DemoError::Configuration("--offline needs a build with --features offline".to_string())),
+        false => cli.redis_url[0].clone(),
+    };
+    
+    // Create Redis client; demo keys live under --key-prefix
+    let tls = cli.tls();
+    let mut base_client = RedisClient::builder(&redis_url).tls(tls.clone()).build()?;
+    let cassette = match (&cli.record, &cli.replay) {
+        (Some(path), _) => Some(Arc::new(Cassette::record(path)?)),
+        (None, Some(path)) => Some(Arc::new(Cassette::replay(path)?)),
+        (None, None) => None,
+    };
+    if let Some(cassette) = &cassette {
+        base_client = base_client.with_cassette(cassette.clone());
+    }
+    if !cli.command.reads_existing_keys() {
+        base_client = base_client.with_key_prefix(&cli.key_prefix);
+    }
+    let key_stats = (cli.key_stats && !matches!(cli.command, Commands::Stats { .. })).then(|| Arc::new(KeyStats::new(10_000)));
+    let mut redis_client = base_client.clone().with_observer(steps.keys());
+    if let Some(stats) = &key_stats {
+        redis_client = redis_client.with_observer(stats.clone());
+    }
+    let budget = cli.budget_ms.map(|ms| Arc::new(TimingBudget::new(std::time::Duration::from_millis(ms))));
+    if let Some(budget) = &budget {
+        redis_client = redis_client.with_observer(budget.clone());
+    }
+    let command_line = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    let history = (!cli.history.is_empty()).then(|| Arc::new(KeyHistory::new(cli.history.clone(), base_client.key_prefix())));
+    if let Some(history) = &history {
+        history.set_step(&command_line);
+        redis_client = redis_client.with_observer(history.clone());
+    }
+    let steps = steps.with_budget(budget.clone()).with_history(history.clone());
+    let run = budget.as_ref().map(|budget| budget.begin(&command_line));
+    
+    let metrics_config = metrics_config(&cli)?;
+    if metrics_config.is_enabled() && !cli.command.exports_metrics() {
+        return Err(redis_rust_demo::DemoError::Configuration(
+            "--push-gateway and --metrics-listen apply to bench load, bench soak, replication lag, streams lag, cache and pattern backpressure".to_string(),
+        ));
+    }
+    let metrics = MetricsExporter::start(metrics_config).await?;
     
-    // Create Redis client
-    let redis_client = RedisClient::new(&cli.redis_url)?;
+    let safety = ConfirmOptions { yes: cli.yes, allow_db0: cli.allow_db0 };
+    let db = redis_client.get_connection_info().redis.db;
     
     // Execute command
     match cli.command {
@@ -29,12 +139,12 @@ async fn main() -> Result<()> {
             match redis_client.ping().await {
                 Ok(()) => {
                     println!("✅ Successfully connected to Redis!");
-                    println!("Redis URL: {}", cli.redis_url);
+                    println!("Redis URL: {}", DisplaySafe(&redis_url));
                 }
                 Err(e) => {
                     error!("Failed to connect to Redis: {}", e);
                     println!("❌ Failed to connect to Redis: {}", e);
-                    println!("Make sure Redis is running on {}", cli.redis_url);
+                    println!("Make sure Redis is running on {}", DisplaySafe(&redis_url));
                 }
             }
         }
@@ -42,35 +152,1166 @@ async fn main() -> Result<()> {
             match operation {
                 BasicOperations::Strings => {
                     let demo = BasicOpsDemo::new(redis_client);
-                    demo.string_operations().await?;
-                    demo.key_operations().await?;
+                    steps.run("string operations", demo.string_operations()).await?;
+                    steps.run("key operations", demo.key_operations()).await?;
                 }
                 BasicOperations::Lists => {
                     let demo = ListDemo::new(redis_client);
-                    demo.demonstrate().await?;
+                    steps.run("list operations", demo.demonstrate()).await?;
                 }
                 BasicOperations::Sets => {
                     let demo = SetDemo::new(redis_client);
-                    demo.demonstrate().await?;
+                    steps.run("set operations", demo.demonstrate()).await?;
                 }
                 BasicOperations::Hashes => {
                     let demo = HashDemo::new(redis_client);
-                    demo.demonstrate().await?;
+                    steps.run("hash operations", demo.demonstrate()).await?;
+                }
+                BasicOperations::SortedSets => {
+                    let demo = SortedSetDemo::new(redis_client);
+                    steps.run("sorted set operations", demo.demonstrate()).await?;
+                }
+                BasicOperations::Geo => {
+                    let demo = GeoDemo::new(redis_client);
+                    steps.run("geo operations", demo.demonstrate()).await?;
+                }
+            }
+        }
+        Commands::Admin { command: AdminCommands::Maintenance { command } } => {
+            let action = match &command {
+                MaintenanceCommands::On { .. } => Some("turn maintenance mode on"),
+                MaintenanceCommands::Off => Some("turn maintenance mode off"),
+                MaintenanceCommands::Status => None,
+            };
+            if let Some(action) = action {
+                if !confirm(action, db, safety)? {
+                    println!("Aborted");
+                    return Ok(());
+                }
+            }
+            let mut conn = redis_client.get_async_connection().await?;
+            match command {
+                MaintenanceCommands::On { message, for_secs } => {
+                    maintenance::enable(&mut conn, &message, for_secs.map(std::time::Duration::from_secs)).await?;
+                    println!("🚧 Maintenance mode on: {}", message);
+                }
+                MaintenanceCommands::Off => {
+                    if maintenance::disable(&mut conn).await? {
+                        println!("✅ Maintenance mode off");
+                    } else {
+                        println!("Maintenance mode was not on");
+                    }
+                }
+                MaintenanceCommands::Status => match maintenance::status(&mut conn).await? {
+                    Some(notice) => println!("🚧 On since {}: {}", notice.since.format("%Y-%m-%d %H:%M:%S UTC"), notice.message),
+                    None => println!("✅ Off"),
+                },
+            }
+        }
+        Commands::Analytics { command } => {
+            match command {
+                AnalyticsCommands::Stats { users } => {
+                    let demo = AnalyticsDemo::new(redis_client);
+                    steps.run("stats", demo.stats(users)).await?;
+                }
+                AnalyticsCommands::Retention { users, weeks, format } => {
+                    let format: RetentionFormat = format.parse()?;
+                    let demo = AnalyticsDemo::new(redis_client);
+                    let triangle = steps.run("retention", demo.retention(users, weeks)).await?;
+                    print!("{}", triangle.render(format));
+                }
+                AnalyticsCommands::Funnel { users, days, from, to } => {
+                    let demo = AnalyticsDemo::new(redis_client);
+                    steps.run("funnel", demo.funnel(users, days, from, to)).await?;
+                }
+            }
+        }
+        Commands::Pattern { pattern } => {
+            match pattern {
+                PatternCommands::Maintenance { grace_secs } => {
+                    let demo = MaintenanceDemo::new(redis_client);
+                    steps.run("maintenance mode", demo.demonstrate(std::time::Duration::from_secs(grace_secs))).await?;
+                }
+                PatternCommands::SoftDelete { trash_ttl_secs } => {
+                    let demo = SoftDeleteDemo::new(redis_client);
+                    steps.run("soft delete", demo.demonstrate(std::time::Duration::from_secs(trash_ttl_secs))).await?;
+                }
+                PatternCommands::Backpressure { jobs, max_len, reject } => {
+                    let mut demo = QueueDemo::new(redis_client);
+                    if let Some(metrics) = metrics {
+                        demo = demo.with_metrics(metrics);
+                    }
+                    steps.run("backpressure", demo.backpressure(jobs, max_len, reject)).await?;
+                }
+                PatternCommands::PriorityAging { low_jobs, rounds, boost } => {
+                    let demo = PriorityAgingDemo::new(redis_client);
+                    steps.run("priority aging", demo.demonstrate(low_jobs, rounds, boost)).await?;
+                }
+                PatternCommands::Calendar => {
+                    let demo = CalendarDemo::new(redis_client);
+                    steps.run("calendar", demo.demonstrate()).await?;
+                }
+                PatternCommands::Coupons { batch, max_uses, contenders } => {
+                    let demo = CouponDemo::new(redis_client);
+                    steps.run("coupons", demo.demonstrate(batch, max_uses, contenders)).await?;
+                }
+                PatternCommands::Crdt { actors, writes, seed } => {
+                    let demo = CrdtDemo::new(redis_client);
+                    steps.run("crdt simulation", demo.simulate(actors, writes, seed)).await?;
+                }
+                PatternCommands::Feed { users, posts } => {
+                    let demo = FeedDemo::new(redis_client);
+                    steps.run("feed", demo.demonstrate(users, posts)).await?;
+                }
+                PatternCommands::Graph { users, depth } => {
+                    let demo = GraphDemo::new(redis_client);
+                    steps.run("graph", demo.demonstrate(users, depth)).await?;
+                }
+                PatternCommands::Inventory { buyers, stock, hold_ms } => {
+                    let demo = InventoryDemo::new(redis_client);
+                    steps.run("inventory", demo.demonstrate(buyers, stock, hold_ms)).await?;
+                }
+                PatternCommands::Lock { workers, rounds, ttl_ms } => {
+                    let demo = LockDemo::new(redis_client);
+                    steps.run("lock", demo.demonstrate(workers, rounds, std::time::Duration::from_millis(ttl_ms))).await?;
+                }
+                PatternCommands::Waitlist { users, batch } => {
+                    let demo = WaitlistDemo::new(redis_client);
+                    steps.run("waitlist", demo.demonstrate(users, batch)).await?;
+                }
+                PatternCommands::Voting { command: VotingCommands::Simulate { users, items, burst } } => {
+                    let demo = VotingDemo::new(redis_client);
+                    steps.run("voting simulation", demo.simulate(users, items, burst)).await?;
                 }
             }
         }
         Commands::RustErrors => {
             let demo = RustErrorsDemo::new(redis_client);
-            demo.demonstrate_ownership_errors().await?;
-            demo.demonstrate_lifetime_errors().await?;
-            demo.demonstrate_type_errors().await?;
-            demo.demonstrate_async_errors().await?;
-            demo.demonstrate_error_handling().await?;
-            demo.demonstrate_performance_pitfalls().await?;
-            demo.cleanup().await?;
+            steps.run("ownership errors", demo.demonstrate_ownership_errors()).await?;
+            steps.run("lifetime errors", demo.demonstrate_lifetime_errors()).await?;
+            steps.run("type errors", demo.demonstrate_type_errors()).await?;
+            steps.run("async errors", demo.demonstrate_async_errors()).await?;
+            steps.run("error handling", demo.demonstrate_error_handling()).await?;
+            steps.run("performance pitfalls", demo.demonstrate_performance_pitfalls()).await?;
             println!("\n✅ Rust errors demonstration completed!");
         }
+        Commands::ConnectionStorm { callers, pool_size, maxclients } => {
+            if let Some(limit) = maxclients {
+                if !confirm(&format!("lower the server's maxclients to {} for this run", limit), db, safety)? {
+                    println!("Aborted");
+                    return Ok(());
+                }
+            }
+            let demo = ConnectionStormDemo::new(redis_client);
+            steps.run("connection storm", demo.demonstrate(&StormOptions { callers, pool_size, maxclients })).await?;
+        }
+        Commands::Proxy { listen, target, latency, jitter, drop } => {
+            let conditions = NetworkConditions { latency, jitter, drop_rate: drop };
+            let proxy = LatencyProxy::start(proxy::listen_addr(&listen)?, &target, conditions).await?;
+            println!("Proxying {} -> {} with {:?} ± {:?} latency, {:.1}% dropped", proxy.addr(), target, latency, jitter, drop * 100.0);
+            println!("Point demos and benches at it with --redis-url {} (Ctrl-C to stop)", proxy.url());
+            tokio::signal::ctrl_c().await?;
+            println!("\n{}", proxy.stats().summary());
+        }
+        Commands::Pipeline { count } => {
+            let demo = PipelineDemo::new(redis_client);
+            steps.run("pipelining", demo.demonstrate(count)).await?;
+        }
+        Commands::Scripting => {
+            let demo = ScriptingDemo::new(redis_client);
+            steps.run("eval and evalsha", demo.demonstrate_basics()).await?;
+            steps.run("script manager", demo.demonstrate_script_manager()).await?;
+        }
+        Commands::Bitmaps { users, days } => {
+            let demo = BitmapDemo::new(redis_client);
+            steps.run("bitmaps", demo.demonstrate(users, days)).await?;
+        }
+        Commands::CompareCardinality { n, false_positive_rate } => {
+            let demo = CardinalityDemo::new(redis_client);
+            steps.run("cardinality", demo.compare(n, false_positive_rate)).await?;
+        }
+        Commands::Config { command } => {
+            let demo = ConfigWatchDemo::new(redis_client.clone());
+            match command {
+                ConfigCommands::Watch { name, seconds, strategy } => {
+                    let strategy: ReloadStrategy = strategy.parse()?;
+                    let change = match strategy {
+                        ReloadStrategy::KeyspaceEvents(_) => key_watch::notify_change(&redis_client, config_watch::VERSION_FLAGS).await?,
+                        ReloadStrategy::Poll(_) => None,
+                    };
+                    if let Some(change) = &change {
+                        if !confirm(&change.describe(), db, safety)? {
+                            println!("Aborted");
+                            return Ok(());
+                        }
+                    }
+                    steps.run("config watch", demo.watch(&name, seconds, strategy, change)).await?;
+                }
+                ConfigCommands::Set { name, field, value } => {
+                    demo.set(&name, &field, &value).await?;
+                }
+            }
+        }
+        Commands::Experiments { command } => {
+            match command {
+                ExperimentCommands::Simulate { name, users, control_rate, treatment_rate, seed } => {
+                    let demo = ExperimentDemo::new(redis_client);
+                    steps.run("experiment simulation", demo.simulate(&name, users, control_rate, treatment_rate, seed)).await?;
+                }
+                ExperimentCommands::Results { name } => {
+                    let mut store = ExperimentStore::new(&redis_client).await?;
+                    match store.load(&name).await? {
+                        Some(experiment) => print!("{}", tally::render_results(&store.results(&experiment).await?)),
+                        None => println!("No experiment '{}'", name),
+                    }
+                }
+            }
+        }
+        Commands::Advise => {
+            let mut conn = redis_client.get_async_connection().await?;
+            let config = ServerConfig::fetch(&mut conn).await?;
+            let suggestions = advise(&config);
+            if suggestions.is_empty() {
+                println!("✅ No suggestions: the server is tuned for these workloads");
+            } else {
+                print!("{}", render_suggestions(&suggestions));
+            }
+        }
+        Commands::Audit { command: AuditCommands::Show { entity, id, count } } => {
+            let mut conn = redis_client.get_async_connection().await?;
+            let entries = audit::history(&mut conn, &entity, &id, count).await?;
+            if entries.is_empty() {
+                println!("No audit history at {}", audit::audit_key(&entity, &id));
+            }
+            for entry in entries {
+                let at = chrono::DateTime::from_timestamp_millis(entry.at_ms as i64).unwrap_or_default();
+                println!("{} {:<8} by {}", at.format("%Y-%m-%d %H:%M:%S%.3f"), entry.op, entry.actor);
+                if let Some(diff) = entry.diff.as_object() {
+                    for (field, change) in diff {
+                        println!("   {}: {} → {}", field, change["from"], change["to"]);
+                    }
+                }
+            }
+        }
+        Commands::Pubsub { command } => {
+            let demo = PubSubDemo::new(redis_client);
+            match command {
+                PubSubCommands::Demo => steps.run("pub/sub", demo.demonstrate()).await?,
+                PubSubCommands::Publish { channel, message } => {
+                    demo.publish(&channel, &message).await?;
+                }
+                PubSubCommands::Subscribe { channels, pattern, limit } => {
+                    let received = demo.subscribe(&channels, &pattern, limit).await?;
+                    println!("\n{} messages", received);
+                }
+            }
+        }
+        Commands::Key { command: KeyCommands::History { key, count } } => {
+            let mut conn = redis_client.get_async_connection().await?;
+            let events = key_history::load(&mut conn, &key).await?;
+            if events.is_empty() {
+                println!("No history at {}; run a demo with --history '<pattern>' to record some", key_history::history_key(&key));
+            } else {
+                println!("{}: {} recorded changes\n", key, events.len());
+                print!("{}", key_history::render(&key, &events, count));
+            }
+        }
+        Commands::Key { command: KeyCommands::Show { key, limit } } => {
+            let mut conn = redis_client.get_async_connection().await?;
+            match inspect::KeyDetails::fetch(&mut conn, &key).await? {
+                Some(details) => print!("{}", details.render(limit)),
+                None => println!("No key {}", key),
+            }
+        }
+        Commands::Key { command: KeyCommands::Set { key, change } } => {
+            let edit = change.into_edit()?;
+            let mut conn = redis_client.get_async_connection().await?;
+            edit.check(&key, inspect::edit::current_type(&mut conn, &key).await?)?;
+            if !confirm(&edit.describe(&key), db, safety)? {
+                println!("Aborted");
+                return Ok(());
+            }
+            edit.apply(&mut conn, &key).await?;
+            if let Some(details) = inspect::KeyDetails::fetch(&mut conn, &key).await? {
+                print!("{}", details.render(20));
+            }
+        }
+        Commands::Key { command: KeyCommands::Gen { template, count, value_size, start, ttl_secs, batch_size, seed } } => {
+            let template: KeyTemplate = template.parse()?;
+            let mut options = GenOptions::new(template, count);
+            options.value_size = value_size.parse()?;
+            options.start = start;
+            options.ttl = ttl_secs.map(std::time::Duration::from_secs);
+            options.batch_size = batch_size;
+            options.seed = seed;
+            let example = options.template.render(start, &mut rand::thread_rng());
+            if !confirm(&format!("write {} keys like {}", count, example), db, safety)? {
+                println!("Aborted");
+                return Ok(());
+            }
+            let summary = steps.run("generate", keygen::generate(&redis_client, &options)).await?;
+            println!(
+                "Wrote {} keys ({} value bytes) in {:.2?}, {:.0} keys/s",
+                summary.keys,
+                summary.value_bytes,
+                summary.elapsed,
+                summary.keys_per_second()
+            );
+        }
+        Commands::Key { command: KeyCommands::Watch { pattern, values, limit } } => {
+            let change = key_watch::notify_change(&redis_client, key_watch::WATCH_FLAGS).await?;
+            if let Some(change) = &change {
+                if !confirm(&change.describe(), db, safety)? {
+                    println!("Aborted");
+                    return Ok(());
+                }
+            }
+            let seen = key_watch::watch(&redis_client, &pattern, &WatchOptions { values, limit }, change).await?;
+            println!("\n{} events", seen);
+        }
+        Commands::Bench { command: BenchCommands::Hydration { users, page_size } } => {
+            let bench = HydrationBench::new(redis_client);
+            bench.run(users, page_size).await?;
+        }
+        Commands::Bench {
+            command:
+                BenchCommands::Load {
+                    requests,
+                    concurrency,
+                    keyspace,
+                    value_size,
+                    distribution,
+                    workload,
+                    warmup,
+                    min_duration,
+                    interval_ms,
+                    hgrm,
+                    hlog,
+                    compare,
+                },
+        } => {
+            let mut workload = match workload {
+                Some(profile) => Workload::load(&profile)?,
+                None => Workload::get_set(keyspace, value_size),
+            };
+            if let Some(distribution) = distribution {
+                workload.keys.distribution = distribution.parse()?;
+            }
+            let opts = LoadOptions {
+                workload,
+                warmup,
+                min_duration: std::time::Duration::from_secs(min_duration),
+                interval: std::time::Duration::from_millis(interval_ms),
+                hgrm: hgrm.map(Into::into),
+                hlog: hlog.map(Into::into),
+                compare: compare.map(Into::into),
+                ..LoadOptions::new(requests, concurrency)
+            };
+            let report = LoadBench::new(redis_client).run(&opts).await?;
+            if let Some(exporter) = &metrics {
+                let mut sample = exporter.metric_set();
+                report.record_metrics(&mut sample);
+                exporter.publish(&sample).await;
+            }
+        }
+        Commands::Bench { command: BenchCommands::Compare { baseline, current, format } } => {
+            let format: ReportFormat = format.parse()?;
+            let diffs = hdr::compare(
+                &hdr::parse_percentiles(&std::fs::read_to_string(&baseline)?)?,
+                &hdr::parse_percentiles(&std::fs::read_to_string(&current)?)?,
+            );
+            match format {
+                ReportFormat::Text => print!("{}", hdr::render_comparison(&diffs)),
+                ReportFormat::Jsonl => {
+                    let records: Vec<DiffRecord> = diffs.iter().map(DiffRecord::from).collect();
+                    write_jsonl(&records, &mut std::io::stdout().lock())?;
+                }
+            }
+        }
+        Commands::Bench { command: BenchCommands::Strategies { requests, concurrency, workload, with_pool } } => {
+            let workload = match workload {
+                Some(profile) => Workload::load(&profile)?,
+                None => Workload::get_set(10_000, 64),
+            };
+            StrategyBench::new(redis_client).run(&workload, requests, concurrency, with_pool).await?;
+        }
+        Commands::Bench { command: BenchCommands::Soak { duration, rate, concurrency, workload, sample_every } } => {
+            let workload = match workload {
+                Some(profile) => Workload::load(&profile)?,
+                None => Workload::get_set(10_000, 256),
+            };
+            let mut bench = SoakBench::new(redis_client);
+            if let Some(metrics) = metrics {
+                bench = bench.with_metrics(metrics);
+            }
+            bench.run(&workload, duration, rate, concurrency, sample_every).await?;
+        }
+        Commands::Bench { command: BenchCommands::Adaptive { target_p99_ms, step_ms, max_concurrency, value_size } } => {
+            let bench = AdaptiveBench::new(redis_client);
+            bench
+                .run(
+                    std::time::Duration::from_secs_f64(target_p99_ms / 1000.0),
+                    std::time::Duration::from_millis(step_ms),
+                    max_concurrency,
+                    value_size,
+                )
+                .await?;
+        }
+        Commands::Bench { command: BenchCommands::Commands { tests, clients, requests, pipeline, payload_size, keyspace } } => {
+            let mut options = CommandBenchOptions::new(commands::parse_ops(&tests)?);
+            options.clients = clients;
+            options.requests = requests;
+            options.pipeline = pipeline;
+            options.payload_size = payload_size;
+            options.keyspace = keyspace;
+            CommandBench::new(redis_client).run(&options).await?;
+        }
+        Commands::Bench { command: BenchCommands::AutoPipeline { tasks, ops, window_us } } => {
+            let bench = AutoPipelineBench::new(redis_client);
+            bench.run(tasks, ops, std::time::Duration::from_micros(window_us)).await?;
+        }
+        Commands::Bench { command: BenchCommands::Bytes { value_size, iterations } } => {
+            let bench = BytesBench::new(redis_client);
+            bench.run(value_size, iterations).await?;
+        }
+        Commands::Bench { command: BenchCommands::FieldWrites { users, updates } } => {
+            FieldWriteBench::new(redis_client).run(users, updates).await?;
+        }
+        Commands::Bench { command: BenchCommands::Export { keys, memory_budget_mb } } => {
+            let bench = ExportBench::new(redis_client);
+            bench.run(keys, memory_budget_mb * 1024 * 1024).await?;
+        }
+        Commands::Bench { command: BenchCommands::Scan { keys, workers } } => {
+            let bench = ScanBench::new(redis_client);
+            bench.run(keys, workers).await?;
+        }
+        Commands::Check { command: CheckCommands::Consistency { repair, format } } => {
+            let format: ReportFormat = format.parse()?;
+            let mut checker = ConsistencyChecker::new(&redis_client)
+                .await?
+                .with_invariant(Box::new(UserIndexesPresent))
+                .with_invariant(Box::new(CartProductsExist));
+            let report = checker.run(repair).await?;
+            print_check_report(&report, format)?;
+        }
+        Commands::Check { command: CheckCommands::Validate { entity, format } } => {
+            let format: ReportFormat = format.parse()?;
+            let invariant: Box<dyn Invariant> = match entity.as_str() {
+                "user" => Box::new(EntitiesValid::<User>::new()),
+                other => {
+                    return Err(redis_rust_demo::DemoError::Configuration(format!("Unknown entity '{}', expected: user", other)));
+                }
+            };
+            let report = ConsistencyChecker::new(&redis_client).await?.with_invariant(invariant).run(false).await?;
+            print_check_report(&report, format)?;
+        }
+        Commands::Export { command: ExportCommands::Json { pattern, out, format, batch_size, memory_budget_mb } } => {
+            let options = ExportOptions {
+                format: format.parse()?,
+                batch_size,
+                memory_budget: memory_budget_mb * 1024 * 1024,
+                ..ExportOptions::new(&pattern, &out)
+            };
+            let summary = JsonExporter::new(redis_client).run(&options).await?;
+            println!("✅ Exported {} keys ({} bytes) to {}", summary.keys, summary.bytes, out);
+        }
+        Commands::Export { command: ExportCommands::Table { key, out, format, rev } } => {
+            let path = std::path::Path::new(&out);
+            let format = match format {
+                Some(format) => format.parse()?,
+                None => TableFormat::for_path(path)?,
+            };
+            let mut conn = redis_client.get_async_connection().await?;
+            let Some(exported) = export::fetch_key(&mut conn, &key).await? else {
+                println!("No key {}", key);
+                return Ok(());
+            };
+            let table = Table::from_key(&exported, rev)?;
+            table.write(path, format)?;
+            println!("✅ Exported {} rows of {} ({}) to {}", table.rows(), key, table.names.join(", "), out);
+        }
+        Commands::Import { file, format, batch_size } => {
+            let text = std::fs::read_to_string(&file)?;
+            let format = match format.as_str() {
+                "auto" => ImportFormat::detect(&text)?,
+                format => format.parse()?,
+            };
+            let keys = export::import::parse(&text, format)?;
+            if !confirm(&format!("write {} keys from {}", keys.len(), file), db, safety)? {
+                println!("Aborted");
+                return Ok(());
+            }
+            let summary = Importer::new(redis_client.get_async_connection().await?).run(&keys, format, batch_size).await?;
+            println!("✅ Imported {} keys from {} ({:?})", summary.keys, file, format);
+            if summary.empty > 0 {
+                println!("   {} empty collections skipped", summary.empty);
+            }
+        }
+        Commands::Failover { replica_url, mode, catch_up_timeout_ms } => {
+            let mode: FailoverMode = mode.parse()?;
+            let demo = FailoverDemo::new(redis_client, RedisClient::builder(&replica_url).tls(tls.clone()).build()?.with_key_prefix(&cli.key_prefix));
+            steps.run("failover", demo.demonstrate(mode, std::time::Duration::from_millis(catch_up_timeout_ms))).await?;
+        }
+        Commands::Inspect { command } => match command {
+            InspectCommands::Wire { command } => {
+                let mut inspector = WireInspector::connect(redis_client.get_connection_info()).await?;
+                let exchange = inspector.send(&command).await?;
+                for (arrow, bytes) in [("→ Sent", &exchange.sent), ("← Received", &exchange.received)] {
+                    println!("\n{} ({} bytes):", arrow, bytes.len());
+                    if let Ok(Some((lines, _))) = inspect::annotate(bytes) {
+                        print!("{}", inspect::render(&lines));
+                    }
+                }
+                println!("\n💡 Every command goes out as an array of bulk strings, numbers included;");
+                println!("   the first byte of each reply line says what type follows.");
+            }
+        },
+        Commands::Info => {
+            let result = fleet::snapshot(redis_client.clone()).await;
+            print!("{}", fleet::render_snapshots(&[TargetResult { target: DisplaySafe(&redis_url).to_string(), result }]));
+        }
+        Commands::Jobs { command } => {
+            let mut conn = redis_client.get_async_connection().await?;
+            match command {
+                JobCommands::List => {
+                    let list = jobs::bulk::list(&mut conn).await?;
+                    if list.is_empty() {
+                        println!("No bulk jobs");
+                    } else {
+                        print!("{}", jobs::render_table(&list));
+                    }
+                }
+                JobCommands::Status { id } => match BulkJob::load(&mut conn, &id).await? {
+                    Some(job) => print!("{}", jobs::render_status(&job)),
+                    None => println!("No job '{}'", id),
+                },
+                JobCommands::Cancel { id } => match jobs::bulk::request_cancel(&mut conn, &id).await? {
+                    Some(JobState::Running) => println!("✅ Cancellation requested; {} stops at its next checkpoint", id),
+                    Some(state) => println!("Job {} is already {}", id, state),
+                    None => println!("No job '{}'", id),
+                },
+            }
+        }
+        Commands::Maintenance { command: MaintenanceTasks::GcIndexes { dry_run, batch_size, pause_ms, resume } } => {
+            if !dry_run && !confirm("delete orphaned index entries", db, safety)? {
+                println!("Aborted");
+                return Ok(());
+            }
+            let options = GcOptions { dry_run, batch_size, pause: std::time::Duration::from_millis(pause_ms) };
+            let specs = IndexSpec::user_indexes();
+            let mut job = match resume {
+                Some(id) => BulkJob::resume(&redis_client, &id, "gc-indexes").await?,
+                None => BulkJob::start(&redis_client, "gc-indexes", specs.len()).await?,
+            };
+            println!("Job {} (if interrupted, rerun with --resume {})", job.id(), job.id());
+            let mut gc = IndexGc::new(&redis_client).await?;
+            let report = gc.run_job(&specs, &options, &mut job).await?;
+            for orphan in &report.orphans {
+                println!("   orphan {} -> missing {}", orphan.index_key, orphan.primary_key);
+            }
+            println!(
+                "{} {} index keys scanned, {} orphans, {} removed",
+                if dry_run { "🔍" } else { "✅" },
+                report.scanned,
+                report.orphans.len(),
+                report.removed
+            );
+            let total = job.checkpoint();
+            if total.processed > report.scanned as u64 {
+                println!("   {} scanned and {} removed across all runs of {}", total.processed, total.changed, total.id);
+            }
+        }
+        Commands::Maintenance { command: MaintenanceTasks::Rename { from, to_prefix, to_db, on_collision, dry_run, batch_size, pause_ms } } => {
+            let to_db = to_db.filter(|&to| to != db);
+            let plan = RenamePlan::new(&from, &to_prefix, to_db.is_none())?;
+            // Both databases get written to; guarding the lower one covers db 0
+            let guarded = to_db.map_or(db, |to| to.min(db));
+            if !dry_run && !confirm(&format!("rename {} to {}…", from, to_prefix), guarded, safety)? {
+                println!("Aborted");
+                return Ok(());
+            }
+            let options = RenameOptions {
+                collision: on_collision.parse()?,
+                dry_run,
+                batch_size,
+                pause: std::time::Duration::from_millis(pause_ms),
+            };
+            let mut rename = BatchRename::new(redis_client.get_async_connection().await?, plan);
+            if let Some(to) = to_db {
+                let target = RedisClient::builder(&redis_url.parse::<RedisUrl>()?.with_db(to).to_string()).tls(tls.clone()).build()?;
+                rename = rename.with_target(target.get_async_connection().await?);
+            }
+            let report = rename.run(&options).await?;
+            for (key, new_key) in &report.collisions {
+                println!("   collision {} -> {} (exists)", key, new_key);
+            }
+            if report.collided > report.collisions.len() {
+                println!("   … {} more collisions", report.collided - report.collisions.len());
+            }
+            println!(
+                "{} {} keys matched, {} {}, {} overwritten, {} collisions left alone, {} gone before renaming",
+                if dry_run { "🔍" } else { "✅" },
+                report.scanned,
+                report.renamed,
+                if dry_run { "would be renamed" } else { "renamed" },
+                report.overwritten,
+                report.collided,
+                report.vanished
+            );
+        }
+        #[cfg(feature = "sqlite")]
+        Commands::Mirror { command } => {
+            use redis_rust_demo::cli::MirrorCommands;
+            use redis_rust_demo::mirror::SqliteMirror;
+            let mut conn = redis_client.get_async_connection().await?;
+            match command {
+                MirrorCommands::Sqlite { pattern, db: path, batch_size } => {
+                    let mut sqlite = SqliteMirror::open(std::path::Path::new(&path))?;
+                    let report = redis_rust_demo::mirror::sqlite::mirror(&mut conn, &mut sqlite, &pattern, batch_size).await?;
+                    println!("✅ Mirrored {} {} rows into {} (table {})", report.rows, pattern, path, report.table);
+                    for (name, kind) in &report.columns {
+                        println!("   {:<24} {}", name, kind);
+                    }
+                    if report.skipped > 0 {
+                        println!("   {} keys skipped: not a JSON object", report.skipped);
+                    }
+                    println!("   Try: sqlite3 {} 'SELECT * FROM \"{}\" LIMIT 10'", path, report.table);
+                }
+                MirrorCommands::SyncBack { db: path, dry_run } => {
+                    let sqlite = SqliteMirror::open(std::path::Path::new(&path))?;
+                    if !dry_run && !confirm(&format!("write rows edited in {} back", path), db, safety)? {
+                        println!("Aborted");
+                        return Ok(());
+                    }
+                    for report in redis_rust_demo::mirror::sqlite::sync_back(&mut conn, &sqlite, dry_run).await? {
+                        println!(
+                            "{} {}: {} updated, {} created, {} unchanged, {} conflicts",
+                            if dry_run { "🔍" } else { "✅" },
+                            report.table,
+                            report.updated,
+                            report.created,
+                            report.unchanged,
+                            report.conflicts.len()
+                        );
+                        for conflict in &report.conflicts {
+                            println!("   ⚠️  {}: {}", conflict.key, conflict.reason);
+                        }
+                    }
+                }
+            }
+        }
+        #[cfg(not(feature = "sqlite"))]
+        Commands::Mirror { .. } => {
+            return Err(redis_rust_demo::DemoError::Configuration("mirror needs a build with --features sqlite".to_string()));
+        }
+        Commands::Webhook { command } => match command {
+            WebhookCommands::Watch { url, secret, events, sessions, max_lag_bytes, max_ack_secs, interval_ms, limit } => {
+                let kinds = match events.is_empty() {
+                    true => EventKind::ALL.to_vec(),
+                    false => events.iter().map(|event| event.parse()).collect::<Result<Vec<EventKind>>>()?,
+                };
+                let change = match kinds.contains(&EventKind::SessionExpired) {
+                    true => key_watch::notify_change(&redis_client, integrations::events::EXPIRED_FLAGS).await?,
+                    false => None,
+                };
+                if let Some(change) = &change {
+                    if !confirm(&change.describe(), db, safety)? {
+                        println!("Aborted");
+                        return Ok(());
+                    }
+                }
+                let sink = WebhookSink::new(webhook_config(&url, secret.as_deref()))?;
+                let options = integrations::WatchOptions {
+                    kinds,
+                    sessions,
+                    thresholds: ReplicaLagThresholds { max_bytes: max_lag_bytes, max_ack_secs },
+                    interval: std::time::Duration::from_millis(interval_ms),
+                    limit,
+                };
+                let summary = integrations::events::watch(&redis_client, &sink, &options, change).await?;
+                println!("\n{} events delivered, {} failed", summary.delivered, summary.failed);
+            }
+            WebhookCommands::Send { url, secret, event, subject, message } => {
+                let sink = WebhookSink::new(webhook_config(&url, secret.as_deref()))?;
+                let event = Event::new(event.parse()?, &subject, message, chrono::Utc::now());
+                let attempts = sink.send(&event).await?;
+                println!("✅ Delivered {} {} to {} ({} attempt(s))", event.kind.as_str(), event.id, WebhookHost(&url), attempts);
+            }
+        },
+        Commands::Stats { command: StatsCommands::ClientKeys { top } } => {
+            let mut conn = redis_client.get_async_connection().await?;
+            match ClientKeySnapshot::load(&mut conn).await? {
+                Some(snapshot) => {
+                    println!("Run: redis-demo {} ({})", snapshot.command, snapshot.recorded_at.format("%Y-%m-%d %H:%M:%S"));
+                    if snapshot.evicted > 0 {
+                        println!("({} rarely used keys were evicted from the tracker)", snapshot.evicted);
+                    }
+                    println!();
+                    print!("{}", snapshot.render_top(top));
+                    println!("\nHeatmap by key family:");
+                    print!("{}", snapshot.render_heatmap());
+                }
+                None => println!("No key stats recorded yet; run any command with --key-stats first"),
+            }
+        }
+        Commands::Metrics { command: MetricsCommands::Rollup { days, retention_hours } } => {
+            let demo = RollupDemo::new(redis_client);
+            steps.run("rollups", demo.demonstrate(days, retention_hours)).await?;
+        }
+        Commands::Monitor { command: MonitorCommands::Dashboard { out, title } } => {
+            let dashboard = serde_json::to_string_pretty(&grafana::dashboard(&title))?;
+            match out {
+                Some(path) => {
+                    std::fs::write(&path, dashboard)?;
+                    println!("✅ Dashboard written to {}; import it in Grafana and pick the Prometheus data source", path);
+                }
+                None => println!("{}", dashboard),
+            }
+        }
+        Commands::RateLimit { algorithm, limit, window_ms, burst, requests, interval_ms } => {
+            let algorithms = match algorithm.is_empty() {
+                true => Algorithm::ALL.to_vec(),
+                false => algorithm.iter().map(|name| name.parse()).collect::<Result<Vec<Algorithm>>>()?,
+            };
+            let limit = RateLimit::new(limit, std::time::Duration::from_millis(window_ms));
+            let demo = RateLimitDemo::new(redis_client);
+            steps.run("rate limiting", demo.run(&algorithms, limit, burst, requests, std::time::Duration::from_millis(interval_ms))).await?;
+        }
+        Commands::Cache { ttl_ms, delay_ms, readers } => {
+            let mut demo = CacheDemo::new(redis_client);
+            if let Some(metrics) = metrics {
+                demo = demo.with_metrics(metrics);
+            }
+            steps.run("caching", demo.run(std::time::Duration::from_millis(ttl_ms), std::time::Duration::from_millis(delay_ms), readers)).await?;
+        }
+        Commands::User { command } => {
+            let trash_ttl = match &command {
+                UserCommands::Delete { trash_ttl_secs, .. } => *trash_ttl_secs,
+                _ => 86_400,
+            };
+            let mut repo = UserRepository::new(&redis_client, std::time::Duration::from_secs(trash_ttl)).await?;
+            match command {
+                UserCommands::Create { username, email, name, age, city, country } => {
+                    let mut user = User::new(username.clone(), email, name.unwrap_or(username));
+                    user.age = age;
+                    user.city = city;
+                    user.country = country;
+                    repo.create(&user).await?;
+                    println!("✅ Created user {} ({})", user.username, user.id);
+                }
+                UserCommands::Get { id, username, email } => {
+                    let user = match (id, username, email) {
+                        (Some(id), _, _) => repo.get(id).await?,
+                        (_, Some(username), _) => repo.find_by_username(&username).await?,
+                        (_, _, Some(email)) => repo.find_by_email(&email).await?,
+                        _ => None,
+                    };
+                    match user {
+                        Some(user) => println!("{}", serde_json::to_string_pretty(&user)?),
+                        None => println!("❌ No such user"),
+                    }
+                }
+                UserCommands::List { limit, after } => {
+                    let cursor = after.map(|after| after.parse()).transpose()?;
+                    let page = repo.list_page(cursor.as_ref(), limit).await?;
+                    for user in &page.users {
+                        println!("{}  {:<20} {:<30} {}", user.id, user.username, user.email, user.created_at.format("%Y-%m-%d %H:%M:%S"));
+                    }
+                    match page.next {
+                        Some(next) => println!("\nNext page: --after {}", next),
+                        None => println!("\nEnd of list"),
+                    }
+                }
+                UserCommands::Delete { id, trash_ttl_secs } => match repo.delete(id).await? {
+                    true => println!("🗑️  User {} moved to the trash for {}s", id, trash_ttl_secs),
+                    false => println!("❌ No such user"),
+                },
+            }
+        }
+        Commands::Quotas { command } => {
+            let mut manager = QuotaManager::new(&redis_client).await?;
+            let current = monthly::period(chrono::Utc::now());
+            match command {
+                QuotaCommands::Set { key, limit, soft_ratio, per_second } => {
+                    manager.set_plan(&key, QuotaPlan { monthly_limit: limit, soft_ratio, per_second }).await?;
+                    println!("✅ {}: {} requests/month", key, limit);
+                }
+                QuotaCommands::Show { key, period } => {
+                    let period = period.unwrap_or(current);
+                    match manager.plan(&key).await? {
+                        Some(plan) => {
+                            let used = manager.usage(&key, &period).await?;
+                            println!("{} ({})", key, period);
+                            println!("   limit:      {} (soft at {:.0}%)", plan.monthly_limit, plan.soft_ratio * 100.0);
+                            println!("   burst:      {}", if plan.per_second == 0 { "unlimited".to_string() } else { format!("{}/s", plan.per_second) });
+                            println!("   used:       {}", used);
+                            println!("   remaining:  {}", plan.monthly_limit.saturating_sub(used));
+                        }
+                        None => println!("No plan for '{}'", key),
+                    }
+                }
+                QuotaCommands::Reset { key, period } => {
+                    let period = period.unwrap_or(current);
+                    if manager.reset(&key, &period).await? {
+                        println!("✅ Reset usage for {} in {}", key, period);
+                    } else {
+                        println!("No usage for {} in {}", key, period);
+                    }
+                }
+                QuotaCommands::Simulate { key, requests } => {
+                    let demo = QuotaDemo::new(redis_client);
+                    steps.run("quota simulation", demo.simulate(&key, requests)).await?;
+                }
+                QuotaCommands::Namespaces { rounds } => {
+                    let demo = NamespaceQuotaDemo::new(redis_client);
+                    steps.run("namespace quotas", demo.run(rounds)).await?;
+                }
+            }
+        }
+        Commands::Replication {
+            command: ReplicationCommands::Lag { watch, interval_ms, max_lag_bytes, max_ack_secs, alert_webhook, alert_template, alert_cooldown_secs },
+        } => {
+            let thresholds = ReplicaLagThresholds { max_bytes: max_lag_bytes, max_ack_secs };
+            let mut notifier = alert_notifier(&redis_client, alert_webhook, alert_template, alert_cooldown_secs).await?;
+            let subject = format!("replication on {}", DisplaySafe(&cli.redis_url[0]));
+            loop {
+                let lag = redis_client.replication_lag().await?;
+                if watch {
+                    print!("\x1B[2J\x1B[H");
+                    println!("Replication lag on {} ({})\n", DisplaySafe(&cli.redis_url[0]), chrono::Local::now().format("%H:%M:%S"));
+                }
+                print!("{}", lag.render_panel(&thresholds));
+                if let Some(exporter) = &metrics {
+                    let mut sample = exporter.metric_set();
+                    lag.record_metrics(&mut sample);
+                    exporter.publish(&sample).await;
+                }
+                let alerts = lag.evaluate(&thresholds);
+                for alert in &alerts {
+                    println!("⚠️  {}", alert);
+                }
+                send_alerts(&mut notifier, &subject, &alerts).await?;
+                if !watch {
+                    break;
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(std::time::Duration::from_millis(interval_ms)) => {}
+                    _ = tokio::signal::ctrl_c() => break,
+                }
+            }
+        }
+        Commands::Scheduler { command } => {
+            let mut scheduler = RecurringScheduler::new(&redis_client).await?;
+            let mut registry = HandlerRegistry::with_builtins(&redis_client);
+            registry.register(
+                "workflow_timeouts",
+                WorkflowTimeoutHandler::new(redis_client.clone(), std::time::Duration::from_secs(900)),
+            );
+            registry.register("metrics_rollup", RollupHandler::new(redis_client.clone(), chrono::Duration::hours(24)));
+            match command {
+                SchedulerCommands::List => {
+                    let jobs = scheduler.list().await?;
+                    if jobs.is_empty() {
+                        println!("No recurring jobs");
+                    }
+                    for (job, next) in jobs {
+                        println!(
+                            "{:<20} {:<16} handler={:<8} catch_up={:?} next={}",
+                            job.name, job.cron, job.handler, job.catch_up, next.format("%Y-%m-%d %H:%M UTC")
+                        );
+                    }
+                }
+                SchedulerCommands::Add { name, cron, handler, catch_up } => {
+                    if registry.get(&handler).is_none() {
+                        return Err(redis_rust_demo::DemoError::Configuration(format!(
+                            "Unknown handler '{}', expected one of {:?}",
+                            handler,
+                            registry.names()
+                        )));
+                    }
+                    let job = RecurringJob { name, cron, handler, catch_up: catch_up.parse()? };
+                    let next = scheduler.add(&job, chrono::Utc::now()).await?;
+                    println!("✅ Scheduled '{}', next run {}", job.name, next.format("%Y-%m-%d %H:%M UTC"));
+                }
+                SchedulerCommands::Remove { name } => {
+                    if scheduler.remove(&name).await? {
+                        println!("✅ Removed '{}'", name);
+                    } else {
+                        println!("No job named '{}'", name);
+                    }
+                }
+                SchedulerCommands::Run { seconds } => {
+                    println!("Running scheduler for {}s (Ctrl-C to stop)...", seconds);
+                    let runs = scheduler
+                        .run_for(&registry, std::time::Duration::from_secs(1), std::time::Duration::from_secs(seconds))
+                        .await?;
+                    println!("✅ {} job runs", runs.len());
+                }
+            }
+        }
+        Commands::Workflow { command, payment_timeout_secs } => {
+            let mut store = WorkflowStore::new(&redis_client, std::time::Duration::from_secs(payment_timeout_secs)).await?;
+            let now = chrono::Utc::now();
+            match command {
+                WorkflowCommands::Create { id } => {
+                    store.create(&id, now).await?;
+                    println!("✅ Order {} created", id);
+                }
+                WorkflowCommands::Advance { id, to, reason } => {
+                    store.advance(&id, to.parse()?, now, &reason).await?;
+                    println!("✅ Order {} is now {}", id, to);
+                }
+                WorkflowCommands::Inspect { id } => match store.inspect(&id).await? {
+                    Some(order) => print!("{}", render_workflow(&order)),
+                    None => println!("No order '{}'", id),
+                },
+            }
+        }
+        Commands::Streams { command } => {
+            match command {
+                StreamCommands::Archive { stream, out, follow, group, consumer, batch_size, rotate_bytes, format } => {
+                    let mut options = ArchiveOptions::new(&stream, out);
+                    options.format = format.parse()?;
+                    options.follow = follow;
+                    options.group = group;
+                    options.consumer = consumer;
+                    options.batch_size = batch_size;
+                    options.rotate_bytes = rotate_bytes;
+                    let summary = StreamArchiver::new(redis_client, options).run().await?;
+                    archive::print_summary(&summary);
+                }
+                StreamCommands::Replay { stream, from, to, speed, to_channel, to_stream } => {
+                    let target = match (to_channel, to_stream) {
+                        (Some(channel), _) => ReplayTarget::Channel(channel),
+                        (None, Some(target)) => ReplayTarget::Stream(target),
+                        (None, None) => unreachable!("clap requires a replay target"),
+                    };
+                    let mut options = ReplayOptions::new(&stream, target);
+                    options.from = from;
+                    options.to = to;
+                    options.speed = speed;
+                    let summary = StreamReplayer::new(redis_client, options).run().await?;
+                    replay::print_summary(&summary);
+                }
+                StreamCommands::Lag { stream, format, config, alert_webhook, alert_template, alert_cooldown_secs } => {
+                    let format: LagFormat = format.parse()?;
+                    let thresholds = match config {
+                        Some(path) => LagThresholds::from_file(std::path::Path::new(&path))?,
+                        None => LagThresholds::default(),
+                    };
+                    let mut notifier = alert_notifier(&redis_client, alert_webhook, alert_template, alert_cooldown_secs).await?;
+                    let report = StreamLagMonitor::new(redis_client).report(&stream).await?;
+                    match format {
+                        LagFormat::Table => print!("{}", lag::render_table(&report)),
+                        LagFormat::Prometheus => print!("{}", lag::render_prometheus(&report)),
+                    }
+                    if let Some(exporter) = &metrics {
+                        let mut sample = exporter.metric_set();
+                        lag::record_metrics(&report, &mut sample);
+                        exporter.publish(&sample).await;
+                    }
+                    let alerts = lag::evaluate(&report, &thresholds);
+                    for alert in &alerts {
+                        println!("⚠️  {}", alert);
+                    }
+                    send_alerts(&mut notifier, &format!("stream {}", stream), &alerts).await?;
+                }
+            }
+        }
+        Commands::ClusterPitfalls { cluster_nodes } => {
+            let demo = ClusterPitfallsDemo::new();
+            steps.run("cluster pitfalls", demo.demonstrate(&cluster_nodes)).await?;
+        }
+        Commands::Cluster { command } => {
+            let live = async { SlotMap::fetch(&mut redis_client.get_async_connection().await?).await };
+            let live = match &command {
+                // Slots are computed locally, so keyslot works without a server
+                ClusterCommands::Keyslot { .. } => live.await.ok().flatten(),
+                _ => live.await?,
+            };
+            let map = |nodes: usize| -> Result<SlotMap> {
+                match &live {
+                    Some(map) => Ok(map.clone()),
+                    None => {
+                        println!("(Not a cluster: showing {} masters with slots split as redis-cli would)\n", nodes);
+                        SlotMap::even(nodes)
+                    }
+                }
+            };
+            match command {
+                ClusterCommands::Slots { nodes } => print!("{}", map(nodes)?.render()),
+                ClusterCommands::Keyslot { keys } => {
+                    let map = map(3)?;
+                    for key in &keys {
+                        println!("{}", cluster::describe_key(key, &map));
+                    }
+                    if keys.len() > 1 {
+                        match same_slot(&keys) {
+                            true => println!("\n✅ One slot: these keys can share MSET, MULTI/EXEC or a Lua script"),
+                            false => println!("\n❌ Different slots: a multi-key command over them fails with CROSSSLOT"),
+                        }
+                    }
+                    println!("💡 Only the text inside the first non-empty {{...}} is hashed; wrap the shared part of related keys in braces.");
+                }
+                ClusterCommands::Distribution { templates, keys, nodes } => {
+                    let map = map(nodes)?;
+                    for template in &templates {
+                        println!("{} × {}:", template, keys);
+                        println!("{}", Distribution::of(&cluster::seeded_keys(template, keys), &map).render());
+                    }
+                    println!("💡 Per-entity tags keep related keys together and still spread; one global tag puts everything on one node.");
+                }
+                ClusterCommands::Reshard { add, remove, nodes, sample, keys_per_sec } => {
+                    let map = map(nodes)?;
+                    let change = match (add, remove) {
+                        (Some(count), _) => Change::Add(count),
+                        (None, node) => Change::Remove(node.unwrap_or_default()),
+                    };
+                    let plan = ReshardPlan::new(&map, change)?;
+                    let moving = plan.moved_slots();
+                    let estimate = match map.live {
+                        true => {
+                            // COUNTKEYSINSLOT only counts the keys of the node it runs on
+                            let base: RedisUrl = redis_url.parse()?;
+                            let mut counts = Vec::new();
+                            for slot in reshard::sample_slots(&moving, sample) {
+                                let Some((host, port)) = map.owner(slot).and_then(|owner| owner.rsplit_once(':')) else { continue };
+                                let node = RedisClient::builder(&base.for_node(host, port.parse().unwrap_or(6379)).to_string()).tls(tls.clone()).build()?;
+                                let mut conn = node.get_async_connection().await?;
+                                counts.push(redis::cmd("CLUSTER").arg("COUNTKEYSINSLOT").arg(slot).query_async(&mut conn).await?);
+                            }
+                            KeyEstimate::from_slot_counts(&counts, moving.len())
+                        }
+                        false => {
+                            let keys = redis_client.sample_keys(sample).await?;
+                            let db_size: u64 = redis::cmd("DBSIZE").query_async(&mut redis_client.get_async_connection().await?).await?;
+                            KeyEstimate::from_key_sample(&keys, &moving.iter().copied().collect(), db_size)
+                        }
+                    };
+                    print!("{}", reshard::render(&plan, &estimate, keys_per_sec));
+                    println!("\n💡 Analysis only: nothing was moved. Keys migrate one slot at a time with MIGRATE, and clients get ASK redirects meanwhile.");
+                }
+            }
+        }
+        Commands::Model { command } => {
+            match command {
+                ModelCommands::Graph { pattern, out, format } => {
+                    let format = match (&format, &out) {
+                        (Some(format), _) => format.parse()?,
+                        (None, Some(path)) => DiagramFormat::from_path(path),
+                        (None, None) => DiagramFormat::Dot,
+                    };
+                    let demo = DataModelDemo::new(redis_client);
+                    let diagram = demo.graph(&pattern, format).await?;
+                    match out {
+                        Some(path) => {
+                            std::fs::write(&path, diagram)?;
+                            println!("✅ Data model written to {}", path);
+                        }
+                        None => print!("{}", diagram),
+                    }
+                }
+            }
+        }
+    }
+    
+    if let Some(run) = run {
+        run.finish();
+    }
+    
+    if let Some(cassette) = cassette.filter(|cassette| cassette.is_replay() && cassette.remaining() > 0) {
+        println!("⚠️  {} recorded round trips in {} were not replayed", cassette.remaining(), cassette.path().display());
+    }
+    
+    if let Some(history) = history {
+        let mut conn = base_client.get_async_connection().await?;
+        let (written, dropped) = history.flush(&mut conn).await?;
+        println!("📼 Recorded {} key changes for `key history`", written);
+        if dropped > 0 {
+            println!("⚠️  {} more changes were dropped: narrow the --history patterns", dropped);
+        }
     }
     
+    if let Some(stats) = key_stats {
+        let command: Vec<String> = std::env::args().skip(1).collect();
+        let mut conn = base_client.get_async_connection().await?;
+        ClientKeySnapshot::capture(&stats, &command.join(" ")).save(&mut conn).await?;
+    }
+    
+    Ok(())
+}
+
+/// Prints a checker's violations and fails if any error is left unrepaired.
+fn print_check_report(report: &ConsistencyReport, format: ReportFormat) -> Result<()> {
+    match format {
+        ReportFormat::Text => print!("{}", render_report(report)),
+        ReportFormat::Jsonl => {
+            let records: Vec<DiffRecord> = report.violations.iter().map(DiffRecord::from).collect();
+            write_jsonl(&records, &mut std::io::stdout().lock())?;
+        }
+    }
+    let unrepaired = report.count(Severity::Error).saturating_sub(report.repaired);
+    if unrepaired > 0 {
+        return Err(redis_rust_demo::DemoError::Demo(format!("{} consistency errors left", unrepaired)));
+    }
+    Ok(())
+}
+
+fn webhook_config(url: &str, secret: Option<&str>) -> WebhookConfig {
+    let config = WebhookConfig::new(url);
+    match secret {
+        Some(secret) => config.with_secret(secret),
+        None => config,
+    }
+}
+
+/// `--metrics-config`, overridden by `--push-gateway` and `--metrics-listen`.
+fn metrics_config(cli: &Cli) -> Result<MetricsConfig> {
+    let mut config = match &cli.metrics_config {
+        Some(path) => MetricsConfig::from_file(std::path::Path::new(path))?,
+        None => MetricsConfig::default(),
+    };
+    if let Some(url) = &cli.push_gateway {
+        config.push_gateway = Some(url.clone());
+    }
+    if let Some(addr) = cli.metrics_listen {
+        config.listen = Some(addr);
+    }
+    Ok(config)
+}
+
+/// The `--alert-webhook` of a lag command, if it was given.
+async fn alert_notifier(client: &RedisClient, url: Option<String>, template: Option<String>, cooldown_secs: u64) -> Result<Option<AlertNotifier>> {
+    match url {
+        Some(url) => Ok(Some(AlertNotifier::for_url(client, &url, template.as_deref(), std::time::Duration::from_secs(cooldown_secs)).await?)),
+        None if template.is_some() => Err(redis_rust_demo::DemoError::Configuration("--alert-template needs --alert-webhook".to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Posts `alerts`; a delivery that fails is reported without stopping the
+/// check, so a chat outage can't take monitoring down with it.
+async fn send_alerts(notifier: &mut Option<AlertNotifier>, subject: &str, alerts: &[String]) -> Result<()> {
+    let Some(notifier) = notifier else { return Ok(()) };
+    if let Err(e) = notifier.notify(subject, alerts).await {
+        if !matches!(e, redis_rust_demo::DemoError::Http { .. }) {
+            return Err(e);
+        }
+        println!("❌ Alert for {} not delivered: {}", subject, e);
+    }
+    Ok(())
+}
+
+/// `ping`, `advise` or `info` against every target at once, as one table.
+async fn compare_targets(command: &Commands, urls: &[String], tls: &TlsOptions) -> Result<()> {
+    if !command.fans_out() {
+        return Err(redis_rust_demo::DemoError::Configuration("Only ping, advise and info accept several --redis-url".to_string()));
+    }
+    let table = match command {
+        Commands::Ping => fleet::render_pings(&fan_out(urls, tls, fleet::ping_latency).await),
+        Commands::Advise => fleet::render_advice(&fan_out(urls, tls, fleet::suggestions).await),
+        _ => fleet::render_snapshots(&fan_out(urls, tls, fleet::snapshot).await),
+    };
+    print!("{}", table);
     Ok(())
 }
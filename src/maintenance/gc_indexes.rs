@@ -0,0 +1,232 @@
+use crate::jobs::BulkJob;
+use crate::models::User;
+use crate::repository::Entity;
+use crate::utils::{KeyScanner, KeyType};
+use crate::{RedisClient, Result};
+use crate::utils::RedisConnection;
+use redis::Script;
+use std::time::Duration;
+use tracing::info;
+
+/// KEYS: index key, primary key
+/// ARGV: id the index pointed at when it was found orphaned
+/// Deletes only if the index still points at that id and the primary key is
+/// still missing, so an index rewritten since the scan is left alone.
+const REMOVE_ORPHAN_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) ~= ARGV[1] then
+    return 0
+end
+if redis.call('EXISTS', KEYS[2]) == 1 then
+    return 0
+end
+return redis.call('DEL', KEYS[1])
+"#;
+
+/// A family of string index keys whose value is the id of a primary key,
+/// e.g. `username:alice` -> `user:<id>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSpec {
+    pub pattern: String,
+    pub primary_prefix: String,
+}
+
+impl IndexSpec {
+    pub fn new(pattern: &str, primary_prefix: &str) -> Self {
+        Self { pattern: pattern.to_string(), primary_prefix: primary_prefix.to_string() }
+    }
+
+    /// One spec per index in `T::INDEXES`, pointing at `<KIND>:<id>`.
+    pub fn for_entity<T: Entity>() -> Vec<Self> {
+        let primary_prefix = T::key_for("");
+        T::INDEXES.iter().map(|index| Self::new(&format!("{}:*", index), &primary_prefix)).collect()
+    }
+
+    /// The `username:*` and `email:*` indexes of `User`.
+    pub fn user_indexes() -> Vec<Self> {
+        Self::for_entity::<User>()
+    }
+
+    pub fn primary_key(&self, id: &str) -> String {
+        format!("{}{}", self.primary_prefix, id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcOptions {
+    /// Report orphans without deleting them.
+    pub dry_run: bool,
+    pub batch_size: usize,
+    /// Sleep between batches to keep the load on a production server low.
+    pub pause: Duration,
+}
+
+impl Default for GcOptions {
+    fn default() -> Self {
+        Self { dry_run: true, batch_size: 500, pause: Duration::from_millis(10) }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Orphan {
+    pub index_key: String,
+    pub primary_key: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GcReport {
+    pub scanned: usize,
+    pub orphans: Vec<Orphan>,
+    pub removed: usize,
+}
+
+/// Finds index keys pointing at primary keys that no longer exist. Index
+/// writes aren't atomic with the primary write or delete, so drift builds up
+/// after crashes and partial failures.
+pub struct IndexGc {
+    conn: RedisConnection,
+    script: Script,
+}
+
+impl IndexGc {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            script: Script::new(REMOVE_ORPHAN_SCRIPT),
+        })
+    }
+
+    pub async fn run(&mut self, specs: &[IndexSpec], options: &GcOptions) -> Result<GcReport> {
+        self.scan(specs, options, None).await
+    }
+
+    /// Like [`run`](Self::run), checkpointing after every batch so an
+    /// interrupted run resumes where it stopped. Each spec is one phase of
+    /// the job; the report covers this run only.
+    pub async fn run_job(&mut self, specs: &[IndexSpec], options: &GcOptions, job: &mut BulkJob) -> Result<GcReport> {
+        match self.scan(specs, options, Some(&mut *job)).await {
+            Ok(report) => {
+                job.complete().await?;
+                Ok(report)
+            }
+            Err(e) => {
+                job.fail(&e).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn scan(&mut self, specs: &[IndexSpec], options: &GcOptions, mut job: Option<&mut BulkJob>) -> Result<GcReport> {
+        let mut report = GcReport::default();
+        for (phase, spec) in specs.iter().enumerate() {
+            let cursor = match &job {
+                Some(job) => match job.checkpoint().start_cursor(phase) {
+                    Some(cursor) => cursor,
+                    None => continue,
+                },
+                None => 0,
+            };
+            let mut scanner = KeyScanner::new(self.conn.clone(), &spec.pattern, Some(KeyType::String))
+                .with_count(options.batch_size)
+                .with_cursor(cursor);
+            while let Some(batch) = scanner.next_batch().await? {
+                let removed_before = report.removed;
+                if !batch.is_empty() {
+                    report.scanned += batch.len();
+                    self.check_batch(spec, &batch, options, &mut report).await?;
+                }
+                if let Some(job) = job.as_deref_mut() {
+                    let removed = (report.removed - removed_before) as u64;
+                    job.advance(phase, scanner.cursor(), batch.len() as u64, removed).await?;
+                }
+                if !batch.is_empty() && !options.pause.is_zero() {
+                    tokio::time::sleep(options.pause).await;
+                }
+            }
+        }
+        info!(
+            "Index GC scanned {} keys, found {} orphans, removed {}",
+            report.scanned,
+            report.orphans.len(),
+            report.removed
+        );
+        Ok(report)
+    }
+
+    async fn check_batch(&mut self, spec: &IndexSpec, batch: &[String], options: &GcOptions, report: &mut GcReport) -> Result<()> {
+        let mut pipe = redis::pipe();
+        for key in batch {
+            pipe.get(key);
+        }
+        let ids: Vec<Option<String>> = pipe.query_async(&mut self.conn).await?;
+
+        let candidates: Vec<(&String, String)> = batch
+            .iter()
+            .zip(ids)
+            .filter_map(|(key, id)| id.map(|id| (key, id)))
+            .collect();
+        let mut pipe = redis::pipe();
+        for (_, id) in &candidates {
+            pipe.exists(spec.primary_key(id));
+        }
+        let exists: Vec<bool> = pipe.query_async(&mut self.conn).await?;
+
+        for ((index_key, id), exists) in candidates.into_iter().zip(exists) {
+            if exists {
+                continue;
+            }
+            let orphan = Orphan { index_key: index_key.clone(), primary_key: spec.primary_key(&id) };
+            if !options.dry_run {
+                let removed: i64 = self
+                    .script
+                    .key(&orphan.index_key)
+                    .key(&orphan.primary_key)
+                    .arg(&id)
+                    .invoke_async(&mut self.conn)
+                    .await?;
+                report.removed += removed as usize;
+            }
+            report.orphans.push(orphan);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use redis::AsyncCommands;
+
+    #[test]
+    fn test_user_index_specs() {
+        let specs = IndexSpec::user_indexes();
+        assert_eq!(specs[0].pattern, "username:*");
+        assert_eq!(specs[1].primary_key("42"), "user:42");
+        assert!(GcOptions::default().dry_run);
+    }
+
+    #[tokio::test]
+    async fn test_orphans_found_and_removed() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let keys = ["gctest_name:alice", "gctest_name:bob", "gctest_user:1"];
+        let _: () = conn.del(&keys).await.unwrap();
+        let _: () = conn.set("gctest_user:1", "{}").await.unwrap();
+        let _: () = conn.set("gctest_name:alice", "1").await.unwrap();
+        let _: () = conn.set("gctest_name:bob", "2").await.unwrap();
+
+        let specs = [IndexSpec::new("gctest_name:*", "gctest_user:")];
+        let mut gc = IndexGc::new(&client).await.unwrap();
+        let options = GcOptions { pause: Duration::ZERO, ..Default::default() };
+        let report = gc.run(&specs, &options).await.unwrap();
+        assert_eq!(report.scanned, 2);
+        assert_eq!(report.orphans, vec![Orphan { index_key: "gctest_name:bob".into(), primary_key: "gctest_user:2".into() }]);
+        assert_eq!(report.removed, 0);
+
+        let report = gc.run(&specs, &GcOptions { dry_run: false, ..options }).await.unwrap();
+        assert_eq!(report.removed, 1);
+        let bob: Option<String> = conn.get("gctest_name:bob").await.unwrap();
+        assert!(bob.is_none());
+
+        let _: () = conn.del(&keys).await.unwrap();
+    }
+}
@@ -0,0 +1,5 @@
+pub mod gc_indexes;
+pub mod rename;
+
+pub use gc_indexes::{GcOptions, GcReport, IndexGc, IndexSpec, Orphan};
+pub use rename::{BatchRename, Collision, RenameOptions, RenamePlan, RenameReport};
@@ -0,0 +1,299 @@
+use crate::utils::{KeyScanner, RedisConnection};
+use crate::{DemoError, Result};
+use redis::{AsyncCommands, ErrorKind, RedisError};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::info;
+
+/// Collisions kept in the report; the rest are only counted.
+const MAX_REPORTED_COLLISIONS: usize = 20;
+
+/// What to do when a key's new name is already taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+    /// Leave both keys alone and carry on.
+    Skip,
+    /// Replace the existing key.
+    Overwrite,
+    /// Stop at the first collision.
+    Fail,
+}
+
+impl FromStr for Collision {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "skip" => Ok(Collision::Skip),
+            "overwrite" => Ok(Collision::Overwrite),
+            "fail" => Ok(Collision::Fail),
+            other => Err(DemoError::Configuration(format!("Unknown collision policy: {} (use skip, overwrite or fail)", other))),
+        }
+    }
+}
+
+/// Which keys move and what they are called afterwards: the literal start
+/// of `from` (everything before the first glob character) is swapped for
+/// `to_prefix`, so `userv1:*` to `user:` turns `userv1:42` into `user:42`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamePlan {
+    pub from: String,
+    pub to_prefix: String,
+}
+
+impl RenamePlan {
+    /// Within one database the new names must not match `from` again, or
+    /// SCAN could hand back a renamed key and it would move twice.
+    pub fn new(from: &str, to_prefix: &str, same_db: bool) -> Result<Self> {
+        let plan = Self { from: from.to_string(), to_prefix: to_prefix.to_string() };
+        let source = plan.source_prefix();
+        if same_db && (to_prefix.starts_with(source) || source.starts_with(to_prefix)) {
+            return Err(DemoError::Configuration(format!(
+                "Renamed keys could match '{}' again: '{}' and '{}' overlap; pick a prefix outside the pattern",
+                from, source, to_prefix
+            )));
+        }
+        Ok(plan)
+    }
+
+    /// The part of `from` before its first glob character.
+    pub fn source_prefix(&self) -> &str {
+        let end = self.from.find(['*', '?', '[', '\\']).unwrap_or(self.from.len());
+        &self.from[..end]
+    }
+
+    /// `None` for a key that doesn't start with the source prefix.
+    pub fn new_key(&self, key: &str) -> Option<String> {
+        key.strip_prefix(self.source_prefix()).map(|rest| format!("{}{}", self.to_prefix, rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenameOptions {
+    pub collision: Collision,
+    /// Count what would move and what would collide without changing anything.
+    pub dry_run: bool,
+    pub batch_size: usize,
+    /// Sleep between batches to keep the load on a production server low.
+    pub pause: Duration,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        Self { collision: Collision::Skip, dry_run: true, batch_size: 500, pause: Duration::from_millis(10) }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RenameReport {
+    pub scanned: usize,
+    pub renamed: usize,
+    /// Existing keys replaced under [`Collision::Overwrite`].
+    pub overwritten: usize,
+    /// `(key, new name)` pairs left alone because the new name was taken,
+    /// up to [`MAX_REPORTED_COLLISIONS`].
+    pub collisions: Vec<(String, String)>,
+    pub collided: usize,
+    /// Keys deleted or expired between the scan and the rename.
+    pub vanished: usize,
+}
+
+impl RenameReport {
+    fn collide(&mut self, key: &str, new_key: &str) {
+        self.collided += 1;
+        if self.collisions.len() < MAX_REPORTED_COLLISIONS {
+            self.collisions.push((key.to_string(), new_key.to_string()));
+        }
+    }
+}
+
+enum Outcome {
+    Renamed,
+    Overwrote,
+    Collided,
+    Vanished,
+}
+
+/// Renames every key matching a pattern to a new prefix, keeping TTLs.
+/// Within a database that's RENAME / RENAMENX; into another database the
+/// value goes across with DUMP and RESTORE and the source is deleted.
+pub struct BatchRename {
+    conn: RedisConnection,
+    /// A connection to the destination database, when it isn't this one.
+    target: Option<RedisConnection>,
+    plan: RenamePlan,
+}
+
+impl BatchRename {
+    pub fn new(conn: RedisConnection, plan: RenamePlan) -> Self {
+        Self { conn, target: None, plan }
+    }
+
+    pub fn with_target(mut self, target: RedisConnection) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Stops with an error on the first collision under
+    /// [`Collision::Fail`]; keys renamed before it stay renamed.
+    pub async fn run(&mut self, options: &RenameOptions) -> Result<RenameReport> {
+        let mut report = RenameReport::default();
+        let mut scanner = KeyScanner::new(self.conn.clone(), &self.plan.from, None).with_count(options.batch_size);
+        while let Some(batch) = scanner.next_batch().await? {
+            for key in &batch {
+                let Some(new_key) = self.plan.new_key(key) else { continue };
+                report.scanned += 1;
+                let outcome = match options.dry_run {
+                    true => self.check(&new_key, options.collision).await?,
+                    false => self.rename(key, &new_key, options.collision).await?,
+                };
+                match outcome {
+                    Outcome::Renamed => report.renamed += 1,
+                    Outcome::Overwrote => {
+                        report.renamed += 1;
+                        report.overwritten += 1;
+                    }
+                    Outcome::Vanished => report.vanished += 1,
+                    Outcome::Collided => {
+                        report.collide(key, &new_key);
+                        if options.collision == Collision::Fail && !options.dry_run {
+                            return Err(DemoError::Configuration(format!(
+                                "{} already exists; stopped after renaming {} of {} keys",
+                                new_key, report.renamed, report.scanned
+                            )));
+                        }
+                    }
+                }
+            }
+            if !batch.is_empty() && !options.pause.is_zero() {
+                tokio::time::sleep(options.pause).await;
+            }
+        }
+        info!(
+            "Rename of {} scanned {} keys, renamed {}, {} collisions",
+            self.plan.from, report.scanned, report.renamed, report.collided
+        );
+        Ok(report)
+    }
+
+    /// A dry run's verdict: is the new name free?
+    async fn check(&mut self, new_key: &str, collision: Collision) -> Result<Outcome> {
+        let conn = self.target.as_mut().unwrap_or(&mut self.conn);
+        let taken: bool = conn.exists(new_key).await?;
+        Ok(match (taken, collision) {
+            (false, _) => Outcome::Renamed,
+            (true, Collision::Overwrite) => Outcome::Overwrote,
+            (true, _) => Outcome::Collided,
+        })
+    }
+
+    async fn rename(&mut self, key: &str, new_key: &str, collision: Collision) -> Result<Outcome> {
+        if self.target.is_some() {
+            return self.move_across(key, new_key, collision).await;
+        }
+        let renamed = match collision {
+            Collision::Overwrite => {
+                let taken: bool = self.conn.exists(new_key).await?;
+                let renamed: std::result::Result<(), RedisError> = self.conn.rename(key, new_key).await;
+                renamed.map(|_| if taken { Outcome::Overwrote } else { Outcome::Renamed })
+            }
+            Collision::Skip | Collision::Fail => {
+                let renamed: std::result::Result<bool, RedisError> = self.conn.rename_nx(key, new_key).await;
+                renamed.map(|renamed| if renamed { Outcome::Renamed } else { Outcome::Collided })
+            }
+        };
+        match renamed {
+            Err(e) if is_missing_key(&e) => Ok(Outcome::Vanished),
+            other => Ok(other?),
+        }
+    }
+
+    async fn move_across(&mut self, key: &str, new_key: &str, collision: Collision) -> Result<Outcome> {
+        let payload: Option<Vec<u8>> = redis::cmd("DUMP").arg(key).query_async(&mut self.conn).await?;
+        let Some(payload) = payload else {
+            return Ok(Outcome::Vanished);
+        };
+        // -2: gone since the DUMP; -1: no expiry, which RESTORE spells 0.
+        let ttl_ms: i64 = match self.conn.pttl(key).await? {
+            -2 => return Ok(Outcome::Vanished),
+            ttl_ms => ttl_ms.max(0),
+        };
+        let target = self.target.as_mut().expect("checked by rename");
+        let taken: bool = target.exists(new_key).await?;
+        let mut restore = redis::cmd("RESTORE");
+        restore.arg(new_key).arg(ttl_ms).arg(payload);
+        if collision == Collision::Overwrite {
+            restore.arg("REPLACE");
+        }
+        let restored: std::result::Result<(), RedisError> = restore.query_async(target).await;
+        match restored {
+            Err(e) if e.code() == Some("BUSYKEY") => return Ok(Outcome::Collided),
+            other => other?,
+        }
+        let _: () = self.conn.del(key).await?;
+        Ok(if taken { Outcome::Overwrote } else { Outcome::Renamed })
+    }
+}
+
+/// RENAME's "ERR no such key": the source was deleted or expired since the
+/// SCAN.
+fn is_missing_key(e: &RedisError) -> bool {
+    e.kind() == ErrorKind::ResponseError && e.detail() == Some("no such key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+
+    #[test]
+    fn test_plan_swaps_the_literal_prefix() {
+        let plan = RenamePlan::new("userv1:*", "user:", true).unwrap();
+        assert_eq!(plan.source_prefix(), "userv1:");
+        assert_eq!(plan.new_key("userv1:42").as_deref(), Some("user:42"));
+        assert_eq!(plan.new_key("other:1"), None);
+        assert!(RenamePlan::new("user:*", "user:v2:", true).unwrap_err().to_string().contains("overlap"));
+        assert!(RenamePlan::new("*", "new:", true).is_err());
+        assert!(RenamePlan::new("user:*", "user:", false).is_ok());
+        assert_eq!("Overwrite".parse::<Collision>().unwrap(), Collision::Overwrite);
+        assert!("merge".parse::<Collision>().is_err());
+    }
+
+    #[test]
+    fn test_missing_key_is_told_by_kind_and_detail() {
+        let missing = RedisError::from((ErrorKind::ResponseError, "An error was signalled by the server", "no such key".to_string()));
+        assert!(is_missing_key(&missing));
+        let other = RedisError::from((ErrorKind::ResponseError, "An error was signalled by the server", "syntax error".to_string()));
+        assert!(!is_missing_key(&other));
+        assert!(!is_missing_key(&RedisError::from((ErrorKind::IoError, "no such key"))));
+    }
+
+    #[tokio::test]
+    async fn test_rename_keeps_ttls_and_skips_collisions() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let keys = ["rntest_old:1", "rntest_old:2", "rntest_new:1", "rntest_new:2"];
+        let _: () = conn.del(&keys).await.unwrap();
+        let _: () = conn.set_ex("rntest_old:1", "a", 100).await.unwrap();
+        let _: () = conn.set("rntest_old:2", "b").await.unwrap();
+        let _: () = conn.set("rntest_new:2", "taken").await.unwrap();
+
+        let plan = RenamePlan::new("rntest_old:*", "rntest_new:", true).unwrap();
+        let mut rename = BatchRename::new(conn.clone(), plan);
+        let options = RenameOptions { dry_run: false, pause: Duration::ZERO, ..Default::default() };
+        let report = rename.run(&options).await.unwrap();
+        assert_eq!(report.renamed, 1);
+        assert_eq!(report.collisions, vec![("rntest_old:2".to_string(), "rntest_new:2".to_string())]);
+        let ttl: i64 = conn.ttl("rntest_new:1").await.unwrap();
+        assert!(ttl > 90);
+        let kept: String = conn.get("rntest_new:2").await.unwrap();
+        assert_eq!(kept, "taken");
+
+        let report = rename.run(&RenameOptions { collision: Collision::Overwrite, ..options }).await.unwrap();
+        assert_eq!((report.renamed, report.overwritten), (1, 1));
+        let moved: String = conn.get("rntest_new:2").await.unwrap();
+        assert_eq!(moved, "b");
+
+        let _: () = conn.del(&keys).await.unwrap();
+    }
+}
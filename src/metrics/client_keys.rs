@@ -0,0 +1,116 @@
+use crate::demos::data_model::key_template;
+use crate::utils::key_stats::{KeyStats, KeyUsage};
+use crate::utils::RedisConnection;
+use crate::Result;
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Where the last `--key-stats` run leaves its counts.
+pub const SNAPSHOT_KEY: &str = "stats:client_keys:last";
+
+/// Keys kept in a snapshot; the in-process tracker may hold more.
+const SNAPSHOT_TOP: usize = 500;
+
+const HEAT_WIDTH: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientKeySnapshot {
+    pub recorded_at: DateTime<Utc>,
+    pub command: String,
+    pub evicted: u64,
+    pub keys: Vec<KeyUsage>,
+}
+
+impl ClientKeySnapshot {
+    pub fn capture(stats: &KeyStats, command: &str) -> Self {
+        Self {
+            recorded_at: Utc::now(),
+            command: command.to_string(),
+            evicted: stats.evicted(),
+            keys: stats.top(SNAPSHOT_TOP),
+        }
+    }
+
+    pub async fn save(&self, conn: &mut RedisConnection) -> Result<()> {
+        let _: () = conn.set(SNAPSHOT_KEY, serde_json::to_string(self)?).await?;
+        Ok(())
+    }
+
+    pub async fn load(conn: &mut RedisConnection) -> Result<Option<Self>> {
+        let doc: Option<String> = conn.get(SNAPSHOT_KEY).await?;
+        Ok(doc.map(|doc| serde_json::from_str(&doc)).transpose()?)
+    }
+
+    /// Totals per key family (`user:{id}`, `coupon:{id}:remaining`, ...),
+    /// busiest first.
+    pub fn families(&self) -> Vec<(String, u64)> {
+        let mut families: BTreeMap<String, u64> = BTreeMap::new();
+        for usage in &self.keys {
+            *families.entry(key_template(&usage.key)).or_default() += usage.total();
+        }
+        let mut families: Vec<(String, u64)> = families.into_iter().collect();
+        families.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        families
+    }
+
+    pub fn render_top(&self, n: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{:<50} {:>8} {:>8} {:>8}", "key", "reads", "writes", "total");
+        for usage in self.keys.iter().take(n) {
+            let _ = writeln!(out, "{:<50} {:>8} {:>8} {:>8}", usage.key, usage.reads, usage.writes, usage.total());
+        }
+        out
+    }
+
+    /// One bar per key family, scaled to the busiest family.
+    pub fn render_heatmap(&self) -> String {
+        let families = self.families();
+        let max = families.first().map_or(0, |(_, total)| *total).max(1);
+        let mut out = String::new();
+        for (family, total) in families {
+            let width = ((total as f64 / max as f64) * HEAT_WIDTH as f64).ceil() as usize;
+            let _ = writeln!(out, "{:<40} {:<width$} {}", family, "█".repeat(width), total, width = HEAT_WIDTH);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> ClientKeySnapshot {
+        let stats = KeyStats::new(100);
+        for id in 0..3 {
+            for _ in 0..=id {
+                stats.record(&format!("user:{}", id), false);
+            }
+        }
+        stats.record("config", true);
+        ClientKeySnapshot::capture(&stats, "pattern feed")
+    }
+
+    #[test]
+    fn test_families_aggregate_by_template() {
+        assert_eq!(
+            snapshot().families(),
+            vec![("user:{id}".to_string(), 6), ("config".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let snapshot = snapshot();
+        let top = snapshot.render_top(1);
+        assert_eq!(top.lines().count(), 2);
+        assert!(top.lines().nth(1).unwrap().starts_with("user:2 "));
+
+        let heatmap = snapshot.render_heatmap();
+        let lines: Vec<&str> = heatmap.lines().collect();
+        assert_eq!(lines[0].matches('█').count(), HEAT_WIDTH);
+        assert_eq!(lines[1].matches('█').count(), 5);
+    }
+}
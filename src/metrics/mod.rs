@@ -0,0 +1,5 @@
+pub mod client_keys;
+pub mod rollup;
+
+pub use client_keys::ClientKeySnapshot;
+pub use rollup::{MetricsStore, RollupDemo, RollupHandler, RollupReport};
@@ -0,0 +1,349 @@
+use crate::scheduler::{CatchUp, HandlerRegistry, JobHandler, RecurringJob, RecurringScheduler};
+use crate::{RedisClient, Result};
+use crate::utils::{RedisConnection, Sections};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, DurationRound, TimeZone, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use redis::{AsyncCommands, Script};
+use std::collections::BTreeMap;
+use tracing::info;
+
+pub const METRICS_KEY: &str = "metrics:names";
+
+/// Hour aggregates are only needed for recent dashboards.
+const HOUR_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// KEYS: minute counter, hour aggregate, day aggregate, minute index zset
+/// ARGV: minute member, hour TTL seconds
+///
+/// Folds one minute into its hour and day and deletes it in the same step,
+/// so a crashed or repeated compaction can never count a minute twice.
+const COMPACT_MINUTE_SCRIPT: &str = r#"
+local value = redis.call('GET', KEYS[1])
+redis.call('ZREM', KEYS[4], ARGV[1])
+if not value then
+    return 0
+end
+redis.call('INCRBY', KEYS[2], value)
+redis.call('EXPIRE', KEYS[2], ARGV[2])
+redis.call('INCRBY', KEYS[3], value)
+redis.call('DEL', KEYS[1])
+return 1
+"#;
+
+pub fn minute_stamp(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%d%H%M").to_string()
+}
+
+pub fn minute_key(metric: &str, at: DateTime<Utc>) -> String {
+    format!("metrics:{}:m:{}", metric, minute_stamp(at))
+}
+
+pub fn hour_key(metric: &str, at: DateTime<Utc>) -> String {
+    format!("metrics:{}:h:{}", metric, at.format("%Y%m%d%H"))
+}
+
+pub fn day_key(metric: &str, at: DateTime<Utc>) -> String {
+    format!("metrics:{}:d:{}", metric, at.format("%Y%m%d"))
+}
+
+/// Index of a metric's live minute keys, scored by minute start.
+pub fn minutes_key(metric: &str) -> String {
+    format!("metrics:{}:minutes", metric)
+}
+
+pub fn parse_minute_stamp(stamp: &str) -> Option<DateTime<Utc>> {
+    chrono::NaiveDateTime::parse_from_str(&format!("{}00", stamp), "%Y%m%d%H%M%S")
+        .ok()
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+fn truncate(at: DateTime<Utc>, unit: Duration) -> DateTime<Utc> {
+    at.duration_trunc(unit).expect("minute, hour and day truncation cannot overflow")
+}
+
+/// Minute-level counters that are compacted into hour and day aggregates
+/// once they fall outside the retention window.
+pub struct MetricsStore {
+    conn: RedisConnection,
+    script: Script,
+}
+
+impl MetricsStore {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            script: Script::new(COMPACT_MINUTE_SCRIPT),
+        })
+    }
+
+    pub async fn record(&mut self, metric: &str, at: DateTime<Utc>, by: i64) -> Result<()> {
+        let mut pipe = redis::pipe();
+        Self::record_in(&mut pipe, metric, at, by);
+        let _: () = pipe.query_async(&mut self.conn).await?;
+        Ok(())
+    }
+
+    /// Pipelined form of [`record`](Self::record) for bulk loads.
+    pub fn record_in(pipe: &mut redis::Pipeline, metric: &str, at: DateTime<Utc>, by: i64) {
+        let minute = truncate(at, Duration::minutes(1));
+        pipe.incr(minute_key(metric, minute), by)
+            .ignore()
+            .zadd(minutes_key(metric), minute_stamp(minute), minute.timestamp())
+            .ignore()
+            .sadd(METRICS_KEY, metric)
+            .ignore();
+    }
+
+    /// Compacts every minute older than `now - retention` for all metrics.
+    /// Returns the number of minute keys folded into aggregates.
+    pub async fn compact(&mut self, now: DateTime<Utc>, retention: Duration) -> Result<usize> {
+        let cutoff = truncate(now - retention, Duration::minutes(1));
+        let metrics: Vec<String> = self.conn.smembers(METRICS_KEY).await?;
+        let mut compacted = 0;
+        for metric in metrics {
+            let stale: Vec<String> = self
+                .conn
+                .zrangebyscore(minutes_key(&metric), "-inf", format!("({}", cutoff.timestamp()))
+                .await?;
+            for stamp in stale {
+                let Some(minute) = parse_minute_stamp(&stamp) else {
+                    let _: () = self.conn.zrem(minutes_key(&metric), &stamp).await?;
+                    continue;
+                };
+                let folded: i64 = self
+                    .script
+                    .key(minute_key(&metric, minute))
+                    .key(hour_key(&metric, minute))
+                    .key(day_key(&metric, minute))
+                    .key(minutes_key(&metric))
+                    .arg(&stamp)
+                    .arg(HOUR_TTL_SECS)
+                    .invoke_async(&mut self.conn)
+                    .await?;
+                compacted += folded as usize;
+            }
+        }
+        Ok(compacted)
+    }
+
+    /// Sum of the minute keys still live inside `[from, to)`.
+    async fn live_minutes(&mut self, metric: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<i64> {
+        let stamps: Vec<String> = self
+            .conn
+            .zrangebyscore(minutes_key(metric), from.timestamp(), format!("({}", to.timestamp()))
+            .await?;
+        if stamps.is_empty() {
+            return Ok(0);
+        }
+        let keys: Vec<String> = stamps.iter().map(|stamp| format!("metrics:{}:m:{}", metric, stamp)).collect();
+        let values: Vec<Option<i64>> = self.conn.get(keys).await?;
+        Ok(values.into_iter().flatten().sum())
+    }
+
+    /// Total for the hour containing `at`, compacted or not.
+    pub async fn hour_total(&mut self, metric: &str, at: DateTime<Utc>) -> Result<i64> {
+        let hour = truncate(at, Duration::hours(1));
+        let aggregate: Option<i64> = self.conn.get(hour_key(metric, hour)).await?;
+        let live = self.live_minutes(metric, hour, hour + Duration::hours(1)).await?;
+        Ok(aggregate.unwrap_or(0) + live)
+    }
+
+    /// Total for the UTC day containing `at`, compacted or not.
+    pub async fn day_total(&mut self, metric: &str, at: DateTime<Utc>) -> Result<i64> {
+        let day = truncate(at, Duration::days(1));
+        let aggregate: Option<i64> = self.conn.get(day_key(metric, day)).await?;
+        let live = self.live_minutes(metric, day, day + Duration::days(1)).await?;
+        Ok(aggregate.unwrap_or(0) + live)
+    }
+
+    pub async fn live_minute_count(&mut self, metric: &str) -> Result<u64> {
+        Ok(self.conn.zcard(minutes_key(metric)).await?)
+    }
+
+    /// Deletes every key belonging to `metric` in `[from, to)`.
+    pub async fn clear(&mut self, metric: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<()> {
+        let mut keys = vec![minutes_key(metric)];
+        let mut at = truncate(from, Duration::minutes(1));
+        while at < to {
+            keys.push(minute_key(metric, at));
+            if at == truncate(at, Duration::hours(1)) {
+                keys.push(hour_key(metric, at));
+            }
+            if at == truncate(at, Duration::days(1)) {
+                keys.push(day_key(metric, at));
+            }
+            at += Duration::minutes(1);
+        }
+        for chunk in keys.chunks(1000) {
+            let _: () = self.conn.del(chunk).await?;
+        }
+        let _: () = self.conn.srem(METRICS_KEY, metric).await?;
+        Ok(())
+    }
+}
+
+/// Scheduler handler that compacts minutes older than `retention`.
+pub struct RollupHandler {
+    client: RedisClient,
+    retention: Duration,
+}
+
+impl RollupHandler {
+    pub fn new(client: RedisClient, retention: Duration) -> Self {
+        Self { client, retention }
+    }
+}
+
+#[async_trait]
+impl JobHandler for RollupHandler {
+    async fn run(&self, _job: &RecurringJob, scheduled_for: DateTime<Utc>) -> Result<()> {
+        let mut store = MetricsStore::new(&self.client).await?;
+        let compacted = store.compact(scheduled_for, self.retention).await?;
+        println!("   🗜  compacted {} minute keys", compacted);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RollupReport {
+    pub minutes_before: u64,
+    pub minutes_after: u64,
+    pub mismatches: Vec<String>,
+}
+
+pub struct RollupDemo {
+    client: RedisClient,
+}
+
+impl RollupDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Records `days` of per-minute traffic, lets the scheduler run the
+    /// rollup job, and checks every hour and day total is unchanged.
+    pub async fn demonstrate(&self, days: i64, retention_hours: i64) -> Result<RollupReport> {
+        let metric = "demo_requests";
+        let now = truncate(Utc::now(), Duration::minutes(1));
+        let start = truncate(now - Duration::days(days), Duration::days(1));
+        let mut store = MetricsStore::new(&self.client).await?;
+        store.clear(metric, start, now + Duration::days(1)).await?;
+
+        println!("\n=== Metrics Rollup Compaction ===\n");
+        let mut sections = Sections::new();
+        sections.next(&format!("Recording per-minute counters from {} to {}", start.format("%Y-%m-%d"), now.format("%Y-%m-%d %H:%M")));
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut expected_hours: BTreeMap<DateTime<Utc>, i64> = BTreeMap::new();
+        let mut expected_days: BTreeMap<DateTime<Utc>, i64> = BTreeMap::new();
+        let mut at = start;
+        let mut pipe = redis::pipe();
+        let mut pending = 0;
+        while at <= now {
+            let value = rng.gen_range(0..100);
+            MetricsStore::record_in(&mut pipe, metric, at, value);
+            *expected_hours.entry(truncate(at, Duration::hours(1))).or_default() += value;
+            *expected_days.entry(truncate(at, Duration::days(1))).or_default() += value;
+            pending += 1;
+            if pending == 1000 {
+                let _: () = pipe.query_async(&mut store.conn).await?;
+                pipe = redis::pipe();
+                pending = 0;
+            }
+            at += Duration::minutes(1);
+        }
+        let _: () = pipe.query_async(&mut store.conn).await?;
+
+        let mut report = RollupReport { minutes_before: store.live_minute_count(metric).await?, ..Default::default() };
+        println!("   {} minute keys", report.minutes_before);
+
+        sections.next(&format!("Scheduler runs 'metrics_rollup' hourly (retention {}h):", retention_hours));
+        let mut scheduler = RecurringScheduler::new(&self.client).await?;
+        let mut registry = HandlerRegistry::new();
+        registry.register("metrics_rollup", RollupHandler::new(self.client.clone(), Duration::hours(retention_hours)));
+        let job = RecurringJob {
+            name: "demo-metrics-rollup".to_string(),
+            cron: "0 * * * *".to_string(),
+            handler: "metrics_rollup".to_string(),
+            catch_up: CatchUp::RunOnce,
+        };
+        scheduler.add(&job, now - Duration::hours(1)).await?;
+        scheduler.tick(&registry, now).await?;
+        scheduler.remove(&job.name).await?;
+
+        report.minutes_after = store.live_minute_count(metric).await?;
+        println!("   {} minute keys left", report.minutes_after);
+
+        sections.next("Verifying aggregates:");
+        for (hour, expected) in &expected_hours {
+            let actual = store.hour_total(metric, *hour).await?;
+            if actual != *expected {
+                report.mismatches.push(format!("hour {}: {} != {}", hour, actual, expected));
+            }
+        }
+        for (day, expected) in &expected_days {
+            let actual = store.day_total(metric, *day).await?;
+            println!("   {}  {:>7} (expected {})", day.format("%Y-%m-%d"), actual, expected);
+            if actual != *expected {
+                report.mismatches.push(format!("day {}: {} != {}", day, actual, expected));
+            }
+        }
+        println!(
+            "   {} hours and {} days checked, {} mismatches",
+            expected_hours.len(),
+            expected_days.len(),
+            report.mismatches.len()
+        );
+
+        store.clear(metric, start, now + Duration::days(1)).await?;
+        info!("Metrics rollup demo completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 14, hour, minute, 42).unwrap()
+    }
+
+    #[test]
+    fn test_keys_truncate_to_their_unit() {
+        assert_eq!(minute_key("req", at(9, 26)), "metrics:req:m:202503140926");
+        assert_eq!(hour_key("req", at(9, 26)), "metrics:req:h:2025031409");
+        assert_eq!(day_key("req", at(9, 26)), "metrics:req:d:20250314");
+    }
+
+    #[test]
+    fn test_parse_minute_stamp() {
+        let minute = truncate(at(9, 26), Duration::minutes(1));
+        assert_eq!(parse_minute_stamp(&minute_stamp(minute)), Some(minute));
+        assert_eq!(parse_minute_stamp("garbage"), None);
+    }
+
+    #[tokio::test]
+    async fn test_compaction_preserves_totals() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut store = MetricsStore::new(&client).await.unwrap();
+        let from = at(0, 0) - Duration::days(1);
+        store.clear("rollup_test", from, at(23, 59)).await.unwrap();
+
+        for minute in 0..180 {
+            store.record("rollup_test", at(8, 0) + Duration::minutes(minute), 2).await.unwrap();
+        }
+        store.record("rollup_test", at(8, 5), 1).await.unwrap();
+
+        assert_eq!(store.compact(at(10, 0), Duration::hours(1)).await.unwrap(), 60);
+        // Running again is a no-op.
+        assert_eq!(store.compact(at(10, 0), Duration::hours(1)).await.unwrap(), 0);
+        assert_eq!(store.live_minute_count("rollup_test").await.unwrap(), 120);
+        assert_eq!(store.hour_total("rollup_test", at(8, 0)).await.unwrap(), 121);
+        assert_eq!(store.hour_total("rollup_test", at(9, 0)).await.unwrap(), 120);
+        assert_eq!(store.day_total("rollup_test", at(12, 0)).await.unwrap(), 361);
+
+        store.clear("rollup_test", from, at(23, 59)).await.unwrap();
+    }
+}
@@ -0,0 +1,7 @@
+pub mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use schema::{Cell, ColumnKind, SyncAction, TableSchema};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{Conflict, MirrorReport, SqliteMirror, SyncReport};
@@ -0,0 +1,278 @@
+use crate::{DemoError, Result};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// The column holding each row's Redis key.
+pub const KEY_COLUMN: &str = "_key";
+/// The column holding the document as it was mirrored, which sync-back
+/// compares both sides against.
+pub const BASE_COLUMN: &str = "_redis_doc";
+
+/// What a document field holds across every entity of a table, and so how
+/// it is stored in SQL and read back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Integer,
+    Real,
+    /// SQL has no booleans: stored as 0 or 1.
+    Boolean,
+    Text,
+    /// Arrays, objects, and fields whose type differs between entities, as
+    /// JSON text.
+    Json,
+}
+
+impl ColumnKind {
+    /// `None` for null, which fits any column.
+    pub fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(ColumnKind::Boolean),
+            Value::Number(n) if n.is_i64() => Some(ColumnKind::Integer),
+            Value::Number(_) => Some(ColumnKind::Real),
+            Value::String(_) => Some(ColumnKind::Text),
+            Value::Array(_) | Value::Object(_) => Some(ColumnKind::Json),
+        }
+    }
+
+    /// The narrowest kind holding values of both.
+    pub fn widen(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnKind::Integer, ColumnKind::Real) | (ColumnKind::Real, ColumnKind::Integer) => ColumnKind::Real,
+            _ => ColumnKind::Json,
+        }
+    }
+
+    pub fn sql_type(&self) -> &'static str {
+        match self {
+            ColumnKind::Integer | ColumnKind::Boolean => "INTEGER",
+            ColumnKind::Real => "REAL",
+            ColumnKind::Text | ColumnKind::Json => "TEXT",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColumnKind::Integer => "integer",
+            ColumnKind::Real => "real",
+            ColumnKind::Boolean => "boolean",
+            ColumnKind::Text => "text",
+            ColumnKind::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for ColumnKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ColumnKind {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "integer" => Ok(ColumnKind::Integer),
+            "real" => Ok(ColumnKind::Real),
+            "boolean" => Ok(ColumnKind::Boolean),
+            "text" => Ok(ColumnKind::Text),
+            "json" => Ok(ColumnKind::Json),
+            other => Err(DemoError::Configuration(format!("Unknown column kind: {}", other))),
+        }
+    }
+}
+
+/// One SQL value, independent of the database library.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+/// How the entities under one key prefix map to a table: the table is
+/// named after the entity kind (`user` for `user:*`), keyed by the Redis
+/// key, with one column per field of the entity hashes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSchema {
+    pub table: String,
+    pub key_prefix: String,
+    pub columns: Vec<(String, ColumnKind)>,
+}
+
+impl TableSchema {
+    /// `user:*` gives the table `user`; the pattern has to start with a
+    /// `<kind>:` prefix, as entity keys do.
+    pub fn for_pattern(pattern: &str) -> Result<Self> {
+        let literal = &pattern[..pattern.find(['*', '?', '[', '\\']).unwrap_or(pattern.len())];
+        let Some((kind, _)) = literal.split_once(':').filter(|(kind, _)| !kind.is_empty()) else {
+            return Err(DemoError::Configuration(format!(
+                "'{}' doesn't name an entity kind; use a pattern like 'user:*'",
+                pattern
+            )));
+        };
+        let table = kind.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        Ok(Self { table, key_prefix: format!("{}:", kind), columns: Vec::new() })
+    }
+
+    /// Adds the document's fields, widening the kind of those already seen.
+    pub fn observe(&mut self, doc: &Map<String, Value>) {
+        for (field, value) in doc {
+            if field == KEY_COLUMN || field == BASE_COLUMN {
+                // Kept in the base document, just not given a column.
+                continue;
+            }
+            let position = self.columns.iter().position(|(name, _)| name == field);
+            match (position, ColumnKind::of(value)) {
+                (Some(at), Some(kind)) => self.columns[at].1 = self.columns[at].1.widen(kind),
+                (Some(_), None) => {}
+                // A field only ever null is still a column; text fits it.
+                (None, kind) => self.columns.push((field.clone(), kind.unwrap_or(ColumnKind::Text))),
+            }
+        }
+    }
+
+    /// The document's cells in column order; missing fields are NULL.
+    pub fn row(&self, doc: &Map<String, Value>) -> Vec<Cell> {
+        self.columns
+            .iter()
+            .map(|(field, kind)| match (doc.get(field), kind) {
+                (None | Some(Value::Null), _) => Cell::Null,
+                (Some(Value::Bool(b)), ColumnKind::Boolean) => Cell::Integer(*b as i64),
+                (Some(Value::Number(n)), ColumnKind::Integer) => n.as_i64().map_or(Cell::Null, Cell::Integer),
+                (Some(Value::Number(n)), ColumnKind::Real) => n.as_f64().map_or(Cell::Null, Cell::Real),
+                (Some(Value::String(s)), ColumnKind::Text) => Cell::Text(s.clone()),
+                (Some(value), _) => Cell::Text(value.to_string()),
+            })
+            .collect()
+    }
+
+    /// The document a row stands for. Fields come from `base`, the
+    /// document as mirrored, overwritten by the cells; a NULL cell only
+    /// writes null where the base had the field, so absent fields stay
+    /// absent.
+    pub fn document(&self, base: Option<&Map<String, Value>>, cells: &[Cell]) -> Result<Map<String, Value>> {
+        let mut doc = base.cloned().unwrap_or_default();
+        for ((field, kind), cell) in self.columns.iter().zip(cells) {
+            let value = match (cell, kind) {
+                (Cell::Null, _) if !doc.contains_key(field) => continue,
+                (Cell::Null, _) => Value::Null,
+                (Cell::Integer(n), ColumnKind::Boolean) => Value::Bool(*n != 0),
+                (Cell::Integer(n), _) => Value::from(*n),
+                (Cell::Real(x), _) => Value::from(*x),
+                (Cell::Text(text), ColumnKind::Json) => serde_json::from_str(text)
+                    .map_err(|e| DemoError::Configuration(format!("{} is not valid JSON: {}", field, e)))?,
+                (Cell::Text(text), _) => Value::String(text.clone()),
+            };
+            doc.insert(field.clone(), value);
+        }
+        Ok(doc)
+    }
+}
+
+/// What sync-back does with one row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncAction {
+    Unchanged,
+    /// Write the row's document; `created` when the row was added in SQL.
+    Write { created: bool },
+    /// Redis moved on since the mirror too; the row is left alone.
+    Conflict(String),
+}
+
+impl SyncAction {
+    /// `base` is the mirrored document (`None` for rows added in SQL),
+    /// `doc` what the row holds now and `current` the key's value in Redis.
+    /// Only rows edited on one side are written.
+    pub fn decide(base: Option<&Map<String, Value>>, doc: &Map<String, Value>, current: Option<&str>) -> Self {
+        if base == Some(doc) {
+            return SyncAction::Unchanged;
+        }
+        let current: Option<Value> = current.map(|text| serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())));
+        match (base, current) {
+            (None, None) => SyncAction::Write { created: true },
+            (None, Some(_)) => SyncAction::Conflict("added in SQL but the key already exists in Redis".to_string()),
+            (Some(_), None) => SyncAction::Conflict("deleted in Redis since the mirror".to_string()),
+            (Some(base), Some(Value::Object(current))) if current == *base => SyncAction::Write { created: false },
+            (Some(_), Some(_)) => SyncAction::Conflict("changed in Redis since the mirror".to_string()),
+        }
+    }
+}
+
+/// A name as a double-quoted SQL identifier.
+pub fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_table_for_pattern() {
+        let schema = TableSchema::for_pattern("user:*").unwrap();
+        assert_eq!((schema.table.as_str(), schema.key_prefix.as_str()), ("user", "user:"));
+        assert_eq!(TableSchema::for_pattern("cart-item:2024:*").unwrap().table, "cart_item");
+        assert!(TableSchema::for_pattern("*").is_err());
+    }
+
+    #[test]
+    fn test_observe_widens_kinds() {
+        let mut schema = TableSchema::for_pattern("user:*").unwrap();
+        schema.observe(&object(json!({"age": 30, "score": 1, "tags": ["a"], "city": null, "active": true})));
+        schema.observe(&object(json!({"age": 31, "score": 2.5, "city": "Oslo", "active": "yes"})));
+        let kinds: Vec<_> = schema.columns.iter().map(|(name, kind)| format!("{}:{}", name, kind)).collect();
+        assert_eq!(kinds, ["active:json", "age:integer", "city:text", "score:real", "tags:json"]);
+    }
+
+    #[test]
+    fn test_row_and_document_round_trip() {
+        let doc = object(json!({"active": true, "age": 30, "city": null, "name": "Amy", "tags": ["a", "b"]}));
+        let mut schema = TableSchema::for_pattern("user:*").unwrap();
+        schema.observe(&doc);
+        schema.observe(&object(json!({"nickname": "A"})));
+        let row = schema.row(&doc);
+        assert_eq!(row[0], Cell::Integer(1));
+        assert_eq!(row[5], Cell::Null);
+        assert_eq!(schema.document(Some(&doc), &row).unwrap(), doc);
+
+        let mut edited = row.clone();
+        edited[1] = Cell::Integer(31);
+        edited[4] = Cell::Text("[oops".to_string());
+        assert!(schema.document(Some(&doc), &edited).unwrap_err().to_string().contains("tags is not valid JSON"));
+        edited[4] = Cell::Text("[]".to_string());
+        let updated = schema.document(Some(&doc), &edited).unwrap();
+        assert_eq!(updated["age"], json!(31));
+        assert_eq!(updated["tags"], json!([]));
+        assert!(!updated.contains_key("nickname"));
+    }
+
+    #[test]
+    fn test_sync_action() {
+        let base = object(json!({"name": "Amy", "age": 30}));
+        let edited = object(json!({"name": "Amy", "age": 31}));
+        assert_eq!(SyncAction::decide(Some(&base), &base, Some("{}")), SyncAction::Unchanged);
+        assert_eq!(SyncAction::decide(Some(&base), &edited, Some(r#"{"age":30,"name":"Amy"}"#)), SyncAction::Write { created: false });
+        assert_eq!(SyncAction::decide(None, &edited, None), SyncAction::Write { created: true });
+        for (base, current, reason) in [
+            (Some(&base), Some(r#"{"name":"Amy","age":32}"#), "changed in Redis"),
+            (Some(&base), Some("not json"), "changed in Redis"),
+            (Some(&base), None, "deleted in Redis"),
+            (None, Some("{}"), "already exists"),
+        ] {
+            match SyncAction::decide(base, &edited, current) {
+                SyncAction::Conflict(why) => assert!(why.contains(reason), "{}", why),
+                other => panic!("expected a conflict, got {:?}", other),
+            }
+        }
+    }
+}
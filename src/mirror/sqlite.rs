@@ -0,0 +1,425 @@
+use super::schema::{quote, Cell, ColumnKind, SyncAction, TableSchema, BASE_COLUMN, KEY_COLUMN};
+use crate::repository::fields::{fields_object, FieldChanges, Fields};
+use crate::utils::scan::{KeyScanner, KeyType};
+use crate::utils::RedisConnection;
+use crate::{DemoError, Result};
+use redis::{AsyncCommands, Script};
+use rusqlite::types::{ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, ToSql};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Which columns each mirrored table has and what they hold, so sync-back
+/// can turn rows into documents again.
+const COLUMNS_TABLE: &str = "_mirror_columns";
+
+/// KEYS: the row's key
+/// ARGV: field count when mirrored (0 for rows added in SQL), the mirrored
+/// field/value pairs, set count, field/value pairs to set, fields to remove
+///
+/// Writes the row's fields only if the hash still holds exactly what was
+/// mirrored (or is still missing), keeping its TTL. Returns {1, false} when
+/// written and {0, current field/value pairs} when Redis moved on.
+const CHECK_AND_SET_SCRIPT: &str = r#"
+local n = tonumber(ARGV[1])
+local same = redis.call('HLEN', KEYS[1]) == n
+for i = 0, n - 1 do
+    if not same then break end
+    same = redis.call('HGET', KEYS[1], ARGV[2 + 2 * i]) == ARGV[3 + 2 * i]
+end
+if not same then
+    return {0, redis.call('HGETALL', KEYS[1])}
+end
+local at = 2 + 2 * n
+local sets = tonumber(ARGV[at])
+if sets > 0 then
+    redis.call('HSET', KEYS[1], unpack(ARGV, at + 1, at + 2 * sets))
+end
+if at + 2 * sets < #ARGV then
+    redis.call('HDEL', KEYS[1], unpack(ARGV, at + 2 * sets + 1, #ARGV))
+end
+return {1, false}
+"#;
+
+fn sql_error(e: rusqlite::Error) -> DemoError {
+    DemoError::Demo(format!("SQLite: {}", e))
+}
+
+impl ToSql for Cell {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            Cell::Null => ToSqlOutput::Borrowed(ValueRef::Null),
+            Cell::Integer(n) => ToSqlOutput::Borrowed(ValueRef::Integer(*n)),
+            Cell::Real(x) => ToSqlOutput::Borrowed(ValueRef::Real(*x)),
+            Cell::Text(text) => ToSqlOutput::Borrowed(ValueRef::Text(text.as_bytes())),
+        })
+    }
+}
+
+impl From<ValueRef<'_>> for Cell {
+    fn from(value: ValueRef<'_>) -> Self {
+        match value {
+            ValueRef::Null => Cell::Null,
+            ValueRef::Integer(n) => Cell::Integer(n),
+            ValueRef::Real(x) => Cell::Real(x),
+            ValueRef::Text(text) | ValueRef::Blob(text) => Cell::Text(String::from_utf8_lossy(text).into_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MirrorReport {
+    pub table: String,
+    pub rows: usize,
+    pub columns: Vec<(String, ColumnKind)>,
+    /// Hashes matching the pattern that are sub-keys rather than entities.
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub key: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub table: String,
+    pub unchanged: usize,
+    pub updated: usize,
+    pub created: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// One key as mirrored: `raw` is the exact text Redis held, which
+/// sync-back compares against before writing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MirroredDoc {
+    pub key: String,
+    pub raw: String,
+    pub doc: Map<String, Value>,
+}
+
+/// One row as sync-back reads it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MirroredRow {
+    pub key: String,
+    /// `None` for rows inserted in SQL.
+    pub base: Option<Map<String, Value>>,
+    /// `base` as the text Redis held.
+    pub raw: Option<String>,
+    pub cells: Vec<Cell>,
+}
+
+/// A SQLite file of entity tables, one per key prefix mirrored.
+pub struct SqliteMirror {
+    db: Connection,
+}
+
+impl SqliteMirror {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::with_connection(Connection::open(path).map_err(sql_error)?)
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory().map_err(sql_error)?)
+    }
+
+    fn with_connection(db: Connection) -> Result<Self> {
+        db.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (table_name TEXT NOT NULL, position INTEGER NOT NULL, column_name TEXT NOT NULL, kind TEXT NOT NULL, PRIMARY KEY (table_name, position))",
+            COLUMNS_TABLE
+        ))
+        .map_err(sql_error)?;
+        Ok(Self { db })
+    }
+
+    /// Replaces the schema's table with one row per document, so a second
+    /// mirror picks up new fields and drops rows for deleted keys.
+    pub fn write_table(&mut self, schema: &TableSchema, docs: &[MirroredDoc]) -> Result<()> {
+        let table = quote(&schema.table);
+        let mut columns = vec![format!("{} TEXT PRIMARY KEY", quote(KEY_COLUMN))];
+        columns.extend(schema.columns.iter().map(|(name, kind)| format!("{} {}", quote(name), kind.sql_type())));
+        columns.push(format!("{} TEXT", quote(BASE_COLUMN)));
+
+        let tx = self.db.transaction().map_err(sql_error)?;
+        tx.execute_batch(&format!("DROP TABLE IF EXISTS {table}; CREATE TABLE {table} ({});", columns.join(", ")))
+            .map_err(sql_error)?;
+        tx.execute(&format!("DELETE FROM {} WHERE table_name = ?1", COLUMNS_TABLE), [&schema.table]).map_err(sql_error)?;
+        for (position, (name, kind)) in schema.columns.iter().enumerate() {
+            tx.execute(
+                &format!("INSERT INTO {} (table_name, position, column_name, kind) VALUES (?1, ?2, ?3, ?4)", COLUMNS_TABLE),
+                params![schema.table, position, name, kind.as_str()],
+            )
+            .map_err(sql_error)?;
+        }
+        {
+            let placeholders: Vec<String> = (1..=schema.columns.len() + 2).map(|n| format!("?{}", n)).collect();
+            let mut insert = tx.prepare(&format!("INSERT INTO {} VALUES ({})", table, placeholders.join(", "))).map_err(sql_error)?;
+            for MirroredDoc { key, raw, doc } in docs {
+                let mut cells = vec![Cell::Text(key.clone())];
+                cells.extend(schema.row(doc));
+                cells.push(Cell::Text(raw.clone()));
+                insert.execute(rusqlite::params_from_iter(&cells)).map_err(sql_error)?;
+            }
+        }
+        tx.commit().map_err(sql_error)
+    }
+
+    /// The tables a mirror wrote, in name order.
+    pub fn tables(&self) -> Result<Vec<String>> {
+        let mut query = self.db.prepare(&format!("SELECT DISTINCT table_name FROM {} ORDER BY table_name", COLUMNS_TABLE)).map_err(sql_error)?;
+        let tables = query.query_map([], |row| row.get(0)).map_err(sql_error)?;
+        tables.collect::<rusqlite::Result<_>>().map_err(sql_error)
+    }
+
+    pub fn read_table(&self, table: &str) -> Result<(TableSchema, Vec<MirroredRow>)> {
+        let mut query = self
+            .db
+            .prepare(&format!("SELECT column_name, kind FROM {} WHERE table_name = ?1 ORDER BY position", COLUMNS_TABLE))
+            .map_err(sql_error)?;
+        let columns = query
+            .query_map([table], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(sql_error)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(sql_error)?;
+        let schema = TableSchema {
+            table: table.to_string(),
+            key_prefix: String::new(),
+            columns: columns.into_iter().map(|(name, kind)| Ok((name, kind.parse()?))).collect::<Result<_>>()?,
+        };
+
+        let mut selected = vec![quote(KEY_COLUMN)];
+        selected.extend(schema.columns.iter().map(|(name, _)| quote(name)));
+        selected.push(quote(BASE_COLUMN));
+        let mut query = self.db.prepare(&format!("SELECT {} FROM {}", selected.join(", "), quote(table))).map_err(sql_error)?;
+        let width = schema.columns.len();
+        let rows = query
+            .query_map([], |row| {
+                let cells = (1..=width).map(|at| row.get_ref(at).map(Cell::from)).collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(width + 1)?, cells))
+            })
+            .map_err(sql_error)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(sql_error)?;
+        let rows = rows
+            .into_iter()
+            .map(|(key, raw, cells)| {
+                let base = raw.as_deref().map(serde_json::from_str::<Fields>).transpose()?.map(|fields| fields_object(&fields));
+                Ok(MirroredRow { key, base, raw, cells })
+            })
+            .collect::<Result<_>>()?;
+        Ok((schema, rows))
+    }
+
+    /// Records `raw` as what Redis now holds for the row's key.
+    pub fn mark_synced(&self, table: &str, key: &str, raw: &str) -> Result<()> {
+        self.db
+            .execute(
+                &format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", quote(table), quote(BASE_COLUMN), quote(KEY_COLUMN)),
+                params![raw, key],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+}
+
+/// Copies the entity hashes matching `pattern` into the table named after
+/// their kind, inferring one column per field. Sub-keys such as
+/// `user:<id>:sessions` are skipped.
+pub async fn mirror(conn: &mut RedisConnection, sqlite: &mut SqliteMirror, pattern: &str, batch_size: usize) -> Result<MirrorReport> {
+    let mut schema = TableSchema::for_pattern(pattern)?;
+    let keys = KeyScanner::new(conn.clone(), pattern, Some(KeyType::Hash)).with_count(batch_size).collect_all().await?;
+    let mut docs = Vec::with_capacity(keys.len());
+    let mut skipped = 0;
+    for batch in keys.chunks(batch_size.max(1)) {
+        let mut pipe = redis::pipe();
+        for key in batch {
+            pipe.hgetall(key);
+        }
+        let hashes: Vec<Fields> = pipe.query_async(conn).await?;
+        for (key, fields) in batch.iter().zip(hashes) {
+            if fields.is_empty() || key.split(':').count() != 2 {
+                skipped += 1;
+                continue;
+            }
+            let doc = fields_object(&fields);
+            schema.observe(&doc);
+            docs.push(MirroredDoc { key: key.clone(), raw: serde_json::to_string(&fields)?, doc });
+        }
+    }
+    docs.sort_by(|a, b| a.key.cmp(&b.key));
+    sqlite.write_table(&schema, &docs)?;
+    Ok(MirrorReport { table: schema.table, rows: docs.len(), columns: schema.columns, skipped })
+}
+
+/// Writes rows edited in SQL back to Redis, table by table. A row is only
+/// written when its hash still holds the fields it was mirrored from; when
+/// both sides changed it is reported as a conflict instead. The check and
+/// the write are one script, so a concurrent write is never lost, and the
+/// key keeps its TTL. Only changed fields are sent. Rows deleted in SQL are
+/// not deleted from Redis.
+pub async fn sync_back(conn: &mut RedisConnection, sqlite: &SqliteMirror, dry_run: bool) -> Result<Vec<SyncReport>> {
+    let check_and_set = Script::new(CHECK_AND_SET_SCRIPT);
+    let mut reports = Vec::new();
+    for table in sqlite.tables()? {
+        let (schema, rows) = sqlite.read_table(&table)?;
+        let mut report = SyncReport { table: table.clone(), ..Default::default() };
+        for row in rows {
+            let doc = match schema.document(row.base.as_ref(), &row.cells) {
+                Ok(doc) => doc,
+                Err(e) => {
+                    report.conflicts.push(Conflict { key: row.key, reason: e.to_string() });
+                    continue;
+                }
+            };
+            if row.base.as_ref() == Some(&doc) {
+                report.unchanged += 1;
+                continue;
+            }
+            let fields: Fields = doc.iter().map(|(field, value)| (field.clone(), value.to_string())).collect();
+            let action = match dry_run {
+                true => conn.hgetall(&row.key).await.map(|current: Fields| SyncAction::decide(row.base.as_ref(), &doc, document_text(&current).as_deref())).map_err(DemoError::from),
+                false => write_if_unchanged(conn, &check_and_set, &row, &doc, &fields).await,
+            };
+            match action {
+                Ok(SyncAction::Unchanged) => report.unchanged += 1,
+                Ok(SyncAction::Conflict(reason)) => report.conflicts.push(Conflict { key: row.key, reason }),
+                Ok(SyncAction::Write { created }) => {
+                    if !dry_run {
+                        sqlite.mark_synced(&table, &row.key, &serde_json::to_string(&fields)?)?;
+                    }
+                    match created {
+                        true => report.created += 1,
+                        false => report.updated += 1,
+                    }
+                }
+                Err(e) => report.conflicts.push(Conflict { key: row.key, reason: e.to_string() }),
+            }
+        }
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+/// A hash as the JSON document [`SyncAction::decide`] compares, `None`
+/// when the key is missing.
+fn document_text(fields: &Fields) -> Option<String> {
+    (!fields.is_empty()).then(|| Value::Object(fields_object(fields)).to_string())
+}
+
+/// Writes `fields` to the row's key if it still holds what was mirrored.
+/// When it doesn't, [`SyncAction::decide`] on what it holds names the
+/// conflict.
+async fn write_if_unchanged(conn: &mut RedisConnection, script: &Script, row: &MirroredRow, doc: &Map<String, Value>, fields: &Fields) -> Result<SyncAction> {
+    let mirrored: Fields = row.raw.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default();
+    let changes = FieldChanges::between(&mirrored, fields);
+    let mut invocation = script.key(&row.key);
+    invocation.arg(mirrored.len());
+    for (field, value) in &mirrored {
+        invocation.arg(field).arg(value);
+    }
+    invocation.arg(changes.set.len());
+    for (field, value) in &changes.set {
+        invocation.arg(field).arg(value);
+    }
+    invocation.arg(&changes.removed);
+    let (written, current): (bool, Option<Fields>) = invocation.invoke_async(conn).await?;
+    if written {
+        return Ok(SyncAction::Write { created: row.raw.is_none() });
+    }
+    let current = document_text(&current.unwrap_or_default());
+    Ok(match SyncAction::decide(row.base.as_ref(), doc, current.as_deref()) {
+        SyncAction::Conflict(reason) => SyncAction::Conflict(reason),
+        _ => SyncAction::Conflict("changed in Redis since the mirror".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    type Docs = Vec<MirroredDoc>;
+
+    fn docs() -> (TableSchema, Docs) {
+        let mut schema = TableSchema::for_pattern("user:*").unwrap();
+        let docs: Docs = [
+            ("user:1", json!({"name": "Amy", "age": 30, "tags": ["admin"], "active": true})),
+            ("user:2", json!({"name": "Bo", "score": 2.5})),
+        ]
+        .into_iter()
+        .map(|(key, doc)| {
+            let doc = doc.as_object().unwrap().clone();
+            let fields: Fields = doc.iter().map(|(field, value)| (field.clone(), value.to_string())).collect();
+            MirroredDoc { key: key.to_string(), raw: serde_json::to_string(&fields).unwrap(), doc }
+        })
+        .collect();
+        for MirroredDoc { doc, .. } in &docs {
+            schema.observe(doc);
+        }
+        (schema, docs)
+    }
+
+    #[test]
+    fn test_rows_round_trip_through_sqlite() {
+        let (schema, docs) = docs();
+        let mut sqlite = SqliteMirror::in_memory().unwrap();
+        sqlite.write_table(&schema, &docs).unwrap();
+        let adults: i64 = sqlite.db.query_row("SELECT COUNT(*) FROM user WHERE age >= 18 AND active", [], |row| row.get(0)).unwrap();
+        assert_eq!(adults, 1);
+
+        let (read, rows) = sqlite.read_table("user").unwrap();
+        assert_eq!(read.columns, schema.columns);
+        assert_eq!(sqlite.tables().unwrap(), ["user"]);
+        for (row, MirroredDoc { key, raw, doc }) in rows.iter().zip(&docs) {
+            assert_eq!(&row.key, key);
+            assert_eq!(row.raw.as_ref(), Some(raw));
+            assert_eq!(row.base.as_ref(), Some(doc));
+            assert_eq!(&read.document(row.base.as_ref(), &row.cells).unwrap(), doc);
+        }
+    }
+
+    #[test]
+    fn test_edits_and_inserts_read_back_as_documents() {
+        let (schema, docs) = docs();
+        let mut sqlite = SqliteMirror::in_memory().unwrap();
+        sqlite.write_table(&schema, &docs).unwrap();
+        sqlite.db.execute_batch("UPDATE user SET age = 31 WHERE _key = 'user:1'; INSERT INTO user (_key, name) VALUES ('user:3', 'Cy');").unwrap();
+
+        let (read, rows) = sqlite.read_table("user").unwrap();
+        let amy = rows.iter().find(|row| row.key == "user:1").unwrap();
+        let doc = read.document(amy.base.as_ref(), &amy.cells).unwrap();
+        assert_eq!(doc["age"], json!(31));
+        let fields: Fields = doc.iter().map(|(field, value)| (field.clone(), value.to_string())).collect();
+        sqlite.mark_synced("user", "user:1", &serde_json::to_string(&fields).unwrap()).unwrap();
+        let cy = rows.iter().find(|row| row.key == "user:3").unwrap();
+        assert_eq!(cy.base, None);
+        assert_eq!(Value::Object(read.document(None, &cy.cells).unwrap()), json!({"name": "Cy"}));
+
+        let (_, rows) = sqlite.read_table("user").unwrap();
+        assert_eq!(rows.iter().find(|row| row.key == "user:1").unwrap().base.as_ref(), Some(&doc));
+    }
+
+    #[tokio::test]
+    async fn test_sync_back_keeps_ttl_and_skips_keys_changed_since() {
+        let client = crate::RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.hset_multiple("syncttl:1", &[("age", "30"), ("name", "\"Amy\"")]).await.unwrap();
+        let _: () = conn.expire("syncttl:1", 600).await.unwrap();
+        let _: () = conn.hset_multiple("syncttl:2", &[("age", "40"), ("name", "\"Bo\"")]).await.unwrap();
+        let mut sqlite = SqliteMirror::in_memory().unwrap();
+        mirror(&mut conn, &mut sqlite, "syncttl:*", 100).await.unwrap();
+        sqlite.db.execute_batch("UPDATE syncttl SET age = age + 1").unwrap();
+        let _: () = conn.hset("syncttl:2", "age", "41").await.unwrap();
+
+        let report = sync_back(&mut conn, &sqlite, false).await.unwrap().remove(0);
+        assert_eq!(report.updated, 1);
+        assert_eq!(report.conflicts, [Conflict { key: "syncttl:2".to_string(), reason: "changed in Redis since the mirror".to_string() }]);
+        let age: String = conn.hget("syncttl:1", "age").await.unwrap();
+        assert_eq!(age, "31");
+        let ttl: i64 = conn.ttl("syncttl:1").await.unwrap();
+        assert!(ttl > 0, "{}", ttl);
+        let _: () = conn.del(&["syncttl:1", "syncttl:2"]).await.unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::{Duration, NaiveDate};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FunnelStep {
+    Visited,
+    SignedUp,
+    Purchased,
+}
+
+impl FunnelStep {
+    pub const ALL: [FunnelStep; 3] = [FunnelStep::Visited, FunnelStep::SignedUp, FunnelStep::Purchased];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FunnelStep::Visited => "visited",
+            FunnelStep::SignedUp => "signed_up",
+            FunnelStep::Purchased => "purchased",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivityEvent {
+    pub user: usize,
+    pub day: NaiveDate,
+    pub step: FunnelStep,
+}
+
+/// Deterministic product activity for analytics demos. Each user first shows
+/// up on a random day, keeps visiting with a decaying probability, and may
+/// sign up and later purchase along the way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivityLog {
+    pub start: NaiveDate,
+    pub days: u32,
+    pub users: usize,
+    pub events: Vec<ActivityEvent>,
+}
+
+impl ActivityLog {
+    pub fn generate(users: usize, start: NaiveDate, days: u32, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut events = Vec::new();
+
+        for user in 0..users {
+            let first_day = rng.gen_range(0..days.max(1));
+            let mut signed_up = false;
+            let mut purchased = false;
+            let mut stickiness = 1.0;
+
+            for offset in first_day..days {
+                if offset > first_day && !rng.gen_bool(stickiness) {
+                    continue;
+                }
+                let day = start + Duration::days(offset as i64);
+                events.push(ActivityEvent { user, day, step: FunnelStep::Visited });
+                if !signed_up && rng.gen_bool(0.15) {
+                    signed_up = true;
+                    events.push(ActivityEvent { user, day, step: FunnelStep::SignedUp });
+                } else if signed_up && !purchased && rng.gen_bool(0.2) {
+                    purchased = true;
+                    events.push(ActivityEvent { user, day, step: FunnelStep::Purchased });
+                }
+                stickiness = (stickiness * 0.85_f64).max(0.05);
+            }
+        }
+
+        Self { start, days, users, events }
+    }
+
+    pub fn end(&self) -> NaiveDate {
+        self.start + Duration::days(self.days.saturating_sub(1) as i64)
+    }
+
+    pub fn users_with(&self, step: FunnelStep, from: NaiveDate, to: NaiveDate) -> usize {
+        let mut users: Vec<usize> = self
+            .events
+            .iter()
+            .filter(|event| event.step == step && event.day >= from && event.day <= to)
+            .map(|event| event.user)
+            .collect();
+        users.sort_unstable();
+        users.dedup();
+        users.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, 6).unwrap()
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        assert_eq!(ActivityLog::generate(100, start(), 14, 7), ActivityLog::generate(100, start(), 14, 7));
+    }
+
+    #[test]
+    fn test_events_stay_in_range_and_steps_are_ordered() {
+        let log = ActivityLog::generate(200, start(), 14, 1);
+        assert!(log.events.iter().all(|event| event.day >= start() && event.day <= log.end()));
+
+        let from = start();
+        let to = log.end();
+        let visited = log.users_with(FunnelStep::Visited, from, to);
+        let signed_up = log.users_with(FunnelStep::SignedUp, from, to);
+        let purchased = log.users_with(FunnelStep::Purchased, from, to);
+        assert_eq!(visited, 200);
+        assert!(signed_up < visited && purchased < signed_up && purchased > 0);
+    }
+}
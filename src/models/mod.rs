@@ -1,3 +1,7 @@
+pub mod activity;
+pub mod social;
 pub mod user;
 
-pub use user::User;
\ No newline at end of file
+pub use activity::{ActivityEvent, ActivityLog, FunnelStep};
+pub use social::SocialGraph;
+pub use user::User;
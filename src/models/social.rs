@@ -0,0 +1,88 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Deterministic follower graph for feed and graph demos. The first
+/// `celebrities` users are followed by most of the network, everyone else
+/// follows a handful of random accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocialGraph {
+    pub users: Vec<String>,
+    pub celebrities: Vec<String>,
+    /// `(follower, followee)` pairs, without duplicates or self-follows.
+    pub follows: Vec<(String, String)>,
+}
+
+impl SocialGraph {
+    pub fn generate(users: usize, celebrities: usize, follows_per_user: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let names: Vec<String> = (0..users).map(|i| format!("user{}", i)).collect();
+        let celebrities: Vec<String> = names.iter().take(celebrities).cloned().collect();
+
+        let mut follows = Vec::new();
+        for (i, follower) in names.iter().enumerate() {
+            let mut picked: Vec<usize> = (0..users).filter(|j| *j != i).collect();
+            picked.shuffle(&mut rng);
+            let mut followees: Vec<usize> = picked.into_iter().take(follows_per_user).collect();
+            for (c, _) in celebrities.iter().enumerate() {
+                if c != i && !followees.contains(&c) && rng.gen_bool(0.8) {
+                    followees.push(c);
+                }
+            }
+            followees.sort_unstable();
+            follows.extend(followees.into_iter().map(|j| (follower.clone(), names[j].clone())));
+        }
+
+        Self {
+            users: names,
+            celebrities,
+            follows,
+        }
+    }
+
+    pub fn following(&self, user: &str) -> Vec<&str> {
+        self.follows
+            .iter()
+            .filter(|(follower, _)| follower == user)
+            .map(|(_, followee)| followee.as_str())
+            .collect()
+    }
+
+    pub fn followers(&self, user: &str) -> Vec<&str> {
+        self.follows
+            .iter()
+            .filter(|(_, followee)| followee == user)
+            .map(|(follower, _)| follower.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        assert_eq!(SocialGraph::generate(20, 2, 3, 7), SocialGraph::generate(20, 2, 3, 7));
+        assert_ne!(SocialGraph::generate(20, 2, 3, 7), SocialGraph::generate(20, 2, 3, 8));
+    }
+
+    #[test]
+    fn test_generate_has_no_self_follows_or_duplicates() {
+        let graph = SocialGraph::generate(30, 2, 4, 1);
+        let mut seen = std::collections::HashSet::new();
+        for (follower, followee) in &graph.follows {
+            assert_ne!(follower, followee);
+            assert!(seen.insert((follower, followee)));
+        }
+    }
+
+    #[test]
+    fn test_celebrities_are_widely_followed() {
+        let graph = SocialGraph::generate(50, 2, 3, 42);
+        for celebrity in &graph.celebrities {
+            assert!(graph.followers(celebrity).len() > 25);
+        }
+        assert!(graph.following("user10").len() >= 3);
+    }
+}
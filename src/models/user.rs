@@ -1,11 +1,15 @@
+use crate::repository::RedisEntity;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, RedisEntity)]
+#[redis(prefix = "user", validate = "crate::repository::user::validate_user")]
 pub struct User {
     pub id: Uuid,
+    #[redis(index)]
     pub username: String,
+    #[redis(index)]
     pub email: String,
     pub full_name: String,
     pub age: Option<u8>,
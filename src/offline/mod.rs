@@ -0,0 +1,7 @@
+pub mod protocol;
+pub mod server;
+pub mod store;
+
+pub use protocol::Reply;
+pub use server::MiniRedis;
+pub use store::Store;
@@ -0,0 +1,162 @@
+use std::fmt::Write as _;
+
+/// A RESP2 reply from the embedded server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reply {
+    Status(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Vec<u8>),
+    Nil,
+    Array(Vec<Reply>),
+    NilArray,
+}
+
+impl Reply {
+    pub fn ok() -> Self {
+        Reply::Status("OK".to_string())
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Reply::Error(message.into())
+    }
+
+    pub fn bulk(value: impl Into<Vec<u8>>) -> Self {
+        Reply::Bulk(value.into())
+    }
+
+    pub fn bulks<I, V>(values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Vec<u8>>,
+    {
+        Reply::Array(values.into_iter().map(Reply::bulk).collect())
+    }
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Reply::Status(status) => out.extend_from_slice(format!("+{}\r\n", status).as_bytes()),
+            Reply::Error(message) => out.extend_from_slice(format!("-{}\r\n", message).as_bytes()),
+            Reply::Integer(n) => out.extend_from_slice(format!(":{}\r\n", n).as_bytes()),
+            Reply::Bulk(value) => {
+                out.extend_from_slice(format!("${}\r\n", value.len()).as_bytes());
+                out.extend_from_slice(value);
+                out.extend_from_slice(b"\r\n");
+            }
+            Reply::Nil => out.extend_from_slice(b"$-1\r\n"),
+            Reply::Array(items) => {
+                out.extend_from_slice(format!("*{}\r\n", items.len()).as_bytes());
+                for item in items {
+                    item.encode(out);
+                }
+            }
+            Reply::NilArray => out.extend_from_slice(b"*-1\r\n"),
+        }
+    }
+}
+
+/// A request's arguments, command name first.
+pub type Args = Vec<Vec<u8>>;
+
+/// Why a request could not be parsed; the connection is closed after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolError(pub String);
+
+fn line(buf: &[u8]) -> Option<(&[u8], usize)> {
+    let end = buf.windows(2).position(|w| w == b"\r\n")?;
+    Some((&buf[..end], end + 2))
+}
+
+fn number(text: &[u8]) -> Result<i64, ProtocolError> {
+    std::str::from_utf8(text)
+        .ok()
+        .and_then(|text| text.parse().ok())
+        .ok_or_else(|| ProtocolError(format!("invalid length '{}'", String::from_utf8_lossy(text))))
+}
+
+/// Parses one request off the front of `buf`: a RESP array of bulk strings
+/// as clients send, or an inline command as typed into telnet. Returns the
+/// arguments and how many bytes they took, or `None` if more input is needed.
+pub fn parse_request(buf: &[u8]) -> Result<Option<(Args, usize)>, ProtocolError> {
+    let Some((header, mut pos)) = line(buf) else {
+        return Ok(None);
+    };
+    if header.first() != Some(&b'*') {
+        let args = header.split(|b| b.is_ascii_whitespace()).filter(|arg| !arg.is_empty()).map(<[u8]>::to_vec).collect();
+        return Ok(Some((args, pos)));
+    }
+    let count = number(&header[1..])?;
+    let mut args = Vec::with_capacity(count.max(0) as usize);
+    for _ in 0..count {
+        let Some((header, used)) = line(&buf[pos..]) else {
+            return Ok(None);
+        };
+        if header.first() != Some(&b'$') {
+            return Err(ProtocolError("expected a bulk string".to_string()));
+        }
+        let len = number(&header[1..])?;
+        if len < 0 {
+            return Err(ProtocolError("null bulk string in a request".to_string()));
+        }
+        let start = pos + used;
+        let end = start + len as usize;
+        if buf.len() < end + 2 {
+            return Ok(None);
+        }
+        if &buf[end..end + 2] != b"\r\n" {
+            return Err(ProtocolError("bulk string not terminated by CRLF".to_string()));
+        }
+        args.push(buf[start..end].to_vec());
+        pos = end + 2;
+    }
+    Ok(Some((args, pos)))
+}
+
+/// Formats a float the way Redis does in replies: `3` rather than `3.0`.
+pub fn format_float(value: f64) -> String {
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    let mut text = String::new();
+    if value.fract() == 0.0 && value.abs() < 1e17 {
+        let _ = write!(text, "{}", value as i64);
+    } else {
+        let _ = write!(text, "{}", value);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let request = b"*2\r\n$3\r\nGET\r\n$5\r\nhello\r\n*1\r\n$4\r\nPING\r\n";
+        let (args, used) = parse_request(request).unwrap().unwrap();
+        assert_eq!(args, [b"GET".to_vec(), b"hello".to_vec()]);
+        assert_eq!(parse_request(&request[used..]).unwrap().unwrap().0, [b"PING".to_vec()]);
+
+        for cut in 1..used {
+            assert_eq!(parse_request(&request[..cut]).unwrap(), None, "cut at {}", cut);
+        }
+        assert_eq!(parse_request(b"SET a  1\r\n").unwrap().unwrap().0, [b"SET".to_vec(), b"a".to_vec(), b"1".to_vec()]);
+        assert!(parse_request(b"*1\r\n:1\r\n").is_err());
+        assert!(parse_request(b"*x\r\n").is_err());
+    }
+
+    #[test]
+    fn test_encode_replies() {
+        let mut out = Vec::new();
+        Reply::Array(vec![Reply::ok(), Reply::Integer(-2), Reply::bulk("hi"), Reply::Nil, Reply::error("ERR no")])
+            .encode(&mut out);
+        assert_eq!(out, b"*5\r\n+OK\r\n:-2\r\n$2\r\nhi\r\n$-1\r\n-ERR no\r\n");
+    }
+
+    #[test]
+    fn test_format_float() {
+        assert_eq!(format_float(3.0), "3");
+        assert_eq!(format_float(-1.5), "-1.5");
+        assert_eq!(format_float(f64::INFINITY), "inf");
+    }
+}
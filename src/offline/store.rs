@@ -0,0 +1,1207 @@
+use super::protocol::{format_float, Reply};
+use crate::utils::glob::glob_match;
+use rand::seq::IteratorRandom;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::ops::{Bound, RangeBounds};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+const NOT_INTEGER: &str = "ERR value is not an integer or out of range";
+const NOT_FLOAT: &str = "ERR value is not a valid float";
+const SYNTAX: &str = "ERR syntax error";
+
+/// Databases, as selected with `SELECT`.
+pub const DATABASES: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Data {
+    String(Vec<u8>),
+    List(VecDeque<Vec<u8>>),
+    Set(BTreeSet<Vec<u8>>),
+    Hash(Hash),
+    /// Member to score; ranges sort by `(score, member)` on demand, which is
+    /// plenty for demo-sized sets.
+    ZSet(ZSet),
+}
+
+impl Data {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Data::String(_) => "string",
+            Data::List(_) => "list",
+            Data::Set(_) => "set",
+            Data::Hash(_) => "hash",
+            Data::ZSet(_) => "zset",
+        }
+    }
+
+    /// A rough MEMORY USAGE: payload bytes plus a fixed overhead per
+    /// element, close enough for demos that compare keys by size.
+    fn approx_bytes(&self) -> usize {
+        const PER_ELEMENT: usize = 16;
+        match self {
+            Data::String(value) => value.len(),
+            Data::List(items) => items.iter().map(|item| item.len() + PER_ELEMENT).sum(),
+            Data::Set(members) => members.iter().map(|member| member.len() + PER_ELEMENT).sum(),
+            Data::Hash(fields) => fields.iter().map(|(field, value)| field.len() + value.len() + PER_ELEMENT).sum(),
+            Data::ZSet(members) => members.keys().map(|member| member.len() + 8 + PER_ELEMENT).sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    data: Data,
+    expires_at: Option<Instant>,
+}
+
+type Db = HashMap<Vec<u8>, Entry>;
+type Hash = BTreeMap<Vec<u8>, Vec<u8>>;
+type ZSet = BTreeMap<Vec<u8>, f64>;
+
+fn text(arg: &[u8]) -> String {
+    String::from_utf8_lossy(arg).into_owned()
+}
+
+fn int(arg: &[u8]) -> Result<i64, Reply> {
+    std::str::from_utf8(arg).ok().and_then(|s| s.parse().ok()).ok_or_else(|| Reply::error(NOT_INTEGER))
+}
+
+fn float(arg: &[u8]) -> Result<f64, Reply> {
+    let parsed = match text(arg).to_ascii_lowercase().as_str() {
+        "inf" | "+inf" => Some(f64::INFINITY),
+        "-inf" => Some(f64::NEG_INFINITY),
+        other => other.parse::<f64>().ok().filter(|f| !f.is_nan()),
+    };
+    parsed.ok_or_else(|| Reply::error(NOT_FLOAT))
+}
+
+/// A score bound for ZRANGEBYSCORE/ZCOUNT: `(` makes it exclusive.
+fn score_bound(arg: &[u8]) -> Result<(f64, bool), Reply> {
+    match arg.strip_prefix(b"(") {
+        Some(rest) => Ok((float(rest)?, true)),
+        None => Ok((float(arg)?, false)),
+    }
+}
+
+/// A member bound for ZRANGEBYLEX: `-`/`+` for unbounded, `[` inclusive
+/// and `(` exclusive.
+fn lex_bound(arg: &[u8]) -> Result<Bound<Vec<u8>>, Reply> {
+    match arg.split_first() {
+        Some((b'-', [])) | Some((b'+', [])) => Ok(Bound::Unbounded),
+        Some((b'[', rest)) => Ok(Bound::Included(rest.to_vec())),
+        Some((b'(', rest)) => Ok(Bound::Excluded(rest.to_vec())),
+        _ => Err(Reply::error("ERR min or max not valid string range item")),
+    }
+}
+
+fn parse_limit(options: &[Vec<u8>]) -> Result<Option<(usize, usize)>, Reply> {
+    match options {
+        [] => Ok(None),
+        [flag, offset, count] if text(flag).eq_ignore_ascii_case("LIMIT") => {
+            let count = int(count)?;
+            Ok(Some((int(offset)?.max(0) as usize, if count < 0 { usize::MAX } else { count as usize })))
+        }
+        _ => Err(Reply::error(SYNTAX)),
+    }
+}
+
+fn in_bounds(score: f64, (min, min_open): (f64, bool), (max, max_open): (f64, bool)) -> bool {
+    (if min_open { score > min } else { score >= min }) && (if max_open { score < max } else { score <= max })
+}
+
+/// Resolves Redis' inclusive, possibly negative `start..=stop` against `len`.
+fn range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+    (start <= stop && start < len).then_some((start as usize, stop as usize))
+}
+
+fn scored(zset: &ZSet) -> Vec<(&Vec<u8>, f64)> {
+    let mut members: Vec<_> = zset.iter().map(|(member, score)| (member, *score)).collect();
+    members.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+    members
+}
+
+fn with_scores(members: Vec<(&Vec<u8>, f64)>, scores: bool) -> Reply {
+    let mut items = Vec::new();
+    for (member, score) in members {
+        items.push(Reply::bulk(member.clone()));
+        if scores {
+            items.push(Reply::bulk(format_float(score)));
+        }
+    }
+    Reply::Array(items)
+}
+
+/// The in-memory keyspace behind the offline server. Commands run one at a
+/// time under the server's lock, so each is atomic like in Redis. Expired
+/// keys are dropped lazily when touched and when keys are listed.
+#[derive(Debug)]
+pub struct Store {
+    dbs: Vec<Db>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self { dbs: vec![Db::new(); DATABASES] }
+    }
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn live(&mut self, db: usize, key: &[u8]) -> Option<&mut Entry> {
+        let expired = self.dbs[db].get(key)?.expires_at.is_some_and(|at| at <= Instant::now());
+        if expired {
+            self.dbs[db].remove(key);
+            return None;
+        }
+        self.dbs[db].get_mut(key)
+    }
+
+    fn purge_expired(&mut self, db: usize) {
+        let now = Instant::now();
+        self.dbs[db].retain(|_, entry| entry.expires_at.is_none_or(|at| at > now));
+    }
+
+    fn sorted_keys(&mut self, db: usize) -> Vec<Vec<u8>> {
+        self.purge_expired(db);
+        let mut keys: Vec<_> = self.dbs[db].keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// The value at `key`, created as `empty` when missing. Fails with
+    /// WRONGTYPE when the key holds another type.
+    fn get_or_create(&mut self, db: usize, key: &[u8], empty: Data) -> Result<&mut Data, Reply> {
+        if self.live(db, key).is_none() {
+            self.dbs[db].insert(key.to_vec(), Entry { data: empty.clone(), expires_at: None });
+        }
+        let entry = self.dbs[db].get_mut(key).expect("just inserted");
+        if std::mem::discriminant(&entry.data) != std::mem::discriminant(&empty) {
+            return Err(Reply::error(WRONGTYPE));
+        }
+        Ok(&mut entry.data)
+    }
+
+    /// Deletes containers left empty by a removal, as Redis does.
+    fn drop_if_empty(&mut self, db: usize, key: &[u8]) {
+        let empty = match self.dbs[db].get(key).map(|entry| &entry.data) {
+            Some(Data::List(list)) => list.is_empty(),
+            Some(Data::Set(set)) => set.is_empty(),
+            Some(Data::Hash(hash)) => hash.is_empty(),
+            Some(Data::ZSet(zset)) => zset.is_empty(),
+            _ => false,
+        };
+        if empty {
+            self.dbs[db].remove(key);
+        }
+    }
+
+    fn string(&mut self, db: usize, key: &[u8]) -> Result<Option<&mut Vec<u8>>, Reply> {
+        match self.live(db, key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Data::String(value)) => Ok(Some(value)),
+            Some(_) => Err(Reply::error(WRONGTYPE)),
+        }
+    }
+
+    fn list(&mut self, db: usize, key: &[u8]) -> Result<Option<&mut VecDeque<Vec<u8>>>, Reply> {
+        match self.live(db, key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Data::List(list)) => Ok(Some(list)),
+            Some(_) => Err(Reply::error(WRONGTYPE)),
+        }
+    }
+
+    fn set(&mut self, db: usize, key: &[u8]) -> Result<Option<&mut BTreeSet<Vec<u8>>>, Reply> {
+        match self.live(db, key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Data::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(Reply::error(WRONGTYPE)),
+        }
+    }
+
+    fn hash(&mut self, db: usize, key: &[u8]) -> Result<Option<&mut Hash>, Reply> {
+        match self.live(db, key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Data::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(Reply::error(WRONGTYPE)),
+        }
+    }
+
+    fn zset(&mut self, db: usize, key: &[u8]) -> Result<Option<&mut ZSet>, Reply> {
+        match self.live(db, key).map(|entry| &mut entry.data) {
+            None => Ok(None),
+            Some(Data::ZSet(zset)) => Ok(Some(zset)),
+            Some(_) => Err(Reply::error(WRONGTYPE)),
+        }
+    }
+
+    fn incr_by(&mut self, db: usize, key: &[u8], by: i64) -> Result<Reply, Reply> {
+        let current = match self.string(db, key)? {
+            Some(value) => int(value)?,
+            None => 0,
+        };
+        let next = current.checked_add(by).ok_or_else(|| Reply::error("ERR increment or decrement would overflow"))?;
+        self.write_string(db, key, next.to_string().into_bytes(), true);
+        Ok(Reply::Integer(next))
+    }
+
+    fn write_string(&mut self, db: usize, key: &[u8], value: Vec<u8>, keep_ttl: bool) {
+        let expires_at = if keep_ttl { self.live(db, key).and_then(|entry| entry.expires_at) } else { None };
+        self.dbs[db].insert(key.to_vec(), Entry { data: Data::String(value), expires_at });
+    }
+
+    fn expire_at(&mut self, db: usize, key: &[u8], at: Instant) -> Reply {
+        match self.live(db, key) {
+            Some(entry) => {
+                entry.expires_at = Some(at);
+                Reply::Integer(1)
+            }
+            None => Reply::Integer(0),
+        }
+    }
+
+    fn ttl(&mut self, db: usize, key: &[u8], unit: Duration) -> Reply {
+        match self.live(db, key) {
+            None => Reply::Integer(-2),
+            Some(Entry { expires_at: None, .. }) => Reply::Integer(-1),
+            Some(Entry { expires_at: Some(at), .. }) => {
+                let left = at.saturating_duration_since(Instant::now());
+                Reply::Integer(left.as_nanos().div_ceil(unit.as_nanos()) as i64)
+            }
+        }
+    }
+
+    fn sets_of(&mut self, db: usize, keys: &[Vec<u8>]) -> Result<Vec<BTreeSet<Vec<u8>>>, Reply> {
+        keys.iter().map(|key| Ok(self.set(db, key)?.cloned().unwrap_or_default())).collect()
+    }
+
+    /// Pops from the front or back of each list in turn, for BLPOP/BRPOP.
+    /// `None` when every list is empty, so the caller can wait and retry.
+    pub fn pop_first(&mut self, db: usize, keys: &[Vec<u8>], front: bool) -> Result<Option<Reply>, Reply> {
+        for key in keys {
+            let Some(list) = self.list(db, key)? else {
+                continue;
+            };
+            let value = if front { list.pop_front() } else { list.pop_back() };
+            if let Some(value) = value {
+                self.drop_if_empty(db, key);
+                return Ok(Some(Reply::bulks([key.clone(), value])));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Runs one command against database `db`. Connection-level commands
+    /// (SELECT, MULTI, ...) are handled by the server before this.
+    pub fn execute(&mut self, db: usize, args: &[Vec<u8>]) -> Reply {
+        let Some(name) = args.first() else {
+            return Reply::error("ERR empty command");
+        };
+        let name = text(name).to_ascii_uppercase();
+        self.run(db, &name, &args[1..]).unwrap_or_else(|error| error)
+    }
+
+    fn run(&mut self, db: usize, name: &str, a: &[Vec<u8>]) -> Result<Reply, Reply> {
+        let arity = |min: usize| -> Result<(), Reply> {
+            if a.len() < min {
+                return Err(Reply::error(format!("ERR wrong number of arguments for '{}' command", name.to_lowercase())));
+            }
+            Ok(())
+        };
+        let reply = match name {
+            // Server
+            "PING" => match a.first() {
+                Some(message) => Reply::bulk(message.clone()),
+                None => Reply::Status("PONG".to_string()),
+            },
+            "ECHO" => {
+                arity(1)?;
+                Reply::bulk(a[0].clone())
+            }
+            "DBSIZE" => {
+                self.purge_expired(db);
+                Reply::Integer(self.dbs[db].len() as i64)
+            }
+            "FLUSHDB" => {
+                self.dbs[db].clear();
+                Reply::ok()
+            }
+            "FLUSHALL" => {
+                self.dbs.iter_mut().for_each(Db::clear);
+                Reply::ok()
+            }
+            "TIME" => {
+                let now = chrono::Utc::now();
+                Reply::bulks([now.timestamp().to_string(), now.timestamp_subsec_micros().to_string()])
+            }
+            "INFO" => {
+                let mut info = String::from("# Server\r\nredis_version:7.0.0\r\nredis_mode:standalone\r\n");
+                info.push_str("mini_redis:offline\r\n");
+                // Not tracked offline; present so INFO consumers parse it.
+                info.push_str("# Clients\r\nconnected_clients:1\r\n# Memory\r\nused_memory:0\r\n# Stats\r\ninstantaneous_ops_per_sec:0\r\n");
+                info.push_str("# Replication\r\nrole:master\r\nconnected_slaves:0\r\n# Keyspace\r\n");
+                for i in 0..DATABASES {
+                    self.purge_expired(i);
+                    if !self.dbs[i].is_empty() {
+                        let expires = self.dbs[i].values().filter(|entry| entry.expires_at.is_some()).count();
+                        info.push_str(&format!("db{}:keys={},expires={},avg_ttl=0\r\n", i, self.dbs[i].len(), expires));
+                    }
+                }
+                Reply::bulk(info)
+            }
+            "CONFIG" | "COMMAND" => Reply::Array(Vec::new()),
+            "MEMORY" => match a.first().map(|sub| text(sub).to_ascii_uppercase()).as_deref() {
+                Some("USAGE") => {
+                    arity(2)?;
+                    match self.live(db, &a[1]) {
+                        // Key and dict entry overhead, as a real server adds.
+                        Some(entry) => Reply::Integer((56 + a[1].len() + entry.data.approx_bytes()) as i64),
+                        None => Reply::Nil,
+                    }
+                }
+                _ => return Err(Reply::error("ERR unknown subcommand for 'memory'")),
+            },
+            "CLIENT" => match a.first().map(|sub| text(sub).to_ascii_uppercase()).as_deref() {
+                Some("GETNAME") => Reply::Nil,
+                Some("ID") => Reply::Integer(1),
+                _ => Reply::ok(),
+            },
+
+            // Keys
+            "DEL" | "UNLINK" => {
+                arity(1)?;
+                let removed = a.iter().filter(|key| self.live(db, key).is_some() && self.dbs[db].remove(*key).is_some());
+                Reply::Integer(removed.count() as i64)
+            }
+            "EXISTS" => {
+                arity(1)?;
+                Reply::Integer(a.iter().filter(|key| self.live(db, key).is_some()).count() as i64)
+            }
+            "TYPE" => {
+                arity(1)?;
+                Reply::Status(self.live(db, &a[0]).map_or("none", |entry| entry.data.type_name()).to_string())
+            }
+            "EXPIRE" | "PEXPIRE" => {
+                arity(2)?;
+                let amount = int(&a[1])?;
+                if amount <= 0 {
+                    Reply::Integer(self.dbs[db].remove(&a[0]).is_some() as i64)
+                } else {
+                    let at = deadline(name, amount, if name == "EXPIRE" { 1000 } else { 1 })?;
+                    self.expire_at(db, &a[0], at)
+                }
+            }
+            "TTL" => {
+                arity(1)?;
+                self.ttl(db, &a[0], Duration::from_secs(1))
+            }
+            "PTTL" => {
+                arity(1)?;
+                self.ttl(db, &a[0], Duration::from_millis(1))
+            }
+            "PERSIST" => {
+                arity(1)?;
+                match self.live(db, &a[0]) {
+                    Some(entry) if entry.expires_at.is_some() => {
+                        entry.expires_at = None;
+                        Reply::Integer(1)
+                    }
+                    _ => Reply::Integer(0),
+                }
+            }
+            "RENAME" | "RENAMENX" => {
+                arity(2)?;
+                if self.live(db, &a[0]).is_none() {
+                    return Err(Reply::error("ERR no such key"));
+                }
+                if name == "RENAMENX" && self.live(db, &a[1]).is_some() {
+                    return Ok(Reply::Integer(0));
+                }
+                let entry = self.dbs[db].remove(&a[0]).expect("checked live");
+                self.dbs[db].insert(a[1].clone(), entry);
+                if name == "RENAMENX" { Reply::Integer(1) } else { Reply::ok() }
+            }
+            "KEYS" => {
+                arity(1)?;
+                Reply::bulks(self.sorted_keys(db).into_iter().filter(|key| glob_match(&a[0], key)))
+            }
+            "RANDOMKEY" => {
+                self.purge_expired(db);
+                match self.dbs[db].keys().choose(&mut rand::thread_rng()) {
+                    Some(key) => Reply::bulk(key.clone()),
+                    None => Reply::Nil,
+                }
+            }
+            "SCAN" => {
+                arity(1)?;
+                let cursor = int(&a[0])?.max(0) as usize;
+                let (mut pattern, mut count, mut kind) = (None, 10usize, None);
+                for option in a[1..].chunks(2) {
+                    let [flag, value] = option else {
+                        return Err(Reply::error(SYNTAX));
+                    };
+                    match text(flag).to_ascii_uppercase().as_str() {
+                        "MATCH" => pattern = Some(value.clone()),
+                        "COUNT" => count = int(value)?.max(1) as usize,
+                        "TYPE" => kind = Some(text(value).to_ascii_lowercase()),
+                        _ => return Err(Reply::error(SYNTAX)),
+                    }
+                }
+                // The cursor is a position in the sorted keyspace: keys
+                // present for the whole scan are returned exactly once.
+                let keys = self.sorted_keys(db);
+                let end = (cursor + count).min(keys.len());
+                let next = if end >= keys.len() { 0 } else { end };
+                let batch: Vec<_> = keys
+                    .get(cursor..end)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|key| pattern.as_ref().is_none_or(|pattern| glob_match(pattern, key)))
+                    .filter(|key| {
+                        kind.as_ref().is_none_or(|kind| self.dbs[db][*key].data.type_name() == kind.as_str())
+                    })
+                    .cloned()
+                    .collect();
+                Reply::Array(vec![Reply::bulk(next.to_string()), Reply::bulks(batch)])
+            }
+
+            // Strings
+            "GET" => {
+                arity(1)?;
+                match self.string(db, &a[0])? {
+                    Some(value) => Reply::bulk(value.clone()),
+                    None => Reply::Nil,
+                }
+            }
+            "GETDEL" => {
+                arity(1)?;
+                let value = self.string(db, &a[0])?.cloned();
+                self.dbs[db].remove(&a[0]);
+                value.map_or(Reply::Nil, Reply::bulk)
+            }
+            "GETSET" => {
+                arity(2)?;
+                let old = self.string(db, &a[0])?.cloned();
+                self.write_string(db, &a[0], a[1].clone(), false);
+                old.map_or(Reply::Nil, Reply::bulk)
+            }
+            "SET" => {
+                arity(2)?;
+                let (mut ttl, mut nx, mut xx, mut keep_ttl, mut get) = (None, false, false, false, false);
+                let mut options = a[2..].iter();
+                while let Some(option) = options.next() {
+                    match text(option).to_ascii_uppercase().as_str() {
+                        unit @ ("EX" | "PX") => {
+                            let amount = int(options.next().ok_or(Reply::error(SYNTAX))?)?;
+                            ttl = Some(deadline(name, amount, if unit == "EX" { 1000 } else { 1 })?);
+                        }
+                        "NX" => nx = true,
+                        "XX" => xx = true,
+                        "KEEPTTL" => keep_ttl = true,
+                        "GET" => get = true,
+                        _ => return Err(Reply::error(SYNTAX)),
+                    }
+                }
+                let old = if get { self.string(db, &a[0])?.cloned() } else { None };
+                let exists = self.live(db, &a[0]).is_some();
+                if (nx && exists) || (xx && !exists) {
+                    return Ok(if get { old.map_or(Reply::Nil, Reply::bulk) } else { Reply::Nil });
+                }
+                self.write_string(db, &a[0], a[1].clone(), keep_ttl);
+                if let Some(at) = ttl {
+                    self.expire_at(db, &a[0], at);
+                }
+                if get { old.map_or(Reply::Nil, Reply::bulk) } else { Reply::ok() }
+            }
+            "SETEX" | "PSETEX" => {
+                arity(3)?;
+                let at = deadline(name, int(&a[1])?, if name == "SETEX" { 1000 } else { 1 })?;
+                self.write_string(db, &a[0], a[2].clone(), false);
+                self.expire_at(db, &a[0], at);
+                Reply::ok()
+            }
+            "SETNX" => {
+                arity(2)?;
+                if self.live(db, &a[0]).is_some() {
+                    Reply::Integer(0)
+                } else {
+                    self.write_string(db, &a[0], a[1].clone(), false);
+                    Reply::Integer(1)
+                }
+            }
+            "MGET" => {
+                arity(1)?;
+                let values = a.iter().map(|key| match self.live(db, key).map(|entry| &entry.data) {
+                    Some(Data::String(value)) => Reply::bulk(value.clone()),
+                    _ => Reply::Nil,
+                });
+                Reply::Array(values.collect())
+            }
+            "MSET" => {
+                if a.is_empty() || !a.len().is_multiple_of(2) {
+                    return Err(Reply::error("ERR wrong number of arguments for 'mset' command"));
+                }
+                for pair in a.chunks(2) {
+                    self.write_string(db, &pair[0], pair[1].clone(), false);
+                }
+                Reply::ok()
+            }
+            "INCR" => {
+                arity(1)?;
+                self.incr_by(db, &a[0], 1)?
+            }
+            "DECR" => {
+                arity(1)?;
+                self.incr_by(db, &a[0], -1)?
+            }
+            "INCRBY" => {
+                arity(2)?;
+                self.incr_by(db, &a[0], int(&a[1])?)?
+            }
+            "DECRBY" => {
+                arity(2)?;
+                let by = int(&a[1])?.checked_neg().ok_or_else(|| Reply::error("ERR increment or decrement would overflow"))?;
+                self.incr_by(db, &a[0], by)?
+            }
+            "INCRBYFLOAT" => {
+                arity(2)?;
+                let current = match self.string(db, &a[0])? {
+                    Some(value) => float(value)?,
+                    None => 0.0,
+                };
+                let next = format_float(current + float(&a[1])?);
+                self.write_string(db, &a[0], next.clone().into_bytes(), true);
+                Reply::bulk(next)
+            }
+            "APPEND" => {
+                arity(2)?;
+                let len = match self.string(db, &a[0])? {
+                    Some(value) => {
+                        value.extend_from_slice(&a[1]);
+                        value.len()
+                    }
+                    None => {
+                        self.write_string(db, &a[0], a[1].clone(), false);
+                        a[1].len()
+                    }
+                };
+                Reply::Integer(len as i64)
+            }
+            "STRLEN" => {
+                arity(1)?;
+                Reply::Integer(self.string(db, &a[0])?.map_or(0, |value| value.len()) as i64)
+            }
+            "GETRANGE" => {
+                arity(3)?;
+                let value = self.string(db, &a[0])?.cloned().unwrap_or_default();
+                match range(int(&a[1])?, int(&a[2])?, value.len()) {
+                    Some((start, stop)) => Reply::bulk(value[start..=stop].to_vec()),
+                    None => Reply::bulk(Vec::new()),
+                }
+            }
+
+            // Lists
+            "LPUSH" | "RPUSH" => {
+                arity(2)?;
+                let Data::List(list) = self.get_or_create(db, &a[0], Data::List(VecDeque::new()))? else {
+                    unreachable!("get_or_create checks the type");
+                };
+                for value in &a[1..] {
+                    if name == "LPUSH" {
+                        list.push_front(value.clone());
+                    } else {
+                        list.push_back(value.clone());
+                    }
+                }
+                Reply::Integer(list.len() as i64)
+            }
+            "LPOP" | "RPOP" => {
+                arity(1)?;
+                let count = a.get(1).map(|count| int(count)).transpose()?;
+                let Some(list) = self.list(db, &a[0])? else {
+                    return Ok(if count.is_some() { Reply::NilArray } else { Reply::Nil });
+                };
+                let take = count.unwrap_or(1).max(0) as usize;
+                let popped: Vec<_> = (0..take)
+                    .map_while(|_| if name == "LPOP" { list.pop_front() } else { list.pop_back() })
+                    .collect();
+                self.drop_if_empty(db, &a[0]);
+                match count {
+                    Some(_) => Reply::bulks(popped),
+                    None => popped.into_iter().next().map_or(Reply::Nil, Reply::bulk),
+                }
+            }
+            "LLEN" => {
+                arity(1)?;
+                Reply::Integer(self.list(db, &a[0])?.map_or(0, |list| list.len()) as i64)
+            }
+            "LRANGE" => {
+                arity(3)?;
+                let (start, stop) = (int(&a[1])?, int(&a[2])?);
+                let Some(list) = self.list(db, &a[0])? else {
+                    return Ok(Reply::Array(Vec::new()));
+                };
+                match range(start, stop, list.len()) {
+                    Some((start, stop)) => Reply::bulks(list.range(start..=stop).cloned()),
+                    None => Reply::Array(Vec::new()),
+                }
+            }
+            "LINDEX" => {
+                arity(2)?;
+                let index = int(&a[1])?;
+                let Some(list) = self.list(db, &a[0])? else {
+                    return Ok(Reply::Nil);
+                };
+                let index = if index < 0 { list.len() as i64 + index } else { index };
+                usize::try_from(index).ok().and_then(|i| list.get(i)).cloned().map_or(Reply::Nil, Reply::bulk)
+            }
+            "LPOS" => {
+                arity(2)?;
+                let (mut rank, mut count) = (1i64, None);
+                for option in a[2..].chunks(2) {
+                    let [flag, value] = option else {
+                        return Err(Reply::error(SYNTAX));
+                    };
+                    match text(flag).to_ascii_uppercase().as_str() {
+                        "RANK" if int(value)? != 0 => rank = int(value)?,
+                        "COUNT" => count = Some(int(value)?.max(0) as usize),
+                        _ => return Err(Reply::error(SYNTAX)),
+                    }
+                }
+                let list = self.list(db, &a[0])?.cloned().unwrap_or_default();
+                let mut found: Vec<usize> = list.iter().enumerate().filter(|(_, v)| **v == a[1]).map(|(i, _)| i).collect();
+                if rank < 0 {
+                    found.reverse();
+                }
+                let found = found.into_iter().skip(rank.unsigned_abs() as usize - 1);
+                match count {
+                    Some(0) => Reply::Array(found.map(|i| Reply::Integer(i as i64)).collect()),
+                    Some(n) => Reply::Array(found.take(n).map(|i| Reply::Integer(i as i64)).collect()),
+                    None => found.into_iter().next().map_or(Reply::Nil, |i| Reply::Integer(i as i64)),
+                }
+            }
+            "LSET" => {
+                arity(3)?;
+                let index = int(&a[1])?;
+                let list = self.list(db, &a[0])?.ok_or_else(|| Reply::error("ERR no such key"))?;
+                let index = if index < 0 { list.len() as i64 + index } else { index };
+                let slot = usize::try_from(index).ok().and_then(|i| list.get_mut(i));
+                *slot.ok_or_else(|| Reply::error("ERR index out of range"))? = a[2].clone();
+                Reply::ok()
+            }
+            "LINSERT" => {
+                arity(4)?;
+                let before = match text(&a[1]).to_ascii_uppercase().as_str() {
+                    "BEFORE" => true,
+                    "AFTER" => false,
+                    _ => return Err(Reply::error(SYNTAX)),
+                };
+                let Some(list) = self.list(db, &a[0])? else {
+                    return Ok(Reply::Integer(0));
+                };
+                match list.iter().position(|value| *value == a[2]) {
+                    Some(at) => {
+                        list.insert(if before { at } else { at + 1 }, a[3].clone());
+                        Reply::Integer(list.len() as i64)
+                    }
+                    None => Reply::Integer(-1),
+                }
+            }
+            "LREM" => {
+                arity(3)?;
+                let count = int(&a[1])?;
+                let Some(list) = self.list(db, &a[0])? else {
+                    return Ok(Reply::Integer(0));
+                };
+                let limit = if count == 0 { usize::MAX } else { count.unsigned_abs() as usize };
+                let mut positions: Vec<_> = list.iter().enumerate().filter(|(_, v)| **v == a[2]).map(|(i, _)| i).collect();
+                if count < 0 {
+                    positions.reverse();
+                }
+                positions.truncate(limit);
+                positions.sort_unstable_by(|x, y| y.cmp(x));
+                for &i in &positions {
+                    list.remove(i);
+                }
+                self.drop_if_empty(db, &a[0]);
+                Reply::Integer(positions.len() as i64)
+            }
+            "LTRIM" => {
+                arity(3)?;
+                let (start, stop) = (int(&a[1])?, int(&a[2])?);
+                if let Some(list) = self.list(db, &a[0])? {
+                    match range(start, stop, list.len()) {
+                        Some((start, stop)) => {
+                            list.truncate(stop + 1);
+                            list.drain(..start);
+                        }
+                        None => list.clear(),
+                    }
+                    self.drop_if_empty(db, &a[0]);
+                }
+                Reply::ok()
+            }
+
+            // Sets
+            "SADD" => {
+                arity(2)?;
+                let Data::Set(set) = self.get_or_create(db, &a[0], Data::Set(BTreeSet::new()))? else {
+                    unreachable!("get_or_create checks the type");
+                };
+                Reply::Integer(a[1..].iter().filter(|member| set.insert((*member).clone())).count() as i64)
+            }
+            "SREM" => {
+                arity(2)?;
+                let Some(set) = self.set(db, &a[0])? else {
+                    return Ok(Reply::Integer(0));
+                };
+                let removed = a[1..].iter().filter(|member| set.remove(*member)).count();
+                self.drop_if_empty(db, &a[0]);
+                Reply::Integer(removed as i64)
+            }
+            "SMEMBERS" => {
+                arity(1)?;
+                Reply::bulks(self.set(db, &a[0])?.map(|set| set.clone()).unwrap_or_default())
+            }
+            "SISMEMBER" => {
+                arity(2)?;
+                Reply::Integer(self.set(db, &a[0])?.is_some_and(|set| set.contains(&a[1])) as i64)
+            }
+            "SCARD" => {
+                arity(1)?;
+                Reply::Integer(self.set(db, &a[0])?.map_or(0, |set| set.len()) as i64)
+            }
+            "SPOP" | "SRANDMEMBER" => {
+                arity(1)?;
+                let count = a.get(1).map(|count| int(count)).transpose()?;
+                let Some(set) = self.set(db, &a[0])? else {
+                    return Ok(if count.is_some() { Reply::Array(Vec::new()) } else { Reply::Nil });
+                };
+                let picked = set.iter().cloned().choose_multiple(&mut rand::thread_rng(), count.unwrap_or(1).unsigned_abs() as usize);
+                if name == "SPOP" {
+                    for member in &picked {
+                        set.remove(member);
+                    }
+                    self.drop_if_empty(db, &a[0]);
+                }
+                match count {
+                    Some(_) => Reply::bulks(picked),
+                    None => picked.into_iter().next().map_or(Reply::Nil, Reply::bulk),
+                }
+            }
+            "SINTER" | "SUNION" | "SDIFF" => {
+                arity(1)?;
+                let mut sets = self.sets_of(db, a)?.into_iter();
+                let first = sets.next().unwrap_or_default();
+                let result = sets.fold(first, |acc, set| match name {
+                    "SINTER" => acc.intersection(&set).cloned().collect(),
+                    "SUNION" => acc.union(&set).cloned().collect(),
+                    _ => acc.difference(&set).cloned().collect(),
+                });
+                Reply::bulks(result)
+            }
+
+            // Hashes
+            "HSET" | "HMSET" => {
+                if a.len() < 3 || a.len().is_multiple_of(2) {
+                    return Err(Reply::error(format!("ERR wrong number of arguments for '{}' command", name.to_lowercase())));
+                }
+                let Data::Hash(hash) = self.get_or_create(db, &a[0], Data::Hash(BTreeMap::new()))? else {
+                    unreachable!("get_or_create checks the type");
+                };
+                let added = a[1..].chunks(2).filter(|pair| hash.insert(pair[0].clone(), pair[1].clone()).is_none()).count();
+                if name == "HSET" { Reply::Integer(added as i64) } else { Reply::ok() }
+            }
+            "HSETNX" => {
+                arity(3)?;
+                let Data::Hash(hash) = self.get_or_create(db, &a[0], Data::Hash(BTreeMap::new()))? else {
+                    unreachable!("get_or_create checks the type");
+                };
+                if hash.contains_key(&a[1]) {
+                    Reply::Integer(0)
+                } else {
+                    hash.insert(a[1].clone(), a[2].clone());
+                    Reply::Integer(1)
+                }
+            }
+            "HGET" => {
+                arity(2)?;
+                self.hash(db, &a[0])?.and_then(|hash| hash.get(&a[1]).cloned()).map_or(Reply::Nil, Reply::bulk)
+            }
+            "HMGET" => {
+                arity(2)?;
+                let hash = self.hash(db, &a[0])?.cloned().unwrap_or_default();
+                Reply::Array(a[1..].iter().map(|field| hash.get(field).cloned().map_or(Reply::Nil, Reply::bulk)).collect())
+            }
+            "HGETALL" => {
+                arity(1)?;
+                let hash = self.hash(db, &a[0])?.cloned().unwrap_or_default();
+                Reply::bulks(hash.into_iter().flat_map(|(field, value)| [field, value]))
+            }
+            "HKEYS" => {
+                arity(1)?;
+                Reply::bulks(self.hash(db, &a[0])?.map(|hash| hash.keys().cloned().collect::<Vec<_>>()).unwrap_or_default())
+            }
+            "HVALS" => {
+                arity(1)?;
+                Reply::bulks(self.hash(db, &a[0])?.map(|hash| hash.values().cloned().collect::<Vec<_>>()).unwrap_or_default())
+            }
+            "HLEN" => {
+                arity(1)?;
+                Reply::Integer(self.hash(db, &a[0])?.map_or(0, |hash| hash.len()) as i64)
+            }
+            "HEXISTS" => {
+                arity(2)?;
+                Reply::Integer(self.hash(db, &a[0])?.is_some_and(|hash| hash.contains_key(&a[1])) as i64)
+            }
+            "HDEL" => {
+                arity(2)?;
+                let Some(hash) = self.hash(db, &a[0])? else {
+                    return Ok(Reply::Integer(0));
+                };
+                let removed = a[1..].iter().filter(|field| hash.remove(*field).is_some()).count();
+                self.drop_if_empty(db, &a[0]);
+                Reply::Integer(removed as i64)
+            }
+            "HINCRBY" | "HINCRBYFLOAT" => {
+                arity(3)?;
+                let Data::Hash(hash) = self.get_or_create(db, &a[0], Data::Hash(BTreeMap::new()))? else {
+                    unreachable!("get_or_create checks the type");
+                };
+                let current = hash.get(&a[1]).cloned();
+                if name == "HINCRBY" {
+                    let current = current.map(|value| int(&value)).transpose()?.unwrap_or(0);
+                    let next = current.checked_add(int(&a[2])?).ok_or_else(|| Reply::error(NOT_INTEGER))?;
+                    hash.insert(a[1].clone(), next.to_string().into_bytes());
+                    Reply::Integer(next)
+                } else {
+                    let current = current.map(|value| float(&value)).transpose()?.unwrap_or(0.0);
+                    let next = format_float(current + float(&a[2])?);
+                    hash.insert(a[1].clone(), next.clone().into_bytes());
+                    Reply::bulk(next)
+                }
+            }
+
+            // Sorted sets
+            "ZADD" => {
+                arity(3)?;
+                let (mut nx, mut xx, mut ch, mut i) = (false, false, false, 1);
+                while i < a.len() {
+                    match text(&a[i]).to_ascii_uppercase().as_str() {
+                        "NX" => nx = true,
+                        "XX" => xx = true,
+                        "CH" => ch = true,
+                        _ => break,
+                    }
+                    i += 1;
+                }
+                let pairs = &a[i..];
+                if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+                    return Err(Reply::error(SYNTAX));
+                }
+                let scores = pairs.chunks(2).map(|pair| Ok((float(&pair[0])?, pair[1].clone()))).collect::<Result<Vec<_>, Reply>>()?;
+                let Data::ZSet(zset) = self.get_or_create(db, &a[0], Data::ZSet(BTreeMap::new()))? else {
+                    unreachable!("get_or_create checks the type");
+                };
+                let (mut added, mut changed) = (0, 0);
+                for (score, member) in scores {
+                    match zset.get(&member) {
+                        Some(_) if nx => {}
+                        None if xx => {}
+                        Some(old) => {
+                            if *old != score {
+                                changed += 1;
+                            }
+                            zset.insert(member, score);
+                        }
+                        None => {
+                            added += 1;
+                            zset.insert(member, score);
+                        }
+                    }
+                }
+                self.drop_if_empty(db, &a[0]);
+                Reply::Integer(if ch { added + changed } else { added })
+            }
+            "ZINCRBY" => {
+                arity(3)?;
+                let by = float(&a[1])?;
+                let Data::ZSet(zset) = self.get_or_create(db, &a[0], Data::ZSet(BTreeMap::new()))? else {
+                    unreachable!("get_or_create checks the type");
+                };
+                let score = zset.entry(a[2].clone()).or_insert(0.0);
+                *score += by;
+                Reply::bulk(format_float(*score))
+            }
+            "ZSCORE" => {
+                arity(2)?;
+                self.zset(db, &a[0])?.and_then(|zset| zset.get(&a[1]).copied()).map_or(Reply::Nil, |s| Reply::bulk(format_float(s)))
+            }
+            "ZCARD" => {
+                arity(1)?;
+                Reply::Integer(self.zset(db, &a[0])?.map_or(0, |zset| zset.len()) as i64)
+            }
+            "ZREM" => {
+                arity(2)?;
+                let Some(zset) = self.zset(db, &a[0])? else {
+                    return Ok(Reply::Integer(0));
+                };
+                let removed = a[1..].iter().filter(|member| zset.remove(*member).is_some()).count();
+                self.drop_if_empty(db, &a[0]);
+                Reply::Integer(removed as i64)
+            }
+            "ZRANK" | "ZREVRANK" => {
+                arity(2)?;
+                let Some(zset) = self.zset(db, &a[0])? else {
+                    return Ok(Reply::Nil);
+                };
+                let mut members = scored(zset);
+                if name == "ZREVRANK" {
+                    members.reverse();
+                }
+                members.iter().position(|(member, _)| **member == a[1]).map_or(Reply::Nil, |rank| Reply::Integer(rank as i64))
+            }
+            "ZRANGE" | "ZREVRANGE" => {
+                arity(3)?;
+                let (start, stop) = (int(&a[1])?, int(&a[2])?);
+                let scores = match a.get(3).map(|option| text(option).to_ascii_uppercase()) {
+                    None => false,
+                    Some(option) if option == "WITHSCORES" && a.len() == 4 => true,
+                    Some(_) => return Err(Reply::error("ERR only the index form of ZRANGE is supported offline")),
+                };
+                let Some(zset) = self.zset(db, &a[0])? else {
+                    return Ok(Reply::Array(Vec::new()));
+                };
+                let mut members = scored(zset);
+                if name == "ZREVRANGE" {
+                    members.reverse();
+                }
+                match range(start, stop, members.len()) {
+                    Some((start, stop)) => with_scores(members[start..=stop].to_vec(), scores),
+                    None => Reply::Array(Vec::new()),
+                }
+            }
+            "ZRANGEBYSCORE" | "ZREVRANGEBYSCORE" | "ZCOUNT" => {
+                arity(3)?;
+                let reverse = name == "ZREVRANGEBYSCORE";
+                let (min, max) = if reverse { (&a[2], &a[1]) } else { (&a[1], &a[2]) };
+                let (min, max) = (score_bound(min)?, score_bound(max)?);
+                let (mut scores, mut limit) = (false, None);
+                let mut options = a[3..].iter();
+                while let Some(option) = options.next() {
+                    match text(option).to_ascii_uppercase().as_str() {
+                        "WITHSCORES" => scores = true,
+                        "LIMIT" => {
+                            let offset = int(options.next().ok_or(Reply::error(SYNTAX))?)?;
+                            let count = int(options.next().ok_or(Reply::error(SYNTAX))?)?;
+                            limit = Some((offset.max(0) as usize, if count < 0 { usize::MAX } else { count as usize }));
+                        }
+                        _ => return Err(Reply::error(SYNTAX)),
+                    }
+                }
+                let Some(zset) = self.zset(db, &a[0])? else {
+                    return Ok(if name == "ZCOUNT" { Reply::Integer(0) } else { Reply::Array(Vec::new()) });
+                };
+                let mut members: Vec<_> = scored(zset).into_iter().filter(|(_, score)| in_bounds(*score, min, max)).collect();
+                if name == "ZCOUNT" {
+                    return Ok(Reply::Integer(members.len() as i64));
+                }
+                if reverse {
+                    members.reverse();
+                }
+                let (offset, count) = limit.unwrap_or((0, usize::MAX));
+                with_scores(members.into_iter().skip(offset).take(count).collect(), scores)
+            }
+            "ZRANGEBYLEX" => {
+                arity(3)?;
+                let bounds: (Bound<Vec<u8>>, Bound<Vec<u8>>) = (lex_bound(&a[1])?, lex_bound(&a[2])?);
+                let (offset, count) = parse_limit(&a[3..])?.unwrap_or((0, usize::MAX));
+                // `-` as max or `+` as min select nothing, as in Redis.
+                if a[1] == b"+" || a[2] == b"-" {
+                    return Ok(Reply::Array(Vec::new()));
+                }
+                let Some(zset) = self.zset(db, &a[0])? else {
+                    return Ok(Reply::Array(Vec::new()));
+                };
+                let members = scored(zset)
+                    .into_iter()
+                    .map(|(member, _)| member)
+                    .filter(|member| bounds.contains(*member))
+                    .skip(offset)
+                    .take(count)
+                    .cloned();
+                Reply::bulks(members.collect::<Vec<_>>())
+            }
+            _ => {
+                return Err(Reply::error(format!(
+                    "ERR unknown command '{}', not supported by the offline server",
+                    name.to_lowercase()
+                )))
+            }
+        };
+        Ok(reply)
+    }
+}
+
+/// When a key given `amount` units of `unit_ms` from now expires. Like
+/// Redis, rejects amounts that are not positive or whose unix time in ms
+/// would overflow an i64.
+fn deadline(command: &str, amount: i64, unit_ms: u64) -> Result<Instant, Reply> {
+    let invalid = || Reply::error(format!("ERR invalid expire time in '{}' command", command.to_lowercase()));
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_millis() as u64);
+    let millis = u64::try_from(amount)
+        .ok()
+        .filter(|amount| *amount > 0)
+        .and_then(|amount| amount.checked_mul(unit_ms))
+        .filter(|millis| *millis <= i64::MAX as u64 - now_ms)
+        .ok_or_else(invalid)?;
+    Instant::now().checked_add(Duration::from_millis(millis)).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(store: &mut Store, command: &str) -> Reply {
+        let args: Vec<Vec<u8>> = command.split_whitespace().map(|arg| arg.as_bytes().to_vec()).collect();
+        store.execute(0, &args)
+    }
+
+    #[test]
+    fn test_strings_and_expiry() {
+        let mut store = Store::new();
+        assert_eq!(run(&mut store, "SET a 1 EX 100"), Reply::ok());
+        assert_eq!(run(&mut store, "INCRBY a 4"), Reply::Integer(5));
+        assert_eq!(run(&mut store, "TTL a"), Reply::Integer(100));
+        assert_eq!(run(&mut store, "SET a x NX"), Reply::Nil);
+        assert_eq!(run(&mut store, "APPEND a 0"), Reply::Integer(2));
+        assert_eq!(run(&mut store, "GET a"), Reply::bulk("50"));
+        assert_eq!(run(&mut store, "INCR missing"), Reply::Integer(1));
+        assert_eq!(run(&mut store, "PEXPIRE a 1"), Reply::Integer(1));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(run(&mut store, "GET a"), Reply::Nil);
+        assert_eq!(run(&mut store, "TTL a"), Reply::Integer(-2));
+        assert_eq!(run(&mut store, "DBSIZE"), Reply::Integer(1));
+    }
+
+    #[test]
+    fn test_overflowing_expiry_is_an_error() {
+        let mut store = Store::new();
+        run(&mut store, "SET a 1");
+        let invalid = Reply::error("ERR invalid expire time in 'expire' command");
+        assert_eq!(run(&mut store, "EXPIRE a 9223372036854775807"), invalid);
+        assert_eq!(run(&mut store, "PEXPIRE a 9223372036854775807"), Reply::error("ERR invalid expire time in 'pexpire' command"));
+        assert_eq!(run(&mut store, "SET b 1 EX 9223372036854775807"), Reply::error("ERR invalid expire time in 'set' command"));
+        assert_eq!(run(&mut store, "SET b 1 PX 0"), Reply::error("ERR invalid expire time in 'set' command"));
+        assert_eq!(run(&mut store, "SETEX b 9223372036854775807 1"), Reply::error("ERR invalid expire time in 'setex' command"));
+        assert_eq!(run(&mut store, "GET a"), Reply::bulk("1"));
+        assert_eq!(run(&mut store, "TTL a"), Reply::Integer(-1));
+        assert_eq!(run(&mut store, "EXISTS b"), Reply::Integer(0));
+    }
+
+    #[test]
+    fn test_overflowing_decrement_is_an_error() {
+        let mut store = Store::new();
+        run(&mut store, "SET a 1");
+        let overflow = Reply::error("ERR increment or decrement would overflow");
+        assert_eq!(run(&mut store, "DECRBY a -9223372036854775808"), overflow);
+        assert_eq!(run(&mut store, "DECRBY a 9223372036854775807"), Reply::Integer(-9223372036854775806));
+        assert_eq!(run(&mut store, "DECRBY a 3"), overflow);
+        assert_eq!(run(&mut store, "GET a"), Reply::bulk("-9223372036854775806"));
+    }
+
+    #[test]
+    fn test_types_are_enforced() {
+        let mut store = Store::new();
+        run(&mut store, "RPUSH l a b c");
+        assert_eq!(run(&mut store, "GET l"), Reply::error(WRONGTYPE));
+        assert_eq!(run(&mut store, "SADD l x"), Reply::error(WRONGTYPE));
+        assert_eq!(run(&mut store, "TYPE l"), Reply::Status("list".to_string()));
+        assert!(matches!(run(&mut store, "BOGUS"), Reply::Error(e) if e.contains("unknown command")));
+    }
+
+    #[test]
+    fn test_memory_usage_grows_with_the_value() {
+        let mut store = Store::new();
+        run(&mut store, "SET small x");
+        run(&mut store, "SET large xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx");
+        let (Reply::Integer(small), Reply::Integer(large)) = (run(&mut store, "MEMORY USAGE small"), run(&mut store, "MEMORY USAGE large")) else {
+            panic!("MEMORY USAGE should return integers");
+        };
+        assert_eq!(large - small, 49);
+        assert_eq!(run(&mut store, "MEMORY USAGE missing"), Reply::Nil);
+    }
+
+    #[test]
+    fn test_lists_sets_and_hashes() {
+        let mut store = Store::new();
+        run(&mut store, "RPUSH l a b c");
+        run(&mut store, "LPUSH l z");
+        assert_eq!(run(&mut store, "LRANGE l 0 -1"), Reply::bulks(["z", "a", "b", "c"]));
+        assert_eq!(run(&mut store, "LINSERT l BEFORE b x"), Reply::Integer(5));
+        assert_eq!(run(&mut store, "LINDEX l -1"), Reply::bulk("c"));
+        assert_eq!(run(&mut store, "LPOS l b RANK -1"), Reply::Integer(3));
+        run(&mut store, "LTRIM l 1 2");
+        assert_eq!(run(&mut store, "LRANGE l 0 -1"), Reply::bulks(["a", "x"]));
+        run(&mut store, "LPOP l 2");
+        assert_eq!(run(&mut store, "EXISTS l"), Reply::Integer(0));
+
+        run(&mut store, "SADD s1 a b c");
+        run(&mut store, "SADD s2 b c d");
+        assert_eq!(run(&mut store, "SINTER s1 s2"), Reply::bulks(["b", "c"]));
+        assert_eq!(run(&mut store, "SDIFF s1 s2"), Reply::bulks(["a"]));
+        assert_eq!(run(&mut store, "SCARD s1"), Reply::Integer(3));
+
+        assert_eq!(run(&mut store, "HSET h f1 v1 f2 v2"), Reply::Integer(2));
+        assert_eq!(run(&mut store, "HINCRBY h n 3"), Reply::Integer(3));
+        assert_eq!(run(&mut store, "HGETALL h"), Reply::bulks(["f1", "v1", "f2", "v2", "n", "3"]));
+        assert_eq!(run(&mut store, "HMGET h f2 nope"), Reply::Array(vec![Reply::bulk("v2"), Reply::Nil]));
+    }
+
+    #[test]
+    fn test_sorted_sets() {
+        let mut store = Store::new();
+        assert_eq!(run(&mut store, "ZADD z 1 a 3 c 2 b"), Reply::Integer(3));
+        assert_eq!(run(&mut store, "ZINCRBY z 2.5 a"), Reply::bulk("3.5"));
+        assert_eq!(run(&mut store, "ZRANGE z 0 -1"), Reply::bulks(["b", "c", "a"]));
+        assert_eq!(run(&mut store, "ZREVRANGE z 0 0 WITHSCORES"), Reply::bulks(["a", "3.5"]));
+        assert_eq!(run(&mut store, "ZRANGEBYSCORE z (2 +inf LIMIT 0 1"), Reply::bulks(["c"]));
+        assert_eq!(run(&mut store, "ZREVRANK z b"), Reply::Integer(2));
+        assert_eq!(run(&mut store, "ZCOUNT z -inf 3"), Reply::Integer(2));
+
+        run(&mut store, "ZADD lex 0 a 0 b 0 c 0 d");
+        assert_eq!(run(&mut store, "ZRANGEBYLEX lex (a + LIMIT 0 2"), Reply::bulks(["b", "c"]));
+        assert_eq!(run(&mut store, "ZRANGEBYLEX lex - [b"), Reply::bulks(["a", "b"]));
+    }
+
+    #[test]
+    fn test_scan_visits_every_key_once() {
+        let mut store = Store::new();
+        for i in 0..25 {
+            run(&mut store, &format!("SET key:{} v", i));
+        }
+        run(&mut store, "SADD other x");
+        let (mut cursor, mut seen) = ("0".to_string(), Vec::new());
+        loop {
+            let Reply::Array(reply) = run(&mut store, &format!("SCAN {} MATCH key:* COUNT 7", cursor)) else {
+                panic!("SCAN replies with an array");
+            };
+            let [Reply::Bulk(next), Reply::Array(keys)] = &reply[..] else {
+                panic!("unexpected SCAN reply {:?}", reply);
+            };
+            seen.extend(keys.iter().cloned());
+            cursor = text(next);
+            if cursor == "0" {
+                break;
+            }
+        }
+        assert_eq!(seen.len(), 25);
+    }
+}
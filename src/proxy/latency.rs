@@ -0,0 +1,268 @@
+use crate::{DemoError, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// The network a [`LatencyProxy`] pretends to be.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NetworkConditions {
+    /// Added to every request on its way to the server.
+    pub latency: Duration,
+    /// Each request's delay is drawn from `latency ± jitter`.
+    pub jitter: Duration,
+    /// Chance, from 0 to 1, that a request is lost. A byte stream can't
+    /// lose part of a command without garbling the rest, so a lost request
+    /// cuts the connection, the way clients see it once TCP gives up. A
+    /// connection's first request, which may be its AUTH/SELECT handshake,
+    /// always goes through: redis 0.24 panics when a handshake is cut.
+    pub drop_rate: f64,
+}
+
+impl NetworkConditions {
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.drop_rate) {
+            return Err(DemoError::Configuration(format!("Drop rate {} is not between 0% and 100%", self.drop_rate)));
+        }
+        Ok(())
+    }
+
+    pub fn delay<R: Rng>(&self, rng: &mut R) -> Duration {
+        if self.jitter.is_zero() {
+            return self.latency;
+        }
+        let low = self.latency.saturating_sub(self.jitter);
+        let high = self.latency + self.jitter;
+        rng.gen_range(low..=high)
+    }
+
+    pub fn drops<R: Rng>(&self, rng: &mut R) -> bool {
+        self.drop_rate > 0.0 && rng.gen_bool(self.drop_rate)
+    }
+}
+
+/// What went through a proxy so far.
+#[derive(Debug, Default)]
+pub struct ProxyStats {
+    pub connections: AtomicU64,
+    /// Reads from clients: a command, or a pipeline of them.
+    pub requests: AtomicU64,
+    pub bytes_up: AtomicU64,
+    pub bytes_down: AtomicU64,
+    pub dropped: AtomicU64,
+}
+
+impl ProxyStats {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} connections, {} requests ({} bytes up, {} bytes down), {} dropped",
+            self.connections.load(Ordering::Relaxed),
+            self.requests.load(Ordering::Relaxed),
+            self.bytes_up.load(Ordering::Relaxed),
+            self.bytes_down.load(Ordering::Relaxed),
+            self.dropped.load(Ordering::Relaxed)
+        )
+    }
+}
+
+/// A TCP proxy in front of a Redis server that makes the network slower
+/// and less reliable than it is. Point `--redis-url` at it to watch a demo
+/// or bench cope with latency, jitter and dropped connections.
+pub struct LatencyProxy {
+    addr: SocketAddr,
+    stats: Arc<ProxyStats>,
+    accept: JoinHandle<()>,
+}
+
+impl LatencyProxy {
+    /// Listens on `listen` and forwards each connection to `target`.
+    pub async fn start(listen: SocketAddr, target: &str, conditions: NetworkConditions) -> Result<Self> {
+        conditions.validate()?;
+        let listener = TcpListener::bind(listen).await?;
+        let addr = listener.local_addr()?;
+        let stats = Arc::new(ProxyStats::default());
+        let target = target.to_string();
+        let accept = {
+            let stats = stats.clone();
+            tokio::spawn(async move {
+                while let Ok((client, peer)) = listener.accept().await {
+                    stats.connections.fetch_add(1, Ordering::Relaxed);
+                    let (stats, target) = (stats.clone(), target.clone());
+                    tokio::spawn(async move {
+                        if let Err(e) = relay(client, &target, conditions, stats).await {
+                            debug!("Proxied client {} disconnected: {}", peer, e);
+                        }
+                    });
+                }
+            })
+        };
+        info!("Latency proxy listening on {}", addr);
+        Ok(Self { addr, stats, accept })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// A connection URL for [`RedisClient::new`](crate::RedisClient::new).
+    pub fn url(&self) -> String {
+        format!("redis://{}", self.addr)
+    }
+
+    pub fn stats(&self) -> &ProxyStats {
+        &self.stats
+    }
+}
+
+impl Drop for LatencyProxy {
+    fn drop(&mut self) {
+        self.accept.abort();
+    }
+}
+
+/// `7000` listens on loopback; `0.0.0.0:7000` on every interface.
+pub fn listen_addr(listen: &str) -> Result<SocketAddr> {
+    match listen.parse::<u16>() {
+        Ok(port) => Ok(SocketAddr::from(([127, 0, 0, 1], port))),
+        Err(_) => listen
+            .parse()
+            .map_err(|_| DemoError::Configuration(format!("Invalid listen address: {} (use a port or host:port)", listen))),
+    }
+}
+
+async fn relay(client: TcpStream, target: &str, conditions: NetworkConditions, stats: Arc<ProxyStats>) -> Result<()> {
+    let server = TcpStream::connect(target).await?;
+    client.set_nodelay(true)?;
+    server.set_nodelay(true)?;
+    let (client_read, client_write) = client.into_split();
+    let (server_read, server_write) = server.into_split();
+
+    // Requests are held in a queue until they are due, so a pipeline's
+    // commands are each delayed without the delays adding up, and jitter
+    // never reorders them. When the client goes, what is queued still goes.
+    let (due_tx, due_rx) = mpsc::unbounded_channel();
+    let up = upstream(client_read, due_tx, conditions, stats.clone());
+    let deliver = deliver(due_rx, server_write);
+    let down = copy_counting(server_read, client_write, &stats.bytes_down);
+    tokio::select! {
+        result = async { tokio::try_join!(up, deliver) } => result.map(|_| ()),
+        result = down => result,
+    }
+}
+
+async fn upstream<R: AsyncRead + Unpin>(
+    mut client: R,
+    due: mpsc::UnboundedSender<(Instant, Vec<u8>)>,
+    conditions: NetworkConditions,
+    stats: Arc<ProxyStats>,
+) -> Result<()> {
+    let mut rng = StdRng::from_entropy();
+    let mut buf = vec![0u8; 16 * 1024];
+    let mut first = true;
+    loop {
+        let read = client.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        stats.requests.fetch_add(1, Ordering::Relaxed);
+        if !std::mem::take(&mut first) && conditions.drops(&mut rng) {
+            stats.dropped.fetch_add(1, Ordering::Relaxed);
+            warn!("Dropping a request and its connection");
+            return Ok(());
+        }
+        stats.bytes_up.fetch_add(read as u64, Ordering::Relaxed);
+        if due.send((Instant::now() + conditions.delay(&mut rng), buf[..read].to_vec())).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+async fn deliver<W: AsyncWrite + Unpin>(mut due: mpsc::UnboundedReceiver<(Instant, Vec<u8>)>, mut server: W) -> Result<()> {
+    let mut previous = Instant::now();
+    while let Some((at, bytes)) = due.recv().await {
+        previous = at.max(previous);
+        tokio::time::sleep_until(previous).await;
+        server.write_all(&bytes).await?;
+    }
+    Ok(())
+}
+
+async fn copy_counting<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(mut from: R, mut to: W, counter: &AtomicU64) -> Result<()> {
+    let mut buf = vec![0u8; 16 * 1024];
+    loop {
+        let read = from.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        counter.fetch_add(read as u64, Ordering::Relaxed);
+        to.write_all(&buf[..read]).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_stays_within_the_jitter() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let conditions = NetworkConditions { latency: Duration::from_millis(50), jitter: Duration::from_millis(20), drop_rate: 0.0 };
+        for _ in 0..1000 {
+            let delay = conditions.delay(&mut rng);
+            assert!((Duration::from_millis(30)..=Duration::from_millis(70)).contains(&delay), "{:?}", delay);
+        }
+        let small = NetworkConditions { latency: Duration::from_millis(5), jitter: Duration::from_millis(20), drop_rate: 0.0 };
+        assert!(small.delay(&mut rng) <= Duration::from_millis(25));
+        assert!(!conditions.drops(&mut rng));
+    }
+
+    #[test]
+    fn test_drop_rate() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let conditions = NetworkConditions { drop_rate: 0.1, ..Default::default() };
+        let dropped = (0..10_000).filter(|_| conditions.drops(&mut rng)).count();
+        assert!((800..1200).contains(&dropped), "{}", dropped);
+        assert!(NetworkConditions { drop_rate: 1.5, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_listen_addr() {
+        assert_eq!(listen_addr("7000").unwrap(), "127.0.0.1:7000".parse().unwrap());
+        assert_eq!(listen_addr("0.0.0.0:7000").unwrap(), "0.0.0.0:7000".parse().unwrap());
+        assert!(listen_addr("nowhere").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forwards_with_latency() {
+        let echo = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = echo.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = echo.accept().await.unwrap();
+            let mut buf = [0u8; 64];
+            while let Ok(read) = socket.read(&mut buf).await {
+                if read == 0 || socket.write_all(&buf[..read]).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let conditions = NetworkConditions { latency: Duration::from_millis(40), ..Default::default() };
+        let proxy = LatencyProxy::start(listen_addr("0").unwrap(), &target, conditions).await.unwrap();
+
+        let mut client = TcpStream::connect(proxy.addr()).await.unwrap();
+        let started = std::time::Instant::now();
+        client.write_all(b"PING").await.unwrap();
+        let mut reply = [0u8; 4];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"PING");
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(proxy.stats().bytes_down.load(Ordering::Relaxed), 4);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod latency;
+
+pub use latency::{listen_addr, LatencyProxy, NetworkConditions, ProxyStats};
@@ -0,0 +1,5 @@
+pub mod priority;
+pub mod work_queue;
+
+pub use priority::{PriorityAgingDemo, PriorityJob, PriorityQueue};
+pub use work_queue::{Backpressure, BackpressurePolicy, QueueDemo, WorkQueue};
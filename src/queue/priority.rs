@@ -0,0 +1,237 @@
+use crate::{RedisClient, Result};
+use crate::utils::RedisConnection;
+use redis::{AsyncCommands, Script};
+use std::time::Duration;
+use tracing::info;
+
+const SEQ_WIDTH: usize = 20;
+
+/// KEYS: priority zset
+/// ARGV: boost
+///
+/// Ages every waiting job in one step so a pop never observes a half-aged queue.
+const AGE_SCRIPT: &str = r#"
+local members = redis.call('ZRANGE', KEYS[1], 0, -1)
+for _, member in ipairs(members) do
+    redis.call('ZINCRBY', KEYS[1], ARGV[1], member)
+end
+return #members
+"#;
+
+pub fn priority_key(name: &str) -> String {
+    format!("pqueue:{}", name)
+}
+
+fn seq_key(name: &str) -> String {
+    format!("pqueue:{}:seq", name)
+}
+
+/// Encodes `payload` so that among equal scores ZPOPMAX (which takes the
+/// lexicographically greatest member) returns the *oldest* job: the sequence
+/// number is inverted before zero-padding.
+pub fn encode_member(seq: u64, payload: &str) -> String {
+    format!("{:0width$}:{}", u64::MAX - seq, payload, width = SEQ_WIDTH)
+}
+
+pub fn decode_member(member: &str) -> Option<(u64, &str)> {
+    let (inverted, payload) = member.split_once(':')?;
+    Some((u64::MAX - inverted.parse::<u64>().ok()?, payload))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriorityJob {
+    pub seq: u64,
+    pub payload: String,
+    /// Score at pop time: base priority plus aging boosts received.
+    pub effective_priority: f64,
+}
+
+/// Highest-priority-first queue on a ZSET. Without aging, a steady stream of
+/// high-priority work starves everything else; `age` bumps every waiting job
+/// so long waits eventually outrank fresh high-priority arrivals.
+#[derive(Clone)]
+pub struct PriorityQueue {
+    conn: RedisConnection,
+    name: String,
+    age_script: Script,
+}
+
+impl PriorityQueue {
+    pub async fn new(client: &RedisClient, name: &str) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            name: name.to_string(),
+            age_script: Script::new(AGE_SCRIPT),
+        })
+    }
+
+    pub async fn push(&mut self, payload: &str, priority: f64) -> Result<u64> {
+        let seq: u64 = self.conn.incr(seq_key(&self.name), 1).await?;
+        let _: () = self
+            .conn
+            .zadd(priority_key(&self.name), encode_member(seq, payload), priority)
+            .await?;
+        Ok(seq)
+    }
+
+    pub async fn pop(&mut self) -> Result<Option<PriorityJob>> {
+        let popped: Vec<(String, f64)> = self.conn.zpopmax(priority_key(&self.name), 1).await?;
+        Ok(popped.into_iter().next().and_then(|(member, score)| {
+            decode_member(&member).map(|(seq, payload)| PriorityJob {
+                seq,
+                payload: payload.to_string(),
+                effective_priority: score,
+            })
+        }))
+    }
+
+    /// One scheduler tick: adds `boost` to every waiting job. Returns how many were aged.
+    pub async fn age(&mut self, boost: f64) -> Result<usize> {
+        let aged: usize = self
+            .age_script
+            .key(priority_key(&self.name))
+            .arg(boost)
+            .invoke_async(&mut self.conn)
+            .await?;
+        Ok(aged)
+    }
+
+    /// Runs `age` every `interval` until the returned handle is aborted.
+    pub fn spawn_aging(&self, interval: Duration, boost: f64) -> tokio::task::JoinHandle<()> {
+        let mut queue = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = queue.age(boost).await {
+                    tracing::warn!("Priority aging tick failed: {}", e);
+                }
+            }
+        })
+    }
+
+    pub async fn len(&mut self) -> Result<usize> {
+        let len: usize = self.conn.zcard(priority_key(&self.name)).await?;
+        Ok(len)
+    }
+
+    pub async fn clear(&mut self) -> Result<()> {
+        let _: () = self.conn.del(&[priority_key(&self.name), seq_key(&self.name)]).await?;
+        Ok(())
+    }
+}
+
+/// Runs `rounds` rounds of: two new high-priority jobs arrive, one aging
+/// tick, one job is processed. Returns the round at which each low-priority
+/// job ran (`None` if it starved).
+pub async fn starvation_rounds(
+    queue: &mut PriorityQueue,
+    low_jobs: usize,
+    rounds: usize,
+    boost: f64,
+) -> Result<Vec<Option<usize>>> {
+    queue.clear().await?;
+    for i in 0..low_jobs {
+        queue.push(&format!("low:{}", i), 1.0).await?;
+    }
+
+    let mut ran_at = vec![None; low_jobs];
+    for round in 0..rounds {
+        queue.push(&format!("high:{}a", round), 10.0).await?;
+        queue.push(&format!("high:{}b", round), 10.0).await?;
+        if boost > 0.0 {
+            queue.age(boost).await?;
+        }
+        if let Some(job) = queue.pop().await? {
+            if let Some(index) = job.payload.strip_prefix("low:").and_then(|i| i.parse::<usize>().ok()) {
+                ran_at[index] = Some(round);
+            }
+        }
+    }
+    Ok(ran_at)
+}
+
+pub struct PriorityAgingDemo {
+    client: RedisClient,
+}
+
+impl PriorityAgingDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn demonstrate(&self, low_jobs: usize, rounds: usize, boost: f64) -> Result<()> {
+        let mut queue = PriorityQueue::new(&self.client, "aging_demo").await?;
+
+        println!("\n=== Priority Aging Demo ===\n");
+        println!(
+            "{} low-priority (1.0) jobs wait while two high-priority (10.0) jobs arrive per processed job",
+            low_jobs
+        );
+
+        for (label, tick_boost) in [("without aging", 0.0), ("with aging", boost)] {
+            let ran_at = starvation_rounds(&mut queue, low_jobs, rounds, tick_boost).await?;
+            let served = ran_at.iter().filter(|round| round.is_some()).count();
+            println!("\n{} (boost {} per tick, {} rounds):", label, tick_boost, rounds);
+            for (i, round) in ran_at.iter().enumerate() {
+                match round {
+                    Some(round) => println!("   low:{} ran at round {}", i, round),
+                    None => println!("   low:{} starved", i),
+                }
+            }
+            println!("   {} of {} low-priority jobs served, {} jobs still queued", served, low_jobs, queue.len().await?);
+        }
+
+        queue.clear().await?;
+        info!("Priority aging demo completed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_member_encoding_orders_oldest_greatest() {
+        let older = encode_member(1, "a");
+        let newer = encode_member(2, "b");
+        assert!(older > newer);
+        assert_eq!(decode_member(&older), Some((1, "a")));
+        assert_eq!(decode_member(&encode_member(7, "job:with:colons")), Some((7, "job:with:colons")));
+        assert_eq!(decode_member("garbage"), None);
+    }
+
+    #[tokio::test]
+    async fn test_pop_order_highest_then_oldest() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut queue = PriorityQueue::new(&client, "test_priority_order").await.unwrap();
+        queue.clear().await.unwrap();
+
+        queue.push("low", 1.0).await.unwrap();
+        queue.push("high-first", 5.0).await.unwrap();
+        queue.push("high-second", 5.0).await.unwrap();
+
+        assert_eq!(queue.pop().await.unwrap().unwrap().payload, "high-first");
+        assert_eq!(queue.pop().await.unwrap().unwrap().payload, "high-second");
+        assert_eq!(queue.pop().await.unwrap().unwrap().payload, "low");
+        assert!(queue.pop().await.unwrap().is_none());
+
+        queue.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_aging_prevents_starvation() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut queue = PriorityQueue::new(&client, "test_priority_aging").await.unwrap();
+
+        let starved = starvation_rounds(&mut queue, 3, 60, 0.0).await.unwrap();
+        assert!(starved.iter().all(Option::is_none));
+
+        let aged = starvation_rounds(&mut queue, 3, 60, 1.0).await.unwrap();
+        assert!(aged.iter().all(Option::is_some));
+
+        queue.clear().await.unwrap();
+    }
+}
@@ -0,0 +1,351 @@
+use crate::integrations::metric_registry::QUEUE_DEPTH;
+use crate::integrations::MetricsExporter;
+use crate::utils::bytes::into_bytes;
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::RedisConnection;
+use bytes::Bytes;
+use redis::aio::ConnectionLike;
+use redis::{AsyncCommands, Script, ToRedisArgs, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// KEYS: queue list
+/// ARGV: job payload, max length
+///
+/// Checking LLEN and pushing in one script keeps concurrent producers from
+/// all seeing room for one more job and overshooting the limit together.
+const PUSH_IF_ROOM_SCRIPT: &str = r#"
+if redis.call('LLEN', KEYS[1]) >= tonumber(ARGV[2]) then
+    return -1
+end
+return redis.call('LPUSH', KEYS[1], ARGV[1])
+"#;
+
+pub fn queue_key(name: &str) -> String {
+    format!("queue:{}", name)
+}
+
+/// What a producer does when the queue is at capacity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackpressurePolicy {
+    /// Fail immediately with `DemoError::QueueFull`.
+    Reject,
+    /// Retry with exponential backoff, giving up with `QueueFull` after `timeout`.
+    Wait {
+        initial: Duration,
+        max: Duration,
+        timeout: Duration,
+    },
+}
+
+impl BackpressurePolicy {
+    pub fn wait(timeout: Duration) -> Self {
+        BackpressurePolicy::Wait {
+            initial: Duration::from_millis(5),
+            max: Duration::from_millis(500),
+            timeout,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backpressure {
+    pub max_len: usize,
+    pub policy: BackpressurePolicy,
+}
+
+/// Delay before retry number `attempt` (0-based), doubling up to `max`.
+pub fn backoff_delay(initial: Duration, max: Duration, attempt: u32) -> Duration {
+    initial.saturating_mul(2u32.saturating_pow(attempt)).min(max)
+}
+
+/// FIFO job queue on a Redis list: LPUSH to enqueue, BRPOP to dequeue.
+#[derive(Clone)]
+pub struct WorkQueue {
+    conn: RedisConnection,
+    name: String,
+    backpressure: Option<Backpressure>,
+    push_if_room: Script,
+    waits: Arc<AtomicUsize>,
+}
+
+impl WorkQueue {
+    pub async fn new(client: &RedisClient, name: &str) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            name: name.to_string(),
+            backpressure: None,
+            push_if_room: Script::new(PUSH_IF_ROOM_SCRIPT),
+            waits: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = Some(backpressure);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Times a producer had to back off because the queue was full.
+    pub fn backoff_count(&self) -> usize {
+        self.waits.load(Ordering::Relaxed)
+    }
+
+    /// Pushes a job, applying the backpressure policy if one is configured.
+    /// Returns the queue length after the push. Payloads can be text or raw
+    /// bytes (`&[u8]`).
+    pub async fn enqueue<P: ToRedisArgs + Send + Sync>(&mut self, payload: P) -> Result<usize> {
+        let Some(backpressure) = self.backpressure else {
+            let len: usize = self.conn.lpush(queue_key(&self.name), payload).await?;
+            return Ok(len);
+        };
+
+        let started = Instant::now();
+        let mut attempt = 0;
+        loop {
+            let len: i64 = self
+                .push_if_room
+                .key(queue_key(&self.name))
+                .arg(&payload)
+                .arg(backpressure.max_len)
+                .invoke_async(&mut self.conn)
+                .await?;
+            if len >= 0 {
+                return Ok(len as usize);
+            }
+
+            match backpressure.policy {
+                BackpressurePolicy::Reject => return Err(DemoError::QueueFull(self.name.clone())),
+                BackpressurePolicy::Wait { initial, max, timeout } => {
+                    if started.elapsed() >= timeout {
+                        return Err(DemoError::QueueFull(self.name.clone()));
+                    }
+                    let delay = backoff_delay(initial, max, attempt);
+                    debug!("Queue '{}' full, backing off {:?}", self.name, delay);
+                    self.waits.fetch_add(1, Ordering::Relaxed);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Pops the oldest job, waiting up to `timeout` for one to arrive.
+    pub async fn dequeue(&mut self, timeout: Duration) -> Result<Option<String>> {
+        let popped: Option<(String, String)> = self
+            .conn
+            .brpop(queue_key(&self.name), timeout.as_secs_f64())
+            .await?;
+        Ok(popped.map(|(_, payload)| payload))
+    }
+
+    /// [`dequeue`](Self::dequeue) for binary payloads: the popped buffer is
+    /// moved into the `Bytes` without copying or UTF-8 validation.
+    pub async fn dequeue_bytes(&mut self, timeout: Duration) -> Result<Option<Bytes>> {
+        let mut cmd = redis::cmd("BRPOP");
+        cmd.arg(queue_key(&self.name)).arg(timeout.as_secs_f64());
+        match self.conn.req_packed_command(&cmd).await? {
+            Value::Bulk(reply) => into_bytes(reply.into_iter().nth(1).unwrap_or(Value::Nil)),
+            Value::Nil => Ok(None),
+            other => Err(DemoError::Demo(format!("Unexpected BRPOP reply: {:?}", other))),
+        }
+    }
+
+    pub async fn len(&mut self) -> Result<usize> {
+        let len: usize = self.conn.llen(queue_key(&self.name)).await?;
+        Ok(len)
+    }
+
+    pub async fn clear(&mut self) -> Result<()> {
+        let _: () = self.conn.del(queue_key(&self.name)).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackpressureReport {
+    pub produced: usize,
+    pub rejected: usize,
+    pub consumed: usize,
+    pub max_observed_len: usize,
+    pub backoffs: usize,
+}
+
+pub struct QueueDemo {
+    client: RedisClient,
+    metrics: Option<MetricsExporter>,
+}
+
+impl QueueDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client, metrics: None }
+    }
+
+    /// Publish the queue's length each time the producer reports progress.
+    pub fn with_metrics(mut self, metrics: MetricsExporter) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    async fn publish(&self, queue: &str, depth: usize) {
+        if let Some(exporter) = &self.metrics {
+            let mut metrics = exporter.metric_set();
+            metrics.gauge(&QUEUE_DEPTH, &[("queue", queue)], depth as f64);
+            exporter.publish(&metrics).await;
+        }
+    }
+
+    /// A fast producer feeds a slow consumer through a bounded queue. With
+    /// the wait policy the producer slows down to the consumer's pace; with
+    /// reject it sheds load instead. Either way the list never grows past `max_len`.
+    pub async fn backpressure(&self, jobs: usize, max_len: usize, reject: bool) -> Result<BackpressureReport> {
+        let policy = if reject {
+            BackpressurePolicy::Reject
+        } else {
+            BackpressurePolicy::wait(Duration::from_secs(10))
+        };
+        let mut producer = WorkQueue::new(&self.client, "backpressure_demo")
+            .await?
+            .with_backpressure(Backpressure { max_len, policy });
+        producer.clear().await?;
+
+        println!("\n=== Producer Backpressure Demo ===\n");
+        println!("{} jobs, queue capped at {}, policy: {:?}", jobs, max_len, policy);
+
+        let produced_done = Arc::new(AtomicUsize::new(0));
+        let mut consumer = WorkQueue::new(&self.client, "backpressure_demo").await?;
+        let consumer_done = produced_done.clone();
+        let consumer_task = tokio::spawn(async move {
+            let mut consumed = 0;
+            loop {
+                match consumer.dequeue(Duration::from_millis(200)).await? {
+                    Some(_) => {
+                        consumed += 1;
+                        tokio::time::sleep(Duration::from_millis(2)).await;
+                    }
+                    None if consumer_done.load(Ordering::SeqCst) == 1 => break,
+                    None => {}
+                }
+            }
+            Ok::<_, DemoError>(consumed)
+        });
+
+        let mut report = BackpressureReport::default();
+        let started = Instant::now();
+        for job in 0..jobs {
+            match producer.enqueue(&format!("job:{}", job)).await {
+                Ok(len) => {
+                    report.produced += 1;
+                    report.max_observed_len = report.max_observed_len.max(len);
+                }
+                Err(DemoError::QueueFull(_)) => report.rejected += 1,
+                Err(e) => return Err(e),
+            }
+            if job % (jobs / 5).max(1) == 0 {
+                let depth = producer.len().await?;
+                println!("   produced {:>5} | queue length {:>4} | elapsed {:?}", job, depth, started.elapsed());
+                self.publish("backpressure_demo", depth).await;
+            }
+        }
+        produced_done.store(1, Ordering::SeqCst);
+        report.consumed = consumer_task
+            .await
+            .map_err(|e| DemoError::Demo(format!("Consumer task failed: {}", e)))??;
+        report.backoffs = producer.backoff_count();
+        self.publish("backpressure_demo", producer.len().await?).await;
+
+        println!("\n   Produced:           {}", report.produced);
+        println!("   Rejected:           {}", report.rejected);
+        println!("   Consumed:           {}", report.consumed);
+        println!("   Producer backoffs:  {}", report.backoffs);
+        println!("   Max queue length:   {} (limit {})", report.max_observed_len, max_len);
+
+        producer.clear().await?;
+        info!("Backpressure demo completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    #[test]
+    fn test_backoff_delay_doubles_and_caps() {
+        let initial = Duration::from_millis(10);
+        let max = Duration::from_millis(100);
+        assert_eq!(backoff_delay(initial, max, 0), Duration::from_millis(10));
+        assert_eq!(backoff_delay(initial, max, 2), Duration::from_millis(40));
+        assert_eq!(backoff_delay(initial, max, 10), max);
+        assert_eq!(backoff_delay(initial, max, 40), max);
+    }
+
+    #[tokio::test]
+    async fn test_reject_policy_returns_queue_full() {
+        let client = get_test_client().await;
+        let mut queue = WorkQueue::new(&client, "test_reject")
+            .await
+            .unwrap()
+            .with_backpressure(Backpressure { max_len: 2, policy: BackpressurePolicy::Reject });
+        queue.clear().await.unwrap();
+
+        assert_eq!(queue.enqueue("a").await.unwrap(), 1);
+        assert_eq!(queue.enqueue("b").await.unwrap(), 2);
+        assert!(matches!(queue.enqueue("c").await, Err(DemoError::QueueFull(_))));
+        assert_eq!(queue.dequeue(Duration::from_secs(1)).await.unwrap().as_deref(), Some("a"));
+
+        queue.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_binary_payloads_round_trip() {
+        let client = get_test_client().await;
+        let mut queue = WorkQueue::new(&client, "test_binary").await.unwrap();
+        queue.clear().await.unwrap();
+
+        let payload: &[u8] = &[0, 159, 146, 150, 255];
+        queue.enqueue(payload).await.unwrap();
+        assert_eq!(queue.dequeue_bytes(Duration::from_secs(1)).await.unwrap().as_deref(), Some(payload));
+        assert_eq!(queue.dequeue_bytes(Duration::from_millis(100)).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_wait_policy_times_out() {
+        let client = get_test_client().await;
+        let mut queue = WorkQueue::new(&client, "test_wait_timeout")
+            .await
+            .unwrap()
+            .with_backpressure(Backpressure {
+                max_len: 1,
+                policy: BackpressurePolicy::wait(Duration::from_millis(100)),
+            });
+        queue.clear().await.unwrap();
+
+        queue.enqueue("a").await.unwrap();
+        let started = Instant::now();
+        assert!(matches!(queue.enqueue("b").await, Err(DemoError::QueueFull(_))));
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert!(queue.backoff_count() > 0);
+
+        queue.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_demo_stays_bounded() {
+        let client = get_test_client().await;
+        let demo = QueueDemo::new(client);
+        let report = demo.backpressure(100, 10, false).await.unwrap();
+        assert_eq!(report.produced, 100);
+        assert_eq!(report.consumed, 100);
+        assert!(report.max_observed_len <= 10);
+    }
+}
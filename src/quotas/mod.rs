@@ -0,0 +1,5 @@
+pub mod monthly;
+pub mod namespace;
+
+pub use monthly::{QuotaDecision, QuotaDemo, QuotaLevel, QuotaManager, QuotaPlan, QuotaReport, QuotaWarning};
+pub use namespace::{Enforcement, NamespaceGuard, NamespaceQuota, NamespaceQuotaDemo, NamespaceUsage, UsageJob};
@@ -0,0 +1,372 @@
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::RedisConnection;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use redis::{AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::info;
+
+pub const WARNING_CHANNEL: &str = "quotas:warnings";
+
+/// Usage keys outlive their month so last month's usage can still be queried.
+const USAGE_TTL_SECS: u64 = 62 * 24 * 60 * 60;
+
+/// KEYS: plan hash, period usage counter, per-second rate counter
+/// ARGV: cost, usage TTL seconds, api key, warning channel, period
+///
+/// Returns {used, level} where level is 0 (ok), 1 (crossed soft limit) or
+/// 2 (reached hard limit), or {-1, used} when the quota is exhausted,
+/// {-2, used} when the per-second rate is exceeded and {-3, 0} without a plan.
+const CONSUME_SCRIPT: &str = r#"
+local limit = tonumber(redis.call('HGET', KEYS[1], 'limit'))
+if not limit then
+    return {-3, 0}
+end
+local used = tonumber(redis.call('GET', KEYS[2]) or '0')
+local per_second = tonumber(redis.call('HGET', KEYS[1], 'per_second') or '0')
+if per_second > 0 then
+    local hits = redis.call('INCR', KEYS[3])
+    if hits == 1 then
+        redis.call('EXPIRE', KEYS[3], 1)
+    end
+    if hits > per_second then
+        return {-2, used}
+    end
+end
+local cost = tonumber(ARGV[1])
+if used + cost > limit then
+    return {-1, used}
+end
+local after = redis.call('INCRBY', KEYS[2], cost)
+if after == cost then
+    redis.call('EXPIRE', KEYS[2], ARGV[2])
+end
+local soft = math.floor(limit * tonumber(redis.call('HGET', KEYS[1], 'soft_ratio') or '1'))
+local level = 0
+if after >= limit then
+    level = 2
+elseif used < soft and after >= soft then
+    level = 1
+end
+if level > 0 then
+    local name = 'soft'
+    if level == 2 then
+        name = 'hard'
+    end
+    redis.call('PUBLISH', ARGV[4], cjson.encode({
+        api_key = ARGV[3], period = ARGV[5], level = name, used = after, limit = limit
+    }))
+end
+return {after, level}
+"#;
+
+pub fn plan_key(api_key: &str) -> String {
+    format!("quotas:{}:plan", api_key)
+}
+
+pub fn usage_key(api_key: &str, period: &str) -> String {
+    format!("quotas:{}:usage:{}", api_key, period)
+}
+
+pub fn rate_key(api_key: &str) -> String {
+    format!("quotas:{}:rate", api_key)
+}
+
+/// Billing period for `now`, e.g. `2025-03`.
+pub fn period(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m").to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuotaPlan {
+    pub monthly_limit: u64,
+    /// Fraction of the limit at which a soft warning is published.
+    pub soft_ratio: f64,
+    /// Burst limit per second; 0 disables it.
+    pub per_second: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaLevel {
+    Soft,
+    Hard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaDecision {
+    Allowed { used: u64, remaining: u64, crossed: Option<QuotaLevel> },
+    RateLimited,
+    Exhausted { used: u64 },
+    NoPlan,
+}
+
+impl QuotaDecision {
+    pub fn from_script_reply(reply: (i64, i64), limit: u64) -> Result<Self> {
+        match reply {
+            (-3, _) => Ok(QuotaDecision::NoPlan),
+            (-2, _) => Ok(QuotaDecision::RateLimited),
+            (-1, used) => Ok(QuotaDecision::Exhausted { used: used as u64 }),
+            (used, level) if used >= 0 => Ok(QuotaDecision::Allowed {
+                used: used as u64,
+                remaining: limit.saturating_sub(used as u64),
+                crossed: match level {
+                    1 => Some(QuotaLevel::Soft),
+                    2 => Some(QuotaLevel::Hard),
+                    _ => None,
+                },
+            }),
+            other => Err(DemoError::Demo(format!("Unexpected quota script reply: {:?}", other))),
+        }
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, QuotaDecision::Allowed { .. })
+    }
+}
+
+/// Published on [`WARNING_CHANNEL`] when a key crosses a threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaWarning {
+    pub api_key: String,
+    pub period: String,
+    pub level: QuotaLevel,
+    pub used: u64,
+    pub limit: u64,
+}
+
+/// Monthly request quotas per API key with a per-second burst limit, soft
+/// and hard thresholds, and pub/sub warnings.
+pub struct QuotaManager {
+    conn: RedisConnection,
+    script: Script,
+}
+
+impl QuotaManager {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            script: Script::new(CONSUME_SCRIPT),
+        })
+    }
+
+    pub async fn set_plan(&mut self, api_key: &str, plan: QuotaPlan) -> Result<()> {
+        let _: () = self
+            .conn
+            .hset_multiple(
+                plan_key(api_key),
+                &[
+                    ("limit", plan.monthly_limit.to_string()),
+                    ("soft_ratio", plan.soft_ratio.to_string()),
+                    ("per_second", plan.per_second.to_string()),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn plan(&mut self, api_key: &str) -> Result<Option<QuotaPlan>> {
+        let fields: HashMap<String, String> = self.conn.hgetall(plan_key(api_key)).await?;
+        let parse = |field: &str| {
+            fields
+                .get(field)
+                .map(|value| value.parse::<f64>())
+                .transpose()
+                .map_err(|_| DemoError::Demo(format!("Invalid quota plan field '{}' for {}", field, api_key)))
+        };
+        let limit = match parse("limit")? {
+            Some(limit) => limit,
+            None => return Ok(None),
+        };
+        Ok(Some(QuotaPlan {
+            monthly_limit: limit as u64,
+            soft_ratio: parse("soft_ratio")?.unwrap_or(1.0),
+            per_second: parse("per_second")?.unwrap_or(0.0) as u32,
+        }))
+    }
+
+    /// Charges `cost` requests to `api_key` for the period containing `now`.
+    pub async fn consume(&mut self, api_key: &str, cost: u64, now: DateTime<Utc>) -> Result<QuotaDecision> {
+        let period = period(now);
+        let reply: (i64, i64) = self
+            .script
+            .key(plan_key(api_key))
+            .key(usage_key(api_key, &period))
+            .key(rate_key(api_key))
+            .arg(cost)
+            .arg(USAGE_TTL_SECS)
+            .arg(api_key)
+            .arg(WARNING_CHANNEL)
+            .arg(&period)
+            .invoke_async(&mut self.conn)
+            .await?;
+        let limit = self.plan(api_key).await?.map_or(0, |plan| plan.monthly_limit);
+        QuotaDecision::from_script_reply(reply, limit)
+    }
+
+    pub async fn usage(&mut self, api_key: &str, period: &str) -> Result<u64> {
+        let used: Option<u64> = self.conn.get(usage_key(api_key, period)).await?;
+        Ok(used.unwrap_or(0))
+    }
+
+    pub async fn remaining(&mut self, api_key: &str, period: &str) -> Result<Option<u64>> {
+        let used = self.usage(api_key, period).await?;
+        Ok(self.plan(api_key).await?.map(|plan| plan.monthly_limit.saturating_sub(used)))
+    }
+
+    /// Clears usage for one period, e.g. after a support-approved top-up.
+    pub async fn reset(&mut self, api_key: &str, period: &str) -> Result<bool> {
+        let removed: u64 = self.conn.del(usage_key(api_key, period)).await?;
+        Ok(removed > 0)
+    }
+
+    pub async fn remove(&mut self, api_key: &str, period: &str) -> Result<()> {
+        let _: () = self
+            .conn
+            .del(&[plan_key(api_key), usage_key(api_key, period), rate_key(api_key)])
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct QuotaReport {
+    pub allowed: usize,
+    pub rate_limited: usize,
+    pub exhausted: usize,
+    pub warnings: Vec<QuotaWarning>,
+}
+
+pub struct QuotaDemo {
+    client: RedisClient,
+}
+
+impl QuotaDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Sends `requests` calls for one key against a small plan while a
+    /// subscriber prints the warnings as they are published.
+    pub async fn simulate(&self, api_key: &str, requests: usize) -> Result<QuotaReport> {
+        let mut manager = QuotaManager::new(&self.client).await?;
+        let now = Utc::now();
+        let plan = QuotaPlan { monthly_limit: 100, soft_ratio: 0.8, per_second: 25 };
+        manager.remove(api_key, &period(now)).await?;
+        manager.set_plan(api_key, plan).await?;
+
+        println!("\n=== Per-API-Key Quotas ===\n");
+        println!("Plan for {}: {} requests/month, warn at {:.0}%, burst {}/s", api_key, plan.monthly_limit, plan.soft_ratio * 100.0, plan.per_second);
+
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(WARNING_CHANNEL).await?;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let subscriber = tokio::spawn(async move {
+            let mut messages = pubsub.into_on_message();
+            while let Some(message) = messages.next().await {
+                let warning = message
+                    .get_payload::<String>()
+                    .ok()
+                    .and_then(|payload| serde_json::from_str::<QuotaWarning>(&payload).ok());
+                if let Some(warning) = warning {
+                    if tx.send(warning).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let mut report = QuotaReport::default();
+        for request in 0..requests {
+            match manager.consume(api_key, 1, Utc::now()).await? {
+                QuotaDecision::Allowed { .. } => report.allowed += 1,
+                QuotaDecision::RateLimited => report.rate_limited += 1,
+                QuotaDecision::Exhausted { .. } => report.exhausted += 1,
+                QuotaDecision::NoPlan => return Err(DemoError::Demo(format!("No plan for {}", api_key))),
+            }
+            // Bursty client: short pauses every 40 requests.
+            if request % 40 == 39 {
+                tokio::time::sleep(Duration::from_millis(1100)).await;
+            }
+            while let Ok(warning) = rx.try_recv() {
+                println!("   📣 {:?} limit for {}: {}/{} used in {}", warning.level, warning.api_key, warning.used, warning.limit, warning.period);
+                report.warnings.push(warning);
+            }
+        }
+        if let Ok(Some(warning)) = tokio::time::timeout(Duration::from_millis(500), rx.recv()).await {
+            println!("   📣 {:?} limit for {}: {}/{} used in {}", warning.level, warning.api_key, warning.used, warning.limit, warning.period);
+            report.warnings.push(warning);
+        }
+        subscriber.abort();
+
+        println!("\n   Allowed:      {}", report.allowed);
+        println!("   Rate-limited: {}", report.rate_limited);
+        println!("   Exhausted:    {}", report.exhausted);
+        println!("   Remaining:    {:?}", manager.remaining(api_key, &period(now)).await?);
+
+        manager.remove(api_key, &period(now)).await?;
+        info!("Quota simulation completed");
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_period_and_keys() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 31, 23, 59, 0).unwrap();
+        assert_eq!(period(now), "2025-03");
+        assert_eq!(usage_key("k1", &period(now)), "quotas:k1:usage:2025-03");
+    }
+
+    #[test]
+    fn test_decision_from_script_reply() {
+        assert_eq!(
+            QuotaDecision::from_script_reply((80, 1), 100).unwrap(),
+            QuotaDecision::Allowed { used: 80, remaining: 20, crossed: Some(QuotaLevel::Soft) }
+        );
+        assert_eq!(QuotaDecision::from_script_reply((-1, 100), 100).unwrap(), QuotaDecision::Exhausted { used: 100 });
+        assert_eq!(QuotaDecision::from_script_reply((-2, 5), 100).unwrap(), QuotaDecision::RateLimited);
+        assert_eq!(QuotaDecision::from_script_reply((-3, 0), 0).unwrap(), QuotaDecision::NoPlan);
+        assert!(QuotaDecision::from_script_reply((-9, 0), 0).is_err());
+    }
+
+    #[test]
+    fn test_warning_payload_matches_script() {
+        let payload = r#"{"api_key":"k1","period":"2025-03","level":"hard","used":100,"limit":100}"#;
+        let warning: QuotaWarning = serde_json::from_str(payload).unwrap();
+        assert_eq!(warning.level, QuotaLevel::Hard);
+    }
+
+    #[tokio::test]
+    async fn test_consume_stops_at_limit() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut manager = QuotaManager::new(&client).await.unwrap();
+        let now = Utc::now();
+        manager.remove("quota_test", &period(now)).await.unwrap();
+        manager
+            .set_plan("quota_test", QuotaPlan { monthly_limit: 5, soft_ratio: 0.6, per_second: 0 })
+            .await
+            .unwrap();
+
+        let mut crossed = Vec::new();
+        for _ in 0..5 {
+            if let QuotaDecision::Allowed { crossed: Some(level), .. } = manager.consume("quota_test", 1, now).await.unwrap() {
+                crossed.push(level);
+            }
+        }
+        assert_eq!(crossed, vec![QuotaLevel::Soft, QuotaLevel::Hard]);
+        assert_eq!(manager.consume("quota_test", 1, now).await.unwrap(), QuotaDecision::Exhausted { used: 5 });
+        assert_eq!(manager.remaining("quota_test", &period(now)).await.unwrap(), Some(0));
+
+        assert!(manager.reset("quota_test", &period(now)).await.unwrap());
+        assert!(manager.consume("quota_test", 1, now).await.unwrap().is_allowed());
+        manager.remove("quota_test", &period(now)).await.unwrap();
+    }
+}
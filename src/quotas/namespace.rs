@@ -0,0 +1,444 @@
+use crate::server::fleet::format_bytes;
+use crate::utils::connection::{command_keys, escape_glob, CommandObserver};
+use crate::utils::key_stats::is_write_command;
+use crate::utils::{KeyScanner, RedisConnection};
+use crate::{RedisClient, Result};
+use redis::{AsyncCommands, Cmd, ErrorKind, RedisError, RedisResult};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Hash of namespace prefix to bytes used, rewritten by every usage pass.
+pub const USAGE_KEY: &str = "quotas:namespaces:usage";
+
+const SCAN_COUNT: usize = 200;
+
+/// Writes that only shrink a namespace. They stay allowed over quota, or a
+/// tenant could never clean up after itself.
+const FREEING_COMMANDS: &[&str] = &[
+    "DEL", "UNLINK", "GETDEL", "HDEL", "LPOP", "RPOP", "LREM", "LTRIM", "SREM", "SPOP", "ZREM", "ZPOPMIN", "ZPOPMAX",
+    "ZREMRANGEBYSCORE", "ZREMRANGEBYRANK", "XDEL", "XTRIM", "EXPIRE", "PEXPIRE", "EXPIREAT", "PEXPIREAT",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Enforcement {
+    /// Writes fail with an error until usage drops below the limit.
+    Refuse,
+    /// Writes go through; the first one over the limit logs a warning.
+    Warn,
+}
+
+/// A storage limit for every key under `prefix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceQuota {
+    pub prefix: String,
+    pub limit_bytes: u64,
+    pub enforcement: Enforcement,
+}
+
+impl NamespaceQuota {
+    pub fn new(prefix: &str, limit_bytes: u64, enforcement: Enforcement) -> Self {
+        Self { prefix: prefix.to_string(), limit_bytes, enforcement }
+    }
+}
+
+/// What one usage pass found under a prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceUsage {
+    pub prefix: String,
+    pub keys: u64,
+    pub bytes: u64,
+}
+
+/// SCANs each namespace and sums MEMORY USAGE over its keys, pipelined one
+/// SCAN batch at a time. Keys that vanish mid-pass count as zero.
+pub async fn measure(conn: &mut RedisConnection, quotas: &[NamespaceQuota]) -> Result<Vec<NamespaceUsage>> {
+    let mut usage = Vec::with_capacity(quotas.len());
+    for quota in quotas {
+        let mut total = NamespaceUsage { prefix: quota.prefix.clone(), keys: 0, bytes: 0 };
+        let mut seen = HashSet::new();
+        let mut scanner = KeyScanner::new(conn.clone(), &format!("{}*", escape_glob(&quota.prefix)), None).with_count(SCAN_COUNT);
+        while let Some(batch) = scanner.next_batch().await? {
+            let batch: Vec<String> = batch.into_iter().filter(|key| seen.insert(key.clone())).collect();
+            if batch.is_empty() {
+                continue;
+            }
+            let mut pipe = redis::pipe();
+            for key in &batch {
+                pipe.cmd("MEMORY").arg("USAGE").arg(key);
+            }
+            let sizes: Vec<Option<u64>> = pipe.query_async(conn).await?;
+            total.keys += sizes.iter().filter(|size| size.is_some()).count() as u64;
+            total.bytes += sizes.into_iter().flatten().sum::<u64>();
+        }
+        usage.push(total);
+    }
+    Ok(usage)
+}
+
+/// Refuses or warns about writes into namespaces that the last usage pass
+/// found over quota. Register it on a client with
+/// [`RedisClient::with_observer`]; it checks keys as they go on the wire,
+/// so a client key prefix counts towards the namespace.
+///
+/// Usage is only as fresh as the last pass, so a namespace can overshoot
+/// its limit by whatever is written between two passes.
+pub struct NamespaceGuard {
+    quotas: Vec<NamespaceQuota>,
+    usage: RwLock<HashMap<String, u64>>,
+    /// Namespaces already warned about since they last went over.
+    warned: Mutex<HashSet<String>>,
+    refused: AtomicU64,
+    allowed_over: AtomicU64,
+}
+
+impl NamespaceGuard {
+    pub fn new(quotas: Vec<NamespaceQuota>) -> Self {
+        Self {
+            quotas,
+            usage: RwLock::new(HashMap::new()),
+            warned: Mutex::new(HashSet::new()),
+            refused: AtomicU64::new(0),
+            allowed_over: AtomicU64::new(0),
+        }
+    }
+
+    pub fn quotas(&self) -> &[NamespaceQuota] {
+        &self.quotas
+    }
+
+    /// The quota covering `key`: the longest matching prefix, so a nested
+    /// namespace can have a limit of its own.
+    pub fn quota_for(&self, key: &str) -> Option<&NamespaceQuota> {
+        self.quotas.iter().filter(|quota| key.starts_with(&quota.prefix)).max_by_key(|quota| quota.prefix.len())
+    }
+
+    pub fn used(&self, prefix: &str) -> u64 {
+        self.usage.read().expect("namespace usage poisoned").get(prefix).copied().unwrap_or(0)
+    }
+
+    /// Takes in a usage pass.
+    pub fn record(&self, usage: &[NamespaceUsage]) {
+        let mut current = self.usage.write().expect("namespace usage poisoned");
+        let mut warned = self.warned.lock().expect("namespace warnings poisoned");
+        for namespace in usage {
+            current.insert(namespace.prefix.clone(), namespace.bytes);
+            let under = self.quotas.iter().any(|quota| quota.prefix == namespace.prefix && namespace.bytes < quota.limit_bytes);
+            if under {
+                warned.remove(&namespace.prefix);
+            }
+        }
+    }
+
+    /// Takes in the counters a usage job stored, e.g. from another process.
+    pub async fn load(&self, conn: &mut RedisConnection) -> Result<()> {
+        let stored: HashMap<String, u64> = conn.hgetall(USAGE_KEY).await?;
+        let usage: Vec<NamespaceUsage> = stored.into_iter().map(|(prefix, bytes)| NamespaceUsage { prefix, keys: 0, bytes }).collect();
+        self.record(&usage);
+        Ok(())
+    }
+
+    /// Writes refused so far.
+    pub fn refused(&self) -> u64 {
+        self.refused.load(Ordering::Relaxed)
+    }
+
+    /// Writes let through into a namespace over a warn-only quota.
+    pub fn allowed_over(&self) -> u64 {
+        self.allowed_over.load(Ordering::Relaxed)
+    }
+}
+
+impl CommandObserver for NamespaceGuard {
+    fn on_command(&self, _cmd: &Cmd) {}
+
+    fn admit(&self, cmd: &Cmd) -> RedisResult<()> {
+        let (name, keys) = command_keys(cmd);
+        if !is_write_command(&name) || FREEING_COMMANDS.contains(&name.as_str()) {
+            return Ok(());
+        }
+        for key in &keys {
+            let Some(quota) = self.quota_for(key) else {
+                continue;
+            };
+            let used = self.used(&quota.prefix);
+            if used < quota.limit_bytes {
+                continue;
+            }
+            let over = format!("{} is over its {} quota ({} used)", quota.prefix, format_bytes(quota.limit_bytes), format_bytes(used));
+            match quota.enforcement {
+                Enforcement::Refuse => {
+                    self.refused.fetch_add(1, Ordering::Relaxed);
+                    return Err(RedisError::from((ErrorKind::ClientError, "Namespace quota exceeded", over)));
+                }
+                Enforcement::Warn => {
+                    self.allowed_over.fetch_add(1, Ordering::Relaxed);
+                    if self.warned.lock().expect("namespace warnings poisoned").insert(quota.prefix.clone()) {
+                        warn!("{}; writes still allowed", over);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The background half: measures every namespace of a guard, stores the
+/// totals in [`USAGE_KEY`] and hands them to the guard.
+pub struct UsageJob {
+    conn: RedisConnection,
+    guard: Arc<NamespaceGuard>,
+}
+
+impl UsageJob {
+    /// `client` should be one without the guard or a key prefix, so the job
+    /// sees whole namespaces and can always write its counters.
+    pub async fn new(client: &RedisClient, guard: Arc<NamespaceGuard>) -> Result<Self> {
+        Ok(Self { conn: client.get_async_connection().await?, guard })
+    }
+
+    pub async fn run_once(&mut self) -> Result<Vec<NamespaceUsage>> {
+        let usage = measure(&mut self.conn, self.guard.quotas()).await?;
+        if !usage.is_empty() {
+            let fields: Vec<(&str, u64)> = usage.iter().map(|namespace| (namespace.prefix.as_str(), namespace.bytes)).collect();
+            let _: () = self.conn.hset_multiple(USAGE_KEY, &fields).await?;
+        }
+        self.guard.record(&usage);
+        Ok(usage)
+    }
+
+    /// Runs a pass every `interval` until the returned handle is aborted.
+    pub fn spawn(mut self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once().await {
+                    warn!("Namespace usage pass failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// One line per namespace: usage against its limit and what happens to
+/// writes.
+pub fn render(usage: &[NamespaceUsage], quotas: &[NamespaceQuota]) -> String {
+    let width = quotas.iter().map(|quota| quota.prefix.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for quota in quotas {
+        let (keys, bytes) = usage
+            .iter()
+            .find(|namespace| namespace.prefix == quota.prefix)
+            .map_or((0, 0), |namespace| (namespace.keys, namespace.bytes));
+        let status = match (bytes >= quota.limit_bytes, quota.enforcement) {
+            (false, _) => "✅ under quota",
+            (true, Enforcement::Refuse) => "⛔ refusing writes",
+            (true, Enforcement::Warn) => "⚠️  over quota, warning only",
+        };
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>4} keys  {:>9} of {:>9} ({:>5.1}%)  {}",
+            quota.prefix,
+            keys,
+            format_bytes(bytes),
+            format_bytes(quota.limit_bytes),
+            bytes as f64 * 100.0 / quota.limit_bytes.max(1) as f64,
+            status,
+            width = width
+        );
+    }
+    out
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TenantReport {
+    pub prefix: String,
+    pub written: usize,
+    pub refused: usize,
+}
+
+pub struct NamespaceQuotaDemo {
+    client: RedisClient,
+}
+
+impl NamespaceQuotaDemo {
+    pub fn new(client: RedisClient) -> Self {
+        Self { client }
+    }
+
+    /// Two tenants write the same stream of records: `acme` has a small quota
+    /// that refuses writes, `globex` a larger one that only warns. A usage
+    /// job measures both in the background while they write.
+    ///
+    /// Namespaces sit under the client's own key prefix, and the job and
+    /// the tenants' clients use whole key names instead of adding it again.
+    pub async fn run(&self, rounds: usize) -> Result<Vec<TenantReport>> {
+        let base = self.client.key_prefix().unwrap_or("");
+        let quotas = vec![
+            NamespaceQuota::new(&format!("{}tenant:acme:", base), 4 * 1024, Enforcement::Refuse),
+            NamespaceQuota::new(&format!("{}tenant:globex:", base), 8 * 1024, Enforcement::Warn),
+        ];
+        let admin = self.client.clone().with_key_prefix("");
+        let guard = Arc::new(NamespaceGuard::new(quotas.clone()));
+        let mut conn = admin.get_async_connection().await?;
+        clear(&admin, &quotas).await?;
+
+        println!("\n=== Per-Namespace Storage Quotas ===\n");
+        for quota in &quotas {
+            println!("   {:<20} {:>9}  {:?}", quota.prefix, format_bytes(quota.limit_bytes), quota.enforcement);
+        }
+
+        let mut tenants = Vec::new();
+        for quota in &quotas {
+            let client = self.client.clone().with_observer(guard.clone()).with_key_prefix(&quota.prefix);
+            let conn = client.get_async_connection().await?;
+            tenants.push((TenantReport { prefix: quota.prefix.clone(), ..Default::default() }, conn));
+        }
+
+        let background = UsageJob::new(&admin, guard.clone()).await?.spawn(Duration::from_millis(100));
+        let record = "x".repeat(256);
+        for round in 1..=rounds {
+            for (report, conn) in tenants.iter_mut() {
+                for i in 0..4 {
+                    let key = format!("record:{}", (round - 1) * 4 + i);
+                    match conn.set::<_, _, ()>(&key, &record).await {
+                        Ok(()) => report.written += 1,
+                        Err(e) if e.kind() == ErrorKind::ClientError => report.refused += 1,
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+            }
+            // Let the background job catch up before the next round.
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            let usage = measure(&mut conn, &quotas).await?;
+            println!("\nAfter round {}:", round);
+            print!("{}", render(&usage, &quotas));
+        }
+        background.abort();
+
+        println!();
+        for (report, _) in &tenants {
+            println!("   {:<20} {} writes accepted, {} refused", report.prefix, report.written, report.refused);
+        }
+        println!("   Writes let through over a warn-only quota: {}", guard.allowed_over());
+
+        clear(&admin, &quotas).await?;
+        info!("Namespace quota demo completed");
+        Ok(tenants.into_iter().map(|(report, _)| report).collect())
+    }
+}
+
+async fn clear(client: &RedisClient, quotas: &[NamespaceQuota]) -> Result<()> {
+    let mut conn = client.get_async_connection().await?;
+    for quota in quotas {
+        let keys = client.scan_keys(&format!("{}*", quota.prefix)).await?.collect_all().await?;
+        if !keys.is_empty() {
+            let _: () = conn.del(keys).await?;
+        }
+    }
+    let _: () = conn.del(USAGE_KEY).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard() -> NamespaceGuard {
+        NamespaceGuard::new(vec![
+            NamespaceQuota::new("tenant:a:", 1000, Enforcement::Refuse),
+            NamespaceQuota::new("tenant:a:archive:", 5000, Enforcement::Refuse),
+            NamespaceQuota::new("tenant:b:", 1000, Enforcement::Warn),
+        ])
+    }
+
+    fn usage(prefix: &str, bytes: u64) -> NamespaceUsage {
+        NamespaceUsage { prefix: prefix.to_string(), keys: 1, bytes }
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let guard = guard();
+        assert_eq!(guard.quota_for("tenant:a:archive:1").unwrap().limit_bytes, 5000);
+        assert_eq!(guard.quota_for("tenant:a:1").unwrap().limit_bytes, 1000);
+        assert!(guard.quota_for("tenant:c:1").is_none());
+    }
+
+    #[test]
+    fn test_refuses_growing_writes_over_quota() {
+        let guard = guard();
+        let mut set = redis::cmd("SET");
+        set.arg("tenant:a:1").arg("v");
+        assert!(guard.admit(&set).is_ok());
+
+        guard.record(&[usage("tenant:a:", 1200)]);
+        let refused = guard.admit(&set).unwrap_err();
+        assert_eq!(refused.kind(), ErrorKind::ClientError);
+        assert!(refused.to_string().contains("tenant:a: is over its 1000 B quota (1.2 KiB used)"), "{}", refused);
+        // Reads, deletes and other namespaces are unaffected.
+        assert!(guard.admit(redis::cmd("GET").arg("tenant:a:1")).is_ok());
+        assert!(guard.admit(redis::cmd("DEL").arg("tenant:a:1")).is_ok());
+        assert!(guard.admit(redis::cmd("SET").arg("tenant:a:archive:1").arg("v")).is_ok());
+        assert_eq!(guard.refused(), 1);
+
+        guard.record(&[usage("tenant:a:", 900)]);
+        assert!(guard.admit(&set).is_ok());
+    }
+
+    #[test]
+    fn test_warn_only_lets_writes_through() {
+        let guard = guard();
+        guard.record(&[usage("tenant:b:", 1000)]);
+        for _ in 0..3 {
+            assert!(guard.admit(redis::cmd("HSET").arg("tenant:b:h").arg("f").arg("v")).is_ok());
+        }
+        assert_eq!(guard.allowed_over(), 3);
+        assert_eq!(guard.refused(), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let guard = guard();
+        let rendered = render(&[usage("tenant:a:", 1500)], &guard.quotas()[..1]);
+        assert_eq!(rendered, "tenant:a:     1 keys    1.5 KiB of    1000 B (150.0%)  ⛔ refusing writes\n");
+    }
+
+    #[tokio::test]
+    async fn test_usage_job_measures_and_stores() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del(&["nsquota_test:1", "nsquota_test:2", USAGE_KEY]).await.unwrap();
+        let _: () = conn.set("nsquota_test:1", "x".repeat(1000)).await.unwrap();
+        let _: () = conn.set("nsquota_test:2", "y").await.unwrap();
+
+        let guard = Arc::new(NamespaceGuard::new(vec![NamespaceQuota::new("nsquota_test:", 1000, Enforcement::Refuse)]));
+        let mut job = UsageJob::new(&client, guard.clone()).await.unwrap();
+        let usage = job.run_once().await.unwrap();
+        assert_eq!(usage[0].keys, 2);
+        assert!(usage[0].bytes > 1000);
+        let stored: u64 = conn.hget(USAGE_KEY, "nsquota_test:").await.unwrap();
+        assert_eq!(stored, usage[0].bytes);
+
+        let guarded = client.clone().with_observer(guard.clone()).with_key_prefix("nsquota_test:");
+        let mut guarded = guarded.get_async_connection().await.unwrap();
+        assert!(guarded.set::<_, _, ()>("3", "z").await.is_err());
+        let _: () = guarded.del(&["1", "2"]).await.unwrap();
+        let _: () = conn.del(USAGE_KEY).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_measure_matches_prefix_literally() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.set("nsquota_glob[1]:a", "x").await.unwrap();
+        let _: () = conn.set("nsquota_glob1:b", "y").await.unwrap();
+
+        let usage = measure(&mut conn, &[NamespaceQuota::new("nsquota_glob[1]:", 1000, Enforcement::Refuse)]).await.unwrap();
+        assert_eq!(usage[0].keys, 1);
+        let _: () = conn.del(&["nsquota_glob[1]:a", "nsquota_glob1:b"]).await.unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+use super::limiter::{now_ms, Algorithm, Decision, RateLimit, RateLimiter};
+use crate::utils::RedisConnection;
+use crate::Result;
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use std::time::Duration;
+
+pub fn window_key(subject: &str, window: u64) -> String {
+    format!("ratelimit:fixed:{}:{}", subject, window)
+}
+
+/// The window `now_ms` falls in, and the milliseconds left until it ends.
+pub fn window_at(now_ms: u64, window_ms: u64) -> (u64, u64) {
+    (now_ms / window_ms, window_ms - now_ms % window_ms)
+}
+
+/// `limit` requests per aligned window, counted with INCR on a key per
+/// window that expires when the window ends.
+pub struct FixedWindowLimiter {
+    conn: RedisConnection,
+    limit: RateLimit,
+}
+
+impl FixedWindowLimiter {
+    pub fn new(conn: RedisConnection, limit: RateLimit) -> Self {
+        Self { conn, limit }
+    }
+}
+
+#[async_trait]
+impl RateLimiter for FixedWindowLimiter {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::FixedWindow
+    }
+
+    fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    async fn check(&self, subject: &str) -> Result<Decision> {
+        let (window, left_ms) = window_at(now_ms(), self.limit.window_ms());
+        let key = window_key(subject, window);
+        // Denied requests count too; they only push the counter further past a
+        // limit it has already reached.
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(&key, 1)
+            .pexpire(&key, left_ms as i64)
+            .ignore()
+            .query_async(&mut self.conn.clone())
+            .await?;
+        let allowed = count <= self.limit.limit;
+        Ok(Decision {
+            allowed,
+            remaining: self.limit.limit.saturating_sub(count),
+            retry_after: (!allowed).then(|| Duration::from_millis(left_ms)),
+        })
+    }
+
+    async fn reset(&self, subject: &str) -> Result<()> {
+        let (window, _) = window_at(now_ms(), self.limit.window_ms());
+        let _: () = self.conn.clone().del(window_key(subject, window)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+
+    #[test]
+    fn test_windows_are_aligned() {
+        assert_eq!(window_at(10_250, 1000), (10, 750));
+        assert_eq!(window_at(10_999, 1000), (10, 1));
+        assert_eq!(window_at(11_000, 1000), (11, 1000));
+    }
+
+    #[tokio::test]
+    async fn test_allows_the_limit_per_window() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let limiter = FixedWindowLimiter::new(client.get_async_connection().await.unwrap(), RateLimit::new(3, Duration::from_secs(60)));
+        limiter.reset("test-fixed").await.unwrap();
+
+        let mut decisions = Vec::new();
+        for _ in 0..5 {
+            decisions.push(limiter.check("test-fixed").await.unwrap());
+        }
+        let allowed: Vec<bool> = decisions.iter().map(|decision| decision.allowed).collect();
+        assert_eq!(allowed, [true, true, true, false, false]);
+        assert_eq!(decisions[1].remaining, 1);
+        assert!(decisions[4].retry_after.unwrap() <= Duration::from_secs(60));
+        limiter.reset("test-fixed").await.unwrap();
+    }
+}
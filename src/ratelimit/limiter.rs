@@ -0,0 +1,145 @@
+use super::{FixedWindowLimiter, SlidingLogLimiter, TokenBucketLimiter};
+use crate::utils::RedisConnection;
+use crate::{DemoError, Result};
+use async_trait::async_trait;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// One INCR counter per window: cheapest, but allows up to twice the
+    /// limit across a window boundary.
+    FixedWindow,
+    /// A sorted set of request timestamps: exact, one member per request.
+    SlidingLog,
+    /// Tokens refilled continuously up to a burst capacity, in a Lua script.
+    TokenBucket,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [Algorithm::FixedWindow, Algorithm::SlidingLog, Algorithm::TokenBucket];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Algorithm::FixedWindow => "fixed-window",
+            Algorithm::SlidingLog => "sliding-log",
+            Algorithm::TokenBucket => "token-bucket",
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.replace('_', "-").as_str() {
+            "fixed-window" | "fixed" => Ok(Algorithm::FixedWindow),
+            "sliding-log" | "sliding" => Ok(Algorithm::SlidingLog),
+            "token-bucket" | "bucket" => Ok(Algorithm::TokenBucket),
+            other => Err(DemoError::Configuration(format!(
+                "Unknown rate limit algorithm: {} (use fixed-window, sliding-log or token-bucket)",
+                other
+            ))),
+        }
+    }
+}
+
+/// `limit` requests per `window`. For the token bucket `limit` is the
+/// burst capacity, refilled at `limit` per `window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub window: Duration,
+}
+
+impl RateLimit {
+    pub fn new(limit: u64, window: Duration) -> Self {
+        Self { limit: limit.max(1), window: window.max(Duration::from_millis(1)) }
+    }
+
+    pub fn per_second(limit: u64) -> Self {
+        Self::new(limit, Duration::from_secs(1))
+    }
+
+    pub(crate) fn window_ms(&self) -> u64 {
+        self.window.as_millis() as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    /// Requests still allowed right now.
+    pub remaining: u64,
+    /// When a denied request could next succeed.
+    pub retry_after: Option<Duration>,
+}
+
+impl Decision {
+    pub(crate) fn from_script_reply((allowed, remaining, retry_ms): (i64, i64, i64)) -> Self {
+        Self {
+            allowed: allowed == 1,
+            remaining: remaining.max(0) as u64,
+            retry_after: (allowed != 1).then(|| Duration::from_millis(retry_ms.max(0) as u64)),
+        }
+    }
+}
+
+/// Decides, per subject (a user, an API key, an IP), whether one more
+/// request fits its [`RateLimit`]. State lives in Redis so every process
+/// enforcing the limit shares it.
+#[async_trait]
+pub trait RateLimiter: Send + Sync {
+    fn algorithm(&self) -> Algorithm;
+
+    fn limit(&self) -> RateLimit;
+
+    /// Counts one request from `subject` if it is allowed.
+    async fn check(&self, subject: &str) -> Result<Decision>;
+
+    /// Forgets everything about `subject`.
+    async fn reset(&self, subject: &str) -> Result<()>;
+}
+
+/// A limiter of the given kind over `conn`.
+pub fn limiter(conn: RedisConnection, algorithm: Algorithm, limit: RateLimit) -> Box<dyn RateLimiter> {
+    match algorithm {
+        Algorithm::FixedWindow => Box::new(FixedWindowLimiter::new(conn, limit)),
+        Algorithm::SlidingLog => Box::new(SlidingLogLimiter::new(conn, limit)),
+        Algorithm::TokenBucket => Box::new(TokenBucketLimiter::new(conn, limit)),
+    }
+}
+
+pub(crate) fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_algorithm_names() {
+        for algorithm in Algorithm::ALL {
+            assert_eq!(algorithm.as_str().parse::<Algorithm>().unwrap(), algorithm);
+        }
+        assert_eq!("token_bucket".parse::<Algorithm>().unwrap(), Algorithm::TokenBucket);
+        assert!("leaky-bucket".parse::<Algorithm>().is_err());
+    }
+
+    #[test]
+    fn test_decision_from_script_reply() {
+        let allowed = Decision::from_script_reply((1, 4, 0));
+        assert_eq!(allowed, Decision { allowed: true, remaining: 4, retry_after: None });
+        let denied = Decision::from_script_reply((0, 0, 250));
+        assert_eq!(denied.retry_after, Some(Duration::from_millis(250)));
+        assert_eq!(RateLimit::new(0, Duration::ZERO), RateLimit { limit: 1, window: Duration::from_millis(1) });
+    }
+}
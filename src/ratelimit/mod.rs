@@ -0,0 +1,11 @@
+pub mod demo;
+pub mod fixed_window;
+pub mod limiter;
+pub mod sliding_log;
+pub mod token_bucket;
+
+pub use demo::RateLimitDemo;
+pub use fixed_window::FixedWindowLimiter;
+pub use limiter::{limiter, Algorithm, Decision, RateLimit, RateLimiter};
+pub use sliding_log::SlidingLogLimiter;
+pub use token_bucket::TokenBucketLimiter;
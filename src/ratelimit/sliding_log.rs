@@ -0,0 +1,103 @@
+use super::limiter::{now_ms, Algorithm, Decision, RateLimit, RateLimiter};
+use crate::utils::RedisConnection;
+use crate::Result;
+use async_trait::async_trait;
+use redis::{AsyncCommands, Script};
+use uuid::Uuid;
+
+/// KEYS: request log sorted set
+/// ARGV: now ms, window ms, limit, member
+///
+/// Drops requests older than the window, then logs this one if fewer than
+/// `limit` remain. Returns {allowed, remaining, ms until the oldest logged
+/// request leaves the window}.
+const CHECK_SCRIPT: &str = r#"
+local now = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local limit = tonumber(ARGV[3])
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+local count = redis.call('ZCARD', KEYS[1])
+if count < limit then
+    redis.call('ZADD', KEYS[1], now, ARGV[4])
+    redis.call('PEXPIRE', KEYS[1], window)
+    return {1, limit - count - 1, 0}
+end
+local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+return {0, 0, tonumber(oldest[2]) + window - now}
+"#;
+
+pub fn log_key(subject: &str) -> String {
+    format!("ratelimit:log:{}", subject)
+}
+
+/// At most `limit` requests in any `window`-long span, from a sorted set
+/// holding the time of every allowed request. Exact, at the cost of one
+/// member per request.
+pub struct SlidingLogLimiter {
+    conn: RedisConnection,
+    limit: RateLimit,
+    script: Script,
+}
+
+impl SlidingLogLimiter {
+    pub fn new(conn: RedisConnection, limit: RateLimit) -> Self {
+        Self { conn, limit, script: Script::new(CHECK_SCRIPT) }
+    }
+}
+
+#[async_trait]
+impl RateLimiter for SlidingLogLimiter {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::SlidingLog
+    }
+
+    fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    async fn check(&self, subject: &str) -> Result<Decision> {
+        let reply: (i64, i64, i64) = self
+            .script
+            .key(log_key(subject))
+            .arg(now_ms())
+            .arg(self.limit.window_ms())
+            .arg(self.limit.limit)
+            // Requests in the same millisecond need members of their own.
+            .arg(Uuid::new_v4().to_string())
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(Decision::from_script_reply(reply))
+    }
+
+    async fn reset(&self, subject: &str) -> Result<()> {
+        let _: () = self.conn.clone().del(log_key(subject)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_window_slides_instead_of_resetting() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let limiter = SlidingLogLimiter::new(client.get_async_connection().await.unwrap(), RateLimit::new(2, Duration::from_millis(300)));
+        limiter.reset("test-sliding").await.unwrap();
+
+        assert!(limiter.check("test-sliding").await.unwrap().allowed);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(limiter.check("test-sliding").await.unwrap().allowed);
+        let denied = limiter.check("test-sliding").await.unwrap();
+        assert!(!denied.allowed);
+        assert!(denied.retry_after.unwrap() <= Duration::from_millis(150));
+
+        // The first request has left the window, the second hasn't.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(limiter.check("test-sliding").await.unwrap().remaining, 0);
+        assert!(!limiter.check("test-sliding").await.unwrap().allowed);
+        limiter.reset("test-sliding").await.unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+use super::limiter::{now_ms, Algorithm, Decision, RateLimit, RateLimiter};
+use crate::utils::RedisConnection;
+use crate::Result;
+use async_trait::async_trait;
+use redis::{AsyncCommands, Script};
+
+/// KEYS: bucket hash (tokens, at)
+/// ARGV: capacity, tokens added per ms, now ms
+///
+/// Refills for the time since the last request, then takes a token if
+/// there is a whole one. Returns {allowed, tokens left, ms until the next
+/// token}. The bucket expires once it would be full again anyway.
+const TAKE_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'at')
+local tokens = tonumber(state[1]) or capacity
+local at = tonumber(state[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - at) * rate)
+local allowed = 0
+local retry = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    retry = math.ceil((1 - tokens) / rate)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'at', tostring(now))
+redis.call('PEXPIRE', KEYS[1], math.ceil((capacity - tokens) / rate) + 1)
+return {allowed, math.floor(tokens), retry}
+"#;
+
+pub fn bucket_key(subject: &str) -> String {
+    format!("ratelimit:bucket:{}", subject)
+}
+
+/// Bursts of up to `limit` requests, then a steady `limit` per `window` as
+/// tokens trickle back. The refill is computed from timestamps in a Lua
+/// script, so nothing has to run between requests.
+pub struct TokenBucketLimiter {
+    conn: RedisConnection,
+    limit: RateLimit,
+    script: Script,
+}
+
+impl TokenBucketLimiter {
+    pub fn new(conn: RedisConnection, limit: RateLimit) -> Self {
+        Self { conn, limit, script: Script::new(TAKE_SCRIPT) }
+    }
+
+    /// Tokens added back per millisecond.
+    pub fn refill_per_ms(&self) -> f64 {
+        self.limit.limit as f64 / self.limit.window_ms() as f64
+    }
+}
+
+#[async_trait]
+impl RateLimiter for TokenBucketLimiter {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::TokenBucket
+    }
+
+    fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    async fn check(&self, subject: &str) -> Result<Decision> {
+        let reply: (i64, i64, i64) = self
+            .script
+            .key(bucket_key(subject))
+            .arg(self.limit.limit)
+            .arg(self.refill_per_ms().to_string())
+            .arg(now_ms())
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(Decision::from_script_reply(reply))
+    }
+
+    async fn reset(&self, subject: &str) -> Result<()> {
+        let _: () = self.conn.clone().del(bucket_key(subject)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_burst_then_refill() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let limiter = TokenBucketLimiter::new(client.get_async_connection().await.unwrap(), RateLimit::new(3, Duration::from_millis(300)));
+        limiter.reset("test-bucket").await.unwrap();
+
+        for remaining in [2, 1, 0] {
+            assert_eq!(limiter.check("test-bucket").await.unwrap().remaining, remaining);
+        }
+        let denied = limiter.check("test-bucket").await.unwrap();
+        assert!(!denied.allowed);
+        assert!(denied.retry_after.unwrap() <= Duration::from_millis(100));
+
+        // One token comes back every 100ms.
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert!(limiter.check("test-bucket").await.unwrap().allowed);
+        assert!(!limiter.check("test-bucket").await.unwrap().allowed);
+        limiter.reset("test-bucket").await.unwrap();
+    }
+}
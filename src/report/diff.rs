@@ -0,0 +1,103 @@
+use crate::{DemoError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use std::str::FromStr;
+
+/// How the output of compare and check commands is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    /// One [`DiffRecord`] per line, for scripts.
+    Jsonl,
+}
+
+impl FromStr for ReportFormat {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(ReportFormat::Text),
+            "jsonl" | "ndjson" => Ok(ReportFormat::Jsonl),
+            other => Err(DemoError::Configuration(format!("Unknown report format: {}", other))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    /// On the right (expected) side only.
+    Missing,
+    /// On the left (actual) side only.
+    Extra,
+    /// On both sides with different values.
+    Changed,
+    /// Present but breaking a rule, with nothing to compare against.
+    Invalid,
+}
+
+/// One difference between what is (`left`) and what should be or was
+/// (`right`), shared by every command that compares or checks data.
+/// Absent sides serialize as `null` so each line has the same fields.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffRecord {
+    pub key: String,
+    pub kind: DiffKind,
+    pub left: Option<Value>,
+    pub right: Option<Value>,
+    /// What would fix it, e.g. a Redis command.
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+pub fn write_jsonl<'a, I>(records: I, out: &mut dyn Write) -> Result<()>
+where
+    I: IntoIterator<Item = &'a DiffRecord>,
+{
+    for record in records {
+        serde_json::to_writer(&mut *out, record)?;
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonl_has_one_record_per_line() {
+        let records = vec![
+            DiffRecord {
+                key: "username:bob".to_string(),
+                kind: DiffKind::Missing,
+                left: None,
+                right: Some(Value::from("42")),
+                action: Some("SET username:bob 42".to_string()),
+                detail: None,
+            },
+            DiffRecord {
+                key: "p99".to_string(),
+                kind: DiffKind::Changed,
+                left: Some(Value::from(1.5)),
+                right: Some(Value::from(1.2)),
+                action: None,
+                detail: Some("+25.0%".to_string()),
+            },
+        ];
+        let mut out = Vec::new();
+        write_jsonl(&records, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"{"key":"username:bob","kind":"missing","left":null,"right":"42","action":"SET username:bob 42"}"#
+        );
+        let parsed: Vec<DiffRecord> = lines.iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(parsed, records);
+        assert_eq!("JSONL".parse::<ReportFormat>().unwrap(), ReportFormat::Jsonl);
+        assert!("xml".parse::<ReportFormat>().is_err());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod diff;
+
+pub use diff::{write_jsonl, DiffKind, DiffRecord, ReportFormat};
@@ -0,0 +1,116 @@
+use super::store::{Change, StoreMiddleware};
+use crate::demos::streams::{entry_fields, parse_stream_id};
+use crate::utils::capped::add_capped_stream;
+use crate::{DemoError, Result};
+use crate::utils::RedisConnection;
+use async_trait::async_trait;
+use redis::streams::StreamRangeReply;
+use redis::AsyncCommands;
+use serde_json::{Map, Value};
+
+/// Entries kept per entity; older history is trimmed.
+pub const AUDIT_MAX_LEN: usize = 1000;
+
+pub fn audit_key(kind: &str, id: &str) -> String {
+    format!("audit:{}:{}", kind, id)
+}
+
+/// Top-level fields that differ between two documents, as
+/// `{"field": {"from": .., "to": ..}}`. A missing side counts as null.
+pub fn json_diff(before: Option<&Value>, after: Option<&Value>) -> Value {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    let mut diff = Map::new();
+    for field in fields {
+        let from = before.get(field).unwrap_or(&Value::Null);
+        let to = after.get(field).unwrap_or(&Value::Null);
+        if from != to {
+            diff.insert(field.clone(), serde_json::json!({ "from": from, "to": to }));
+        }
+    }
+    Value::Object(diff)
+}
+
+/// Appends every change to the entity's `audit:<kind>:<id>` stream.
+pub struct AuditTrail;
+
+#[async_trait]
+impl StoreMiddleware for AuditTrail {
+    async fn on_change(&self, conn: &mut RedisConnection, change: &Change) -> Result<()> {
+        let diff = json_diff(change.before.as_ref(), change.after.as_ref()).to_string();
+        let before = change.before.as_ref().map(Value::to_string).unwrap_or_default();
+        let after = change.after.as_ref().map(Value::to_string).unwrap_or_default();
+        let fields = [
+            ("actor", change.actor.as_str()),
+            ("op", change.op.as_str()),
+            ("diff", diff.as_str()),
+            ("before", before.as_str()),
+            ("after", after.as_str()),
+        ];
+        add_capped_stream(conn, &audit_key(change.kind, &change.id), &fields, AUDIT_MAX_LEN).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub stream_id: String,
+    pub at_ms: u64,
+    pub actor: String,
+    pub op: String,
+    pub diff: Value,
+}
+
+/// The newest `count` audit entries for an entity, newest first.
+pub async fn history(conn: &mut RedisConnection, kind: &str, id: &str, count: usize) -> Result<Vec<AuditEntry>> {
+    let reply: StreamRangeReply = conn.xrevrange_count(audit_key(kind, id), "+", "-", count).await?;
+    reply
+        .ids
+        .iter()
+        .map(|entry| {
+            let mut fields = entry_fields(entry);
+            let diff = fields.remove("diff").unwrap_or_else(|| "{}".to_string());
+            Ok(AuditEntry {
+                stream_id: entry.id.clone(),
+                at_ms: parse_stream_id(&entry.id)
+                    .map(|(ms, _)| ms)
+                    .ok_or_else(|| DemoError::Demo(format!("Invalid stream id: {}", entry.id)))?,
+                actor: fields.remove("actor").unwrap_or_default(),
+                op: fields.remove("op").unwrap_or_default(),
+                diff: serde_json::from_str(&diff)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_diff_update() {
+        let before = json!({"name": "Amy", "city": "Oslo", "age": 30});
+        let after = json!({"name": "Amy", "city": "Bergen", "age": 30});
+        assert_eq!(json_diff(Some(&before), Some(&after)), json!({"city": {"from": "Oslo", "to": "Bergen"}}));
+    }
+
+    #[test]
+    fn test_json_diff_create_and_delete() {
+        let doc = json!({"name": "Amy"});
+        assert_eq!(json_diff(None, Some(&doc)), json!({"name": {"from": null, "to": "Amy"}}));
+        assert_eq!(json_diff(Some(&doc), None), json!({"name": {"from": "Amy", "to": null}}));
+        assert_eq!(json_diff(Some(&doc), Some(&doc)), json!({}));
+    }
+
+    #[test]
+    fn test_audit_key() {
+        assert_eq!(audit_key("user", "42"), "audit:user:42");
+    }
+}
@@ -0,0 +1,148 @@
+use super::store::Entity;
+use crate::{DemoError, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+
+/// An entity's top-level fields, each as its JSON encoding: the hash an
+/// [`EntityStore`](super::EntityStore) keeps at `<KIND>:<id>`.
+pub type Fields = BTreeMap<String, String>;
+
+pub fn to_fields<T: Entity>(entity: &T) -> Result<Fields> {
+    match serde_json::to_value(entity)? {
+        Value::Object(map) => map.into_iter().map(|(field, value)| Ok((field, serde_json::to_string(&value)?))).collect(),
+        other => Err(DemoError::Configuration(format!("{} serializes to {}, not an object with fields", T::KIND, other))),
+    }
+}
+
+/// One stored field. A value that isn't JSON, such as a name set by hand
+/// with `HSET user:<id> full_name Amy`, is taken as that string.
+pub fn decode_field(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
+
+/// The fields as one JSON object, the shape the entity serializes to.
+pub fn fields_object(fields: &Fields) -> Map<String, Value> {
+    fields.iter().map(|(field, value)| (field.clone(), decode_field(value))).collect()
+}
+
+pub fn from_fields<T: DeserializeOwned>(fields: &Fields) -> Result<T> {
+    Ok(serde_json::from_value(Value::Object(fields_object(fields)))?)
+}
+
+/// What a save has to write to turn `saved` into `current`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FieldChanges {
+    pub set: Vec<(String, String)>,
+    pub removed: Vec<String>,
+}
+
+impl FieldChanges {
+    pub fn between(saved: &Fields, current: &Fields) -> Self {
+        Self {
+            set: current.iter().filter(|(field, value)| saved.get(*field) != Some(value)).map(|(f, v)| (f.clone(), v.clone())).collect(),
+            removed: saved.keys().filter(|field| !current.contains_key(*field)).cloned().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.removed.is_empty()
+    }
+
+    /// Field names and values sent, the bytes a save costs.
+    pub fn bytes(&self) -> usize {
+        self.set.iter().map(|(field, value)| field.len() + value.len()).sum::<usize>() + self.removed.iter().map(String::len).sum::<usize>()
+    }
+}
+
+/// An entity with the fields it had when last loaded or saved, so
+/// [`EntityStore::save_changes`](super::EntityStore::save_changes) knows
+/// which ones changed. Change it through `DerefMut` like the entity
+/// itself.
+#[derive(Debug, Clone)]
+pub struct Tracked<T> {
+    pub(super) entity: T,
+    pub(super) saved: Fields,
+}
+
+impl<T: Entity> Tracked<T> {
+    /// Fields changed since the last save.
+    pub fn dirty_fields(&self) -> Result<Vec<String>> {
+        let changes = FieldChanges::between(&self.saved, &to_fields(&self.entity)?);
+        Ok(changes.set.into_iter().map(|(field, _)| field).chain(changes.removed).collect())
+    }
+
+    pub fn into_inner(self) -> T {
+        self.entity
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.entity
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.entity
+    }
+}
+
+/// Fields written by one save.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SaveStats {
+    pub fields: usize,
+    pub bytes: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::User;
+
+    fn user() -> User {
+        User::new("amy".to_string(), "amy@example.com".to_string(), "Amy Pond".to_string())
+    }
+
+    #[test]
+    fn test_fields_round_trip() {
+        let mut user = user();
+        user.city = Some("Leadworth".to_string());
+        let fields = to_fields(&user).unwrap();
+        assert_eq!(fields["username"], "\"amy\"");
+        assert_eq!(fields["age"], "null");
+        assert_eq!(fields["login_count"], "0");
+        assert_eq!(from_fields::<User>(&fields).unwrap(), user);
+
+        let mut edited = fields.clone();
+        edited.insert("full_name".to_string(), "Amelia Pond".to_string());
+        assert_eq!(from_fields::<User>(&edited).unwrap().full_name, "Amelia Pond");
+    }
+
+    #[test]
+    fn test_only_changed_fields_are_dirty() {
+        let user = user();
+        let mut tracked = Tracked { saved: to_fields(&user).unwrap(), entity: user };
+        assert!(tracked.dirty_fields().unwrap().is_empty());
+        tracked.login_count += 1;
+        tracked.city = Some("London".to_string());
+        assert_eq!(tracked.dirty_fields().unwrap(), ["city", "login_count"]);
+
+        let changes = FieldChanges::between(&tracked.saved, &to_fields(&tracked.entity).unwrap());
+        assert_eq!(changes.set, [("city".to_string(), "\"London\"".to_string()), ("login_count".to_string(), "1".to_string())]);
+        assert_eq!(changes.bytes(), "city".len() + "\"London\"".len() + "login_count".len() + 1);
+        let whole = serde_json::to_string(&tracked.entity).unwrap().len();
+        assert!(changes.bytes() * 5 < whole, "{} vs {}", changes.bytes(), whole);
+    }
+
+    #[test]
+    fn test_removed_fields() {
+        let saved: Fields = [("a", "1"), ("b", "2")].into_iter().map(|(f, v)| (f.to_string(), v.to_string())).collect();
+        let current: Fields = [("a", "1")].into_iter().map(|(f, v)| (f.to_string(), v.to_string())).collect();
+        assert_eq!(FieldChanges::between(&saved, &current), FieldChanges { set: vec![], removed: vec!["b".to_string()] });
+    }
+}
@@ -0,0 +1,12 @@
+pub mod audit;
+pub mod fields;
+pub mod store;
+pub mod user;
+pub mod validation;
+
+pub use redis_rust_demo_derive::RedisEntity;
+pub use audit::{AuditEntry, AuditTrail};
+pub use fields::{Fields, SaveStats, Tracked};
+pub use store::{Change, ChangeOp, Entity, EntityStore, StoreMiddleware};
+pub use user::{RestoreOutcome, UserRepository};
+pub use validation::{ValidationIssue, ValidationReport};
@@ -0,0 +1,485 @@
+use super::fields::{fields_object, from_fields, to_fields, FieldChanges, Fields, SaveStats, Tracked};
+use super::validation::ValidationReport;
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::RedisConnection;
+use async_trait::async_trait;
+use redis::{AsyncCommands, Script};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// KEYS: entity key, index keys to point at the entity, then index keys
+/// it dropped
+/// ARGV: entity id, how many index keys to point, TTL in seconds (0 for
+/// none), 1 to delete the entity, how many fields the hash had when the
+/// caller read it ('' to check only the fields listed next, on a hash that
+/// still exists), how many fields to check, those fields and the values the
+/// caller read, how many fields to set, those fields and their values, then
+/// fields to remove
+///
+/// Returns 1, or without writing 0 if the hash changed since the caller read
+/// it, since the dropped index keys were worked out from that read, and -n
+/// when the nth index key to point belongs to another entity. A dropped
+/// index key is only deleted while it still points at this entity, so one
+/// another entity has taken over since is left alone.
+const WRITE_SCRIPT: &str = r#"
+if ARGV[5] == '' then
+    if redis.call('EXISTS', KEYS[1]) == 0 then
+        return 0
+    end
+elseif redis.call('HLEN', KEYS[1]) ~= tonumber(ARGV[5]) then
+    return 0
+end
+local checks = tonumber(ARGV[6])
+for i = 7, 6 + 2 * checks, 2 do
+    if redis.call('HGET', KEYS[1], ARGV[i]) ~= ARGV[i + 1] then
+        return 0
+    end
+end
+local point = tonumber(ARGV[2]) + 1
+for i = 2, point do
+    local owner = redis.call('GET', KEYS[i])
+    if owner and owner ~= ARGV[1] then
+        return -i + 1
+    end
+end
+local at = 7 + 2 * checks
+local set = tonumber(ARGV[at])
+if ARGV[4] == '1' then
+    redis.call('DEL', KEYS[1])
+else
+    if set > 0 then
+        redis.call('HSET', KEYS[1], unpack(ARGV, at + 1, at + 2 * set))
+    end
+    if #ARGV > at + 2 * set then
+        redis.call('HDEL', KEYS[1], unpack(ARGV, at + 2 * set + 1))
+    end
+    if ARGV[3] ~= '0' then
+        redis.call('EXPIRE', KEYS[1], ARGV[3])
+    end
+end
+for i = 2, #KEYS do
+    if i > point then
+        if redis.call('GET', KEYS[i]) == ARGV[1] then
+            redis.call('DEL', KEYS[i])
+        end
+    elseif ARGV[3] == '0' then
+        redis.call('SET', KEYS[i], ARGV[1])
+    else
+        redis.call('SET', KEYS[i], ARGV[1], 'EX', ARGV[3])
+    end
+end
+return 1
+"#;
+
+/// How often a put or delete re-reads a document that keeps changing
+/// under it before giving up.
+const WRITE_ATTEMPTS: usize = 10;
+
+/// Something stored as a hash at `<KIND>:<id>`, one field per top-level
+/// field holding its JSON encoding. Usually derived with
+/// `#[derive(RedisEntity)]`.
+pub trait Entity: Serialize + DeserializeOwned + Send + Sync {
+    const KIND: &'static str;
+
+    /// Expiry set by every save; `None` keeps entities until deleted.
+    const TTL: Option<Duration> = None;
+
+    fn entity_id(&self) -> String;
+
+    fn key_for(id: &str) -> String {
+        format!("{}:{}", Self::KIND, id)
+    }
+
+    /// Names of the lookups in `index_keys`, in the same order.
+    const INDEXES: &'static [&'static str] = &[];
+
+    /// Lookup keys (`<index>:<value>`) that should point at this entity.
+    /// [`EntityStore`] keeps them in step with every put and delete.
+    fn index_keys(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Field rules beyond what deserializing checks. Saves of an invalid
+    /// value are rejected; loads return it anyway and log the issues, so
+    /// a hand-edited hash can still be read and fixed.
+    fn validate(&self) -> ValidationReport {
+        ValidationReport::default()
+    }
+}
+
+/// Logs what `validate` finds wrong with an entity that was just loaded.
+pub(crate) fn warn_if_invalid<T: Entity>(entity: &T) {
+    let report = entity.validate();
+    if !report.is_valid() {
+        warn!("Loaded invalid {} {}: {}", T::KIND, entity.entity_id(), report);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOp {
+    Create,
+    Update,
+    Delete,
+    Restore,
+}
+
+impl ChangeOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeOp::Create => "create",
+            ChangeOp::Update => "update",
+            ChangeOp::Delete => "delete",
+            ChangeOp::Restore => "restore",
+        }
+    }
+}
+
+impl fmt::Display for ChangeOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A committed mutation, handed to every middleware of the store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: &'static str,
+    pub id: String,
+    pub op: ChangeOp,
+    pub actor: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// Runs after each successful write. A failing middleware fails the call,
+/// but the write itself has already happened.
+#[async_trait]
+pub trait StoreMiddleware: Send + Sync {
+    async fn on_change(&self, conn: &mut RedisConnection, change: &Change) -> Result<()>;
+}
+
+/// Generic hash store for any [`Entity`], with middleware that sees every
+/// change. Repositories with extra bookkeeping (indexes, trash) do
+/// their own writes and report them through [`EntityStore::record`].
+pub struct EntityStore<T: Entity> {
+    conn: RedisConnection,
+    actor: String,
+    middleware: Vec<Arc<dyn StoreMiddleware>>,
+    write: Script,
+    _entity: PhantomData<T>,
+}
+
+impl<T: Entity> EntityStore<T> {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            actor: "system".to_string(),
+            middleware: Vec::new(),
+            write: Script::new(WRITE_SCRIPT),
+            _entity: PhantomData,
+        })
+    }
+
+    pub fn with_middleware(mut self, middleware: Arc<dyn StoreMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Who subsequent changes are attributed to.
+    pub fn set_actor(&mut self, actor: &str) {
+        self.actor = actor.to_string();
+    }
+
+    pub async fn get(&mut self, id: &str) -> Result<Option<T>> {
+        let fields = self.get_fields(id).await?;
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        let entity: T = from_fields(&fields)?;
+        warn_if_invalid(&entity);
+        Ok(Some(entity))
+    }
+
+    /// The entity an index key like `email:<value>` points at.
+    pub async fn find_by_index(&mut self, index: &str, value: &str) -> Result<Option<T>> {
+        let id: Option<String> = self.conn.get(format!("{}:{}", index, value)).await?;
+        match id {
+            Some(id) => self.get(&id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// The entity's hash as stored, empty if there is none.
+    pub async fn get_fields(&mut self, id: &str) -> Result<Fields> {
+        Ok(self.conn.hgetall(T::key_for(id)).await?)
+    }
+
+    pub async fn put(&mut self, entity: &T) -> Result<()> {
+        let id = entity.entity_id();
+        entity.validate().into_result(T::KIND, &id)?;
+        let after = to_fields(entity)?;
+        let before = self.write(&id, Some((entity, &after))).await?;
+        let op = if before.is_empty() { ChangeOp::Create } else { ChangeOp::Update };
+        self.record(op, &id, Some(&before).filter(|before| !before.is_empty()), Some(&after)).await
+    }
+
+    pub async fn delete(&mut self, id: &str) -> Result<bool> {
+        let before = self.write(id, None).await?;
+        if before.is_empty() {
+            return Ok(false);
+        }
+        self.record(ChangeOp::Delete, id, Some(&before), None).await?;
+        Ok(true)
+    }
+
+    /// Loads an entity for [`save_changes`](Self::save_changes).
+    pub async fn load(&mut self, id: &str) -> Result<Option<Tracked<T>>> {
+        let fields = self.get_fields(id).await?;
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        let entity: T = from_fields(&fields)?;
+        warn_if_invalid(&entity);
+        Ok(Some(Tracked { entity, saved: fields }))
+    }
+
+    /// Writes only the fields changed since the entity was loaded or last
+    /// saved, and nothing at all if none did, moving its index keys along
+    /// in the same script. Changes others made to the remaining fields
+    /// meanwhile are kept; a changed field that no longer holds what was
+    /// loaded, or a deleted entity, fails the save.
+    pub async fn save_changes(&mut self, tracked: &mut Tracked<T>) -> Result<SaveStats> {
+        let current = to_fields(&tracked.entity)?;
+        let changes = FieldChanges::between(&tracked.saved, &current);
+        if changes.is_empty() {
+            return Ok(SaveStats::default());
+        }
+        let id = tracked.entity.entity_id();
+        tracked.entity.validate().into_result(T::KIND, &id)?;
+        // An index key only moves with its field, so checking the touched
+        // fields also checks the index keys dropped.
+        let touched: Vec<&String> = changes.set.iter().map(|(field, _)| field).chain(&changes.removed).collect();
+        let checked: Fields = tracked
+            .saved
+            .iter()
+            .filter(|(field, _)| touched.contains(field))
+            .map(|(field, value)| (field.clone(), value.clone()))
+            .collect();
+        if !self.run_write(&id, Some(&tracked.entity), &tracked.saved, None, &checked, &changes).await? {
+            return Err(DemoError::Demo(format!("{} {} changed while saving, try again", T::KIND, id)));
+        }
+        self.record(ChangeOp::Update, &id, Some(&tracked.saved), Some(&current)).await?;
+        tracked.saved = current;
+        Ok(SaveStats { fields: changes.set.len() + changes.removed.len(), bytes: changes.bytes() })
+    }
+
+    /// Writes `after` (deletes for `None`) together with its index keys in
+    /// one script, dropping the index keys only the hash it replaces had.
+    /// If another writer gets in between the read and the script, it starts
+    /// over from a fresh read. Returns the fields replaced.
+    async fn write(&mut self, id: &str, after: Option<(&T, &Fields)>) -> Result<Fields> {
+        for _ in 0..WRITE_ATTEMPTS {
+            let before = self.get_fields(id).await?;
+            if before.is_empty() && after.is_none() {
+                return Ok(before);
+            }
+            let changes = after.map(|(_, after)| FieldChanges::between(&before, after)).unwrap_or_default();
+            if self.run_write(id, after.map(|(entity, _)| entity), &before, Some(before.len()), &before, &changes).await? {
+                return Ok(before);
+            }
+        }
+        Err(DemoError::Demo(format!("{} {} kept changing while writing, try again", T::KIND, id)))
+    }
+
+    /// Runs WRITE_SCRIPT for `after` over the hash that held `read` when
+    /// read. `count` is how many fields it had then, when the whole hash
+    /// was read. A `read` that no longer deserializes has no index keys to
+    /// drop. Returns false if the hash changed since, and fails if another
+    /// entity holds one of `after`'s index keys.
+    async fn run_write(&mut self, id: &str, after: Option<&T>, read: &Fields, count: Option<usize>, checked: &Fields, changes: &FieldChanges) -> Result<bool> {
+        let new = after.map(Entity::index_keys).unwrap_or_default();
+        let old = from_fields::<T>(read).map(|entity| entity.index_keys()).unwrap_or_default();
+        let dropped: Vec<&String> = old.iter().filter(|key| !new.contains(key)).collect();
+        let written: i64 = self
+            .write
+            .key(T::key_for(id))
+            .key(&new)
+            .key(dropped)
+            .arg(id)
+            .arg(new.len())
+            .arg(T::TTL.map_or(0, |ttl| ttl.as_secs()))
+            .arg(u8::from(after.is_none()))
+            .arg(count.map(|count| count.to_string()).unwrap_or_default())
+            .arg(checked.len())
+            .arg(checked.iter().collect::<Vec<_>>())
+            .arg(changes.set.len())
+            .arg(&changes.set)
+            .arg(&changes.removed)
+            .invoke_async(&mut self.conn)
+            .await?;
+        match written {
+            1 => Ok(true),
+            0 => Ok(false),
+            taken => match usize::try_from(-taken).ok().and_then(|n| new.get(n - 1)) {
+                Some(key) => Err(DemoError::Demo(format!("{} is already taken", key))),
+                None => Err(DemoError::Demo(format!("Unexpected store script reply: {}", taken))),
+            },
+        }
+    }
+
+    /// Passes a change made outside `put`/`delete` through the middleware.
+    pub async fn record(&mut self, op: ChangeOp, id: &str, before: Option<&Fields>, after: Option<&Fields>) -> Result<()> {
+        if self.middleware.is_empty() {
+            return Ok(());
+        }
+        let change = Change {
+            kind: T::KIND,
+            id: id.to_string(),
+            op,
+            actor: self.actor.clone(),
+            before: before.map(|fields| Value::Object(fields_object(fields))),
+            after: after.map(|fields| Value::Object(fields_object(fields))),
+        };
+        for middleware in &self.middleware {
+            middleware.on_change(&mut self.conn, &change).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::User;
+    use crate::repository::RedisEntity;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, RedisEntity)]
+    #[redis(ttl_secs = 1800)]
+    struct LoginSession {
+        #[redis(id)]
+        token: String,
+        #[redis(index = "session_owner")]
+        user: u64,
+    }
+
+    #[test]
+    fn test_derive_attributes() {
+        let session = LoginSession { token: "t1".to_string(), user: 42 };
+        assert_eq!(LoginSession::KIND, "login_session");
+        assert_eq!(LoginSession::TTL, Some(Duration::from_secs(1800)));
+        assert_eq!(LoginSession::key_for(&session.entity_id()), "login_session:t1");
+        assert_eq!(LoginSession::INDEXES, ["session_owner"]);
+        assert_eq!(session.index_keys(), ["session_owner:42"]);
+        assert!(session.validate().is_valid());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize, RedisEntity)]
+    #[redis(prefix = "store_test_device")]
+    struct Device {
+        id: u32,
+        #[redis(index = "store_test_serial")]
+        serial: String,
+    }
+
+    #[tokio::test]
+    async fn test_put_and_delete_maintain_indexes() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut store = EntityStore::<Device>::new(&client).await.unwrap();
+        let mut device = Device { id: 7, serial: "SN-1".to_string() };
+        store.put(&device).await.unwrap();
+        assert_eq!(store.find_by_index("store_test_serial", "SN-1").await.unwrap().as_ref(), Some(&device));
+
+        device.serial = "SN-2".to_string();
+        store.put(&device).await.unwrap();
+        assert!(store.find_by_index("store_test_serial", "SN-1").await.unwrap().is_none());
+        assert_eq!(store.find_by_index("store_test_serial", "SN-2").await.unwrap().as_ref(), Some(&device));
+
+        let mut tracked = store.load("7").await.unwrap().unwrap();
+        tracked.serial = "SN-3".to_string();
+        store.save_changes(&mut tracked).await.unwrap();
+        assert!(store.find_by_index("store_test_serial", "SN-2").await.unwrap().is_none());
+        assert_eq!(store.find_by_index("store_test_serial", "SN-3").await.unwrap().as_ref(), Some(&*tracked));
+
+        assert!(store.delete("7").await.unwrap());
+        let mut conn = client.get_async_connection().await.unwrap();
+        let index: Option<String> = conn.get("store_test_serial:SN-3").await.unwrap();
+        assert!(index.is_none());
+        assert!(!store.delete("7").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_put_refuses_an_index_key_another_entity_holds() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut store = EntityStore::<Device>::new(&client).await.unwrap();
+        let holder = Device { id: 10, serial: "SN-TAKEN".to_string() };
+        store.put(&holder).await.unwrap();
+
+        let err = store.put(&Device { id: 11, serial: "SN-TAKEN".to_string() }).await.unwrap_err();
+        assert!(err.to_string().contains("store_test_serial:SN-TAKEN is already taken"), "{}", err);
+        assert!(store.get("11").await.unwrap().is_none());
+        assert_eq!(store.find_by_index("store_test_serial", "SN-TAKEN").await.unwrap().as_ref(), Some(&holder));
+
+        assert!(store.delete("10").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_puts_leave_no_stale_index() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut stores = Vec::new();
+        for _ in 0..8 {
+            stores.push(EntityStore::<Device>::new(&client).await.unwrap());
+        }
+        let puts = stores.iter_mut().enumerate().map(|(n, store)| async move {
+            store.put(&Device { id: 8, serial: format!("RACE-{}", n) }).await.unwrap();
+        });
+        futures::future::join_all(puts).await;
+
+        let mut store = EntityStore::<Device>::new(&client).await.unwrap();
+        let device = store.get("8").await.unwrap().unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        for n in 0..8 {
+            let serial = format!("RACE-{}", n);
+            let index: Option<String> = conn.get(format!("store_test_serial:{}", serial)).await.unwrap();
+            assert_eq!(index.is_some(), serial == device.serial, "{}", serial);
+        }
+        assert!(store.delete("8").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_save_changes_writes_only_changed_fields() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut store = EntityStore::<User>::new(&client).await.unwrap();
+        let user = User::new("storedirty".to_string(), "storedirty@example.com".to_string(), "Amy Pond".to_string());
+        let id = user.entity_id();
+        store.put(&user).await.unwrap();
+        let mut tracked = store.load(&id).await.unwrap().unwrap();
+        assert_eq!(store.save_changes(&mut tracked).await.unwrap(), SaveStats::default());
+
+        // A write the tracked copy doesn't know about survives a partial save
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.hset(user.redis_key(), "full_name", "\"Amelia Pond\"").await.unwrap();
+        tracked.login_count = 2;
+        tracked.city = Some("Leadworth".to_string());
+        assert_eq!(store.save_changes(&mut tracked).await.unwrap().fields, 2);
+        let loaded = store.get(&id).await.unwrap().unwrap();
+        assert_eq!((loaded.login_count, loaded.city.as_deref(), loaded.full_name.as_str()), (2, Some("Leadworth"), "Amelia Pond"));
+
+        // ...but a changed field someone else wrote since fails it
+        let _: () = conn.hset(user.redis_key(), "login_count", "5").await.unwrap();
+        tracked.login_count = 3;
+        assert!(store.save_changes(&mut tracked).await.is_err());
+
+        assert!(store.delete(&id).await.unwrap());
+        tracked.city = None;
+        assert!(store.save_changes(&mut tracked).await.is_err());
+        let exists: bool = conn.exists(user.redis_key()).await.unwrap();
+        assert!(!exists);
+    }
+}
@@ -0,0 +1,652 @@
+use super::fields::{from_fields, to_fields, FieldChanges, Fields, SaveStats, Tracked};
+use super::store::{warn_if_invalid, ChangeOp, Entity, EntityStore, StoreMiddleware};
+use super::validation::{is_plausible_email, ValidationReport};
+use crate::models::User;
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::RedisConnection;
+use chrono::Utc;
+use redis::{AsyncCommands, Script};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Soft-deleted users, scored by when their trash copy expires.
+pub const TRASH_INDEX: &str = "trash:users";
+
+/// Live user ids scored by `created_at` in ms; the order of list views.
+pub const LIST_INDEX: &str = "users:by_created";
+
+/// KEYS: user key, username index, email index, list index, previous
+/// username index, previous email index
+/// ARGV: id, created_at ms, mode (create, update or save), how many fields
+/// the hash had when the caller read it ('' to check only the fields
+/// listed next), how many fields to check, those fields and the values the
+/// caller read, how many fields to set, those fields and their values, then
+/// fields to remove
+/// Returns 1, 0 if a checked field changed since the caller read it, -1/-2
+/// when the username/email belongs to another user, and -3/-4 when
+/// creating a user that exists or updating one that doesn't.
+const SAVE_SCRIPT: &str = r#"
+local exists = redis.call('EXISTS', KEYS[1]) == 1
+if ARGV[3] == 'create' and exists then
+    return -3
+end
+if ARGV[3] == 'update' and not exists then
+    return -4
+end
+if ARGV[4] ~= '' and redis.call('HLEN', KEYS[1]) ~= tonumber(ARGV[4]) then
+    return 0
+end
+local checks = tonumber(ARGV[5])
+for i = 6, 5 + 2 * checks, 2 do
+    if redis.call('HGET', KEYS[1], ARGV[i]) ~= ARGV[i + 1] then
+        return 0
+    end
+end
+for i = 2, 3 do
+    local owner = redis.call('GET', KEYS[i])
+    if owner and owner ~= ARGV[1] then
+        return -i + 1
+    end
+end
+for i = 5, 6 do
+    if KEYS[i] ~= KEYS[2] and KEYS[i] ~= KEYS[3] and redis.call('GET', KEYS[i]) == ARGV[1] then
+        redis.call('DEL', KEYS[i])
+    end
+end
+local at = 6 + 2 * checks
+local set = tonumber(ARGV[at])
+if set > 0 then
+    redis.call('HSET', KEYS[1], unpack(ARGV, at + 1, at + 2 * set))
+end
+if #ARGV > at + 2 * set then
+    redis.call('HDEL', KEYS[1], unpack(ARGV, at + 2 * set + 1))
+end
+redis.call('SET', KEYS[2], ARGV[1])
+redis.call('SET', KEYS[3], ARGV[1])
+redis.call('ZADD', KEYS[4], ARGV[2], ARGV[1])
+return 1
+"#;
+
+/// KEYS: list index
+/// ARGV: cursor score, cursor id (both empty for the first page), page size
+/// Returns the ids of the page. Resumes right after the cursor id, or after
+/// its score if that user has been deleted since.
+const PAGE_SCRIPT: &str = r#"
+local start = 0
+if ARGV[2] ~= '' then
+    local rank = redis.call('ZRANK', KEYS[1], ARGV[2])
+    if rank then
+        start = rank + 1
+    else
+        start = redis.call('ZCOUNT', KEYS[1], '-inf', ARGV[1])
+    end
+end
+return redis.call('ZRANGE', KEYS[1], start, start + tonumber(ARGV[3]) - 1, 'WITHSCORES')
+"#;
+
+/// KEYS: user key, trash key, username index, email index, trash index, list index
+/// ARGV: id, ttl seconds, expiry ms, how many fields the user had when the
+/// caller read it, then those fields and their values
+/// Returns 1 when moved to the trash, 0 if the user changed or vanished.
+const SOFT_DELETE_SCRIPT: &str = r#"
+local read = tonumber(ARGV[4])
+if redis.call('HLEN', KEYS[1]) ~= read then
+    return 0
+end
+for i = 5, 4 + 2 * read, 2 do
+    if redis.call('HGET', KEYS[1], ARGV[i]) ~= ARGV[i + 1] then
+        return 0
+    end
+end
+redis.call('RENAME', KEYS[1], KEYS[2])
+redis.call('EXPIRE', KEYS[2], ARGV[2])
+for i = 3, 4 do
+    if redis.call('GET', KEYS[i]) == ARGV[1] then
+        redis.call('DEL', KEYS[i])
+    end
+end
+redis.call('ZADD', KEYS[5], ARGV[3], ARGV[1])
+redis.call('ZREM', KEYS[6], ARGV[1])
+return 1
+"#;
+
+/// KEYS: trash key, user key, username index, email index, trash index, list index
+/// ARGV: id, created_at ms, how many fields the trash copy had when the
+/// caller read it, then those fields and their values
+/// Returns 1 when restored, 0 if no longer in the trash, -1 if the id is
+/// live again and -2/-3 when the username/email was taken meanwhile.
+const RESTORE_SCRIPT: &str = r#"
+local read = tonumber(ARGV[3])
+if redis.call('HLEN', KEYS[1]) ~= read then
+    return 0
+end
+for i = 4, 3 + 2 * read, 2 do
+    if redis.call('HGET', KEYS[1], ARGV[i]) ~= ARGV[i + 1] then
+        return 0
+    end
+end
+if redis.call('EXISTS', KEYS[2]) == 1 then
+    return -1
+end
+for i = 3, 4 do
+    local owner = redis.call('GET', KEYS[i])
+    if owner and owner ~= ARGV[1] then
+        return -i + 1
+    end
+end
+redis.call('RENAME', KEYS[1], KEYS[2])
+redis.call('PERSIST', KEYS[2])
+redis.call('SET', KEYS[3], ARGV[1])
+redis.call('SET', KEYS[4], ARGV[1])
+redis.call('ZREM', KEYS[5], ARGV[1])
+redis.call('ZADD', KEYS[6], ARGV[2], ARGV[1])
+return 1
+"#;
+
+pub fn user_key(id: Uuid) -> String {
+    format!("user:{}", id)
+}
+
+pub fn trash_key(id: Uuid) -> String {
+    format!("trash:user:{}", id)
+}
+
+/// `User`'s [`Entity::validate`], named in its `#[redis(validate)]`.
+pub fn validate_user(user: &User) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    report
+        .check(!user.username.trim().is_empty(), "username", "is empty")
+        .check(!user.username.contains(char::is_whitespace), "username", format!("{:?} contains whitespace", user.username))
+        .check(is_plausible_email(&user.email), "email", format!("{:?} is not an email address", user.email))
+        .check(!user.full_name.trim().is_empty(), "full_name", "is empty");
+    if let Some(age) = user.age {
+        report.check((13..=120).contains(&age), "age", format!("{} is outside 13..=120", age));
+    }
+    if let Some(last_login) = user.last_login {
+        report.check(last_login >= user.created_at, "last_login", "is before created_at");
+    }
+    report
+}
+
+/// Position in the list view: the last user of the previous page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    pub created_ms: i64,
+    pub id: Uuid,
+}
+
+impl fmt::Display for PageCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.created_ms, self.id)
+    }
+}
+
+impl FromStr for PageCursor {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || DemoError::Configuration(format!("Invalid page cursor: {}", s));
+        let (created_ms, id) = s.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            created_ms: created_ms.parse().map_err(|_| invalid())?,
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserPage {
+    pub users: Vec<User>,
+    /// `None` once the last page has been returned.
+    pub next: Option<PageCursor>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RestoreOutcome {
+    Restored(User),
+    NotInTrash,
+    AlreadyLive,
+    /// Another user took this username or email while it was in the trash.
+    Conflict { index_key: String },
+}
+
+/// Maps a save/restore script's conflict reply to the index key involved.
+fn conflicting_index(user: &User, reply: i64) -> Result<String> {
+    match reply {
+        -1 => Ok(user.username_index_key()),
+        -2 => Ok(user.email_index_key()),
+        other => Err(DemoError::Demo(format!("Unexpected repository script reply: {}", other))),
+    }
+}
+
+/// Users stored as hashes at `user:<id>`, one field per `User` field, with
+/// `username:` and `email:` index keys holding the id. The index keys are
+/// kept in step with the hash's own `username` and `email` fields by the
+/// same script that writes them. Deletes are soft: the hash moves to
+/// `trash:user:<id>` for `trash_ttl` and can be restored until then.
+/// Every change is reported to the middleware of the underlying store.
+pub struct UserRepository {
+    conn: RedisConnection,
+    store: EntityStore<User>,
+    trash_ttl: Duration,
+    save: Script,
+    soft_delete: Script,
+    restore: Script,
+    page: Script,
+}
+
+impl UserRepository {
+    pub async fn new(client: &RedisClient, trash_ttl: Duration) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            store: EntityStore::new(client).await?,
+            trash_ttl,
+            save: Script::new(SAVE_SCRIPT),
+            soft_delete: Script::new(SOFT_DELETE_SCRIPT),
+            restore: Script::new(RESTORE_SCRIPT),
+            page: Script::new(PAGE_SCRIPT),
+        })
+    }
+
+    pub fn with_middleware(mut self, middleware: Arc<dyn StoreMiddleware>) -> Self {
+        self.store = self.store.with_middleware(middleware);
+        self
+    }
+
+    /// Who subsequent changes are attributed to.
+    pub fn set_actor(&mut self, actor: &str) {
+        self.store.set_actor(actor);
+    }
+
+    /// Writes the user and both indexes atomically, refusing a username or
+    /// email that belongs to someone else. Index keys left behind by a
+    /// changed username or email are dropped.
+    pub async fn save(&mut self, user: &User) -> Result<()> {
+        self.write(user, "save").await
+    }
+
+    /// Like [`save`](Self::save), but fails if the id is already taken.
+    pub async fn create(&mut self, user: &User) -> Result<()> {
+        self.write(user, "create").await
+    }
+
+    /// Like [`save`](Self::save), but fails if the user doesn't exist.
+    pub async fn update(&mut self, user: &User) -> Result<()> {
+        self.write(user, "update").await
+    }
+
+    async fn write(&mut self, user: &User, mode: &str) -> Result<()> {
+        let id = user.id.to_string();
+        user.validate().into_result(User::KIND, &id)?;
+        let before = self.store.get_fields(&id).await?;
+        let previous = match before.is_empty() {
+            true => None,
+            false => Some(from_fields::<User>(&before)?),
+        };
+        let after = to_fields(user)?;
+        let changes = FieldChanges::between(&before, &after);
+        self.run_save(user, previous.as_ref().unwrap_or(user), mode, Some(before.len()), &before, &changes).await?;
+        let op = if before.is_empty() { ChangeOp::Create } else { ChangeOp::Update };
+        self.store.record(op, &id, Some(&before).filter(|before| !before.is_empty()), Some(&after)).await
+    }
+
+    /// Loads a user for [`save_changes`](Self::save_changes).
+    pub async fn load(&mut self, id: Uuid) -> Result<Option<Tracked<User>>> {
+        self.store.load(&id.to_string()).await
+    }
+
+    /// Writes only the fields changed since the user was loaded or last
+    /// saved, and nothing at all if none did. Changes others made to the
+    /// remaining fields meanwhile are kept; a changed field, the username
+    /// or the email that no longer holds what was loaded fails the save.
+    pub async fn save_changes(&mut self, tracked: &mut Tracked<User>) -> Result<SaveStats> {
+        let current = to_fields(&tracked.entity)?;
+        let changes = FieldChanges::between(&tracked.saved, &current);
+        if changes.is_empty() {
+            return Ok(SaveStats::default());
+        }
+        let id = tracked.entity.id.to_string();
+        tracked.entity.validate().into_result(User::KIND, &id)?;
+        let previous: User = from_fields(&tracked.saved)?;
+        let touched: Vec<&String> = changes.set.iter().map(|(field, _)| field).chain(&changes.removed).collect();
+        let checked: Fields = tracked
+            .saved
+            .iter()
+            .filter(|(field, _)| touched.contains(field) || ["username", "email"].contains(&field.as_str()))
+            .map(|(field, value)| (field.clone(), value.clone()))
+            .collect();
+        self.run_save(&tracked.entity, &previous, "update", None, &checked, &changes).await?;
+        self.store.record(ChangeOp::Update, &id, Some(&tracked.saved), Some(&current)).await?;
+        tracked.saved = current;
+        Ok(SaveStats { fields: changes.set.len() + changes.removed.len(), bytes: changes.bytes() })
+    }
+
+    /// Runs SAVE_SCRIPT for `user`, whose hash held `previous` when read.
+    /// `count` is how many fields it had then, when the whole hash was read.
+    async fn run_save(&mut self, user: &User, previous: &User, mode: &str, count: Option<usize>, checked: &Fields, changes: &FieldChanges) -> Result<()> {
+        let id = user.id.to_string();
+        let reply: i64 = self
+            .save
+            .key(user.redis_key())
+            .key(user.username_index_key())
+            .key(user.email_index_key())
+            .key(LIST_INDEX)
+            .key(previous.username_index_key())
+            .key(previous.email_index_key())
+            .arg(&id)
+            .arg(user.created_at.timestamp_millis())
+            .arg(mode)
+            .arg(count.map(|count| count.to_string()).unwrap_or_default())
+            .arg(checked.len())
+            .arg(checked.iter().collect::<Vec<_>>())
+            .arg(changes.set.len())
+            .arg(&changes.set)
+            .arg(&changes.removed)
+            .invoke_async(&mut self.conn)
+            .await?;
+        match reply {
+            1 => Ok(()),
+            0 => Err(DemoError::Demo(format!("User {} changed while saving, try again", id))),
+            -3 => Err(DemoError::Demo(format!("User {} already exists", id))),
+            -4 => Err(DemoError::Demo(format!("User {} not found", id))),
+            other => Err(DemoError::Demo(format!("{} is already taken", conflicting_index(user, other)?))),
+        }
+    }
+
+    pub async fn get(&mut self, id: Uuid) -> Result<Option<User>> {
+        self.store.get(&id.to_string()).await
+    }
+
+    pub async fn find_by_username(&mut self, username: &str) -> Result<Option<User>> {
+        self.find_by_index(&format!("username:{}", username)).await
+    }
+
+    pub async fn find_by_email(&mut self, email: &str) -> Result<Option<User>> {
+        self.find_by_index(&format!("email:{}", email)).await
+    }
+
+    async fn find_by_index(&mut self, index_key: &str) -> Result<Option<User>> {
+        let id: Option<String> = self.conn.get(index_key).await?;
+        match id.map(|id| Uuid::parse_str(&id)) {
+            Some(Ok(id)) => self.get(id).await,
+            Some(Err(e)) => Err(DemoError::Demo(format!("Invalid id in {}: {}", index_key, e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Up to `n` users in creation order after `cursor`, fetched with one
+    /// script call for the ids and one pipeline of HGETALLs for the users.
+    pub async fn list_page(&mut self, cursor: Option<&PageCursor>, n: usize) -> Result<UserPage> {
+        let (score, id) = cursor.map_or((String::new(), String::new()), |c| (c.created_ms.to_string(), c.id.to_string()));
+        let entries: Vec<(String, i64)> = self
+            .page
+            .key(LIST_INDEX)
+            .arg(score)
+            .arg(id)
+            .arg(n.max(1))
+            .invoke_async(&mut self.conn)
+            .await?;
+        let Some((last_id, last_score)) = entries.last().cloned() else {
+            return Ok(UserPage { users: Vec::new(), next: None });
+        };
+
+        let mut pipe = redis::pipe();
+        for (id, _) in &entries {
+            pipe.hgetall(format!("user:{}", id));
+        }
+        let hashes: Vec<Fields> = pipe.query_async(&mut self.conn).await?;
+        // A user deleted between the two calls simply drops out of the page.
+        let users = hashes
+            .iter()
+            .filter(|fields| !fields.is_empty())
+            .map(from_fields)
+            .collect::<Result<Vec<User>>>()?;
+        users.iter().for_each(warn_if_invalid);
+
+        let next = if entries.len() < n.max(1) {
+            None
+        } else {
+            Some(PageCursor {
+                created_ms: last_score,
+                id: Uuid::parse_str(&last_id).map_err(|e| DemoError::Demo(format!("Invalid id in list index: {}", e)))?,
+            })
+        };
+        Ok(UserPage { users, next })
+    }
+
+    /// Moves the user to the trash and drops its indexes. Returns false if
+    /// there was nothing to delete.
+    pub async fn delete(&mut self, id: Uuid) -> Result<bool> {
+        let fields: Fields = self.conn.hgetall(user_key(id)).await?;
+        if fields.is_empty() {
+            return Ok(false);
+        }
+        let user: User = from_fields(&fields)?;
+        let expires_at = Utc::now().timestamp_millis() + self.trash_ttl.as_millis() as i64;
+        let moved: i64 = self
+            .soft_delete
+            .key(user_key(id))
+            .key(trash_key(id))
+            .key(user.username_index_key())
+            .key(user.email_index_key())
+            .key(TRASH_INDEX)
+            .key(LIST_INDEX)
+            .arg(id.to_string())
+            .arg(self.trash_ttl.as_secs().max(1))
+            .arg(expires_at)
+            .arg(fields.len())
+            .arg(fields.iter().collect::<Vec<_>>())
+            .invoke_async(&mut self.conn)
+            .await?;
+        if moved == 0 {
+            return Err(DemoError::Demo(format!("User {} changed while deleting, try again", id)));
+        }
+        self.store.record(ChangeOp::Delete, &id.to_string(), Some(&fields), None).await?;
+        Ok(true)
+    }
+
+    pub async fn restore(&mut self, id: Uuid) -> Result<RestoreOutcome> {
+        let fields: Fields = self.conn.hgetall(trash_key(id)).await?;
+        if fields.is_empty() {
+            return Ok(RestoreOutcome::NotInTrash);
+        }
+        let user: User = from_fields(&fields)?;
+        let reply: i64 = self
+            .restore
+            .key(trash_key(id))
+            .key(user_key(id))
+            .key(user.username_index_key())
+            .key(user.email_index_key())
+            .key(TRASH_INDEX)
+            .key(LIST_INDEX)
+            .arg(id.to_string())
+            .arg(user.created_at.timestamp_millis())
+            .arg(fields.len())
+            .arg(fields.iter().collect::<Vec<_>>())
+            .invoke_async(&mut self.conn)
+            .await?;
+        match reply {
+            1 => {
+                self.store.record(ChangeOp::Restore, &id.to_string(), None, Some(&fields)).await?;
+                Ok(RestoreOutcome::Restored(user))
+            }
+            0 => Ok(RestoreOutcome::NotInTrash),
+            -1 => Ok(RestoreOutcome::AlreadyLive),
+            other => Ok(RestoreOutcome::Conflict { index_key: conflicting_index(&user, other + 1)? }),
+        }
+    }
+
+    /// Ids in the trash with their expiry in epoch ms, soonest first.
+    pub async fn trashed(&mut self) -> Result<Vec<(Uuid, i64)>> {
+        let entries: Vec<(String, i64)> = self.conn.zrange_withscores(TRASH_INDEX, 0, -1).await?;
+        entries
+            .into_iter()
+            .map(|(id, expires_at)| {
+                Uuid::parse_str(&id)
+                    .map(|id| (id, expires_at))
+                    .map_err(|e| DemoError::Demo(format!("Invalid id in trash index: {}", e)))
+            })
+            .collect()
+    }
+
+    /// Permanently removes trash entries that expired by `now_ms`. The trash
+    /// keys expire on their own; this drops them from the index and deletes
+    /// any copy whose TTL was lost, e.g. through a restore from backup.
+    pub async fn purge_expired(&mut self, now_ms: i64) -> Result<Vec<Uuid>> {
+        let expired: Vec<String> = self.conn.zrangebyscore(TRASH_INDEX, "-inf", now_ms).await?;
+        let mut purged = Vec::new();
+        for id in expired {
+            let Ok(uuid) = Uuid::parse_str(&id) else { continue };
+            let _: () = redis::pipe()
+                .atomic()
+                .del(trash_key(uuid))
+                .ignore()
+                .zrem(TRASH_INDEX, &id)
+                .ignore()
+                .query_async(&mut self.conn)
+                .await?;
+            purged.push(uuid);
+        }
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys() {
+        let id = Uuid::nil();
+        assert_eq!(user_key(id), "user:00000000-0000-0000-0000-000000000000");
+        assert_eq!(trash_key(id), "trash:user:00000000-0000-0000-0000-000000000000");
+    }
+
+    #[test]
+    fn test_derived_entity() {
+        let user = User::new("amy".to_string(), "amy@example.com".to_string(), "Amy".to_string());
+        assert_eq!(User::KIND, "user");
+        assert_eq!(User::TTL, None);
+        assert_eq!(User::key_for(&user.entity_id()), user_key(user.id));
+        assert_eq!(user.index_keys(), [user.username_index_key(), user.email_index_key()]);
+    }
+
+    #[test]
+    fn test_user_validation() {
+        let mut user = User::new("amy".to_string(), "amy@example.com".to_string(), "Amy".to_string());
+        user.age = Some(30);
+        assert!(user.validate().is_valid());
+
+        user.email = "amy.example.com".to_string();
+        user.age = Some(7);
+        user.last_login = Some(user.created_at - chrono::Duration::days(1));
+        let fields: Vec<&str> = user.validate().issues.iter().map(|issue| issue.field).collect();
+        assert_eq!(fields, ["email", "age", "last_login"]);
+    }
+
+    #[test]
+    fn test_page_cursor_round_trip() {
+        let cursor = PageCursor { created_ms: 1_700_000_000_000, id: Uuid::nil() };
+        let encoded = cursor.to_string();
+        assert_eq!(encoded, "1700000000000:00000000-0000-0000-0000-000000000000");
+        assert_eq!(encoded.parse::<PageCursor>().unwrap(), cursor);
+        assert!("nope".parse::<PageCursor>().is_err());
+        assert!("12:not-a-uuid".parse::<PageCursor>().is_err());
+    }
+
+    #[test]
+    fn test_conflicting_index() {
+        let user = User::new("amy".to_string(), "amy@example.com".to_string(), "Amy".to_string());
+        assert_eq!(conflicting_index(&user, -1).unwrap(), "username:amy");
+        assert_eq!(conflicting_index(&user, -2).unwrap(), "email:amy@example.com");
+        assert!(conflicting_index(&user, 7).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_and_restore() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut repo = UserRepository::new(&client, Duration::from_secs(60)).await.unwrap();
+        let user = User::new("softdel".to_string(), "softdel@example.com".to_string(), "Soft Del".to_string());
+        repo.save(&user).await.unwrap();
+
+        assert!(repo.delete(user.id).await.unwrap());
+        assert!(repo.get(user.id).await.unwrap().is_none());
+        assert!(repo.find_by_username("softdel").await.unwrap().is_none());
+        assert!(repo.trashed().await.unwrap().iter().any(|(id, _)| *id == user.id));
+
+        assert_eq!(repo.restore(user.id).await.unwrap(), RestoreOutcome::Restored(user.clone()));
+        assert_eq!(repo.find_by_username("softdel").await.unwrap().unwrap().id, user.id);
+        assert_eq!(repo.restore(user.id).await.unwrap(), RestoreOutcome::NotInTrash);
+
+        let page = repo.list_page(None, 1000).await.unwrap();
+        assert!(page.users.iter().any(|u| u.id == user.id));
+
+        repo.delete(user.id).await.unwrap();
+        let page = repo.list_page(None, 1000).await.unwrap();
+        assert!(page.users.iter().all(|u| u.id != user.id));
+        let purged = repo.purge_expired(i64::MAX).await.unwrap();
+        assert!(purged.contains(&user.id));
+        assert_eq!(repo.restore(user.id).await.unwrap(), RestoreOutcome::NotInTrash);
+    }
+
+    #[tokio::test]
+    async fn test_create_update_and_lookups() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut repo = UserRepository::new(&client, Duration::from_secs(60)).await.unwrap();
+        let mut user = User::new("crud".to_string(), "crud@example.com".to_string(), "Crud".to_string());
+        assert!(repo.update(&user).await.is_err());
+        repo.create(&user).await.unwrap();
+        assert!(repo.create(&user).await.is_err());
+        assert_eq!(repo.find_by_email("crud@example.com").await.unwrap().unwrap().id, user.id);
+
+        user.email = "crud2@example.com".to_string();
+        repo.update(&user).await.unwrap();
+        assert!(repo.find_by_email("crud@example.com").await.unwrap().is_none());
+        assert_eq!(repo.find_by_email("crud2@example.com").await.unwrap(), Some(user.clone()));
+
+        let mut conn = client.get_async_connection().await.unwrap();
+        let username: String = conn.hget(user_key(user.id), "username").await.unwrap();
+        assert_eq!(username, "\"crud\"");
+
+        repo.delete(user.id).await.unwrap();
+        repo.purge_expired(i64::MAX).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_save_changes_writes_only_changed_fields() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut repo = UserRepository::new(&client, Duration::from_secs(60)).await.unwrap();
+        let user = User::new("dirty".to_string(), "dirty@example.com".to_string(), "Amy Pond".to_string());
+        repo.create(&user).await.unwrap();
+        let mut tracked = repo.load(user.id).await.unwrap().unwrap();
+        assert_eq!(repo.save_changes(&mut tracked).await.unwrap(), SaveStats::default());
+
+        // A write the tracked copy doesn't know about survives a partial save
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.hset(user_key(user.id), "full_name", "\"Amelia Pond\"").await.unwrap();
+        tracked.login_count = 3;
+        assert_eq!(repo.save_changes(&mut tracked).await.unwrap().fields, 1);
+        let loaded = repo.get(user.id).await.unwrap().unwrap();
+        assert_eq!((loaded.login_count, loaded.full_name.as_str()), (3, "Amelia Pond"));
+
+        // A changed email moves the index with it
+        tracked.email = "dirty2@example.com".to_string();
+        repo.save_changes(&mut tracked).await.unwrap();
+        assert!(repo.find_by_email("dirty@example.com").await.unwrap().is_none());
+        assert_eq!(repo.find_by_email("dirty2@example.com").await.unwrap().unwrap().id, user.id);
+
+        // ...but not when someone else changed it since the load
+        let mut stale = repo.load(user.id).await.unwrap().unwrap();
+        let _: () = conn.hset(user_key(user.id), "email", "\"dirty3@example.com\"").await.unwrap();
+        stale.email = "dirty4@example.com".to_string();
+        assert!(repo.save_changes(&mut stale).await.is_err());
+        assert!(repo.find_by_email("dirty4@example.com").await.unwrap().is_none());
+
+        repo.delete(user.id).await.unwrap();
+        repo.purge_expired(i64::MAX).await.unwrap();
+        let _: () = conn.del("email:dirty2@example.com").await.unwrap();
+    }
+}
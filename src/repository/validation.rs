@@ -0,0 +1,89 @@
+use crate::{DemoError, Result};
+use std::fmt;
+
+/// A rule one field broke.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// What [`Entity::validate`](super::Entity::validate) found wrong with a
+/// value; empty when it is valid.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Records `message` against `field` unless `ok`.
+    pub fn check(&mut self, ok: bool, field: &'static str, message: impl Into<String>) -> &mut Self {
+        if !ok {
+            self.issues.push(ValidationIssue { field, message: message.into() });
+        }
+        self
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// An error naming the entity and every issue, if there are any.
+    pub fn into_result(self, kind: &str, id: &str) -> Result<()> {
+        match self.is_valid() {
+            true => Ok(()),
+            false => Err(DemoError::Validation(format!("{} {}: {}", kind, id, self))),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (n, issue) in self.issues.iter().enumerate() {
+            if n > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{} {}", issue.field, issue.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// `local@domain.tld` with no spaces: catches typos and hand edits, not a
+/// full RFC 5322 parser.
+pub fn is_plausible_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else { return false };
+    !local.is_empty()
+        && !email.contains(char::is_whitespace)
+        && !domain.contains('@')
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|part| !part.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plausible_emails() {
+        for email in ["amy@example.com", "a.b+tag@mail.example.co.uk"] {
+            assert!(is_plausible_email(email), "{}", email);
+        }
+        for email in ["", "amy", "amy@", "@example.com", "amy@example", "amy@@example.com", "amy@example..com", "amy pond@example.com"] {
+            assert!(!is_plausible_email(email), "{}", email);
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let mut report = ValidationReport::default();
+        report.check(true, "username", "is empty");
+        assert!(report.is_valid());
+        assert!(report.clone().into_result("user", "42").is_ok());
+
+        report.check(false, "email", "is not an address").check(false, "age", "200 is over 120");
+        assert_eq!(report.to_string(), "email is not an address; age 200 is over 120");
+        let err = report.into_result("user", "42").unwrap_err();
+        assert_eq!(err.to_string(), "Validation failed: user 42: email is not an address; age 200 is over 120");
+    }
+}
@@ -0,0 +1,222 @@
+/// One RESP value. The first eight variants are RESP2, which every server
+/// speaks; the rest arrive only after `HELLO 3` switches a connection to
+/// RESP3.
+///
+/// Encoding writes what a server would send and does no validation:
+/// a `Simple` or `Error` holding CR or LF, or a `Verbatim` format that is
+/// not three bytes, produces bytes that won't parse back.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    /// `+OK\r\n`
+    Simple(String),
+    /// `-ERR unknown command\r\n`
+    Error(String),
+    /// `:42\r\n`
+    Integer(i64),
+    /// `$5\r\nhello\r\n`, binary safe
+    Bulk(Vec<u8>),
+    /// `$-1\r\n`, RESP2's nil for a missing value
+    NullBulk,
+    /// `*2\r\n...`
+    Array(Vec<Frame>),
+    /// `*-1\r\n`, RESP2's nil for a missing aggregate
+    NullArray,
+    /// `_\r\n`, RESP3's single nil
+    Null,
+    /// `,3.14\r\n`, also `inf`, `-inf` and `nan`
+    Double(f64),
+    /// `#t\r\n` or `#f\r\n`
+    Boolean(bool),
+    /// `(3492890328409238509324850943850943825024385\r\n`, kept as digits
+    BigNumber(String),
+    /// `!21\r\nSYNTAX invalid syntax\r\n`, a binary-safe error
+    BulkError(Vec<u8>),
+    /// `=15\r\ntxt:Some string\r\n`: text with a three-letter format
+    Verbatim { format: String, text: Vec<u8> },
+    /// `%1\r\n+key\r\n:1\r\n`, pairs in the order received
+    Map(Vec<(Frame, Frame)>),
+    /// `~2\r\n...`
+    Set(Vec<Frame>),
+    /// `|1\r\n...`: metadata about the reply that follows it, which is
+    /// a separate frame
+    Attribute(Vec<(Frame, Frame)>),
+    /// `>3\r\n...`: out-of-band data such as pub/sub messages
+    Push(Vec<Frame>),
+}
+
+impl Frame {
+    /// A command as clients send it: an array of bulk strings.
+    pub fn command<I, A>(args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: AsRef<[u8]>,
+    {
+        Frame::Array(args.into_iter().map(|arg| Frame::Bulk(arg.as_ref().to_vec())).collect())
+    }
+
+    /// The arguments of an array of bulk strings, i.e. of a command.
+    pub fn as_command(&self) -> Option<Vec<Vec<u8>>> {
+        let Frame::Array(items) = self else {
+            return None;
+        };
+        items
+            .iter()
+            .map(|item| match item {
+                Frame::Bulk(arg) => Some(arg.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The type byte that starts the frame on the wire.
+    pub fn prefix(&self) -> u8 {
+        match self {
+            Frame::Simple(_) => b'+',
+            Frame::Error(_) => b'-',
+            Frame::Integer(_) => b':',
+            Frame::Bulk(_) | Frame::NullBulk => b'$',
+            Frame::Array(_) | Frame::NullArray => b'*',
+            Frame::Null => b'_',
+            Frame::Double(_) => b',',
+            Frame::Boolean(_) => b'#',
+            Frame::BigNumber(_) => b'(',
+            Frame::BulkError(_) => b'!',
+            Frame::Verbatim { .. } => b'=',
+            Frame::Map(_) => b'%',
+            Frame::Set(_) => b'~',
+            Frame::Attribute(_) => b'|',
+            Frame::Push(_) => b'>',
+        }
+    }
+
+    /// What the frame is, as the protocol spec names it.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Frame::Simple(_) => "simple string",
+            Frame::Error(_) => "simple error",
+            Frame::Integer(_) => "integer",
+            Frame::Bulk(_) => "bulk string",
+            Frame::NullBulk => "null bulk string",
+            Frame::Array(_) => "array",
+            Frame::NullArray => "null array",
+            Frame::Null => "null",
+            Frame::Double(_) => "double",
+            Frame::Boolean(_) => "boolean",
+            Frame::BigNumber(_) => "big number",
+            Frame::BulkError(_) => "bulk error",
+            Frame::Verbatim { .. } => "verbatim string",
+            Frame::Map(_) => "map",
+            Frame::Set(_) => "set",
+            Frame::Attribute(_) => "attribute",
+            Frame::Push(_) => "push",
+        }
+    }
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let line = |out: &mut Vec<u8>, text: &str| {
+            out.push(self.prefix());
+            out.extend_from_slice(text.as_bytes());
+            out.extend_from_slice(b"\r\n");
+        };
+        let payload = |out: &mut Vec<u8>, bytes: &[u8]| {
+            out.extend_from_slice(bytes);
+            out.extend_from_slice(b"\r\n");
+        };
+        match self {
+            Frame::Simple(text) | Frame::Error(text) | Frame::BigNumber(text) => line(out, text),
+            Frame::Integer(n) => line(out, &n.to_string()),
+            Frame::NullBulk | Frame::NullArray => line(out, "-1"),
+            Frame::Null => line(out, ""),
+            Frame::Double(value) => line(out, &format_double(*value)),
+            Frame::Boolean(value) => line(out, if *value { "t" } else { "f" }),
+            Frame::Bulk(bytes) | Frame::BulkError(bytes) => {
+                line(out, &bytes.len().to_string());
+                payload(out, bytes);
+            }
+            Frame::Verbatim { format, text } => {
+                line(out, &(format.len() + 1 + text.len()).to_string());
+                out.extend_from_slice(format.as_bytes());
+                out.push(b':');
+                payload(out, text);
+            }
+            Frame::Array(items) | Frame::Set(items) | Frame::Push(items) => {
+                line(out, &items.len().to_string());
+                items.iter().for_each(|item| item.encode(out));
+            }
+            Frame::Map(pairs) | Frame::Attribute(pairs) => {
+                line(out, &pairs.len().to_string());
+                for (key, value) in pairs {
+                    key.encode(out);
+                    value.encode(out);
+                }
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+}
+
+/// `inf`, `-inf` and `nan` are spelled out; finite values use Rust's
+/// shortest representation that parses back to the same bits.
+pub(crate) fn format_double(value: f64) -> String {
+    match value {
+        v if v.is_nan() => "nan".to_string(),
+        v if v == f64::INFINITY => "inf".to_string(),
+        v if v == f64::NEG_INFINITY => "-inf".to_string(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_resp2() {
+        let reply = Frame::Array(vec![
+            Frame::Simple("OK".to_string()),
+            Frame::Error("ERR no".to_string()),
+            Frame::Integer(-7),
+            Frame::Bulk(b"hi".to_vec()),
+            Frame::NullBulk,
+            Frame::NullArray,
+            Frame::Array(Vec::new()),
+        ]);
+        assert_eq!(reply.to_bytes(), b"*7\r\n+OK\r\n-ERR no\r\n:-7\r\n$2\r\nhi\r\n$-1\r\n*-1\r\n*0\r\n");
+    }
+
+    #[test]
+    fn test_encode_resp3() {
+        let cases: Vec<(Frame, &[u8])> = vec![
+            (Frame::Null, b"_\r\n"),
+            (Frame::Double(1.5), b",1.5\r\n"),
+            (Frame::Double(f64::NEG_INFINITY), b",-inf\r\n"),
+            (Frame::Double(f64::NAN), b",nan\r\n"),
+            (Frame::Boolean(false), b"#f\r\n"),
+            (Frame::BigNumber("-123456789012345678901234567890".to_string()), b"(-123456789012345678901234567890\r\n"),
+            (Frame::BulkError(b"SYNTAX bad".to_vec()), b"!10\r\nSYNTAX bad\r\n"),
+            (Frame::Verbatim { format: "txt".to_string(), text: b"Some string".to_vec() }, b"=15\r\ntxt:Some string\r\n"),
+            (Frame::Map(vec![(Frame::Simple("a".to_string()), Frame::Integer(1))]), b"%1\r\n+a\r\n:1\r\n"),
+            (Frame::Set(vec![Frame::Boolean(true)]), b"~1\r\n#t\r\n"),
+            (Frame::Attribute(vec![(Frame::Simple("ttl".to_string()), Frame::Integer(3))]), b"|1\r\n+ttl\r\n:3\r\n"),
+            (Frame::Push(vec![Frame::Bulk(b"message".to_vec())]), b">1\r\n$7\r\nmessage\r\n"),
+        ];
+        for (frame, bytes) in cases {
+            assert_eq!(frame.to_bytes(), bytes, "{:?}", frame);
+            assert_eq!(frame.prefix(), bytes[0]);
+        }
+    }
+
+    #[test]
+    fn test_commands() {
+        let command = Frame::command(["SET", "k", "v"]);
+        assert_eq!(command.to_bytes(), b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n");
+        assert_eq!(command.as_command(), Some(vec![b"SET".to_vec(), b"k".to_vec(), b"v".to_vec()]));
+        assert_eq!(Frame::Array(vec![Frame::Integer(1)]).as_command(), None);
+        assert_eq!(Frame::Bulk(Vec::new()).as_command(), None);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod frame;
+pub mod parse;
+
+pub use frame::Frame;
+pub use parse::{parse_all, ParseError, MAX_DEPTH};
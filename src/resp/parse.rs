@@ -0,0 +1,375 @@
+use super::frame::Frame;
+
+/// Aggregates nested deeper than this are rejected rather than risking the
+/// stack on hostile input.
+pub const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Invalid RESP at byte {offset}: {message}")]
+pub struct ParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+type Parsed<T> = Result<Option<(T, usize)>, ParseError>;
+
+struct Parser<'a> {
+    buf: &'a [u8],
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, offset: usize, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError { offset, message: message.into() })
+    }
+
+    /// The line starting at `at`, without its CRLF, and where the next
+    /// line starts.
+    fn line(&self, at: usize) -> Parsed<&[u8]> {
+        let rest = &self.buf[at..];
+        let Some(end) = rest.iter().position(|b| *b == b'\n') else {
+            return Ok(None);
+        };
+        if end == 0 || rest[end - 1] != b'\r' {
+            return self.error(at + end, "line not terminated by CRLF");
+        }
+        Ok(Some((&rest[..end - 1], at + end + 1)))
+    }
+
+    fn number(&self, at: usize, text: &[u8]) -> Result<i64, ParseError> {
+        match std::str::from_utf8(text).ok().and_then(|text| text.parse().ok()) {
+            Some(n) => Ok(n),
+            None => self.error(at, format!("'{}' is not an integer", String::from_utf8_lossy(text))),
+        }
+    }
+
+    /// A length or count: `-1` for null, otherwise non-negative.
+    fn size(&self, at: usize, text: &[u8]) -> Result<Option<usize>, ParseError> {
+        match self.number(at, text)? {
+            -1 => Ok(None),
+            n if n < 0 => self.error(at, format!("negative length {}", n)),
+            n => Ok(Some(n as usize)),
+        }
+    }
+
+    fn text(&self, at: usize, bytes: &[u8]) -> Result<String, ParseError> {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Ok(text.to_string()),
+            Err(_) => self.error(at, "not UTF-8"),
+        }
+    }
+
+    /// `len` payload bytes at `at`, followed by CRLF.
+    fn payload(&self, at: usize, len: usize) -> Parsed<&[u8]> {
+        let Some(end) = at.checked_add(len).filter(|end| end.checked_add(2).is_some()) else {
+            return self.error(at, "length overflows");
+        };
+        if self.buf.len() < end + 2 {
+            return Ok(None);
+        }
+        if &self.buf[end..end + 2] != b"\r\n" {
+            return self.error(end, "payload not followed by CRLF");
+        }
+        Ok(Some((&self.buf[at..end], end + 2)))
+    }
+
+    fn items(&self, mut at: usize, count: usize, depth: usize) -> Parsed<Vec<Frame>> {
+        // Each element takes at least three bytes, so a count the buffer
+        // can't hold doesn't get to reserve memory.
+        let mut items = Vec::with_capacity(count.min((self.buf.len() - at) / 3));
+        for _ in 0..count {
+            let Some((item, next)) = self.frame(at, depth + 1)? else {
+                return Ok(None);
+            };
+            items.push(item);
+            at = next;
+        }
+        Ok(Some((items, at)))
+    }
+
+    fn pairs(&self, at: usize, count: usize, depth: usize) -> Parsed<Vec<(Frame, Frame)>> {
+        let Some(doubled) = count.checked_mul(2) else {
+            return self.error(at, "count overflows");
+        };
+        let Some((items, next)) = self.items(at, doubled, depth)? else {
+            return Ok(None);
+        };
+        let mut items = items.into_iter();
+        let mut pairs = Vec::with_capacity(count);
+        while let (Some(key), Some(value)) = (items.next(), items.next()) {
+            pairs.push((key, value));
+        }
+        Ok(Some((pairs, next)))
+    }
+
+    fn frame(&self, at: usize, depth: usize) -> Parsed<Frame> {
+        if depth > MAX_DEPTH {
+            return self.error(at, format!("nested deeper than {}", MAX_DEPTH));
+        }
+        let Some((line, next)) = self.line(at)? else {
+            return Ok(None);
+        };
+        let Some((&kind, body)) = line.split_first() else {
+            return self.error(at, "empty line where a type byte was expected");
+        };
+        let body_at = at + 1;
+        let done = |frame: Frame| Ok(Some((frame, next)));
+        match kind {
+            b'+' => done(Frame::Simple(self.text(body_at, body)?)),
+            b'-' => done(Frame::Error(self.text(body_at, body)?)),
+            b':' => done(Frame::Integer(self.number(body_at, body)?)),
+            b'_' if body.is_empty() => done(Frame::Null),
+            b'_' => self.error(body_at, "null with a body"),
+            b'#' => match body {
+                b"t" => done(Frame::Boolean(true)),
+                b"f" => done(Frame::Boolean(false)),
+                _ => self.error(body_at, "boolean that is neither t nor f"),
+            },
+            b',' => {
+                let text = self.text(body_at, body)?;
+                let value = match text.as_str() {
+                    "inf" | "+inf" => Some(f64::INFINITY),
+                    "-inf" => Some(f64::NEG_INFINITY),
+                    "nan" => Some(f64::NAN),
+                    text => text.parse().ok(),
+                };
+                match value {
+                    Some(value) => done(Frame::Double(value)),
+                    None => self.error(body_at, format!("'{}' is not a double", text)),
+                }
+            }
+            b'(' => {
+                let text = self.text(body_at, body)?;
+                let digits = text.strip_prefix(['-', '+']).unwrap_or(&text);
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return self.error(body_at, format!("'{}' is not a big number", text));
+                }
+                done(Frame::BigNumber(text))
+            }
+            b'$' | b'!' | b'=' => {
+                let Some(len) = self.size(body_at, body)? else {
+                    return match kind {
+                        b'$' => done(Frame::NullBulk),
+                        _ => self.error(body_at, "only bulk strings can be null"),
+                    };
+                };
+                let Some((bytes, next)) = self.payload(next, len)? else {
+                    return Ok(None);
+                };
+                let frame = match kind {
+                    b'$' => Frame::Bulk(bytes.to_vec()),
+                    b'!' => Frame::BulkError(bytes.to_vec()),
+                    _ => match bytes.get(3) {
+                        Some(b':') => Frame::Verbatim { format: self.text(body_at, &bytes[..3])?, text: bytes[4..].to_vec() },
+                        _ => return self.error(body_at, "verbatim string without a 'fmt:' prefix"),
+                    },
+                };
+                Ok(Some((frame, next)))
+            }
+            b'*' | b'~' | b'>' => {
+                let Some(count) = self.size(body_at, body)? else {
+                    return match kind {
+                        b'*' => done(Frame::NullArray),
+                        _ => self.error(body_at, "only arrays can be null"),
+                    };
+                };
+                let Some((items, next)) = self.items(next, count, depth)? else {
+                    return Ok(None);
+                };
+                let frame = match kind {
+                    b'*' => Frame::Array(items),
+                    b'~' => Frame::Set(items),
+                    _ => Frame::Push(items),
+                };
+                Ok(Some((frame, next)))
+            }
+            b'%' | b'|' => {
+                let Some(count) = self.size(body_at, body)? else {
+                    return self.error(body_at, "only arrays can be null");
+                };
+                let Some((pairs, next)) = self.pairs(next, count, depth)? else {
+                    return Ok(None);
+                };
+                Ok(Some((if kind == b'%' { Frame::Map(pairs) } else { Frame::Attribute(pairs) }, next)))
+            }
+            b'?' => self.error(at, "streamed strings and aggregates are not supported"),
+            other => self.error(at, format!("unknown type byte 0x{:02x}", other)),
+        }
+    }
+}
+
+impl Frame {
+    /// Parses the frame at the start of `buf`, returning it and how many
+    /// bytes it took. `Ok(None)` means `buf` ends mid-frame: read more and
+    /// call again with the longer buffer.
+    pub fn parse(buf: &[u8]) -> Result<Option<(Frame, usize)>, ParseError> {
+        Parser { buf }.frame(0, 0)
+    }
+}
+
+/// Every frame in `buf`, which must hold nothing but complete frames, such
+/// as a packed pipeline.
+pub fn parse_all(buf: &[u8]) -> Result<Vec<Frame>, ParseError> {
+    let mut frames = Vec::new();
+    let mut at = 0;
+    while at < buf.len() {
+        match Frame::parse(&buf[at..]) {
+            Ok(Some((frame, used))) => {
+                frames.push(frame);
+                at += used;
+            }
+            Ok(None) => return Err(ParseError { offset: buf.len(), message: "ends mid-frame".to_string() }),
+            Err(error) => return Err(ParseError { offset: at + error.offset, ..error }),
+        }
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn parse(bytes: &[u8]) -> Frame {
+        let (frame, used) = Frame::parse(bytes).unwrap().expect("complete frame");
+        assert_eq!(used, bytes.len());
+        frame
+    }
+
+    fn error(bytes: &[u8]) -> ParseError {
+        Frame::parse(bytes).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_every_type() {
+        assert_eq!(parse(b"+OK\r\n"), Frame::Simple("OK".to_string()));
+        assert_eq!(parse(b"-WRONGTYPE no\r\n"), Frame::Error("WRONGTYPE no".to_string()));
+        assert_eq!(parse(b":-12\r\n"), Frame::Integer(-12));
+        assert_eq!(parse(b"$0\r\n\r\n"), Frame::Bulk(Vec::new()));
+        assert_eq!(parse(b"$4\r\na\r\nb\r\n"), Frame::Bulk(b"a\r\nb".to_vec()));
+        assert_eq!(parse(b"$-1\r\n"), Frame::NullBulk);
+        assert_eq!(parse(b"*-1\r\n"), Frame::NullArray);
+        assert_eq!(parse(b"*2\r\n:1\r\n*1\r\n+x\r\n"), Frame::Array(vec![Frame::Integer(1), Frame::Array(vec![Frame::Simple("x".to_string())])]));
+        assert_eq!(parse(b"_\r\n"), Frame::Null);
+        assert_eq!(parse(b",+inf\r\n"), Frame::Double(f64::INFINITY));
+        assert_eq!(parse(b",1e3\r\n"), Frame::Double(1000.0));
+        assert!(matches!(parse(b",nan\r\n"), Frame::Double(v) if v.is_nan()));
+        assert_eq!(parse(b"#t\r\n"), Frame::Boolean(true));
+        assert_eq!(parse(b"(+42\r\n"), Frame::BigNumber("+42".to_string()));
+        assert_eq!(parse(b"!3\r\nERR\r\n"), Frame::BulkError(b"ERR".to_vec()));
+        assert_eq!(parse(b"=7\r\nmkd:# a\r\n"), Frame::Verbatim { format: "mkd".to_string(), text: b"# a".to_vec() });
+        assert_eq!(parse(b"%1\r\n+k\r\n_\r\n"), Frame::Map(vec![(Frame::Simple("k".to_string()), Frame::Null)]));
+        assert_eq!(parse(b"~0\r\n"), Frame::Set(Vec::new()));
+        assert_eq!(parse(b"|0\r\n"), Frame::Attribute(Vec::new()));
+        assert_eq!(parse(b">2\r\n+a\r\n+b\r\n").prefix(), b'>');
+    }
+
+    #[test]
+    fn test_incomplete_input_asks_for_more() {
+        let bytes = b"*3\r\n$3\r\nGET\r\n%1\r\n+k\r\n,2.5\r\n$-1\r\n";
+        for cut in 0..bytes.len() {
+            assert_eq!(Frame::parse(&bytes[..cut]), Ok(None), "cut at {}", cut);
+        }
+        assert!(Frame::parse(bytes).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_malformed_input_is_rejected() {
+        assert_eq!(error(b"?\r\n").message, "streamed strings and aggregates are not supported");
+        assert_eq!(error(b"@1\r\n").message, "unknown type byte 0x40");
+        assert_eq!(error(b"+OK\n").message, "line not terminated by CRLF");
+        assert_eq!(error(b":12a\r\n").offset, 1);
+        assert_eq!(error(b"$3\r\nabcd\r\n").message, "payload not followed by CRLF");
+        assert_eq!(error(b"$-2\r\n").message, "negative length -2");
+        assert_eq!(error(b"%-1\r\n").message, "only arrays can be null");
+        assert_eq!(error(b"!-1\r\n").message, "only bulk strings can be null");
+        assert_eq!(error(b"#x\r\n").message, "boolean that is neither t nor f");
+        assert_eq!(error(b"(12.5\r\n").message, "'12.5' is not a big number");
+        assert_eq!(error(b"=3\r\ntxt\r\n").message, "verbatim string without a 'fmt:' prefix");
+        assert_eq!(error(b"_x\r\n").message, "null with a body");
+        assert_eq!(error(b"\r\n").message, "empty line where a type byte was expected");
+        assert_eq!(error(b"$18446744073709551615\r\n").message, "'18446744073709551615' is not an integer");
+        // Huge sizes the buffer can't back wait for more bytes rather than
+        // allocating up front.
+        assert_eq!(Frame::parse(b"$9223372036854775807\r\nx"), Ok(None));
+        assert_eq!(Frame::parse(b"*2147483647\r\n:1\r\n"), Ok(None));
+    }
+
+    #[test]
+    fn test_nesting_is_bounded() {
+        let deep = "*1\r\n".repeat(MAX_DEPTH + 1) + ":1\r\n";
+        assert!(error(deep.as_bytes()).message.contains("nested deeper"));
+        let ok = "*1\r\n".repeat(MAX_DEPTH) + ":1\r\n";
+        assert!(Frame::parse(ok.as_bytes()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_parse_all() {
+        let frames = parse_all(b"*1\r\n$4\r\nPING\r\n*2\r\n$4\r\nECHO\r\n$2\r\nhi\r\n").unwrap();
+        assert_eq!(frames, [Frame::command(["PING"]), Frame::command(["ECHO", "hi"])]);
+        assert_eq!(parse_all(b"+OK\r\n+O").unwrap_err().offset, 7);
+        assert_eq!(parse_all(b"+OK\r\n:x\r\n").unwrap_err().offset, 6);
+        assert!(parse_all(b"").unwrap().is_empty());
+    }
+
+    fn line_text() -> impl Strategy<Value = String> {
+        "[^\r\n]{0,12}"
+    }
+
+    fn frames() -> impl Strategy<Value = Frame> {
+        let leaf = prop_oneof![
+            line_text().prop_map(Frame::Simple),
+            line_text().prop_map(Frame::Error),
+            any::<i64>().prop_map(Frame::Integer),
+            prop::collection::vec(any::<u8>(), 0..16).prop_map(Frame::Bulk),
+            Just(Frame::NullBulk),
+            Just(Frame::NullArray),
+            Just(Frame::Null),
+            any::<f64>().prop_filter("nan never equals itself", |v| !v.is_nan()).prop_map(Frame::Double),
+            any::<bool>().prop_map(Frame::Boolean),
+            "-?[0-9]{1,40}".prop_map(Frame::BigNumber),
+            prop::collection::vec(any::<u8>(), 0..16).prop_map(Frame::BulkError),
+            ("[a-z]{3}", prop::collection::vec(any::<u8>(), 0..16)).prop_map(|(format, text)| Frame::Verbatim { format, text }),
+        ];
+        leaf.prop_recursive(4, 32, 6, |inner| {
+            let items = prop::collection::vec(inner.clone(), 0..6);
+            let pairs = prop::collection::vec((inner.clone(), inner), 0..4);
+            prop_oneof![
+                items.clone().prop_map(Frame::Array),
+                items.clone().prop_map(Frame::Set),
+                items.prop_map(Frame::Push),
+                pairs.clone().prop_map(Frame::Map),
+                pairs.prop_map(Frame::Attribute),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_encoding_round_trips(frame in frames()) {
+            let bytes = frame.to_bytes();
+            prop_assert_eq!(Frame::parse(&bytes).unwrap(), Some((frame, bytes.len())));
+        }
+
+        #[test]
+        fn prop_every_prefix_is_incomplete(frame in frames()) {
+            let bytes = frame.to_bytes();
+            for cut in 0..bytes.len() {
+                prop_assert_eq!(Frame::parse(&bytes[..cut]).unwrap(), None);
+            }
+        }
+
+        #[test]
+        fn prop_arbitrary_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..64)) {
+            let _ = Frame::parse(&bytes);
+            let _ = parse_all(&bytes);
+        }
+
+        #[test]
+        fn prop_mutated_frames_never_panic(frame in frames(), at in any::<prop::sample::Index>(), byte in any::<u8>()) {
+            let mut bytes = frame.to_bytes();
+            let at = at.index(bytes.len());
+            bytes[at] = byte;
+            let _ = Frame::parse(&bytes);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{DemoError, Result};
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+/// Upper bound on the search for the next matching minute, so impossible
+/// expressions such as `0 0 31 2 *` fail instead of looping forever.
+const MAX_SEARCH_YEARS: i32 = 5;
+
+/// Standard five-field cron expression (minute hour day-of-month month
+/// day-of-week), evaluated in UTC. Supports `*` (or `?` in the day fields),
+/// lists, ranges, steps, `JAN`-`DEC` and `SUN`-`SAT` names and the
+/// `@hourly`/`@daily`/`@weekly`/`@monthly`/`@yearly` shorthands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    source: String,
+    minutes: BTreeSet<u32>,
+    hours: BTreeSet<u32>,
+    days_of_month: BTreeSet<u32>,
+    months: BTreeSet<u32>,
+    days_of_week: BTreeSet<u32>,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+const MONTH_NAMES: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+const DAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// `names[i]` stands for `min + i`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<BTreeSet<u32>> {
+    let invalid = || DemoError::Configuration(format!("Invalid cron field '{}' (range {}-{})", field, min, max));
+    let value = |text: &str| match names.iter().position(|name| name.eq_ignore_ascii_case(text)) {
+        Some(at) => Ok(min + at as u32),
+        None => text.parse::<u32>().map_err(|_| invalid()),
+    };
+    let mut values = BTreeSet::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (value(start)?, value(end)?)
+        } else {
+            let value = value(range)?;
+            // `5/15` means "from 5 every 15", like `5-max/15`.
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    Ok(values)
+}
+
+/// `?` is Quartz's "no specific value" for a day field, i.e. `*`.
+fn any_day(field: &str) -> &str {
+    if field == "?" {
+        "*"
+    } else {
+        field
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let expanded = match s.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, dom, month, dow] = fields[..] else {
+            return Err(DemoError::Configuration(format!(
+                "Cron expression '{}' must have 5 fields",
+                s
+            )));
+        };
+
+        let days_of_month = parse_field(any_day(dom), 1, 31, &[])?;
+        let mut days_of_week = parse_field(any_day(dow), 0, 7, &DAY_NAMES)?;
+        // Both 0 and 7 mean Sunday.
+        if days_of_week.remove(&7) {
+            days_of_week.insert(0);
+        }
+        Ok(Self {
+            source: s.trim().to_string(),
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            // Restricted by what the field allows, not how it is spelled,
+            // so `*/1` and `1-31` leave the other day field in charge.
+            dom_restricted: days_of_month.len() < 31,
+            dow_restricted: days_of_week.len() < 7,
+            days_of_month,
+            months: parse_field(month, 1, 12, &MONTH_NAMES)?,
+            days_of_week,
+        })
+    }
+}
+
+impl std::fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl CronSchedule {
+    /// Classic cron rule: when both day fields are restricted a day matches
+    /// if *either* does; otherwise both must match.
+    fn day_matches(&self, time: &DateTime<Utc>) -> bool {
+        let dom = self.days_of_month.contains(&time.day());
+        let dow = self.days_of_week.contains(&time.weekday().num_days_from_sunday());
+        if self.dom_restricted && self.dow_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+
+    /// First matching minute strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after.year() + MAX_SEARCH_YEARS;
+
+        while time.year() <= limit {
+            if !self.months.contains(&time.month()) {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+                continue;
+            }
+            if !self.day_matches(&time) {
+                time = (time + Duration::days(1)).with_hour(0)?.with_minute(0)?;
+                continue;
+            }
+            if !self.hours.contains(&time.hour()) {
+                time = (time + Duration::hours(1)).with_minute(0)?;
+                continue;
+            }
+            if !self.minutes.contains(&time.minute()) {
+                time += Duration::minutes(1);
+                continue;
+            }
+            return Some(time);
+        }
+        None
+    }
+
+    /// Every occurrence in `(after, until]`, capped at `limit` entries.
+    pub fn occurrences_between(&self, after: DateTime<Utc>, until: DateTime<Utc>, limit: usize) -> Vec<DateTime<Utc>> {
+        let mut runs = Vec::new();
+        let mut cursor = after;
+        while runs.len() < limit {
+            match self.next_after(cursor) {
+                Some(next) if next <= until => {
+                    runs.push(next);
+                    cursor = next;
+                }
+                _ => break,
+            }
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn cron(expr: &str) -> CronSchedule {
+        expr.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(parse_field("*/15", 0, 59, &[]).unwrap(), BTreeSet::from([0, 15, 30, 45]));
+        assert_eq!(parse_field("1-3,10", 0, 59, &[]).unwrap(), BTreeSet::from([1, 2, 3, 10]));
+        assert_eq!(parse_field("5/20", 0, 59, &[]).unwrap(), BTreeSet::from([5, 25, 45]));
+        assert!(parse_field("60", 0, 59, &[]).is_err());
+        assert!(parse_field("*/0", 0, 59, &[]).is_err());
+        assert!(parse_field("5-1", 0, 59, &[]).is_err());
+        assert!("* * *".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn test_next_after_simple() {
+        let every_5 = cron("*/5 * * * *");
+        assert_eq!(every_5.next_after(at(2024, 1, 1, 10, 2)), Some(at(2024, 1, 1, 10, 5)));
+        assert_eq!(every_5.next_after(at(2024, 1, 1, 10, 5)), Some(at(2024, 1, 1, 10, 10)));
+        assert_eq!(cron("@daily").next_after(at(2024, 1, 1, 10, 2)), Some(at(2024, 1, 2, 0, 0)));
+        assert_eq!(cron("30 9 * * 1-5").next_after(at(2024, 1, 5, 10, 0)), Some(at(2024, 1, 8, 9, 30)));
+    }
+
+    #[test]
+    fn test_next_after_rolls_over_months_and_years() {
+        assert_eq!(cron("0 0 1 * *").next_after(at(2024, 12, 15, 0, 0)), Some(at(2025, 1, 1, 0, 0)));
+        assert_eq!(cron("0 12 29 2 *").next_after(at(2024, 3, 1, 0, 0)), Some(at(2028, 2, 29, 12, 0)));
+        assert_eq!(cron("0 0 31 2 *").next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_dom_or_dow_when_both_restricted() {
+        // The 13th or any Friday.
+        let schedule = cron("0 0 13 * 5");
+        assert_eq!(schedule.next_after(at(2024, 1, 1, 0, 0)), Some(at(2024, 1, 5, 0, 0)));
+        assert_eq!(schedule.next_after(at(2024, 1, 12, 0, 0)), Some(at(2024, 1, 13, 0, 0)));
+        assert_eq!(cron("0 0 * * 7").next_after(at(2024, 1, 1, 0, 0)), Some(at(2024, 1, 7, 0, 0)));
+    }
+
+    #[test]
+    fn test_unrestricted_day_fields_leave_the_other_in_charge() {
+        // 2024-01-01 was a Monday.
+        let mondays = cron("0 0 ? * MON");
+        assert_eq!(mondays.next_after(at(2024, 1, 2, 0, 0)), Some(at(2024, 1, 8, 0, 0)));
+        assert_eq!(mondays.occurrences_between(at(2024, 1, 1, 0, 0), at(2024, 1, 31, 0, 0), 10).len(), 4);
+        assert_eq!(cron("0 0 */1 * 1").next_after(at(2024, 1, 2, 0, 0)), Some(at(2024, 1, 8, 0, 0)));
+        assert_eq!(cron("0 0 1-31 * mon").next_after(at(2024, 1, 2, 0, 0)), Some(at(2024, 1, 8, 0, 0)));
+        // And the other way round: the 15th, whatever the weekday.
+        assert_eq!(cron("0 0 15 * ?").next_after(at(2024, 1, 2, 0, 0)), Some(at(2024, 1, 15, 0, 0)));
+        assert_eq!(cron("0 0 15 * */1").next_after(at(2024, 1, 2, 0, 0)), Some(at(2024, 1, 15, 0, 0)));
+        assert_eq!(cron("0 0 1 JAN-MAR ?").next_after(at(2024, 3, 2, 0, 0)), Some(at(2025, 1, 1, 0, 0)));
+        assert!("0 0 ? * FUNDAY".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn test_occurrences_between() {
+        let hourly = cron("@hourly");
+        let runs = hourly.occurrences_between(at(2024, 1, 1, 0, 0), at(2024, 1, 1, 5, 0), 100);
+        assert_eq!(runs.len(), 5);
+        assert_eq!(runs[4], at(2024, 1, 1, 5, 0));
+        assert_eq!(hourly.occurrences_between(at(2024, 1, 1, 0, 0), at(2024, 1, 2, 0, 0), 3).len(), 3);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod cron;
+pub mod recurring;
+
+pub use cron::CronSchedule;
+pub use recurring::{CatchUp, HandlerRegistry, JobHandler, JobRun, RecurringJob, RecurringScheduler};
@@ -0,0 +1,372 @@
+use super::cron::CronSchedule;
+use crate::{DemoError, RedisClient, Result};
+use crate::utils::RedisConnection;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use redis::{AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub const JOBS_KEY: &str = "scheduler:recurring";
+pub const DUE_KEY: &str = "scheduler:recurring:due";
+
+/// Most missed runs replayed by `CatchUp::RunAll` in a single tick.
+const MAX_CATCH_UP_RUNS: usize = 100;
+
+/// KEYS: due zset
+/// ARGV: job name, score the caller saw, next score
+///
+/// Moves the job to its next run only if nobody else already did, so with
+/// several scheduler processes each occurrence is claimed exactly once.
+const CLAIM_SCRIPT: &str = r#"
+local current = redis.call('ZSCORE', KEYS[1], ARGV[1])
+if not current or tonumber(current) ~= tonumber(ARGV[2]) then
+    return 0
+end
+redis.call('ZADD', KEYS[1], ARGV[3], ARGV[1])
+return 1
+"#;
+
+/// What to do with occurrences that passed while no scheduler was running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUp {
+    /// Drop missed runs; only an on-time occurrence runs.
+    Skip,
+    /// Run once for the most recent missed occurrence.
+    RunOnce,
+    /// Run every missed occurrence (capped at 100 per tick).
+    RunAll,
+}
+
+impl FromStr for CatchUp {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "skip" => Ok(CatchUp::Skip),
+            "run_once" | "once" => Ok(CatchUp::RunOnce),
+            "run_all" | "all" => Ok(CatchUp::RunAll),
+            other => Err(DemoError::Configuration(format!("Unknown catch-up policy: {}", other))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecurringJob {
+    pub name: String,
+    pub cron: String,
+    pub handler: String,
+    pub catch_up: CatchUp,
+}
+
+impl RecurringJob {
+    pub fn schedule(&self) -> Result<CronSchedule> {
+        self.cron.parse()
+    }
+}
+
+/// Occurrences to execute for a job due at `scheduled`, given the time
+/// is now `now`. Everything in `(scheduled, now]` counts as missed.
+pub fn runs_to_execute(
+    schedule: &CronSchedule,
+    catch_up: CatchUp,
+    scheduled: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Vec<DateTime<Utc>> {
+    let mut due = vec![scheduled];
+    due.extend(schedule.occurrences_between(scheduled, now, MAX_CATCH_UP_RUNS));
+    match catch_up {
+        CatchUp::Skip if due.len() == 1 => due,
+        CatchUp::Skip => Vec::new(),
+        CatchUp::RunOnce => due.last().copied().into_iter().collect(),
+        CatchUp::RunAll => {
+            due.truncate(MAX_CATCH_UP_RUNS);
+            due
+        }
+    }
+}
+
+fn from_millis(ms: i64) -> Result<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(ms)
+        .single()
+        .ok_or_else(|| DemoError::Demo(format!("Invalid timestamp: {}", ms)))
+}
+
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    async fn run(&self, job: &RecurringJob, scheduled_for: DateTime<Utc>) -> Result<()>;
+}
+
+/// Prints each run; the default handler for jobs added from the CLI.
+pub struct LogHandler;
+
+#[async_trait]
+impl JobHandler for LogHandler {
+    async fn run(&self, job: &RecurringJob, scheduled_for: DateTime<Utc>) -> Result<()> {
+        println!("   ▶ {} ({}) run for {}", job.name, job.cron, scheduled_for.format("%Y-%m-%d %H:%M"));
+        Ok(())
+    }
+}
+
+/// Counts runs per job in `scheduler:runs:<name>`.
+pub struct CounterHandler {
+    client: RedisClient,
+}
+
+pub fn runs_key(job: &str) -> String {
+    format!("scheduler:runs:{}", job)
+}
+
+#[async_trait]
+impl JobHandler for CounterHandler {
+    async fn run(&self, job: &RecurringJob, _scheduled_for: DateTime<Utc>) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let _: () = conn.incr(runs_key(&job.name), 1).await?;
+        Ok(())
+    }
+}
+
+/// Maps handler names stored with job definitions to code.
+#[derive(Default, Clone)]
+pub struct HandlerRegistry {
+    handlers: HashMap<String, Arc<dyn JobHandler>>,
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the built-in `log` and `count` handlers.
+    pub fn with_builtins(client: &RedisClient) -> Self {
+        let mut registry = Self::new();
+        registry.register("log", LogHandler);
+        registry.register("count", CounterHandler { client: client.clone() });
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, handler: impl JobHandler + 'static) {
+        self.handlers.insert(name.to_string(), Arc::new(handler));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn JobHandler>> {
+        self.handlers.get(name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.handlers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobRun {
+    pub job: String,
+    pub scheduled_for: DateTime<Utc>,
+    pub outcome: std::result::Result<(), String>,
+}
+
+/// Recurring jobs stored as JSON definitions in a hash, with each job's
+/// next run time in a ZSET so a tick is one ZRANGEBYSCORE.
+pub struct RecurringScheduler {
+    conn: RedisConnection,
+    claim: Script,
+}
+
+impl RecurringScheduler {
+    pub async fn new(client: &RedisClient) -> Result<Self> {
+        Ok(Self {
+            conn: client.get_async_connection().await?,
+            claim: Script::new(CLAIM_SCRIPT),
+        })
+    }
+
+    /// Stores (or replaces) a job and schedules its first run after `now`.
+    pub async fn add(&mut self, job: &RecurringJob, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        let next = job
+            .schedule()?
+            .next_after(now)
+            .ok_or_else(|| DemoError::Configuration(format!("Cron '{}' never fires", job.cron)))?;
+        let _: () = redis::pipe()
+            .atomic()
+            .hset(JOBS_KEY, &job.name, serde_json::to_string(job)?)
+            .ignore()
+            .zadd(DUE_KEY, &job.name, next.timestamp_millis())
+            .ignore()
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(next)
+    }
+
+    pub async fn remove(&mut self, name: &str) -> Result<bool> {
+        let (removed, _): (usize, usize) = redis::pipe()
+            .atomic()
+            .hdel(JOBS_KEY, name)
+            .zrem(DUE_KEY, name)
+            .query_async(&mut self.conn)
+            .await?;
+        Ok(removed == 1)
+    }
+
+    pub async fn get(&mut self, name: &str) -> Result<Option<RecurringJob>> {
+        let json: Option<String> = self.conn.hget(JOBS_KEY, name).await?;
+        json.map(|json| serde_json::from_str(&json).map_err(DemoError::from))
+            .transpose()
+    }
+
+    /// All jobs with their next run, soonest first.
+    pub async fn list(&mut self) -> Result<Vec<(RecurringJob, DateTime<Utc>)>> {
+        let due: Vec<(String, i64)> = self.conn.zrange_withscores(DUE_KEY, 0, -1).await?;
+        let mut jobs = Vec::with_capacity(due.len());
+        for (name, next_ms) in due {
+            if let Some(job) = self.get(&name).await? {
+                jobs.push((job, from_millis(next_ms)?));
+            }
+        }
+        Ok(jobs)
+    }
+
+    /// Runs every job due at `now`, honoring each job's catch-up policy,
+    /// and reschedules it after `now`.
+    pub async fn tick(&mut self, registry: &HandlerRegistry, now: DateTime<Utc>) -> Result<Vec<JobRun>> {
+        let due: Vec<(String, i64)> = self
+            .conn
+            .zrangebyscore_withscores(DUE_KEY, "-inf", now.timestamp_millis())
+            .await?;
+        let mut runs = Vec::new();
+
+        for (name, scheduled_ms) in due {
+            let Some(job) = self.get(&name).await? else {
+                let _: () = self.conn.zrem(DUE_KEY, &name).await?;
+                continue;
+            };
+            let schedule = job.schedule()?;
+            let next = schedule.next_after(now).map_or(i64::MAX, |next| next.timestamp_millis());
+            let claimed: i64 = self
+                .claim
+                .key(DUE_KEY)
+                .arg(&name)
+                .arg(scheduled_ms)
+                .arg(next)
+                .invoke_async(&mut self.conn)
+                .await?;
+            if claimed == 0 {
+                continue;
+            }
+
+            let scheduled = from_millis(scheduled_ms)?;
+            for scheduled_for in runs_to_execute(&schedule, job.catch_up, scheduled, now) {
+                let outcome = match registry.get(&job.handler) {
+                    Some(handler) => handler.run(&job, scheduled_for).await.map_err(|e| e.to_string()),
+                    None => Err(format!("No handler registered as '{}'", job.handler)),
+                };
+                if let Err(e) = &outcome {
+                    warn!("Recurring job '{}' failed: {}", job.name, e);
+                }
+                runs.push(JobRun {
+                    job: job.name.clone(),
+                    scheduled_for,
+                    outcome,
+                });
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Ticks every `interval` for `duration`, returning every run performed.
+    pub async fn run_for(
+        &mut self,
+        registry: &HandlerRegistry,
+        interval: Duration,
+        duration: Duration,
+    ) -> Result<Vec<JobRun>> {
+        let deadline = tokio::time::Instant::now() + duration;
+        let mut ticker = tokio::time::interval(interval);
+        let mut runs = Vec::new();
+        while tokio::time::Instant::now() < deadline {
+            ticker.tick().await;
+            runs.extend(self.tick(registry, Utc::now()).await?);
+        }
+        info!("Scheduler performed {} runs", runs.len());
+        Ok(runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_catch_up_policies() {
+        let schedule: CronSchedule = "*/10 * * * *".parse().unwrap();
+        let scheduled = at(10, 0);
+
+        let on_time = at(10, 5);
+        assert_eq!(runs_to_execute(&schedule, CatchUp::Skip, scheduled, on_time), vec![at(10, 0)]);
+
+        let late = at(10, 35);
+        assert!(runs_to_execute(&schedule, CatchUp::Skip, scheduled, late).is_empty());
+        assert_eq!(runs_to_execute(&schedule, CatchUp::RunOnce, scheduled, late), vec![at(10, 30)]);
+        assert_eq!(
+            runs_to_execute(&schedule, CatchUp::RunAll, scheduled, late),
+            vec![at(10, 0), at(10, 10), at(10, 20), at(10, 30)]
+        );
+    }
+
+    #[test]
+    fn test_catch_up_from_str_and_serde() {
+        assert_eq!("run-once".parse::<CatchUp>().unwrap(), CatchUp::RunOnce);
+        assert_eq!("ALL".parse::<CatchUp>().unwrap(), CatchUp::RunAll);
+        assert!("later".parse::<CatchUp>().is_err());
+        assert_eq!(serde_json::to_string(&CatchUp::RunAll).unwrap(), "\"run_all\"");
+    }
+
+    #[test]
+    fn test_registry_builtins() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let registry = HandlerRegistry::with_builtins(&client);
+        assert_eq!(registry.names(), vec!["count", "log"]);
+        assert!(registry.get("missing").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tick_runs_due_jobs_once_and_reschedules() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del(&[JOBS_KEY.to_string(), DUE_KEY.to_string(), runs_key("test_every_10")]).await.unwrap();
+
+        let mut scheduler = RecurringScheduler::new(&client).await.unwrap();
+        let registry = HandlerRegistry::with_builtins(&client);
+        let job = RecurringJob {
+            name: "test_every_10".to_string(),
+            cron: "*/10 * * * *".to_string(),
+            handler: "count".to_string(),
+            catch_up: CatchUp::RunAll,
+        };
+        assert_eq!(scheduler.add(&job, at(9, 55)).await.unwrap(), at(10, 0));
+
+        assert!(scheduler.tick(&registry, at(9, 59)).await.unwrap().is_empty());
+        let runs = scheduler.tick(&registry, at(10, 25)).await.unwrap();
+        assert_eq!(runs.len(), 3);
+        assert!(runs.iter().all(|run| run.outcome.is_ok()));
+        assert!(scheduler.tick(&registry, at(10, 25)).await.unwrap().is_empty());
+
+        let count: u32 = conn.get(runs_key("test_every_10")).await.unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(scheduler.list().await.unwrap()[0].1, at(10, 30));
+
+        assert!(scheduler.remove("test_every_10").await.unwrap());
+        assert!(scheduler.list().await.unwrap().is_empty());
+        let _: () = conn.del(runs_key("test_every_10")).await.unwrap();
+    }
+}
@@ -0,0 +1,227 @@
+use super::config::{AppendFsync, MaxmemoryPolicy, ServerConfig};
+use crate::utils::config_watch::VERSION_FLAGS;
+use crate::utils::key_watch::notify_flags;
+use std::fmt::{self, Write};
+
+const EVICTION_DOCS: &str = "https://redis.io/docs/latest/develop/reference/eviction/";
+const PERSISTENCE_DOCS: &str = "https://redis.io/docs/latest/operate/oss_and_stack/management/persistence/";
+const CONFIG_DOCS: &str = "https://redis.io/docs/latest/operate/oss_and_stack/management/config-file/";
+const NOTIFICATIONS_DOCS: &str = "https://redis.io/docs/latest/develop/use/keyspace-notifications/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Advice {
+    /// Worth knowing; the default is defensible.
+    Consider,
+    /// Likely to hurt one of the demo workloads.
+    Change,
+}
+
+impl fmt::Display for Advice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Advice::Consider => "consider",
+            Advice::Change => "change",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub advice: Advice,
+    pub setting: &'static str,
+    pub current: String,
+    pub recommended: String,
+    pub reason: &'static str,
+    pub reference: &'static str,
+}
+
+impl Suggestion {
+    fn new(advice: Advice, setting: &'static str, current: impl ToString, recommended: impl ToString) -> Self {
+        Self {
+            advice,
+            setting,
+            current: current.to_string(),
+            recommended: recommended.to_string(),
+            reason: "",
+            reference: CONFIG_DOCS,
+        }
+    }
+
+    fn because(mut self, reason: &'static str, reference: &'static str) -> Self {
+        self.reason = reason;
+        self.reference = reference;
+        self
+    }
+}
+
+/// Checks the settings that matter for this crate's workloads: caches with
+/// TTLs next to persistent users, queues and quotas, big sets and streams,
+/// and keyspace notifications for live config. Most important first.
+pub fn advise(config: &ServerConfig) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+
+    if config.maxmemory == 0 {
+        suggestions.push(
+            Suggestion::new(Advice::Change, "maxmemory", 0, "~75% of available RAM")
+                .because("Without a limit Redis grows until the OS kills it instead of evicting cache entries.", EVICTION_DOCS),
+        );
+    }
+    match &config.maxmemory_policy {
+        MaxmemoryPolicy::NoEviction if config.maxmemory > 0 => suggestions.push(
+            Suggestion::new(Advice::Change, "maxmemory-policy", &config.maxmemory_policy, MaxmemoryPolicy::VolatileLfu)
+                .because("At the limit every write fails; volatile-lfu sheds cold cache keys (the ones with TTLs) first.", EVICTION_DOCS),
+        ),
+        policy if policy.evicts_persistent_keys() => suggestions.push(
+            Suggestion::new(Advice::Change, "maxmemory-policy", policy, MaxmemoryPolicy::VolatileLfu)
+                .because("allkeys-* policies can evict users, queues and quota counters that have no TTL.", EVICTION_DOCS),
+        ),
+        _ => {}
+    }
+
+    match (config.appendonly, config.appendfsync) {
+        (false, _) => suggestions.push(
+            Suggestion::new(Advice::Consider, "appendonly", "no", "yes")
+                .because("RDB snapshots alone can lose minutes of queue jobs and quota usage on a crash.", PERSISTENCE_DOCS),
+        ),
+        (true, AppendFsync::Always) => suggestions.push(
+            Suggestion::new(Advice::Change, "appendfsync", AppendFsync::Always, AppendFsync::EverySec)
+                .because("An fsync per write caps throughput at disk latency; everysec risks at most one second.", PERSISTENCE_DOCS),
+        ),
+        (true, AppendFsync::No) => suggestions.push(
+            Suggestion::new(Advice::Consider, "appendfsync", AppendFsync::No, AppendFsync::EverySec)
+                .because("Leaving fsync to the OS can lose ~30 seconds of writes.", PERSISTENCE_DOCS),
+        ),
+        _ => {}
+    }
+
+    if config.hz < 10 {
+        suggestions.push(
+            Suggestion::new(Advice::Change, "hz", config.hz, 10)
+                .because("Expired keys and client timeouts are processed too rarely.", CONFIG_DOCS),
+        );
+    } else if config.hz > 100 {
+        suggestions.push(
+            Suggestion::new(Advice::Change, "hz", config.hz, 10)
+                .because("Values above 100 burn idle CPU; let dynamic-hz raise it under load instead.", CONFIG_DOCS),
+        );
+    }
+    if !config.dynamic_hz {
+        suggestions.push(
+            Suggestion::new(Advice::Consider, "dynamic-hz", "no", "yes")
+                .because("Scales background work with the number of connected clients.", CONFIG_DOCS),
+        );
+    }
+
+    let lazyfree = [
+        ("lazyfree-lazy-eviction", Some(config.lazyfree_lazy_eviction)),
+        ("lazyfree-lazy-expire", Some(config.lazyfree_lazy_expire)),
+        ("lazyfree-lazy-server-del", Some(config.lazyfree_lazy_server_del)),
+        ("lazyfree-lazy-user-del", config.lazyfree_lazy_user_del),
+    ];
+    for (setting, enabled) in lazyfree {
+        if enabled == Some(false) {
+            suggestions.push(
+                Suggestion::new(Advice::Consider, setting, "no", "yes")
+                    .because("Freeing big sets, feeds and streams on the main thread stalls every other client.", CONFIG_DOCS),
+            );
+        }
+    }
+
+    if let Some(flags) = notify_flags(&config.notify_keyspace_events, VERSION_FLAGS) {
+        suggestions.push(
+            Suggestion::new(Advice::Consider, "notify-keyspace-events", format!("\"{}\"", config.notify_keyspace_events), format!("\"{}\"", flags))
+                .because("`config watch --strategy events` needs keyspace events for its version keys.", NOTIFICATIONS_DOCS),
+        );
+    }
+
+    if config.slowlog_log_slower_than < 0 {
+        suggestions.push(
+            Suggestion::new(Advice::Consider, "slowlog-log-slower-than", config.slowlog_log_slower_than, 10000)
+                .because("The slow log is disabled, so slow Lua scripts and big-key commands go unnoticed.", CONFIG_DOCS),
+        );
+    }
+
+    suggestions.sort_by_key(|s| std::cmp::Reverse(s.advice));
+    suggestions
+}
+
+pub fn render_suggestions(suggestions: &[Suggestion]) -> String {
+    let mut out = String::new();
+    for suggestion in suggestions {
+        let _ = writeln!(
+            out,
+            "[{}] {}: {} → {}",
+            suggestion.advice, suggestion.setting, suggestion.current, suggestion.recommended
+        );
+        let _ = writeln!(out, "   {}", suggestion.reason);
+        // Approximate recommendations ("~75% of RAM") need a human to pick the value.
+        if !suggestion.recommended.starts_with('~') {
+            let _ = writeln!(out, "   CONFIG SET {} {}", suggestion.setting, suggestion.recommended);
+        }
+        let _ = writeln!(out, "   see {}", suggestion.reference);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::config::tests::defaults;
+
+    fn config(overrides: &[(&str, &str)]) -> ServerConfig {
+        let mut raw = defaults();
+        for (name, value) in overrides {
+            raw.insert(name.to_string(), value.to_string());
+        }
+        ServerConfig::from_pairs(raw).unwrap()
+    }
+
+    fn settings(suggestions: &[Suggestion]) -> Vec<&str> {
+        suggestions.iter().map(|s| s.setting).collect()
+    }
+
+    #[test]
+    fn test_stock_server() {
+        let suggestions = advise(&config(&[]));
+        assert_eq!(suggestions[0].setting, "maxmemory");
+        assert_eq!(suggestions[0].advice, Advice::Change);
+        assert!(settings(&suggestions).contains(&"appendonly"));
+        assert!(settings(&suggestions).contains(&"notify-keyspace-events"));
+        assert!(!settings(&suggestions).contains(&"maxmemory-policy"));
+    }
+
+    #[test]
+    fn test_tuned_server_is_quiet() {
+        let suggestions = advise(&config(&[
+            ("maxmemory", "1073741824"),
+            ("maxmemory-policy", "volatile-lfu"),
+            ("appendonly", "yes"),
+            ("lazyfree-lazy-eviction", "yes"),
+            ("lazyfree-lazy-expire", "yes"),
+            ("lazyfree-lazy-server-del", "yes"),
+            ("lazyfree-lazy-user-del", "yes"),
+            ("notify-keyspace-events", "K$"),
+        ]));
+        assert!(suggestions.is_empty(), "{:?}", suggestions);
+    }
+
+    #[test]
+    fn test_risky_settings() {
+        let suggestions = advise(&config(&[
+            ("maxmemory", "100"),
+            ("maxmemory-policy", "allkeys-lru"),
+            ("appendonly", "yes"),
+            ("appendfsync", "always"),
+            ("hz", "500"),
+        ]));
+        let changes: Vec<&str> = suggestions
+            .iter()
+            .filter(|s| s.advice == Advice::Change)
+            .map(|s| s.setting)
+            .collect();
+        assert_eq!(changes, vec!["maxmemory-policy", "appendfsync", "hz"]);
+
+        let rendered = render_suggestions(&suggestions[..1]);
+        assert!(rendered.contains("CONFIG SET maxmemory-policy volatile-lfu"));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::utils::RedisConnection;
+use crate::{DemoError, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MaxmemoryPolicy {
+    NoEviction,
+    AllKeysLru,
+    AllKeysLfu,
+    AllKeysRandom,
+    VolatileLru,
+    VolatileLfu,
+    VolatileRandom,
+    VolatileTtl,
+    Other(String),
+}
+
+impl MaxmemoryPolicy {
+    /// Whether keys without a TTL (users, queues, quotas) can be evicted.
+    pub fn evicts_persistent_keys(&self) -> bool {
+        matches!(self, MaxmemoryPolicy::AllKeysLru | MaxmemoryPolicy::AllKeysLfu | MaxmemoryPolicy::AllKeysRandom)
+    }
+}
+
+impl From<&str> for MaxmemoryPolicy {
+    fn from(value: &str) -> Self {
+        match value {
+            "noeviction" => MaxmemoryPolicy::NoEviction,
+            "allkeys-lru" => MaxmemoryPolicy::AllKeysLru,
+            "allkeys-lfu" => MaxmemoryPolicy::AllKeysLfu,
+            "allkeys-random" => MaxmemoryPolicy::AllKeysRandom,
+            "volatile-lru" => MaxmemoryPolicy::VolatileLru,
+            "volatile-lfu" => MaxmemoryPolicy::VolatileLfu,
+            "volatile-random" => MaxmemoryPolicy::VolatileRandom,
+            "volatile-ttl" => MaxmemoryPolicy::VolatileTtl,
+            other => MaxmemoryPolicy::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for MaxmemoryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MaxmemoryPolicy::NoEviction => "noeviction",
+            MaxmemoryPolicy::AllKeysLru => "allkeys-lru",
+            MaxmemoryPolicy::AllKeysLfu => "allkeys-lfu",
+            MaxmemoryPolicy::AllKeysRandom => "allkeys-random",
+            MaxmemoryPolicy::VolatileLru => "volatile-lru",
+            MaxmemoryPolicy::VolatileLfu => "volatile-lfu",
+            MaxmemoryPolicy::VolatileRandom => "volatile-random",
+            MaxmemoryPolicy::VolatileTtl => "volatile-ttl",
+            MaxmemoryPolicy::Other(other) => other,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendFsync {
+    Always,
+    EverySec,
+    No,
+}
+
+impl FromStr for AppendFsync {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "always" => Ok(AppendFsync::Always),
+            "everysec" => Ok(AppendFsync::EverySec),
+            "no" => Ok(AppendFsync::No),
+            other => Err(DemoError::Configuration(format!("Unknown appendfsync value: {}", other))),
+        }
+    }
+}
+
+impl fmt::Display for AppendFsync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AppendFsync::Always => "always",
+            AppendFsync::EverySec => "everysec",
+            AppendFsync::No => "no",
+        })
+    }
+}
+
+/// The settings the advisor reasons about, parsed from `CONFIG GET *`.
+/// Everything else stays available in `raw`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub maxmemory: u64,
+    pub maxmemory_policy: MaxmemoryPolicy,
+    pub appendonly: bool,
+    pub appendfsync: AppendFsync,
+    /// RDB save points as (seconds, changes).
+    pub save: Vec<(u64, u64)>,
+    pub hz: u32,
+    pub dynamic_hz: bool,
+    pub lazyfree_lazy_eviction: bool,
+    pub lazyfree_lazy_expire: bool,
+    pub lazyfree_lazy_server_del: bool,
+    /// Added in Redis 6.0.
+    pub lazyfree_lazy_user_del: Option<bool>,
+    pub notify_keyspace_events: String,
+    pub slowlog_log_slower_than: i64,
+    pub maxclients: u64,
+    pub raw: BTreeMap<String, String>,
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool> {
+    match value {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        other => Err(DemoError::Configuration(format!("Expected yes/no for {}, got {}", name, other))),
+    }
+}
+
+fn parse_number<T: FromStr>(name: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| DemoError::Configuration(format!("Expected a number for {}, got {}", name, value)))
+}
+
+/// Parses the `save` setting, e.g. `"3600 1 300 100"`.
+pub fn parse_save_points(value: &str) -> Result<Vec<(u64, u64)>> {
+    let numbers = value
+        .split_whitespace()
+        .map(|n| parse_number::<u64>("save", n))
+        .collect::<Result<Vec<u64>>>()?;
+    if !numbers.len().is_multiple_of(2) {
+        return Err(DemoError::Configuration(format!("Odd number of values in save: {}", value)));
+    }
+    Ok(numbers.chunks(2).map(|pair| (pair[0], pair[1])).collect())
+}
+
+impl ServerConfig {
+    pub fn from_pairs(raw: BTreeMap<String, String>) -> Result<Self> {
+        let get = |name: &str| -> Result<&str> {
+            raw.get(name)
+                .map(String::as_str)
+                .ok_or_else(|| DemoError::Configuration(format!("CONFIG GET did not return {}", name)))
+        };
+        let flag = |name: &str| -> Result<bool> { parse_bool(name, get(name)?) };
+
+        Ok(Self {
+            maxmemory: parse_number("maxmemory", get("maxmemory")?)?,
+            maxmemory_policy: MaxmemoryPolicy::from(get("maxmemory-policy")?),
+            appendonly: flag("appendonly")?,
+            appendfsync: get("appendfsync")?.parse()?,
+            save: parse_save_points(get("save")?)?,
+            hz: parse_number("hz", get("hz")?)?,
+            dynamic_hz: flag("dynamic-hz")?,
+            lazyfree_lazy_eviction: flag("lazyfree-lazy-eviction")?,
+            lazyfree_lazy_expire: flag("lazyfree-lazy-expire")?,
+            lazyfree_lazy_server_del: flag("lazyfree-lazy-server-del")?,
+            lazyfree_lazy_user_del: raw
+                .get("lazyfree-lazy-user-del")
+                .map(|value| parse_bool("lazyfree-lazy-user-del", value))
+                .transpose()?,
+            notify_keyspace_events: get("notify-keyspace-events")?.to_string(),
+            slowlog_log_slower_than: parse_number("slowlog-log-slower-than", get("slowlog-log-slower-than")?)?,
+            maxclients: parse_number("maxclients", get("maxclients")?)?,
+            raw,
+        })
+    }
+
+    pub async fn fetch(conn: &mut RedisConnection) -> Result<Self> {
+        let pairs: Vec<(String, String)> = redis::cmd("CONFIG").arg("GET").arg("*").query_async(conn).await?;
+        Self::from_pairs(pairs.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// CONFIG GET output of a stock Redis 7 server.
+    pub(crate) fn defaults() -> BTreeMap<String, String> {
+        [
+            ("maxmemory", "0"),
+            ("maxmemory-policy", "noeviction"),
+            ("appendonly", "no"),
+            ("appendfsync", "everysec"),
+            ("save", "3600 1 300 100 60 10000"),
+            ("hz", "10"),
+            ("dynamic-hz", "yes"),
+            ("lazyfree-lazy-eviction", "no"),
+            ("lazyfree-lazy-expire", "no"),
+            ("lazyfree-lazy-server-del", "no"),
+            ("lazyfree-lazy-user-del", "no"),
+            ("notify-keyspace-events", ""),
+            ("slowlog-log-slower-than", "10000"),
+            ("maxclients", "10000"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let config = ServerConfig::from_pairs(defaults()).unwrap();
+        assert_eq!(config.maxmemory_policy, MaxmemoryPolicy::NoEviction);
+        assert_eq!(config.appendfsync, AppendFsync::EverySec);
+        assert_eq!(config.save, vec![(3600, 1), (300, 100), (60, 10000)]);
+        assert_eq!(config.lazyfree_lazy_user_del, Some(false));
+        assert!(!config.appendonly);
+    }
+
+    #[test]
+    fn test_parse_errors_and_older_servers() {
+        let mut raw = defaults();
+        raw.remove("lazyfree-lazy-user-del");
+        assert_eq!(ServerConfig::from_pairs(raw.clone()).unwrap().lazyfree_lazy_user_del, None);
+
+        raw.insert("hz".to_string(), "fast".to_string());
+        assert!(ServerConfig::from_pairs(raw.clone()).is_err());
+        raw.remove("hz");
+        assert!(ServerConfig::from_pairs(raw).is_err());
+        assert!(parse_save_points("3600").is_err());
+        assert_eq!(MaxmemoryPolicy::from("weird").to_string(), "weird");
+    }
+}
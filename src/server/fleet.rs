@@ -0,0 +1,237 @@
+use super::advisor::{advise, Advice, Suggestion};
+use super::config::ServerConfig;
+use super::replication::parse_info;
+use crate::utils::connection::RedisConnection;
+use crate::utils::error::{DemoError, Result};
+use crate::utils::url::DisplaySafe;
+use crate::utils::TlsOptions;
+use crate::RedisClient;
+use futures::future::join_all;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// How long one target gets to answer before its row shows an error, so a
+/// dead instance can't hold up the whole table.
+pub const TARGET_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The INFO fields worth comparing side by side across instances.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub version: String,
+    pub role: String,
+    pub used_memory: u64,
+    pub connected_clients: u64,
+    pub keys: u64,
+    pub ops_per_sec: u64,
+}
+
+impl Snapshot {
+    pub fn parse(text: &str) -> Result<Self> {
+        let fields = parse_info(text);
+        let get = |name: &str| -> Result<&str> {
+            fields
+                .get(name)
+                .map(String::as_str)
+                .ok_or_else(|| DemoError::Demo(format!("INFO did not include {}", name)))
+        };
+        let number = |name: &str| -> Result<u64> {
+            get(name)?.parse().map_err(|_| DemoError::Demo(format!("Unexpected value for {} in INFO", name)))
+        };
+        Ok(Self {
+            version: get("redis_version")?.to_string(),
+            role: get("role")?.to_string(),
+            used_memory: number("used_memory")?,
+            connected_clients: number("connected_clients")?,
+            keys: keyspace_total(&fields),
+            ops_per_sec: number("instantaneous_ops_per_sec")?,
+        })
+    }
+
+    pub async fn fetch(conn: &mut RedisConnection) -> Result<Self> {
+        let text: String = redis::cmd("INFO").query_async(conn).await?;
+        Self::parse(&text)
+    }
+}
+
+/// Sums `keys=` over the `db0:keys=1,expires=0,avg_ttl=0` lines.
+fn keyspace_total(fields: &BTreeMap<String, String>) -> u64 {
+    fields
+        .iter()
+        .filter(|(field, _)| field.strip_prefix("db").is_some_and(|n| n.parse::<u32>().is_ok()))
+        .filter_map(|(_, value)| value.split(',').find_map(|pair| pair.strip_prefix("keys=")))
+        .filter_map(|keys| keys.parse::<u64>().ok())
+        .sum()
+}
+
+/// One target's outcome, labelled with its URL minus any password.
+pub struct TargetResult<T> {
+    pub target: String,
+    pub result: Result<T>,
+}
+
+/// Runs `task` against every URL at once and returns the results in the
+/// order the URLs were given. A target that fails or times out gets an
+/// error row; the others are unaffected.
+pub async fn fan_out<T, F, Fut>(urls: &[String], tls: &TlsOptions, task: F) -> Vec<TargetResult<T>>
+where
+    F: Fn(RedisClient) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    join_all(urls.iter().map(|url| {
+        let client = RedisClient::builder(url).tls(tls.clone()).build();
+        let task = &task;
+        async move {
+            let result = match client {
+                Ok(client) => tokio::time::timeout(TARGET_TIMEOUT, task(client))
+                    .await
+                    .unwrap_or_else(|_| Err(DemoError::Demo(format!("no answer within {:?}", TARGET_TIMEOUT)))),
+                Err(e) => Err(e),
+            };
+            TargetResult { target: DisplaySafe(url).to_string(), result }
+        }
+    }))
+    .await
+}
+
+/// Round-trip time of one PING.
+pub async fn ping_latency(client: RedisClient) -> Result<Duration> {
+    let mut conn = client.get_async_connection().await?;
+    let started = Instant::now();
+    let _: String = redis::cmd("PING").query_async(&mut conn).await?;
+    Ok(started.elapsed())
+}
+
+pub async fn snapshot(client: RedisClient) -> Result<Snapshot> {
+    let mut conn = client.get_async_connection().await?;
+    Snapshot::fetch(&mut conn).await
+}
+
+pub async fn suggestions(client: RedisClient) -> Result<Vec<Suggestion>> {
+    let mut conn = client.get_async_connection().await?;
+    Ok(advise(&ServerConfig::fetch(&mut conn).await?))
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", value, UNITS[unit]),
+    }
+}
+
+/// A plain-text table, one row per target. Failed targets span the
+/// remaining columns with their error.
+pub fn render_table<T>(headers: &[&str], results: &[TargetResult<T>], row: impl Fn(&T) -> Vec<String>) -> String {
+    let rows: Vec<(String, std::result::Result<Vec<String>, String>)> = results
+        .iter()
+        .map(|result| (result.target.clone(), result.result.as_ref().map(&row).map_err(|e| format!("❌ {}", e))))
+        .collect();
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.chars().count()).collect();
+    widths[0] = widths[0].max(rows.iter().map(|(target, _)| target.chars().count()).max().unwrap_or(0));
+    for (_, cells) in &rows {
+        for (i, cell) in cells.iter().flatten().enumerate() {
+            widths[i + 1] = widths[i + 1].max(cell.chars().count());
+        }
+    }
+    let mut out = String::new();
+    let line = |out: &mut String, cells: &[&str]| {
+        let padded: Vec<_> = cells.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+        let _ = writeln!(out, "{}", padded.join("  ").trim_end());
+    };
+    line(&mut out, headers);
+    let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+    line(&mut out, &rule.iter().map(String::as_str).collect::<Vec<_>>());
+    for (target, cells) in &rows {
+        match cells {
+            Ok(cells) => line(&mut out, &[target.as_str()].into_iter().chain(cells.iter().map(String::as_str)).collect::<Vec<_>>()),
+            Err(error) => line(&mut out, &[target.as_str(), error.as_str()]),
+        }
+    }
+    out
+}
+
+pub fn render_snapshots(results: &[TargetResult<Snapshot>]) -> String {
+    render_table(&["Target", "Version", "Role", "Memory", "Clients", "Keys", "Ops/s"], results, |snapshot| {
+        vec![
+            snapshot.version.clone(),
+            snapshot.role.clone(),
+            format_bytes(snapshot.used_memory),
+            snapshot.connected_clients.to_string(),
+            snapshot.keys.to_string(),
+            snapshot.ops_per_sec.to_string(),
+        ]
+    })
+}
+
+pub fn render_advice(results: &[TargetResult<Vec<Suggestion>>]) -> String {
+    render_table(&["Target", "Change", "Consider", "Settings"], results, |suggestions| {
+        let count = |advice: Advice| suggestions.iter().filter(|s| s.advice == advice).count().to_string();
+        let settings: Vec<_> = suggestions.iter().map(|s| s.setting).collect();
+        vec![count(Advice::Change), count(Advice::Consider), settings.join(", ")]
+    })
+}
+
+pub fn render_pings(results: &[TargetResult<Duration>]) -> String {
+    render_table(&["Target", "PING"], results, |latency| vec![format!("✅ {:.2} ms", latency.as_secs_f64() * 1000.0)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: &str = "# Server\r\nredis_version:7.2.4\r\n\r\n# Clients\r\nconnected_clients:3\r\n\r\n# Memory\r\nused_memory:1572864\r\n\r\n# Stats\r\ninstantaneous_ops_per_sec:42\r\n\r\n# Replication\r\nrole:master\r\n\r\n# Keyspace\r\ndb0:keys=10,expires=2,avg_ttl=0\r\ndb15:keys=5,expires=0,avg_ttl=0\r\n";
+
+    #[test]
+    fn test_parse_snapshot() {
+        let snapshot = Snapshot::parse(INFO).unwrap();
+        assert_eq!(
+            snapshot,
+            Snapshot {
+                version: "7.2.4".to_string(),
+                role: "master".to_string(),
+                used_memory: 1572864,
+                connected_clients: 3,
+                keys: 15,
+                ops_per_sec: 42,
+            }
+        );
+        assert!(Snapshot::parse("# Server\r\nredis_version:7.2.4\r\n").is_err());
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1572864), "1.5 MiB");
+    }
+
+    #[test]
+    fn test_render_table_keeps_failed_targets() {
+        let results = vec![
+            TargetResult { target: "redis://a:6379".to_string(), result: Snapshot::parse(INFO) },
+            TargetResult { target: "redis://:***@b:6379".to_string(), result: Err(DemoError::Demo("no answer".to_string())) },
+        ];
+        let table = render_snapshots(&results);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("Target               Version  Role"), "{}", table);
+        assert!(lines[2].contains("7.2.4") && lines[2].contains("1.5 MiB") && lines[2].ends_with("42"));
+        assert!(lines[3].starts_with("redis://:***@b:6379  ❌ Demo-specific error: no answer"), "{}", table);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_reports_each_target() {
+        let urls = vec!["redis://127.0.0.1:1".to_string(), "redis://:secret@127.0.0.1:1/2".to_string()];
+        let results = fan_out(&urls, &TlsOptions::default(), ping_latency).await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].target, "redis://:***@127.0.0.1:1/2");
+        assert!(results.iter().all(|result| result.result.is_err()));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod advisor;
+pub mod config;
+pub mod fleet;
+pub mod replication;
+
+pub use advisor::{advise, render_suggestions, Advice, Suggestion};
+pub use config::{AppendFsync, MaxmemoryPolicy, ServerConfig};
+pub use fleet::{fan_out, Snapshot, TargetResult};
+pub use replication::{ConnectedReplica, ReplicaLag, ReplicaLagThresholds, ReplicationInfo, ReplicationLag, Role};
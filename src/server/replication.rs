@@ -0,0 +1,324 @@
+use crate::integrations::metric_registry::{CONNECTED_REPLICAS, MASTER_REPL_OFFSET, REPLICA_LAG, REPLICA_LAST_ACK, REPLICA_ONLINE};
+use crate::integrations::MetricSet;
+use crate::utils::RedisConnection;
+use crate::{DemoError, Result};
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+const PANEL_WIDTH: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Role {
+    Master,
+    Replica {
+        master_host: String,
+        master_port: u16,
+        link_up: bool,
+        /// Replication offset this replica has processed.
+        offset: u64,
+    },
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Master => f.write_str("master"),
+            Role::Replica { master_host, master_port, link_up, .. } => write!(
+                f,
+                "replica of {}:{} (link {})",
+                master_host,
+                master_port,
+                if *link_up { "up" } else { "down" }
+            ),
+        }
+    }
+}
+
+/// One `slaveN:` line of a master's INFO replication section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectedReplica {
+    pub ip: String,
+    pub port: u16,
+    pub state: String,
+    pub offset: u64,
+    /// Seconds since the replica last acknowledged.
+    pub lag: u64,
+}
+
+impl ConnectedReplica {
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+}
+
+/// Typed view of `INFO replication`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationInfo {
+    pub role: Role,
+    /// Offset of the replication stream this server has produced (on a
+    /// master) or received and proxied (on a replica).
+    pub master_repl_offset: u64,
+    pub replicas: Vec<ConnectedReplica>,
+}
+
+/// Splits INFO output into `field -> value`, skipping section headers.
+pub fn parse_info(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(field, value)| (field.to_string(), value.to_string()))
+        .collect()
+}
+
+fn parse_field<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| DemoError::Demo(format!("Unexpected value for {} in INFO replication: {}", name, value)))
+}
+
+/// Parses `ip=127.0.0.1,port=6380,state=online,offset=1234,lag=0`.
+fn parse_replica(line: &str) -> Result<ConnectedReplica> {
+    let fields: BTreeMap<&str, &str> = line.split(',').filter_map(|pair| pair.split_once('=')).collect();
+    let get = |name: &str| {
+        fields
+            .get(name)
+            .copied()
+            .ok_or_else(|| DemoError::Demo(format!("Replica entry without {}: {}", name, line)))
+    };
+    Ok(ConnectedReplica {
+        ip: get("ip")?.to_string(),
+        port: parse_field("port", get("port")?)?,
+        state: get("state")?.to_string(),
+        offset: parse_field("offset", get("offset")?)?,
+        lag: parse_field("lag", get("lag")?)?,
+    })
+}
+
+impl ReplicationInfo {
+    pub fn parse(text: &str) -> Result<Self> {
+        let fields = parse_info(text);
+        let get = |name: &str| -> Result<&str> {
+            fields
+                .get(name)
+                .map(String::as_str)
+                .ok_or_else(|| DemoError::Demo(format!("INFO replication did not include {}", name)))
+        };
+
+        let role = match get("role")? {
+            "master" => Role::Master,
+            "slave" => Role::Replica {
+                master_host: get("master_host")?.to_string(),
+                master_port: parse_field("master_port", get("master_port")?)?,
+                link_up: get("master_link_status")? == "up",
+                offset: parse_field("slave_repl_offset", get("slave_repl_offset")?)?,
+            },
+            other => return Err(DemoError::Demo(format!("Unknown replication role: {}", other))),
+        };
+
+        let mut replicas = fields
+            .iter()
+            .filter(|(field, _)| field.strip_prefix("slave").is_some_and(|n| n.parse::<u32>().is_ok()))
+            .map(|(field, value)| Ok((field[5..].parse::<u32>().unwrap_or_default(), parse_replica(value)?)))
+            .collect::<Result<Vec<(u32, ConnectedReplica)>>>()?;
+        replicas.sort_by_key(|(n, _)| *n);
+
+        Ok(Self {
+            role,
+            master_repl_offset: parse_field("master_repl_offset", get("master_repl_offset")?)?,
+            replicas: replicas.into_iter().map(|(_, replica)| replica).collect(),
+        })
+    }
+
+    pub async fn fetch(conn: &mut RedisConnection) -> Result<Self> {
+        let text: String = redis::cmd("INFO").arg("replication").query_async(conn).await?;
+        Self::parse(&text)
+    }
+
+    pub fn is_master(&self) -> bool {
+        self.role == Role::Master
+    }
+
+    /// The connected replica at `host:port`, if any. Masters report replica
+    /// IPs, so `localhost` is matched as `127.0.0.1`.
+    pub fn replica_at(&self, host: &str, port: u16) -> Option<&ConnectedReplica> {
+        let host = if host == "localhost" { "127.0.0.1" } else { host };
+        self.replicas.iter().find(|replica| replica.ip == host && replica.port == port)
+    }
+
+    /// How far each connected replica trails this master. Errors on a
+    /// replica, which only knows its own offset.
+    pub fn lag(&self) -> Result<ReplicationLag> {
+        if let Role::Replica { master_host, master_port, .. } = &self.role {
+            return Err(DemoError::Demo(format!(
+                "Connected to a replica; measure lag on its master {}:{}",
+                master_host, master_port
+            )));
+        }
+        Ok(ReplicationLag {
+            master_offset: self.master_repl_offset,
+            replicas: self
+                .replicas
+                .iter()
+                .map(|replica| ReplicaLag {
+                    addr: replica.addr(),
+                    state: replica.state.clone(),
+                    bytes: self.master_repl_offset.saturating_sub(replica.offset),
+                    last_ack_secs: replica.lag,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaLag {
+    pub addr: String,
+    pub state: String,
+    /// Replication stream bytes the replica has not acknowledged yet.
+    pub bytes: u64,
+    pub last_ack_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationLag {
+    pub master_offset: u64,
+    pub replicas: Vec<ReplicaLag>,
+}
+
+/// Alert limits for [`ReplicationLag::evaluate`]; `None` disables a check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplicaLagThresholds {
+    pub max_bytes: Option<u64>,
+    pub max_ack_secs: Option<u64>,
+}
+
+impl ReplicationLag {
+    pub fn max_bytes(&self) -> u64 {
+        self.replicas.iter().map(|replica| replica.bytes).max().unwrap_or(0)
+    }
+
+    /// Threshold violations as human-readable messages. A master without
+    /// replicas is always worth an alert: reads routed to replicas have
+    /// nowhere to go.
+    pub fn evaluate(&self, thresholds: &ReplicaLagThresholds) -> Vec<String> {
+        let mut alerts = Vec::new();
+        if self.replicas.is_empty() {
+            alerts.push("no replicas connected".to_string());
+        }
+        for replica in &self.replicas {
+            if replica.state != "online" {
+                alerts.push(format!("replica {} is {}", replica.addr, replica.state));
+            }
+            if let Some(max) = thresholds.max_bytes.filter(|max| replica.bytes > *max) {
+                alerts.push(format!("replica {} is {} bytes behind (max {})", replica.addr, replica.bytes, max));
+            }
+            if let Some(max) = thresholds.max_ack_secs.filter(|max| replica.last_ack_secs > *max) {
+                alerts.push(format!("replica {} last acknowledged {}s ago (max {})", replica.addr, replica.last_ack_secs, max));
+            }
+        }
+        alerts
+    }
+
+    /// Gauges for a dashboard: the master's offset and, per replica, its
+    /// lag, acknowledgement age and whether it is online.
+    pub fn record_metrics(&self, metrics: &mut MetricSet) {
+        metrics
+            .gauge(&MASTER_REPL_OFFSET, &[], self.master_offset as f64)
+            .gauge(&CONNECTED_REPLICAS, &[], self.replicas.len() as f64);
+        for replica in &self.replicas {
+            let labels = [("replica", replica.addr.as_str())];
+            metrics
+                .gauge(&REPLICA_LAG, &labels, replica.bytes as f64)
+                .gauge(&REPLICA_LAST_ACK, &labels, replica.last_ack_secs as f64)
+                .gauge(&REPLICA_ONLINE, &labels, if replica.state == "online" { 1.0 } else { 0.0 });
+        }
+    }
+
+    /// One bar per replica, scaled to the byte threshold when there is one
+    /// and to the furthest-behind replica otherwise.
+    pub fn render_panel(&self, thresholds: &ReplicaLagThresholds) -> String {
+        let scale = thresholds.max_bytes.unwrap_or_else(|| self.max_bytes()).max(1);
+        let mut out = String::new();
+        let _ = writeln!(out, "master offset {}", self.master_offset);
+        let _ = writeln!(out, "{:<22} {:<12} {:<width$} {:>12} {:>6}", "replica", "state", "lag", "bytes", "ack", width = PANEL_WIDTH);
+        for replica in &self.replicas {
+            let filled = ((replica.bytes as f64 / scale as f64) * PANEL_WIDTH as f64).ceil() as usize;
+            let bar = format!("{}{}", "█".repeat(filled.min(PANEL_WIDTH)), if filled > PANEL_WIDTH { "▶" } else { "" });
+            let _ = writeln!(
+                out,
+                "{:<22} {:<12} {:<width$} {:>12} {:>5}s",
+                replica.addr,
+                replica.state,
+                bar,
+                replica.bytes,
+                replica.last_ack_secs,
+                width = PANEL_WIDTH
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const MASTER_INFO: &str = "# Replication\r\nrole:master\r\nconnected_slaves:2\r\n\
+        slave0:ip=127.0.0.1,port=6380,state=online,offset=1200,lag=0\r\n\
+        slave1:ip=10.0.0.7,port=6379,state=wait_bgsave,offset=0,lag=3\r\n\
+        master_failover_state:no-failover\r\nmaster_replid:8f1c\r\nmaster_repl_offset:1234\r\n";
+
+    pub(crate) const REPLICA_INFO: &str = "# Replication\r\nrole:slave\r\nmaster_host:127.0.0.1\r\nmaster_port:6379\r\n\
+        master_link_status:up\r\nmaster_last_io_seconds_ago:1\r\nslave_repl_offset:1200\r\n\
+        slave_read_only:1\r\nconnected_slaves:0\r\nmaster_repl_offset:1200\r\n";
+
+    #[test]
+    fn test_parse_master() {
+        let info = ReplicationInfo::parse(MASTER_INFO).unwrap();
+        assert!(info.is_master());
+        assert_eq!(info.master_repl_offset, 1234);
+        assert_eq!(info.replicas.len(), 2);
+        assert_eq!(info.replicas[1].state, "wait_bgsave");
+        assert_eq!(info.replica_at("localhost", 6380).unwrap().offset, 1200);
+        assert!(info.replica_at("127.0.0.1", 6381).is_none());
+    }
+
+    #[test]
+    fn test_parse_replica() {
+        let info = ReplicationInfo::parse(REPLICA_INFO).unwrap();
+        assert_eq!(
+            info.role,
+            Role::Replica { master_host: "127.0.0.1".to_string(), master_port: 6379, link_up: true, offset: 1200 }
+        );
+        assert_eq!(info.role.to_string(), "replica of 127.0.0.1:6379 (link up)");
+        assert!(info.replicas.is_empty());
+    }
+
+    #[test]
+    fn test_lag_and_alerts() {
+        let lag = ReplicationInfo::parse(MASTER_INFO).unwrap().lag().unwrap();
+        assert_eq!(lag.replicas[0].bytes, 34);
+        assert_eq!(lag.replicas[1].bytes, 1234);
+        assert_eq!(lag.max_bytes(), 1234);
+
+        assert_eq!(lag.evaluate(&ReplicaLagThresholds::default()), vec!["replica 10.0.0.7:6379 is wait_bgsave"]);
+        let alerts = lag.evaluate(&ReplicaLagThresholds { max_bytes: Some(100), max_ack_secs: Some(2) });
+        assert_eq!(alerts.len(), 3);
+        assert!(alerts[1].contains("1234 bytes behind"));
+
+        let panel = lag.render_panel(&ReplicaLagThresholds { max_bytes: Some(100), max_ack_secs: None });
+        let lines: Vec<&str> = panel.lines().collect();
+        assert_eq!(lines[2].matches('█').count(), 11);
+        assert!(lines[3].contains('▶'));
+
+        assert!(ReplicationInfo::parse(REPLICA_INFO).unwrap().lag().is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(ReplicationInfo::parse("role:sentinel\r\nmaster_repl_offset:0").is_err());
+        assert!(ReplicationInfo::parse("role:master").is_err());
+        assert!(ReplicationInfo::parse("role:master\r\nmaster_repl_offset:0\r\nslave0:ip=1.2.3.4").is_err());
+    }
+}
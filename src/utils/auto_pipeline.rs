@@ -0,0 +1,245 @@
+use crate::utils::RedisConnection;
+use redis::aio::ConnectionLike;
+use redis::{Cmd, Pipeline, RedisError, RedisFuture, RedisResult, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+/// Batch size histogram buckets: 1, 2-3, 4-7, ... 512+.
+const BUCKETS: usize = 10;
+
+type Pending = (Cmd, oneshot::Sender<RedisResult<Value>>);
+
+/// Histogram bucket for a batch of `size` commands.
+pub fn bucket(size: usize) -> usize {
+    (usize::BITS - size.max(1).leading_zeros() - 1).min(BUCKETS as u32 - 1) as usize
+}
+
+#[derive(Default)]
+struct Counters {
+    batches: AtomicU64,
+    commands: AtomicU64,
+    max_batch: AtomicU64,
+    /// Batches the server rejected a command in; every command in them got
+    /// that error.
+    failed: AtomicU64,
+    histogram: [AtomicU64; BUCKETS],
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchStats {
+    pub batches: u64,
+    pub commands: u64,
+    pub max_batch: u64,
+    pub failed: u64,
+    pub histogram: [u64; BUCKETS],
+}
+
+impl BatchStats {
+    pub fn mean_batch(&self) -> f64 {
+        if self.batches == 0 {
+            return 0.0;
+        }
+        self.commands as f64 / self.batches as f64
+    }
+
+    /// `"1: 12, 2-3: 40, 4-7: 310"`, skipping empty buckets.
+    pub fn render_histogram(&self) -> String {
+        self.histogram
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(i, count)| {
+                let (low, high) = (1u64 << i, (1u64 << (i + 1)) - 1);
+                match (low == high, i == BUCKETS - 1) {
+                    (_, true) => format!("{}+: {}", low, count),
+                    (true, _) => format!("{}: {}", low, count),
+                    _ => format!("{}-{}: {}", low, high, count),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Connection wrapper that coalesces single commands issued concurrently
+/// from many tasks into one pipeline, like StackExchange.Redis does.
+///
+/// The first command of a batch opens a window of `window`; everything that
+/// arrives before it closes (or until `max_batch` commands) is written in a
+/// single round trip. Explicit pipelines bypass the batcher. Use it anywhere
+/// a `RedisConnection` is accepted via `ConnectionLike`.
+#[derive(Clone)]
+pub struct AutoPipeline {
+    sender: mpsc::UnboundedSender<Pending>,
+    direct: RedisConnection,
+    counters: Arc<Counters>,
+}
+
+impl AutoPipeline {
+    pub fn new(conn: RedisConnection, window: Duration, max_batch: usize) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let counters = Arc::new(Counters::default());
+        tokio::spawn(Self::batcher(conn.clone(), receiver, window, max_batch.max(1), counters.clone()));
+        Self { sender, direct: conn, counters }
+    }
+
+    pub fn stats(&self) -> BatchStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        BatchStats {
+            batches: load(&self.counters.batches),
+            commands: load(&self.counters.commands),
+            max_batch: load(&self.counters.max_batch),
+            failed: load(&self.counters.failed),
+            histogram: std::array::from_fn(|i| load(&self.counters.histogram[i])),
+        }
+    }
+
+    async fn batcher(
+        mut conn: RedisConnection,
+        mut receiver: mpsc::UnboundedReceiver<Pending>,
+        window: Duration,
+        max_batch: usize,
+        counters: Arc<Counters>,
+    ) {
+        while let Some(first) = receiver.recv().await {
+            let mut batch = vec![first];
+            let deadline = Instant::now() + window;
+            while batch.len() < max_batch {
+                match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                    Ok(Some(pending)) => batch.push(pending),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            counters.batches.fetch_add(1, Ordering::Relaxed);
+            counters.commands.fetch_add(batch.len() as u64, Ordering::Relaxed);
+            counters.max_batch.fetch_max(batch.len() as u64, Ordering::Relaxed);
+            counters.histogram[bucket(batch.len())].fetch_add(1, Ordering::Relaxed);
+
+            if let [(cmd, _)] = batch.as_slice() {
+                let result = conn.req_packed_command(cmd).await;
+                if let Some((_, reply)) = batch.pop() {
+                    let _ = reply.send(result);
+                }
+                continue;
+            }
+
+            let mut pipe = redis::pipe();
+            for (cmd, _) in &batch {
+                pipe.add_command(cmd.clone());
+            }
+            match conn.req_packed_commands(&pipe, 0, batch.len()).await {
+                Ok(values) => {
+                    for ((_, reply), value) in batch.into_iter().zip(values) {
+                        let _ = reply.send(Ok(value));
+                    }
+                }
+                // A pipeline fails as a whole when one of its commands gets an
+                // error reply, but the others have already run. Re-sending them
+                // would apply INCR, LPUSH or XADD twice, so every caller gets
+                // the batch's error instead.
+                Err(err) => {
+                    counters.failed.fetch_add(1, Ordering::Relaxed);
+                    for (_, reply) in batch {
+                        let _ = reply.send(Err(batch_error(&err)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `RedisError` isn't `Clone`; keep the kind and message for each caller.
+fn batch_error(err: &RedisError) -> RedisError {
+    RedisError::from((err.kind(), "Auto-pipelined batch failed", err.to_string()))
+}
+
+impl ConnectionLike for AutoPipeline {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(async move {
+            let (reply, response) = oneshot::channel();
+            self.sender
+                .send((cmd.clone(), reply))
+                .map_err(|_| RedisError::from((redis::ErrorKind::IoError, "Auto-pipeline batcher stopped")))?;
+            response
+                .await
+                .map_err(|_| RedisError::from((redis::ErrorKind::IoError, "Auto-pipeline batcher dropped the command")))?
+        })
+    }
+
+    fn req_packed_commands<'a>(&'a mut self, cmd: &'a Pipeline, offset: usize, count: usize) -> RedisFuture<'a, Vec<Value>> {
+        self.direct.req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.direct.get_db()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+    use redis::AsyncCommands;
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(bucket(0), 0);
+        assert_eq!(bucket(1), 0);
+        assert_eq!(bucket(3), 1);
+        assert_eq!(bucket(4), 2);
+        assert_eq!(bucket(100_000), BUCKETS - 1);
+    }
+
+    #[test]
+    fn test_stats_rendering() {
+        let mut stats = BatchStats { batches: 4, commands: 10, ..Default::default() };
+        stats.histogram[0] = 1;
+        stats.histogram[1] = 2;
+        stats.histogram[BUCKETS - 1] = 1;
+        assert_eq!(stats.mean_batch(), 2.5);
+        assert_eq!(stats.render_histogram(), "1: 1, 2-3: 2, 512+: 1");
+        assert_eq!(BatchStats::default().mean_batch(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_commands_share_batches() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let auto = client.auto_pipeline(Duration::from_millis(5), 64).await.unwrap();
+        let mut setup = auto.clone();
+        let _: () = setup.del("auto_pipeline_test").await.unwrap();
+
+        let handles: Vec<_> = (0..50)
+            .map(|_| {
+                let mut conn = auto.clone();
+                tokio::spawn(async move { conn.incr::<_, _, i64>("auto_pipeline_test", 1).await.unwrap() })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        let total: i64 = setup.get("auto_pipeline_test").await.unwrap();
+        assert_eq!(total, 50);
+        assert!(auto.stats().max_batch > 1);
+
+        // A bad command fails its whole batch, and the INCR sharing it isn't
+        // replayed.
+        let _: () = setup.set("auto_pipeline_test", 0).await.unwrap();
+        let _: () = setup.set("auto_pipeline_test:text", "text").await.unwrap();
+        let (mut first, mut second) = (auto.clone(), auto.clone());
+        let (counted, bad) = tokio::join!(
+            first.incr::<_, _, i64>("auto_pipeline_test", 1),
+            second.incr::<_, _, i64>("auto_pipeline_test:text", 1)
+        );
+        assert!(bad.is_err());
+        assert!(counted.is_err());
+        assert_eq!(auto.stats().failed, 1);
+        let total: i64 = setup.get("auto_pipeline_test").await.unwrap();
+        assert_eq!(total, 1);
+
+        let _: () = setup.del(&["auto_pipeline_test", "auto_pipeline_test:text"]).await.unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use crate::utils::error::Result;
+use crate::utils::RedisConnection;
+use redis::{AsyncCommands, Script};
+
+/// KEYS: id -> index hash, index counter
+/// ARGV: ids
+/// Returns one index per id, allocating the next free index for unseen ids.
+const ASSIGN_SCRIPT: &str = r#"
+local indexes = {}
+for i, id in ipairs(ARGV) do
+    local index = redis.call('HGET', KEYS[1], id)
+    if not index then
+        index = redis.call('INCR', KEYS[2]) - 1
+        redis.call('HSET', KEYS[1], id, index)
+    end
+    indexes[i] = tonumber(index)
+end
+return indexes
+"#;
+
+/// Maps arbitrary ids (UUIDs, emails, ...) to dense integers so they can be
+/// used as bitmap offsets. Offsets are allocated sequentially, keeping
+/// bitmaps as small as the number of distinct ids.
+pub struct BitIndexMap {
+    conn: RedisConnection,
+    namespace: String,
+    script: Script,
+}
+
+impl BitIndexMap {
+    pub fn new(conn: RedisConnection, namespace: &str) -> Self {
+        Self {
+            conn,
+            namespace: namespace.to_string(),
+            script: Script::new(ASSIGN_SCRIPT),
+        }
+    }
+
+    pub fn ids_key(&self) -> String {
+        format!("{}:bit_index", self.namespace)
+    }
+
+    pub fn counter_key(&self) -> String {
+        format!("{}:bit_index:next", self.namespace)
+    }
+
+    /// Returns the offset for `id`, allocating one on first use.
+    pub async fn index_of(&mut self, id: &str) -> Result<u64> {
+        Ok(self.assign(&[id]).await?[0])
+    }
+
+    /// Batch form of [`index_of`](Self::index_of), one round trip per call.
+    pub async fn assign<S: AsRef<str>>(&mut self, ids: &[S]) -> Result<Vec<u64>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut invocation = self.script.key(self.ids_key());
+        invocation.key(self.counter_key());
+        for id in ids {
+            invocation.arg(id.as_ref());
+        }
+        Ok(invocation.invoke_async(&mut self.conn).await?)
+    }
+
+    /// Offset for `id` without allocating one.
+    pub async fn lookup(&mut self, id: &str) -> Result<Option<u64>> {
+        Ok(self.conn.hget(self.ids_key(), id).await?)
+    }
+
+    pub async fn len(&mut self) -> Result<u64> {
+        Ok(self.conn.hlen(self.ids_key()).await?)
+    }
+
+    pub async fn clear(&mut self) -> Result<()> {
+        let _: () = self.conn.del(&[self.ids_key(), self.counter_key()]).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_indexes_are_dense_and_stable() {
+        let client = get_test_client().await;
+        let mut map = BitIndexMap::new(client.get_async_connection().await.unwrap(), "bit_index_test");
+        map.clear().await.unwrap();
+
+        assert_eq!(map.assign(&["alice", "bob", "alice"]).await.unwrap(), vec![0, 1, 0]);
+        assert_eq!(map.index_of("carol").await.unwrap(), 2);
+        assert_eq!(map.lookup("bob").await.unwrap(), Some(1));
+        assert_eq!(map.lookup("dave").await.unwrap(), None);
+        assert_eq!(map.len().await.unwrap(), 3);
+
+        map.clear().await.unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+use chrono::{Duration, NaiveDate};
+
+/// One bitmap per day, e.g. `dau:2025-03-01`, with a bit per user id.
+pub fn day_key(prefix: &str, day: NaiveDate) -> String {
+    format!("{}:{}", prefix, day.format("%Y-%m-%d"))
+}
+
+/// `days` consecutive days starting at `start`.
+pub fn days_from(start: NaiveDate, days: usize) -> Vec<NaiveDate> {
+    (0..days as i64).map(|n| start + Duration::days(n)).collect()
+}
+
+/// Bit for `day` in a per-user bitmap counting days since `epoch`, or
+/// `None` for days before it (SETBIT offsets can't be negative).
+pub fn day_offset(epoch: NaiveDate, day: NaiveDate) -> Option<usize> {
+    usize::try_from((day - epoch).num_days()).ok()
+}
+
+pub fn day_at(epoch: NaiveDate, offset: usize) -> NaiveDate {
+    epoch + Duration::days(offset as i64)
+}
+
+/// Byte index and mask of a bit offset. Offset 0 is the most significant
+/// bit of the first byte, as SETBIT, GETBIT and BITPOS count them.
+pub fn bit_position(offset: usize) -> (usize, u8) {
+    (offset / 8, 0x80 >> (offset % 8))
+}
+
+/// Bytes Redis allocates once `offset` is set: the string grows to hold
+/// the highest bit, however few bits are set.
+pub fn bitmap_bytes(offset: usize) -> usize {
+    bit_position(offset).0 + 1
+}
+
+/// Offsets of the set bits in a bitmap read with GET.
+pub fn set_offsets(bitmap: &[u8]) -> Vec<usize> {
+    (0..bitmap.len() * 8)
+        .filter(|offset| {
+            let (byte, mask) = bit_position(*offset);
+            bitmap[byte] & mask != 0
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    #[test]
+    fn test_day_keys_and_offsets() {
+        assert_eq!(day_key("dau", date(1)), "dau:2025-03-01");
+        assert_eq!(days_from(date(30), 3), [date(30), date(31), NaiveDate::from_ymd_opt(2025, 4, 1).unwrap()]);
+        assert_eq!(day_offset(date(1), date(8)), Some(7));
+        assert_eq!(day_offset(date(8), date(1)), None);
+        assert_eq!(day_at(date(1), 7), date(8));
+    }
+
+    #[test]
+    fn test_bit_order_matches_setbit() {
+        // SETBIT k 0 1 and SETBIT k 9 1 leave "\x80\x40"
+        assert_eq!(bit_position(0), (0, 0x80));
+        assert_eq!(bit_position(9), (1, 0x40));
+        assert_eq!(set_offsets(&[0x80, 0x40]), [0, 9]);
+        assert_eq!(set_offsets(&[]), Vec::<usize>::new());
+        assert_eq!(bitmap_bytes(7), 1);
+        assert_eq!(bitmap_bytes(1_000_000), 125_001);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::utils::connection::CommandObserver;
+use redis::Cmd;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Counters since the observer was attached; steps diff two snapshots.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Totals {
+    connects: u64,
+    connect: Duration,
+    round_trips: u64,
+    latency: Duration,
+}
+
+/// Where a step's time went, from the connection instrumentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepTiming {
+    pub step: String,
+    pub budget: Duration,
+    pub elapsed: Duration,
+    pub connects: u64,
+    pub connect: Duration,
+    pub round_trips: u64,
+    pub latency: Duration,
+}
+
+impl StepTiming {
+    pub fn over_budget(&self) -> bool {
+        self.elapsed > self.budget
+    }
+
+    /// Wall time not spent connecting or waiting on replies. Concurrent
+    /// commands overlap, so their summed latency can exceed the wall time;
+    /// this then bottoms out at zero.
+    pub fn local(&self) -> Duration {
+        self.elapsed.saturating_sub(self.connect + self.latency)
+    }
+}
+
+impl fmt::Display for StepTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        writeln!(
+            f,
+            "⚠️  Step '{}' took {:.0} ms, over its {:.0} ms budget",
+            self.step,
+            ms(self.elapsed),
+            ms(self.budget)
+        )?;
+        writeln!(f, "     connection setup  {:>8.1} ms  ({} connections)", ms(self.connect), self.connects)?;
+        writeln!(f, "     command latency   {:>8.1} ms  ({} round trips)", ms(self.latency), self.round_trips)?;
+        writeln!(f, "     local processing  {:>8.1} ms", ms(self.local()))
+    }
+}
+
+/// Times demo steps against `--budget-ms`. Attach it to the client with
+/// `with_observer` so it sees connection setup and every reply, then wrap
+/// each step in [`begin`](Self::begin) and [`Step::finish`].
+pub struct TimingBudget {
+    budget: Duration,
+    connects: AtomicU64,
+    connect_us: AtomicU64,
+    round_trips: AtomicU64,
+    latency_us: AtomicU64,
+}
+
+impl TimingBudget {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            connects: AtomicU64::new(0),
+            connect_us: AtomicU64::new(0),
+            round_trips: AtomicU64::new(0),
+            latency_us: AtomicU64::new(0),
+        }
+    }
+
+    fn totals(&self) -> Totals {
+        Totals {
+            connects: self.connects.load(Ordering::Relaxed),
+            connect: Duration::from_micros(self.connect_us.load(Ordering::Relaxed)),
+            round_trips: self.round_trips.load(Ordering::Relaxed),
+            latency: Duration::from_micros(self.latency_us.load(Ordering::Relaxed)),
+        }
+    }
+
+    pub fn begin(&self, step: &str) -> Step<'_> {
+        Step { budget: self, step: step.to_string(), started: Instant::now(), at_start: self.totals() }
+    }
+}
+
+impl CommandObserver for TimingBudget {
+    fn on_command(&self, _cmd: &Cmd) {}
+
+    fn on_reply(&self, elapsed: Duration) {
+        self.round_trips.fetch_add(1, Ordering::Relaxed);
+        self.latency_us.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn on_connect(&self, elapsed: Duration) {
+        self.connects.fetch_add(1, Ordering::Relaxed);
+        self.connect_us.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// One timed step. Steps may nest or overlap; each counts what happened
+/// on the budget's connections between its begin and finish.
+pub struct Step<'a> {
+    budget: &'a TimingBudget,
+    step: String,
+    started: Instant,
+    at_start: Totals,
+}
+
+impl Step<'_> {
+    /// The step's timing, printing the breakdown when it ran over budget.
+    pub fn finish(self) -> StepTiming {
+        let now = self.budget.totals();
+        let timing = StepTiming {
+            step: self.step,
+            budget: self.budget.budget,
+            elapsed: self.started.elapsed(),
+            connects: now.connects - self.at_start.connects,
+            connect: now.connect - self.at_start.connect,
+            round_trips: now.round_trips - self.at_start.round_trips,
+            latency: now.latency - self.at_start.latency,
+        };
+        if timing.over_budget() {
+            print!("{}", timing);
+        }
+        timing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_breakdown() {
+        let budget = TimingBudget::new(Duration::ZERO);
+        budget.on_connect(Duration::from_millis(5));
+        let step = budget.begin("strings");
+        budget.on_connect(Duration::from_millis(200));
+        budget.on_reply(Duration::from_millis(3));
+        budget.on_reply(Duration::from_millis(7));
+        let timing = step.finish();
+        assert_eq!((timing.connects, timing.connect), (1, Duration::from_millis(200)));
+        assert_eq!((timing.round_trips, timing.latency), (2, Duration::from_millis(10)));
+        assert!(timing.over_budget());
+    }
+
+    #[test]
+    fn test_local_time_and_report() {
+        let timing = StepTiming {
+            step: "lists".to_string(),
+            budget: Duration::from_millis(500),
+            elapsed: Duration::from_millis(1800),
+            connects: 2,
+            connect: Duration::from_millis(1200),
+            round_trips: 34,
+            latency: Duration::from_millis(540),
+        };
+        assert_eq!(timing.local(), Duration::from_millis(60));
+        let report = timing.to_string();
+        assert!(report.starts_with("⚠️  Step 'lists' took 1800 ms, over its 500 ms budget"));
+        assert!(report.contains("1200.0 ms  (2 connections)"));
+        assert!(report.contains("60.0 ms"));
+
+        let overlapping = StepTiming { latency: Duration::from_secs(5), ..timing };
+        assert_eq!(overlapping.local(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::utils::error::{DemoError, Result};
+use crate::utils::RedisConnection;
+use bytes::Bytes;
+use redis::aio::ConnectionLike;
+use redis::Value;
+
+/// Takes ownership of a bulk string reply. `Nil` is `None`; anything else
+/// is a type error.
+pub fn into_bytes(value: Value) -> Result<Option<Bytes>> {
+    match value {
+        Value::Data(data) => Ok(Some(Bytes::from(data))),
+        Value::Nil => Ok(None),
+        other => Err(DemoError::Demo(format!("Expected a bulk string, got {:?}", other))),
+    }
+}
+
+/// Like [`into_bytes`] for callers that do need text; reuses the buffer, so
+/// the only cost left is the UTF-8 check.
+pub fn into_string(value: Value) -> Result<Option<String>> {
+    match value {
+        Value::Data(data) => String::from_utf8(data)
+            .map(Some)
+            .map_err(|e| DemoError::Demo(format!("Value is not UTF-8: {}", e))),
+        Value::Status(status) => Ok(Some(status)),
+        Value::Nil => Ok(None),
+        other => Err(DemoError::Demo(format!("Expected a bulk string, got {:?}", other))),
+    }
+}
+
+/// GET without the typed API's copies: `query_async::<String>` clones the
+/// payload out of the parsed reply and validates it as UTF-8, two passes over
+/// data the caller often just forwards. Sending through `ConnectionLike`
+/// returns the owned reply, whose buffer becomes the `Bytes` as-is.
+pub async fn get_bytes(conn: &mut RedisConnection, key: &str) -> Result<Option<Bytes>> {
+    let value = conn.req_packed_command(redis::cmd("GET").arg(key)).await?;
+    into_bytes(value)
+}
+
+pub async fn mget_bytes(conn: &mut RedisConnection, keys: &[String]) -> Result<Vec<Option<Bytes>>> {
+    if keys.is_empty() {
+        return Ok(Vec::new());
+    }
+    match conn.req_packed_command(redis::cmd("MGET").arg(keys)).await? {
+        Value::Bulk(values) => values.into_iter().map(into_bytes).collect(),
+        other => Err(DemoError::Demo(format!("Expected an array from MGET, got {:?}", other))),
+    }
+}
+
+/// Sends a pipeline and returns each reply as an owned `Value`, without the
+/// per-element clone `query_async::<Vec<Value>>` makes.
+pub async fn query_owned(conn: &mut RedisConnection, pipe: &redis::Pipeline) -> Result<Vec<Value>> {
+    let count = pipe.cmd_iter().count();
+    Ok(conn.req_packed_commands(pipe, 0, count).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_bytes_moves_the_buffer() {
+        let data = vec![0u8, 159, 146, 150];
+        let pointer = data.as_ptr();
+        let bytes = into_bytes(Value::Data(data)).unwrap().unwrap();
+        assert_eq!(bytes.as_ptr(), pointer);
+        assert_eq!(into_bytes(Value::Nil).unwrap(), None);
+        assert!(into_bytes(Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn test_into_string() {
+        assert_eq!(into_string(Value::Data(b"hi".to_vec())).unwrap().as_deref(), Some("hi"));
+        assert_eq!(into_string(Value::Status("OK".to_string())).unwrap().as_deref(), Some("OK"));
+        assert!(into_string(Value::Data(vec![0xff, 0xfe])).is_err());
+    }
+}
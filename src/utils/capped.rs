@@ -0,0 +1,153 @@
+use crate::utils::error::{DemoError, Result};
+use crate::utils::RedisConnection;
+use redis::streams::StreamMaxlen;
+use redis::AsyncCommands;
+
+/// The LTRIM stop index keeping `max_len` entries. A cap of 0 is refused:
+/// its stop of -1 would mean "through the last entry" and keep them all.
+fn trim_stop(max_len: usize) -> Result<isize> {
+    match isize::try_from(max_len) {
+        Ok(0) | Err(_) => Err(DemoError::Configuration(format!("a capped list must keep between 1 and {} entries, not {}", isize::MAX, max_len))),
+        Ok(len) => Ok(len - 1),
+    }
+}
+
+/// Pushes `value` to the head of the list and trims it to `max_len` entries
+/// in one MULTI/EXEC, so concurrent writers can never observe (or leave) an
+/// over-long list. Returns the list length after trimming. `max_len` must
+/// be at least 1.
+pub async fn push_capped_list(
+    conn: &mut RedisConnection,
+    key: &str,
+    value: &str,
+    max_len: usize,
+) -> Result<usize> {
+    let stop = trim_stop(max_len)?;
+    let (pushed_len,): (usize,) = redis::pipe()
+        .atomic()
+        .lpush(key, value)
+        .ltrim(key, 0, stop)
+        .ignore()
+        .query_async(conn)
+        .await?;
+    Ok(pushed_len.min(max_len))
+}
+
+/// Appends an entry to a stream capped at roughly `max_len` entries
+/// (`XADD key MAXLEN ~ max_len * ...`). Approximate trimming lets Redis drop
+/// whole radix-tree nodes, so the stream may briefly exceed the cap.
+/// Returns the generated entry id.
+pub async fn add_capped_stream(
+    conn: &mut RedisConnection,
+    key: &str,
+    fields: &[(&str, &str)],
+    max_len: usize,
+) -> Result<String> {
+    let id: String = conn
+        .xadd_maxlen(key, StreamMaxlen::Approx(max_len), "*", fields)
+        .await?;
+    Ok(id)
+}
+
+/// Same as [`add_capped_stream`] but trims exactly (`MAXLEN =`), trading
+/// throughput for a hard bound.
+pub async fn add_capped_stream_exact(
+    conn: &mut RedisConnection,
+    key: &str,
+    fields: &[(&str, &str)],
+    max_len: usize,
+) -> Result<String> {
+    let id: String = conn
+        .xadd_maxlen(key, StreamMaxlen::Equals(max_len), "*", fields)
+        .await?;
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+
+    async fn get_test_client() -> RedisClient {
+        RedisClient::new("redis://localhost:6379/15").unwrap()
+    }
+
+    #[test]
+    fn test_trim_stop_refuses_zero() {
+        assert_eq!(trim_stop(1).unwrap(), 0);
+        assert_eq!(trim_stop(25).unwrap(), 24);
+        assert!(matches!(trim_stop(0), Err(DemoError::Configuration(_))));
+    }
+
+    #[tokio::test]
+    async fn test_push_capped_list_keeps_newest() {
+        let client = get_test_client().await;
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del("capped_test:list").await.unwrap();
+
+        for i in 0..10 {
+            let len = push_capped_list(&mut conn, "capped_test:list", &i.to_string(), 3).await.unwrap();
+            assert!(len <= 3);
+        }
+
+        let items: Vec<String> = conn.lrange("capped_test:list", 0, -1).await.unwrap();
+        assert_eq!(items, vec!["9", "8", "7"]);
+
+        let _: () = conn.del("capped_test:list").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_push_capped_list_concurrent_bound() {
+        let client = get_test_client().await;
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del("capped_test:concurrent").await.unwrap();
+
+        let mut handles = Vec::new();
+        for worker in 0..8 {
+            let client = client.clone();
+            handles.push(tokio::spawn(async move {
+                let mut conn = client.get_async_connection().await.unwrap();
+                for i in 0..50 {
+                    let value = format!("{}-{}", worker, i);
+                    let len = push_capped_list(&mut conn, "capped_test:concurrent", &value, 25)
+                        .await
+                        .unwrap();
+                    assert!(len <= 25);
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let len: usize = conn.llen("capped_test:concurrent").await.unwrap();
+        assert_eq!(len, 25);
+
+        let _: () = conn.del("capped_test:concurrent").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_add_capped_stream_bounds() {
+        let client = get_test_client().await;
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del(&["capped_test:stream", "capped_test:stream_exact"]).await.unwrap();
+
+        for i in 0..500 {
+            let value = i.to_string();
+            add_capped_stream(&mut conn, "capped_test:stream", &[("n", value.as_str())], 100)
+                .await
+                .unwrap();
+            add_capped_stream_exact(&mut conn, "capped_test:stream_exact", &[("n", value.as_str())], 100)
+                .await
+                .unwrap();
+        }
+
+        let approx_len: usize = conn.xlen("capped_test:stream").await.unwrap();
+        let exact_len: usize = conn.xlen("capped_test:stream_exact").await.unwrap();
+        // Approximate trimming works in whole macro-nodes (100 entries by default).
+        assert!((100..=200).contains(&approx_len));
+        assert_eq!(exact_len, 100);
+
+        let _: () = conn.del(&["capped_test:stream", "capped_test:stream_exact"]).await.unwrap();
+    }
+}
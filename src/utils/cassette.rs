@@ -0,0 +1,233 @@
+use crate::utils::error::{DemoError, Result};
+use redis::{ErrorKind, RedisError, RedisResult, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A reply as stored in a cassette. Mirrors `redis::Value`, keeping bulk
+/// strings readable when they are UTF-8.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordedValue {
+    Nil,
+    Int(i64),
+    Data(String),
+    Binary(Vec<u8>),
+    Bulk(Vec<RecordedValue>),
+    Status(String),
+    Okay,
+}
+
+impl From<&Value> for RecordedValue {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Nil => RecordedValue::Nil,
+            Value::Int(n) => RecordedValue::Int(*n),
+            Value::Data(bytes) => match std::str::from_utf8(bytes) {
+                Ok(text) => RecordedValue::Data(text.to_string()),
+                Err(_) => RecordedValue::Binary(bytes.clone()),
+            },
+            Value::Bulk(items) => RecordedValue::Bulk(items.iter().map(RecordedValue::from).collect()),
+            Value::Status(status) => RecordedValue::Status(status.clone()),
+            Value::Okay => RecordedValue::Okay,
+        }
+    }
+}
+
+impl From<&RecordedValue> for Value {
+    fn from(value: &RecordedValue) -> Self {
+        match value {
+            RecordedValue::Nil => Value::Nil,
+            RecordedValue::Int(n) => Value::Int(*n),
+            RecordedValue::Data(text) => Value::Data(text.clone().into_bytes()),
+            RecordedValue::Binary(bytes) => Value::Data(bytes.clone()),
+            RecordedValue::Bulk(items) => Value::Bulk(items.iter().map(Value::from).collect()),
+            RecordedValue::Status(status) => Value::Status(status.clone()),
+            RecordedValue::Okay => Value::Okay,
+        }
+    }
+}
+
+/// One round trip: a command, or every command of a pipeline, and what the
+/// server answered. Server errors are kept as their `CODE message` line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub commands: Vec<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replies: Vec<RecordedValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Interaction {
+    fn result(&self) -> RedisResult<Vec<Value>> {
+        match &self.error {
+            // Re-parsing the error line gives back the same ErrorKind the
+            // live reply had.
+            Some(error) => match redis::parse_redis_value(format!("-{}\r\n", error).as_bytes()) {
+                Err(error) => Err(error),
+                Ok(_) => Err(RedisError::from((ErrorKind::ResponseError, "Recorded error", error.clone()))),
+            },
+            None => Ok(self.replies.iter().map(Value::from).collect()),
+        }
+    }
+}
+
+pub(crate) fn describe(commands: &[Vec<String>]) -> String {
+    commands.iter().map(|args| args.join(" ")).collect::<Vec<_>>().join("; ")
+}
+
+enum Tape {
+    Recording(BufWriter<File>),
+    /// Recorded interactions by request, each queue in recording order.
+    Playing(HashMap<Vec<Vec<String>>, VecDeque<Interaction>>),
+}
+
+/// A VCR-style tape of server round trips, attached to a client with
+/// `RedisClient::with_cassette`.
+///
+/// When recording, every command and pipeline sent over the client's async
+/// connections is appended to a JSON-lines file together with its reply.
+/// When replaying, no server is contacted: each request is answered with
+/// the next reply recorded for exactly the same arguments. Requests may
+/// arrive in a different order than recorded, but a request whose arguments
+/// change between runs (timestamps, random ids) finds no match and fails.
+/// Like the key prefix, this only covers `RedisConnection`; pub/sub, sync
+/// and pooled connections still need a server.
+pub struct Cassette {
+    path: PathBuf,
+    tape: Mutex<Tape>,
+}
+
+impl Cassette {
+    /// Starts a new cassette at `path`, replacing any file there.
+    pub fn record(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::create(&path)?;
+        Ok(Self { path, tape: Mutex::new(Tape::Recording(BufWriter::new(file))) })
+    }
+
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut recorded: HashMap<_, VecDeque<_>> = HashMap::new();
+        for (i, line) in BufReader::new(File::open(&path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let interaction: Interaction = serde_json::from_str(&line).map_err(|e| {
+                DemoError::Configuration(format!("{} line {}: not a cassette entry: {}", path.display(), i + 1, e))
+            })?;
+            recorded.entry(interaction.commands.clone()).or_default().push_back(interaction);
+        }
+        Ok(Self { path, tape: Mutex::new(Tape::Playing(recorded)) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(*self.tape.lock().expect("cassette poisoned"), Tape::Playing(_))
+    }
+
+    /// Recorded round trips not replayed yet; zero after a faithful replay.
+    pub fn remaining(&self) -> usize {
+        match &*self.tape.lock().expect("cassette poisoned") {
+            Tape::Playing(recorded) => recorded.values().map(VecDeque::len).sum(),
+            Tape::Recording(_) => 0,
+        }
+    }
+
+    /// Appends a round trip. Errors that did not come from the server
+    /// (connection drops, timeouts) are not recorded.
+    pub(crate) fn save(&self, commands: Vec<Vec<String>>, result: std::result::Result<&[Value], &RedisError>) {
+        let interaction = match result {
+            Ok(replies) => Interaction { commands, replies: replies.iter().map(RecordedValue::from).collect(), error: None },
+            Err(error) => match error.code() {
+                Some(code) => {
+                    let error = format!("{} {}", code, error.detail().unwrap_or_default());
+                    Interaction { commands, replies: Vec::new(), error: Some(error) }
+                }
+                None => return,
+            },
+        };
+        if let Tape::Recording(out) = &mut *self.tape.lock().expect("cassette poisoned") {
+            let line = serde_json::to_string(&interaction).expect("interactions serialize");
+            if let Err(e) = writeln!(out, "{}", line).and_then(|_| out.flush()) {
+                tracing::warn!("Failed to write cassette {}: {}", self.path.display(), e);
+            }
+        }
+    }
+
+    /// The recorded reply for `commands`, or a client error naming the
+    /// request when the cassette has none left.
+    pub(crate) fn play(&self, commands: &[Vec<String>]) -> RedisResult<Vec<Value>> {
+        let next = match &mut *self.tape.lock().expect("cassette poisoned") {
+            Tape::Playing(recorded) => recorded.get_mut(commands).and_then(VecDeque::pop_front),
+            Tape::Recording(_) => None,
+        };
+        match next {
+            Some(interaction) => interaction.result(),
+            None => Err(RedisError::from((
+                ErrorKind::ClientError,
+                "No recorded reply in cassette",
+                format!("{} ({})", describe(commands), self.path.display()),
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(args: &[&str]) -> Vec<Vec<String>> {
+        vec![args.iter().map(|arg| arg.to_string()).collect()]
+    }
+
+    #[test]
+    fn test_values_survive_the_round_trip() {
+        let value = Value::Bulk(vec![
+            Value::Data(b"text".to_vec()),
+            Value::Data(vec![0xff, 0x00]),
+            Value::Int(7),
+            Value::Nil,
+            Value::Okay,
+            Value::Status("PONG".to_string()),
+        ]);
+        let json = serde_json::to_string(&RecordedValue::from(&value)).unwrap();
+        assert!(json.contains("\"data\":\"text\""), "{}", json);
+        let back: RecordedValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(Value::from(&back), value);
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let path = std::env::temp_dir().join(format!("cassette-{}.jsonl", uuid::Uuid::new_v4()));
+        let recorder = Cassette::record(&path).unwrap();
+        recorder.save(request(&["INCR", "n"]), Ok(&[Value::Int(1)]));
+        recorder.save(request(&["INCR", "n"]), Ok(&[Value::Int(2)]));
+        let wrong = redis::parse_redis_value(b"-WRONGTYPE Operation against a key\r\n").unwrap_err();
+        recorder.save(request(&["LPUSH", "n", "x"]), Err(&wrong));
+        let dropped = RedisError::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        recorder.save(request(&["GET", "n"]), Err(&dropped));
+        assert!(!recorder.is_replay());
+        drop(recorder);
+
+        let player = Cassette::replay(&path).unwrap();
+        assert!(player.is_replay());
+        assert_eq!(player.remaining(), 3);
+        assert_eq!(player.play(&request(&["INCR", "n"])).unwrap(), [Value::Int(1)]);
+        let error = player.play(&request(&["LPUSH", "n", "x"])).unwrap_err();
+        assert_eq!((error.kind(), error.code()), (wrong.kind(), Some("WRONGTYPE")));
+        assert_eq!(player.play(&request(&["INCR", "n"])).unwrap(), [Value::Int(2)]);
+        let missing = player.play(&request(&["INCR", "n"])).unwrap_err();
+        assert!(missing.to_string().contains("INCR n"), "{}", missing);
+        assert_eq!(player.remaining(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+/// Number of hash slots in a Redis Cluster.
+pub const SLOT_COUNT: u16 = 16384;
+
+/// The part of a key that is hashed: the content of the first `{...}` if it
+/// is non-empty, otherwise the whole key. Keys sharing a hash tag always
+/// land in the same slot.
+pub fn hash_tag(key: &str) -> &str {
+    if let Some(open) = key.find('{') {
+        if let Some(close) = key[open + 1..].find('}') {
+            if close > 0 {
+                return &key[open + 1..open + 1 + close];
+            }
+        }
+    }
+    key
+}
+
+/// CRC16-XMODEM, the checksum Redis Cluster uses for key slots.
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Same result as `CLUSTER KEYSLOT key`.
+pub fn key_slot(key: &str) -> u16 {
+    crc16(hash_tag(key).as_bytes()) % SLOT_COUNT
+}
+
+/// True if every key maps to the same slot, i.e. a multi-key command over
+/// them won't fail with CROSSSLOT.
+pub fn same_slot<S: AsRef<str>>(keys: &[S]) -> bool {
+    let mut slots = keys.iter().map(|key| key_slot(key.as_ref()));
+    match slots.next() {
+        Some(first) => slots.all(|slot| slot == first),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_tag_rules() {
+        assert_eq!(hash_tag("user:1000"), "user:1000");
+        assert_eq!(hash_tag("{user:1000}:profile"), "user:1000");
+        assert_eq!(hash_tag("foo{}{bar}"), "foo{}{bar}");
+        assert_eq!(hash_tag("foo{{bar}}zap"), "{bar");
+        assert_eq!(hash_tag("foo{bar}{zap}"), "bar");
+        assert_eq!(hash_tag("{unclosed"), "{unclosed");
+    }
+
+    #[test]
+    fn test_key_slot_matches_redis() {
+        // Values from CLUSTER KEYSLOT.
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        assert_eq!(key_slot("foo"), 12182);
+        assert_eq!(key_slot("bar"), 5061);
+        assert_eq!(key_slot("somekey"), 11058);
+        assert_eq!(key_slot("{user1000}.following"), key_slot("{user1000}.followers"));
+    }
+
+    #[test]
+    fn test_same_slot() {
+        assert!(same_slot(&["{cart:1}:items", "{cart:1}:total"]));
+        assert!(!same_slot(&["foo", "bar"]));
+        assert!(same_slot::<&str>(&[]));
+    }
+}
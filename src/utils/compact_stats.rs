@@ -0,0 +1,204 @@
+use crate::utils::error::Result;
+use crate::utils::RedisConnection;
+
+/// Per-user counters packed into a single BITFIELD string. Lanes are laid
+/// out back to back in declaration order, 80 bits (10 bytes) in total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatLane {
+    Logins,
+    Posts,
+    Comments,
+    LikesGiven,
+    StreakDays,
+    Warnings,
+}
+
+impl StatLane {
+    pub const ALL: [StatLane; 6] = [
+        StatLane::Logins,
+        StatLane::Posts,
+        StatLane::Comments,
+        StatLane::LikesGiven,
+        StatLane::StreakDays,
+        StatLane::Warnings,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StatLane::Logins => "logins",
+            StatLane::Posts => "posts",
+            StatLane::Comments => "comments",
+            StatLane::LikesGiven => "likes_given",
+            StatLane::StreakDays => "streak_days",
+            StatLane::Warnings => "warnings",
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            StatLane::StreakDays | StatLane::Warnings => 8,
+            _ => 16,
+        }
+    }
+
+    /// BITFIELD type, e.g. `u16`.
+    pub fn encoding(self) -> String {
+        format!("u{}", self.bits())
+    }
+
+    pub fn offset(self) -> u32 {
+        Self::ALL
+            .iter()
+            .take_while(|lane| **lane != self)
+            .map(|lane| lane.bits())
+            .sum()
+    }
+
+    pub fn max(self) -> u64 {
+        (1 << self.bits()) - 1
+    }
+}
+
+pub fn total_bits() -> u32 {
+    StatLane::ALL.iter().map(|lane| lane.bits()).sum()
+}
+
+/// Typed view over all lanes of one user's stats value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UserStats {
+    pub logins: u16,
+    pub posts: u16,
+    pub comments: u16,
+    pub likes_given: u16,
+    pub streak_days: u8,
+    pub warnings: u8,
+}
+
+impl UserStats {
+    fn from_values(values: &[u64]) -> Self {
+        let get = |lane: StatLane| {
+            let index = StatLane::ALL.iter().position(|l| *l == lane).unwrap_or_default();
+            values.get(index).copied().unwrap_or(0)
+        };
+        Self {
+            logins: get(StatLane::Logins) as u16,
+            posts: get(StatLane::Posts) as u16,
+            comments: get(StatLane::Comments) as u16,
+            likes_given: get(StatLane::LikesGiven) as u16,
+            streak_days: get(StatLane::StreakDays) as u8,
+            warnings: get(StatLane::Warnings) as u8,
+        }
+    }
+}
+
+/// Adds `by` to a lane, saturating at the lane's maximum (or zero) instead
+/// of wrapping. Returns the new lane value.
+pub async fn incr(conn: &mut RedisConnection, key: &str, lane: StatLane, by: i64) -> Result<u64> {
+    let values: Vec<u64> = redis::cmd("BITFIELD")
+        .arg(key)
+        .arg("OVERFLOW")
+        .arg("SAT")
+        .arg("INCRBY")
+        .arg(lane.encoding())
+        .arg(lane.offset())
+        .arg(by)
+        .query_async(conn)
+        .await?;
+    Ok(values.first().copied().unwrap_or(0))
+}
+
+/// Appends a saturating increment to a pipeline, for bulk updates.
+pub fn incr_in(pipe: &mut redis::Pipeline, key: &str, lane: StatLane, by: i64) {
+    pipe.cmd("BITFIELD")
+        .arg(key)
+        .arg("OVERFLOW")
+        .arg("SAT")
+        .arg("INCRBY")
+        .arg(lane.encoding())
+        .arg(lane.offset())
+        .arg(by)
+        .ignore();
+}
+
+pub async fn set(conn: &mut RedisConnection, key: &str, lane: StatLane, value: u64) -> Result<()> {
+    let _: () = redis::cmd("BITFIELD")
+        .arg(key)
+        .arg("SET")
+        .arg(lane.encoding())
+        .arg(lane.offset())
+        .arg(value.min(lane.max()))
+        .query_async(conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn get(conn: &mut RedisConnection, key: &str, lane: StatLane) -> Result<u64> {
+    let values: Vec<u64> = redis::cmd("BITFIELD")
+        .arg(key)
+        .arg("GET")
+        .arg(lane.encoding())
+        .arg(lane.offset())
+        .query_async(conn)
+        .await?;
+    Ok(values.first().copied().unwrap_or(0))
+}
+
+/// Reads every lane with a single BITFIELD call.
+pub async fn get_all(conn: &mut RedisConnection, key: &str) -> Result<UserStats> {
+    let mut cmd = redis::cmd("BITFIELD");
+    cmd.arg(key);
+    for lane in StatLane::ALL {
+        cmd.arg("GET").arg(lane.encoding()).arg(lane.offset());
+    }
+    let values: Vec<u64> = cmd.query_async(conn).await?;
+    Ok(UserStats::from_values(&values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+    use redis::AsyncCommands;
+
+    #[test]
+    fn test_lane_layout() {
+        assert_eq!(StatLane::Logins.offset(), 0);
+        assert_eq!(StatLane::Posts.offset(), 16);
+        assert_eq!(StatLane::StreakDays.offset(), 64);
+        assert_eq!(StatLane::Warnings.offset(), 72);
+        assert_eq!(StatLane::Warnings.encoding(), "u8");
+        assert_eq!(StatLane::Warnings.max(), 255);
+        assert_eq!(total_bits(), 80);
+    }
+
+    #[test]
+    fn test_user_stats_from_values() {
+        let stats = UserStats::from_values(&[1, 2, 3, 4, 5, 6]);
+        assert_eq!(stats.logins, 1);
+        assert_eq!(stats.likes_given, 4);
+        assert_eq!(stats.warnings, 6);
+        assert_eq!(UserStats::from_values(&[]), UserStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_incr_saturates() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let key = "compact_stats_test:user";
+        let _: () = conn.del(key).await.unwrap();
+
+        assert_eq!(incr(&mut conn, key, StatLane::Warnings, 200).await.unwrap(), 200);
+        assert_eq!(incr(&mut conn, key, StatLane::Warnings, 200).await.unwrap(), 255);
+        assert_eq!(incr(&mut conn, key, StatLane::Warnings, -300).await.unwrap(), 0);
+        assert_eq!(incr(&mut conn, key, StatLane::Posts, 3).await.unwrap(), 3);
+        set(&mut conn, key, StatLane::Logins, 70_000).await.unwrap();
+
+        let stats = get_all(&mut conn, key).await.unwrap();
+        assert_eq!(stats.logins, u16::MAX);
+        assert_eq!(stats.posts, 3);
+        assert_eq!(stats.warnings, 0);
+        assert_eq!(get(&mut conn, key, StatLane::Posts).await.unwrap(), 3);
+
+        let _: () = conn.del(key).await.unwrap();
+    }
+}
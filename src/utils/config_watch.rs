@@ -0,0 +1,305 @@
+use crate::utils::error::{DemoError, Result};
+use crate::RedisClient;
+use crate::utils::key_watch::{NotifyChange, NotifyGuard};
+use crate::utils::RedisConnection;
+use futures::StreamExt;
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Flags `--strategy events` needs: generic events on keyspace channels.
+pub const VERSION_FLAGS: &str = "K$";
+
+pub fn config_key(name: &str) -> String {
+    format!("config:{}", name)
+}
+
+pub fn version_key(name: &str) -> String {
+    format!("config:{}:version", name)
+}
+
+/// The keyspace channel for `name`'s version key. Async connections put
+/// the client's key prefix on every key, so the channel needs it too.
+pub fn version_channel(db: i64, prefix: Option<&str>, name: &str) -> String {
+    format!("__keyspace@{}__:{}{}", db, prefix.unwrap_or(""), version_key(name))
+}
+
+/// How a [`Config`] notices that the stored configuration changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadStrategy {
+    /// GET the version key on a fixed interval.
+    Poll(Duration),
+    /// Subscribe to keyspace notifications for the version key. Notifications
+    /// are fire-and-forget, so the version is still polled on the given
+    /// (typically long) interval to catch anything missed while reconnecting.
+    KeyspaceEvents(Duration),
+}
+
+impl FromStr for ReloadStrategy {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "poll" => Ok(ReloadStrategy::Poll(Duration::from_secs(1))),
+            "events" | "keyspace" => Ok(ReloadStrategy::KeyspaceEvents(Duration::from_secs(30))),
+            other => Err(DemoError::Configuration(format!("Unknown reload strategy: {}", other))),
+        }
+    }
+}
+
+/// Flattens a JSON object into hash fields. Strings are stored raw so the hash
+/// stays readable and editable from redis-cli; everything else is JSON. A
+/// string that would read back as something else ("42", "true", "null") is
+/// stored JSON-quoted to keep its type.
+pub fn encode_fields(value: &Value) -> Result<Vec<(String, String)>> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| DemoError::Configuration("Configuration must serialize to a JSON object".to_string()))?;
+    Ok(object
+        .iter()
+        .map(|(field, value)| {
+            let encoded = match value {
+                Value::String(s) if serde_json::from_str::<Value>(s).is_err() => s.clone(),
+                other => other.to_string(),
+            };
+            (field.clone(), encoded)
+        })
+        .collect())
+}
+
+/// Inverse of [`encode_fields`]: values that parse as JSON are used as-is,
+/// anything else becomes a string.
+pub fn decode_fields(fields: HashMap<String, String>) -> Value {
+    let object: Map<String, Value> = fields
+        .into_iter()
+        .map(|(field, raw)| {
+            let value = serde_json::from_str(&raw).unwrap_or(Value::String(raw));
+            (field, value)
+        })
+        .collect();
+    Value::Object(object)
+}
+
+/// Replaces the whole configuration and bumps its version atomically.
+/// Returns the new version.
+pub async fn publish<T: Serialize>(conn: &mut RedisConnection, name: &str, config: &T) -> Result<u64> {
+    let fields = encode_fields(&serde_json::to_value(config)?)?;
+    let (version,): (u64,) = redis::pipe()
+        .atomic()
+        .del(config_key(name))
+        .ignore()
+        .hset_multiple(config_key(name), &fields)
+        .ignore()
+        .incr(version_key(name), 1)
+        .query_async(conn)
+        .await?;
+    Ok(version)
+}
+
+/// Updates a single field and bumps the version. Returns the new version.
+pub async fn set_field(conn: &mut RedisConnection, name: &str, field: &str, value: &str) -> Result<u64> {
+    let (version,): (u64,) = redis::pipe()
+        .atomic()
+        .hset(config_key(name), field, value)
+        .ignore()
+        .incr(version_key(name), 1)
+        .query_async(conn)
+        .await?;
+    Ok(version)
+}
+
+async fn load<T: DeserializeOwned>(conn: &mut RedisConnection, name: &str) -> Result<(u64, T)> {
+    let (version, fields): (Option<u64>, HashMap<String, String>) = redis::pipe()
+        .atomic()
+        .get(version_key(name))
+        .hgetall(config_key(name))
+        .query_async(conn)
+        .await?;
+    Ok((version.unwrap_or(0), serde_json::from_value(decode_fields(fields))?))
+}
+
+/// Locally cached, typed view of a configuration hash that reloads itself in
+/// the background. Reads never touch Redis.
+pub struct Config<T> {
+    receiver: watch::Receiver<(u64, Arc<T>)>,
+    task: JoinHandle<()>,
+    /// Puts `notify-keyspace-events` back when the watcher is dropped.
+    _notify: NotifyGuard,
+}
+
+impl<T: DeserializeOwned + Send + Sync + 'static> Config<T> {
+    /// Loads `name` and keeps it fresh. With [`ReloadStrategy::KeyspaceEvents`],
+    /// `change` (from `key_watch::notify_change` with [`VERSION_FLAGS`], once
+    /// confirmed) is made for as long as the watcher lives.
+    pub async fn watch(client: &RedisClient, name: &str, strategy: ReloadStrategy, change: Option<NotifyChange>) -> Result<Self> {
+        let mut conn = client.get_async_connection().await?;
+        let (version, value) = load::<T>(&mut conn, name).await?;
+        let (sender, receiver) = watch::channel((version, Arc::new(value)));
+
+        let mut events = None;
+        let events_wanted = matches!(strategy, ReloadStrategy::KeyspaceEvents(_));
+        let notify = NotifyGuard::apply(client, change.filter(|_| events_wanted)).await?;
+        let interval = match strategy {
+            ReloadStrategy::Poll(interval) => interval,
+            ReloadStrategy::KeyspaceEvents(interval) => {
+                let mut pubsub = client.get_async_pubsub().await?;
+                let db = client.get_connection_info().redis.db;
+                pubsub.subscribe(version_channel(db, client.key_prefix(), name)).await?;
+                events = Some(pubsub.into_on_message());
+                interval
+            }
+        };
+
+        let name = name.to_string();
+        let task = tokio::spawn(async move {
+            while !sender.is_closed() {
+                let mut closed = false;
+                match events.as_mut() {
+                    Some(events) => {
+                        tokio::select! {
+                            message = events.next() => closed = message.is_none(),
+                            _ = tokio::time::sleep(interval) => {}
+                        }
+                    }
+                    None => tokio::time::sleep(interval).await,
+                }
+                if closed {
+                    warn!("Keyspace subscription for '{}' closed, falling back to polling", name);
+                    events = None;
+                }
+                if let Err(e) = Self::refresh(&mut conn, &name, &sender).await {
+                    warn!("Failed to reload configuration '{}': {}", name, e);
+                }
+            }
+        });
+
+        Ok(Self { receiver, task, _notify: notify })
+    }
+
+    async fn refresh(conn: &mut RedisConnection, name: &str, sender: &watch::Sender<(u64, Arc<T>)>) -> Result<()> {
+        let version: Option<u64> = conn.get(version_key(name)).await?;
+        if version.unwrap_or(0) == sender.borrow().0 {
+            return Ok(());
+        }
+        let (version, value) = load::<T>(conn, name).await?;
+        debug!("Configuration '{}' reloaded at version {}", name, version);
+        sender.send_replace((version, Arc::new(value)));
+        Ok(())
+    }
+
+    /// The latest loaded configuration.
+    pub fn current(&self) -> Arc<T> {
+        self.receiver.borrow().1.clone()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.receiver.borrow().0
+    }
+
+    /// Waits until a newer version has been loaded.
+    pub async fn changed(&mut self) -> Result<Arc<T>> {
+        self.receiver
+            .changed()
+            .await
+            .map_err(|_| DemoError::Demo("Configuration watcher stopped".to_string()))?;
+        Ok(self.receiver.borrow_and_update().1.clone())
+    }
+}
+
+impl<T> Drop for Config<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    struct Settings {
+        max_connections: u32,
+        banner: Option<String>,
+        dark_mode: bool,
+    }
+
+    #[test]
+    fn test_fields_round_trip() {
+        let settings = Settings { max_connections: 10, banner: Some("hi".to_string()), dark_mode: true };
+        let fields: HashMap<String, String> = encode_fields(&serde_json::to_value(&settings).unwrap())
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(fields["banner"], "hi");
+        assert_eq!(fields["max_connections"], "10");
+        let decoded: Settings = serde_json::from_value(decode_fields(fields)).unwrap();
+        assert_eq!(decoded, settings);
+        assert!(encode_fields(&Value::from(3)).is_err());
+    }
+
+    #[test]
+    fn test_strings_that_look_like_json_keep_their_type() {
+        for banner in ["42", "true", "null", "\"quoted\""] {
+            let settings = Settings { banner: Some(banner.to_string()), ..Default::default() };
+            let fields: HashMap<String, String> = encode_fields(&serde_json::to_value(&settings).unwrap())
+                .unwrap()
+                .into_iter()
+                .collect();
+            let decoded: Settings = serde_json::from_value(decode_fields(fields)).unwrap();
+            assert_eq!(decoded.banner.as_deref(), Some(banner));
+        }
+    }
+
+    #[test]
+    fn test_version_channel_has_the_key_prefix() {
+        assert_eq!(version_channel(0, None, "app"), "__keyspace@0__:config:app:version");
+        assert_eq!(version_channel(3, Some("demo:"), "app"), "__keyspace@3__:demo:config:app:version");
+    }
+
+    #[tokio::test]
+    async fn test_events_arrive_under_a_key_prefix() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap().with_key_prefix("config-test:");
+        let mut conn = client.get_async_connection().await.unwrap();
+        publish(&mut conn, "events_test", &Settings { max_connections: 1, ..Default::default() }).await.unwrap();
+
+        // Polling every 30s can't see the change in time; only an event can.
+        let change = crate::utils::key_watch::notify_change(&client, VERSION_FLAGS).await.unwrap();
+        let strategy = ReloadStrategy::KeyspaceEvents(Duration::from_secs(30));
+        let mut config = Config::<Settings>::watch(&client, "events_test", strategy, change).await.unwrap();
+        set_field(&mut conn, "events_test", "max_connections", "2").await.unwrap();
+        let updated = tokio::time::timeout(Duration::from_secs(2), config.changed()).await.unwrap().unwrap();
+        assert_eq!(updated.max_connections, 2);
+
+        client.delete_prefixed_keys().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watcher_picks_up_changes() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del(&[config_key("watch_test"), version_key("watch_test")]).await.unwrap();
+        publish(&mut conn, "watch_test", &Settings { max_connections: 5, ..Default::default() }).await.unwrap();
+
+        let mut config = Config::<Settings>::watch(&client, "watch_test", ReloadStrategy::Poll(Duration::from_millis(50)), None)
+            .await
+            .unwrap();
+        assert_eq!(config.current().max_connections, 5);
+
+        set_field(&mut conn, "watch_test", "max_connections", "8").await.unwrap();
+        let updated = tokio::time::timeout(Duration::from_secs(2), config.changed()).await.unwrap().unwrap();
+        assert_eq!(updated.max_connections, 8);
+        assert_eq!(config.version(), 2);
+
+        let _: () = conn.del(&[config_key("watch_test"), version_key("watch_test")]).await.unwrap();
+    }
+}
@@ -0,0 +1,513 @@
+use crate::resp::{self, Frame};
+use crate::utils::cassette::Cassette;
+use redis::aio::{ConnectionLike, ConnectionManager};
+use redis::{Arg, Cmd, ErrorKind, Pipeline, RedisError, RedisFuture, RedisResult, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Sees every command sent through a [`RedisConnection`], including each
+/// command of a pipeline, before it goes on the wire.
+pub trait CommandObserver: Send + Sync {
+    fn on_command(&self, cmd: &Cmd);
+
+    /// Called before `cmd` goes out; an error refuses it and is returned to
+    /// the caller without anything reaching the server.
+    fn admit(&self, _cmd: &Cmd) -> RedisResult<()> {
+        Ok(())
+    }
+
+    /// A reply arrived `elapsed` after its request went out: once per
+    /// command, or once for a whole pipeline.
+    fn on_reply(&self, _elapsed: Duration) {}
+
+    /// `RedisClient` took `elapsed` to open a new connection.
+    fn on_connect(&self, _elapsed: Duration) {}
+}
+
+/// The connection handed out by `RedisClient`: a `ConnectionManager` plus
+/// the client's command observers, key prefix and cassette. Cloning is
+/// cheap and shares all of them.
+///
+/// With a prefix, every key a command names is rewritten on the way out
+/// (`GET user:1` becomes `GET demo:user:1`) and taken back off key names in
+/// replies, so callers never see it. Keys a Lua script builds itself rather
+/// than receiving through `KEYS` are not rewritten, and RANDOMKEY can still
+/// return keys from outside the prefix.
+///
+/// A [`Cassette`] sees commands after prefixing, as they go on the wire.
+/// A connection replaying one has no server behind it at all.
+#[derive(Clone)]
+pub struct RedisConnection {
+    inner: Option<ConnectionManager>,
+    db: i64,
+    observers: Arc<Vec<Arc<dyn CommandObserver>>>,
+    prefix: Option<Arc<str>>,
+    cassette: Option<Arc<Cassette>>,
+}
+
+impl RedisConnection {
+    pub fn new(inner: ConnectionManager, observers: Arc<Vec<Arc<dyn CommandObserver>>>) -> Self {
+        let db = inner.get_db();
+        Self { inner: Some(inner), db, observers, prefix: None, cassette: None }
+    }
+
+    /// A connection answered entirely from `cassette`, posing as database `db`.
+    pub fn replaying(cassette: Arc<Cassette>, db: i64, observers: Arc<Vec<Arc<dyn CommandObserver>>>) -> Self {
+        Self { inner: None, db, observers, prefix: None, cassette: Some(cassette) }
+    }
+
+    /// Records every round trip to `cassette`, or replays from it.
+    pub fn with_cassette(mut self, cassette: Option<Arc<Cassette>>) -> Self {
+        self.cassette = cassette;
+        self
+    }
+
+    pub fn with_key_prefix(mut self, prefix: Option<Arc<str>>) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn key_prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    fn admit(&self, cmd: &Cmd) -> RedisResult<()> {
+        self.observers.iter().try_for_each(|observer| observer.admit(cmd))
+    }
+
+    fn observe(&self, cmd: &Cmd) {
+        for observer in self.observers.iter() {
+            observer.on_command(cmd);
+        }
+    }
+
+    fn observe_reply(&self, sent: Instant) {
+        let elapsed = sent.elapsed();
+        for observer in self.observers.iter() {
+            observer.on_reply(elapsed);
+        }
+    }
+
+    fn server(&mut self) -> RedisResult<&mut ConnectionManager> {
+        self.inner
+            .as_mut()
+            .ok_or_else(|| RedisError::from((ErrorKind::ClientError, "Replaying a cassette: no server connection")))
+    }
+
+    /// Sends one command, through the cassette when there is one.
+    async fn send(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        let Some(cassette) = self.cassette.clone() else {
+            return self.server()?.req_packed_command(cmd).await;
+        };
+        let commands = recorded(&cmd.get_packed_command());
+        if cassette.is_replay() {
+            return Ok(cassette.play(&commands)?.pop().unwrap_or(Value::Nil));
+        }
+        let value = self.server()?.req_packed_command(cmd).await;
+        cassette.save(commands, value.as_ref().map(std::slice::from_ref));
+        value
+    }
+
+    async fn send_pipeline(&mut self, pipeline: &Pipeline, offset: usize, count: usize) -> RedisResult<Vec<Value>> {
+        let Some(cassette) = self.cassette.clone() else {
+            return self.server()?.req_packed_commands(pipeline, offset, count).await;
+        };
+        let commands = recorded(&pipeline.get_packed_pipeline());
+        if cassette.is_replay() {
+            return cassette.play(&commands);
+        }
+        let values = self.server()?.req_packed_commands(pipeline, offset, count).await;
+        cassette.save(commands, values.as_deref());
+        values
+    }
+}
+
+/// Commands as a cassette keys them: arguments as (lossy) text.
+fn recorded(packed: &[u8]) -> Vec<Vec<String>> {
+    unpack(packed).iter().map(|args| args.iter().map(|arg| lossy(arg)).collect()).collect()
+}
+
+impl ConnectionLike for RedisConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let Some(prefix) = self.prefix.clone() else {
+            if let Err(e) = self.admit(cmd) {
+                return Box::pin(async move { Err(e) });
+            }
+            self.observe(cmd);
+            return Box::pin(async move {
+                let sent = Instant::now();
+                let value = self.send(cmd).await;
+                self.observe_reply(sent);
+                value
+            });
+        };
+        let (name, cmd) = prefixed(cmd, &prefix);
+        if let Err(e) = self.admit(&cmd) {
+            return Box::pin(async move { Err(e) });
+        }
+        self.observe(&cmd);
+        Box::pin(async move {
+            let sent = Instant::now();
+            let value = self.send(&cmd).await;
+            self.observe_reply(sent);
+            Ok(strip_reply(&name, value?, &prefix))
+        })
+    }
+
+    fn req_packed_commands<'a>(&'a mut self, cmd: &'a Pipeline, offset: usize, count: usize) -> RedisFuture<'a, Vec<Value>> {
+        let Some(prefix) = self.prefix.clone() else {
+            // One refused command refuses the whole pipeline.
+            if let Err(e) = cmd.cmd_iter().try_for_each(|command| self.admit(command)) {
+                return Box::pin(async move { Err(e) });
+            }
+            for command in cmd.cmd_iter() {
+                self.observe(command);
+            }
+            return Box::pin(async move {
+                let sent = Instant::now();
+                let values = self.send_pipeline(cmd, offset, count).await;
+                self.observe_reply(sent);
+                values
+            });
+        };
+        // The packed form includes MULTI/EXEC for atomic pipelines, so
+        // sending it back as a plain pipeline keeps the transaction.
+        let mut names = Vec::new();
+        let mut pipeline = Pipeline::new();
+        for args in unpack(&cmd.get_packed_pipeline()) {
+            let (name, command) = prefixed(&pack(&args), &prefix);
+            if let Err(e) = self.admit(&command) {
+                return Box::pin(async move { Err(e) });
+            }
+            names.push(name);
+            pipeline.add_command(command);
+        }
+        for (name, command) in names.iter().zip(pipeline.cmd_iter()) {
+            if name != "MULTI" && name != "EXEC" {
+                self.observe(command);
+            }
+        }
+        Box::pin(async move {
+            let sent = Instant::now();
+            let values = self.send_pipeline(&pipeline, offset, count).await;
+            self.observe_reply(sent);
+            let values = values?;
+            let atomic = names.first().is_some_and(|name| name == "MULTI");
+            Ok(values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| match value {
+                    Value::Bulk(replies) if atomic => Value::Bulk(
+                        replies
+                            .into_iter()
+                            .zip(&names[1..])
+                            .map(|(reply, name)| strip_reply(name, reply, &prefix))
+                            .collect(),
+                    ),
+                    value => match names.get(offset + i) {
+                        Some(name) if !atomic => strip_reply(name, value, &prefix),
+                        _ => value,
+                    },
+                })
+                .collect())
+        })
+    }
+
+    fn get_db(&self) -> i64 {
+        self.db
+    }
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Indexes into `rest` (the arguments after the command name) that hold
+/// keys. Key positions come from a table of common commands; anything
+/// unknown is assumed to take a single key as its first argument. Panics
+/// on a multi-key command whose keys it can't find (a missing numkeys, or
+/// MIGRATE), rather than quietly treating the wrong argument as the key.
+fn key_positions(name: &str, rest: &[String]) -> Vec<usize> {
+    let numkeys = |at: usize| match rest.get(at).and_then(|n| n.parse::<usize>().ok()) {
+        Some(n) => n,
+        None => panic!("{} needs a key count at argument {}", name, at + 1),
+    };
+    // The key after STORE (or STOREDIST), looked for from the options on.
+    let store = |from: usize| {
+        let is_store = |arg: &&String| arg.eq_ignore_ascii_case("STORE") || arg.eq_ignore_ascii_case("STOREDIST");
+        let at = rest.iter().skip(from).position(|arg| is_store(&arg))?;
+        Some(from + at + 1).filter(|at| *at < rest.len())
+    };
+    let all = 0..rest.len();
+
+    match name {
+        "PING" | "ECHO" | "INFO" | "CONFIG" | "SCAN" | "KEYS" | "DBSIZE" | "FLUSHDB" | "FLUSHALL" | "SELECT"
+        | "CLIENT" | "PUBLISH" | "SUBSCRIBE" | "PSUBSCRIBE" | "UNSUBSCRIBE" | "PUNSUBSCRIBE" | "PUBSUB"
+        | "SCRIPT" | "MULTI" | "EXEC" | "DISCARD" | "UNWATCH" | "TIME" | "RANDOMKEY" | "SLOWLOG" | "LATENCY"
+        | "CLUSTER" | "COMMAND" | "HELLO" | "AUTH" | "READONLY" | "WAIT" | "REPLICAOF" | "SLAVEOF" | "ROLE"
+        | "SAVE" | "BGSAVE" | "LASTSAVE" | "DEBUG" | "FUNCTION" | "MONITOR" | "ACL" | "MODULE" | "SWAPDB" => {
+            Vec::new()
+        }
+        "DEL" | "UNLINK" | "EXISTS" | "TOUCH" | "MGET" | "WATCH" | "SINTER" | "SUNION" | "SDIFF" | "PFCOUNT"
+        | "PFMERGE" | "SINTERSTORE" | "SUNIONSTORE" | "SDIFFSTORE" => all.collect(),
+        "MSET" | "MSETNX" => all.step_by(2).collect(),
+        "RENAME" | "RENAMENX" | "SMOVE" | "RPOPLPUSH" | "LMOVE" | "BLMOVE" | "COPY" | "ZRANGESTORE" | "LCS"
+        | "GEOSEARCHSTORE" => {
+            all.take(2).collect()
+        }
+        "BLPOP" | "BRPOP" | "BZPOPMIN" | "BZPOPMAX" => all.take(rest.len().saturating_sub(1)).collect(),
+        "BITOP" => all.skip(1).collect(),
+        "OBJECT" | "MEMORY" | "XGROUP" | "XINFO" => all.skip(1).take(1).collect(),
+        "EVAL" | "EVALSHA" | "FCALL" => all.skip(2).take(numkeys(1)).collect(),
+        "ZUNIONSTORE" | "ZINTERSTORE" | "ZDIFFSTORE" => (0..1).chain(all.skip(2).take(numkeys(1))).collect(),
+        "ZUNION" | "ZINTER" | "ZDIFF" | "SINTERCARD" | "ZINTERCARD" | "LMPOP" | "ZMPOP" => {
+            all.skip(1).take(numkeys(0)).collect()
+        }
+        "BLMPOP" | "BZMPOP" => all.skip(2).take(numkeys(1)).collect(),
+        "SORT" => (0..1).chain(store(1)).collect(),
+        "GEORADIUSBYMEMBER" => (0..1).chain(store(4)).collect(),
+        "GEORADIUS" => (0..1).chain(store(5)).collect(),
+        "XREAD" | "XREADGROUP" => match rest.iter().position(|arg| arg.eq_ignore_ascii_case("STREAMS")) {
+            Some(at) => (at + 1..at + 1 + (rest.len() - at - 1) / 2).collect(),
+            None => Vec::new(),
+        },
+        "MIGRATE" => panic!("MIGRATE key positions aren't supported"),
+        _ => all.take(1).collect(),
+    }
+}
+
+/// Upper-cased command name and the keys it touches; see [`key_positions`].
+pub fn command_keys(cmd: &Cmd) -> (String, Vec<String>) {
+    let args: Vec<String> = cmd
+        .args_iter()
+        .filter_map(|arg| match arg {
+            Arg::Simple(bytes) => Some(lossy(bytes)),
+            Arg::Cursor => None,
+        })
+        .collect();
+    let Some((name, rest)) = args.split_first() else {
+        return (String::new(), Vec::new());
+    };
+    let name = name.to_ascii_uppercase();
+    let keys = key_positions(&name, rest).into_iter().map(|at| rest[at].clone()).collect();
+    (name, keys)
+}
+
+/// Escapes glob metacharacters so a prefix matches literally in SCAN MATCH.
+pub(crate) fn escape_glob(prefix: &str) -> String {
+    let mut escaped = String::with_capacity(prefix.len());
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Rewrites one command's arguments so every key it names lives under
+/// `prefix`. SCAN and KEYS patterns are scoped to the prefix too, so a
+/// bare `SCAN 0` only ever sees this client's keys.
+pub fn prefix_args(args: &mut Vec<Vec<u8>>, prefix: &str) {
+    let strings: Vec<String> = args.iter().map(|arg| lossy(arg)).collect();
+    let Some((name, rest)) = strings.split_first() else {
+        return;
+    };
+    let scoped = |pattern: &[u8]| [escape_glob(prefix).as_bytes(), pattern].concat();
+    match name.to_ascii_uppercase().as_str() {
+        "SCAN" => match rest.iter().position(|arg| arg.eq_ignore_ascii_case("MATCH")) {
+            Some(at) if at + 2 < args.len() => args[at + 2] = scoped(&args[at + 2]),
+            _ => {
+                args.push(b"MATCH".to_vec());
+                args.push(scoped(b"*"));
+            }
+        },
+        "KEYS" if args.len() > 1 => args[1] = scoped(&args[1]),
+        name => {
+            for at in key_positions(name, rest) {
+                args[at + 1] = [prefix.as_bytes(), &args[at + 1]].concat();
+            }
+        }
+    }
+}
+
+fn strip_key(value: Value, prefix: &str) -> Value {
+    match value {
+        Value::Data(bytes) if bytes.starts_with(prefix.as_bytes()) => Value::Data(bytes[prefix.len()..].to_vec()),
+        other => other,
+    }
+}
+
+fn strip_first(value: Value, prefix: &str) -> Value {
+    match value {
+        Value::Bulk(mut items) if !items.is_empty() => {
+            items[0] = strip_key(std::mem::replace(&mut items[0], Value::Nil), prefix);
+            Value::Bulk(items)
+        }
+        other => other,
+    }
+}
+
+/// Takes `prefix` back off the key names in replies that carry them, so
+/// callers see the same names they sent.
+pub fn strip_reply(name: &str, value: Value, prefix: &str) -> Value {
+    let each = |value: Value, f: &dyn Fn(Value) -> Value| match value {
+        Value::Bulk(items) => Value::Bulk(items.into_iter().map(f).collect()),
+        other => other,
+    };
+    match name {
+        "KEYS" => each(value, &|key| strip_key(key, prefix)),
+        "RANDOMKEY" => strip_key(value, prefix),
+        "BLPOP" | "BRPOP" | "BZPOPMIN" | "BZPOPMAX" => strip_first(value, prefix),
+        "XREAD" | "XREADGROUP" => each(value, &|stream| strip_first(stream, prefix)),
+        "SCAN" => match value {
+            Value::Bulk(mut parts) if parts.len() == 2 => {
+                let keys = parts.pop().unwrap_or(Value::Nil);
+                parts.push(each(keys, &|key| strip_key(key, prefix)));
+                Value::Bulk(parts)
+            }
+            other => other,
+        },
+        _ => value,
+    }
+}
+
+/// Splits a packed command or pipeline back into each command's arguments.
+/// Going through the packed form resolves `cursor_arg` placeholders.
+fn unpack(packed: &[u8]) -> Vec<Vec<Vec<u8>>> {
+    // redis-rs packs well-formed arrays of bulk strings, so nothing is lost
+    // by skipping anything else.
+    resp::parse_all(packed).unwrap_or_default().iter().filter_map(Frame::as_command).collect()
+}
+
+fn pack(args: &[Vec<u8>]) -> Cmd {
+    let mut cmd = Cmd::new();
+    for arg in args {
+        cmd.arg(&arg[..]);
+    }
+    cmd
+}
+
+/// A command with its keys moved under `prefix`, and its upper-cased name
+/// for [`strip_reply`].
+pub fn prefixed(cmd: &Cmd, prefix: &str) -> (String, Cmd) {
+    let mut args = unpack(&cmd.get_packed_command()).pop().unwrap_or_default();
+    prefix_args(&mut args, prefix);
+    let name = args.first().map(|name| lossy(name).to_ascii_uppercase()).unwrap_or_default();
+    (name, pack(&args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys_of(cmd: &Cmd) -> Vec<String> {
+        command_keys(cmd).1
+    }
+
+    #[test]
+    fn test_single_key_commands() {
+        let (name, keys) = command_keys(redis::cmd("get").arg("user:1"));
+        assert_eq!(name, "GET");
+        assert_eq!(keys, vec!["user:1"]);
+        assert_eq!(keys_of(redis::cmd("HSET").arg("h").arg("f").arg("v")), vec!["h"]);
+        assert!(keys_of(&redis::cmd("PING")).is_empty());
+    }
+
+    #[test]
+    fn test_multi_key_commands() {
+        assert_eq!(keys_of(redis::cmd("MGET").arg("a").arg("b")), vec!["a", "b"]);
+        assert_eq!(keys_of(redis::cmd("MSET").arg("a").arg(1).arg("b").arg(2)), vec!["a", "b"]);
+        assert_eq!(keys_of(redis::cmd("EVALSHA").arg("sha").arg(2).arg("k1").arg("k2").arg("argv")), vec!["k1", "k2"]);
+        assert_eq!(
+            keys_of(redis::cmd("ZUNIONSTORE").arg("dest").arg(2).arg("z1").arg("z2").arg("WEIGHTS").arg(1).arg(2)),
+            vec!["dest", "z1", "z2"]
+        );
+        assert_eq!(
+            keys_of(redis::cmd("XREAD").arg("COUNT").arg(10).arg("STREAMS").arg("s1").arg("s2").arg("0").arg("0")),
+            vec!["s1", "s2"]
+        );
+        assert_eq!(keys_of(redis::cmd("MEMORY").arg("USAGE").arg("big")), vec!["big"]);
+    }
+
+    #[test]
+    fn test_numkeys_first_commands() {
+        assert_eq!(keys_of(redis::cmd("ZUNION").arg(2).arg("z1").arg("z2").arg("WITHSCORES")), vec!["z1", "z2"]);
+        assert_eq!(keys_of(redis::cmd("ZINTER").arg(1).arg("z1").arg("WEIGHTS").arg(2)), vec!["z1"]);
+        assert_eq!(keys_of(redis::cmd("ZDIFF").arg(2).arg("z1").arg("z2")), vec!["z1", "z2"]);
+        assert_eq!(keys_of(redis::cmd("SINTERCARD").arg(2).arg("s1").arg("s2").arg("LIMIT").arg(5)), vec!["s1", "s2"]);
+        assert_eq!(keys_of(redis::cmd("ZINTERCARD").arg(2).arg("z1").arg("z2")), vec!["z1", "z2"]);
+        assert_eq!(keys_of(redis::cmd("LMPOP").arg(2).arg("l1").arg("l2").arg("LEFT")), vec!["l1", "l2"]);
+        assert_eq!(keys_of(redis::cmd("ZMPOP").arg(1).arg("z1").arg("MIN").arg("COUNT").arg(3)), vec!["z1"]);
+        assert_eq!(keys_of(redis::cmd("BLMPOP").arg(5).arg(2).arg("l1").arg("l2").arg("RIGHT")), vec!["l1", "l2"]);
+    }
+
+    #[test]
+    fn test_two_key_and_store_commands() {
+        assert_eq!(keys_of(redis::cmd("LCS").arg("a").arg("b").arg("LEN")), vec!["a", "b"]);
+        assert_eq!(
+            keys_of(redis::cmd("GEOSEARCHSTORE").arg("dest").arg("src").arg("FROMMEMBER").arg("m").arg("BYRADIUS").arg(5).arg("km")),
+            vec!["dest", "src"]
+        );
+        assert_eq!(keys_of(redis::cmd("SORT").arg("list").arg("ALPHA").arg("STORE").arg("sorted")), vec!["list", "sorted"]);
+        assert_eq!(keys_of(redis::cmd("SORT").arg("list").arg("DESC")), vec!["list"]);
+        assert_eq!(
+            keys_of(redis::cmd("GEORADIUS").arg("geo").arg(13.3).arg(38.1).arg(200).arg("km").arg("STOREDIST").arg("near")),
+            vec!["geo", "near"]
+        );
+        assert_eq!(
+            keys_of(redis::cmd("GEORADIUSBYMEMBER").arg("geo").arg("store").arg(200).arg("km").arg("STORE").arg("near")),
+            vec!["geo", "near"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ZUNION needs a key count")]
+    fn test_missing_numkeys_fails_loudly() {
+        keys_of(redis::cmd("ZUNION").arg("z1").arg("z2"));
+    }
+
+    fn args_of(cmd: &Cmd) -> Vec<String> {
+        cmd.args_iter()
+            .map(|arg| match arg {
+                Arg::Simple(bytes) => lossy(bytes),
+                Arg::Cursor => "<cursor>".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_prefixing_rewrites_only_keys() {
+        let rewrite = |cmd: &Cmd| args_of(&prefixed(cmd, "demo:").1);
+        assert_eq!(rewrite(redis::cmd("SET").arg("user:1").arg("user:2")), ["SET", "demo:user:1", "user:2"]);
+        assert_eq!(rewrite(redis::cmd("MSET").arg("a").arg("b").arg("c").arg("d")), ["MSET", "demo:a", "b", "demo:c", "d"]);
+        assert_eq!(rewrite(redis::cmd("BLPOP").arg("q1").arg("q2").arg(5)), ["BLPOP", "demo:q1", "demo:q2", "5"]);
+        assert_eq!(
+            rewrite(redis::cmd("XGROUP").arg("CREATE").arg("events").arg("workers").arg("$")),
+            ["XGROUP", "CREATE", "demo:events", "workers", "$"]
+        );
+        assert_eq!(rewrite(&redis::cmd("PING")), ["PING"]);
+    }
+
+    #[test]
+    fn test_prefixing_scopes_patterns() {
+        let rewrite = |cmd: &Cmd, prefix: &str| args_of(&prefixed(cmd, prefix).1);
+        assert_eq!(rewrite(redis::cmd("KEYS").arg("user:*"), "demo:"), ["KEYS", "demo:user:*"]);
+        assert_eq!(
+            rewrite(redis::cmd("SCAN").cursor_arg(42).arg("MATCH").arg("user:*").arg("COUNT").arg(10), "demo:"),
+            ["SCAN", "42", "MATCH", "demo:user:*", "COUNT", "10"]
+        );
+        assert_eq!(rewrite(redis::cmd("SCAN").arg(0), "demo:"), ["SCAN", "0", "MATCH", "demo:*"]);
+        assert_eq!(rewrite(redis::cmd("SCAN").arg(0), "t[1]*:"), ["SCAN", "0", "MATCH", "t\\[1\\]\\*:*"]);
+    }
+
+    #[test]
+    fn test_replies_lose_the_prefix() {
+        let data = |s: &str| Value::Data(s.as_bytes().to_vec());
+        let scan = Value::Bulk(vec![data("0"), Value::Bulk(vec![data("demo:a"), data("demo:b")])]);
+        assert_eq!(strip_reply("SCAN", scan, "demo:"), Value::Bulk(vec![data("0"), Value::Bulk(vec![data("a"), data("b")])]));
+        assert_eq!(strip_reply("BLPOP", Value::Bulk(vec![data("demo:q"), data("demo:job")]), "demo:"), Value::Bulk(vec![data("q"), data("demo:job")]));
+        assert_eq!(strip_reply("RANDOMKEY", data("other"), "demo:"), data("other"));
+        assert_eq!(strip_reply("GET", data("demo:value"), "demo:"), data("demo:value"));
+    }
+}
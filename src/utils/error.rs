@@ -19,10 +19,63 @@ pub enum DemoError {
     
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    
+    #[error("Queue '{0}' is full")]
+    QueueFull(String),
+    
+    #[error("Timed out waiting for lock '{0}'")]
+    LockTimeout(String),
+    
+    #[error("Validation failed: {0}")]
+    Validation(String),
+    
+    /// An HTTP call failed: `status` is `None` when there was no response.
+    #[error("HTTP error: {message}")]
+    Http { status: Option<u16>, message: String },
 }
 
 pub type Result<T> = std::result::Result<T, DemoError>;
 
+/// Whether repeating what failed can help.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The connection dropped, timed out or was refused, or the server is
+    /// loading, failing over or asked to try again (for HTTP: 429 or 5xx):
+    /// the same request may well succeed in a moment.
+    Retryable,
+    /// The server rejected the command itself, or the problem is on this
+    /// side; retrying gives the same answer.
+    Permanent,
+}
+
+impl DemoError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            DemoError::Redis(e) if is_transient(e) => ErrorClass::Retryable,
+            // r2d2 gives up after its connection timeout
+            DemoError::Pool(_) => ErrorClass::Retryable,
+            DemoError::Http { status: None | Some(429 | 500..=599), .. } => ErrorClass::Retryable,
+            _ => ErrorClass::Permanent,
+        }
+    }
+    
+    pub fn is_retryable(&self) -> bool {
+        self.class() == ErrorClass::Retryable
+    }
+}
+
+fn is_transient(e: &redis::RedisError) -> bool {
+    use redis::ErrorKind;
+    e.is_io_error()
+        || e.is_timeout()
+        || e.is_connection_dropped()
+        || e.is_connection_refusal()
+        || matches!(
+            e.kind(),
+            ErrorKind::BusyLoadingError | ErrorKind::TryAgain | ErrorKind::ClusterDown | ErrorKind::MasterDown | ErrorKind::ReadOnly
+        )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -39,6 +92,19 @@ mod tests {
         assert_eq!(error.to_string(), "Demo-specific error: Something went wrong");
     }
     
+    #[test]
+    fn test_queue_full_error() {
+        let error = DemoError::QueueFull("emails".to_string());
+        assert_eq!(error.to_string(), "Queue 'emails' is full");
+    }
+    
+    #[test]
+    fn test_lock_timeout_error() {
+        let error = DemoError::LockTimeout("reports".to_string());
+        assert_eq!(error.to_string(), "Timed out waiting for lock 'reports'");
+        assert!(!error.is_retryable());
+    }
+    
     #[test]
     fn test_redis_error_conversion() {
         let redis_err = redis::RedisError::from((
@@ -50,6 +116,21 @@ mod tests {
         assert!(demo_err.to_string().contains("Redis error"));
     }
     
+    #[test]
+    fn test_error_classes() {
+        let redis_error = |kind, detail: &str| DemoError::Redis(redis::RedisError::from((kind, "Test error", detail.to_string())));
+        let dropped = DemoError::Redis(redis::RedisError::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset)));
+        assert_eq!(dropped.class(), ErrorClass::Retryable);
+        assert!(redis_error(redis::ErrorKind::BusyLoadingError, "LOADING").is_retryable());
+        assert!(redis_error(redis::ErrorKind::ReadOnly, "READONLY").is_retryable());
+        assert!(!redis_error(redis::ErrorKind::TypeError, "WRONGTYPE").is_retryable());
+        assert!(!redis_error(redis::ErrorKind::AuthenticationFailed, "WRONGPASS").is_retryable());
+        assert_eq!(DemoError::Configuration("bad".to_string()).class(), ErrorClass::Permanent);
+        let http = |status| DemoError::Http { status, message: String::new() };
+        assert!(http(None).is_retryable() && http(Some(503)).is_retryable() && http(Some(429)).is_retryable());
+        assert!(!http(Some(404)).is_retryable());
+    }
+    
     #[test]
     fn test_io_error_conversion() {
         let io_err = std::io::Error::new(
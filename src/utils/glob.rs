@@ -0,0 +1,53 @@
+/// Redis-style glob matching, as used by KEYS and SCAN MATCH.
+pub fn glob_match(pattern: &[u8], subject: &[u8]) -> bool {
+    match pattern.first() {
+        None => subject.is_empty(),
+        Some(b'*') => (0..=subject.len()).any(|skip| glob_match(&pattern[1..], &subject[skip..])),
+        Some(b'?') => !subject.is_empty() && glob_match(&pattern[1..], &subject[1..]),
+        Some(b'[') => {
+            let Some(&c) = subject.first() else {
+                return false;
+            };
+            let mut i = 1;
+            let negate = pattern.get(i) == Some(&b'^');
+            if negate {
+                i += 1;
+            }
+            let mut matched = false;
+            while i < pattern.len() && pattern[i] != b']' {
+                if pattern[i] == b'\\' && i + 1 < pattern.len() {
+                    i += 1;
+                    matched |= pattern[i] == c;
+                } else if i + 2 < pattern.len() && pattern[i + 1] == b'-' && pattern[i + 2] != b']' {
+                    let (lo, hi) = (pattern[i].min(pattern[i + 2]), pattern[i].max(pattern[i + 2]));
+                    matched |= (lo..=hi).contains(&c);
+                    i += 2;
+                } else {
+                    matched |= pattern[i] == c;
+                }
+                i += 1;
+            }
+            let rest = if i < pattern.len() { &pattern[i + 1..] } else { &pattern[i..] };
+            matched != negate && glob_match(rest, &subject[1..])
+        }
+        Some(b'\\') if pattern.len() > 1 => subject.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &subject[1..]),
+        Some(&p) => subject.first() == Some(&p) && glob_match(&pattern[1..], &subject[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"user:*", b"user:1"));
+        assert!(glob_match(b"h?llo", b"hello"));
+        assert!(glob_match(b"h[ae]llo", b"hallo"));
+        assert!(!glob_match(b"h[^e]llo", b"hello"));
+        assert!(glob_match(b"h[a-c]llo", b"hbllo"));
+        assert!(glob_match(b"demo\\*", b"demo*"));
+        assert!(!glob_match(b"demo\\*", b"demox"));
+        assert!(!glob_match(b"user:*", b"session:1"));
+    }
+}
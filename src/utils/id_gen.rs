@@ -0,0 +1,94 @@
+use crate::utils::error::Result;
+use crate::utils::RedisConnection;
+use redis::AsyncCommands;
+
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Unique, increasing ids shared by every process using the same `name`.
+/// Ids are reserved from Redis in blocks (INCRBY), so most calls are served
+/// locally; ids left in a block when a process exits are simply skipped.
+pub struct IdGenerator {
+    conn: RedisConnection,
+    key: String,
+    block_size: u64,
+    next: u64,
+    end: u64,
+}
+
+impl IdGenerator {
+    pub fn new(conn: RedisConnection, name: &str, block_size: u64) -> Self {
+        Self {
+            conn,
+            key: format!("ids:{}", name),
+            block_size: block_size.max(1),
+            next: 1,
+            end: 0,
+        }
+    }
+
+    pub async fn next_id(&mut self) -> Result<u64> {
+        if self.next > self.end {
+            let end: u64 = self.conn.incr(&self.key, self.block_size).await?;
+            self.end = end;
+            self.next = end - self.block_size + 1;
+        }
+        let id = self.next;
+        self.next += 1;
+        Ok(id)
+    }
+
+    pub async fn next_ids(&mut self, count: usize) -> Result<Vec<u64>> {
+        let mut ids = Vec::with_capacity(count);
+        for _ in 0..count {
+            ids.push(self.next_id().await?);
+        }
+        Ok(ids)
+    }
+}
+
+/// Crockford base32 (no I, L, O or U), left-padded with zeros to `width`.
+/// Compact and safe to read aloud or type from a printed coupon.
+pub fn encode_crockford(mut value: u64, width: usize) -> String {
+    let mut out = Vec::new();
+    while value > 0 {
+        out.push(CROCKFORD[(value % 32) as usize]);
+        value /= 32;
+    }
+    while out.len() < width {
+        out.push(b'0');
+    }
+    out.reverse();
+    String::from_utf8(out).expect("alphabet is ASCII")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+
+    #[test]
+    fn test_encode_crockford() {
+        assert_eq!(encode_crockford(0, 4), "0000");
+        assert_eq!(encode_crockford(31, 1), "Z");
+        assert_eq!(encode_crockford(32, 1), "10");
+        assert_eq!(encode_crockford(1234567, 5), "15NM7");
+    }
+
+    #[tokio::test]
+    async fn test_ids_are_unique_across_generators() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del("ids:id_gen_test").await.unwrap();
+
+        let mut a = IdGenerator::new(conn.clone(), "id_gen_test", 10);
+        let mut b = IdGenerator::new(conn.clone(), "id_gen_test", 10);
+        let mut ids = a.next_ids(15).await.unwrap();
+        ids.extend(b.next_ids(15).await.unwrap());
+        let mut unique = ids.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), 30);
+
+        let _: () = conn.del("ids:id_gen_test").await.unwrap();
+    }
+}
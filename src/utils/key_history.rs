@@ -0,0 +1,471 @@
+use crate::demos::streams::entry_fields;
+use crate::utils::capped::add_capped_stream;
+use crate::utils::connection::{command_keys, CommandObserver};
+use crate::utils::glob::glob_match;
+use crate::utils::key_stats::is_write_command;
+use crate::utils::RedisConnection;
+use crate::{DemoError, Result};
+use redis::streams::StreamRangeReply;
+use redis::{Arg, AsyncCommands, Cmd};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::{self, Write};
+use std::sync::Mutex;
+
+/// Changes kept per key; older history is trimmed.
+pub const HISTORY_MAX_LEN: usize = 1000;
+
+/// Changes buffered between flushes; later ones are dropped and counted.
+const PENDING_MAX: usize = 50_000;
+
+/// Longest value shown per line before it is cut off.
+const VALUE_WIDTH: usize = 100;
+
+pub fn history_key(key: &str) -> String {
+    format!("history:{}", key)
+}
+
+/// One recorded write to a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub at_ms: i64,
+    /// The demo step running when the command went out.
+    pub step: String,
+    pub command: String,
+    /// Everything after the command name, keys without the client prefix.
+    pub args: Vec<String>,
+}
+
+impl fmt::Display for KeyEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.command)?;
+        for arg in &self.args {
+            match arg.is_empty() || arg.contains(char::is_whitespace) {
+                true => write!(f, " {:?}", arg)?,
+                false => write!(f, " {}", arg)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Pending {
+    events: Vec<(String, KeyEvent)>,
+    dropped: usize,
+}
+
+/// Records every write to keys matching its patterns, with the demo step
+/// that caused it, so `key history` can replay a key's values later.
+/// Register it as a [`CommandObserver`]; writes are buffered in process and
+/// land in `history:<key>` streams on [`flush`](Self::flush).
+///
+/// Keys are recorded and matched without the client's key prefix.
+pub struct KeyHistory {
+    patterns: Vec<String>,
+    prefix: String,
+    step: Mutex<String>,
+    pending: Mutex<Pending>,
+}
+
+impl KeyHistory {
+    pub fn new(patterns: Vec<String>, prefix: Option<&str>) -> Self {
+        Self {
+            patterns,
+            prefix: prefix.unwrap_or_default().to_string(),
+            step: Mutex::new(String::new()),
+            pending: Mutex::new(Pending::default()),
+        }
+    }
+
+    /// Labels the changes from now on; returns the previous label.
+    pub fn set_step(&self, step: &str) -> String {
+        std::mem::replace(&mut *self.step.lock().unwrap_or_else(|e| e.into_inner()), step.to_string())
+    }
+
+    fn records(&self, key: &str) -> bool {
+        !key.starts_with("history:") && self.patterns.iter().any(|pattern| glob_match(pattern.as_bytes(), key.as_bytes()))
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).events.len()
+    }
+
+    /// Appends the buffered changes to their keys' streams. Returns how many
+    /// were written and how many were dropped since the last flush.
+    pub async fn flush(&self, conn: &mut RedisConnection) -> Result<(usize, usize)> {
+        let Pending { events, dropped } = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        for (key, event) in &events {
+            let at = event.at_ms.to_string();
+            let args = serde_json::to_string(&event.args)?;
+            let fields = [("at", at.as_str()), ("step", event.step.as_str()), ("cmd", event.command.as_str()), ("args", args.as_str())];
+            add_capped_stream(conn, &history_key(key), &fields, HISTORY_MAX_LEN).await?;
+        }
+        Ok((events.len(), dropped))
+    }
+}
+
+impl CommandObserver for KeyHistory {
+    fn on_command(&self, cmd: &Cmd) {
+        let (name, keys) = command_keys(cmd);
+        if !is_write_command(&name) {
+            return;
+        }
+        let unprefixed = |arg: String| match keys.contains(&arg) {
+            true => arg.strip_prefix(&self.prefix).map_or(arg.clone(), str::to_string),
+            false => arg,
+        };
+        let args: Vec<String> = cmd
+            .args_iter()
+            .skip(1)
+            .filter_map(|arg| match arg {
+                Arg::Simple(bytes) => Some(unprefixed(String::from_utf8_lossy(bytes).into_owned())),
+                Arg::Cursor => None,
+            })
+            .collect();
+        let step = self.step.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let at_ms = chrono::Utc::now().timestamp_millis();
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        for key in keys.iter().map(|key| key.strip_prefix(&self.prefix).unwrap_or(key)) {
+            if !self.records(key) {
+                continue;
+            }
+            if pending.events.len() >= PENDING_MAX {
+                pending.dropped += 1;
+                continue;
+            }
+            let event = KeyEvent { at_ms, step: step.clone(), command: name.clone(), args: args.clone() };
+            pending.events.push((key.to_string(), event));
+        }
+    }
+}
+
+/// A key's recorded changes, oldest first.
+pub async fn load(conn: &mut RedisConnection, key: &str) -> Result<Vec<KeyEvent>> {
+    let reply: StreamRangeReply = conn.xrange_all(history_key(key)).await?;
+    reply
+        .ids
+        .iter()
+        .map(|entry| {
+            let mut fields = entry_fields(entry);
+            let at_ms = fields
+                .get("at")
+                .and_then(|at| at.parse().ok())
+                .ok_or_else(|| DemoError::Demo(format!("History entry {} has no timestamp", entry.id)))?;
+            Ok(KeyEvent {
+                at_ms,
+                step: fields.remove("step").unwrap_or_default(),
+                command: fields.remove("cmd").unwrap_or_default(),
+                args: serde_json::from_str(fields.get("args").map_or("[]", String::as_str))?,
+            })
+        })
+        .collect()
+}
+
+/// A key's value as rebuilt from its recorded changes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyValue {
+    Missing,
+    String(String),
+    Hash(BTreeMap<String, String>),
+    List(VecDeque<String>),
+    Set(BTreeSet<String>),
+    ZSet(BTreeMap<String, f64>),
+    /// Changed by a command the replay doesn't model, or one whose outcome
+    /// depends on server state (SPOP, RENAME into the key, ...).
+    Unknown,
+}
+
+impl KeyValue {
+    /// The value after `event` ran against `key`. Options that make a write
+    /// conditional (NX, XX, ...) are assumed to have let it through.
+    pub fn apply(self, key: &str, event: &KeyEvent) -> KeyValue {
+        let args = &event.args;
+        // Arguments after the key, for the single-key commands.
+        let rest = match args.split_first() {
+            Some((first, rest)) if first == key => rest,
+            _ => &[][..],
+        };
+        let number = |value: &str| value.parse::<f64>().ok();
+        match (event.command.as_str(), self) {
+            ("DEL" | "UNLINK" | "GETDEL", _) => KeyValue::Missing,
+            ("SET" | "GETSET", _) if !rest.is_empty() => KeyValue::String(rest[0].clone()),
+            ("SETEX" | "PSETEX", _) if rest.len() == 2 => KeyValue::String(rest[1].clone()),
+            ("SETNX", KeyValue::Missing) if rest.len() == 1 => KeyValue::String(rest[0].clone()),
+            ("SETNX", value) => value,
+            ("MSET" | "MSETNX", _) => match args.chunks(2).rfind(|pair| pair.len() == 2 && pair[0] == key) {
+                Some(pair) => KeyValue::String(pair[1].clone()),
+                None => KeyValue::Unknown,
+            },
+            ("APPEND", KeyValue::Missing) if rest.len() == 1 => KeyValue::String(rest[0].clone()),
+            ("APPEND", KeyValue::String(value)) if rest.len() == 1 => KeyValue::String(value + &rest[0]),
+            ("INCR" | "DECR" | "INCRBY" | "DECRBY" | "INCRBYFLOAT", value) => {
+                let current = match value {
+                    KeyValue::Missing => Some(0.0),
+                    KeyValue::String(value) => number(&value),
+                    _ => None,
+                };
+                let delta = match event.command.as_str() {
+                    "INCR" => Some(1.0),
+                    "DECR" => Some(-1.0),
+                    "DECRBY" => rest.first().and_then(|by| number(by)).map(|by| -by),
+                    _ => rest.first().and_then(|by| number(by)),
+                };
+                match current.zip(delta) {
+                    Some((current, delta)) => KeyValue::String(format_number(current + delta)),
+                    None => KeyValue::Unknown,
+                }
+            }
+            ("EXPIRE" | "PEXPIRE" | "EXPIREAT" | "PEXPIREAT" | "PERSIST", value) => value,
+
+            ("HSET" | "HMSET", value @ (KeyValue::Missing | KeyValue::Hash(_))) if rest.len() % 2 == 0 => {
+                let mut fields = value.into_hash();
+                for pair in rest.chunks(2) {
+                    fields.insert(pair[0].clone(), pair[1].clone());
+                }
+                KeyValue::Hash(fields)
+            }
+            ("HSETNX", value @ (KeyValue::Missing | KeyValue::Hash(_))) if rest.len() == 2 => {
+                let mut fields = value.into_hash();
+                fields.entry(rest[0].clone()).or_insert_with(|| rest[1].clone());
+                KeyValue::Hash(fields)
+            }
+            ("HDEL", KeyValue::Hash(mut fields)) => {
+                rest.iter().for_each(|field| {
+                    fields.remove(field);
+                });
+                KeyValue::Hash(fields).or_missing()
+            }
+            ("HINCRBY" | "HINCRBYFLOAT", value @ (KeyValue::Missing | KeyValue::Hash(_))) if rest.len() == 2 => {
+                let mut fields = value.into_hash();
+                let current = fields.get(&rest[0]).map_or(Some(0.0), |value| number(value));
+                match current.zip(number(&rest[1])) {
+                    Some((current, delta)) => {
+                        fields.insert(rest[0].clone(), format_number(current + delta));
+                        KeyValue::Hash(fields)
+                    }
+                    None => KeyValue::Unknown,
+                }
+            }
+
+            ("LPUSH" | "RPUSH", value @ (KeyValue::Missing | KeyValue::List(_))) => {
+                let mut items = match value {
+                    KeyValue::List(items) => items,
+                    _ => VecDeque::new(),
+                };
+                for item in rest {
+                    match event.command.as_str() {
+                        "LPUSH" => items.push_front(item.clone()),
+                        _ => items.push_back(item.clone()),
+                    }
+                }
+                KeyValue::List(items)
+            }
+            ("LPOP" | "RPOP", KeyValue::List(mut items)) => {
+                let count = rest.first().and_then(|count| count.parse().ok()).unwrap_or(1);
+                for _ in 0..count {
+                    match event.command.as_str() {
+                        "LPOP" => items.pop_front(),
+                        _ => items.pop_back(),
+                    };
+                }
+                KeyValue::List(items).or_missing()
+            }
+            ("LPOP" | "RPOP", KeyValue::Missing) => KeyValue::Missing,
+
+            ("SADD", value @ (KeyValue::Missing | KeyValue::Set(_))) => {
+                let mut members = match value {
+                    KeyValue::Set(members) => members,
+                    _ => BTreeSet::new(),
+                };
+                members.extend(rest.iter().cloned());
+                KeyValue::Set(members)
+            }
+            ("SREM", KeyValue::Set(mut members)) => {
+                rest.iter().for_each(|member| {
+                    members.remove(member);
+                });
+                KeyValue::Set(members).or_missing()
+            }
+
+            ("ZADD", value @ (KeyValue::Missing | KeyValue::ZSet(_))) => {
+                let mut members = match value {
+                    KeyValue::ZSet(members) => members,
+                    _ => BTreeMap::new(),
+                };
+                let flags = rest.iter().take_while(|arg| matches!(arg.to_ascii_uppercase().as_str(), "NX" | "XX" | "GT" | "LT" | "CH" | "INCR"));
+                let incr = flags.clone().any(|flag| flag.eq_ignore_ascii_case("INCR"));
+                let pairs = &rest[flags.count()..];
+                if pairs.len() % 2 != 0 {
+                    return KeyValue::Unknown;
+                }
+                for pair in pairs.chunks(2) {
+                    let Some(score) = number(&pair[0]) else {
+                        return KeyValue::Unknown;
+                    };
+                    let previous = if incr { members.get(&pair[1]).copied().unwrap_or(0.0) } else { 0.0 };
+                    members.insert(pair[1].clone(), previous + score);
+                }
+                KeyValue::ZSet(members)
+            }
+            ("ZINCRBY", value @ (KeyValue::Missing | KeyValue::ZSet(_))) if rest.len() == 2 => {
+                let mut members = match value {
+                    KeyValue::ZSet(members) => members,
+                    _ => BTreeMap::new(),
+                };
+                let Some(delta) = number(&rest[0]) else {
+                    return KeyValue::Unknown;
+                };
+                *members.entry(rest[1].clone()).or_insert(0.0) += delta;
+                KeyValue::ZSet(members)
+            }
+            ("ZREM", KeyValue::ZSet(mut members)) => {
+                rest.iter().for_each(|member| {
+                    members.remove(member);
+                });
+                KeyValue::ZSet(members).or_missing()
+            }
+
+            _ => KeyValue::Unknown,
+        }
+    }
+
+    fn into_hash(self) -> BTreeMap<String, String> {
+        match self {
+            KeyValue::Hash(fields) => fields,
+            _ => BTreeMap::new(),
+        }
+    }
+
+    /// Redis deletes a collection when its last element goes.
+    fn or_missing(self) -> KeyValue {
+        let empty = match &self {
+            KeyValue::Hash(fields) => fields.is_empty(),
+            KeyValue::List(items) => items.is_empty(),
+            KeyValue::Set(members) => members.is_empty(),
+            KeyValue::ZSet(members) => members.is_empty(),
+            _ => false,
+        };
+        if empty {
+            KeyValue::Missing
+        } else {
+            self
+        }
+    }
+}
+
+impl fmt::Display for KeyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs = |pairs: Vec<String>| format!("{{{}}}", pairs.join(", "));
+        match self {
+            KeyValue::Missing => write!(f, "(no key)"),
+            KeyValue::Unknown => write!(f, "(unknown)"),
+            KeyValue::String(value) => write!(f, "{:?}", value),
+            KeyValue::Hash(fields) => write!(f, "{}", pairs(fields.iter().map(|(field, value)| format!("{}: {:?}", field, value)).collect())),
+            KeyValue::List(items) => write!(f, "[{}]", items.iter().map(|item| format!("{:?}", item)).collect::<Vec<_>>().join(", ")),
+            KeyValue::Set(members) => write!(f, "{}", pairs(members.iter().map(|member| format!("{:?}", member)).collect())),
+            KeyValue::ZSet(members) => write!(f, "{}", pairs(members.iter().map(|(member, score)| format!("{:?}: {}", member, format_number(*score))).collect())),
+        }
+    }
+}
+
+fn format_number(value: f64) -> String {
+    match value.fract() == 0.0 && value.abs() < 1e15 {
+        true => format!("{}", value as i64),
+        false => value.to_string(),
+    }
+}
+
+/// Each change paired with the value it left behind. The key is assumed
+/// not to exist before its first recorded change.
+pub fn replay(key: &str, events: &[KeyEvent]) -> Vec<KeyValue> {
+    events
+        .iter()
+        .scan(KeyValue::Missing, |value, event| {
+            *value = std::mem::replace(value, KeyValue::Missing).apply(key, event);
+            Some(value.clone())
+        })
+        .collect()
+}
+
+/// The last `count` changes: when, which step, the command and the value
+/// after it.
+pub fn render(key: &str, events: &[KeyEvent], count: usize) -> String {
+    let values = replay(key, events);
+    let skip = events.len().saturating_sub(count);
+    let step_width = events[skip..].iter().map(|event| event.step.chars().count()).max().unwrap_or(0);
+    let mut out = String::new();
+    if skip > 0 {
+        let _ = writeln!(out, "   ... {} earlier changes", skip);
+    }
+    for (event, value) in events.iter().zip(&values).skip(skip) {
+        let at = chrono::DateTime::from_timestamp_millis(event.at_ms).unwrap_or_default();
+        let _ = writeln!(out, "{}  {:<width$}  {}", at.format("%Y-%m-%d %H:%M:%S%.3f"), event.step, event, width = step_width);
+        let mut shown = value.to_string();
+        if shown.chars().count() > VALUE_WIDTH {
+            shown = shown.chars().take(VALUE_WIDTH).collect::<String>() + "…";
+        }
+        let _ = writeln!(out, "   = {}", shown);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(command: &str, args: &[&str]) -> KeyEvent {
+        KeyEvent { at_ms: 0, step: "step".to_string(), command: command.to_string(), args: args.iter().map(|arg| arg.to_string()).collect() }
+    }
+
+    #[test]
+    fn test_records_matching_writes_without_prefix() {
+        let history = KeyHistory::new(vec!["user:*".to_string()], Some("demo:"));
+        history.set_step("signup");
+        history.on_command(redis::cmd("SET").arg("demo:user:1").arg("amy"));
+        history.on_command(redis::cmd("GET").arg("demo:user:1"));
+        history.on_command(redis::cmd("SET").arg("demo:session:1").arg("x"));
+        history.on_command(redis::cmd("MSET").arg("demo:user:2").arg("bo").arg("demo:cart:2").arg("[]"));
+
+        let pending = history.pending.lock().unwrap();
+        let recorded: Vec<_> = pending.events.iter().map(|(key, event)| (key.as_str(), event.to_string(), event.step.as_str())).collect();
+        assert_eq!(recorded, [("user:1", "SET user:1 amy".to_string(), "signup"), ("user:2", "MSET user:2 bo cart:2 []".to_string(), "signup")]);
+    }
+
+    #[test]
+    fn test_replays_strings_and_counters() {
+        let events = [
+            event("SET", &["k", "10"]),
+            event("INCRBY", &["k", "5"]),
+            event("EXPIRE", &["k", "60"]),
+            event("APPEND", &["k", "0"]),
+            event("DEL", &["k"]),
+            event("MSET", &["other", "1", "k", "x"]),
+        ];
+        let values: Vec<String> = replay("k", &events).iter().map(KeyValue::to_string).collect();
+        assert_eq!(values, ["\"10\"", "\"15\"", "\"15\"", "\"150\"", "(no key)", "\"x\""]);
+    }
+
+    #[test]
+    fn test_replays_collections() {
+        let hash = replay("h", &[event("HSET", &["h", "name", "Amy", "age", "30"]), event("HINCRBY", &["h", "age", "1"]), event("HDEL", &["h", "name", "age"])]);
+        assert_eq!(hash[1].to_string(), "{age: \"31\", name: \"Amy\"}");
+        assert_eq!(hash[2], KeyValue::Missing);
+
+        let list = replay("l", &[event("RPUSH", &["l", "a", "b"]), event("LPUSH", &["l", "z"]), event("RPOP", &["l"])]);
+        assert_eq!(list[2].to_string(), "[\"z\", \"a\"]");
+
+        let zset = replay("z", &[event("ZADD", &["z", "1", "a", "2", "b"]), event("ZADD", &["z", "INCR", "5", "a"]), event("ZREM", &["z", "b"])]);
+        assert_eq!(zset[2].to_string(), "{\"a\": 6}");
+
+        let unknown = replay("s", &[event("SADD", &["s", "x"]), event("SPOP", &["s"]), event("SET", &["s", "v"])]);
+        assert_eq!(unknown[1], KeyValue::Unknown);
+        assert_eq!(unknown[2], KeyValue::String("v".to_string()));
+    }
+
+    #[test]
+    fn test_render_keeps_the_latest() {
+        let events = [event("SET", &["k", "a"]), event("SET", &["k", "b b"])];
+        let rendered = render("k", &events, 1);
+        assert_eq!(rendered, "   ... 1 earlier changes\n1970-01-01 00:00:00.000  step  SET k \"b b\"\n   = \"b b\"\n");
+    }
+}
@@ -0,0 +1,136 @@
+use crate::utils::connection::{command_keys, CommandObserver};
+use redis::Cmd;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Commands counted as writes; everything else touching a key is a read.
+const WRITE_COMMANDS: &[&str] = &[
+    "SET", "SETEX", "PSETEX", "SETNX", "GETSET", "GETDEL", "MSET", "MSETNX", "DEL", "UNLINK", "INCR", "INCRBY",
+    "INCRBYFLOAT", "DECR", "DECRBY", "APPEND", "EXPIRE", "PEXPIRE", "EXPIREAT", "PEXPIREAT", "PERSIST", "RENAME",
+    "RENAMENX", "HSET", "HMSET", "HSETNX", "HDEL", "HINCRBY", "HINCRBYFLOAT", "LPUSH", "RPUSH", "LPOP", "RPOP",
+    "LSET", "LREM", "LTRIM", "LMOVE", "RPOPLPUSH", "SADD", "SREM", "SPOP", "SMOVE", "ZADD", "ZREM", "ZINCRBY",
+    "ZPOPMIN", "ZPOPMAX", "ZREMRANGEBYSCORE", "ZREMRANGEBYRANK", "XADD", "XDEL", "XTRIM", "XACK", "PFADD",
+    "PFMERGE", "SETBIT", "BITOP", "GEOADD", "COPY",
+];
+
+/// Whether `name` (upper case) is one of the commands counted as writes.
+pub fn is_write_command(name: &str) -> bool {
+    WRITE_COMMANDS.contains(&name)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyUsage {
+    pub key: String,
+    pub reads: u64,
+    pub writes: u64,
+}
+
+impl KeyUsage {
+    pub fn total(&self) -> u64 {
+        self.reads + self.writes
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    usage: HashMap<String, (KeyUsage, u64)>,
+    /// Last-touch tick -> key, to find the least recently used key.
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    evicted: u64,
+}
+
+/// In-process counts of the keys this process touches, bounded to
+/// `capacity` keys by evicting the least recently used one. Register it as a
+/// [`CommandObserver`] on the `RedisClient`.
+pub struct KeyStats {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+impl KeyStats {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), inner: Mutex::new(Inner::default()) }
+    }
+
+    pub fn record(&self, key: &str, write: bool) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        if !inner.usage.contains_key(key) && inner.usage.len() >= self.capacity {
+            if let Some((_, oldest)) = inner.recency.pop_first() {
+                inner.usage.remove(&oldest);
+                inner.evicted += 1;
+            }
+        }
+        let entry = inner
+            .usage
+            .entry(key.to_string())
+            .or_insert_with(|| (KeyUsage { key: key.to_string(), ..Default::default() }, 0));
+        let previous = entry.1;
+        entry.1 = tick;
+        if write {
+            entry.0.writes += 1;
+        } else {
+            entry.0.reads += 1;
+        }
+        inner.recency.remove(&previous);
+        inner.recency.insert(tick, key.to_string());
+    }
+
+    /// The `n` most used keys, busiest first.
+    pub fn top(&self, n: usize) -> Vec<KeyUsage> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut usage: Vec<KeyUsage> = inner.usage.values().map(|(usage, _)| usage.clone()).collect();
+        usage.sort_by(|a, b| b.total().cmp(&a.total()).then_with(|| a.key.cmp(&b.key)));
+        usage.truncate(n);
+        usage
+    }
+
+    /// Keys dropped to stay within capacity; their counts are lost.
+    pub fn evicted(&self) -> u64 {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).evicted
+    }
+}
+
+impl CommandObserver for KeyStats {
+    fn on_command(&self, cmd: &Cmd) {
+        let (name, keys) = command_keys(cmd);
+        let write = is_write_command(&name);
+        for key in keys {
+            self.record(&key, write);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_reads_and_writes() {
+        let stats = KeyStats::new(10);
+        stats.on_command(redis::cmd("SET").arg("a").arg(1));
+        stats.on_command(redis::cmd("GET").arg("a"));
+        stats.on_command(redis::cmd("MGET").arg("a").arg("b"));
+
+        let top = stats.top(10);
+        assert_eq!(top[0], KeyUsage { key: "a".to_string(), reads: 2, writes: 1 });
+        assert_eq!(top[1], KeyUsage { key: "b".to_string(), reads: 1, writes: 0 });
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let stats = KeyStats::new(2);
+        stats.record("a", false);
+        stats.record("b", false);
+        stats.record("a", false);
+        stats.record("c", false);
+
+        let keys: Vec<String> = stats.top(10).into_iter().map(|u| u.key).collect();
+        assert_eq!(keys, vec!["a", "c"]);
+        assert_eq!(stats.evicted(), 1);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::utils::RedisConnection;
+use crate::{RedisClient, Result};
+use chrono::{DateTime, Local};
+use futures::StreamExt;
+use redis::AsyncCommands;
+use tracing::warn;
+
+/// Longest value printed with `--values` before it is cut off.
+const VALUE_WIDTH: usize = 80;
+
+/// Flags `key watch` needs: every event class on keyspace channels.
+pub const WATCH_FLAGS: &str = "KA";
+
+/// Event classes the `A` alias stands for.
+const ALL_CLASSES: &str = "g$lshzxetd";
+
+/// The `notify-keyspace-events` value that adds each of `needed` missing
+/// from `current`, or `None` if `current` already publishes them.
+pub fn notify_flags(current: &str, needed: &str) -> Option<String> {
+    let covered = |flag: char| current.contains(flag) || (current.contains('A') && ALL_CLASSES.contains(flag));
+    let missing: String = needed.chars().filter(|flag| !covered(*flag)).collect();
+    (!missing.is_empty()).then(|| format!("{}{}", current, missing))
+}
+
+/// A change to the server-wide `notify-keyspace-events` setting that a
+/// watch needs, to be confirmed before [`watch`] makes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotifyChange {
+    pub previous: String,
+    pub flags: String,
+}
+
+impl NotifyChange {
+    pub fn describe(&self) -> String {
+        format!("set notify-keyspace-events from \"{}\" to \"{}\" while watching", self.previous, self.flags)
+    }
+}
+
+/// What `notify-keyspace-events` has to become to publish `needed`
+/// (e.g. [`WATCH_FLAGS`]), or `None` if it already does.
+pub async fn notify_change(client: &RedisClient, needed: &str) -> Result<Option<NotifyChange>> {
+    let mut conn = client.get_async_connection().await?;
+    let current: Vec<String> = redis::cmd("CONFIG").arg("GET").arg("notify-keyspace-events").query_async(&mut conn).await?;
+    let previous = current.get(1).cloned().unwrap_or_default();
+    Ok(notify_flags(&previous, needed).map(|flags| NotifyChange { previous, flags }))
+}
+
+/// Keyspace notifications turned on for the length of a watch. Call
+/// [`restore`](Self::restore) when done; if the guard is dropped instead,
+/// on an error or a panic, it puts the old setting back over a blocking
+/// connection.
+pub struct NotifyGuard {
+    client: RedisClient,
+    previous: Option<String>,
+}
+
+impl NotifyGuard {
+    /// Makes a confirmed `change`, or nothing when there is none.
+    pub async fn apply(client: &RedisClient, change: Option<NotifyChange>) -> Result<Self> {
+        let mut guard = Self { client: client.clone(), previous: None };
+        if let Some(change) = change {
+            let mut conn = client.get_async_connection().await?;
+            println!("Turning on keyspace notifications (notify-keyspace-events \"{}\" → \"{}\") while watching", change.previous, change.flags);
+            let _: () = redis::cmd("CONFIG").arg("SET").arg("notify-keyspace-events").arg(&change.flags).query_async(&mut conn).await?;
+            guard.previous = Some(change.previous);
+        }
+        Ok(guard)
+    }
+
+    pub async fn restore(mut self) -> Result<()> {
+        if let Some(previous) = self.previous.take() {
+            let mut conn = self.client.get_async_connection().await?;
+            let _: () = redis::cmd("CONFIG").arg("SET").arg("notify-keyspace-events").arg(&previous).query_async(&mut conn).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for NotifyGuard {
+    fn drop(&mut self) {
+        let Some(previous) = self.previous.take() else {
+            return;
+        };
+        let restored = self
+            .client
+            .get_sync_connection()
+            .and_then(|mut conn| Ok(redis::cmd("CONFIG").arg("SET").arg("notify-keyspace-events").arg(&previous).query::<()>(&mut conn)?));
+        if let Err(e) = restored {
+            warn!("Could not put notify-keyspace-events back to \"{}\": {}", previous, e);
+        }
+    }
+}
+
+/// One keyspace notification: `event` (set, del, expire, rename_from, ...)
+/// happened to `key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyspaceEvent {
+    pub at: DateTime<Local>,
+    pub key: String,
+    pub event: String,
+}
+
+impl KeyspaceEvent {
+    /// From a `__keyspace@<db>__:<key>` message, with the client's key
+    /// prefix taken off the key.
+    pub fn parse(channel: &str, event: &str, prefix: Option<&str>, at: DateTime<Local>) -> Option<Self> {
+        let key = channel.strip_prefix("__keyspace@")?.split_once("__:")?.1;
+        let key = prefix.and_then(|prefix| key.strip_prefix(prefix)).unwrap_or(key);
+        Some(Self { at, key: key.to_string(), event: event.to_string() })
+    }
+
+    /// Whether the key may still hold a value worth fetching.
+    pub fn leaves_value(&self) -> bool {
+        !matches!(self.event.as_str(), "del" | "expired" | "evicted" | "rename_from")
+    }
+
+    pub fn render(&self, value: Option<&str>) -> String {
+        let mut line = format!("{}  {:<12} {}", self.at.format("%H:%M:%S%.3f"), self.event, self.key);
+        if let Some(value) = value {
+            line.push_str("  = ");
+            match value.chars().count() > VALUE_WIDTH {
+                true => line.extend(value.chars().take(VALUE_WIDTH).chain(std::iter::once('…'))),
+                false => line.push_str(value),
+            }
+        }
+        line
+    }
+}
+
+/// A short look at a key's current value: strings in full, collections
+/// by type and size.
+pub async fn peek(conn: &mut RedisConnection, key: &str) -> Result<String> {
+    let kind: String = redis::cmd("TYPE").arg(key).query_async(conn).await?;
+    let size: Option<usize> = match kind.as_str() {
+        "none" => return Ok("(gone)".to_string()),
+        "string" => {
+            let value: Option<String> = conn.get(key).await?;
+            return Ok(value.map_or("(gone)".to_string(), |value| format!("{:?}", value)));
+        }
+        "hash" => Some(conn.hlen(key).await?),
+        "list" => Some(conn.llen(key).await?),
+        "set" => Some(conn.scard(key).await?),
+        "zset" => Some(conn.zcard(key).await?),
+        "stream" => Some(conn.xlen(key).await?),
+        _ => None,
+    };
+    Ok(match size {
+        Some(size) => format!("({}, {} items)", kind, size),
+        None => format!("({})", kind),
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WatchOptions {
+    /// Fetch and print the value after each event.
+    pub values: bool,
+    /// Stop after this many events.
+    pub limit: Option<usize>,
+}
+
+/// Prints every keyspace event for keys matching `pattern` (under the
+/// client's key prefix) until Ctrl-C or `limit`. Makes `change`, from
+/// [`notify_change`], for the duration and puts the old setting back
+/// afterwards, on errors too. Returns how many events were printed.
+pub async fn watch(client: &RedisClient, pattern: &str, options: &WatchOptions, change: Option<NotifyChange>) -> Result<usize> {
+    let guard = NotifyGuard::apply(client, change).await?;
+    let seen = print_events(client, pattern, options).await?;
+    guard.restore().await?;
+    Ok(seen)
+}
+
+async fn print_events(client: &RedisClient, pattern: &str, options: &WatchOptions) -> Result<usize> {
+    let mut conn = client.get_async_connection().await?;
+    let prefix = client.key_prefix();
+    let db = client.get_connection_info().redis.db;
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.psubscribe(format!("__keyspace@{}__:{}{}", db, prefix.unwrap_or(""), pattern)).await?;
+    println!("Watching {} in db {} (Ctrl-C to stop)\n", pattern, db);
+
+    let mut messages = pubsub.into_on_message();
+    let mut seen = 0;
+    while options.limit.is_none_or(|limit| seen < limit) {
+        let message = tokio::select! {
+            message = messages.next() => message,
+            _ = tokio::signal::ctrl_c() => None,
+        };
+        let Some(message) = message else {
+            break;
+        };
+        let event: String = message.get_payload()?;
+        let Some(event) = KeyspaceEvent::parse(message.get_channel_name(), &event, prefix, Local::now()) else {
+            continue;
+        };
+        let value = match options.values && event.leaves_value() {
+            true => Some(peek(&mut conn, &event.key).await?),
+            false => None,
+        };
+        println!("{}", event.render(value.as_deref()));
+        seen += 1;
+    }
+    Ok(seen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_notify_flags() {
+        assert_eq!(notify_flags("", WATCH_FLAGS), Some("KA".to_string()));
+        assert_eq!(notify_flags("Ex", WATCH_FLAGS), Some("ExKA".to_string()));
+        assert_eq!(notify_flags("KEA", WATCH_FLAGS), None);
+        assert_eq!(notify_flags("KA", "K$"), None);
+        assert_eq!(notify_flags("KA", "Ex"), Some("KAE".to_string()));
+        assert_eq!(notify_flags("Ex", "Ex"), None);
+        let change = NotifyChange { previous: "Ex".to_string(), flags: "ExKA".to_string() };
+        assert_eq!(change.describe(), "set notify-keyspace-events from \"Ex\" to \"ExKA\" while watching");
+    }
+
+    #[tokio::test]
+    async fn test_dropped_guard_restores_the_setting() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = redis::cmd("CONFIG").arg("SET").arg("notify-keyspace-events").arg("").query_async(&mut conn).await.unwrap();
+        let change = notify_change(&client, WATCH_FLAGS).await.unwrap().unwrap();
+        assert_eq!(change.previous, "");
+
+        let guard = NotifyGuard::apply(&client, Some(change)).await.unwrap();
+        assert!(notify_change(&client, WATCH_FLAGS).await.unwrap().is_none());
+        // As on an error path: dropped without restore().
+        drop(guard);
+        assert_eq!(notify_change(&client, WATCH_FLAGS).await.unwrap().unwrap().previous, "");
+    }
+
+    #[test]
+    fn test_parse_and_render() {
+        let at = Local.with_ymd_and_hms(2025, 3, 1, 12, 0, 5).unwrap();
+        let event = KeyspaceEvent::parse("__keyspace@2__:demo:user:1", "hset", Some("demo:"), at).unwrap();
+        assert_eq!(event.key, "user:1");
+        assert!(event.leaves_value());
+        assert_eq!(event.render(Some("(hash, 2 items)")), "12:00:05.000  hset         user:1  = (hash, 2 items)");
+        assert!(!KeyspaceEvent::parse("__keyspace@0__:k", "del", None, at).unwrap().leaves_value());
+        assert!(KeyspaceEvent::parse("__keyevent@0__:del", "k", None, at).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::utils::error::Result;
+use crate::utils::RedisConnection;
+use redis::{AsyncCommands, LposOptions, Script};
+
+/// Removes the element at `index` only if it still equals `expected`.
+/// Returns 1 when the element was removed, 0 otherwise.
+const LREM_IF_AT_SCRIPT: &str = r#"
+local current = redis.call('LINDEX', KEYS[1], ARGV[1])
+if current ~= ARGV[2] then
+    return 0
+end
+local marker = '__lrem_if_at__' .. ARGV[1]
+redis.call('LSET', KEYS[1], ARGV[1], marker)
+return redis.call('LREM', KEYS[1], 1, marker)
+"#;
+
+/// Rewrites the list keeping only the first occurrence of each element.
+/// Returns the number of removed duplicates.
+const DEDUPE_LIST_SCRIPT: &str = r#"
+local items = redis.call('LRANGE', KEYS[1], 0, -1)
+local seen = {}
+local unique = {}
+for _, item in ipairs(items) do
+    if not seen[item] then
+        seen[item] = true
+        unique[#unique + 1] = item
+    end
+end
+local removed = #items - #unique
+if removed == 0 then
+    return 0
+end
+local ttl = redis.call('PTTL', KEYS[1])
+redis.call('DEL', KEYS[1])
+for i = 1, #unique, 1000 do
+    redis.call('RPUSH', KEYS[1], unpack(unique, i, math.min(i + 999, #unique)))
+end
+if ttl > 0 then
+    redis.call('PEXPIRE', KEYS[1], ttl)
+end
+return removed
+"#;
+
+/// Positions of `element` in the list (LPOS ... COUNT 0 returns all matches).
+pub async fn positions_of(conn: &mut RedisConnection, key: &str, element: &str) -> Result<Vec<usize>> {
+    let positions: Vec<usize> = conn.lpos(key, element, LposOptions::default().count(0)).await?;
+    Ok(positions)
+}
+
+/// Position of the `rank`-th occurrence of `element` (negative ranks search from the tail).
+pub async fn position_of(
+    conn: &mut RedisConnection,
+    key: &str,
+    element: &str,
+    rank: isize,
+) -> Result<Option<usize>> {
+    let position: Option<usize> = conn.lpos(key, element, LposOptions::default().rank(rank)).await?;
+    Ok(position)
+}
+
+/// Atomically removes the element at `index` if it still equals `expected`.
+pub async fn remove_if_at(
+    conn: &mut RedisConnection,
+    key: &str,
+    index: isize,
+    expected: &str,
+) -> Result<bool> {
+    let removed: i64 = Script::new(LREM_IF_AT_SCRIPT)
+        .key(key)
+        .arg(index)
+        .arg(expected)
+        .invoke_async(conn)
+        .await?;
+    Ok(removed == 1)
+}
+
+/// Removes duplicate elements, preserving the first occurrence and the key's TTL.
+pub async fn dedupe_list(conn: &mut RedisConnection, key: &str) -> Result<usize> {
+    let removed: usize = Script::new(DEDUPE_LIST_SCRIPT)
+        .key(key)
+        .invoke_async(conn)
+        .await?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+
+    async fn seeded_list(key: &str, items: &[&str]) -> RedisConnection {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.del(key).await.unwrap();
+        let _: () = conn.rpush(key, items).await.unwrap();
+        conn
+    }
+
+    #[tokio::test]
+    async fn test_positions_of_duplicates() {
+        let mut conn = seeded_list("lists_test:lpos", &["a", "b", "a", "c", "a"]).await;
+
+        assert_eq!(positions_of(&mut conn, "lists_test:lpos", "a").await.unwrap(), vec![0, 2, 4]);
+        assert_eq!(position_of(&mut conn, "lists_test:lpos", "a", 2).await.unwrap(), Some(2));
+        assert_eq!(position_of(&mut conn, "lists_test:lpos", "a", -1).await.unwrap(), Some(4));
+        assert_eq!(position_of(&mut conn, "lists_test:lpos", "z", 1).await.unwrap(), None);
+
+        let _: () = conn.del("lists_test:lpos").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_remove_if_at() {
+        let mut conn = seeded_list("lists_test:lrem", &["a", "b", "a"]).await;
+
+        assert!(!remove_if_at(&mut conn, "lists_test:lrem", 1, "a").await.unwrap());
+        assert!(remove_if_at(&mut conn, "lists_test:lrem", 2, "a").await.unwrap());
+
+        let list: Vec<String> = conn.lrange("lists_test:lrem", 0, -1).await.unwrap();
+        assert_eq!(list, vec!["a", "b"]);
+
+        let _: () = conn.del("lists_test:lrem").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dedupe_list_preserves_first_occurrence() {
+        let mut conn = seeded_list("lists_test:dedupe", &["b", "a", "b", "c", "a", "b"]).await;
+        let _: () = conn.expire("lists_test:dedupe", 60).await.unwrap();
+
+        let removed = dedupe_list(&mut conn, "lists_test:dedupe").await.unwrap();
+        assert_eq!(removed, 3);
+
+        let list: Vec<String> = conn.lrange("lists_test:dedupe", 0, -1).await.unwrap();
+        assert_eq!(list, vec!["b", "a", "c"]);
+        let ttl: i64 = conn.ttl("lists_test:dedupe").await.unwrap();
+        assert!(ttl > 0);
+
+        assert_eq!(dedupe_list(&mut conn, "lists_test:dedupe").await.unwrap(), 0);
+
+        let _: () = conn.del("lists_test:dedupe").await.unwrap();
+    }
+}
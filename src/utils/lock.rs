@@ -0,0 +1,267 @@
+use crate::utils::{RedisConnection, RetryPolicy};
+use crate::{DemoError, RedisClient, Result};
+use redis::Script;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// KEYS: lock key
+/// ARGV: token
+///
+/// Deletes the lock only while it still holds our token, so a holder whose
+/// lock expired can't release the next holder's.
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// KEYS: lock key
+/// ARGV: token, ttl ms
+///
+/// Returns 1 if the lock was ours and got a fresh TTL, 0 if it was lost.
+const EXTEND_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+pub fn lock_key(name: &str) -> String {
+    format!("lock:{}", name)
+}
+
+/// How often the watchdog renews a lock held for `ttl`: three times per
+/// TTL, so one slow or failed renewal doesn't lose it.
+pub fn watchdog_interval(ttl: Duration) -> Duration {
+    (ttl / 3).max(Duration::from_millis(1))
+}
+
+/// A mutex shared by every process talking to the same Redis: `SET NX PX`
+/// with a random token, released and extended by scripts that check the
+/// token first. The TTL bounds how long a crashed holder blocks others.
+///
+/// This is the single-instance form of Redlock. It is safe against crashes
+/// but not against a failover that loses the key before it reached the
+/// replica, so it suits work that is merely wasteful to run twice.
+#[derive(Clone)]
+pub struct DistributedLock {
+    conn: RedisConnection,
+    name: String,
+    ttl: Duration,
+    retry: RetryPolicy,
+}
+
+impl DistributedLock {
+    pub async fn new(client: &RedisClient, name: &str, ttl: Duration) -> Result<Self> {
+        Ok(Self::with_connection(client.get_async_connection().await?, name, ttl))
+    }
+
+    pub fn with_connection(conn: RedisConnection, name: &str, ttl: Duration) -> Self {
+        let retry = RetryPolicy::default().with_backoff(Duration::from_millis(10), Duration::from_millis(200));
+        Self { conn, name: name.to_string(), ttl: ttl.max(Duration::from_millis(1)), retry }
+    }
+
+    /// The backoff [`acquire`](Self::acquire) waits between tries; only its
+    /// delays are used, `acquire` keeps trying until its timeout.
+    pub fn with_backoff(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Takes the lock if nobody holds it.
+    pub async fn try_acquire(&self) -> Result<Option<LockGuard>> {
+        let mut conn = self.conn.clone();
+        let token = Uuid::new_v4().to_string();
+        let set: Option<String> = redis::cmd("SET")
+            .arg(lock_key(&self.name))
+            .arg(&token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await?;
+        Ok(set.map(|_| LockGuard {
+            conn,
+            key: lock_key(&self.name),
+            token,
+            ttl: self.ttl,
+            lost: Arc::new(AtomicBool::new(false)),
+            watchdog: None,
+        }))
+    }
+
+    /// Waits up to `timeout` for the lock, failing with
+    /// `DemoError::LockTimeout` when it stays taken.
+    pub async fn acquire(&self, timeout: Duration) -> Result<LockGuard> {
+        let started = Instant::now();
+        let mut attempt = 0;
+        loop {
+            if let Some(guard) = self.try_acquire().await? {
+                return Ok(guard);
+            }
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                return Err(DemoError::LockTimeout(self.name.clone()));
+            }
+            let delay = self.retry.delay(attempt, rand::random()).min(timeout - elapsed);
+            debug!("Lock '{}' is taken, retrying in {:?}", self.name, delay);
+            tokio::time::sleep(delay).await;
+            attempt = attempt.saturating_add(1);
+        }
+    }
+}
+
+/// A held lock. Dropping it without [`release`](Self::release) stops the
+/// watchdog and leaves the lock to expire after its TTL.
+pub struct LockGuard {
+    conn: RedisConnection,
+    key: String,
+    token: String,
+    ttl: Duration,
+    lost: Arc<AtomicBool>,
+    watchdog: Option<JoinHandle<()>>,
+}
+
+impl LockGuard {
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Whether the lock turned out to have expired and maybe passed to
+    /// someone else, as last seen by [`extend`](Self::extend) or the
+    /// watchdog. Work done under a lost lock wasn't exclusive.
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::SeqCst)
+    }
+
+    /// Resets the TTL. Returns false, and marks the guard lost, if the lock
+    /// had already expired.
+    pub async fn extend(&mut self) -> Result<bool> {
+        let extended = extend(&mut self.conn, &self.key, &self.token, self.ttl).await?;
+        if !extended {
+            self.lost.store(true, Ordering::SeqCst);
+        }
+        Ok(extended)
+    }
+
+    /// Keeps extending the lock in the background until it is released or
+    /// dropped, for work that may outlast the TTL. The TTL still frees the
+    /// lock soon after this process dies.
+    pub fn keep_alive(&mut self) {
+        if self.watchdog.is_some() {
+            return;
+        }
+        let mut conn = self.conn.clone();
+        let (key, token, ttl, lost) = (self.key.clone(), self.token.clone(), self.ttl, self.lost.clone());
+        self.watchdog = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(watchdog_interval(ttl));
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match extend(&mut conn, &key, &token, ttl).await {
+                    Ok(true) => debug!("Extended {} by {:?}", key, ttl),
+                    Ok(false) => {
+                        warn!("Lost {} before it could be extended", key);
+                        lost.store(true, Ordering::SeqCst);
+                        return;
+                    }
+                    Err(e) => warn!("Extending {} failed: {}", key, e),
+                }
+            }
+        }));
+    }
+
+    /// Gives the lock up. Returns false if it had already expired, in which
+    /// case whoever holds it now keeps it.
+    pub async fn release(mut self) -> Result<bool> {
+        if let Some(watchdog) = self.watchdog.take() {
+            watchdog.abort();
+        }
+        let released: i64 = Script::new(RELEASE_SCRIPT).key(&self.key).arg(&self.token).invoke_async(&mut self.conn).await?;
+        if released == 0 {
+            self.lost.store(true, Ordering::SeqCst);
+        }
+        Ok(released == 1)
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if let Some(watchdog) = self.watchdog.take() {
+            watchdog.abort();
+        }
+    }
+}
+
+async fn extend(conn: &mut RedisConnection, key: &str, token: &str, ttl: Duration) -> Result<bool> {
+    let extended: i64 = Script::new(EXTEND_SCRIPT).key(key).arg(token).arg(ttl.as_millis() as u64).invoke_async(conn).await?;
+    Ok(extended == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn lock(name: &str, ttl: Duration) -> DistributedLock {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        DistributedLock::new(&client, name, ttl).await.unwrap()
+    }
+
+    #[test]
+    fn test_watchdog_renews_well_within_the_ttl() {
+        assert_eq!(watchdog_interval(Duration::from_millis(900)), Duration::from_millis(300));
+        assert_eq!(watchdog_interval(Duration::ZERO), Duration::from_millis(1));
+        assert_eq!(lock_key("reports"), "lock:reports");
+    }
+
+    #[tokio::test]
+    async fn test_only_one_holder_and_only_the_holder_releases() {
+        let lock = lock("test-exclusive", Duration::from_secs(5)).await;
+        let guard = lock.try_acquire().await.unwrap().expect("lock was free");
+        assert!(lock.try_acquire().await.unwrap().is_none());
+        assert!(matches!(lock.acquire(Duration::from_millis(50)).await, Err(DemoError::LockTimeout(_))));
+
+        assert!(guard.release().await.unwrap());
+        let guard = lock.acquire(Duration::from_millis(50)).await.unwrap();
+        assert!(guard.release().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expired_holder_cannot_release_the_next_holders_lock() {
+        let lock = lock("test-expired", Duration::from_millis(50)).await;
+        let mut first = lock.try_acquire().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let second = lock.try_acquire().await.unwrap().expect("first lock expired");
+
+        assert!(!first.extend().await.unwrap());
+        assert!(first.is_lost());
+        assert!(!first.release().await.unwrap());
+        assert!(lock.try_acquire().await.unwrap().is_none());
+        assert!(second.release().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_keeps_the_lock_past_its_ttl() {
+        let lock = lock("test-watchdog", Duration::from_millis(150)).await;
+        let mut guard = lock.try_acquire().await.unwrap().unwrap();
+        guard.keep_alive();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        assert!(lock.try_acquire().await.unwrap().is_none());
+        assert!(!guard.is_lost());
+        assert!(guard.release().await.unwrap());
+    }
+}
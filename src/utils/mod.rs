@@ -1,5 +1,46 @@
 pub mod redis_client;
+pub mod auto_pipeline;
+pub mod bit_index;
+pub mod bitmaps;
+pub mod budget;
+pub mod bytes;
+pub mod capped;
+pub mod cassette;
+pub mod cluster;
+pub mod compact_stats;
+pub mod config_watch;
+pub mod connection;
 pub mod error;
+pub mod glob;
+pub mod id_gen;
+pub mod key_history;
+pub mod key_watch;
+pub mod key_stats;
+pub mod lists;
+pub mod lock;
+pub mod partitioned_scan;
+pub mod retry;
+pub mod sampling;
+pub mod scan;
+pub mod scoped_keys;
+pub mod scripts;
+pub mod steps;
+pub mod telemetry;
+pub mod url;
+pub mod zset;
 
-pub use redis_client::RedisClient;
-pub use error::{DemoError, Result};
\ No newline at end of file
+pub use redis_client::{RedisClient, RedisClientBuilder, TlsOptions};
+pub use auto_pipeline::{AutoPipeline, BatchStats};
+pub use budget::{StepTiming, TimingBudget};
+pub use cassette::Cassette;
+pub use connection::{CommandObserver, RedisConnection};
+pub use error::{DemoError, ErrorClass, Result};
+pub use lock::{DistributedLock, LockGuard};
+pub use partitioned_scan::PartitionedScan;
+pub use retry::RetryPolicy;
+pub use scan::{KeyScanner, KeyType};
+pub use scoped_keys::ScopedKeys;
+pub use scripts::{ScriptCall, ScriptManager};
+pub use steps::{DemoSteps, KeyRecorder, Sections};
+pub use telemetry::{LogFormat, Telemetry};
+pub use url::{DisplaySafe, RedisUrl};
\ No newline at end of file
@@ -0,0 +1,175 @@
+use crate::utils::error::{DemoError, Result};
+use crate::utils::scan::KeyScanner;
+use crate::RedisClient;
+use crate::utils::RedisConnection;
+use redis::AsyncCommands;
+use std::future::Future;
+
+/// First characters of the keys this crate generates (ids, uuids, names).
+/// Anything else lands in the catch-all partition.
+const ALPHABET: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn is_literal(prefix: &str) -> bool {
+    !prefix.contains(['*', '?', '[', ']', '\\'])
+}
+
+/// Splits `prefix*` into `n` disjoint MATCH patterns by the first character
+/// after the prefix: `n - 1` classes dealt round-robin from [`ALPHABET`] (so
+/// hex ids spread evenly) plus one negated class for everything else.
+///
+/// Patterns that are not a literal prefix followed by `*` can't be split and
+/// come back unchanged. None of the patterns match the bare prefix itself.
+pub fn prefix_partitions(pattern: &str, n: usize) -> Vec<String> {
+    let prefix = match pattern.strip_suffix('*') {
+        Some(prefix) if n > 1 && is_literal(prefix) => prefix,
+        _ => return vec![pattern.to_string()],
+    };
+    let groups = (n - 1).min(ALPHABET.len());
+    let mut classes = vec![String::new(); groups];
+    for (i, c) in ALPHABET.chars().enumerate() {
+        classes[i % groups].push(c);
+    }
+    classes
+        .iter()
+        .map(|class| format!("{}[{}]*", prefix, class))
+        .chain(std::iter::once(format!("{}[^{}]*", prefix, ALPHABET)))
+        .collect()
+}
+
+/// Runs one SCAN per partition concurrently and hands every batch to a
+/// processing function on that worker's connection.
+///
+/// Partitions are either MATCH prefixes on one server or, on a cluster, the
+/// master nodes themselves, each of which owns a disjoint range of slots.
+/// Like SCAN itself this is at-least-once: a key may be processed twice.
+pub struct PartitionedScan {
+    partitions: Vec<(RedisClient, String)>,
+    /// Key equal to the prefix, which no prefix partition matches.
+    bare_prefix: Option<(RedisClient, String)>,
+    count: usize,
+}
+
+impl PartitionedScan {
+    pub fn by_prefix(client: &RedisClient, pattern: &str, workers: usize) -> Self {
+        let partitions: Vec<(RedisClient, String)> = prefix_partitions(pattern, workers)
+            .into_iter()
+            .map(|pattern| (client.clone(), pattern))
+            .collect();
+        let bare_prefix = (partitions.len() > 1)
+            .then(|| pattern.trim_end_matches('*').to_string())
+            .filter(|prefix| !prefix.is_empty())
+            .map(|prefix| (client.clone(), prefix));
+        Self { partitions, bare_prefix, count: 1000 }
+    }
+
+    /// One worker per cluster master. Pass masters only: a replica would
+    /// report its master's keys a second time.
+    pub fn by_node(masters: &[RedisClient], pattern: &str) -> Self {
+        Self {
+            partitions: masters.iter().map(|node| (node.clone(), pattern.to_string())).collect(),
+            bare_prefix: None,
+            count: 1000,
+        }
+    }
+
+    /// SCAN COUNT hint for every worker.
+    pub fn with_count(mut self, count: usize) -> Self {
+        self.count = count.max(1);
+        self
+    }
+
+    pub fn patterns(&self) -> Vec<&str> {
+        self.partitions.iter().map(|(_, pattern)| pattern.as_str()).collect()
+    }
+
+    /// Scans all partitions and returns whatever `process` produced for each
+    /// batch, in no particular order. The first error stops the run.
+    pub async fn run<T, F, Fut>(&self, process: F) -> Result<Vec<T>>
+    where
+        T: Send + 'static,
+        F: Fn(RedisConnection, Vec<String>) -> Fut + Clone + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<T>>> + Send,
+    {
+        let mut handles = Vec::with_capacity(self.partitions.len());
+        for (client, pattern) in &self.partitions {
+            let conn = client.get_async_connection().await?;
+            let mut scanner = KeyScanner::new(conn.clone(), pattern, None).with_count(self.count);
+            let process = process.clone();
+            handles.push(tokio::spawn(async move {
+                let mut results = Vec::new();
+                while let Some(batch) = scanner.next_batch().await? {
+                    if !batch.is_empty() {
+                        results.extend(process(conn.clone(), batch).await?);
+                    }
+                }
+                Ok::<_, DemoError>(results)
+            }));
+        }
+
+        let mut results = Vec::new();
+        if let Some((client, key)) = &self.bare_prefix {
+            let mut conn = client.get_async_connection().await?;
+            if conn.exists(key).await? {
+                results.extend(process(conn, vec![key.clone()]).await?);
+            }
+        }
+        for handle in handles {
+            results.extend(handle.await.map_err(|e| DemoError::Demo(e.to_string()))??);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_partitions_cover_alphabet_once() {
+        let partitions = prefix_partitions("user:*", 4);
+        assert_eq!(partitions.len(), 4);
+        assert!(partitions[0].starts_with("user:[0369"));
+        assert_eq!(partitions[3], format!("user:[^{}]*", ALPHABET));
+
+        let mut dealt: Vec<char> = partitions[..3]
+            .iter()
+            .flat_map(|p| p.trim_start_matches("user:[").trim_end_matches("]*").chars())
+            .collect();
+        dealt.sort_unstable();
+        let mut expected: Vec<char> = ALPHABET.chars().collect();
+        expected.sort_unstable();
+        assert_eq!(dealt, expected);
+    }
+
+    #[test]
+    fn test_unsplittable_patterns() {
+        assert_eq!(prefix_partitions("user:*", 1), vec!["user:*"]);
+        assert_eq!(prefix_partitions("user:*:cart", 4), vec!["user:*:cart"]);
+        assert_eq!(prefix_partitions("us?r:*", 4), vec!["us?r:*"]);
+        assert_eq!(prefix_partitions("*", 2), vec![format!("[{}]*", ALPHABET), format!("[^{}]*", ALPHABET)]);
+    }
+
+    #[tokio::test]
+    async fn test_partitions_cover_every_key() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let keys: Vec<String> = ["a", "Z", "7", "-x", "_"]
+            .iter()
+            .map(|suffix| format!("pscan_test:{}", suffix))
+            .chain(std::iter::once("pscan_test:".to_string()))
+            .collect();
+        for key in &keys {
+            let _: () = conn.set(key, 1).await.unwrap();
+        }
+
+        let scan = PartitionedScan::by_prefix(&client, "pscan_test:*", 3);
+        let mut found = scan.run(|_, batch| async move { Ok(batch) }).await.unwrap();
+        found.sort();
+        found.dedup();
+        let mut expected = keys.clone();
+        expected.sort();
+        assert_eq!(found, expected);
+
+        let _: () = conn.del(&keys).await.unwrap();
+    }
+}
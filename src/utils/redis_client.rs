@@ -1,54 +1,367 @@
-use crate::utils::error::Result;
+use crate::server::replication::{ReplicationInfo, ReplicationLag};
+use crate::utils::auto_pipeline::AutoPipeline;
+use crate::utils::cassette::Cassette;
+use crate::utils::connection::{CommandObserver, RedisConnection};
+use crate::utils::error::{DemoError, Result};
+use crate::utils::retry::RetryPolicy;
+use crate::utils::sampling::Reservoir;
+use crate::utils::scan::{KeyScanner, KeyType};
+use crate::utils::url::{DisplaySafe, Endpoint, RedisUrl, Tls};
+use rand::Rng;
 use redis::{aio::ConnectionManager, Client, ConnectionInfo};
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{debug, info};
 
+/// When the keyspace is at least this many times larger than the requested
+/// sample, RANDOMKEY is used instead of a full SCAN pass.
+const RANDOMKEY_SPARSITY_FACTOR: u64 = 100;
+
+/// Certificates for `rediss://` connections, beyond what the URL can say.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsOptions {
+    /// PEM CA certificate trusted instead of the system roots, for servers
+    /// with a self-signed or private CA.
+    pub ca_cert: Option<PathBuf>,
+    /// PEM client certificate and key, for servers with `tls-auth-clients yes`.
+    pub client_cert: Option<(PathBuf, PathBuf)>,
+    /// Accept any server certificate, as a `#insecure` URL does.
+    pub insecure: bool,
+}
+
+impl TlsOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A [`RedisClient`] with TLS options; `RedisClient::new(url)` is
+/// `RedisClient::builder(url).build()`.
+#[derive(Debug, Clone)]
+pub struct RedisClientBuilder {
+    url: String,
+    tls: TlsOptions,
+}
+
+impl RedisClientBuilder {
+    pub fn ca_cert(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tls.ca_cert = Some(path.into());
+        self
+    }
+    
+    pub fn client_cert(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        self.tls.client_cert = Some((cert.into(), key.into()));
+        self
+    }
+    
+    pub fn insecure(mut self, insecure: bool) -> Self {
+        self.tls.insecure = insecure;
+        self
+    }
+    
+    pub fn tls(mut self, options: TlsOptions) -> Self {
+        self.tls = options;
+        self
+    }
+    
+    /// Checks the URL and options together and loads the certificates;
+    /// nothing connects until the first command.
+    pub fn build(self) -> Result<RedisClient> {
+        let mut url: RedisUrl = self.url.parse()?;
+        let tls = match url.endpoint() {
+            Endpoint::Tcp { tls, .. } => *tls,
+            Endpoint::Socket(_) => Tls::Off,
+        };
+        if tls == Tls::Off && !self.tls.is_empty() {
+            return Err(DemoError::Configuration(format!("TLS options need a rediss:// URL, not {}", DisplaySafe(&self.url))));
+        }
+        if self.tls.insecure {
+            url = url.with_tls(Tls::Insecure);
+        }
+        if self.tls.ca_cert.is_some() && (self.tls.insecure || tls == Tls::Insecure) {
+            return Err(DemoError::Configuration(
+                "An insecure connection accepts any certificate, so the CA certificate would be ignored".to_string(),
+            ));
+        }
+        let connection_info = url.connection_info()?;
+        let client = open(connection_info.clone(), &self.tls)?;
+        
+        info!("Redis client initialized with URL: {}", DisplaySafe(&self.url));
+        
+        Ok(RedisClient {
+            client: Arc::new(client),
+            connection_info,
+            observers: Arc::new(Vec::new()),
+            key_prefix: None,
+            cassette: None,
+            retry: RetryPolicy::default(),
+        })
+    }
+}
+
+#[cfg(feature = "tls")]
+fn open(connection_info: ConnectionInfo, tls: &TlsOptions) -> Result<Client> {
+    if tls.ca_cert.is_none() && tls.client_cert.is_none() {
+        return Ok(Client::open(connection_info)?);
+    }
+    let read = |path: &std::path::Path| {
+        std::fs::read(path).map_err(|e| DemoError::Configuration(format!("Can't read {}: {}", path.display(), e)))
+    };
+    let client_tls = match &tls.client_cert {
+        Some((cert, key)) => Some(redis::ClientTlsConfig { client_cert: read(cert)?, client_key: read(key)? }),
+        None => None,
+    };
+    let certificates = redis::TlsCertificates { client_tls, root_cert: tls.ca_cert.as_deref().map(read).transpose()? };
+    Ok(Client::build_with_tls(connection_info, certificates)?)
+}
+
+/// Only reached with a `rediss://` URL when built with the `tls` feature,
+/// which [`RedisUrl::connection_info`] checks first.
+#[cfg(not(feature = "tls"))]
+fn open(connection_info: ConnectionInfo, _tls: &TlsOptions) -> Result<Client> {
+    Ok(Client::open(connection_info)?)
+}
+
 #[derive(Clone)]
 pub struct RedisClient {
     client: Arc<Client>,
     connection_info: ConnectionInfo,
+    observers: Arc<Vec<Arc<dyn CommandObserver>>>,
+    key_prefix: Option<Arc<str>>,
+    cassette: Option<Arc<Cassette>>,
+    retry: RetryPolicy,
 }
 
 impl RedisClient {
     pub fn new(redis_url: &str) -> Result<Self> {
-        let connection_info: ConnectionInfo = redis_url.parse()?;
-        let client = Client::open(connection_info.clone())?;
-        
-        info!("Redis client initialized with URL: {}", redis_url);
-        
-        Ok(Self {
-            client: Arc::new(client),
-            connection_info,
-        })
+        Self::builder(redis_url).build()
+    }
+    
+    /// For `rediss://` servers that need a private CA, client certificates
+    /// or `insecure` mode.
+    pub fn builder(redis_url: &str) -> RedisClientBuilder {
+        RedisClientBuilder { url: redis_url.to_string(), tls: TlsOptions::default() }
+    }
+    
+    /// Adds an observer that sees every command sent over connections
+    /// created from now on.
+    pub fn with_observer(mut self, observer: Arc<dyn CommandObserver>) -> Self {
+        Arc::make_mut(&mut self.observers).push(observer);
+        self
+    }
+    
+    /// Puts every key sent over async connections under `prefix`, so demos
+    /// can't clobber other data in the same database; see
+    /// [`RedisConnection`]. An empty prefix turns it off.
+    pub fn with_key_prefix(mut self, prefix: &str) -> Self {
+        self.key_prefix = (!prefix.is_empty()).then(|| Arc::from(prefix));
+        self
+    }
+    
+    pub fn key_prefix(&self) -> Option<&str> {
+        self.key_prefix.as_deref()
+    }
+    
+    /// Records async traffic to `cassette`, or answers it from there
+    /// without a server when the cassette is replaying; see [`Cassette`].
+    pub fn with_cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+    
+    pub fn cassette(&self) -> Option<&Cassette> {
+        self.cassette.as_deref()
+    }
+    
+    /// How connecting and [`execute_with_retry`](Self::execute_with_retry)
+    /// ride out transient errors; [`RetryPolicy::default`] unless set.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+    
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
     }
     
-    pub async fn get_async_connection(&self) -> Result<ConnectionManager> {
+    pub async fn get_async_connection(&self) -> Result<RedisConnection> {
+        if let Some(cassette) = self.cassette.as_ref().filter(|cassette| cassette.is_replay()) {
+            debug!("Replaying async connection from {}", cassette.path().display());
+            let conn = RedisConnection::replaying(cassette.clone(), self.connection_info.redis.db, self.observers.clone());
+            return Ok(conn.with_key_prefix(self.key_prefix.clone()));
+        }
         debug!("Creating async connection manager");
-        let connection_manager = ConnectionManager::new(self.client.as_ref().clone()).await?;
-        Ok(connection_manager)
+        let started = std::time::Instant::now();
+        // The manager's own connect retries are off so the policy decides
+        let connection_manager = self
+            .retry
+            .run(|| async { Ok(ConnectionManager::new_with_backoff(self.client.as_ref().clone(), 2, 100, 0).await?) })
+            .await?;
+        for observer in self.observers.iter() {
+            observer.on_connect(started.elapsed());
+        }
+        Ok(RedisConnection::new(connection_manager, self.observers.clone())
+            .with_key_prefix(self.key_prefix.clone())
+            .with_cassette(self.cassette.clone()))
+    }
+    
+    /// A connection that coalesces commands sent concurrently within
+    /// `window` into one pipeline; see [`AutoPipeline`].
+    pub async fn auto_pipeline(&self, window: std::time::Duration, max_batch: usize) -> Result<AutoPipeline> {
+        Ok(AutoPipeline::new(self.get_async_connection().await?, window, max_batch))
+    }
+    
+    /// Deletes every key under this client's prefix. Refuses to run without
+    /// one, so it can never empty a whole database.
+    pub async fn delete_prefixed_keys(&self) -> Result<usize> {
+        let Some(prefix) = self.key_prefix() else {
+            return Err(DemoError::Configuration("Refusing to delete keys without a key prefix".to_string()));
+        };
+        debug!("Deleting every key under '{}'", prefix);
+        let mut conn = self.get_async_connection().await?;
+        let mut scanner = self.scan_keys("*").await?;
+        let mut deleted = 0;
+        while let Some(keys) = scanner.next_batch().await? {
+            if !keys.is_empty() {
+                let removed: usize = redis::cmd("DEL").arg(&keys).query_async(&mut conn).await?;
+                deleted += removed;
+            }
+        }
+        Ok(deleted)
+    }
+    
+    /// Pub/sub and blocking connections talk to the server directly: they
+    /// don't see observers or the key prefix.
+    pub async fn get_async_pubsub(&self) -> Result<redis::aio::PubSub> {
+        debug!("Creating async pub/sub connection");
+        let connection = self.client.get_async_connection().await?;
+        Ok(connection.into_pubsub())
     }
     
+    /// A socket of its own with no reconnects or retries, for demos about
+    /// connections themselves. Like pub/sub it bypasses observers and the
+    /// key prefix.
+    pub async fn get_dedicated_connection(&self) -> Result<redis::aio::MultiplexedConnection> {
+        Ok(self.client.get_multiplexed_tokio_connection().await?)
+    }
+
     pub fn get_sync_connection(&self) -> Result<redis::Connection> {
         debug!("Creating sync connection");
         let connection = self.client.get_connection()?;
         Ok(connection)
     }
     
+    /// An r2d2 pool of blocking connections, for threaded callers.
+    pub fn sync_pool(&self, max_size: u32) -> Result<r2d2::Pool<Client>> {
+        debug!("Creating sync connection pool of {}", max_size);
+        Ok(r2d2::Pool::builder().max_size(max_size).build(self.client.as_ref().clone())?)
+    }
+    
     pub async fn ping(&self) -> Result<()> {
-        let mut conn = self.get_async_connection().await?;
-        redis::cmd("PING").query_async::<_, ()>(&mut conn).await?;
+        self.execute_with_retry::<()>(&redis::cmd("PING")).await?;
         info!("Successfully pinged Redis server");
         Ok(())
     }
     
+    /// Sends `cmd` on a new connection, repeating it per the retry policy
+    /// when the connection drops or the server is briefly unavailable. The
+    /// manager reconnects between attempts. Only for commands that are safe
+    /// to send twice, such as reads and idempotent writes.
+    pub async fn execute_with_retry<T: redis::FromRedisValue>(&self, cmd: &redis::Cmd) -> Result<T> {
+        let conn = self.get_async_connection().await?;
+        self.retry
+            .run(|| {
+                let mut conn = conn.clone();
+                async move { Ok(cmd.query_async(&mut conn).await?) }
+            })
+            .await
+    }
+    
+    pub async fn scan_keys(&self, pattern: &str) -> Result<KeyScanner> {
+        let conn = self.get_async_connection().await?;
+        Ok(KeyScanner::new(conn, pattern, None))
+    }
+    
+    pub async fn scan_keys_of_type(&self, pattern: &str, key_type: KeyType) -> Result<KeyScanner> {
+        let conn = self.get_async_connection().await?;
+        Ok(KeyScanner::new(conn, pattern, Some(key_type)))
+    }
+    
+    /// Returns up to `n` distinct keys chosen uniformly at random.
+    ///
+    /// Huge keyspaces take a RANDOMKEY fast path; otherwise (or when RANDOMKEY
+    /// keeps returning duplicates) a reservoir sample is taken over one SCAN pass.
+    /// SCAN can return a key twice while the keyspace is rehashing; every key
+    /// is offered to the sample once, so the pass remembers each key it saw.
+    pub async fn sample_keys(&self, n: usize) -> Result<Vec<String>> {
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+        
+        let mut conn = self.get_async_connection().await?;
+        let db_size: u64 = redis::cmd("DBSIZE").query_async(&mut conn).await?;
+        
+        // RANDOMKEY and DBSIZE see the whole database, not just the prefix.
+        if self.key_prefix.is_none() && db_size >= n as u64 * RANDOMKEY_SPARSITY_FACTOR {
+            let mut picked = HashSet::with_capacity(n);
+            for _ in 0..n * 4 {
+                let key: Option<String> = redis::cmd("RANDOMKEY").query_async(&mut conn).await?;
+                if let Some(key) = key {
+                    picked.insert(key);
+                }
+                if picked.len() == n {
+                    debug!("Sampled {} keys via RANDOMKEY", n);
+                    return Ok(picked.into_iter().collect());
+                }
+            }
+            debug!("RANDOMKEY returned too many duplicates, falling back to SCAN");
+        }
+        
+        let mut rng = rand::thread_rng();
+        let mut reservoir = Reservoir::new(n);
+        let mut offered = HashSet::new();
+        let mut scanner = KeyScanner::new(conn, "*", None);
+        while let Some(batch) = scanner.next_batch().await? {
+            offer_new_keys(&mut reservoir, &mut offered, batch, &mut rng);
+        }
+        
+        let sampled = reservoir.into_vec();
+        debug!("Sampled {} of {} keys via SCAN reservoir", sampled.len(), offered.len());
+        Ok(sampled)
+    }
+    
+    /// Offset delta between this master and each of its replicas, from
+    /// INFO replication.
+    pub async fn replication_lag(&self) -> Result<ReplicationLag> {
+        let mut conn = self.get_async_connection().await?;
+        ReplicationInfo::fetch(&mut conn).await?.lag()
+    }
+    
     pub fn get_connection_info(&self) -> &ConnectionInfo {
         &self.connection_info
     }
 }
 
+/// Offers the keys of one SCAN batch that no earlier batch returned.
+fn offer_new_keys<R: Rng + ?Sized>(
+    reservoir: &mut Reservoir<String>,
+    offered: &mut HashSet<String>,
+    batch: Vec<String>,
+    rng: &mut R,
+) {
+    for key in batch {
+        if offered.insert(key.clone()) {
+            reservoir.offer(key, rng);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     
     #[test]
     fn test_redis_client_creation_valid_url() {
@@ -56,12 +369,46 @@ mod tests {
         assert!(client.is_ok());
     }
     
+    #[test]
+    fn test_scan_repeat_after_eviction_is_not_offered_again() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut reservoir = Reservoir::new(1);
+        let mut offered = HashSet::new();
+        // With room for one key, "a" or "b" has left the sample by the time
+        // SCAN repeats both.
+        offer_new_keys(&mut reservoir, &mut offered, vec!["a".to_string(), "b".to_string()], &mut rng);
+        offer_new_keys(&mut reservoir, &mut offered, vec!["a".to_string(), "b".to_string()], &mut rng);
+        assert_eq!(reservoir.seen(), 2);
+        assert_eq!(reservoir.into_vec().len(), 1);
+    }
+    
     #[test]
     fn test_redis_client_creation_invalid_url() {
         let client = RedisClient::new("invalid://url");
         assert!(client.is_err());
     }
     
+    #[test]
+    fn test_builder_checks_tls_options() {
+        let error = |builder: RedisClientBuilder| builder.build().err().unwrap().to_string();
+        assert!(error(RedisClient::builder("redis://localhost").insecure(true)).contains("need a rediss:// URL"));
+        assert!(error(RedisClient::builder("rediss://localhost").insecure(true).ca_cert("ca.pem")).contains("CA certificate would be ignored"));
+        assert!(error(RedisClient::builder("rediss://localhost#insecure").ca_cert("ca.pem")).contains("CA certificate would be ignored"));
+        if !cfg!(feature = "tls") {
+            assert!(error(RedisClient::builder("rediss://localhost")).contains("--features tls"));
+        }
+    }
+    
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_builder_with_tls() {
+        let client = RedisClient::builder("rediss://cache:6380").insecure(true).build().unwrap();
+        assert!(matches!(client.get_connection_info().addr, redis::ConnectionAddr::TcpTls { insecure: true, .. }));
+        let missing = std::env::temp_dir().join("no-such-ca.pem");
+        let error = RedisClient::builder("rediss://cache").ca_cert(&missing).build().err().unwrap();
+        assert!(error.to_string().contains("Can't read"));
+    }
+    
     #[test]
     fn test_redis_client_clone() {
         let client = RedisClient::new("redis://localhost:6379").unwrap();
@@ -101,6 +448,85 @@ mod tests {
         assert!(matches!(info.addr, redis::ConnectionAddr::Tcp(_, _)));
     }
     
+    #[tokio::test]
+    async fn test_sample_keys_against_seeded_keyspace() {
+        let client = RedisClient::new("redis://localhost:6379/13").unwrap().with_key_prefix("sample-test:");
+        let mut conn = client.get_async_connection().await.unwrap();
+        client.delete_prefixed_keys().await.unwrap();
+        
+        let seeded: HashSet<String> = (0..200).map(|i| format!("sample:{}", i)).collect();
+        for key in &seeded {
+            let _: () = redis::cmd("SET").arg(key).arg(1).query_async(&mut conn).await.unwrap();
+        }
+        
+        // Every sample is distinct, drawn from the seeded set, and repeated
+        // sampling eventually touches nearly the whole keyspace.
+        let mut covered = HashSet::new();
+        for _ in 0..100 {
+            let sample = client.sample_keys(20).await.unwrap();
+            let unique: HashSet<_> = sample.iter().cloned().collect();
+            assert_eq!(unique.len(), 20);
+            assert!(unique.is_subset(&seeded));
+            covered.extend(unique);
+        }
+        assert!(covered.len() > 190);
+        
+        assert!(client.sample_keys(0).await.unwrap().is_empty());
+        assert_eq!(client.sample_keys(500).await.unwrap().len(), 200);
+        
+        assert_eq!(client.delete_prefixed_keys().await.unwrap(), 200);
+    }
+    
+    #[tokio::test]
+    async fn test_key_prefix_is_invisible_to_callers() {
+        let raw = RedisClient::new("redis://localhost:6379/13").unwrap();
+        let client = raw.clone().with_key_prefix("prefix-test:");
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = redis::cmd("SET").arg("greeting").arg("hi").query_async(&mut conn).await.unwrap();
+        
+        let mut raw_conn = raw.get_async_connection().await.unwrap();
+        let stored: Option<String> = redis::cmd("GET").arg("prefix-test:greeting").query_async(&mut raw_conn).await.unwrap();
+        assert_eq!(stored.as_deref(), Some("hi"));
+        assert_eq!(client.scan_keys("greet*").await.unwrap().collect_all().await.unwrap(), vec!["greeting"]);
+        
+        assert_eq!(client.delete_prefixed_keys().await.unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_delete_prefixed_keys_needs_a_prefix() {
+        let client = RedisClient::new("redis://localhost:6379/13").unwrap();
+        assert!(client.delete_prefixed_keys().await.is_err());
+        assert_eq!(client.with_key_prefix("").key_prefix(), None);
+    }
+    
+    #[tokio::test]
+    async fn test_replay_needs_no_server() {
+        let path = std::env::temp_dir().join(format!("replay-{}.jsonl", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            concat!(
+                "{\"commands\":[[\"GET\",\"t:greeting\"]],\"replies\":[{\"data\":\"hi\"}]}\n",
+                "{\"commands\":[[\"MULTI\"],[\"INCRBY\",\"t:n\",\"1\"],[\"EXEC\"]],\"replies\":[{\"bulk\":[{\"int\":1}]}]}\n",
+                "{\"commands\":[[\"SCAN\",\"0\",\"MATCH\",\"t:*\"]],\"replies\":[{\"bulk\":[{\"data\":\"0\"},{\"bulk\":[{\"data\":\"t:n\"}]}]}]}\n",
+            ),
+        )
+        .unwrap();
+        let cassette = Arc::new(Cassette::replay(&path).unwrap());
+        let client = RedisClient::new("redis://127.0.0.1:1/4").unwrap().with_key_prefix("t:").with_cassette(cassette);
+        let mut conn = client.get_async_connection().await.unwrap();
+        
+        let greeting: String = redis::cmd("GET").arg("greeting").query_async(&mut conn).await.unwrap();
+        assert_eq!(greeting, "hi");
+        let (n,): (i64,) = redis::pipe().atomic().incr("n", 1).query_async(&mut conn).await.unwrap();
+        assert_eq!(n, 1);
+        let keys: (u64, Vec<String>) = redis::cmd("SCAN").arg(0).arg("MATCH").arg("*").query_async(&mut conn).await.unwrap();
+        assert_eq!(keys, (0, vec!["n".to_string()]));
+        assert!(redis::cmd("GET").arg("greeting").query_async::<_, String>(&mut conn).await.is_err());
+        assert_eq!(client.cassette().unwrap().remaining(), 0);
+        assert_eq!(redis::aio::ConnectionLike::get_db(&conn), 4);
+        std::fs::remove_file(path).unwrap();
+    }
+    
     #[tokio::test]
     async fn test_connection_with_different_db() {
         let client = RedisClient::new("redis://localhost:6379/2").unwrap();
@@ -0,0 +1,131 @@
+use crate::utils::error::Result;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// How often and how patiently to repeat an operation that failed with a
+/// transient error (see [`DemoError::is_retryable`]). Delays double from
+/// `initial_delay` up to `max_delay`, each shortened by a random share of
+/// up to `jitter` so clients that failed together don't retry together.
+///
+/// [`DemoError::is_retryable`]: crate::DemoError::is_retryable
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Including the first try; 1 never retries.
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// 0 waits exactly the backoff, 1 anywhere between nothing and it.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    /// Rides out a restart or a failover of a second or two.
+    fn default() -> Self {
+        Self { max_attempts: 5, initial_delay: Duration::from_millis(100), max_delay: Duration::from_secs(2), jitter: 0.5 }
+    }
+}
+
+impl RetryPolicy {
+    /// Fail on the first error.
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_backoff(mut self, initial_delay: Duration, max_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self.max_delay = max_delay.max(initial_delay);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The wait before retry number `retry` (0-based), with `random` in
+    /// `[0, 1)` picking how much jitter takes off.
+    pub fn delay(&self, retry: u32, random: f64) -> Duration {
+        let backoff = self.initial_delay.saturating_mul(2u32.saturating_pow(retry)).min(self.max_delay);
+        backoff.mul_f64(1.0 - self.jitter * random.clamp(0.0, 1.0))
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error that isn't
+    /// retryable, or has been tried `max_attempts` times; the last error is
+    /// returned. Only for operations that are safe to repeat: a write whose
+    /// reply was lost may already have been applied.
+    pub async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(e) if e.is_retryable() && attempt < self.max_attempts => {
+                    let delay = self.delay(attempt - 1, rand::random());
+                    warn!("Attempt {}/{} failed ({}), retrying in {:?}", attempt, self.max_attempts, e, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DemoError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn dropped() -> DemoError {
+        redis::RedisError::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset)).into()
+    }
+
+    #[test]
+    fn test_delay_doubles_caps_and_jitters() {
+        let policy = RetryPolicy::default().with_backoff(Duration::from_millis(10), Duration::from_millis(50)).with_jitter(0.5);
+        let delays: Vec<_> = (0..4).map(|retry| policy.delay(retry, 0.0).as_millis()).collect();
+        assert_eq!(delays, [10, 20, 40, 50]);
+        assert_eq!(policy.delay(1, 0.999_999).as_millis(), 10);
+        assert_eq!(policy.delay(40, 0.0), Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_transient_errors_only() {
+        let policy = RetryPolicy::default().with_backoff(Duration::ZERO, Duration::ZERO);
+        let calls = AtomicU32::new(0);
+        let value = policy
+            .run(|| async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(dropped()),
+                    n => Ok(n),
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, 2);
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<()> = policy
+            .run(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(DemoError::Redis(redis::RedisError::from((redis::ErrorKind::TypeError, "WRONGTYPE"))))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<()> = policy.with_max_attempts(3).run(|| async { calls.fetch_add(1, Ordering::SeqCst); Err(dropped()) }).await;
+        assert!(result.unwrap_err().is_retryable());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
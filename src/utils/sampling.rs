@@ -0,0 +1,117 @@
+use rand::Rng;
+
+/// Fixed-size uniform sample over a stream of unknown length (Algorithm R).
+///
+/// Every item offered has an equal `capacity / seen` chance of ending up in
+/// the final sample, which lets us sample a keyspace with a single SCAN pass
+/// and bounded memory.
+pub struct Reservoir<T> {
+    capacity: usize,
+    seen: u64,
+    items: Vec<T>,
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            items: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns whichever item leaves the sample: the one `item` displaced,
+    /// or `item` itself when it was not taken.
+    pub fn offer<R: Rng + ?Sized>(&mut self, item: T, rng: &mut R) -> Option<T> {
+        self.seen += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(item);
+            return None;
+        }
+
+        let slot = rng.gen_range(0..self.seen);
+        if (slot as usize) < self.capacity {
+            Some(std::mem::replace(&mut self.items[slot as usize], item))
+        } else {
+            Some(item)
+        }
+    }
+
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_reservoir_keeps_everything_when_under_capacity() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut reservoir = Reservoir::new(10);
+        for i in 0..5 {
+            reservoir.offer(i, &mut rng);
+        }
+        assert_eq!(reservoir.seen(), 5);
+        assert_eq!(reservoir.into_vec(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_reservoir_respects_capacity() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut reservoir = Reservoir::new(10);
+        for i in 0..10_000 {
+            reservoir.offer(i, &mut rng);
+        }
+        let sample = reservoir.into_vec();
+        assert_eq!(sample.len(), 10);
+        let unique: std::collections::HashSet<_> = sample.iter().collect();
+        assert_eq!(unique.len(), 10);
+    }
+
+    #[test]
+    fn test_reservoir_reports_what_leaves_the_sample() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut reservoir = Reservoir::new(3);
+        let mut kept: std::collections::HashSet<u32> = std::collections::HashSet::new();
+        for i in 0..1_000 {
+            kept.insert(i);
+            if let Some(dropped) = reservoir.offer(i, &mut rng) {
+                assert!(kept.remove(&dropped));
+            }
+        }
+        let sample: std::collections::HashSet<u32> = reservoir.into_vec().into_iter().collect();
+        assert_eq!(sample, kept);
+    }
+
+    #[test]
+    fn test_reservoir_is_approximately_uniform() {
+        // Sample 10 of 100 items many times; each item should be picked
+        // roughly trials * 10 / 100 times.
+        let mut rng = StdRng::seed_from_u64(42);
+        let trials = 20_000;
+        let mut hits = [0u32; 100];
+        for _ in 0..trials {
+            let mut reservoir = Reservoir::new(10);
+            for i in 0..100 {
+                reservoir.offer(i, &mut rng);
+            }
+            for i in reservoir.into_vec() {
+                hits[i] += 1;
+            }
+        }
+
+        let expected = trials as f64 * 10.0 / 100.0;
+        for count in hits {
+            let deviation = (count as f64 - expected).abs() / expected;
+            assert!(deviation < 0.1, "count {} deviates from {}", count, expected);
+        }
+    }
+}
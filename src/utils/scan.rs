@@ -0,0 +1,196 @@
+use crate::utils::error::{DemoError, Result};
+use crate::utils::RedisConnection;
+use std::fmt;
+use std::str::FromStr;
+
+const DEFAULT_SCAN_COUNT: usize = 100;
+
+/// Redis value types accepted by `SCAN ... TYPE <type>` and returned by `TYPE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyType {
+    String,
+    List,
+    Set,
+    ZSet,
+    Hash,
+    Stream,
+}
+
+impl KeyType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyType::String => "string",
+            KeyType::List => "list",
+            KeyType::Set => "set",
+            KeyType::ZSet => "zset",
+            KeyType::Hash => "hash",
+            KeyType::Stream => "stream",
+        }
+    }
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for KeyType {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "string" => Ok(KeyType::String),
+            "list" => Ok(KeyType::List),
+            "set" => Ok(KeyType::Set),
+            "zset" => Ok(KeyType::ZSet),
+            "hash" => Ok(KeyType::Hash),
+            "stream" => Ok(KeyType::Stream),
+            other => Err(DemoError::Configuration(format!("Unknown key type: {}", other))),
+        }
+    }
+}
+
+/// Cursor-driven iterator over keys matching a pattern, optionally restricted
+/// to a single value type via SCAN's TYPE option (Redis >= 6.0).
+pub struct KeyScanner {
+    conn: RedisConnection,
+    pattern: String,
+    key_type: Option<KeyType>,
+    count: usize,
+    cursor: u64,
+    finished: bool,
+}
+
+impl KeyScanner {
+    pub fn new(conn: RedisConnection, pattern: &str, key_type: Option<KeyType>) -> Self {
+        Self {
+            conn,
+            pattern: pattern.to_string(),
+            key_type,
+            count: DEFAULT_SCAN_COUNT,
+            cursor: 0,
+            finished: false,
+        }
+    }
+
+    /// Hint for how many keys Redis should examine per SCAN call.
+    pub fn with_count(mut self, count: usize) -> Self {
+        self.count = count.max(1);
+        self
+    }
+
+    /// Starts from a cursor saved by an earlier scan instead of 0.
+    pub fn with_cursor(mut self, cursor: u64) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Where the scan continues from: 0 before the first batch and after
+    /// the last.
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    pub fn key_type(&self) -> Option<KeyType> {
+        self.key_type
+    }
+
+    /// Fetches the next batch of keys. Returns `None` once the cursor wraps
+    /// back to 0. A batch may be empty while the scan is still in progress.
+    pub async fn next_batch(&mut self) -> Result<Option<Vec<String>>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let mut cmd = redis::cmd("SCAN");
+        cmd.arg(self.cursor)
+            .arg("MATCH")
+            .arg(&self.pattern)
+            .arg("COUNT")
+            .arg(self.count);
+        if let Some(key_type) = self.key_type {
+            cmd.arg("TYPE").arg(key_type.as_str());
+        }
+
+        let (next_cursor, batch): (u64, Vec<String>) = cmd.query_async(&mut self.conn).await?;
+        self.cursor = next_cursor;
+        if next_cursor == 0 {
+            self.finished = true;
+        }
+
+        Ok(Some(batch))
+    }
+
+    /// Drains the scan into a vector. SCAN may return a key more than once,
+    /// so duplicates are removed while preserving discovery order.
+    pub async fn collect_all(mut self) -> Result<Vec<String>> {
+        let mut seen = std::collections::HashSet::new();
+        let mut keys = Vec::new();
+        while let Some(batch) = self.next_batch().await? {
+            for key in batch {
+                if seen.insert(key.clone()) {
+                    keys.push(key);
+                }
+            }
+        }
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+    use redis::AsyncCommands;
+
+    #[test]
+    fn test_key_type_round_trip() {
+        for key_type in [
+            KeyType::String,
+            KeyType::List,
+            KeyType::Set,
+            KeyType::ZSet,
+            KeyType::Hash,
+            KeyType::Stream,
+        ] {
+            let parsed: KeyType = key_type.as_str().parse().unwrap();
+            assert_eq!(parsed, key_type);
+        }
+    }
+
+    #[test]
+    fn test_key_type_parse_case_insensitive() {
+        assert_eq!("HASH".parse::<KeyType>().unwrap(), KeyType::Hash);
+        assert_eq!(KeyType::ZSet.to_string(), "zset");
+    }
+
+    #[test]
+    fn test_key_type_parse_unknown() {
+        let err = "bitmap".parse::<KeyType>().unwrap_err();
+        assert!(matches!(err, DemoError::Configuration(_)));
+    }
+
+    #[tokio::test]
+    async fn test_scan_keys_of_type_filters() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let _: () = conn.set("scan_test:string", "v").await.unwrap();
+        let _: () = conn.hset("scan_test:hash", "f", "v").await.unwrap();
+        let _: () = conn.zadd("scan_test:zset", "m", 1).await.unwrap();
+
+        let hashes = client
+            .scan_keys_of_type("scan_test:*", KeyType::Hash)
+            .await
+            .unwrap()
+            .collect_all()
+            .await
+            .unwrap();
+        assert_eq!(hashes, vec!["scan_test:hash".to_string()]);
+
+        let _: () = conn
+            .del(&["scan_test:string", "scan_test:hash", "scan_test:zset"])
+            .await
+            .unwrap();
+    }
+}
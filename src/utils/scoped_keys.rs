@@ -0,0 +1,140 @@
+use crate::utils::error::Result;
+use crate::utils::RedisConnection;
+use redis::AsyncCommands;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tracing::{debug, warn};
+
+/// DEL batch size, so cleaning up a large demo doesn't block the server.
+const DELETE_CHUNK: usize = 1000;
+
+async fn delete(conn: &mut RedisConnection, keys: &[String]) -> Result<usize> {
+    let mut deleted = 0;
+    for chunk in keys.chunks(DELETE_CHUNK) {
+        let removed: usize = conn.del(chunk).await?;
+        deleted += removed;
+    }
+    Ok(deleted)
+}
+
+/// Remembers every key a demo creates and deletes them when the demo is
+/// done, including when it bails out early with `?`.
+///
+/// Call [`finish`](Self::finish) on the happy path to delete and see
+/// errors. If the guard is dropped without it, the keys are deleted from
+/// `Drop`: blocking on the multi-threaded runtime (what `main` uses), or in
+/// a spawned task on a current-thread runtime, which is best-effort.
+pub struct ScopedKeys {
+    conn: RedisConnection,
+    keys: Mutex<BTreeSet<String>>,
+}
+
+impl ScopedKeys {
+    pub fn new(conn: RedisConnection) -> Self {
+        Self { conn, keys: Mutex::new(BTreeSet::new()) }
+    }
+
+    /// Registers `key` and hands it back, so it can wrap a key where it is
+    /// first used: `conn.set(keys.track("demo:a"), 1)`.
+    pub fn track(&self, key: impl Into<String>) -> String {
+        let key = key.into();
+        self.keys.lock().expect("scoped keys poisoned").insert(key.clone());
+        key
+    }
+
+    pub fn track_all<I, K>(&self, keys: I)
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.keys.lock().expect("scoped keys poisoned").extend(keys.into_iter().map(Into::into));
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.lock().expect("scoped keys poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn snapshot(&self) -> Vec<String> {
+        self.keys.lock().expect("scoped keys poisoned").iter().cloned().collect()
+    }
+
+    /// Deletes the tracked keys now but keeps tracking them, e.g. to clear
+    /// leftovers from an earlier interrupted run before starting.
+    pub async fn purge(&self) -> Result<usize> {
+        let keys = self.snapshot();
+        let mut conn = self.conn.clone();
+        delete(&mut conn, &keys).await
+    }
+
+    /// Deletes every tracked key and ends the scope. Returns how many
+    /// existed.
+    pub async fn finish(self) -> Result<usize> {
+        let keys = std::mem::take(&mut *self.keys.lock().expect("scoped keys poisoned"));
+        let keys: Vec<String> = keys.into_iter().collect();
+        let mut conn = self.conn.clone();
+        let deleted = delete(&mut conn, &keys).await?;
+        debug!("Cleaned up {} of {} tracked keys", deleted, keys.len());
+        Ok(deleted)
+    }
+}
+
+impl Drop for ScopedKeys {
+    fn drop(&mut self) {
+        let keys: Vec<String> = std::mem::take(self.keys.get_mut().unwrap_or_else(|e| e.into_inner())).into_iter().collect();
+        if keys.is_empty() {
+            return;
+        }
+        let Ok(handle) = Handle::try_current() else {
+            warn!("{} demo keys left behind: no runtime to delete them on", keys.len());
+            return;
+        };
+        let mut conn = self.conn.clone();
+        let cleanup = async move {
+            if let Err(e) = delete(&mut conn, &keys).await {
+                warn!("Failed to clean up {} demo keys: {}", keys.len(), e);
+            }
+        };
+        match handle.runtime_flavor() {
+            RuntimeFlavor::MultiThread => tokio::task::block_in_place(|| handle.block_on(cleanup)),
+            _ => {
+                handle.spawn(cleanup);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+
+    async fn exists(conn: &mut RedisConnection, key: &str) -> bool {
+        conn.exists(key).await.unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_keys_deleted_on_finish_and_on_early_return() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+
+        let keys = ScopedKeys::new(conn.clone());
+        let _: () = conn.set(keys.track("scoped:a"), 1).await.unwrap();
+        keys.track_all(["scoped:b", "scoped:a"]);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys.finish().await.unwrap(), 1);
+        assert!(!exists(&mut conn, "scoped:a").await);
+
+        async fn failing_demo(conn: &mut RedisConnection) -> Result<()> {
+            let keys = ScopedKeys::new(conn.clone());
+            let _: () = conn.set(keys.track("scoped:c"), 1).await?;
+            Err(crate::DemoError::Demo("failed mid-way".to_string()))
+        }
+        assert!(failing_demo(&mut conn).await.is_err());
+        assert!(!exists(&mut conn, "scoped:c").await);
+    }
+}
@@ -0,0 +1,143 @@
+use super::connection::RedisConnection;
+use crate::{DemoError, Result};
+use redis::{ErrorKind, FromRedisValue, Script, ToRedisArgs};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+struct Registered {
+    source: String,
+    sha: String,
+}
+
+/// Named Lua scripts run with EVALSHA. The SHA1 of each script is worked
+/// out when it is registered, so the body only goes over the wire when the
+/// server answers NOSCRIPT (after a restart, a failover or SCRIPT FLUSH):
+/// then it is loaded with SCRIPT LOAD and the call retried once.
+#[derive(Default)]
+pub struct ScriptManager {
+    scripts: HashMap<String, Registered>,
+    reloads: AtomicU64,
+}
+
+impl ScriptManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `source` under `name`, replacing any script already there,
+    /// and returns its SHA.
+    pub fn register(&mut self, name: &str, source: &str) -> &str {
+        let sha = Script::new(source).get_hash().to_string();
+        let registered = Registered { source: source.to_string(), sha };
+        self.scripts.insert(name.to_string(), registered);
+        &self.scripts[name].sha
+    }
+
+    pub fn sha(&self, name: &str) -> Option<&str> {
+        self.scripts.get(name).map(|script| script.sha.as_str())
+    }
+
+    /// Times a NOSCRIPT reply made a script be loaded again.
+    pub fn reloads(&self) -> u64 {
+        self.reloads.load(Ordering::Relaxed)
+    }
+
+    /// SCRIPT LOADs every script up front, so the first calls don't each
+    /// pay a NOSCRIPT round trip.
+    pub async fn load_all(&self, conn: &mut RedisConnection) -> Result<()> {
+        for script in self.scripts.values() {
+            load(conn, script).await?;
+        }
+        Ok(())
+    }
+
+    /// Starts a call of the script registered as `name`.
+    pub fn call<'a>(&'a self, name: &str) -> Result<ScriptCall<'a>> {
+        let script = self
+            .scripts
+            .get(name)
+            .ok_or_else(|| DemoError::Configuration(format!("No script registered as '{}'", name)))?;
+        Ok(ScriptCall { manager: self, script, keys: Vec::new(), args: Vec::new() })
+    }
+}
+
+async fn load(conn: &mut RedisConnection, script: &Registered) -> Result<()> {
+    let sha: String = redis::cmd("SCRIPT").arg("LOAD").arg(&script.source).query_async(conn).await?;
+    if sha != script.sha {
+        return Err(DemoError::Demo(format!("SCRIPT LOAD returned {} for a script hashed as {}", sha, script.sha)));
+    }
+    Ok(())
+}
+
+/// The KEYS and ARGV of one EVALSHA, built like `redis::ScriptInvocation`.
+pub struct ScriptCall<'a> {
+    manager: &'a ScriptManager,
+    script: &'a Registered,
+    keys: Vec<Vec<u8>>,
+    args: Vec<Vec<u8>>,
+}
+
+impl ScriptCall<'_> {
+    pub fn key<T: ToRedisArgs>(mut self, key: T) -> Self {
+        self.keys.extend(key.to_redis_args());
+        self
+    }
+
+    pub fn arg<T: ToRedisArgs>(mut self, arg: T) -> Self {
+        self.args.extend(arg.to_redis_args());
+        self
+    }
+
+    pub async fn invoke<T: FromRedisValue>(&self, conn: &mut RedisConnection) -> Result<T> {
+        match self.evalsha(conn).await {
+            Err(e) if e.kind() == ErrorKind::NoScriptError => {
+                self.manager.reloads.fetch_add(1, Ordering::Relaxed);
+                load(conn, self.script).await?;
+                Ok(self.evalsha(conn).await?)
+            }
+            result => Ok(result?),
+        }
+    }
+
+    async fn evalsha<T: FromRedisValue>(&self, conn: &mut RedisConnection) -> redis::RedisResult<T> {
+        redis::cmd("EVALSHA")
+            .arg(&self.script.sha)
+            .arg(self.keys.len())
+            .arg(&self.keys)
+            .arg(&self.args)
+            .query_async(conn)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedisClient;
+
+    #[test]
+    fn test_register_hashes_the_source() {
+        let mut scripts = ScriptManager::new();
+        let sha = scripts.register("one", "return 1").to_string();
+        assert_eq!(sha, "e0e1f9fabfc9d4800c877a703b823ac0578ff8db");
+        assert_eq!(scripts.sha("one"), Some(sha.as_str()));
+        assert_eq!(scripts.sha("two"), None);
+        assert!(scripts.call("two").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reloads_on_noscript() {
+        let client = RedisClient::new("redis://localhost:6379/15").unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let mut scripts = ScriptManager::new();
+        let nonce = uuid::Uuid::new_v4();
+        scripts.register("echo", &format!("-- {}\nreturn {{KEYS[1], ARGV[1]}}", nonce));
+
+        let call = scripts.call("echo").unwrap().key("scripts-test:k").arg(7);
+        let reply: (String, String) = call.invoke(&mut conn).await.unwrap();
+        assert_eq!(reply, ("scripts-test:k".to_string(), "7".to_string()));
+        assert_eq!(scripts.reloads(), 1);
+        let _: (String, String) = call.invoke(&mut conn).await.unwrap();
+        assert_eq!(scripts.reloads(), 1);
+    }
+}
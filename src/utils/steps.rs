@@ -0,0 +1,208 @@
+use super::budget::TimingBudget;
+use super::connection::{command_keys, CommandObserver};
+use super::key_history::KeyHistory;
+use crate::Result;
+use redis::Cmd;
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{field, info, info_span, warn, Instrument, Span};
+
+/// Target of the `step finished` and `section finished` events, which the
+/// text log leaves out so they don't interleave with the demo output.
+pub const TIMING_TARGET: &str = "redis_rust_demo::timing";
+
+/// Keys named on a step's span; the rest only count towards `key_count`.
+const LISTED_KEYS: usize = 8;
+/// Distinct keys remembered per step, so a bench step can't grow it
+/// without bound.
+const MAX_KEYS: usize = 1024;
+
+/// Distinct keys the client's commands named since the last [`take`](Self::take).
+/// Once full it stops locking, so a bench's hot path only pays an atomic load.
+#[derive(Default)]
+pub struct KeyRecorder {
+    keys: Mutex<BTreeSet<String>>,
+    full: AtomicBool,
+}
+
+impl KeyRecorder {
+    pub fn take(&self) -> BTreeSet<String> {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        self.full.store(false, Ordering::Relaxed);
+        std::mem::take(&mut *keys)
+    }
+}
+
+impl CommandObserver for KeyRecorder {
+    fn on_command(&self, cmd: &Cmd) {
+        if self.full.load(Ordering::Relaxed) {
+            return;
+        }
+        let named = command_keys(cmd).1;
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        for key in named {
+            if keys.len() >= MAX_KEYS {
+                self.full.store(true, Ordering::Relaxed);
+                break;
+            }
+            keys.insert(key);
+        }
+    }
+}
+
+/// `a, b, c (+5 more)`: the first few keys for the span field.
+fn list_keys(keys: &BTreeSet<String>) -> String {
+    let listed = keys.iter().take(LISTED_KEYS).map(String::as_str).collect::<Vec<_>>().join(", ");
+    match keys.len().saturating_sub(LISTED_KEYS) {
+        0 => listed,
+        more => format!("{} (+{} more)", listed, more),
+    }
+}
+
+/// Runs a command's demo steps. The command gets a `demo` span; each step
+/// runs inside a `step` span under it carrying `demo`, `step` and the
+/// `keys` its commands named, and ends with a `step finished` (or `step
+/// failed`) event with its `elapsed_ms`; see [`TIMING_TARGET`]. The same steps are timed against
+/// `--budget-ms` and label `--history` changes when those are on.
+///
+/// Attach [`keys`](Self::keys) to the client with `with_observer` so the
+/// steps can see which keys they touched.
+pub struct DemoSteps {
+    demo: String,
+    span: Span,
+    keys: Arc<KeyRecorder>,
+    budget: Option<Arc<TimingBudget>>,
+    history: Option<Arc<KeyHistory>>,
+}
+
+impl DemoSteps {
+    pub fn new(demo: &str) -> Self {
+        Self {
+            demo: demo.to_string(),
+            span: info_span!("demo", demo = demo),
+            keys: Arc::new(KeyRecorder::default()),
+            budget: None,
+            history: None,
+        }
+    }
+
+    pub fn with_budget(mut self, budget: Option<Arc<TimingBudget>>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn with_history(mut self, history: Option<Arc<KeyHistory>>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// The `demo` span; run the whole command inside it.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn keys(&self) -> Arc<KeyRecorder> {
+        self.keys.clone()
+    }
+
+    pub async fn run<T>(&self, step: &str, future: impl Future<Output = Result<T>>) -> Result<T> {
+        let span = info_span!(parent: &self.span, "step", demo = self.demo.as_str(), step = step, keys = field::Empty, key_count = field::Empty);
+        self.keys.take();
+        let timer = self.budget.as_ref().map(|budget| budget.begin(step));
+        let outer = self.history.as_ref().map(|history| history.set_step(step));
+        let started = Instant::now();
+
+        let result = future.instrument(span.clone()).await;
+
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let keys = self.keys.take();
+        span.record("keys", list_keys(&keys).as_str());
+        span.record("key_count", keys.len());
+        if let Some(timer) = timer {
+            timer.finish();
+        }
+        if let Some((history, outer)) = self.history.as_ref().zip(outer) {
+            history.set_step(&outer);
+        }
+        match &result {
+            Ok(_) => info!(target: TIMING_TARGET, parent: &span, elapsed_ms, "step finished"),
+            Err(e) => warn!(parent: &span, elapsed_ms, error = %e, "step failed"),
+        }
+        result
+    }
+}
+
+/// The numbered sections a demo step prints. Each [`next`](Self::next)
+/// prints the heading and opens a `section` span under the current step,
+/// closing the previous one with a `section finished` event carrying its
+/// `elapsed_ms`; dropping the sections closes the last.
+#[derive(Default)]
+pub struct Sections {
+    count: usize,
+    open: Option<(Span, Instant)>,
+}
+
+impl Sections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prints `N. title` and times what follows as section N.
+    pub fn next(&mut self, title: &str) {
+        self.close();
+        self.count += 1;
+        match self.count {
+            1 => println!("{}. {}", self.count, title),
+            n => println!("\n{}. {}", n, title),
+        }
+        let span = info_span!("section", section = title.trim_end_matches(':'), n = self.count);
+        self.open = Some((span, Instant::now()));
+    }
+
+    fn close(&mut self) {
+        if let Some((span, started)) = self.open.take() {
+            info!(target: TIMING_TARGET, parent: &span, elapsed_ms = started.elapsed().as_secs_f64() * 1000.0, "section finished");
+        }
+    }
+}
+
+impl Drop for Sections {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_distinct_keys() {
+        let recorder = KeyRecorder::default();
+        recorder.on_command(redis::cmd("MSET").arg("b").arg(1).arg("a").arg(2));
+        recorder.on_command(redis::cmd("GET").arg("a"));
+        recorder.on_command(&redis::cmd("PING"));
+        let keys = recorder.take();
+        assert_eq!(keys.iter().map(String::as_str).collect::<Vec<_>>(), ["a", "b"]);
+        assert!(recorder.take().is_empty());
+    }
+
+    #[test]
+    fn test_list_keys() {
+        let few: BTreeSet<String> = ["x", "y"].map(String::from).into();
+        assert_eq!(list_keys(&few), "x, y");
+        let many: BTreeSet<String> = (0..10).map(|i| format!("k{}", i)).collect();
+        assert_eq!(list_keys(&many), "k0, k1, k2, k3, k4, k5, k6, k7 (+2 more)");
+    }
+
+    #[tokio::test]
+    async fn test_run_passes_the_result_through() {
+        let steps = DemoSteps::new("test");
+        assert_eq!(steps.run("ok", async { Ok(7) }).await.unwrap(), 7);
+        let failed: Result<()> = steps.run("fails", async { Err(crate::DemoError::Demo("boom".to_string())) }).await;
+        assert!(failed.is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use super::steps::TIMING_TARGET;
+use crate::{DemoError, Result};
+use std::str::FromStr;
+use tracing_subscriber::filter::{filter_fn, EnvFilter};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
+
+/// How `tracing` events are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Readable lines on stdout, between the demo output. Step and
+    /// section timings are left out.
+    Text,
+    /// One JSON object per event on stderr, with the `demo` and `step`
+    /// spans it happened in, so the demo output on stdout stays clean.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = DemoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(DemoError::Configuration(format!("Unknown log format: {} (use text or json)", other))),
+        }
+    }
+}
+
+/// Keeps the OTLP exporter alive. Dropping it flushes the spans that are
+/// still batched, so hold it until the command is done.
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+#[cfg(feature = "otel")]
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("⚠️  Could not flush spans to the OTLP endpoint: {}", e);
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber: `RUST_LOG` (default
+/// `redis_rust_demo=info`) picks the events, `format` how they are
+/// written, and with `otlp_endpoint` spans also go to an OpenTelemetry
+/// collector over OTLP/HTTP.
+pub fn init(format: LogFormat, otlp_endpoint: Option<&str>) -> Result<Telemetry> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| "redis_rust_demo=info".into());
+    let text = (format == LogFormat::Text).then(|| tracing_subscriber::fmt::layer().with_filter(filter_fn(|meta| meta.target() != TIMING_TARGET)));
+    let json = (format == LogFormat::Json).then(|| tracing_subscriber::fmt::layer().json().with_writer(std::io::stderr));
+
+    #[cfg(feature = "otel")]
+    {
+        use opentelemetry::trace::TracerProvider as _;
+
+        let provider = otlp_endpoint.map(otel_provider).transpose()?;
+        let otel = provider.as_ref().map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("redis-rust-demo")));
+        tracing_subscriber::registry().with(filter).with(text).with(json).with(otel).init();
+        Ok(Telemetry { provider })
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        if otlp_endpoint.is_some() {
+            return Err(DemoError::Configuration("--otlp-endpoint needs a build with --features otel".to_string()));
+        }
+        tracing_subscriber::registry().with(filter).with(text).with(json).init();
+        Ok(Telemetry {})
+    }
+}
+
+/// `http://collector:4318` or the full `.../v1/traces` URL.
+#[cfg(feature = "otel")]
+fn traces_url(endpoint: &str) -> String {
+    match endpoint.ends_with("/v1/traces") {
+        true => endpoint.to_string(),
+        false => format!("{}/v1/traces", endpoint.trim_end_matches('/')),
+    }
+}
+
+#[cfg(feature = "otel")]
+fn otel_provider(endpoint: &str) -> Result<opentelemetry_sdk::trace::TracerProvider> {
+    use opentelemetry_otlp::WithExportConfig;
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(traces_url(endpoint))
+        .build()
+        .map_err(|e| DemoError::Configuration(format!("Invalid OTLP endpoint {}: {}", endpoint, e)))?;
+    Ok(opentelemetry_sdk::trace::TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+        .with_resource(opentelemetry_sdk::Resource::new([opentelemetry::KeyValue::new("service.name", "redis-rust-demo")]))
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format_names() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("Text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+
+    #[cfg(feature = "otel")]
+    #[test]
+    fn test_traces_url() {
+        assert_eq!(traces_url("http://localhost:4318"), "http://localhost:4318/v1/traces");
+        assert_eq!(traces_url("http://localhost:4318/"), "http://localhost:4318/v1/traces");
+        assert_eq!(traces_url("http://collector/v1/traces"), "http://collector/v1/traces");
+    }
+}
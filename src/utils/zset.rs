@@ -0,0 +1,128 @@
+use crate::utils::error::Result;
+use crate::utils::RedisConnection;
+
+/// Bits reserved for the millisecond timestamp in a composite score.
+/// 2^42 ms is roughly year 2109, and pinned flag + timestamp stays below
+/// 2^53 so the score survives the round trip through an f64 exactly.
+const TIMESTAMP_BITS: u32 = 42;
+const TIMESTAMP_MASK: u64 = (1 << TIMESTAMP_BITS) - 1;
+
+/// Width used when zero-padding numeric sort keys inside members.
+const SORT_KEY_WIDTH: usize = 20;
+
+/// Packs `(pinned, timestamp_ms)` into one score so that ZREVRANGE returns
+/// pinned items first and then newest first within each group.
+pub fn composite_score(pinned: bool, timestamp_ms: u64) -> f64 {
+    let pinned_bit = if pinned { 1u64 << TIMESTAMP_BITS } else { 0 };
+    (pinned_bit | (timestamp_ms & TIMESTAMP_MASK)) as f64
+}
+
+pub fn decode_composite_score(score: f64) -> (bool, u64) {
+    let raw = score as u64;
+    (raw >> TIMESTAMP_BITS == 1, raw & TIMESTAMP_MASK)
+}
+
+/// Builds a member like `00000000001700000000:post-7`. With every member at
+/// the same score, lexicographic order equals numeric `sort_key` order with
+/// `id` as tiebreaker, which is what ZRANGEBYLEX needs.
+pub fn encode_lex_member(sort_key: u64, id: &str) -> String {
+    format!("{:0width$}:{}", sort_key, id, width = SORT_KEY_WIDTH)
+}
+
+pub fn decode_lex_member(member: &str) -> Option<(u64, &str)> {
+    let (sort_key, id) = member.split_once(':')?;
+    Some((sort_key.parse().ok()?, id))
+}
+
+/// Exclusive lower bound for the page after `cursor`, or `-` for the first page.
+pub fn lex_lower_bound(cursor: Option<&str>) -> String {
+    match cursor {
+        Some(member) => format!("({}", member),
+        None => "-".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexPage {
+    pub members: Vec<String>,
+    /// Pass back to fetch the following page; `None` when exhausted.
+    pub next_cursor: Option<String>,
+}
+
+/// Keyset pagination over a same-score ZSET with ZRANGEBYLEX. Unlike
+/// offset-based paging this stays stable while members are inserted.
+pub async fn range_by_lex_page(
+    conn: &mut RedisConnection,
+    key: &str,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<LexPage> {
+    let members: Vec<String> = redis::cmd("ZRANGEBYLEX")
+        .arg(key)
+        .arg(lex_lower_bound(cursor))
+        .arg("+")
+        .arg("LIMIT")
+        .arg(0)
+        .arg(limit)
+        .query_async(conn)
+        .await?;
+
+    let next_cursor = if members.len() == limit {
+        members.last().cloned()
+    } else {
+        None
+    };
+    Ok(LexPage { members, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_composite_score_ordering() {
+        let pinned_old = composite_score(true, 1_000);
+        let recent = composite_score(false, 1_700_000_000_000);
+        let older = composite_score(false, 1_600_000_000_000);
+        assert!(pinned_old > recent);
+        assert!(recent > older);
+    }
+
+    #[test]
+    fn test_composite_score_round_trip() {
+        let ts = 1_700_000_123_456;
+        assert_eq!(decode_composite_score(composite_score(true, ts)), (true, ts));
+        assert_eq!(decode_composite_score(composite_score(false, ts)), (false, ts));
+    }
+
+    #[test]
+    fn test_lex_member_ordering_matches_numeric() {
+        let mut members = vec![
+            encode_lex_member(100, "b"),
+            encode_lex_member(9, "z"),
+            encode_lex_member(100, "a"),
+        ];
+        members.sort();
+        assert_eq!(
+            members,
+            vec![
+                encode_lex_member(9, "z"),
+                encode_lex_member(100, "a"),
+                encode_lex_member(100, "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_lex_member() {
+        let member = encode_lex_member(42, "post:7");
+        assert_eq!(decode_lex_member(&member), Some((42, "post:7")));
+        assert_eq!(decode_lex_member("garbage"), None);
+    }
+
+    #[test]
+    fn test_lex_lower_bound() {
+        assert_eq!(lex_lower_bound(None), "-");
+        assert_eq!(lex_lower_bound(Some("abc")), "(abc");
+    }
+}
@@ -1,8 +1,10 @@
+#![allow(dead_code)]
+
 use redis_rust_demo::RedisClient;
-use std::sync::Once;
+use std::sync::{Once, OnceLock};
 
 static INIT: Once = Once::new();
-static mut TEST_REDIS_URL: Option<String> = None;
+static TEST_REDIS_URL: OnceLock<String> = OnceLock::new();
 
 pub fn setup() {
     INIT.call_once(|| {
@@ -13,34 +15,32 @@ pub fn setup() {
             .try_init();
         
         // Set up test Redis URL
-        unsafe {
-            TEST_REDIS_URL = Some(
-                std::env::var("TEST_REDIS_URL")
-                    .unwrap_or_else(|_| "redis://localhost:6379/1".to_string())
-            );
-        }
+        let _ = TEST_REDIS_URL.set(
+            std::env::var("TEST_REDIS_URL")
+                .unwrap_or_else(|_| "redis://localhost:6379/1".to_string())
+        );
     });
 }
 
 pub fn get_test_redis_url() -> String {
     setup();
-    unsafe {
-        TEST_REDIS_URL.as_ref().unwrap().clone()
-    }
+    TEST_REDIS_URL.get().unwrap().clone()
 }
 
 pub async fn get_test_client() -> RedisClient {
     let url = get_test_redis_url();
-    RedisClient::new(&url).expect("Failed to create test Redis client")
+    RedisClient::new(&url)
+        .expect("Failed to create test Redis client")
+        .with_key_prefix("test:")
 }
 
+/// Deletes keys matching `pattern` under the test client's key prefix.
 pub async fn cleanup_test_keys(client: &RedisClient, pattern: &str) {
     let mut conn = client.get_async_connection().await.unwrap();
-    let keys: Vec<String> = redis::cmd("KEYS")
-        .arg(pattern)
-        .query_async(&mut conn)
-        .await
-        .unwrap_or_default();
+    let Ok(scanner) = client.scan_keys(pattern).await else {
+        return;
+    };
+    let keys = scanner.collect_all().await.unwrap_or_default();
     
     if !keys.is_empty() {
         let _: () = redis::cmd("DEL")
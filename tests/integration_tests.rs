@@ -21,28 +21,28 @@ async fn test_full_user_workflow() -> Result<()> {
     
     // Store user as JSON
     let user_json = serde_json::to_string(&user)?;
-    conn.set(&user.redis_key(), &user_json).await?;
+    let _: () = conn.set(user.redis_key(), &user_json).await?;
     
     // Create indexes
-    conn.set(&user.username_index_key(), &user.id.to_string()).await?;
-    conn.set(&user.email_index_key(), &user.id.to_string()).await?;
+    let _: () = conn.set(user.username_index_key(), user.id.to_string()).await?;
+    let _: () = conn.set(user.email_index_key(), user.id.to_string()).await?;
     
     // Retrieve user by username index
-    let user_id: String = conn.get(&user.username_index_key()).await?;
+    let user_id: String = conn.get(user.username_index_key()).await?;
     assert_eq!(user_id, user.id.to_string());
     
     // Retrieve user data
-    let stored_json: String = conn.get(&user.redis_key()).await?;
+    let stored_json: String = conn.get(user.redis_key()).await?;
     let retrieved_user: User = serde_json::from_str(&stored_json)?;
     
     assert_eq!(retrieved_user.username, user.username);
     assert_eq!(retrieved_user.email, user.email);
     
     // Update user login count
-    conn.hincr(&user.redis_key(), "login_count", 1).await?;
+    let _: () = conn.hincr(user.redis_key(), "login_count", 1).await?;
     
     // Clean up
-    conn.del(vec![
+    let _: () = conn.del(vec![
         user.redis_key(),
         user.username_index_key(),
         user.email_index_key()
@@ -83,7 +83,7 @@ async fn test_concurrent_operations() -> Result<()> {
     let mut conn = client.get_async_connection().await?;
     
     // Set up a counter
-    conn.set("concurrent_counter", 0).await?;
+    let _: () = conn.set("concurrent_counter", 0).await?;
     
     // Run concurrent increments
     let mut handles = vec![];
@@ -110,7 +110,7 @@ async fn test_concurrent_operations() -> Result<()> {
     assert_eq!(final_value, 1000);
     
     // Clean up
-    conn.del("concurrent_counter").await?;
+    let _: () = conn.del("concurrent_counter").await?;
     
     Ok(())
 }
@@ -141,7 +141,7 @@ async fn test_transaction_workflow() -> Result<()> {
     assert_eq!(counter, 2);
     
     // Clean up
-    conn.del(vec!["tx_key1", "tx_key2", "tx_counter"]).await?;
+    let _: () = conn.del(vec!["tx_key1", "tx_key2", "tx_counter"]).await?;
     
     Ok(())
 }
@@ -173,7 +173,7 @@ async fn test_pattern_matching() -> Result<()> {
     
     // Create keys with pattern
     for i in 0..5 {
-        conn.set(format!("pattern:test:{}", i), i).await?;
+        let _: () = conn.set(format!("pattern:test:{}", i), i).await?;
     }
     
     // Find keys matching pattern
@@ -186,7 +186,7 @@ async fn test_pattern_matching() -> Result<()> {
     
     // Clean up
     if !keys.is_empty() {
-        conn.del(keys).await?;
+        let _: () = conn.del(keys).await?;
     }
     
     Ok(())
@@ -0,0 +1,120 @@
+use chrono::Utc;
+use redis_rust_demo::integrations::webhook::{self, EVENT_HEADER, ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use redis_rust_demo::integrations::metric_registry::SOAK_OPS;
+use redis_rust_demo::integrations::{AlertSink, ChatFormat, ChatSink, Event, EventKind, MetricsConfig, MetricsExporter, WebhookConfig, WebhookSink};
+use redis_rust_demo::utils::RetryPolicy;
+use redis_rust_demo::DemoError;
+use std::time::Duration;
+use wiremock::matchers::{header as has_header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn sink(server: &MockServer) -> WebhookSink {
+    let retry = RetryPolicy::default().with_max_attempts(3).with_backoff(Duration::ZERO, Duration::ZERO);
+    let config = WebhookConfig::new(&format!("{}/hook", server.uri())).with_secret("s3cret").with_retry(retry);
+    WebhookSink::new(config).unwrap()
+}
+
+fn header<'a>(request: &'a wiremock::Request, name: &str) -> &'a str {
+    request.headers.get(name).unwrap().to_str().unwrap()
+}
+
+#[tokio::test]
+async fn test_delivery_is_signed() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).and(path("/hook")).respond_with(ResponseTemplate::new(204)).expect(1).mount(&server).await;
+
+    let event = Event::session_expired("session:abc", Utc::now());
+    assert_eq!(sink(&server).send(&event).await.unwrap(), 1);
+
+    let requests = server.received_requests().await.unwrap();
+    let request = &requests[0];
+    let timestamp: i64 = header(request, TIMESTAMP_HEADER).parse().unwrap();
+    assert!(webhook::verify(b"s3cret", timestamp, &request.body, header(request, SIGNATURE_HEADER)));
+    assert!(!webhook::verify(b"wrong", timestamp, &request.body, header(request, SIGNATURE_HEADER)));
+    assert_eq!(header(request, EVENT_HEADER), "session_expired");
+    assert_eq!(header(request, ID_HEADER), event.id.to_string());
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(body["subject"], "session:abc");
+}
+
+#[tokio::test]
+async fn test_server_errors_are_retried_with_the_same_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(ResponseTemplate::new(503)).up_to_n_times(2).mount(&server).await;
+    Mock::given(method("POST")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+    let event = Event::threshold_breached("replication", "replica 10.0.0.2:6379 is 5000 bytes behind (max 100)", Utc::now());
+    assert_eq!(sink(&server).send(&event).await.unwrap(), 3);
+
+    let requests = server.received_requests().await.unwrap();
+    let ids: Vec<&str> = requests.iter().map(|request| header(request, ID_HEADER)).collect();
+    let id = event.id.to_string();
+    assert_eq!(ids, [id.as_str(); 3]);
+}
+
+#[tokio::test]
+async fn test_client_errors_are_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(ResponseTemplate::new(400)).expect(1).mount(&server).await;
+
+    let event = Event::new(EventKind::JobFailed, "job-1", "gc-indexes job failed".to_string(), Utc::now());
+    match sink(&server).send(&event).await {
+        Err(DemoError::Http { status: Some(400), .. }) => {}
+        other => panic!("expected a 400, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_unreachable_endpoint_gives_up_after_the_policy() {
+    // A port nothing listens on any more
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let uri = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let retry = RetryPolicy::default().with_max_attempts(2).with_backoff(Duration::ZERO, Duration::ZERO);
+    let sink = WebhookSink::new(WebhookConfig::new(&uri).with_retry(retry)).unwrap();
+    let error = sink.send(&Event::session_expired("session:abc", Utc::now())).await.unwrap_err();
+    assert!(matches!(error, DemoError::Http { status: None, .. }));
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn test_chat_sink_posts_the_rendered_template() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).and(path("/hook")).respond_with(ResponseTemplate::new(200)).expect(1).mount(&server).await;
+
+    let sink = ChatSink::new(&format!("{}/hook", server.uri()), ChatFormat::Discord)
+        .unwrap()
+        .with_template("{subject} → {message}".parse().unwrap());
+    sink.send(&Event::threshold_breached("stream orders", "group 'billing' is 500 entries behind (max 100)", Utc::now())).await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body, serde_json::json!({ "content": "stream orders → group 'billing' is 500 entries behind (max 100)" }));
+}
+
+#[tokio::test]
+async fn test_metrics_are_pushed_to_the_job_group() {
+    let server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/metrics/job/workshop/instance/laptop-3"))
+        .and(has_header("content-type", "text/plain; version=0.0.4"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config = MetricsConfig {
+        push_gateway: Some(server.uri()),
+        job: Some("workshop".to_string()),
+        instance: Some("laptop-3".to_string()),
+        ..Default::default()
+    };
+    let exporter = MetricsExporter::start(config).await.unwrap().unwrap();
+    let mut metrics = exporter.metric_set();
+    metrics.gauge(&SOAK_OPS, &[], 42.0);
+    exporter.publish(&metrics).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert!(String::from_utf8_lossy(&requests[0].body).ends_with("redis_demo_soak_ops 42\n"));
+}